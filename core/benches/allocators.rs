@@ -0,0 +1,75 @@
+//! Compares the allocators of QuickJS on allocation heavy scripts.
+//!
+//! Run with `cargo bench -p rquickjs-core --features mimalloc,jemalloc --bench allocators`.
+
+use std::time::{Duration, Instant};
+
+use rquickjs_core::{allocator::RustAllocator, Context, Runtime};
+
+const ITERATIONS: u32 = 10;
+
+const SCRIPTS: &[(&str, &str)] = &[
+    (
+        "objects",
+        r#"
+        let list = [];
+        for (let i = 0; i < 200000; i++) {
+            list.push({ index: i, name: "item" + i, tags: [i, i * 2] });
+        }
+        list.filter((x) => x.index % 3 === 0).length
+        "#,
+    ),
+    (
+        "strings",
+        r#"
+        let total = 0;
+        for (let i = 0; i < 100000; i++) {
+            total += JSON.stringify({ value: i, text: "x".repeat(i % 64) }).length;
+        }
+        total
+        "#,
+    ),
+    (
+        "closures",
+        r#"
+        let fns = [];
+        for (let i = 0; i < 200000; i++) {
+            fns.push(() => i);
+        }
+        fns.reduce((acc, f) => acc + f(), 0)
+        "#,
+    ),
+];
+
+fn run(rt: Runtime, script: &str) -> Duration {
+    let ctx = Context::full(&rt).unwrap();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        ctx.with(|ctx| ctx.eval::<(), _>(script).unwrap());
+        rt.run_gc();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let allocators: Vec<(&str, fn() -> Runtime)> = vec![
+        ("default", || Runtime::new().unwrap()),
+        ("rust", || Runtime::new_with_alloc(RustAllocator).unwrap()),
+        #[cfg(feature = "mimalloc")]
+        ("mimalloc", || {
+            Runtime::new_with_alloc(rquickjs_core::allocator::MiMalloc).unwrap()
+        }),
+        #[cfg(feature = "jemalloc")]
+        ("jemalloc", || {
+            Runtime::new_with_alloc(rquickjs_core::allocator::Jemalloc).unwrap()
+        }),
+    ];
+
+    for (name, script) in SCRIPTS {
+        println!("{name}:");
+        for (allocator, new_runtime) in &allocators {
+            let time = run(new_runtime(), script);
+            println!("  {allocator:<10} {time:>12.2?}");
+        }
+    }
+}
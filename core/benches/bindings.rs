@@ -0,0 +1,96 @@
+//! Measures the overhead of the bindings: evaluating scripts, calling between Rust and
+//! JavaScript, converting values and awaiting promises.
+//!
+//! Run with `cargo bench -p rquickjs-core --bench bindings`. Changes aimed at performance should
+//! save a baseline before the change with `-- --save-baseline before` and compare it after the
+//! change with `-- --baseline before`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rquickjs_core::{Array, Function, Object, Promise, Value};
+
+mod harness;
+
+use harness::Harness;
+
+fn eval(c: &mut Criterion) {
+    let harness = Harness::new();
+    let mut group = c.benchmark_group("eval");
+    harness.bench(&mut group, "expression", |ctx| {
+        ctx.eval::<i32, _>(black_box("1 + 2")).unwrap();
+    });
+    harness.bench(&mut group, "loop", |ctx| {
+        ctx.eval::<i32, _>(black_box(
+            "let sum = 0; for (let i = 0; i < 1000; i++) { sum += i; } sum",
+        ))
+        .unwrap();
+    });
+    group.finish();
+}
+
+fn call(c: &mut Criterion) {
+    let harness = Harness::new().setup("function add(a, b) { return a + b; }");
+    harness.with(|ctx| {
+        let add = Function::new(ctx.clone(), |a: i32, b: i32| a + b).unwrap();
+        ctx.globals().set("native", add).unwrap();
+    });
+    let mut group = c.benchmark_group("call");
+    harness.bench(&mut group, "rust to js", |ctx| {
+        let add: Function = ctx.globals().get("add").unwrap();
+        add.call::<_, i32>((black_box(1), black_box(2))).unwrap();
+    });
+    harness.bench(&mut group, "js to rust", |ctx| {
+        ctx.eval::<i32, _>("let r = 0; for (let i = 0; i < 100; i++) { r = native(r, i); } r")
+            .unwrap();
+    });
+    group.finish();
+}
+
+fn convert(c: &mut Criterion) {
+    let harness = Harness::new();
+    let numbers: Vec<f64> = (0..1000).map(f64::from).collect();
+    let names: Vec<String> = (0..100).map(|i| format!("item{i}")).collect();
+    let mut group = c.benchmark_group("convert");
+    harness.bench(&mut group, "numbers into js", |ctx| {
+        ctx.globals().set("numbers", black_box(&numbers)).unwrap();
+    });
+    harness.bench(&mut group, "numbers from js", |ctx| {
+        let array: Value = ctx
+            .eval("Array.from({ length: 1000 }, (_, i) => i)")
+            .unwrap();
+        black_box(array.get::<Vec<f64>>().unwrap());
+    });
+    harness.bench(&mut group, "strings into js", |ctx| {
+        ctx.globals().set("names", black_box(&names)).unwrap();
+    });
+    harness.bench(&mut group, "object round trip", |ctx| {
+        let object = Object::new(ctx.clone()).unwrap();
+        for (index, name) in names.iter().enumerate() {
+            object.set(name.as_str(), index).unwrap();
+        }
+        let array: Array = ctx.eval("[]").unwrap();
+        for (index, name) in names.iter().enumerate() {
+            array
+                .set(index, object.get::<_, usize>(name.as_str()).unwrap())
+                .unwrap();
+        }
+        black_box(array);
+    });
+    group.finish();
+}
+
+fn promise(c: &mut Criterion) {
+    let harness =
+        Harness::new().setup("async function double(promise) { return 2 * await promise; }");
+    let mut group = c.benchmark_group("promise");
+    harness.bench(&mut group, "round trip", |ctx| {
+        let (promise, resolve, _) = Promise::new(ctx).unwrap();
+        let double: Function = ctx.globals().get("double").unwrap();
+        let result: Promise = double.call((promise,)).unwrap();
+        resolve.call::<_, ()>((black_box(21),)).unwrap();
+        assert_eq!(result.finish::<i32>().unwrap(), 42);
+    });
+    group.finish();
+}
+
+criterion_group!(bindings, eval, call, convert, promise);
+criterion_main!(bindings);
@@ -0,0 +1,53 @@
+//! The shared setup of the benchmarks.
+//!
+//! Benchmarks of other bindings can copy this module and add their functions with
+//! [`Harness::bench`], which measures only the closure and not the setup of the context.
+
+use criterion::{measurement::WallTime, Bencher, BenchmarkGroup};
+use rquickjs_core::{Context, Ctx, Runtime};
+
+/// A runtime with a full context, reused across the iterations of a benchmark.
+pub struct Harness {
+    ctx: Context,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        Harness { ctx }
+    }
+
+    /// Run a setup script, like the definition of the functions used by the benchmarks.
+    pub fn setup(self, source: &str) -> Self {
+        self.ctx.with(|ctx| ctx.eval::<(), _>(source).unwrap());
+        self
+    }
+
+    /// Run a closure inside the context outside of the measurements.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> R,
+    {
+        self.ctx.with(f)
+    }
+
+    /// Measure a closure running inside the context.
+    ///
+    /// Pending jobs are run after every iteration and the garbage collector after every sample,
+    /// so the promises and garbage of one iteration don't pile up in the next.
+    pub fn bench<F>(&self, group: &mut BenchmarkGroup<'_, WallTime>, name: &str, mut f: F)
+    where
+        F: for<'js> FnMut(&Ctx<'js>),
+    {
+        group.bench_function(name, |b: &mut Bencher| {
+            self.ctx.with(|ctx| {
+                b.iter(|| {
+                    f(&ctx);
+                    while ctx.execute_pending_job() {}
+                });
+                ctx.run_gc();
+            })
+        });
+    }
+}
@@ -1,10 +1,22 @@
 //! Tools for using different allocators with QuickJS.
 
 use crate::qjs;
-use std::ptr;
-
+use std::{collections::HashMap, ptr, ptr::NonNull};
+
+mod arena;
+mod failing;
+#[cfg(feature = "jemalloc")]
+mod jemalloc;
+#[cfg(feature = "mimalloc")]
+mod mimalloc;
 mod rust;
 
+pub use arena::{Arena, BumpAllocator};
+pub use failing::{FailingAllocator, FailureInjector};
+#[cfg(feature = "jemalloc")]
+pub use jemalloc::Jemalloc;
+#[cfg(feature = "mimalloc")]
+pub use mimalloc::MiMalloc;
 pub use rust::RustAllocator;
 
 /// Raw memory pointer
@@ -54,8 +66,167 @@ pub unsafe trait Allocator {
 
 type DynAllocator = Box<dyn Allocator>;
 
+/// The memory attributed to a context.
+#[derive(Debug, Default)]
+pub(crate) struct ContextMemory {
+    pub used: usize,
+    pub limit: Option<usize>,
+    /// Set when an allocation failed because of the limit.
+    pub exceeded: bool,
+}
+
+/// Attributes the memory allocated while a context is entered to that context.
+///
+/// The accounting is approximate: memory freed while a context is entered is subtracted from
+/// that context, even if it was allocated by another one.
+///
+/// Contexts are identified by an id stored in their opaque, so a context allocated at the
+/// address of a freed one doesn't inherit its memory.
 #[derive(Debug)]
-pub(crate) struct AllocatorHolder(*mut DynAllocator);
+pub(crate) struct MemoryAccounting {
+    /// The memory of the innermost entered context, cached from `entered` for the allocation
+    /// functions.
+    current: *mut ContextMemory,
+    /// The ids of the entered contexts, the innermost last.
+    entered: Vec<usize>,
+    contexts: HashMap<usize, Box<ContextMemory>>,
+    next_id: usize,
+    /// Set when an allocation failed, taken when the resulting exception is returned as an error.
+    pub out_of_memory: bool,
+}
+
+impl MemoryAccounting {
+    fn new() -> Self {
+        Self {
+            current: ptr::null_mut(),
+            entered: Vec::new(),
+            contexts: HashMap::new(),
+            next_id: 1,
+            out_of_memory: false,
+        }
+    }
+
+    /// Returns the id of a context, if it was ever entered or given a limit.
+    fn id(ctx: NonNull<qjs::JSContext>) -> Option<usize> {
+        let id = unsafe { qjs::JS_GetContextOpaque(ctx.as_ptr()) } as usize;
+        (id != 0).then_some(id)
+    }
+
+    /// Returns the id of a context, assigning a new one if it has none.
+    fn id_or_assign(&mut self, ctx: NonNull<qjs::JSContext>) -> usize {
+        Self::id(ctx).unwrap_or_else(|| {
+            let id = self.next_id;
+            self.next_id += 1;
+            unsafe { qjs::JS_SetContextOpaque(ctx.as_ptr(), id as *mut qjs::c_void) };
+            id
+        })
+    }
+
+    /// Returns the memory of a context, if it was ever entered or given a limit.
+    pub fn context(&self, ctx: NonNull<qjs::JSContext>) -> Option<&ContextMemory> {
+        self.contexts.get(&Self::id(ctx)?).map(|x| &**x)
+    }
+
+    /// Returns the memory of a context, tracking it from now on.
+    pub fn context_mut(&mut self, ctx: NonNull<qjs::JSContext>) -> &mut ContextMemory {
+        let id = self.id_or_assign(ctx);
+        self.contexts.entry(id).or_default()
+    }
+
+    /// Attribute the allocations to `ctx` until the returned scope is dropped.
+    pub fn enter(&mut self, ctx: NonNull<qjs::JSContext>) -> MemoryScope {
+        let id = self.id_or_assign(ctx);
+        self.entered.push(id);
+        self.update_current();
+        MemoryScope {
+            accounting: self,
+            depth: self.entered.len(),
+        }
+    }
+
+    /// Stop tracking a context.
+    pub fn forget(&mut self, ctx: NonNull<qjs::JSContext>) {
+        if let Some(id) = Self::id(ctx) {
+            self.contexts.remove(&id);
+            unsafe { qjs::JS_SetContextOpaque(ctx.as_ptr(), ptr::null_mut()) };
+            self.update_current();
+        }
+    }
+
+    fn update_current(&mut self) {
+        self.current = self
+            .entered
+            .last()
+            .and_then(|id| self.contexts.get_mut(id))
+            .map_or(ptr::null_mut(), |x| &mut **x);
+    }
+
+    /// Returns false, marking the current context, if allocating `size` more bytes would exceed
+    /// its limit.
+    fn allows(&mut self, size: usize) -> bool {
+        let Some(current) = (unsafe { self.current.as_mut() }) else {
+            return true;
+        };
+        match current.limit {
+            Some(limit) if current.used.saturating_add(size) > limit => {
+                current.exceeded = true;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn allocated(&mut self, size: usize) {
+        if let Some(current) = unsafe { self.current.as_mut() } {
+            current.used += size;
+        }
+    }
+
+    fn freed(&mut self, size: usize) {
+        if let Some(current) = unsafe { self.current.as_mut() } {
+            current.used = current.used.saturating_sub(size);
+        }
+    }
+}
+
+/// Restores the previously entered context when dropped.
+///
+/// Scopes must be dropped in the reverse order they were entered in, on the thread holding the
+/// runtime lock, so the scope is neither `Send` nor `Sync`.
+pub(crate) struct MemoryScope {
+    accounting: *mut MemoryAccounting,
+    /// The number of entered contexts including the one of this scope.
+    depth: usize,
+}
+
+impl MemoryScope {
+    /// A scope which doesn't attribute anything, for runtimes without a custom allocator.
+    pub fn none() -> Self {
+        Self {
+            accounting: ptr::null_mut(),
+            depth: 0,
+        }
+    }
+}
+
+impl Drop for MemoryScope {
+    fn drop(&mut self) {
+        if let Some(accounting) = unsafe { self.accounting.as_mut() } {
+            debug_assert_eq!(accounting.entered.len(), self.depth);
+            accounting.entered.pop();
+            accounting.update_current();
+        }
+    }
+}
+
+/// The state passed to the allocation functions.
+struct AllocatorState {
+    allocator: DynAllocator,
+    accounting: MemoryAccounting,
+}
+
+#[derive(Debug)]
+pub(crate) struct AllocatorHolder(*mut AllocatorState);
 
 impl Drop for AllocatorHolder {
     fn drop(&mut self) {
@@ -80,11 +251,18 @@ impl AllocatorHolder {
     where
         A: Allocator + 'static,
     {
-        Self(Box::into_raw(Box::new(Box::new(allocator))))
+        Self(Box::into_raw(Box::new(AllocatorState {
+            allocator: Box::new(allocator),
+            accounting: MemoryAccounting::new(),
+        })))
+    }
+
+    pub(crate) fn opaque_ptr(&self) -> *mut qjs::c_void {
+        self.0.cast()
     }
 
-    pub(crate) fn opaque_ptr(&self) -> *mut DynAllocator {
-        self.0
+    pub(crate) fn accounting(&self) -> *mut MemoryAccounting {
+        unsafe { &mut (*self.0).accounting }
     }
 
     fn size_t(size: usize) -> qjs::size_t {
@@ -103,23 +281,30 @@ impl AllocatorHolder {
         }
 
         let state = &mut *state;
+        let alloc_state = &mut *(state.opaque as *mut AllocatorState);
 
         if state.malloc_size + size > state.malloc_limit {
+            alloc_state.accounting.out_of_memory = true;
             return ptr::null_mut();
         }
 
         let rust_size: usize = size.try_into().expect(qjs::SIZE_T_ERROR);
         // simulate the default behavior of libc::malloc
 
-        let allocator = &mut *(state.opaque as *mut DynAllocator);
+        if !alloc_state.accounting.allows(rust_size) {
+            alloc_state.accounting.out_of_memory = true;
+            return ptr::null_mut();
+        }
 
-        let res = allocator.alloc(rust_size as _);
+        let res = alloc_state.allocator.alloc(rust_size as _);
 
         if res.is_null() {
+            alloc_state.accounting.out_of_memory = true;
             return ptr::null_mut();
         }
 
         let size = A::usable_size(res);
+        alloc_state.accounting.allocated(size);
 
         state.malloc_count += 1;
         state.malloc_size += Self::size_t(size);
@@ -142,8 +327,9 @@ impl AllocatorHolder {
 
         let size = A::usable_size(ptr as RawMemPtr);
 
-        let allocator = &mut *(state.opaque as *mut DynAllocator);
-        allocator.dealloc(ptr as _);
+        let alloc_state = &mut *(state.opaque as *mut AllocatorState);
+        alloc_state.allocator.dealloc(ptr as _);
+        alloc_state.accounting.freed(size);
 
         state.malloc_size -= Self::size_t(size);
     }
@@ -157,7 +343,7 @@ impl AllocatorHolder {
         A: Allocator,
     {
         let state_ref = &mut *state;
-        let allocator = &mut *(state_ref.opaque as *mut DynAllocator);
+        let alloc_state = &mut *(state_ref.opaque as *mut AllocatorState);
 
         // simulate the default behavior of libc::realloc
         if ptr.is_null() {
@@ -171,17 +357,32 @@ impl AllocatorHolder {
 
         let new_malloc_size = state_ref.malloc_size - old_size + size;
         if new_malloc_size > state_ref.malloc_limit {
+            alloc_state.accounting.out_of_memory = true;
+            return ptr::null_mut();
+        }
+
+        let rust_size: usize = size.try_into().expect(qjs::SIZE_T_ERROR);
+        let rust_old_size = A::usable_size(ptr as RawMemPtr);
+        if !alloc_state
+            .accounting
+            .allows(rust_size.saturating_sub(rust_old_size))
+        {
+            alloc_state.accounting.out_of_memory = true;
             return ptr::null_mut();
         }
 
-        let ptr = allocator.realloc(ptr as _, size.try_into().expect(qjs::SIZE_T_ERROR))
-            as *mut qjs::c_void;
+        let ptr = alloc_state.allocator.realloc(ptr as _, rust_size) as *mut qjs::c_void;
 
         if ptr.is_null() {
+            alloc_state.accounting.out_of_memory = true;
             return ptr::null_mut();
         }
 
         let actual_size = Self::size_t(A::usable_size(ptr as RawMemPtr));
+        alloc_state.accounting.freed(rust_old_size);
+        alloc_state
+            .accounting
+            .allocated(A::usable_size(ptr as RawMemPtr));
 
         state_ref.malloc_size -= old_size;
         state_ref.malloc_size += actual_size;
@@ -200,3 +401,45 @@ impl AllocatorHolder {
         A::usable_size(ptr as _).try_into().unwrap()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::RustAllocator;
+    use crate::{Context, Error, Runtime};
+
+    #[test]
+    fn context_memory_limit() {
+        let rt = Runtime::new_with_alloc(RustAllocator).unwrap();
+        let greedy = Context::full(&rt).unwrap();
+        let other = Context::full(&rt).unwrap();
+        greedy.with(|ctx| {
+            ctx.set_memory_limit(Some(ctx.memory_used().unwrap() + 1024 * 1024));
+            let res = ctx.eval::<(), _>("globalThis.x = []; for (;;) x.push({ a: [1, 2, 3] });");
+            assert!(matches!(res, Err(Error::ContextMemoryExceeded { .. })));
+            let _ = ctx.eval::<(), _>("delete globalThis.x");
+        });
+        other.with(|ctx| {
+            let used = ctx.memory_used().unwrap();
+            let len: usize = ctx
+                .eval("Array.from({ length: 100000 }, (_, i) => ({ i })).length")
+                .unwrap();
+            assert_eq!(len, 100000);
+            assert!(ctx.memory_used().unwrap() > used);
+        });
+    }
+
+    #[test]
+    fn context_memory_not_reused() {
+        let rt = Runtime::new_with_alloc(RustAllocator).unwrap();
+        for _ in 0..8 {
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                // The limit of a dropped context doesn't apply to a new one.
+                ctx.eval::<(), _>("new Array(100000).fill(0)").unwrap();
+                ctx.set_memory_limit(Some(ctx.memory_used().unwrap() + 1024));
+                let res = ctx.eval::<(), _>("new Array(100000).fill(0)");
+                assert!(matches!(res, Err(Error::ContextMemoryExceeded { .. })));
+            });
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    alloc::{alloc, dealloc, Layout},
+    collections::HashMap,
+    mem,
+    ptr::{self, NonNull},
+    sync::{Arc, Mutex},
+};
+
+use super::{Allocator, RawMemPtr};
+
+/// The alignment of all allocations, the same as the alignment guaranteed by `malloc`.
+const ALLOC_ALIGN: usize = 16;
+
+/// The size of the header in front of every allocation which stores its size.
+const HEADER_SIZE: usize = ALLOC_ALIGN;
+
+/// The default size of the chunks of an arena.
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+#[inline]
+fn round_size(size: usize) -> usize {
+    (size + ALLOC_ALIGN - 1) / ALLOC_ALIGN * ALLOC_ALIGN
+}
+
+/// A block of memory allocations are bumped from.
+struct Chunk {
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+// A chunk is only used by one allocator at a time.
+unsafe impl Send for Chunk {}
+
+impl Chunk {
+    fn new(size: usize) -> Option<Self> {
+        let layout = Layout::from_size_align(size, ALLOC_ALIGN).ok()?;
+        let ptr = NonNull::new(unsafe { alloc(layout) })?;
+        Some(Chunk { ptr, size })
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe {
+            dealloc(
+                self.ptr.as_ptr(),
+                Layout::from_size_align_unchecked(self.size, ALLOC_ALIGN),
+            )
+        }
+    }
+}
+
+/// A pool of memory which [`BumpAllocator`]s allocate from.
+///
+/// Tearing down a runtime normally frees every object one by one. A runtime using a
+/// [`BumpAllocator`] instead returns all its memory to the arena at once when it is dropped, and
+/// the next runtime created from the arena reuses that memory without asking the system for it
+/// again. This makes creating a runtime for every run of a script, like a request handler, cheap.
+///
+/// The memory of an arena is scoped to the runtimes: it only goes back to the arena when the
+/// runtime is dropped, resetting a context of the runtime doesn't return it. Until then memory
+/// freed by QuickJS is only reused for allocations of the same size, so an arena is not suited
+/// for long running scripts. The memory limit of the runtime still only counts memory which
+/// QuickJS hasn't freed.
+///
+/// ```
+/// # use rquickjs::{allocator::Arena, Context, Runtime};
+/// let arena = Arena::new();
+/// for i in 0..10 {
+///     let rt = Runtime::new_with_alloc(arena.allocator()).unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///     let res: i32 = ctx.with(|ctx| ctx.eval(format!("{i} * 2"))).unwrap();
+///     assert_eq!(res, i * 2);
+/// }
+/// assert!(arena.capacity() > 0);
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "allocator")))]
+#[derive(Clone)]
+pub struct Arena {
+    chunks: Arc<Mutex<Vec<Chunk>>>,
+    chunk_size: usize,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Arena {
+    /// Create an arena which allocates memory in chunks of 1 MiB.
+    pub fn new() -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Create an arena which allocates memory in chunks of the given size.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        Arena {
+            chunks: Arc::new(Mutex::new(Vec::new())),
+            chunk_size: round_size(chunk_size.max(HEADER_SIZE)),
+        }
+    }
+
+    /// Create an allocator using the memory of this arena.
+    pub fn allocator(&self) -> BumpAllocator {
+        BumpAllocator {
+            arena: self.clone(),
+            chunks: Vec::new(),
+            top: 0,
+            free: HashMap::new(),
+        }
+    }
+
+    /// Returns the amount of memory held by the arena which isn't in use by an allocator.
+    pub fn capacity(&self) -> usize {
+        self.lock().iter().map(|x| x.size).sum()
+    }
+
+    /// Frees the memory held by the arena which isn't in use by an allocator.
+    pub fn shrink(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Chunk>> {
+        self.chunks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes a chunk of at least the given size from the arena or allocates a new one.
+    fn take(&self, size: usize) -> Option<Chunk> {
+        {
+            let mut chunks = self.lock();
+            if let Some(idx) = chunks.iter().position(|x| x.size >= size) {
+                return Some(chunks.swap_remove(idx));
+            }
+        }
+        Chunk::new(size.max(self.chunk_size))
+    }
+}
+
+/// The allocator which bumps allocations from the memory of an [`Arena`].
+///
+/// Freeing the last allocation gives its memory back to the chunk, other freed allocations are
+/// kept in a list per size and reused by the next allocation of that size. All memory is returned
+/// to the arena when the allocator is dropped together with its runtime.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "allocator")))]
+pub struct BumpAllocator {
+    arena: Arena,
+    chunks: Vec<Chunk>,
+    /// The offset of the free memory in the last chunk.
+    top: usize,
+    /// The first freed allocation of every size, each one stores a pointer to the next.
+    free: HashMap<usize, RawMemPtr>,
+}
+
+// The freed allocations are in the chunks owned by the allocator.
+unsafe impl Send for BumpAllocator {}
+
+impl BumpAllocator {
+    fn remaining(&self) -> usize {
+        self.chunks.last().map_or(0, |x| x.size - self.top)
+    }
+
+    fn header(ptr: RawMemPtr) -> *mut usize {
+        unsafe { ptr.sub(HEADER_SIZE).cast() }
+    }
+
+    /// Returns if the allocation is the last one in the current chunk.
+    unsafe fn is_last(&self, ptr: RawMemPtr) -> bool {
+        self.chunks.last().map_or(false, |chunk| {
+            let start = chunk.ptr.as_ptr();
+            ptr > start && ptr.add(*Self::header(ptr)) == start.add(self.top)
+        })
+    }
+}
+
+unsafe impl Allocator for BumpAllocator {
+    fn alloc(&mut self, size: usize) -> RawMemPtr {
+        let size = round_size(size);
+        if let Some(ptr) = self.free.remove(&size) {
+            let next = unsafe { ptr.cast::<RawMemPtr>().read() };
+            if !next.is_null() {
+                self.free.insert(size, next);
+            }
+            return ptr;
+        }
+        let alloc_size = size + HEADER_SIZE;
+        if self.remaining() < alloc_size {
+            let Some(chunk) = self.arena.take(alloc_size) else {
+                return ptr::null_mut();
+            };
+            self.chunks.push(chunk);
+            self.top = 0;
+        }
+        let chunk = self.chunks.last().expect("a chunk was just added");
+        let ptr = unsafe { chunk.ptr.as_ptr().add(self.top) };
+        self.top += alloc_size;
+        unsafe {
+            ptr.cast::<usize>().write(size);
+            ptr.add(HEADER_SIZE)
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn dealloc(&mut self, ptr: RawMemPtr) {
+        let size = *Self::header(ptr);
+        if self.is_last(ptr) {
+            self.top -= size + HEADER_SIZE;
+        } else if size > 0 {
+            let next = self.free.insert(size, ptr).unwrap_or(ptr::null_mut());
+            ptr.cast::<RawMemPtr>().write(next);
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn realloc(&mut self, ptr: RawMemPtr, new_size: usize) -> RawMemPtr {
+        let new_size = round_size(new_size);
+        let size = *Self::header(ptr);
+        if new_size <= size {
+            return ptr;
+        }
+        if self.is_last(ptr) && self.remaining() >= new_size - size {
+            self.top += new_size - size;
+            *Self::header(ptr) = new_size;
+            return ptr;
+        }
+        let new_ptr = self.alloc(new_size);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, size);
+            self.dealloc(ptr);
+        }
+        new_ptr
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn usable_size(ptr: RawMemPtr) -> usize {
+        *Self::header(ptr)
+    }
+}
+
+impl Drop for BumpAllocator {
+    fn drop(&mut self) {
+        self.free.clear();
+        let chunks = mem::take(&mut self.chunks);
+        self.arena.lock().extend(chunks);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn bump() {
+        let arena = Arena::with_chunk_size(512);
+        let mut alloc = arena.allocator();
+        let a = alloc.alloc(10);
+        let b = alloc.alloc(20);
+        unsafe {
+            assert_eq!(BumpAllocator::usable_size(a), 16);
+            assert_eq!(b, a.add(16 + HEADER_SIZE));
+            // The last allocation grows in place.
+            assert_eq!(alloc.realloc(b, 100), b);
+            let c = alloc.realloc(a, 100);
+            assert_eq!(c, b.add(112 + HEADER_SIZE));
+            // Freed allocations are reused by allocations of the same size.
+            assert_eq!(alloc.alloc(16), a);
+            alloc.dealloc(b);
+            assert_eq!(alloc.alloc(100), b);
+            // Larger allocations get a chunk of their own.
+            assert!(!alloc.alloc(1000).is_null());
+        }
+        assert_eq!(arena.capacity(), 0);
+        drop(alloc);
+        assert_eq!(arena.capacity(), 512 + 1024);
+        arena.shrink();
+        assert_eq!(arena.capacity(), 0);
+    }
+
+    #[test]
+    fn runtime() {
+        let arena = Arena::new();
+        for _ in 0..3 {
+            let rt = Runtime::new_with_alloc(arena.allocator()).unwrap();
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                let res: usize = ctx
+                    .eval("Array.from({ length: 10000 }, (_, i) => ({ i })).length")
+                    .unwrap();
+                assert_eq!(res, 10000);
+            });
+        }
+        assert!(arena.capacity() >= DEFAULT_CHUNK_SIZE);
+    }
+}
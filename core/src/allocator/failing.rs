@@ -0,0 +1,197 @@
+use std::{
+    ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{Allocator, RawMemPtr, RustAllocator};
+
+/// The shared counters of a failing allocator and its injector.
+#[derive(Default)]
+struct State {
+    /// The number of allocations since the last call to `fail_at`.
+    count: AtomicUsize,
+    /// The allocation which fails, `0` if none does.
+    fail_at: AtomicUsize,
+    /// The number of allocations which failed.
+    failures: AtomicUsize,
+}
+
+/// An allocator which fails a chosen allocation, for testing how code handles running out of
+/// memory.
+///
+/// Every allocation and reallocation made by QuickJS is counted and the one selected with
+/// [`FailureInjector::fail_at`] returns a null pointer, like an allocator which ran out of
+/// memory. All other allocations are forwarded to the inner allocator.
+///
+/// Running a piece of code with every allocation failing in turn exercises all of its out of
+/// memory paths:
+///
+/// ```
+/// # use rquickjs::{allocator::FailingAllocator, Context, Error, Runtime};
+/// let allocator = FailingAllocator::new();
+/// let injector = allocator.injector();
+/// let rt = Runtime::new_with_alloc(allocator).unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     for n in 1..100 {
+///         injector.fail_at(n);
+///         match ctx.eval::<Vec<i32>, _>("[1, 2, 3].map(x => x * 2)") {
+///             Ok(res) => assert_eq!(res, [2, 4, 6]),
+///             Err(error) => assert!(matches!(error, Error::Allocation | Error::Exception)),
+///         }
+///         ctx.catch();
+///     }
+/// });
+/// ```
+pub struct FailingAllocator<A = RustAllocator> {
+    inner: A,
+    state: Arc<State>,
+}
+
+impl FailingAllocator {
+    /// Create a failing allocator using the [`RustAllocator`].
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::with_allocator(RustAllocator)
+    }
+}
+
+impl<A: Allocator> FailingAllocator<A> {
+    /// Create a failing allocator forwarding allocations to the given allocator.
+    pub fn with_allocator(inner: A) -> Self {
+        FailingAllocator {
+            inner,
+            state: Arc::new(State::default()),
+        }
+    }
+
+    /// Returns a handle to select the failing allocation, which stays usable after the allocator
+    /// was moved into a runtime.
+    pub fn injector(&self) -> FailureInjector {
+        FailureInjector(self.state.clone())
+    }
+
+    fn should_fail(&self) -> bool {
+        let count = self.state.count.fetch_add(1, Ordering::Relaxed) + 1;
+        if count == self.state.fail_at.load(Ordering::Relaxed) {
+            self.state.failures.fetch_add(1, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+unsafe impl<A: Allocator> Allocator for FailingAllocator<A> {
+    fn alloc(&mut self, size: usize) -> RawMemPtr {
+        if self.should_fail() {
+            return ptr::null_mut();
+        }
+        self.inner.alloc(size)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: RawMemPtr) {
+        self.inner.dealloc(ptr)
+    }
+
+    unsafe fn realloc(&mut self, ptr: RawMemPtr, new_size: usize) -> RawMemPtr {
+        if self.should_fail() {
+            return ptr::null_mut();
+        }
+        self.inner.realloc(ptr, new_size)
+    }
+
+    unsafe fn usable_size(ptr: RawMemPtr) -> usize {
+        A::usable_size(ptr)
+    }
+}
+
+/// A handle to select which allocation of a [`FailingAllocator`] fails.
+#[derive(Clone)]
+pub struct FailureInjector(Arc<State>);
+
+impl FailureInjector {
+    /// Fail the `n`th allocation from now on, counting from `1`.
+    ///
+    /// Resets the allocation count, `0` disables the failure.
+    pub fn fail_at(&self, n: usize) {
+        self.0.count.store(0, Ordering::Relaxed);
+        self.0.fail_at.store(n, Ordering::Relaxed);
+    }
+
+    /// Stop failing allocations.
+    pub fn disable(&self) {
+        self.fail_at(0)
+    }
+
+    /// Returns the number of allocations since the last call to [`FailureInjector::fail_at`].
+    pub fn allocations(&self) -> usize {
+        self.0.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of allocations which failed.
+    pub fn failures(&self) -> usize {
+        self.0.failures.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Error, Object, Runtime, String};
+
+    #[test]
+    fn fail_nth() {
+        let mut allocator = FailingAllocator::new();
+        let injector = allocator.injector();
+        injector.fail_at(2);
+        unsafe {
+            let a = allocator.alloc(16);
+            assert!(!a.is_null());
+            assert!(allocator.alloc(16).is_null());
+            let a = allocator.realloc(a, 32);
+            assert!(!a.is_null());
+            allocator.dealloc(a);
+        }
+        assert_eq!(injector.allocations(), 3);
+        assert_eq!(injector.failures(), 1);
+    }
+
+    #[test]
+    fn conversions() {
+        let allocator = FailingAllocator::new();
+        let injector = allocator.injector();
+        let rt = Runtime::new_with_alloc(allocator).unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let long = "a".repeat(1024);
+            injector.fail_at(1);
+            let res = String::from_str(ctx.clone(), &long);
+            assert!(matches!(res, Err(Error::Allocation)));
+            assert!(!ctx.catch().is_object());
+
+            for n in 1..200 {
+                injector.fail_at(n);
+                let res = (|| {
+                    let obj = Object::new(ctx.clone())?;
+                    obj.set(long.as_str(), vec![1, 2, 3])?;
+                    obj.get::<_, Vec<i32>>(long.as_str())
+                })();
+                match res {
+                    Ok(res) => assert_eq!(res, [1, 2, 3]),
+                    Err(error) => assert!(matches!(error, Error::Allocation), "{error}"),
+                }
+            }
+            injector.disable();
+            assert_eq!(ctx.eval::<i32, _>("1 + 1").unwrap(), 2);
+
+            // Errors thrown by scripts are not mistaken for allocation failures.
+            let res = ctx.eval::<(), _>("throw new InternalError('out of memory')");
+            assert!(matches!(res, Err(Error::Exception)));
+            ctx.catch();
+        });
+    }
+}
@@ -0,0 +1,50 @@
+use libmimalloc_sys as ffi;
+
+use super::{Allocator, RawMemPtr};
+
+/// The allocator which uses [mimalloc](https://github.com/microsoft/mimalloc)
+///
+/// mimalloc reports the usable size of allocations to QuickJS itself, so unlike
+/// [`RustAllocator`](super::RustAllocator) no header is stored in front of every allocation.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "mimalloc")))]
+pub struct MiMalloc;
+
+unsafe impl Allocator for MiMalloc {
+    fn alloc(&mut self, size: usize) -> RawMemPtr {
+        unsafe { ffi::mi_malloc(size).cast() }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn dealloc(&mut self, ptr: RawMemPtr) {
+        ffi::mi_free(ptr.cast())
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn realloc(&mut self, ptr: RawMemPtr, new_size: usize) -> RawMemPtr {
+        ffi::mi_realloc(ptr.cast(), new_size).cast()
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    unsafe fn usable_size(ptr: RawMemPtr) -> usize {
+        ffi::mi_usable_size(ptr.cast_const().cast())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MiMalloc;
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn eval() {
+        let rt = Runtime::new_with_alloc(MiMalloc).unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let res: String = ctx
+                .eval("Array.from({ length: 1000 }, (_, i) => ({ i })).map(x => x.i).join()")
+                .unwrap();
+            assert!(res.starts_with("0,1,2"));
+        });
+        assert!(rt.memory_usage().malloc_size > 0);
+    }
+}
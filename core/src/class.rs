@@ -10,18 +10,26 @@ use std::{
     ffi::CString,
     hash::Hash,
     marker::PhantomData,
+    mem,
     ops::Deref,
     ptr::{self, NonNull},
 };
 
+#[cfg(feature = "futures")]
+mod async_drop;
 mod cell;
+mod exotic;
 mod ffi;
 mod id;
 mod trace;
 
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+pub use async_drop::{AsyncDrop, AsyncFinalizer};
 pub use cell::{
     Borrow, BorrowMut, JsCell, Mutability, OwnedBorrow, OwnedBorrowMut, Readable, Writable,
 };
+pub use exotic::{Exotic, ExoticMethods, Indexed};
 pub use id::ClassId;
 pub use trace::{Trace, Tracer};
 #[doc(hidden)]
@@ -53,6 +61,23 @@ pub trait JsClass<'js>: Trace<'js> {
     fn function() -> Option<StaticJsFn> {
         None
     }
+
+    /// Hooks intercepting the property access of the objects of this class.
+    ///
+    /// Return [`ExoticMethods::new`] for a type implementing [`Exotic`] or
+    /// [`ExoticMethods::indexed`] for a type implementing [`Indexed`] to enable them.
+    fn exotic() -> Option<ExoticMethods> {
+        None
+    }
+
+    /// A finalizer spawning the asynchronous clean up of the objects of this class.
+    ///
+    /// Return [`AsyncFinalizer::new`] for a type implementing [`AsyncDrop`] to enable it.
+    #[cfg(feature = "futures")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+    fn async_finalizer() -> Option<AsyncFinalizer> {
+        None
+    }
 }
 
 /// A object which is instance of a Rust class.
@@ -196,13 +221,15 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
             } else {
                 None
             };
+            #[cfg(feature = "futures")]
+            let finalizer = C::async_finalizer().map(|x| x.0).or(finalizer);
             let call = C::function().map(|x| x.0);
             let class_def = qjs::JSClassDef {
                 class_name: class_name.as_ptr(),
                 finalizer,
                 gc_mark: Some(ffi::trace::<C>),
                 call,
-                exotic: ptr::null_mut(),
+                exotic: C::exotic().map_or(ptr::null_mut(), |x| x.0 as *const _ as *mut _),
             };
             if 0 != unsafe { qjs::JS_NewClass(rt, class_id, &class_def) } {
                 return Err(Error::Unknown);
@@ -276,6 +303,26 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
         self.get_cell().try_borrow_mut().map_err(Error::ClassBorrow)
     }
 
+    /// Replace the Rust value of the class object, returning the previous value.
+    ///
+    /// This moves a value handed to JavaScript back out of the object intact, while the object
+    /// keeps working with the new value. Returns an error when the class is already borrowed or
+    /// can't be borrowed mutably, like [`Class::try_borrow_mut`].
+    pub fn replace(&self, value: C) -> Result<C> {
+        let mut borrow = self.try_borrow_mut()?;
+        Ok(mem::replace(&mut *borrow, value))
+    }
+
+    /// Take the Rust value out of the class object, leaving the default value in its place.
+    ///
+    /// See [`Class::replace`].
+    pub fn take(&self) -> Result<C>
+    where
+        C: Default,
+    {
+        self.replace(C::default())
+    }
+
     /// returns a pointer to the class object.
     #[inline]
     pub(crate) fn get_class_ptr(&self) -> NonNull<JsCell<'js, C>> {
@@ -307,11 +354,42 @@ impl<'js, C: JsClass<'js>> Class<'js, C> {
         if let Some(cls) = value.as_object().and_then(Self::from_object) {
             return Ok(cls);
         }
-        Err(Error::FromJs {
+        Err(Self::not_an_instance(value))
+    }
+
+    /// Borrow the Rust data of an instance of the class.
+    ///
+    /// Instances of JavaScript classes extending the class are instances too, as long as their
+    /// constructor called `super(...)`. Fails with a [`Error::FromJs`] naming the class if the
+    /// object isn't an instance, for example if it was created with
+    /// `Object.create(Class.prototype)`, and with [`Error::ClassBorrow`] if the data is borrowed
+    /// mutably.
+    pub fn instance_data<'a>(object: &'a Object<'js>) -> Result<Borrow<'a, 'js, C>> {
+        object
+            .as_class::<C>()
+            .ok_or_else(|| Self::not_an_instance(object.as_value()))?
+            .try_borrow()
+    }
+
+    /// Borrow the Rust data of an instance of the class mutably.
+    ///
+    /// Like [`Class::instance_data`], but also fails if the data is borrowed or can't be borrowed
+    /// mutably.
+    pub fn instance_data_mut<'a>(object: &'a Object<'js>) -> Result<BorrowMut<'a, 'js, C>> {
+        object
+            .as_class::<C>()
+            .ok_or_else(|| Self::not_an_instance(object.as_value()))?
+            .try_borrow_mut()
+    }
+
+    fn not_an_instance(value: &Value<'js>) -> Error {
+        Error::FromJs {
             from: value.type_name(),
             to: C::NAME,
-            message: None,
-        })
+            message: value
+                .is_object()
+                .then(|| format!("the object is not an instance of {}", C::NAME)),
+        }
     }
 
     /// Turn the class into a value.
@@ -334,13 +412,8 @@ impl<'js> Object<'js> {
             return false;
         }
 
-        let p = unsafe {
-            qjs::JS_GetOpaque2(
-                self.0.ctx.as_ptr(),
-                self.0.as_js_value(),
-                C::class_id().get(),
-            )
-        };
+        // `JS_GetOpaque2` would leave a type error behind for objects of other classes.
+        let p = unsafe { qjs::JS_GetOpaque(self.0.as_js_value(), C::class_id().get()) };
         !p.is_null()
     }
 
@@ -557,6 +630,92 @@ mod test {
 
             let name: String = ctx.eval("new Vec3(1,2,3).constructor.name").unwrap();
             assert_eq!(name, Vec3::NAME);
+
+            assert!(ctx.eval::<(), _>("Vec3(1,2,3)").is_err());
+            ctx.catch();
+
+            let constr: Constructor = ctx.globals().get("Vec3").unwrap();
+            let v: Vec3 = constr.construct((1.0, 2.0, 3.0)).unwrap();
+            approx::assert_abs_diff_eq!(v.z, 3.0);
+
+            // Instances of derived JavaScript classes are still instances of the Rust class.
+            let v: Class<Vec3> = ctx
+                .eval(
+                    r"
+                class Vec4 extends Vec3 {
+                    constructor(x, y, z, w) {
+                        super(x, y, z);
+                        this.w = w;
+                    }
+                    length() {
+                        return this.w;
+                    }
+                }
+                globalThis.v = new Vec4(1, 2, 3, 4);
+                if (!(v instanceof Vec4 && v instanceof Vec3) || v.length() !== 4) {
+                    throw new Error('wrong prototype');
+                }
+                v
+            ",
+                )
+                .unwrap();
+            approx::assert_abs_diff_eq!(v.borrow().y, 2.0);
+            let added: Vec3 = ctx.eval("v.add(new Vec3(1, 1, 1))").unwrap();
+            approx::assert_abs_diff_eq!(added.x, 2.0);
+            approx::assert_abs_diff_eq!(Class::<Vec3>::instance_data(&v).unwrap().z, 3.0);
+            Class::<Vec3>::instance_data_mut(&v).unwrap().z = 5.0;
+            let z: f32 = ctx.eval("v.add(new Vec3(0, 0, 0)).z").unwrap();
+            approx::assert_abs_diff_eq!(z, 5.0);
+
+            let fake: Object = ctx.eval("Object.create(Vec3.prototype)").unwrap();
+            let err = Class::<Vec3>::instance_data(&fake).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                "Error converting from js 'object' into type 'Vec3': the object is not an instance of Vec3"
+            );
+            assert!(ctx.catch().is_null());
+        })
+    }
+
+    #[test]
+    fn replace() {
+        #[derive(Default, Debug, PartialEq)]
+        pub struct Buffer(Vec<u8>);
+
+        impl<'js> Trace<'js> for Buffer {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        impl<'js> JsClass<'js> for Buffer {
+            const NAME: &'static str = "Buffer";
+
+            type Mutable = Writable;
+
+            fn class_id() -> &'static ClassId {
+                static ID: ClassId = ClassId::new();
+                &ID
+            }
+
+            fn prototype(ctx: &crate::Ctx<'js>) -> crate::Result<Option<Object<'js>>> {
+                Object::new(ctx.clone()).map(Some)
+            }
+
+            fn constructor(_ctx: &crate::Ctx<'js>) -> crate::Result<Option<Constructor<'js>>> {
+                Ok(None)
+            }
+        }
+
+        test_with(|ctx| {
+            let cls = Class::instance(ctx.clone(), Buffer(vec![1, 2])).unwrap();
+            ctx.globals().set("buffer", cls).unwrap();
+
+            let cls: Class<Buffer> = ctx.globals().get("buffer").unwrap();
+            assert_eq!(cls.replace(Buffer(vec![3])).unwrap(), Buffer(vec![1, 2]));
+            let borrow = cls.borrow();
+            assert!(cls.take().is_err());
+            drop(borrow);
+            assert_eq!(cls.take().unwrap(), Buffer(vec![3]));
+            assert_eq!(*cls.borrow(), Buffer::default());
         })
     }
 
@@ -592,4 +751,178 @@ mod test {
             Class::<X>::register(&ctx).unwrap();
         })
     }
+
+    #[test]
+    fn exotic() {
+        use crate::{
+            class::{Exotic, ExoticMethods},
+            Atom, Value,
+        };
+
+        /// A lazily materialized list of squares.
+        pub struct Squares(u32);
+
+        impl<'js> Trace<'js> for Squares {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        impl<'js> JsClass<'js> for Squares {
+            const NAME: &'static str = "Squares";
+
+            type Mutable = Readable;
+
+            fn class_id() -> &'static ClassId {
+                static ID: ClassId = ClassId::new();
+                &ID
+            }
+
+            fn prototype(ctx: &crate::Ctx<'js>) -> crate::Result<Option<Object<'js>>> {
+                Object::new(ctx.clone()).map(Some)
+            }
+
+            fn constructor(_ctx: &crate::Ctx<'js>) -> crate::Result<Option<Constructor<'js>>> {
+                Ok(None)
+            }
+
+            fn exotic() -> Option<ExoticMethods> {
+                Some(ExoticMethods::new::<Self>())
+            }
+        }
+
+        impl<'js> Exotic<'js> for Squares {
+            fn get_own_property(
+                this: &Class<'js, Self>,
+                key: Atom<'js>,
+            ) -> crate::Result<Option<Value<'js>>> {
+                let Ok(idx) = key.to_string()?.parse::<u32>() else {
+                    return Ok(None);
+                };
+                if idx >= this.borrow().0 {
+                    return Ok(None);
+                }
+                Ok(Some(Value::new_int(this.ctx().clone(), (idx * idx) as i32)))
+            }
+
+            fn own_property_names(this: &Class<'js, Self>) -> crate::Result<Vec<Atom<'js>>> {
+                (0..this.borrow().0)
+                    .map(|idx| Atom::from_u32(this.ctx().clone(), idx))
+                    .collect()
+            }
+        }
+
+        test_with(|ctx| {
+            ctx.globals()
+                .set("squares", Class::instance(ctx.clone(), Squares(4)).unwrap())
+                .unwrap();
+            assert_eq!(ctx.eval::<i32, _>("squares[3]").unwrap(), 9);
+            assert!(ctx.eval::<bool, _>("squares[4] === undefined").unwrap());
+            assert!(ctx
+                .eval::<bool, _>("2 in squares && !(5 in squares)")
+                .unwrap());
+            assert_eq!(
+                ctx.eval::<String, _>("Object.keys(squares).join()")
+                    .unwrap(),
+                "0,1,2,3"
+            );
+            assert_eq!(
+                ctx.eval::<String, _>("JSON.stringify(squares)").unwrap(),
+                r#"{"0":0,"1":1,"2":4,"3":9}"#
+            );
+            // Normal properties take precedence.
+            ctx.eval::<(), _>("Object.defineProperty(squares, 1, { value: -1 })")
+                .unwrap();
+            assert_eq!(ctx.eval::<i32, _>("squares[1]").unwrap(), -1);
+        })
+    }
+
+    #[test]
+    fn indexed() {
+        use crate::{
+            class::{ExoticMethods, Indexed},
+            Value,
+        };
+
+        pub struct List(Vec<i32>);
+
+        impl<'js> Trace<'js> for List {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        impl<'js> JsClass<'js> for List {
+            const NAME: &'static str = "List";
+
+            type Mutable = Writable;
+
+            fn class_id() -> &'static ClassId {
+                static ID: ClassId = ClassId::new();
+                &ID
+            }
+
+            fn prototype(ctx: &crate::Ctx<'js>) -> crate::Result<Option<Object<'js>>> {
+                Object::new(ctx.clone()).map(Some)
+            }
+
+            fn constructor(_ctx: &crate::Ctx<'js>) -> crate::Result<Option<Constructor<'js>>> {
+                Ok(None)
+            }
+
+            fn exotic() -> Option<ExoticMethods> {
+                Some(ExoticMethods::indexed::<Self>())
+            }
+        }
+
+        impl<'js> Indexed<'js> for List {
+            fn len(this: &Class<'js, Self>) -> crate::Result<u32> {
+                Ok(this.borrow().0.len() as u32)
+            }
+
+            fn get(this: &Class<'js, Self>, index: u32) -> crate::Result<Value<'js>> {
+                this.borrow().0[index as usize].into_js(this.ctx())
+            }
+
+            fn set(this: &Class<'js, Self>, index: u32, value: Value<'js>) -> crate::Result<()> {
+                let value = i32::from_js(this.ctx(), value)?;
+                let mut list = this.borrow_mut();
+                match (index as usize).cmp(&list.0.len()) {
+                    std::cmp::Ordering::Less => list.0[index as usize] = value,
+                    std::cmp::Ordering::Equal => list.0.push(value),
+                    std::cmp::Ordering::Greater => {
+                        return Err(crate::Exception::throw_range(
+                            this.ctx(),
+                            "index out of range",
+                        ))
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        test_with(|ctx| {
+            let list = Class::instance(ctx.clone(), List(vec![1, 2, 3])).unwrap();
+            ctx.globals().set("list", list.clone()).unwrap();
+            assert_eq!(ctx.eval::<i32, _>("list[1] + list.length").unwrap(), 5);
+            assert!(ctx.eval::<bool, _>("list[3] === undefined").unwrap());
+            assert_eq!(
+                ctx.eval::<String, _>("Object.keys(list).join()").unwrap(),
+                "0,1,2"
+            );
+            assert_eq!(
+                ctx.eval::<i32, _>("let sum = 0; for (const x of list) sum += x; sum")
+                    .unwrap(),
+                6
+            );
+            assert_eq!(
+                ctx.eval::<String, _>("Array.from(list, (x) => x * 2).join()")
+                    .unwrap(),
+                "2,4,6"
+            );
+
+            ctx.eval::<(), _>("list[0] = 10; list[list.length] = 4; list.name = 'list'")
+                .unwrap();
+            assert_eq!(list.borrow().0, vec![10, 2, 3, 4]);
+            assert_eq!(ctx.eval::<String, _>("list.name").unwrap(), "list");
+            assert!(ctx.eval::<(), _>("list[10] = 1").is_err());
+            assert!(ctx.eval::<(), _>("'use strict'; list.length = 0").is_err());
+        })
+    }
 }
@@ -0,0 +1,71 @@
+use super::{ffi, JsClass};
+use crate::qjs;
+use std::{future::Future, pin::Pin};
+
+/// Asynchronous clean up of the resources of a class, like sockets or files.
+///
+/// When an object of a class implementing this trait is garbage collected in an
+/// [`AsyncRuntime`](crate::AsyncRuntime), the Rust value is passed to [`AsyncDrop::async_drop`]
+/// and the returned future is spawned on the runtime, instead of blocking in [`Drop`] or leaking
+/// the resource. In a [`Runtime`](crate::Runtime) the value is just dropped.
+///
+/// The garbage collector can run much later than the resource is last used, so classes should
+/// also provide a method, like `close`, which releases the resource explicitly. `async_drop` then
+/// returns `None` for closed objects.
+///
+/// Futures which haven't finished when the runtime is dropped are dropped without completing.
+/// Objects which are only collected when the runtime is dropped or after it was shut down, for
+/// example because they are reachable from the globals, are dropped without calling
+/// `async_drop`.
+///
+/// Enable the clean up by returning [`AsyncFinalizer::new`] from [`JsClass::async_finalizer`] or
+/// by adding the `async_drop` flag to the [`class`](crate::class) macro.
+///
+/// ```
+/// # use rquickjs::{class::{AsyncDrop, Trace}, Ctx};
+/// # use std::{future::Future, pin::Pin};
+/// # struct Connection;
+/// # impl Connection {
+/// #     async fn shutdown(self) {}
+/// # }
+/// #[derive(Trace)]
+/// #[rquickjs::class(async_drop)]
+/// struct Socket {
+///     #[qjs(skip_trace)]
+///     connection: Option<Connection>,
+/// }
+///
+/// #[rquickjs::methods]
+/// impl Socket {
+///     /// Closes the socket right away instead of when it is garbage collected.
+///     pub fn close<'js>(&mut self, ctx: Ctx<'js>) {
+///         if let Some(connection) = self.connection.take() {
+///             ctx.spawn(connection.shutdown());
+///         }
+///     }
+/// }
+///
+/// impl<'js> AsyncDrop<'js> for Socket {
+///     fn async_drop(self) -> Option<Pin<Box<dyn Future<Output = ()>>>> {
+///         let connection = self.connection?;
+///         Some(Box::pin(connection.shutdown()))
+///     }
+/// }
+/// ```
+pub trait AsyncDrop<'js>: JsClass<'js> + Sized {
+    /// Returns the future cleaning up the resources of a garbage collected object.
+    ///
+    /// The future can't borrow JavaScript values, which are freed by the garbage collector.
+    fn async_drop(self) -> Option<Pin<Box<dyn Future<Output = ()>>>>;
+}
+
+/// The finalizer of a class implementing [`AsyncDrop`].
+#[derive(Clone, Copy, Debug)]
+pub struct AsyncFinalizer(pub(crate) unsafe extern "C" fn(*mut qjs::JSRuntime, qjs::JSValue));
+
+impl AsyncFinalizer {
+    /// Create the finalizer for a type implementing the [`AsyncDrop`] trait.
+    pub fn new<'js, C: AsyncDrop<'js>>() -> Self {
+        AsyncFinalizer(ffi::async_finalizer::<C>)
+    }
+}
@@ -20,6 +20,10 @@ pub unsafe trait Mutability {
     /// Create a new cell.
     fn new_cell<T>(t: T) -> Self::Cell<T>;
 
+    #[doc(hidden)]
+    /// Returns the value of a cell.
+    fn into_inner<T>(cell: Self::Cell<T>) -> T;
+
     #[doc(hidden)]
     /// Will be called before deref
     ///
@@ -77,6 +81,10 @@ unsafe impl Mutability for Readable {
         t
     }
 
+    fn into_inner<T>(cell: Self::Cell<T>) -> T {
+        cell
+    }
+
     unsafe fn borrow<'a, T>(_cell: &'a Self::Cell<T>) -> Result<(), BorrowError> {
         Ok(())
     }
@@ -163,6 +171,10 @@ unsafe impl Mutability for Writable {
         }
     }
 
+    fn into_inner<T>(cell: Self::Cell<T>) -> T {
+        cell.value.into_inner()
+    }
+
     unsafe fn borrow<'a, T>(cell: &'a Self::Cell<T>) -> Result<(), BorrowError> {
         let count = cell.count.get();
         if count == usize::MAX {
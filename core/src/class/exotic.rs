@@ -0,0 +1,173 @@
+use super::{ffi, Class, JsClass};
+use crate::{qjs, Atom, Exception, Result, Value};
+use std::marker::PhantomData;
+
+/// Hooks intercepting the access of properties of the instances of a class.
+///
+/// The hooks are only consulted for properties which aren't stored on the object itself, so a
+/// class wrapping a large Rust structure can materialize its fields when they are accessed
+/// instead of defining all of them up front. This is cheaper than wrapping the object in a
+/// `Proxy`.
+///
+/// The properties returned by the hooks are enumerable, configurable and read-only. Assigning to
+/// them fails, unless the property is explicitly defined on the object first.
+///
+/// Enable the hooks by returning [`ExoticMethods::new`] from [`JsClass::exotic`] or by adding
+/// the `exotic` flag to the [`class`](crate::class) macro.
+///
+/// ```
+/// # use rquickjs::{class::{Exotic, Trace}, Atom, Class, Context, Runtime, Result, Value};
+/// #[derive(Trace)]
+/// #[rquickjs::class(exotic)]
+/// struct Env {
+///     #[qjs(skip_trace)]
+///     vars: Vec<(String, String)>,
+/// }
+///
+/// impl<'js> Exotic<'js> for Env {
+///     fn get_own_property(this: &Class<'js, Self>, key: Atom<'js>) -> Result<Option<Value<'js>>> {
+///         let key = key.to_string()?;
+///         let vars = &this.borrow().vars;
+///         let Some((_, value)) = vars.iter().find(|(name, _)| *name == key) else {
+///             return Ok(None);
+///         };
+///         let value = rquickjs::String::from_str(this.ctx().clone(), value)?;
+///         Ok(Some(value.into_value()))
+///     }
+/// }
+///
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let env = Env {
+///         vars: vec![("HOME".into(), "/home/user".into())],
+///     };
+///     ctx.globals().set("env", env).unwrap();
+///     let home: String = ctx.eval("env.HOME").unwrap();
+///     assert_eq!(home, "/home/user");
+///     assert!(ctx.eval::<bool, _>("env.PATH === undefined").unwrap());
+/// });
+/// ```
+pub trait Exotic<'js>: JsClass<'js> + Sized {
+    /// Returns the value of an own property which isn't stored on the object.
+    ///
+    /// Returning `None` means that the object doesn't have the property, the lookup then
+    /// continues with the prototype.
+    fn get_own_property(this: &Class<'js, Self>, key: Atom<'js>) -> Result<Option<Value<'js>>>;
+
+    /// Returns the keys of the properties provided by [`Exotic::get_own_property`].
+    ///
+    /// The keys are used when enumerating the object, for example by `Object.keys`, and should not
+    /// include properties stored on the object itself. Returns no keys by default.
+    fn own_property_names(this: &Class<'js, Self>) -> Result<Vec<Atom<'js>>> {
+        let _ = this;
+        Ok(Vec::new())
+    }
+}
+
+/// Array like indexing of the instances of a class.
+///
+/// Objects of the class get the properties `0` up to `length - 1`, which are forwarded to
+/// [`Indexed::get`] and [`Indexed::set`], a read-only `length` property and a
+/// `[Symbol.iterator]` method, so they can be used like arrays in scripts, including in
+/// `for...of` loops.
+///
+/// Assigning to an index, including indices beyond the length, calls [`Indexed::set`]. Other
+/// properties behave as on normal objects.
+///
+/// Enable the indexing by returning [`ExoticMethods::indexed`] from [`JsClass::exotic`] or by
+/// adding the `indexed` flag to the [`class`](crate::class) macro.
+///
+/// ```
+/// # use rquickjs::{class::{Indexed, Trace}, Class, Context, IntoJs, Runtime, Result, Value};
+/// #[derive(Trace)]
+/// #[rquickjs::class(indexed)]
+/// struct Samples {
+///     #[qjs(skip_trace)]
+///     values: Vec<f64>,
+/// }
+///
+/// impl<'js> Indexed<'js> for Samples {
+///     fn len(this: &Class<'js, Self>) -> Result<u32> {
+///         Ok(this.borrow().values.len() as u32)
+///     }
+///
+///     fn get(this: &Class<'js, Self>, index: u32) -> Result<Value<'js>> {
+///         this.borrow().values[index as usize].into_js(this.ctx())
+///     }
+/// }
+///
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let samples = Samples {
+///         values: vec![1.0, 2.5, 4.0],
+///     };
+///     ctx.globals().set("samples", samples).unwrap();
+///     let sum: f64 = ctx
+///         .eval("let sum = 0; for (const x of samples) sum += x; sum")
+///         .unwrap();
+///     assert_eq!(sum, 7.5);
+///     assert_eq!(ctx.eval::<f64, _>("samples[samples.length - 1]").unwrap(), 4.0);
+/// });
+/// ```
+pub trait Indexed<'js>: JsClass<'js> + Sized {
+    /// Returns the number of elements, exposed to scripts as `length`.
+    fn len(this: &Class<'js, Self>) -> Result<u32>;
+
+    /// Returns the element at an index smaller than the length.
+    fn get(this: &Class<'js, Self>, index: u32) -> Result<Value<'js>>;
+
+    /// Sets the element at an index.
+    ///
+    /// The index can be larger than or equal to the length. Throws a `TypeError` by default.
+    fn set(this: &Class<'js, Self>, index: u32, value: Value<'js>) -> Result<()> {
+        let _ = (index, value);
+        let message = format!("the elements of {} are read-only", Self::NAME);
+        Err(Exception::throw_type(this.ctx(), &message))
+    }
+}
+
+/// The exotic hooks of a class, see [`Exotic`] and [`Indexed`].
+#[derive(Clone, Copy, Debug)]
+pub struct ExoticMethods(pub(crate) &'static qjs::JSClassExoticMethods);
+
+struct VTable<'js, C>(PhantomData<(&'js (), C)>);
+
+impl<'js, C: Exotic<'js>> VTable<'js, C> {
+    const METHODS: qjs::JSClassExoticMethods = qjs::JSClassExoticMethods {
+        get_own_property: Some(ffi::get_own_property::<C>),
+        get_own_property_names: Some(ffi::get_own_property_names::<C>),
+        delete_property: None,
+        define_own_property: None,
+        has_property: None,
+        get_property: None,
+        set_property: None,
+    };
+}
+
+struct IndexedVTable<'js, C>(PhantomData<(&'js (), C)>);
+
+impl<'js, C: Indexed<'js>> IndexedVTable<'js, C> {
+    const METHODS: qjs::JSClassExoticMethods = qjs::JSClassExoticMethods {
+        get_own_property: Some(ffi::indexed_get_own_property::<C>),
+        get_own_property_names: Some(ffi::indexed_get_own_property_names::<C>),
+        delete_property: None,
+        define_own_property: Some(ffi::indexed_define_own_property::<C>),
+        has_property: None,
+        get_property: None,
+        set_property: None,
+    };
+}
+
+impl ExoticMethods {
+    /// Create the hooks for a type implementing the [`Exotic`] trait.
+    pub fn new<'js, C: Exotic<'js>>() -> Self {
+        ExoticMethods(&VTable::<'js, C>::METHODS)
+    }
+
+    /// Create the hooks for a type implementing the [`Indexed`] trait.
+    pub fn indexed<'js, C: Indexed<'js>>() -> Self {
+        ExoticMethods(&IndexedVTable::<'js, C>::METHODS)
+    }
+}
@@ -1,6 +1,9 @@
-use super::{JsClass, Mutability, Tracer};
-use crate::{class::JsCell, qjs};
-use std::mem;
+use super::{Class, Exotic, Indexed, JsClass, Mutability, Tracer};
+use crate::{
+    atom::PredefinedAtom, class::JsCell, qjs, runtime::raw::Opaque, Atom, Ctx, Exception, Object,
+    Result, Value,
+};
+use std::{mem, panic::AssertUnwindSafe};
 
 /// FFI finalizer, destroying the object once it is delete by the Gc.
 pub(crate) unsafe extern "C" fn finalizer<'js, C: JsClass<'js>>(
@@ -13,6 +16,32 @@ pub(crate) unsafe extern "C" fn finalizer<'js, C: JsClass<'js>>(
     mem::drop(inst);
 }
 
+/// FFI finalizer, spawning the clean up of the object on the runtime if it is async.
+#[cfg(feature = "futures")]
+pub(crate) unsafe extern "C" fn async_finalizer<'js, C: super::AsyncDrop<'js>>(
+    rt: *mut qjs::JSRuntime,
+    val: qjs::JSValue,
+) {
+    let ptr = qjs::JS_GetOpaque(val, C::class_id().get()).cast::<JsCell<C>>();
+    debug_assert!(!ptr.is_null());
+    let inst = <C::Mutable as Mutability>::into_inner(Box::from_raw(ptr).cell);
+    // The opaque is null while the runtime is freed and a shut down runtime doesn't run spawned
+    // futures anymore, the value is dropped right away then.
+    let spawner = qjs::JS_GetRuntimeOpaque(rt)
+        .cast::<Opaque>()
+        .as_mut()
+        .filter(|opaque| !opaque.terminated)
+        .and_then(|opaque| opaque.spawner.as_mut());
+    match spawner {
+        Some(spawner) => {
+            if let Some(future) = inst.async_drop() {
+                spawner.push(future);
+            }
+        }
+        None => mem::drop(inst),
+    }
+}
+
 /// FFI tracing function.
 pub(crate) unsafe extern "C" fn trace<'js, C: JsClass<'js>>(
     rt: *mut qjs::JSRuntime,
@@ -24,3 +53,183 @@ pub(crate) unsafe extern "C" fn trace<'js, C: JsClass<'js>>(
     let tracer = Tracer::from_ffi(rt, mark_func);
     <C::Mutable as Mutability>::deref(&(*ptr).cell).trace(tracer)
 }
+
+/// Runs an exotic hook, turning errors and panics into a pending exception.
+unsafe fn exotic_hook<'js, C, F>(ctx: *mut qjs::JSContext, obj: qjs::JSValue, f: F) -> qjs::c_int
+where
+    C: JsClass<'js>,
+    F: FnOnce(Class<'js, C>) -> Result<qjs::c_int>,
+{
+    let ctx = Ctx::from_ptr(ctx);
+    let res = ctx.handle_panic(AssertUnwindSafe(|| {
+        let res = Class::<C>::from_object(&Object::from_js_value_const(ctx.clone(), obj))
+            .ok_or(crate::Error::Unknown)
+            .and_then(f);
+        match res {
+            Ok(x) => qjs::JS_MKVAL(qjs::JS_TAG_INT, x),
+            Err(e) => e.throw(&ctx),
+        }
+    }));
+    if qjs::JS_VALUE_GET_NORM_TAG(res) == qjs::JS_TAG_EXCEPTION {
+        -1
+    } else {
+        qjs::JS_VALUE_GET_INT(res)
+    }
+}
+
+/// FFI hook for looking up own properties which aren't stored on the object.
+pub(crate) unsafe extern "C" fn get_own_property<'js, C: Exotic<'js>>(
+    ctx: *mut qjs::JSContext,
+    desc: *mut qjs::JSPropertyDescriptor,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+) -> qjs::c_int {
+    exotic_hook::<C, _>(ctx, obj, |this| {
+        let key = Atom::from_atom_val_dup(this.ctx().clone(), prop);
+        let Some(value) = C::get_own_property(&this, key)? else {
+            return Ok(0);
+        };
+        write_desc(
+            desc,
+            value,
+            qjs::JS_PROP_ENUMERABLE | qjs::JS_PROP_CONFIGURABLE,
+        );
+        Ok(1)
+    })
+}
+
+/// FFI hook for listing the properties provided by [`get_own_property`].
+pub(crate) unsafe extern "C" fn get_own_property_names<'js, C: Exotic<'js>>(
+    ctx: *mut qjs::JSContext,
+    ptab: *mut *mut qjs::JSPropertyEnum,
+    plen: *mut u32,
+    obj: qjs::JSValue,
+) -> qjs::c_int {
+    exotic_hook::<C, _>(ctx, obj, |this| {
+        let names = C::own_property_names(&this)?;
+        let names: Vec<_> = names.iter().map(|x| x.atom).collect();
+        write_names(this.ctx(), ptab, plen, &names)?;
+        Ok(0)
+    })
+}
+
+/// Fills in the descriptor of a data property, if QuickJS asked for it.
+unsafe fn write_desc(desc: *mut qjs::JSPropertyDescriptor, value: Value, flags: u32) {
+    if !desc.is_null() {
+        desc.write(qjs::JSPropertyDescriptor {
+            flags: flags as _,
+            value: value.into_js_value(),
+            getter: qjs::JS_UNDEFINED,
+            setter: qjs::JS_UNDEFINED,
+        });
+    }
+}
+
+/// Allocates the table of property names for QuickJS, which takes ownership of the table and
+/// the atoms in it.
+unsafe fn write_names(
+    ctx: &Ctx,
+    ptab: *mut *mut qjs::JSPropertyEnum,
+    plen: *mut u32,
+    names: &[qjs::JSAtom],
+) -> Result<()> {
+    let len = names.len();
+    let size = mem::size_of::<qjs::JSPropertyEnum>() * len.max(1);
+    let tab = qjs::js_malloc(ctx.as_ptr(), size as _).cast::<qjs::JSPropertyEnum>();
+    if tab.is_null() {
+        return Err(crate::Error::Exception);
+    }
+    for (idx, &atom) in names.iter().enumerate() {
+        tab.add(idx).write(qjs::JSPropertyEnum {
+            is_enumerable: 0,
+            atom: qjs::JS_DupAtom(ctx.as_ptr(), atom),
+        });
+    }
+    *ptab = tab;
+    *plen = len as u32;
+    Ok(())
+}
+
+/// QuickJS stores array indices up to `2^31 - 1` as atoms tagged with the highest bit.
+const ATOM_TAG_INT: qjs::JSAtom = 1 << 31;
+
+fn atom_to_index(atom: qjs::JSAtom) -> Option<u32> {
+    (atom & ATOM_TAG_INT != 0).then_some(atom & !ATOM_TAG_INT)
+}
+
+/// FFI hook for looking up the elements, the length and the iterator of indexed classes.
+pub(crate) unsafe extern "C" fn indexed_get_own_property<'js, C: Indexed<'js>>(
+    ctx: *mut qjs::JSContext,
+    desc: *mut qjs::JSPropertyDescriptor,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+) -> qjs::c_int {
+    exotic_hook::<C, _>(ctx, obj, |this| {
+        let ctx = this.ctx();
+        if let Some(index) = atom_to_index(prop) {
+            if index >= C::len(&this)? {
+                return Ok(0);
+            }
+            write_desc(desc, C::get(&this, index)?, qjs::JS_PROP_C_W_E);
+        } else if prop == PredefinedAtom::Length as qjs::JSAtom {
+            let len = Value::new_number(ctx.clone(), C::len(&this)? as f64);
+            write_desc(desc, len, 0);
+        } else if prop == PredefinedAtom::SymbolIterator as qjs::JSAtom {
+            let array: Object = ctx.globals().get(PredefinedAtom::Array)?;
+            let proto: Object = array.get(PredefinedAtom::Prototype)?;
+            let values = proto.get(PredefinedAtom::Values)?;
+            write_desc(desc, values, qjs::JS_PROP_CONFIGURABLE);
+        } else {
+            return Ok(0);
+        }
+        Ok(1)
+    })
+}
+
+/// FFI hook for listing the elements and the length of indexed classes.
+pub(crate) unsafe extern "C" fn indexed_get_own_property_names<'js, C: Indexed<'js>>(
+    ctx: *mut qjs::JSContext,
+    ptab: *mut *mut qjs::JSPropertyEnum,
+    plen: *mut u32,
+    obj: qjs::JSValue,
+) -> qjs::c_int {
+    exotic_hook::<C, _>(ctx, obj, |this| {
+        // Larger indices aren't tagged atoms and therefore not elements.
+        let len = C::len(&this)?.min(ATOM_TAG_INT);
+        let names = (0..len)
+            .map(|idx| idx | ATOM_TAG_INT)
+            .chain([PredefinedAtom::Length as qjs::JSAtom])
+            .collect::<Vec<_>>();
+        write_names(this.ctx(), ptab, plen, &names)?;
+        Ok(0)
+    })
+}
+
+/// FFI hook for assigning the elements of indexed classes.
+pub(crate) unsafe extern "C" fn indexed_define_own_property<'js, C: Indexed<'js>>(
+    ctx: *mut qjs::JSContext,
+    obj: qjs::JSValue,
+    prop: qjs::JSAtom,
+    val: qjs::JSValue,
+    getter: qjs::JSValue,
+    setter: qjs::JSValue,
+    flags: qjs::c_int,
+) -> qjs::c_int {
+    let Some(index) = atom_to_index(prop) else {
+        // Other properties are defined as on normal objects.
+        let flags = flags | qjs::JS_PROP_NO_EXOTIC as qjs::c_int;
+        return qjs::JS_DefineProperty(ctx, obj, prop, val, getter, setter, flags);
+    };
+    exotic_hook::<C, _>(ctx, obj, |this| {
+        let flags = flags as u32;
+        if flags & (qjs::JS_PROP_HAS_GET | qjs::JS_PROP_HAS_SET) != 0 {
+            let message = format!("cannot define an accessor on an element of {}", C::NAME);
+            return Err(Exception::throw_type(this.ctx(), &message));
+        }
+        if flags & qjs::JS_PROP_HAS_VALUE != 0 {
+            let value = Value::from_js_value_const(this.ctx().clone(), val);
+            C::set(&this, index, value)?;
+        }
+        Ok(1)
+    })
+}
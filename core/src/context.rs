@@ -3,7 +3,12 @@
 mod base;
 mod builder;
 mod ctx;
+mod injected;
+mod json;
 mod r#ref;
+mod script;
+mod shared;
+mod snapshot;
 
 #[cfg(feature = "futures")]
 mod r#async;
@@ -23,8 +28,12 @@ pub trait MultiWith<'js> {
 }
 
 pub use base::Context;
-pub use builder::{intrinsic, ContextBuilder, Intrinsic};
+pub use builder::{intrinsic, ContextBuilder, Intrinsic, IntrinsicKind};
 pub use ctx::{Ctx, EvalOptions};
+pub use injected::InjectedGlobals;
+pub use script::CompiledScript;
+pub use shared::SharedGlobals;
+pub use snapshot::Snapshot;
 
 #[cfg(feature = "futures")]
 pub use r#async::AsyncContext;
@@ -130,6 +130,9 @@ impl Drop for Inner {
             }
         };
         guard.runtime.update_stack_top();
+        if unsafe { (*(self.ctx.as_ptr() as *mut qjs::JSRefCountHeader)).ref_count } <= 1 {
+            guard.runtime.forget_context(self.ctx);
+        }
         unsafe { qjs::JS_FreeContext(self.ctx.as_ptr()) }
         // Explicitly drop the guard to ensure it is valid during the entire use of runtime
         mem::drop(guard);
@@ -168,6 +171,7 @@ impl AsyncContext {
         let guard = runtime.inner.lock().await;
         let ctx = NonNull::new(unsafe { qjs::JS_NewContextRaw(guard.runtime.rt.as_ptr()) })
             .ok_or_else(|| Error::Allocation)?;
+        guard.runtime.forget_context(ctx);
         unsafe { I::add_intrinsic(ctx) };
         let res = Inner {
             ctx,
@@ -179,13 +183,15 @@ impl AsyncContext {
         Ok(AsyncContext(ContextRef::new(res)))
     }
 
-    /// Creates a context with all standard available intrinsics registered.
+    /// Creates a context with all standard available intrinsics and `queueMicrotask` registered.
     /// If precise control is required of which functions are available use
     /// [`AsyncContext::custom`] or [`AsyncContext::builder`].
     pub async fn full(runtime: &AsyncRuntime) -> Result<Self> {
         let guard = runtime.inner.lock().await;
         let ctx = NonNull::new(unsafe { qjs::JS_NewContext(guard.runtime.rt.as_ptr()) })
             .ok_or_else(|| Error::Allocation)?;
+        guard.runtime.forget_context(ctx);
+        unsafe { intrinsic::QueueMicrotask::add_intrinsic(ctx) };
         let res = Inner {
             ctx,
             rt: runtime.clone(),
@@ -244,6 +250,9 @@ impl AsyncContext {
     {
         let guard = self.0.rt.inner.lock().await;
         guard.runtime.update_stack_top();
+        guard.runtime.free_deferred();
+        #[cfg(feature = "allocator")]
+        let _scope = guard.runtime.enter_context(self.0.ctx);
         let ctx = unsafe { Ctx::new_async(self) };
         let res = f(ctx);
         guard.drop_pending();
@@ -88,6 +88,9 @@ where
         };
 
         lock.runtime.update_stack_top();
+        lock.runtime.free_deferred();
+        #[cfg(feature = "allocator")]
+        let _scope = lock.runtime.enter_context(this.context.0.ctx);
 
         // At this point we have locked the runtime so we start running the actual future
         // we can move this memory since the future is boxed and thus movable.
@@ -1,17 +1,39 @@
-use super::{intrinsic, r#ref::ContextRef, ContextBuilder, Intrinsic};
-use crate::{class::Class, function::RustFunction, qjs, Ctx, Error, Result, Runtime};
-use std::{mem, ptr::NonNull};
+use super::{intrinsic, r#ref::ContextRef, ContextBuilder, Intrinsic, IntrinsicKind};
+use crate::{
+    class::Class, function::RustFunction, qjs, runtime::RuntimeOptions, Ctx, Error, Exception,
+    FromJs, Persistent, Result, Runtime, Value,
+};
+#[cfg(feature = "futures")]
+use std::{future::Future, pin::Pin};
+use std::{mem, ptr::NonNull, string::String as StdString, time::Duration};
+
+#[cfg(feature = "futures")]
+mod future;
+
+#[cfg(feature = "futures")]
+use future::WithFuture;
+
+/// The intrinsics a context was created with, used to create it again in [`Context::reset`].
+#[derive(Clone)]
+pub(crate) enum Init {
+    /// All standard intrinsics, also used for contexts created from a raw pointer.
+    Full,
+    Custom(unsafe fn(NonNull<qjs::JSContext>)),
+    Intrinsics(Vec<IntrinsicKind>),
+}
 
 pub(crate) struct Inner {
     pub(crate) ctx: NonNull<qjs::JSContext>,
     pub(crate) rt: Runtime,
+    pub(crate) init: Init,
 }
 
 impl Clone for Inner {
     fn clone(&self) -> Inner {
         let ctx = unsafe { NonNull::new_unchecked(qjs::JS_DupContext(self.ctx.as_ptr())) };
         let rt = self.rt.clone();
-        Self { ctx, rt }
+        let init = self.init.clone();
+        Self { ctx, rt, init }
     }
 }
 
@@ -29,7 +51,11 @@ impl Context {
     /// The context must also have valid reference count, one which can be decremented when this
     /// object is dropped without going negative.
     pub unsafe fn from_raw(ctx: NonNull<qjs::JSContext>, rt: Runtime) -> Self {
-        Context(ContextRef::new(Inner { ctx, rt }))
+        Context(ContextRef::new(Inner {
+            ctx,
+            rt,
+            init: Init::Full,
+        }))
     }
 
     pub fn as_raw(&self) -> NonNull<qjs::JSContext> {
@@ -47,33 +73,81 @@ impl Context {
     /// If additional functions are required use [`Context::custom`],
     /// [`Context::builder`] or [`Context::full`].
     pub fn custom<I: Intrinsic>(runtime: &Runtime) -> Result<Self> {
+        Self::from_add_intrinsic(runtime, I::add_intrinsic)
+    }
+
+    fn from_add_intrinsic(
+        runtime: &Runtime,
+        add_intrinsic: unsafe fn(NonNull<qjs::JSContext>),
+    ) -> Result<Self> {
         let guard = runtime.inner.lock();
         let ctx = NonNull::new(unsafe { qjs::JS_NewContextRaw(guard.rt.as_ptr()) })
             .ok_or_else(|| Error::Allocation)?;
+        guard.forget_context(ctx);
         // rquickjs assumes the base objects exist, so we allways need to add this.
         unsafe { intrinsic::Base::add_intrinsic(ctx) };
-        unsafe { I::add_intrinsic(ctx) };
+        unsafe { add_intrinsic(ctx) };
         unsafe { Self::init_raw(ctx.as_ptr()) }
         let res = Inner {
             ctx,
             rt: runtime.clone(),
+            init: Init::Custom(add_intrinsic),
         };
         mem::drop(guard);
 
         Ok(Context(ContextRef::new(res)))
     }
 
-    /// Creates a context with all standard available intrinsics registered.
+    /// Creates a context with a set of intrinsics which is only known at runtime.
+    ///
+    /// Like [`Context::custom`] the base objects are always registered.
+    pub fn from_intrinsics(runtime: &Runtime, intrinsics: &[IntrinsicKind]) -> Result<Self> {
+        let guard = runtime.inner.lock();
+        let ctx = NonNull::new(unsafe { qjs::JS_NewContextRaw(guard.rt.as_ptr()) })
+            .ok_or_else(|| Error::Allocation)?;
+        guard.forget_context(ctx);
+        unsafe { intrinsic::Base::add_intrinsic(ctx) };
+        for intrinsic in intrinsics {
+            unsafe { intrinsic.add_intrinsic(ctx) };
+        }
+        unsafe { Self::init_raw(ctx.as_ptr()) }
+        let res = Inner {
+            ctx,
+            rt: runtime.clone(),
+            init: Init::Intrinsics(intrinsics.to_vec()),
+        };
+        mem::drop(guard);
+
+        Ok(Context(ContextRef::new(res)))
+    }
+
+    /// Creates a context with the intrinsics enabled in the options.
+    ///
+    /// If the options don't specify any intrinsics all standard intrinsics are registered, like
+    /// [`Context::full`].
+    pub fn from_options(runtime: &Runtime, options: &RuntimeOptions) -> Result<Self> {
+        match options.intrinsics {
+            Some(ref intrinsics) => Self::from_intrinsics(runtime, intrinsics),
+            None => Self::full(runtime),
+        }
+    }
+
+    /// Creates a context with all standard available intrinsics and `queueMicrotask` registered.
     /// If precise control is required of which functions are available use
     /// [`Context::custom`] or [`Context::builder`].
     pub fn full(runtime: &Runtime) -> Result<Self> {
         let guard = runtime.inner.lock();
         let ctx = NonNull::new(unsafe { qjs::JS_NewContext(guard.rt.as_ptr()) })
             .ok_or_else(|| Error::Allocation)?;
-        unsafe { Self::init_raw(ctx.as_ptr()) }
+        guard.forget_context(ctx);
+        unsafe {
+            Self::init_raw(ctx.as_ptr());
+            intrinsic::QueueMicrotask::add_intrinsic(ctx);
+        }
         let res = Inner {
             ctx,
             rt: runtime.clone(),
+            init: Init::Full,
         };
         // Explicitly drop the guard to ensure it is valid during the entire use of runtime
         mem::drop(guard);
@@ -94,6 +168,119 @@ impl Context {
         mem::drop(guard)
     }
 
+    /// Replaces the context with a new context of the same runtime and with the same intrinsics,
+    /// clearing all its state.
+    ///
+    /// Other handles to the old context, like clones of this `Context`, keep using the old
+    /// context. The old context is freed together with the last of its objects, which is
+    /// collected right away if nothing refers to it anymore.
+    ///
+    /// Resetting a context is cheaper than creating a new runtime but the memory of the old
+    /// context stays with the runtime, including memory allocated from an `Arena` which only
+    /// returns to the arena when the runtime is dropped. To reclaim all memory of a run-once
+    /// execution at once, give it a runtime of its own allocated from an `Arena`, see the
+    /// `allocator` module.
+    pub fn reset(&mut self) -> Result<()> {
+        let runtime = self.runtime().clone();
+        *self = self.new_like()?;
+        runtime.run_gc();
+        Ok(())
+    }
+
+    /// Creates a new context of the same runtime and with the same intrinsics which starts with
+    /// a deep copy of the globals of this context.
+    ///
+    /// The data globals are copied like a [`Snapshot`](super::Snapshot) taken with
+    /// [`Ctx::freeze`], so changes to them in one context don't affect the other. Globals which
+    /// are Rust functions are shared by reference. This makes it cheap to run tests against a
+    /// prepared context without one test contaminating another.
+    ///
+    /// Functions defined by scripts can't be copied and sharing them would let the fork modify
+    /// the globals of this context through their closures, so a global which is a JS function
+    /// makes the fork fail. Use [`Context::fork_with`] to define them again in the fork.
+    ///
+    /// Returns an error if a data global can't be copied, like an object of a Rust class or an
+    /// object containing functions.
+    pub fn fork(&self) -> Result<Context> {
+        self.fork_with(|_| Ok(()))
+    }
+
+    /// Creates a fork of this context like [`Context::fork`], calling `setup` in the fork before
+    /// the globals are copied.
+    ///
+    /// `setup` can evaluate the scripts which define the JS function globals of this context, so
+    /// the functions of the fork close over the globals of the fork. The copied data globals
+    /// then overwrite the globals set by `setup`.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Ctx, Result, Runtime};
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// let script = "var count = 0; function next() { return ++count; }";
+    /// let setup = |ctx: Ctx| ctx.eval::<(), _>(script);
+    /// ctx.with(|ctx| setup(ctx.clone()).unwrap());
+    ///
+    /// let fork = ctx.fork_with(setup).unwrap();
+    /// fork.with(|ctx| assert_eq!(ctx.eval::<i32, _>("next()").unwrap(), 1));
+    /// ctx.with(|ctx| assert_eq!(ctx.eval::<i32, _>("count").unwrap(), 0));
+    /// ```
+    pub fn fork_with<F>(&self, setup: F) -> Result<Context>
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> Result<()>,
+    {
+        let fork = self.new_like()?;
+        let defined = fork.with(|ctx| -> Result<Vec<StdString>> {
+            setup(ctx.clone())?;
+            let mut defined = Vec::new();
+            for prop in ctx.globals().props::<StdString, Value>() {
+                let (key, value) = prop?;
+                if value.is_function() {
+                    defined.push(key);
+                }
+            }
+            Ok(defined)
+        })?;
+
+        let (snapshot, functions) = self.with(|ctx| -> Result<_> {
+            let mut functions = Vec::new();
+            for prop in ctx.globals().props::<StdString, Value>() {
+                let (key, value) = prop?;
+                if let Some(func) = value.into_function() {
+                    if func.as_inner().instance_of::<RustFunction>() {
+                        functions.push((key, Persistent::save(&ctx, func)));
+                    } else if !defined.contains(&key) {
+                        return Err(Exception::throw_type(
+                            &ctx,
+                            &format!(
+                                "can't fork the JS function global `{key}`, define it in the fork"
+                            ),
+                        ));
+                    }
+                }
+            }
+            Ok((ctx.freeze()?, functions))
+        })?;
+        fork.with(|ctx| -> Result<()> {
+            ctx.thaw(&snapshot)?;
+            let globals = ctx.globals();
+            for (key, func) in functions {
+                globals.set(key, func.restore(&ctx)?)?;
+            }
+            Ok(())
+        })?;
+        Ok(fork)
+    }
+
+    /// Creates a new context of the same runtime and with the same intrinsics.
+    fn new_like(&self) -> Result<Context> {
+        let runtime = self.runtime();
+        match self.0.init {
+            Init::Full => Self::full(runtime),
+            Init::Intrinsics(ref intrinsics) => Self::from_intrinsics(runtime, intrinsics),
+            Init::Custom(add_intrinsic) => Self::from_add_intrinsic(runtime, add_intrinsic),
+        }
+    }
+
     /// Returns the associated runtime
     pub fn runtime(&self) -> &Runtime {
         &self.0.rt
@@ -118,10 +305,68 @@ impl Context {
     {
         let guard = self.0.rt.inner.lock();
         guard.update_stack_top();
+        guard.free_deferred();
+        #[cfg(feature = "allocator")]
+        let _scope = guard.enter_context(self.0.ctx);
         let ctx = unsafe { Ctx::new(self) };
         f(ctx)
     }
 
+    /// Evaluate a script, interrupting it when it runs longer than `timeout`.
+    ///
+    /// Protects the host from runaway user supplied scripts, see [`Ctx::eval_with_timeout`].
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Error, Runtime};
+    /// # use std::time::Duration;
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// let res = ctx.eval_with_timeout::<(), _>("while(true){}", Duration::from_millis(10));
+    /// assert!(matches!(res, Err(Error::Timeout)));
+    /// let res: i32 = ctx.eval_with_timeout("1 + 1", Duration::from_millis(10)).unwrap();
+    /// assert_eq!(res, 2);
+    /// ```
+    pub fn eval_with_timeout<V, S>(&self, source: S, timeout: Duration) -> Result<V>
+    where
+        V: for<'js> FromJs<'js>,
+        S: Into<Vec<u8>>,
+    {
+        self.with(|ctx| ctx.eval_with_timeout(source, timeout))
+    }
+
+    /// A entry point for using JavaScript objects and scripts from asynchronous code.
+    ///
+    /// Unlike [`Context::with`] the runtime is only locked while the returned future is polled,
+    /// so the closure can await Rust futures between JavaScript operations without blocking other
+    /// users of the runtime. Pending jobs are executed whenever the future is waiting, so
+    /// JavaScript promises can be awaited as well. Spawning futures with [`Ctx::spawn`] requires
+    /// an [`AsyncContext`](crate::AsyncContext).
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Promise, Runtime};
+    /// # async fn run() {
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// let res = ctx
+    ///     .async_with(|ctx| {
+    ///         Box::pin(async move {
+    ///             let promise: Promise = ctx.eval("Promise.resolve(1)").unwrap();
+    ///             promise.into_future::<i32>().await.unwrap()
+    ///         })
+    ///     })
+    ///     .await;
+    /// assert_eq!(res, 1);
+    /// # }
+    /// ```
+    #[cfg(feature = "futures")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+    pub fn async_with<F, R>(&self, f: F) -> WithFuture<'_, F, R>
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> Pin<Box<dyn Future<Output = R> + 'js>>,
+    {
+        WithFuture::new(self, f)
+    }
+
     pub(crate) unsafe fn init_raw(ctx: *mut qjs::JSContext) {
         Class::<RustFunction>::register(&Ctx::from_ptr(ctx))
             .expect("failed to initialized callback class");
@@ -147,6 +392,9 @@ impl Drop for Context {
             }
         };
         guard.update_stack_top();
+        if unsafe { (*(self.0.ctx.as_ptr() as *mut qjs::JSRefCountHeader)).ref_count } <= 1 {
+            guard.forget_context(self.0.ctx);
+        }
         unsafe { qjs::JS_FreeContext(self.0.ctx.as_ptr()) }
         // Explicitly drop the guard to ensure it is valid during the entire use of runtime
         mem::drop(guard);
@@ -194,6 +442,91 @@ mod test {
         });
     }
 
+    #[test]
+    fn reset() {
+        let rt = Runtime::new().unwrap();
+        let mut ctx = Context::custom::<(intrinsic::Eval, intrinsic::Json)>(&rt).unwrap();
+        ctx.with(|ctx| ctx.eval::<(), _>("globalThis.x = 1").unwrap());
+        ctx.reset().unwrap();
+        ctx.with(|ctx| {
+            let res: bool = ctx
+                .eval("typeof x === 'undefined' && typeof JSON === 'object' && typeof Date === 'undefined'")
+                .unwrap();
+            assert!(res);
+        });
+    }
+
+    #[test]
+    fn fork() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.globals()
+                .set("double", Function::new(ctx.clone(), |x: i32| x * 2))
+                .unwrap();
+            ctx.eval::<(), _>("var state = { list: [1, 2] };").unwrap();
+        });
+
+        let fork = ctx.fork().unwrap();
+        fork.with(|ctx| {
+            let res: i32 = ctx
+                .eval("state.list.push(3); double(state.list.length)")
+                .unwrap();
+            assert_eq!(res, 6);
+            ctx.eval::<(), _>("globalThis.added = true").unwrap();
+        });
+        ctx.with(|ctx| {
+            let res: bool = ctx
+                .eval("state.list.length === 2 && typeof added === 'undefined'")
+                .unwrap();
+            assert!(res);
+        });
+    }
+
+    #[test]
+    fn fork_js_functions() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let script = "var count = 0; function next() { return ++count; }";
+        ctx.with(|ctx| ctx.eval::<(), _>(script).unwrap());
+
+        // Sharing `next` would increment the count of this context from the fork.
+        let err = ctx.fork().err().unwrap();
+        ctx.with(|ctx| {
+            let err = CaughtError::from_error(&ctx, err).to_string();
+            assert!(err.contains("`next`"), "{err}");
+        });
+
+        ctx.with(|ctx| assert_eq!(ctx.eval::<i32, _>("next()").unwrap(), 1));
+        let fork = ctx.fork_with(|ctx| ctx.eval(script)).unwrap();
+        fork.with(|ctx| {
+            assert_eq!(ctx.eval::<i32, _>("next()").unwrap(), 2);
+            assert_eq!(ctx.eval::<i32, _>("next()").unwrap(), 3);
+        });
+        ctx.with(|ctx| assert_eq!(ctx.eval::<i32, _>("count").unwrap(), 1));
+
+        // Functions nested in data globals can't be copied either.
+        ctx.with(|ctx| ctx.eval::<(), _>("var api = { next };").unwrap());
+        assert!(ctx.fork_with(|ctx| ctx.eval(script)).is_err());
+    }
+
+    #[cfg(feature = "allocator")]
+    #[test]
+    fn reset_arena() {
+        let allocator = allocator::Arena::new().allocator();
+        let rt = Runtime::new_with_alloc(allocator).unwrap();
+        let mut ctx = Context::from_intrinsics(&rt, &[IntrinsicKind::Eval]).unwrap();
+        for _ in 0..3 {
+            ctx.with(|ctx| {
+                let res: i32 = ctx
+                    .eval("globalThis.n = (globalThis.n || 0) + 1; n")
+                    .unwrap();
+                assert_eq!(res, 1);
+            });
+            ctx.reset().unwrap();
+        }
+    }
+
     #[test]
     fn module() {
         test_with(|ctx| {
@@ -273,4 +606,57 @@ mod test {
             }
         });
     }
+
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn async_with() {
+        use std::time::Duration;
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let task = ctx.async_with(|ctx| {
+            Box::pin(async move {
+                ctx.globals().set("value", 1).unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let promise: Promise = ctx.eval("Promise.resolve(value + 1)").unwrap();
+                promise.into_future::<i32>().await.unwrap()
+            })
+        });
+        // The runtime is not locked while the task awaits the sleep.
+        let other = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            ctx.with(|ctx| ctx.globals().set("value", 41).unwrap());
+        };
+        let (res, _) = tokio::join!(task, other);
+        assert_eq!(res, 42);
+    }
+
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn async_with_dropped_pending() {
+        use std::time::Duration;
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let task = ctx.async_with(|ctx| {
+            Box::pin(async move {
+                let object = Object::new(ctx.clone()).unwrap();
+                let promise: Promise = ctx.eval("new Promise(() => {})").unwrap();
+                promise.into_future::<()>().await.unwrap();
+                drop(object);
+            })
+        });
+        // The task never finishes, so it is dropped while it holds JS values.
+        assert!(tokio::time::timeout(Duration::from_millis(10), task)
+            .await
+            .is_err());
+        ctx.with(|ctx| {
+            let res: i32 = ctx.eval("1 + 1").unwrap();
+            assert_eq!(res, 2);
+        });
+        rt.run_gc();
+        // Freeing the runtime asserts that no objects are leaked.
+        drop(ctx);
+        drop(rt);
+    }
 }
@@ -0,0 +1,101 @@
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use crate::{Context, Ctx};
+
+pub struct WithFuture<'a, F, R> {
+    context: &'a Context,
+    state: WithFutureState<'a, F, R>,
+}
+
+enum WithFutureState<'a, F, R> {
+    Initial {
+        closure: F,
+    },
+    FutureCreated {
+        future: Pin<Box<dyn Future<Output = R> + 'a>>,
+    },
+    Done,
+}
+
+impl<'a, F, R> WithFuture<'a, F, R>
+where
+    F: for<'js> FnOnce(Ctx<'js>) -> Pin<Box<dyn Future<Output = R> + 'js>>,
+{
+    pub fn new(context: &'a Context, f: F) -> Self {
+        Self {
+            context,
+            state: WithFutureState::Initial { closure: f },
+        }
+    }
+}
+
+impl<'a, F, R> Drop for WithFuture<'a, F, R> {
+    fn drop(&mut self) {
+        // A pending future can hold JS values, which must only be freed while holding the lock.
+        if let WithFutureState::FutureCreated { future } =
+            mem::replace(&mut self.state, WithFutureState::Done)
+        {
+            let lock = self.context.0.rt.inner.lock();
+            lock.update_stack_top();
+            mem::drop(future);
+            lock.free_deferred();
+        }
+    }
+}
+
+impl<'a, F, R> Future for WithFuture<'a, F, R>
+where
+    F: for<'js> FnOnce(Ctx<'js>) -> Pin<Box<dyn Future<Output = R> + 'js>>,
+{
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        // SAFETY: Nothing is structurally pinned, the future is boxed.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        // The lock is only held while polling, so it is released across await points of the
+        // future and other code can use the runtime in the meantime.
+        let mut lock = this.context.0.rt.inner.lock();
+        lock.update_stack_top();
+        lock.free_deferred();
+
+        let mut future = match mem::replace(&mut this.state, WithFutureState::Done) {
+            WithFutureState::Initial { closure } => {
+                // SAFETY: we have a lock, so creating this ctx is save.
+                let ctx = unsafe { Ctx::new(this.context) };
+                closure(ctx)
+            }
+            WithFutureState::FutureCreated { future } => future,
+            // The future was called an additional time,
+            // We don't have anything valid to do here so just panic.
+            WithFutureState::Done => panic!("With future called after it returned"),
+        };
+
+        let res = loop {
+            if let Poll::Ready(x) = future.as_mut().poll(cx) {
+                break Poll::Ready(x);
+            }
+
+            // Promises are resolved by jobs, so run them to see if the future can make progress.
+            let mut made_progress = false;
+            while let Ok(true) | Err(_) = lock.execute_pending_job() {
+                made_progress = true;
+            }
+
+            if !made_progress {
+                this.state = WithFutureState::FutureCreated { future };
+                break Poll::Pending;
+            }
+        };
+
+        // Manually drop the lock so it isn't accidentally moved into somewhere.
+        mem::drop(lock);
+
+        res
+    }
+}
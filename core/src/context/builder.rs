@@ -1,8 +1,8 @@
-use std::{marker::PhantomData, ptr::NonNull};
+use std::{marker::PhantomData, ptr::NonNull, str::FromStr};
 
 #[cfg(feature = "futures")]
 use crate::{context::AsyncContext, runtime::AsyncRuntime};
-use crate::{qjs, Context, Result, Runtime};
+use crate::{qjs, Context, Error, Result, Runtime};
 
 /// The internal trait to add JS builtins
 pub trait Intrinsic {
@@ -84,6 +84,63 @@ pub mod intrinsic {
         BignumExt JS_EnableBignumExt (1),
     }
 
+    /// Add a minimal `Intl` with `NumberFormat` and `DateTimeFormat`
+    ///
+    /// It isn't part of [`All`] as it is implemented in Rust instead of QuickJS.
+    #[cfg(feature = "intl")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "intl")))]
+    pub struct Intl;
+
+    #[cfg(feature = "intl")]
+    impl Intrinsic for Intl {
+        unsafe fn add_intrinsic(ctx: NonNull<qjs::JSContext>) {
+            let ctx = crate::Ctx::from_raw(ctx);
+            if crate::intl::define(&ctx).is_err() {
+                ctx.catch();
+            }
+        }
+    }
+
+    /// Add the `queueMicrotask` global
+    ///
+    /// It is also added to the contexts created with [`Context::full`](crate::Context::full). It
+    /// isn't part of [`All`] as it is implemented in Rust instead of QuickJS.
+    pub struct QueueMicrotask;
+
+    impl Intrinsic for QueueMicrotask {
+        unsafe fn add_intrinsic(ctx: NonNull<qjs::JSContext>) {
+            fn define(ctx: &crate::Ctx<'_>) -> crate::Result<()> {
+                let queue = crate::Function::new(ctx.clone(), |callback: crate::Function| {
+                    callback.defer(())
+                })?
+                .with_name("queueMicrotask")?;
+                ctx.globals().set("queueMicrotask", queue)
+            }
+
+            let ctx = crate::Ctx::from_raw(ctx);
+            if define(&ctx).is_err() {
+                ctx.catch();
+            }
+        }
+    }
+
+    /// Add a `performance` global with `now()` read from a
+    /// [`MonotonicClock`](crate::performance::MonotonicClock)
+    ///
+    /// It isn't part of [`All`] as it is implemented in Rust instead of QuickJS. Use
+    /// [`performance::define`](crate::performance::define) for another clock.
+    pub struct Performance;
+
+    impl Intrinsic for Performance {
+        unsafe fn add_intrinsic(ctx: NonNull<qjs::JSContext>) {
+            let ctx = crate::Ctx::from_raw(ctx);
+            let clock = crate::performance::MonotonicClock::new();
+            if crate::performance::define(&ctx, clock).is_err() {
+                ctx.catch();
+            }
+        }
+    }
+
     /// An alias for [`BaseObjects`]
     pub type Base = BaseObjects;
 
@@ -132,6 +189,110 @@ intrinsic_impls! {
     A B C D E F G H I J K L M N O P R,
 }
 
+/// An intrinsic selected at runtime.
+///
+/// Used when the set of intrinsics is only known at runtime, for example when it is read from a
+/// configuration file, see [`Context::from_intrinsics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IntrinsicKind {
+    Date,
+    Eval,
+    StringNormalize,
+    RegExpCompiler,
+    RegExp,
+    Json,
+    Proxy,
+    MapSet,
+    TypedArrays,
+    Promise,
+    BigInt,
+    BigFloat,
+    BigDecimal,
+    Operators,
+    BignumExt,
+}
+
+impl IntrinsicKind {
+    /// All the intrinsics, the same set as [`intrinsic::All`].
+    pub const ALL: [IntrinsicKind; 15] = [
+        IntrinsicKind::Date,
+        IntrinsicKind::Eval,
+        IntrinsicKind::StringNormalize,
+        IntrinsicKind::RegExpCompiler,
+        IntrinsicKind::RegExp,
+        IntrinsicKind::Json,
+        IntrinsicKind::Proxy,
+        IntrinsicKind::MapSet,
+        IntrinsicKind::TypedArrays,
+        IntrinsicKind::Promise,
+        IntrinsicKind::BigInt,
+        IntrinsicKind::BigFloat,
+        IntrinsicKind::BigDecimal,
+        IntrinsicKind::Operators,
+        IntrinsicKind::BignumExt,
+    ];
+
+    /// Returns the name of the intrinsic, the same name which is accepted by
+    /// [`FromStr`](std::str::FromStr).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IntrinsicKind::Date => "date",
+            IntrinsicKind::Eval => "eval",
+            IntrinsicKind::StringNormalize => "string_normalize",
+            IntrinsicKind::RegExpCompiler => "reg_exp_compiler",
+            IntrinsicKind::RegExp => "reg_exp",
+            IntrinsicKind::Json => "json",
+            IntrinsicKind::Proxy => "proxy",
+            IntrinsicKind::MapSet => "map_set",
+            IntrinsicKind::TypedArrays => "typed_arrays",
+            IntrinsicKind::Promise => "promise",
+            IntrinsicKind::BigInt => "big_int",
+            IntrinsicKind::BigFloat => "big_float",
+            IntrinsicKind::BigDecimal => "big_decimal",
+            IntrinsicKind::Operators => "operators",
+            IntrinsicKind::BignumExt => "bignum_ext",
+        }
+    }
+
+    /// # Safety
+    /// Same as [`Intrinsic::add_intrinsic`].
+    pub unsafe fn add_intrinsic(self, ctx: NonNull<qjs::JSContext>) {
+        match self {
+            IntrinsicKind::Date => intrinsic::Date::add_intrinsic(ctx),
+            IntrinsicKind::Eval => intrinsic::Eval::add_intrinsic(ctx),
+            IntrinsicKind::StringNormalize => intrinsic::StringNormalize::add_intrinsic(ctx),
+            IntrinsicKind::RegExpCompiler => intrinsic::RegExpCompiler::add_intrinsic(ctx),
+            IntrinsicKind::RegExp => intrinsic::RegExp::add_intrinsic(ctx),
+            IntrinsicKind::Json => intrinsic::Json::add_intrinsic(ctx),
+            IntrinsicKind::Proxy => intrinsic::Proxy::add_intrinsic(ctx),
+            IntrinsicKind::MapSet => intrinsic::MapSet::add_intrinsic(ctx),
+            IntrinsicKind::TypedArrays => intrinsic::TypedArrays::add_intrinsic(ctx),
+            IntrinsicKind::Promise => intrinsic::Promise::add_intrinsic(ctx),
+            IntrinsicKind::BigInt => intrinsic::BigInt::add_intrinsic(ctx),
+            IntrinsicKind::BigFloat => intrinsic::BigFloat::add_intrinsic(ctx),
+            IntrinsicKind::BigDecimal => intrinsic::BigDecimal::add_intrinsic(ctx),
+            IntrinsicKind::Operators => intrinsic::Operators::add_intrinsic(ctx),
+            IntrinsicKind::BignumExt => intrinsic::BignumExt::add_intrinsic(ctx),
+        }
+    }
+}
+
+impl FromStr for IntrinsicKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        IntrinsicKind::ALL
+            .iter()
+            .copied()
+            .find(|x| x.as_str() == s)
+            .ok_or_else(|| {
+                Error::new_invalid_option("intrinsics", format!("unknown intrinsic `{s}`"))
+            })
+    }
+}
+
 impl Default for ContextBuilder<()> {
     fn default() -> Self {
         ContextBuilder(PhantomData)
@@ -187,4 +348,22 @@ mod tests {
         let result: usize = ctx.with(|ctx| ctx.eval("test+1")).unwrap();
         assert_eq!(result, 43);
     }
+
+    #[test]
+    fn intrinsic_kind() {
+        for kind in IntrinsicKind::ALL {
+            assert_eq!(kind.as_str().parse::<IntrinsicKind>().unwrap(), kind);
+        }
+        assert!("foo".parse::<IntrinsicKind>().is_err());
+
+        let rt = crate::Runtime::new().unwrap();
+        let ctx = Context::from_intrinsics(&rt, &[IntrinsicKind::Json]).unwrap();
+        ctx.with(|ctx| {
+            let globals = ctx.globals();
+            assert!(globals.contains_key("JSON").unwrap());
+            assert!(!globals.contains_key("Map").unwrap());
+            // Without the eval intrinsic scripts can't be evaluated.
+            assert!(ctx.eval::<(), _>("1").is_err());
+        });
+    }
 }
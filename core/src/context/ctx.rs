@@ -1,22 +1,42 @@
-#[cfg(feature = "futures")]
-use std::future::Future;
 use std::{
     ffi::{CStr, CString},
     fs,
+    future::Future,
+    io,
     mem::{self, MaybeUninit},
     path::Path,
+    pin::Pin,
     ptr::NonNull,
+    slice,
+    sync::{
+        atomic::{fence, AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context as TaskContext, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant},
 };
 
+#[cfg(feature = "loader")]
+use crate::loader::ImportMap;
 #[cfg(feature = "futures")]
 use crate::AsyncContext;
 use crate::{
-    atom::PredefinedAtom, cstr, markers::Invariant, qjs, runtime::raw::Opaque, Atom, Context,
-    Error, FromJs, Function, IntoJs, Object, Promise, Result, String, Value,
+    atom::PredefinedAtom,
+    context::json::JsonWriter,
+    cstr,
+    function::{Args, OnceFn, This},
+    markers::Invariant,
+    qjs,
+    runtime::raw::{stack_pointer, update_interrupt_handler, Opaque},
+    source::SourceFile,
+    Atom, CatchResultExt, CaughtResult, Context, Error, Exception, FromJs, Function, IntoJs,
+    Module, Object, Promise, Result, String, Value,
 };
 
 /// Eval options.
 #[non_exhaustive]
+#[derive(Debug, Clone)]
 pub struct EvalOptions {
     /// Global code.
     pub global: bool,
@@ -26,6 +46,8 @@ pub struct EvalOptions {
     pub backtrace_barrier: bool,
     /// Support top-level-await.
     pub promise: bool,
+    /// Origin label the evaluation is attributed to, see [`Ctx::with_origin`].
+    pub origin: Option<std::string::String>,
 }
 
 impl EvalOptions {
@@ -59,6 +81,7 @@ impl Default for EvalOptions {
             strict: true,
             backtrace_barrier: false,
             promise: false,
+            origin: None,
         }
     }
 }
@@ -125,8 +148,16 @@ impl<'js> Ctx<'js> {
         file_name: &CStr,
         flag: i32,
     ) -> Result<qjs::JSValue> {
+        if (*self.get_opaque()).termination_requested() {
+            return Err(Error::Terminated);
+        }
         let src = source.into();
         let len = src.len();
+        if let Some(sources) = (*self.get_opaque()).sources.as_mut() {
+            let name = file_name.to_string_lossy().into_owned();
+            let source = SourceFile::new(name.clone(), src.clone());
+            sources.insert(name, Arc::new(source));
+        }
         let src = CString::new(src)?;
         let val = qjs::JS_Eval(
             self.ctx.as_ptr(),
@@ -143,6 +174,39 @@ impl<'js> Ctx<'js> {
         self.eval_with_options(source, Default::default())
     }
 
+    /// Evaluate a script in global context, interrupting it when it runs longer than `timeout`.
+    ///
+    /// A script which is interrupted returns [`Error::Timeout`] and the context can be used
+    /// again afterwards. The deadline is checked when the engine checks for interrupts, so a
+    /// script blocked in a call to Rust is only interrupted once the call returns. Nested
+    /// evaluations are interrupted at the earliest deadline.
+    pub fn eval_with_timeout<V: FromJs<'js>, S: Into<Vec<u8>>>(
+        &self,
+        source: S,
+        timeout: Duration,
+    ) -> Result<V> {
+        let deadline = Instant::now() + timeout;
+        let previous = unsafe { (*self.get_opaque()).deadline };
+        let earliest = previous.map_or(deadline, |previous| previous.min(deadline));
+        let rt = unsafe { qjs::JS_GetRuntime(self.as_ptr()) };
+        unsafe {
+            (*self.get_opaque()).deadline = Some(earliest);
+            update_interrupt_handler(rt);
+        }
+        let res = self.eval(source);
+        unsafe {
+            (*self.get_opaque()).deadline = previous;
+            update_interrupt_handler(rt);
+        }
+        match res {
+            Err(Error::Exception) if Instant::now() >= deadline => {
+                self.catch();
+                Err(Error::Timeout)
+            }
+            res => res,
+        }
+    }
+
     /// Evaluate a script in global context with top level await support.
     ///
     /// This function always returns a promise which resolves to the result of the evaluated
@@ -157,6 +221,35 @@ impl<'js> Ctx<'js> {
         )
     }
 
+    /// Evaluate the source of a module, top-level await included.
+    ///
+    /// Returns a promise which resolves to the namespace of the module when its evaluation
+    /// finished, after every top-level `await` completed. If the evaluation throws, or a
+    /// top-level `await` rejects, the promise rejects with the thrown value, which
+    /// [`Promise::finish`] returns as [`Error::Exception`]. With the `futures` feature the
+    /// promise can be awaited with [`Promise::into_future`].
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Object, Runtime};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let source = "export const value = await Promise.resolve(42);";
+    ///     let namespace: Object = ctx.eval_module("main", source).unwrap().finish().unwrap();
+    ///     assert_eq!(namespace.get::<_, i32>("value").unwrap(), 42);
+    /// });
+    /// ```
+    pub fn eval_module<N, S>(&self, name: N, source: S) -> Result<Promise<'js>>
+    where
+        N: Into<Vec<u8>>,
+        S: Into<Vec<u8>>,
+    {
+        let (module, promise) = Module::declare(self.clone(), name, source)?.eval()?;
+        // Rejections pass through `then` without a handler.
+        let namespace = Function::new(self.clone(), move || module.namespace())?;
+        promise.then()?.call((This(promise.clone()), namespace))
+    }
+
     /// Evaluate a script with the given options.
     pub fn eval_with_options<V: FromJs<'js>, S: Into<Vec<u8>>>(
         &self,
@@ -164,11 +257,171 @@ impl<'js> Ctx<'js> {
         options: EvalOptions,
     ) -> Result<V> {
         let file_name = cstr!("eval_script");
+        let flag = options.to_flag();
+        let eval = || -> Result<Value<'js>> {
+            let val = unsafe { self.eval_raw(source, file_name, flag)? };
+            Ok(unsafe { Value::from_js_value(self.clone(), val) })
+        };
+        let value = match options.origin {
+            Some(origin) => self.with_origin(&origin, eval),
+            None => eval(),
+        }?;
+        V::from_js(self, value)
+    }
 
-        V::from_js(self, unsafe {
-            let val = self.eval_raw(source, file_name, options.to_flag())?;
+    /// Evaluate a script as the body of a function with the given bindings as its parameters.
+    ///
+    /// Every binding is passed as an argument named after the binding, which makes the values
+    /// available to the script without setting them as globals. Since the script is the body of
+    /// a function its result must be returned with `return`. A script which closes the function
+    /// body to run code outside of it is rejected with a `SyntaxError`.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Object, Runtime, Value};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let user = Object::new(ctx.clone()).unwrap();
+    ///     user.set("age", 21).unwrap();
+    ///     let limit = Value::new_int(ctx.clone(), 18);
+    ///     let adult: bool = ctx
+    ///         .eval_with_bindings(
+    ///             "return user.age >= limit",
+    ///             [("user", user.into_value()), ("limit", limit)],
+    ///         )
+    ///         .unwrap();
+    ///     assert!(adult);
+    /// });
+    /// ```
+    pub fn eval_with_bindings<V, S, I, N, T>(&self, source: S, bindings: I) -> Result<V>
+    where
+        V: FromJs<'js>,
+        S: Into<Vec<u8>>,
+        I: IntoIterator<Item = (N, T)>,
+        N: AsRef<str>,
+        T: IntoJs<'js>,
+    {
+        let (names, values): (Vec<N>, Vec<T>) = bindings.into_iter().unzip();
+
+        // The script is wrapped on the first line to keep the line numbers of errors intact.
+        let mut wrapped = b"(function (".to_vec();
+        for (i, name) in names.iter().enumerate() {
+            let name = name.as_ref();
+            if !is_identifier(name) {
+                return Err(Exception::throw_type(
+                    self,
+                    &format!("invalid binding name `{name}`"),
+                ));
+            }
+            if i > 0 {
+                wrapped.extend_from_slice(b", ");
+            }
+            wrapped.extend_from_slice(name.as_bytes());
+        }
+        wrapped.extend_from_slice(b") { ");
+        wrapped.extend(source.into());
+        wrapped.extend_from_slice(b"\n})");
+
+        // QuickJS parses the wrapper as a whole, so a script like `}); f(); (function () {` would
+        // close the function early and run code at the top level. The Function constructor of
+        // QuickJS wraps its body the same way, so it can't be used to avoid this either.
+        if !self.is_single_function(&wrapped)? {
+            return Err(Exception::throw_syntax(
+                self,
+                "the script closes the function it is evaluated in",
+            ));
+        }
+
+        let file_name = cstr!("eval_script");
+        let function: Function = unsafe {
+            let val = self.eval_raw(wrapped, file_name, qjs::JS_EVAL_TYPE_GLOBAL as i32)?;
             Value::from_js_value(self.clone(), val)
-        })
+        }
+        .get()?;
+        let mut args = Args::new(self.clone(), values.len());
+        args.push_args(values)?;
+        function.call_arg(args)
+    }
+
+    /// Returns whether the script evaluates to a single function expression which spans the whole
+    /// script apart from the enclosing parentheses, like `(function () { ... })`.
+    ///
+    /// The script is evaluated in a scratch context of the same runtime, so code outside of the
+    /// function can't affect this context. A script with a syntax error is reported as a single
+    /// function, evaluating it again then returns the syntax error without running anything.
+    fn is_single_function(&self, script: &[u8]) -> Result<bool> {
+        let ctx = unsafe {
+            let rt = qjs::JS_GetRuntime(self.as_ptr());
+            let ctx = NonNull::new(qjs::JS_NewContextRaw(rt)).ok_or(Error::Allocation)?;
+            qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr());
+            qjs::JS_AddIntrinsicEval(ctx.as_ptr());
+            let scratch = Ctx::from_ptr(ctx.as_ptr());
+            // `Ctx::from_ptr` took its own reference.
+            qjs::JS_FreeContext(ctx.as_ptr());
+            scratch
+        };
+
+        // Taken before the script runs, which could replace it.
+        let to_string: Function = Function::prototype(ctx.clone()).get("toString")?;
+        let flag = qjs::JS_EVAL_TYPE_GLOBAL | qjs::JS_EVAL_FLAG_COMPILE_ONLY;
+        let function =
+            match unsafe { ctx.eval_raw(script.to_vec(), cstr!("eval_script"), flag as i32) } {
+                Ok(function) => function,
+                Err(Error::Exception) => {
+                    ctx.catch();
+                    return Ok(true);
+                }
+                Err(error) => return Err(error),
+            };
+        // Takes ownership of the function.
+        let value =
+            match unsafe { ctx.handle_exception(qjs::JS_EvalFunction(ctx.as_ptr(), function)) } {
+                Ok(value) => unsafe { Value::from_js_value(ctx.clone(), value) },
+                Err(Error::Exception) => {
+                    ctx.catch();
+                    return Ok(false);
+                }
+                Err(error) => return Err(error),
+            };
+        if !value.is_function() {
+            return Ok(false);
+        }
+        let source: std::string::String = to_string.call((This(value),))?;
+        Ok(source.as_bytes() == &script[1..script.len() - 1])
+    }
+
+    /// Evaluate a list of scripts in global context one after another, collecting the result of
+    /// every script instead of stopping at the first error.
+    ///
+    /// Every script is evaluated on its own with the given options, so an exception or syntax
+    /// error only fails that script, while the global declarations of earlier scripts stay
+    /// visible to later ones. A failing script doesn't roll back the side effects it had before
+    /// throwing.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Runtime, CaughtError};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let results = ctx.eval_all::<Option<i32>, _, _>(
+    ///         ["var a = 1", "a = b", "a + 1"],
+    ///         Default::default(),
+    ///     );
+    ///     assert_eq!(results[0].as_ref().ok(), Some(&None));
+    ///     assert!(matches!(results[1], Err(CaughtError::Exception(_))));
+    ///     assert_eq!(results[2].as_ref().ok(), Some(&Some(2)));
+    /// });
+    /// ```
+    pub fn eval_all<V, I, S>(&self, sources: I, options: EvalOptions) -> Vec<CaughtResult<'js, V>>
+    where
+        V: FromJs<'js>,
+        I: IntoIterator<Item = S>,
+        S: Into<Vec<u8>>,
+    {
+        sources
+            .into_iter()
+            .map(|source| self.eval_with_options(source, options.clone()).catch(self))
+            .collect()
     }
 
     /// Evaluate a script directly from a file.
@@ -196,6 +449,68 @@ impl<'js> Ctx<'js> {
         })
     }
 
+    /// Compile a script to bytecode without running it.
+    ///
+    /// The bytecode is evaluated with [`Ctx::eval_bytecode`], which skips parsing the source, so
+    /// scripts can be compiled ahead of time and shipped without their source. The bytecode
+    /// can only be read by the same version of QuickJS. Modules are compiled with
+    /// [`Module::declare`] and [`Module::write`] instead. The `embed_script!` macro compiles a
+    /// script at build time into a [`CompiledScript`](crate::context::CompiledScript).
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Runtime};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let bytecode = ctx.compile("const answer = 6 * 7; answer").unwrap();
+    ///     let res: i32 = unsafe { ctx.eval_bytecode(&bytecode) }.unwrap();
+    ///     assert_eq!(res, 42);
+    /// });
+    /// ```
+    pub fn compile<S: Into<Vec<u8>>>(&self, source: S) -> Result<Vec<u8>> {
+        let flag = EvalOptions::default().to_flag() | qjs::JS_EVAL_FLAG_COMPILE_ONLY as i32;
+        let function = unsafe {
+            let function = self.eval_raw(source, cstr!("eval_script"), flag)?;
+            Value::from_js_value(self.clone(), function)
+        };
+        let mut len = MaybeUninit::uninit();
+        let buf = unsafe {
+            qjs::JS_WriteObject(
+                self.as_ptr(),
+                len.as_mut_ptr(),
+                function.as_js_value(),
+                qjs::JS_WRITE_OBJ_BYTECODE as i32,
+            )
+        };
+        if buf.is_null() {
+            return Err(self.raise_exception());
+        }
+        let bytes = unsafe { slice::from_raw_parts(buf, len.assume_init() as _) }.to_vec();
+        unsafe { qjs::js_free(self.as_ptr(), buf as _) };
+        Ok(bytes)
+    }
+
+    /// Evaluate a script compiled to bytecode with [`Ctx::compile`].
+    ///
+    /// # Safety
+    /// QuickJS doesn't validate bytecode, the bytes must have been written by [`Ctx::compile`]
+    /// with the same version of QuickJS.
+    pub unsafe fn eval_bytecode<V: FromJs<'js>>(&self, bytes: &[u8]) -> Result<V> {
+        if (*self.get_opaque()).termination_requested() {
+            return Err(Error::Terminated);
+        }
+        let function = qjs::JS_ReadObject(
+            self.as_ptr(),
+            bytes.as_ptr(),
+            bytes.len() as _,
+            qjs::JS_READ_OBJ_BYTECODE as i32,
+        );
+        let function = self.handle_exception(function)?;
+        // Takes ownership of the function.
+        let value = self.handle_exception(qjs::JS_EvalFunction(self.as_ptr(), function))?;
+        V::from_js(self, Value::from_js_value(self.clone(), value))
+    }
+
     /// Returns the global object of this context.
     pub fn globals(&self) -> Object<'js> {
         unsafe {
@@ -335,6 +650,27 @@ impl<'js> Ctx<'js> {
         )
     }
 
+    /// Stringify a JavaScript value into its JSON representation, writing it to `writer` while
+    /// it is produced.
+    ///
+    /// The result is the same as the one of [`Ctx::json_stringify`], but arrays and objects are
+    /// walked in Rust and only the JSON of primitive values and keys is created by QuickJS, so
+    /// the JSON text of a large value is never built in the JavaScript heap. The output is
+    /// buffered, `writer` doesn't need to be.
+    ///
+    /// Returns `false` without writing anything if the value has no JSON representation, like
+    /// `undefined`. Cyclic values fail with [`Error::Cycle`] after the output up to the cycle
+    /// was written, and nesting is limited like conversions, see
+    /// [`Runtime::set_max_conversion_depth`](crate::Runtime::set_max_conversion_depth).
+    pub fn json_stringify_to<W, V>(&self, writer: W, value: V) -> Result<bool>
+    where
+        W: io::Write,
+        V: IntoJs<'js>,
+    {
+        let value = value.into_js(self)?;
+        JsonWriter::new(self.clone(), writer)?.write(value)
+    }
+
     // Inner non-generic version of json stringify>
     fn json_stringify_inner(
         &self,
@@ -380,12 +716,222 @@ impl<'js> Ctx<'js> {
     /// Returns wether a job was actually executed.
     /// If this function returned false, no job was pending.
     pub fn execute_pending_job(&self) -> bool {
+        if unsafe { (*self.get_opaque()).termination_requested() } {
+            return false;
+        }
         let mut ptr = MaybeUninit::<*mut qjs::JSContext>::uninit();
         let rt = unsafe { qjs::JS_GetRuntime(self.ctx.as_ptr()) };
         let res = unsafe { qjs::JS_ExecutePendingJob(rt, ptr.as_mut_ptr()) };
         res != 0
     }
 
+    /// Blocks the current thread until the future is complete.
+    ///
+    /// Pending quickjs jobs are executed while the future is waiting, so the future can await
+    /// promises. The thread is only parked while something else, for example another thread,
+    /// holds the waker of the future. Promises awaited by the future are checked again after
+    /// jobs were executed and don't hold the waker.
+    ///
+    /// Returns [`Error::WouldBlock`] instead of deadlocking when the future can't make any more
+    /// progress, i.e. when it is pending, the job queue is empty and nothing could wake it. As the
+    /// spawned futures of an async runtime are only driven by the executor which is blocked by this
+    /// call, this function always returns [`Error::WouldBlock`] in an async runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> Result<F::Output> {
+        #[cfg(feature = "futures")]
+        let blocking = unsafe {
+            let opaque = &mut *self.get_opaque();
+            if opaque.spawner.is_some() {
+                return Err(Error::WouldBlock);
+            }
+            mem::replace(&mut opaque.blocking, true)
+        };
+        let res = self.block_on_inner(future);
+        #[cfg(feature = "futures")]
+        unsafe {
+            (*self.get_opaque()).blocking = blocking;
+        }
+        res
+    }
+
+    fn block_on_inner<F: Future>(&self, mut future: F) -> Result<F::Output> {
+        // Safety: the future is shadowed and never moved again
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+        let waker = Arc::new(ThreadWaker {
+            thread: thread::current(),
+            woken: AtomicBool::new(false),
+        });
+        let task_waker = Waker::from(waker.clone());
+        let mut cx = TaskContext::from_waker(&task_waker);
+        // One reference is held by this function and one by the waker of the task context.
+        let owned = Arc::strong_count(&waker);
+        loop {
+            waker.woken.store(false, Ordering::SeqCst);
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return Ok(output);
+            }
+
+            if self.execute_pending_job() {
+                while self.execute_pending_job() {}
+                continue;
+            }
+
+            if waker.woken.load(Ordering::SeqCst) {
+                continue;
+            }
+            if Arc::strong_count(&waker) <= owned {
+                // A waker which was dropped after waking the future must be seen as woken.
+                fence(Ordering::Acquire);
+                if waker.woken.load(Ordering::SeqCst) {
+                    continue;
+                }
+                return Err(Error::WouldBlock);
+            }
+            thread::park();
+        }
+    }
+
+    /// Returns an approximation of the amount of stack in bytes currently used by the runtime.
+    ///
+    /// Can be compared with the max stack size set with
+    /// [`Runtime::set_max_stack_size`](crate::Runtime::set_max_stack_size) to find how close
+    /// a call chain is to overflowing the stack.
+    pub fn stack_usage(&self) -> usize {
+        let stack_top = unsafe { (*self.get_opaque()).stack_top };
+        stack_top.saturating_sub(stack_pointer())
+    }
+
+    /// Returns whether conversions to Rust maps should skip prototype keys.
+    pub(crate) fn hardened_conversions(&self) -> bool {
+        unsafe { (*self.get_opaque()).hardened_conversions }
+    }
+
+    /// Set a limit on the memory attributed to this context, `None` removes the limit.
+    ///
+    /// Contexts of one runtime share its memory, so one context can use up the memory limit of
+    /// the runtime and make every other context fail. With a limit per context, an allocation
+    /// which would exceed it fails and the operation returns [`Error::ContextMemoryExceeded`]
+    /// instead, leaving the memory of the runtime for the other contexts.
+    ///
+    /// The accounting is approximate. Memory allocated while the context is entered with
+    /// [`Context::with`], [`AsyncContext::with`](crate::AsyncContext::with) or an `async_with`
+    /// is attributed to it, and memory freed while it is entered is subtracted from it, even if
+    /// another context allocated it. Jobs and futures run by the runtime outside of those are
+    /// not attributed to any context.
+    ///
+    /// Only has an effect for runtimes created with a custom allocator, like
+    /// [`Runtime::new_with_alloc`](crate::Runtime::new_with_alloc) or with the `rust-alloc`
+    /// feature.
+    #[cfg(feature = "allocator")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "allocator")))]
+    pub fn set_memory_limit(&self, limit: Option<usize>) {
+        unsafe {
+            if let Some(accounting) = (*self.get_opaque()).memory_accounting.as_mut() {
+                let memory = accounting.context_mut(self.ctx);
+                memory.limit = limit;
+                memory.exceeded = false;
+            }
+        }
+    }
+
+    /// Returns the approximate amount of memory in bytes attributed to this context, see
+    /// [`Ctx::set_memory_limit`].
+    ///
+    /// Returns `None` if the runtime doesn't use a custom allocator.
+    #[cfg(feature = "allocator")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "allocator")))]
+    pub fn memory_used(&self) -> Option<usize> {
+        unsafe {
+            let accounting = (*self.get_opaque()).memory_accounting.as_ref()?;
+            Some(accounting.context(self.ctx).map_or(0, |x| x.used))
+        }
+    }
+
+    /// Returns the limit of this context if an allocation failed because of it since the last
+    /// call, see [`Ctx::set_memory_limit`].
+    #[cfg(feature = "allocator")]
+    pub(crate) fn take_memory_exceeded(&self) -> Option<usize> {
+        unsafe {
+            let accounting = (*self.get_opaque()).memory_accounting.as_mut()?;
+            let memory = accounting.context_mut(self.ctx);
+            if !mem::take(&mut memory.exceeded) {
+                return None;
+            }
+            memory.limit
+        }
+    }
+
+    /// Returns whether an allocation of the runtime failed since the last call.
+    #[cfg(feature = "allocator")]
+    pub(crate) fn take_out_of_memory(&self) -> bool {
+        match unsafe { (*self.get_opaque()).memory_accounting.as_mut() } {
+            Some(accounting) => mem::take(&mut accounting.out_of_memory),
+            None => false,
+        }
+    }
+
+    /// Run `f` with code attributed to the given origin label, like a tenant id or plugin name.
+    ///
+    /// While `f` runs, errors thrown from JavaScript get a non-enumerable `origin` property,
+    /// see [`Exception::origin`], the handler set with
+    /// [`Runtime::set_interrupt_handler_with_origin`](crate::Runtime::set_interrupt_handler_with_origin)
+    /// receives the label and samples of the runtime sampler record it. Modules loaded and
+    /// functions called within `f` are attributed to the label as well. Jobs executed later,
+    /// outside of `f`, are attributed to the origin active when they run, if any.
+    ///
+    /// The label applies to the whole runtime, calls can be nested and the previous label is
+    /// restored when `f` returns.
+    pub fn with_origin<R, F: FnOnce() -> R>(&self, origin: &str, f: F) -> R {
+        struct Restore<'a, 'js> {
+            ctx: &'a Ctx<'js>,
+            prev: Option<Arc<str>>,
+        }
+
+        impl Drop for Restore<'_, '_> {
+            fn drop(&mut self) {
+                unsafe { (*self.ctx.get_opaque()).origin = self.prev.take() };
+            }
+        }
+
+        let prev = unsafe { mem::replace(&mut (*self.get_opaque()).origin, Some(origin.into())) };
+        let _restore = Restore { ctx: self, prev };
+        f()
+    }
+
+    /// Returns the origin label set with [`Ctx::with_origin`], if any.
+    pub fn origin(&self) -> Option<std::string::String> {
+        unsafe { (*self.get_opaque()).origin.as_deref().map(Into::into) }
+    }
+
+    /// Returns the kept source of a script or module by its file name.
+    ///
+    /// Returns `None` if source retention is disabled, see
+    /// [`Runtime::set_source_retention`](crate::Runtime::set_source_retention).
+    pub fn source(&self, name: &str) -> Option<Arc<SourceFile>> {
+        unsafe { (*self.get_opaque()).sources.as_ref()?.get(name).cloned() }
+    }
+
+    /// Set the import map used by the [`ImportMap`] resolver for this context.
+    ///
+    /// The map replaces the one of the resolver for every module loaded in this context, setting
+    /// `None` uses the map of the resolver again.
+    #[cfg(feature = "loader")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+    pub fn set_import_map(&self, import_map: Option<ImportMap>) {
+        let import_maps = unsafe { &mut (*self.get_opaque()).import_maps };
+        let key = self.ctx.as_ptr() as usize;
+        match import_map {
+            Some(import_map) => import_maps.insert(key, Arc::new(import_map)),
+            None => import_maps.remove(&key),
+        };
+    }
+
+    /// Returns the import map set for this context, if any.
+    #[cfg(feature = "loader")]
+    pub(crate) fn import_map(&self) -> Option<Arc<ImportMap>> {
+        let import_maps = unsafe { &(*self.get_opaque()).import_maps };
+        import_maps.get(&(self.ctx.as_ptr() as usize)).cloned()
+    }
+
     pub(crate) unsafe fn get_opaque(&self) -> *mut Opaque<'js> {
         let rt = qjs::JS_GetRuntime(self.ctx.as_ptr());
         qjs::JS_GetRuntimeOpaque(rt).cast::<Opaque>()
@@ -398,7 +944,24 @@ impl<'js> Ctx<'js> {
     where
         F: Future<Output = ()> + 'js,
     {
-        unsafe { (*self.get_opaque()).spawner().push(future) }
+        let opaque = unsafe { &mut *self.get_opaque() };
+        // A shut down or terminated runtime drops futures instead of running them.
+        if !opaque.is_terminated() {
+            unsafe { opaque.spawner().push(future) }
+        }
+    }
+
+    /// Queue a closure to run as a microtask, like `queueMicrotask` in JavaScript.
+    ///
+    /// The closure runs on the job queue of the runtime once the current JavaScript stack has
+    /// unwound and after the jobs queued before it, such as promise reactions. It never runs
+    /// during this call, even when called from within another job. An error returned by the
+    /// closure is thrown from the job like an exception thrown by a JavaScript microtask.
+    pub fn queue_microtask<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(Ctx<'js>) -> Result<()> + 'js,
+    {
+        Function::new(self.clone(), OnceFn::new(f))?.defer(())
     }
 
     /// Create a new `Ctx` from a pointer to the context and a invariant lifetime.
@@ -446,9 +1009,61 @@ impl<'js> Ctx<'js> {
     }
 }
 
+struct ThreadWaker {
+    thread: Thread,
+    /// Set when the future was woken since it was last polled.
+    woken: AtomicBool,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+/// Returns whether the name is a valid JavaScript identifier, only ASCII identifiers are
+/// accepted.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
 #[cfg(test)]
 mod test {
 
+    #[test]
+    fn queue_microtask() {
+        use crate::Array;
+
+        crate::test_with(|ctx| {
+            ctx.eval::<(), _>("globalThis.log = []; queueMicrotask(() => log.push('js'));")
+                .unwrap();
+            ctx.queue_microtask(|ctx| {
+                let log: Array = ctx.globals().get("log")?;
+                log.set(log.len(), "rust")
+            })
+            .unwrap();
+            ctx.eval::<(), _>(
+                "Promise.resolve().then(() => log.push('promise')); log.push('sync')",
+            )
+            .unwrap();
+            while ctx.execute_pending_job() {}
+            let log: Vec<std::string::String> = ctx.globals().get("log").unwrap();
+            assert_eq!(log, ["sync", "js", "rust", "promise"]);
+
+            let name: std::string::String = ctx
+                .eval("try { queueMicrotask(1) } catch (e) { e.name }")
+                .unwrap();
+            assert_eq!(name, "TypeError");
+        });
+    }
+
     #[test]
     fn exports() {
         use crate::{context::intrinsic, Context, Function, Module, Promise, Runtime};
@@ -582,6 +1197,153 @@ mod test {
         })
     }
 
+    #[test]
+    fn eval_with_origin() {
+        use crate::{context::EvalOptions, Context, Error, Exception, Runtime};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let options = EvalOptions {
+                origin: Some("tenant-a".into()),
+                ..Default::default()
+            };
+            let Err(Error::Exception) =
+                ctx.eval_with_options::<(), _>("throw new Error('x')", options)
+            else {
+                panic!("expected an exception");
+            };
+            let exception = Exception::from_value(ctx.catch()).unwrap();
+            assert_eq!(exception.origin().as_deref(), Some("tenant-a"));
+            assert_eq!(ctx.origin(), None);
+
+            ctx.with_origin("outer", || {
+                ctx.with_origin("inner", || {
+                    assert_eq!(ctx.origin().as_deref(), Some("inner"))
+                });
+                assert_eq!(ctx.origin().as_deref(), Some("outer"));
+                let _ = ctx.eval::<(), _>("throw new Error('y')");
+                let exception = Exception::from_value(ctx.catch()).unwrap();
+                assert_eq!(exception.origin().as_deref(), Some("outer"));
+            });
+
+            let _ = ctx.eval::<(), _>("throw new Error('z')");
+            let exception = Exception::from_value(ctx.catch()).unwrap();
+            assert_eq!(exception.origin(), None);
+        })
+    }
+
+    #[test]
+    fn bytecode() {
+        use crate::{Context, Runtime};
+
+        let bytecode = {
+            let runtime = Runtime::new().unwrap();
+            let ctx = Context::full(&runtime).unwrap();
+            ctx.with(|ctx| {
+                assert!(ctx.compile("syntax error(").is_err());
+                ctx.catch();
+                ctx.compile("var counter = (globalThis.counter || 0) + 1; counter")
+                    .unwrap()
+            })
+        };
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            assert_eq!(unsafe { ctx.eval_bytecode::<i32>(&bytecode) }.unwrap(), 1);
+            assert_eq!(unsafe { ctx.eval_bytecode::<i32>(&bytecode) }.unwrap(), 2);
+        })
+    }
+
+    #[test]
+    fn eval_with_timeout() {
+        use crate::{Context, Error, Runtime};
+        use std::time::Duration;
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let timeout = Duration::from_millis(10);
+            let res = ctx.eval_with_timeout::<(), _>("try { for(;;) {} } catch(e) {}", timeout);
+            assert!(matches!(res, Err(Error::Timeout)));
+            let res: i32 = ctx.eval_with_timeout("1 + 1", timeout).unwrap();
+            assert_eq!(res, 2);
+            let res = ctx.eval_with_timeout::<(), _>("throw new Error('x')", timeout);
+            assert!(matches!(res, Err(Error::Exception)));
+            ctx.catch();
+            // The deadline is cleared after the evaluation.
+            let res: i32 = ctx.eval("let i = 0; for(; i < 1000000; i++) {} i").unwrap();
+            assert_eq!(res, 1000000);
+        })
+    }
+
+    #[test]
+    fn eval_all() {
+        use crate::{context::EvalOptions, CaughtError, Context, Runtime};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let results = ctx.eval_all::<i32, _, _>(
+                [
+                    "var a = 1; a",
+                    "throw new Error('b')",
+                    "a += 1; syntax error(",
+                    "function c() { return a + 1 }; c()",
+                    "'d'",
+                ],
+                EvalOptions::default(),
+            );
+            assert_eq!(results.len(), 5);
+            assert_eq!(*results[0].as_ref().unwrap(), 1);
+            let Err(CaughtError::Exception(error)) = &results[1] else {
+                panic!("expected an exception");
+            };
+            assert_eq!(error.message().as_deref(), Some("b"));
+            assert!(matches!(results[2], Err(CaughtError::Exception(_))));
+            // The syntax error prevented the assignment.
+            assert_eq!(*results[3].as_ref().unwrap(), 2);
+            assert!(matches!(results[4], Err(CaughtError::Error(_))));
+            assert!(ctx.catch().is_null());
+        })
+    }
+
+    #[test]
+    fn eval_with_bindings() {
+        use crate::{Context, Error, Runtime};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let res: i32 = ctx
+                .eval_with_bindings("var c = a + b; return c", [("a", 1), ("b", 2)])
+                .unwrap();
+            assert_eq!(res, 3);
+            let leaked: bool = ctx
+                .eval("typeof a !== 'undefined' || typeof c !== 'undefined'")
+                .unwrap();
+            assert!(!leaked);
+
+            let res = ctx.eval_with_bindings::<(), _, _, _, _>("", [("a, b", 1)]);
+            assert!(matches!(res, Err(Error::Exception)));
+            ctx.catch();
+
+            let res = ctx.eval_with_bindings::<(), _, _, _, _>(
+                "}); globalThis.leak = 1; (function(){ return (0",
+                [("a", 1)],
+            );
+            assert!(matches!(res, Err(Error::Exception)));
+            ctx.catch();
+            let leaked: bool = ctx.eval("typeof leak !== 'undefined'").unwrap();
+            assert!(!leaked);
+
+            let res = ctx.eval_with_bindings::<(), _, _, _, _>("return (", [("a", 1)]);
+            assert!(matches!(res, Err(Error::Exception)));
+            assert!(ctx.catch().as_exception().is_some());
+        })
+    }
+
     #[test]
     fn json_parse() {
         use crate::{Array, Context, Object, Runtime};
@@ -654,4 +1416,152 @@ mod test {
             assert_eq!(str, r#"{"a":{"b":1,"c":true},"d":[0,"foo"]}"#);
         })
     }
+
+    #[test]
+    fn json_stringify_to() {
+        use crate::{Context, Error, Runtime, Value};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let value: Value = ctx
+                .eval(
+                    r#"({
+                        a: [1, -0, 1.5, NaN, "\"\u2028\ud800", undefined, () => 1, Symbol()],
+                        b: { toJSON(key) { return key + "!"; } },
+                        c: undefined,
+                        "\n": new Date(0),
+                        d: [new Number(1), new String("s"), Object(Symbol())],
+                        e: { f: null, g: true },
+                    })"#,
+                )
+                .unwrap();
+            let mut out = Vec::new();
+            assert!(ctx.json_stringify_to(&mut out, value.clone()).unwrap());
+            let expected = ctx
+                .json_stringify(value)
+                .unwrap()
+                .unwrap()
+                .to_string()
+                .unwrap();
+            assert_eq!(std::str::from_utf8(&out).unwrap(), expected);
+
+            let mut out = Vec::new();
+            assert!(!ctx.json_stringify_to(&mut out, ()).unwrap());
+            assert!(out.is_empty());
+
+            let value: Value = ctx.eval("const c = [1]; c.push(c); c").unwrap();
+            let res = ctx.json_stringify_to(Vec::new(), value);
+            assert!(matches!(res, Err(Error::Cycle { .. })));
+        })
+    }
+
+    #[test]
+    fn block_on() {
+        use crate::{Context, Error, Runtime};
+        use std::{sync::mpsc, task::Poll, thread, time::Duration};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            // Woken from another thread.
+            let (send, recv) = mpsc::channel();
+            let mut started = false;
+            let res = ctx
+                .block_on(std::future::poll_fn(|cx| {
+                    if let Ok(value) = recv.try_recv() {
+                        return Poll::Ready(value);
+                    }
+                    if !started {
+                        started = true;
+                        let (send, waker) = (send.clone(), cx.waker().clone());
+                        thread::spawn(move || {
+                            thread::sleep(Duration::from_millis(10));
+                            send.send(3).unwrap();
+                            waker.wake();
+                        });
+                    }
+                    Poll::Pending
+                }))
+                .unwrap();
+            assert_eq!(res, 3);
+
+            // Nothing can wake the future.
+            let res = ctx.block_on(std::future::pending::<()>());
+            assert!(matches!(res, Err(Error::WouldBlock)));
+        })
+    }
+
+    #[test]
+    fn eval_module() {
+        use crate::{Context, Error, Function, Object, Runtime};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let promise = ctx
+                .eval_module(
+                    "tla",
+                    r#"
+                    export const a = await Promise.resolve(1);
+                    export let b = 0;
+                    await new Promise((resolve) => { globalThis.resume = resolve; });
+                    b = 2;
+                    "#,
+                )
+                .unwrap();
+            assert!(matches!(promise.finish::<Object>(), Err(Error::WouldBlock)));
+            let resume: Function = ctx.globals().get("resume").unwrap();
+            resume.call::<_, ()>(()).unwrap();
+            let namespace: Object = promise.finish().unwrap();
+            assert_eq!(namespace.get::<_, i32>("a").unwrap(), 1);
+            assert_eq!(namespace.get::<_, i32>("b").unwrap(), 2);
+
+            let res = ctx
+                .eval_module("rejected", "await Promise.reject(new Error('boom'));")
+                .and_then(|promise| promise.finish::<Object>());
+            assert!(matches!(res, Err(Error::Exception)));
+            let error: Object = ctx.catch().into_object().unwrap();
+            assert_eq!(error.get::<_, String>("message").unwrap(), "boom");
+
+            let res = ctx
+                .eval_module("thrown", "throw new Error('sync');")
+                .and_then(|promise| promise.finish::<Object>());
+            assert!(matches!(res, Err(Error::Exception)));
+            let error: Object = ctx.catch().into_object().unwrap();
+            assert_eq!(error.get::<_, String>("message").unwrap(), "sync");
+        })
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn block_on_promise() {
+        use crate::{Context, Error, Promise, Runtime};
+
+        let runtime = Runtime::new().unwrap();
+        let ctx = Context::full(&runtime).unwrap();
+        ctx.with(|ctx| {
+            let promise: Promise = ctx
+                .eval("(async () => { await null; return 2 })()")
+                .unwrap();
+            let res = ctx.block_on(promise.into_future::<i32>()).unwrap();
+            assert_eq!(res.unwrap(), 2);
+
+            let promise: Promise = ctx.eval("new Promise(() => {})").unwrap();
+            let res = ctx.block_on(promise.into_future::<()>());
+            assert!(matches!(res, Err(Error::WouldBlock)));
+
+            // A future waking itself is polled again.
+            let mut yielded = false;
+            let res = ctx.block_on(std::future::poll_fn(|cx| {
+                if yielded {
+                    return std::task::Poll::Ready(3);
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }));
+            assert_eq!(res.unwrap(), 3);
+        })
+    }
 }
@@ -0,0 +1,216 @@
+use std::mem::{self, MaybeUninit};
+
+use crate::{qjs, Atom, Ctx, IntoAtom, IntoJs, Object, Result, Value};
+
+/// The own property of the global object a key had before it was first injected.
+struct Saved<'js> {
+    key: Atom<'js>,
+    /// The value, getter, setter and flags of the property, `None` if it didn't exist.
+    prop: Option<(Value<'js>, Value<'js>, Value<'js>, qjs::c_int)>,
+}
+
+/// A guard which sets globals of a context and restores them when it is dropped
+///
+/// Every global set through the guard is removed again if it didn't exist before, or restored
+/// to its original property, including its attributes, if it was replaced. This makes exposing
+/// an API to a script for a limited time, or isolating the globals of tests which share a
+/// context, reliable even when the scope is left early.
+///
+/// Only globals set through the guard are restored, globals defined by scripts are left alone.
+///
+/// ```
+/// # use rquickjs::{context::InjectedGlobals, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     {
+///         let mut injected = InjectedGlobals::new(&ctx);
+///         injected.set("secret", 42).unwrap();
+///         injected.set("Math", "shadowed").unwrap();
+///         assert_eq!(ctx.eval::<i32, _>("secret").unwrap(), 42);
+///     }
+///     let res: bool = ctx
+///         .eval("typeof secret === 'undefined' && typeof Math === 'object'")
+///         .unwrap();
+///     assert!(res);
+/// });
+/// ```
+#[must_use = "the globals are restored when the guard is dropped"]
+pub struct InjectedGlobals<'js> {
+    globals: Object<'js>,
+    saved: Vec<Saved<'js>>,
+}
+
+impl<'js> InjectedGlobals<'js> {
+    /// Create a guard for the globals of the context.
+    pub fn new(ctx: &Ctx<'js>) -> Self {
+        InjectedGlobals {
+            globals: ctx.globals(),
+            saved: Vec::new(),
+        }
+    }
+
+    /// Set a global, recording the property it replaces the first time a key is set.
+    pub fn set<K: IntoAtom<'js>, V: IntoJs<'js>>(&mut self, key: K, value: V) -> Result<()> {
+        let ctx = self.globals.ctx();
+        let key = key.into_atom(ctx)?;
+        if !self.saved.iter().any(|x| x.key == key) {
+            let prop = self.own_property(&key)?;
+            self.saved.push(Saved {
+                key: key.clone(),
+                prop,
+            });
+        }
+        self.globals.set(key, value)
+    }
+
+    /// Restore the globals, returning the first error which occurred while restoring them.
+    pub fn restore(mut self) -> Result<()> {
+        self.restore_all()
+    }
+
+    fn own_property(
+        &self,
+        key: &Atom<'js>,
+    ) -> Result<Option<(Value<'js>, Value<'js>, Value<'js>, qjs::c_int)>> {
+        let ctx = self.globals.ctx();
+        let mut desc = MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = unsafe {
+            qjs::JS_GetOwnProperty(
+                ctx.as_ptr(),
+                desc.as_mut_ptr(),
+                self.globals.as_js_value(),
+                key.atom,
+            )
+        };
+        if res < 0 {
+            return Err(ctx.raise_exception());
+        }
+        if res == 0 {
+            return Ok(None);
+        }
+        let desc = unsafe { desc.assume_init() };
+        Ok(Some(unsafe {
+            (
+                Value::from_js_value(ctx.clone(), desc.value),
+                Value::from_js_value(ctx.clone(), desc.getter),
+                Value::from_js_value(ctx.clone(), desc.setter),
+                desc.flags,
+            )
+        }))
+    }
+
+    fn restore_all(&mut self) -> Result<()> {
+        let ctx = self.globals.ctx().clone();
+        let mut res = Ok(());
+        for saved in mem::take(&mut self.saved) {
+            let ok = match saved.prop {
+                None => unsafe {
+                    qjs::JS_DeleteProperty(
+                        ctx.as_ptr(),
+                        self.globals.as_js_value(),
+                        saved.key.atom,
+                        qjs::JS_PROP_THROW as _,
+                    )
+                },
+                Some((value, getter, setter, flags)) => {
+                    let mut flags = flags
+                        | (qjs::JS_PROP_HAS_CONFIGURABLE
+                            | qjs::JS_PROP_HAS_ENUMERABLE
+                            | qjs::JS_PROP_THROW) as qjs::c_int;
+                    if flags & qjs::JS_PROP_GETSET as qjs::c_int != 0 {
+                        flags |= (qjs::JS_PROP_HAS_GET | qjs::JS_PROP_HAS_SET) as qjs::c_int;
+                    } else {
+                        flags |= (qjs::JS_PROP_HAS_VALUE | qjs::JS_PROP_HAS_WRITABLE) as qjs::c_int;
+                    }
+                    unsafe {
+                        qjs::JS_DefineProperty(
+                            ctx.as_ptr(),
+                            self.globals.as_js_value(),
+                            saved.key.atom,
+                            value.as_js_value(),
+                            getter.as_js_value(),
+                            setter.as_js_value(),
+                            flags,
+                        )
+                    }
+                }
+            };
+            if ok < 0 {
+                let error = ctx.raise_exception();
+                if res.is_ok() {
+                    res = Err(error);
+                } else {
+                    ctx.catch();
+                }
+            }
+        }
+        res
+    }
+}
+
+impl Drop for InjectedGlobals<'_> {
+    fn drop(&mut self) {
+        if self.restore_all().is_err() {
+            // There is no way to report the error, so don't leave the exception pending.
+            self.globals.ctx().catch();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_with, Error};
+
+    #[test]
+    fn restore() {
+        test_with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                globalThis.existing = 1;
+                Object.defineProperty(globalThis, "hidden", {
+                    value: "hidden",
+                    writable: true,
+                    configurable: true,
+                    enumerable: false,
+                });
+            "#,
+            )
+            .unwrap();
+
+            let mut injected = InjectedGlobals::new(&ctx);
+            injected.set("existing", 2).unwrap();
+            injected.set("existing", 3).unwrap();
+            injected.set("hidden", "shown").unwrap();
+            injected.set("added", true).unwrap();
+            assert_eq!(ctx.eval::<i32, _>("existing").unwrap(), 3);
+            injected.restore().unwrap();
+
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    existing === 1 &&
+                    hidden === "hidden" &&
+                    !Object.getOwnPropertyDescriptor(globalThis, "hidden").enumerable &&
+                    !("added" in globalThis)
+                "#,
+                )
+                .unwrap();
+            assert!(res);
+        })
+    }
+
+    #[test]
+    fn restore_on_drop() {
+        test_with(|ctx| {
+            let res: Result<()> = (|| {
+                let mut injected = InjectedGlobals::new(&ctx);
+                injected.set("temporary", 1)?;
+                Err(Error::Unknown)
+            })();
+            assert!(res.is_err());
+            assert!(!ctx.globals().contains_own_key("temporary").unwrap());
+        })
+    }
+}
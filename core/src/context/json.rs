@@ -0,0 +1,121 @@
+use std::io::{BufWriter, Write};
+
+use crate::{
+    convert::ConversionGuard, function::This, Array, Atom, Ctx, IntoJs, Object, Result, Value,
+};
+
+/// Writes the JSON representation of values piece by piece, used by
+/// [`Ctx::json_stringify_to`].
+///
+/// Arrays and objects are walked in Rust, only primitive values and keys are stringified by
+/// QuickJS, so the JSON text of a value never exists as a whole in the JavaScript heap.
+pub(crate) struct JsonWriter<'js, W: Write> {
+    ctx: Ctx<'js>,
+    out: BufWriter<W>,
+    /// The constructors of the boxed primitives, which are stringified like their value.
+    boxed: Vec<Value<'js>>,
+}
+
+impl<'js, W: Write> JsonWriter<'js, W> {
+    pub fn new(ctx: Ctx<'js>, writer: W) -> Result<Self> {
+        let globals = ctx.globals();
+        let boxed = ["Number", "String", "Boolean", "BigInt"]
+            .iter()
+            .map(|name| globals.get(*name))
+            .collect::<Result<_>>()?;
+        Ok(JsonWriter {
+            ctx,
+            out: BufWriter::new(writer),
+            boxed,
+        })
+    }
+
+    /// Write a value, returns `false` if the value has no JSON representation.
+    pub fn write(mut self, value: Value<'js>) -> Result<bool> {
+        let key = "".into_js(&self.ctx)?;
+        let written = match self.prepare(key, value)? {
+            Some(value) => {
+                self.write_value(value)?;
+                true
+            }
+            None => false,
+        };
+        self.out.flush()?;
+        Ok(written)
+    }
+
+    /// Call the `toJSON` method of a value, returns `None` if the result isn't written.
+    fn prepare(&self, key: Value<'js>, value: Value<'js>) -> Result<Option<Value<'js>>> {
+        let value = match value.as_object() {
+            Some(object) => match object.get::<_, Value>("toJSON")?.into_function() {
+                Some(to_json) => to_json.call((This(value), key))?,
+                None => value,
+            },
+            None => value,
+        };
+        if value.is_undefined() || value.is_function() || value.is_symbol() {
+            return Ok(None);
+        }
+        Ok(Some(value))
+    }
+
+    fn write_value(&mut self, value: Value<'js>) -> Result<()> {
+        if let Some(array) = value.as_array() {
+            return self.write_array(array);
+        }
+        if let Some(object) = value.as_object() {
+            if !self.boxed.iter().any(|ctor| object.is_instance_of(ctor)) {
+                return self.write_object(object);
+            }
+        }
+        self.write_primitive(value)
+    }
+
+    fn write_array(&mut self, array: &Array<'js>) -> Result<()> {
+        let _guard = ConversionGuard::enter(&self.ctx, array.as_value(), "JSON")?;
+        self.out.write_all(b"[")?;
+        for idx in 0..array.len() {
+            if idx > 0 {
+                self.out.write_all(b",")?;
+            }
+            let key = idx.to_string().into_js(&self.ctx)?;
+            match self.prepare(key, array.get::<Value>(idx)?)? {
+                Some(item) => self.write_value(item)?,
+                None => self.out.write_all(b"null")?,
+            }
+        }
+        self.out.write_all(b"]")?;
+        Ok(())
+    }
+
+    fn write_object(&mut self, object: &Object<'js>) -> Result<()> {
+        let _guard = ConversionGuard::enter(&self.ctx, object.as_value(), "JSON")?;
+        self.out.write_all(b"{")?;
+        let mut first = true;
+        for key in object.keys::<Atom>() {
+            let key = key?;
+            let value = object.get(key.clone())?;
+            let key = key.to_js_string()?.into_value();
+            let Some(value) = self.prepare(key.clone(), value)? else {
+                continue;
+            };
+            if !first {
+                self.out.write_all(b",")?;
+            }
+            first = false;
+            self.write_primitive(key)?;
+            self.out.write_all(b":")?;
+            self.write_value(value)?;
+        }
+        self.out.write_all(b"}")?;
+        Ok(())
+    }
+
+    fn write_primitive(&mut self, value: Value<'js>) -> Result<()> {
+        // Only values without a JSON representation stringify to undefined, which were skipped.
+        if let Some(json) = self.ctx.json_stringify(value)? {
+            self.out.write_all(json.to_string()?.as_bytes())?;
+        }
+        Ok(())
+    }
+}
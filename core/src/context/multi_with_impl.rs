@@ -23,6 +23,7 @@ macro_rules! impl_multi_with{
                           "Tried to use contexts of different runtimes with each other");)*
                 let guard = self.0.0.rt.inner.lock();
                 guard.update_stack_top();
+                guard.free_deferred();
                 let res = f(($(unsafe{ Ctx::new($t) },)*));
                 mem::drop(guard);
                 res
@@ -0,0 +1,40 @@
+use crate::{Ctx, FromJs, Result};
+
+/// A script compiled to bytecode
+///
+/// Usually created at build time with the `embed_script!` macro, which compiles the script with
+/// [`Ctx::compile`] so the source doesn't have to be shipped with the binary.
+///
+/// ```
+/// # use rquickjs::{context::CompiledScript, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let bytecode = ctx.compile("6 * 7").unwrap();
+///     let script = unsafe { CompiledScript::from_bytecode(bytecode.leak()) };
+///     assert_eq!(script.eval::<i32>(&ctx).unwrap(), 42);
+/// });
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CompiledScript(&'static [u8]);
+
+impl CompiledScript {
+    /// Create a script from bytecode
+    ///
+    /// # Safety
+    /// QuickJS doesn't validate bytecode, the bytes must have been written by [`Ctx::compile`]
+    /// with the same version of QuickJS.
+    pub const unsafe fn from_bytecode(bytecode: &'static [u8]) -> Self {
+        CompiledScript(bytecode)
+    }
+
+    /// Returns the bytecode of the script
+    pub fn bytecode(&self) -> &'static [u8] {
+        self.0
+    }
+
+    /// Evaluate the script, returning the value of its last expression
+    pub fn eval<'js, V: FromJs<'js>>(&self, ctx: &Ctx<'js>) -> Result<V> {
+        unsafe { ctx.eval_bytecode(self.0) }
+    }
+}
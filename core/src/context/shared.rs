@@ -0,0 +1,218 @@
+use crate::{Atom, Context, Ctx, Filter, Function, Object, Persistent, Result, Runtime, Value};
+
+/// Locks down the context of a template, run once after the globals are set.
+///
+/// Every context installing the template can reach the objects of the template context through
+/// the prototypes of shared values, so all of them are frozen. The function constructors are
+/// replaced as they would evaluate code with the global object of the template context.
+const LOCKDOWN: &str = r#"
+(api) => {
+    const forbidden = function () {
+        throw new TypeError("code generation is not allowed in shared globals");
+    };
+    const functions = [function () {}, function* () {}, async function () {}, async function* () {}];
+    for (const f of functions) {
+        Object.defineProperty(Object.getPrototypeOf(f), "constructor", { value: forbidden });
+    }
+    delete globalThis.eval;
+    delete globalThis.Function;
+
+    const { freeze, getPrototypeOf, getOwnPropertyDescriptors } = Object;
+    const { ownKeys } = Reflect;
+    const pending = [
+        globalThis,
+        api,
+        ...functions,
+        functions[1](),
+        functions[3](),
+        [][Symbol.iterator](),
+        new Map()[Symbol.iterator](),
+        new Set()[Symbol.iterator](),
+        ""[Symbol.iterator](),
+        /a/[Symbol.matchAll](""),
+    ];
+    const visited = new Set();
+    while (pending.length > 0) {
+        const value = pending.pop();
+        if (
+            (typeof value !== "object" && typeof value !== "function") ||
+            value === null ||
+            visited.has(value)
+        ) {
+            continue;
+        }
+        visited.add(value);
+        freeze(value);
+        pending.push(getPrototypeOf(value));
+        const descriptors = getOwnPropertyDescriptors(value);
+        for (const key of ownKeys(descriptors)) {
+            const { value, get, set } = descriptors[key];
+            pending.push(value, get, set);
+        }
+    }
+}
+"#;
+
+/// A frozen template of globals shared by reference between the contexts of a runtime
+///
+/// Binding a large API into every context of a runtime running many tenants takes time and
+/// memory for every context. A `SharedGlobals` is built once in a context of its own which is
+/// then locked down: everything reachable from the template and the global object of its context,
+/// prototypes included, is frozen and the function constructors of the context are removed.
+/// [`SharedGlobals::install`] then only defines a global in
+/// the context for every entry of the template which refers to the shared object, so the
+/// objects of the API exist once in the runtime.
+///
+/// The shared objects and their prototypes can't be mutated, so contexts can't communicate through
+/// them, but a context can replace the globals installed from the
+/// template with its own values without affecting other contexts.
+///
+/// Functions of the template belong to the context of the template: Rust functions are called
+/// with the [`Ctx`] of the template and objects created by shared functions have the prototypes
+/// of the template context, so for example `instanceof Array` is `false` in other contexts for
+/// arrays created by a shared function. The template context can't evaluate code from other
+/// contexts, `eval` and the `Function` constructors are not available in it after it is locked
+/// down.
+///
+/// Like a [`Persistent`] the template must not outlive its runtime.
+///
+/// ```
+/// # use rquickjs::{context::SharedGlobals, Context, Function, Object, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// let shared = SharedGlobals::new(&rt, |ctx, globals| {
+///     let api = Object::new(ctx.clone())?;
+///     api.set("add", Function::new(ctx, |a: i32, b: i32| a + b))?;
+///     globals.set("api", api)
+/// })
+/// .unwrap();
+///
+/// for _ in 0..10 {
+///     let ctx = Context::full(&rt).unwrap();
+///     ctx.with(|ctx| {
+///         shared.install(&ctx).unwrap();
+///         assert_eq!(ctx.eval::<i32, _>("api.add(1, 2)").unwrap(), 3);
+///     });
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedGlobals {
+    globals: Persistent<Object<'static>>,
+}
+
+impl SharedGlobals {
+    /// Create a template by setting the shared globals on the object passed to `f`
+    pub fn new<F>(runtime: &Runtime, f: F) -> Result<Self>
+    where
+        F: for<'js> FnOnce(Ctx<'js>, &Object<'js>) -> Result<()>,
+    {
+        let context = Context::full(runtime)?;
+        context.with(|ctx| {
+            let globals = Object::new(ctx.clone())?;
+            f(ctx.clone(), &globals)?;
+            let lockdown: Function = ctx.eval(LOCKDOWN)?;
+            lockdown.call::<_, ()>((globals.clone(),))?;
+            Ok(SharedGlobals {
+                globals: Persistent::save(&ctx, globals),
+            })
+        })
+    }
+
+    /// Define the globals of the template in a context of the same runtime
+    ///
+    /// Returns [`Error::UnrelatedRuntime`](crate::Error::UnrelatedRuntime) if the context
+    /// belongs to another runtime.
+    pub fn install(&self, ctx: &Ctx<'_>) -> Result<()> {
+        let template = self.globals.clone().restore(ctx)?;
+        let globals = ctx.globals();
+        for prop in template.own_props::<Atom, Value>(Filter::new().string().symbol()) {
+            let (key, value) = prop?;
+            globals.set(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn install() {
+        let rt = Runtime::new().unwrap();
+        let shared = SharedGlobals::new(&rt, |ctx, globals| {
+            let api = Object::new(ctx.clone())?;
+            api.set("version", 1)?;
+            api.set("double", Function::new(ctx, |x: i32| x * 2))?;
+            globals.set("api", api)
+        })
+        .unwrap();
+
+        let a = Context::full(&rt).unwrap();
+        let b = Context::full(&rt).unwrap();
+        let api_a = a.with(|ctx| {
+            shared.install(&ctx).unwrap();
+            assert_eq!(ctx.eval::<i32, _>("api.double(api.version)").unwrap(), 2);
+            // The shared object is frozen.
+            assert!(ctx.eval::<(), _>("api.version = 2").is_err());
+            ctx.catch();
+            let api: Object = ctx.globals().get("api").unwrap();
+            let api = Persistent::save(&ctx, api);
+            // Replacing the global only affects this context.
+            ctx.eval::<(), _>("globalThis.api = null").unwrap();
+            api
+        });
+        b.with(|ctx| {
+            shared.install(&ctx).unwrap();
+            let api: Object = ctx.globals().get("api").unwrap();
+            assert_eq!(api, api_a.restore(&ctx).unwrap());
+            assert_eq!(ctx.eval::<i32, _>("api.version").unwrap(), 1);
+        });
+
+        let other = Runtime::new().unwrap();
+        Context::full(&other).unwrap().with(|ctx| {
+            assert!(matches!(shared.install(&ctx), Err(Error::UnrelatedRuntime)));
+        });
+    }
+
+    #[test]
+    fn locked_down() {
+        let rt = Runtime::new().unwrap();
+        let shared = SharedGlobals::new(&rt, |ctx, globals| {
+            let api = Object::new(ctx.clone())?;
+            api.set("list", Function::new(ctx, || vec![1, 2]))?;
+            globals.set("api", api)
+        })
+        .unwrap();
+
+        let a = Context::full(&rt).unwrap();
+        let b = Context::full(&rt).unwrap();
+        a.with(|ctx| {
+            shared.install(&ctx).unwrap();
+            for source in [
+                "Object.getPrototypeOf(api).leak = 1",
+                "Object.getPrototypeOf(api.list).leak = 1",
+                "Object.getPrototypeOf(api.list()).leak = 1",
+                "api.list.constructor.leak = 1",
+                "api.list.constructor('return globalThis')().leak = 1",
+            ] {
+                let source = format!("'use strict'; {source}");
+                assert!(ctx.eval::<(), _>(source).is_err());
+                ctx.catch();
+            }
+        });
+        b.with(|ctx| {
+            shared.install(&ctx).unwrap();
+            let leaked: bool = ctx
+                .eval(
+                    r#"
+                    "leak" in Object.getPrototypeOf(api)
+                        || "leak" in Object.getPrototypeOf(api.list)
+                        || "leak" in Object.getPrototypeOf(api.list())
+                    "#,
+                )
+                .unwrap();
+            assert!(!leaked);
+        });
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    io::{Error as IoError, ErrorKind},
+    mem::MaybeUninit,
+    slice,
+};
+
+use crate::{qjs, Ctx, Error, Module, Object, Result, Value};
+
+const MAGIC: &[u8; 8] = b"RQJSSNAP";
+
+/// An experimental snapshot of the state of a context
+///
+/// A snapshot is taken with [`Ctx::freeze`] and restored into a context of any runtime with
+/// [`Ctx::thaw`], which allows checkpointing the state of scripts and restoring it after a
+/// process restart. The snapshot can be stored with [`Snapshot::to_bytes`].
+///
+/// QuickJS can't serialize closures or the state of evaluated modules, so a snapshot contains:
+///
+/// - The data of the global object: every enumerable global whose value is not a function.
+///   Shared references and cycles between the values are preserved. Values which can't be
+///   serialized, like objects of Rust classes or objects containing functions, make
+///   [`Ctx::freeze`] fail. Variables declared with `let`, `const` or `class` at the top level of a
+///   script are not properties of the global object and are not captured.
+/// - Module bytecode added with [`Snapshot::add_module`], declared and evaluated on thaw.
+/// - The names of modules which are imported again on thaw with the loader of the runtime.
+///
+/// Functions defined by scripts are restored by evaluating the scripts again before thawing, the
+/// data of the snapshot then overwrites the globals they set.
+///
+/// The format of the snapshot depends on the version of the library, a snapshot can only be
+/// thawed by the same version which froze it.
+///
+/// QuickJS doesn't validate serialized data and bytecode, loading crafted input is memory unsafe.
+/// Therefore [`Snapshot::from_bytes`] and [`Snapshot::add_module`] are unsafe and must only be
+/// given data created by this library and stored where it can't be tampered with.
+///
+/// ```
+/// # use rquickjs::{Context, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// let bytes = ctx.with(|ctx| {
+///     ctx.eval::<(), _>("var state = { count: 1 }; state.count += 1;").unwrap();
+///     ctx.freeze().unwrap().to_bytes()
+/// });
+///
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     // Safety: the bytes were just created by `Snapshot::to_bytes`.
+///     let snapshot = unsafe { rquickjs::context::Snapshot::from_bytes(&bytes) }.unwrap();
+///     ctx.thaw(&snapshot).unwrap();
+///     assert_eq!(ctx.eval::<i32, _>("state.count").unwrap(), 2);
+/// });
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    globals: Vec<u8>,
+    modules: Vec<(String, Vec<u8>)>,
+    imports: Vec<String>,
+}
+
+impl Snapshot {
+    /// Add module bytecode, as returned by [`Module::write`], which is declared and evaluated on
+    /// thaw before the globals are restored
+    ///
+    /// # Safety
+    /// The bytecode must have been written by [`Module::write`] of this version of the library
+    /// and not been modified since, see [`Module::load`].
+    pub unsafe fn add_module<N: Into<String>>(&mut self, name: N, bytecode: Vec<u8>) -> &mut Self {
+        self.modules.push((name.into(), bytecode));
+        self
+    }
+
+    /// Add module bytecode which is declared and evaluated on thaw
+    ///
+    /// # Safety
+    /// The same as for [`Snapshot::add_module`].
+    #[must_use]
+    pub unsafe fn with_module<N: Into<String>>(mut self, name: N, bytecode: Vec<u8>) -> Self {
+        self.add_module(name, bytecode);
+        self
+    }
+
+    /// Add the name of a module which is imported on thaw before the globals are restored
+    pub fn add_import<N: Into<String>>(&mut self, name: N) -> &mut Self {
+        self.imports.push(name.into());
+        self
+    }
+
+    /// Add the name of a module which is imported on thaw
+    #[must_use]
+    pub fn with_import<N: Into<String>>(mut self, name: N) -> Self {
+        self.add_import(name);
+        self
+    }
+
+    /// Serialize the snapshot
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        write_bytes(&mut bytes, env!("CARGO_PKG_VERSION").as_bytes());
+        write_bytes(&mut bytes, &self.globals);
+        write_len(&mut bytes, self.modules.len());
+        for (name, bytecode) in &self.modules {
+            write_bytes(&mut bytes, name.as_bytes());
+            write_bytes(&mut bytes, bytecode);
+        }
+        write_len(&mut bytes, self.imports.len());
+        for name in &self.imports {
+            write_bytes(&mut bytes, name.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a snapshot created with [`Snapshot::to_bytes`]
+    ///
+    /// Returns an error if the bytes are not a snapshot or the snapshot was created by another
+    /// version of the library.
+    ///
+    /// # Safety
+    /// The bytes must have been created by [`Snapshot::to_bytes`] and not been modified since.
+    /// Only the header of the snapshot is checked, the globals and the module bytecode are loaded
+    /// by QuickJS without validation when the snapshot is thawed.
+    pub unsafe fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a snapshot"));
+        }
+        if reader.bytes()? != env!("CARGO_PKG_VERSION").as_bytes() {
+            return Err(invalid("snapshot was created by another version"));
+        }
+        let globals = reader.bytes()?.to_vec();
+        let modules = (0..reader.len()?)
+            .map(|_| Ok((reader.string()?, reader.bytes()?.to_vec())))
+            .collect::<Result<_>>()?;
+        let imports = (0..reader.len()?)
+            .map(|_| reader.string())
+            .collect::<Result<_>>()?;
+        if !reader.0.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(Snapshot {
+            globals,
+            modules,
+            imports,
+        })
+    }
+}
+
+impl<'js> Ctx<'js> {
+    /// Take an experimental snapshot of the data of the context, see [`Snapshot`]
+    pub fn freeze(&self) -> Result<Snapshot> {
+        let data = Object::new(self.clone())?;
+        for prop in self.globals().props::<String, Value>() {
+            let (key, value) = prop?;
+            if !value.is_function() {
+                data.set(key, value)?;
+            }
+        }
+        Ok(Snapshot {
+            globals: write_value(self, data.as_value())?,
+            ..Snapshot::default()
+        })
+    }
+
+    /// Restore a snapshot taken with [`Ctx::freeze`]
+    ///
+    /// The modules of the snapshot are evaluated first, then the globals of the snapshot are set,
+    /// overwriting existing globals with the same name.
+    pub fn thaw(&self, snapshot: &Snapshot) -> Result<()> {
+        for (name, bytecode) in &snapshot.modules {
+            // Safety: module bytecode can only be added with the unsafe `Snapshot::add_module` and
+            // `Snapshot::from_bytes`, whose callers guarantee it was written by `Module::write`.
+            let module = unsafe { Module::load_copied(self.clone(), bytecode)? };
+            let (_, promise) = module.eval()?;
+            #[cfg(feature = "loader")]
+            promise.finish::<()>().map_err(|e| {
+                Error::new_loading_message(name, format!("evaluating snapshot module failed: {e}"))
+            })?;
+            #[cfg(not(feature = "loader"))]
+            {
+                let _ = name;
+                promise.finish::<()>()?;
+            }
+        }
+        for name in &snapshot.imports {
+            Module::import(self, name.as_str())?.finish::<()>()?;
+        }
+
+        if !snapshot.globals.is_empty() {
+            // Safety: the globals were written by `Ctx::freeze`, or else deserialized by the
+            // unsafe `Snapshot::from_bytes`.
+            let data = unsafe { read_value(self, &snapshot.globals)? };
+            let data = data
+                .into_object()
+                .ok_or_else(|| invalid("snapshot globals are not an object"))?;
+            let globals = self.globals();
+            for prop in data.props::<String, Value>() {
+                let (key, value) = prop?;
+                globals.set(key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_value<'js>(ctx: &Ctx<'js>, value: &Value<'js>) -> Result<Vec<u8>> {
+    let mut len = MaybeUninit::uninit();
+    let buf = unsafe {
+        qjs::JS_WriteObject(
+            ctx.as_ptr(),
+            len.as_mut_ptr(),
+            value.as_js_value(),
+            qjs::JS_WRITE_OBJ_REFERENCE as i32,
+        )
+    };
+    if buf.is_null() {
+        return Err(ctx.raise_exception());
+    }
+    let bytes = unsafe { slice::from_raw_parts(buf, len.assume_init() as _) }.to_vec();
+    unsafe { qjs::js_free(ctx.as_ptr(), buf as _) };
+    Ok(bytes)
+}
+
+/// # Safety
+/// The bytes must have been written by `write_value`.
+unsafe fn read_value<'js>(ctx: &Ctx<'js>, bytes: &[u8]) -> Result<Value<'js>> {
+    let value = qjs::JS_ReadObject(
+        ctx.as_ptr(),
+        bytes.as_ptr(),
+        bytes.len() as _,
+        qjs::JS_READ_OBJ_REFERENCE as i32,
+    );
+    let value = ctx.handle_exception(value)?;
+    Ok(Value::from_js_value(ctx.clone(), value))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Io(IoError::new(
+        ErrorKind::InvalidData,
+        format!("invalid snapshot: {message}"),
+    ))
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_len(bytes, data.len());
+    bytes.extend_from_slice(data);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = u64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        usize::try_from(len).map_err(|_| invalid("length out of range"))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| invalid("name is not UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn serialize() {
+        let snapshot = unsafe {
+            Snapshot {
+                globals: vec![1, 2, 3],
+                ..Snapshot::default()
+            }
+            .with_module("a", vec![4])
+        }
+        .with_import("b");
+        let bytes = snapshot.to_bytes();
+        unsafe {
+            assert_eq!(Snapshot::from_bytes(&bytes).unwrap(), snapshot);
+            assert!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(Snapshot::from_bytes(b"not a snapshot").is_err());
+        }
+    }
+
+    #[test]
+    fn freeze_thaw() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let bytes = ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                var shared = { value: 1 };
+                var state = { list: [shared, shared], name: "test" };
+                state.self = state;
+                function helper() {}
+                "#,
+            )
+            .unwrap();
+            let module = Module::declare(ctx.clone(), "setup", "globalThis.fromModule = 42;")
+                .unwrap()
+                .write_le()
+                .unwrap();
+            unsafe { ctx.freeze().unwrap().with_module("setup", module) }.to_bytes()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let snapshot = unsafe { Snapshot::from_bytes(&bytes) }.unwrap();
+            ctx.thaw(&snapshot).unwrap();
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    state.name === "test"
+                        && state.self === state
+                        && state.list[0] === state.list[1]
+                        && state.list[0].value === 1
+                        && fromModule === 42
+                        && typeof helper === "undefined"
+                    "#,
+                )
+                .unwrap();
+            assert!(res);
+        });
+    }
+}
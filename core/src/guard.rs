@@ -0,0 +1,222 @@
+//! Host hooks on the lookup of global variables.
+//!
+//! [`install`] moves the properties of the global object behind a [`GlobalGuard`], which is then
+//! consulted every time a script reads one of them or a global which doesn't exist. The guard
+//! can let the lookup through, for example after recording which APIs a script uses, hide the
+//! global or provide a different value, like a shim for a deprecated API or a global which is
+//! only created when it is first used.
+//!
+//! The globals are moved to the prototype of the global object, which keeps them reachable by
+//! name and through `globalThis`, but they are no longer own properties of the global object
+//! and are read-only. Globals defined after the guard is installed, including the variables
+//! declared by scripts, don't pass through the guard.
+//!
+//! ```
+//! # use std::{cell::RefCell, rc::Rc};
+//! # use rquickjs::{guard::{self, Access, GlobalGuard}, Context, Ctx, Result, Runtime, Value};
+//! #[derive(Default)]
+//! struct Telemetry(Rc<RefCell<Vec<String>>>);
+//!
+//! impl GlobalGuard for Telemetry {
+//!     fn access<'js>(
+//!         &self,
+//!         _ctx: &Ctx<'js>,
+//!         name: &str,
+//!         _value: Option<&Value<'js>>,
+//!     ) -> Result<Access<'js>> {
+//!         self.0.borrow_mut().push(name.to_owned());
+//!         Ok(if name == "escape" { Access::Deny } else { Access::Allow })
+//!     }
+//! }
+//!
+//! let rt = Runtime::new().unwrap();
+//! let ctx = Context::full(&rt).unwrap();
+//! let used = Rc::new(RefCell::new(Vec::new()));
+//! ctx.with(|ctx| {
+//!     guard::install(&ctx, Telemetry(used.clone())).unwrap();
+//!     assert_eq!(ctx.eval::<i32, _>("Math.max(1, 2)").unwrap(), 2);
+//!     assert!(ctx.eval::<bool, _>("typeof escape === 'undefined'").unwrap());
+//! });
+//! assert_eq!(*used.borrow(), ["Math", "escape"]);
+//! ```
+
+use crate::{
+    class::{ClassId, Exotic, ExoticMethods, JsClass, Readable, Trace, Tracer},
+    value::Constructor,
+    Atom, Class, Ctx, Filter, Object, Result, Value,
+};
+
+/// The outcome of the lookup of a global.
+pub enum Access<'js> {
+    /// Return the value of the global, the lookup fails if it doesn't exist.
+    Allow,
+    /// Act as if the global doesn't exist.
+    Deny,
+    /// Return the given value instead.
+    Provide(Value<'js>),
+}
+
+/// A hook deciding the outcome of the lookup of globals.
+pub trait GlobalGuard {
+    /// Called when a script reads the global `name`, which has the given value or doesn't exist.
+    ///
+    /// Returning an error throws it in the script.
+    fn access<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        name: &str,
+        value: Option<&Value<'js>>,
+    ) -> Result<Access<'js>>;
+}
+
+/// The prototype of the global object, holding the guarded globals.
+struct GuardedGlobals<'js> {
+    globals: Object<'js>,
+    enumerable: Vec<Atom<'js>>,
+    guard: Box<dyn GlobalGuard>,
+}
+
+impl<'js> Trace<'js> for GuardedGlobals<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.globals.trace(tracer)
+    }
+}
+
+impl<'js> JsClass<'js> for GuardedGlobals<'js> {
+    const NAME: &'static str = "GuardedGlobals";
+
+    type Mutable = Readable;
+
+    fn class_id() -> &'static ClassId {
+        static ID: ClassId = ClassId::new();
+        &ID
+    }
+
+    fn prototype(_ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        Ok(None)
+    }
+
+    fn constructor(_ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        Ok(None)
+    }
+
+    fn exotic() -> Option<ExoticMethods> {
+        Some(ExoticMethods::new::<Self>())
+    }
+}
+
+impl<'js> Exotic<'js> for GuardedGlobals<'js> {
+    fn get_own_property(this: &Class<'js, Self>, key: Atom<'js>) -> Result<Option<Value<'js>>> {
+        let this = this.borrow();
+        let value = if this.globals.contains_key(key.clone())? {
+            Some(this.globals.get(key.clone())?)
+        } else {
+            None
+        };
+        let name = key.to_value()?;
+        let Some(name) = name.as_string() else {
+            // Symbols can't be looked up by name, so they aren't guarded.
+            return Ok(value);
+        };
+        let access = this
+            .guard
+            .access(name.ctx(), &name.to_string()?, value.as_ref())?;
+        Ok(match access {
+            Access::Allow => value,
+            Access::Deny => None,
+            Access::Provide(value) => Some(value),
+        })
+    }
+
+    fn own_property_names(this: &Class<'js, Self>) -> Result<Vec<Atom<'js>>> {
+        Ok(this.borrow().enumerable.clone())
+    }
+}
+
+/// Move the globals behind the given guard.
+///
+/// Properties of the global object which can't be deleted, like `undefined`, stay in place and
+/// aren't guarded.
+pub fn install<G: GlobalGuard + 'static>(ctx: &Ctx<'_>, guard: G) -> Result<()> {
+    let globals = ctx.globals();
+    let enumerable = globals.keys::<Atom>().collect::<Result<Vec<_>>>()?;
+    let guarded = Object::new(ctx.clone())?;
+    for key in globals.own_keys::<Atom>(Filter::new().string().symbol()) {
+        let key = key?;
+        let value: Value = globals.get(key.clone())?;
+        if globals.remove(key.clone()).is_err() {
+            ctx.catch();
+            continue;
+        }
+        guarded.set(key, value)?;
+    }
+    let enumerable = enumerable
+        .into_iter()
+        .filter(|key| guarded.contains_key(key.clone()).unwrap_or(false))
+        .collect();
+    let proto = Class::instance(
+        ctx.clone(),
+        GuardedGlobals {
+            globals: guarded,
+            enumerable,
+            guard: Box::new(guard),
+        },
+    )?;
+    proto.set_prototype(globals.get_prototype().as_ref())?;
+    globals.set_prototype(Some(&proto))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Exception, Runtime};
+
+    struct Shims;
+
+    impl GlobalGuard for Shims {
+        fn access<'js>(
+            &self,
+            ctx: &Ctx<'js>,
+            name: &str,
+            value: Option<&Value<'js>>,
+        ) -> Result<Access<'js>> {
+            Ok(match name {
+                "escape" => return Err(Exception::throw_reference(ctx, "escape is deprecated")),
+                "eval" => Access::Deny,
+                "answer" => Access::Provide(Value::new_int(ctx.clone(), 42)),
+                "parseFloat" if value.is_some() => Access::Provide(ctx.eval("Number.parseInt")?),
+                _ => Access::Allow,
+            })
+        }
+    }
+
+    #[test]
+    fn global_guard() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.globals().set("host", 1).unwrap();
+            install(&ctx, Shims).unwrap();
+            ctx.globals().set("late", 2).unwrap();
+
+            let res: String = ctx
+                .eval(
+                    r#"[
+                        typeof eval, typeof Math.max, answer, parseFloat("1.5"), host, late,
+                        globalThis.answer, undefined === void 0, Object.keys(globalThis),
+                    ].join(" ")"#,
+                )
+                .unwrap();
+            assert_eq!(res, "undefined function 42 1 1 2 42 true late");
+
+            let err: String = ctx
+                .eval(r#"try { escape("a") } catch (e) { e.name + ": " + e.message }"#)
+                .unwrap();
+            assert_eq!(err, "ReferenceError: escape is deprecated");
+            let err: String = ctx
+                .eval(r#"try { eval("1") } catch (e) { e.name }"#)
+                .unwrap();
+            assert_eq!(err, "ReferenceError");
+        });
+    }
+}
@@ -0,0 +1,484 @@
+//! A minimal `Intl` backed by ICU4X.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fixed_decimal::{FixedDecimal, FloatPrecision};
+use icu::{
+    calendar::DateTime,
+    datetime::{options::length, DateTimeFormatter},
+    decimal::{options::GroupingStrategy, FixedDecimalFormatter},
+    locid::Locale,
+};
+
+use crate::{
+    class::{ClassId, JsClass, Readable, Trace, Tracer},
+    function::{Opt, This},
+    value::Constructor,
+    Class, Coerced, Ctx, Exception, FromJs, Function, Object, Result, Value,
+};
+
+/// The locale used when a script doesn't request one.
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// The largest number of fraction digits a `NumberFormat` accepts.
+const MAX_FRACTION_DIGITS: f64 = 20.0;
+
+const STYLES: [&str; 4] = ["full", "long", "medium", "short"];
+
+/// Define the `Intl` global object.
+pub(crate) fn define(ctx: &Ctx<'_>) -> Result<()> {
+    let intl = Object::new(ctx.clone())?;
+    Class::<NumberFormat>::define(&intl)?;
+    Class::<DateTimeFormat>::define(&intl)?;
+    ctx.globals().set("Intl", intl)
+}
+
+/// The first of the requested locales, given as a language tag or an array of them.
+fn locale<'js>(ctx: &Ctx<'js>, locales: Option<Value<'js>>) -> Result<Locale> {
+    let tag = match locales {
+        Some(locales) if locales.is_string() => Some(locales.get::<String>()?),
+        Some(locales) => locales
+            .get::<Option<Vec<String>>>()?
+            .and_then(|tags| tags.into_iter().next()),
+        None => None,
+    };
+    let tag = tag.as_deref().unwrap_or(DEFAULT_LOCALE);
+    tag.parse().map_err(|error| {
+        Exception::throw_range(ctx, &format!("Invalid language tag '{tag}': {error}"))
+    })
+}
+
+fn option<'js, T: FromJs<'js>>(options: &Option<Object<'js>>, name: &str) -> Result<Option<T>> {
+    match options {
+        Some(options) => options.get(name),
+        None => Ok(None),
+    }
+}
+
+fn fraction_digits<'js>(
+    ctx: &Ctx<'js>,
+    options: &Option<Object<'js>>,
+    name: &str,
+) -> Result<Option<u8>> {
+    match option::<f64>(options, name)? {
+        Some(digits) if (0.0..=MAX_FRACTION_DIGITS).contains(&digits) => Ok(Some(digits as u8)),
+        Some(_) => Err(Exception::throw_range(
+            ctx,
+            &format!("{name} must be between 0 and {MAX_FRACTION_DIGITS}"),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// `Intl.NumberFormat` with the `decimal` style.
+pub(crate) struct NumberFormat {
+    locale: Locale,
+    formatter: FixedDecimalFormatter,
+    use_grouping: bool,
+    minimum_fraction_digits: u8,
+    maximum_fraction_digits: u8,
+}
+
+impl NumberFormat {
+    fn new<'js>(
+        ctx: &Ctx<'js>,
+        locales: Opt<Value<'js>>,
+        options: Opt<Option<Object<'js>>>,
+    ) -> Result<Self> {
+        let locale = locale(ctx, locales.0)?;
+        let options = options.0.flatten();
+        if let Some(style) = option::<String>(&options, "style")? {
+            if style != "decimal" {
+                return Err(Exception::throw_range(
+                    ctx,
+                    &format!("Unsupported number format style '{style}'"),
+                ));
+            }
+        }
+        let use_grouping = option(&options, "useGrouping")?.unwrap_or(true);
+        let minimum = fraction_digits(ctx, &options, "minimumFractionDigits")?;
+        let maximum = fraction_digits(ctx, &options, "maximumFractionDigits")?;
+        let (minimum_fraction_digits, maximum_fraction_digits) = match (minimum, maximum) {
+            (minimum, Some(maximum)) => (minimum.unwrap_or(0), maximum),
+            (Some(minimum), None) => (minimum, minimum.max(3)),
+            (None, None) => (0, 3),
+        };
+        if minimum_fraction_digits > maximum_fraction_digits {
+            return Err(Exception::throw_range(
+                ctx,
+                "maximumFractionDigits is less than minimumFractionDigits",
+            ));
+        }
+        let grouping = if use_grouping {
+            GroupingStrategy::Auto
+        } else {
+            GroupingStrategy::Never
+        };
+        let formatter = FixedDecimalFormatter::try_new(&(&locale).into(), grouping.into())
+            .map_err(|error| {
+                Exception::throw_range(ctx, &format!("Unsupported locale '{locale}': {error}"))
+            })?;
+        Ok(Self {
+            locale,
+            formatter,
+            use_grouping,
+            minimum_fraction_digits,
+            maximum_fraction_digits,
+        })
+    }
+
+    fn format(&self, value: f64) -> String {
+        if value.is_nan() {
+            return "NaN".into();
+        }
+        if value.is_infinite() {
+            return if value < 0.0 { "-∞" } else { "∞" }.into();
+        }
+        let Ok(mut decimal) = FixedDecimal::try_from_f64(value, FloatPrecision::Floating) else {
+            return value.to_string();
+        };
+        decimal.half_expand(-i16::from(self.maximum_fraction_digits));
+        decimal.trim_end();
+        decimal.pad_end(-i16::from(self.minimum_fraction_digits));
+        self.formatter.format_to_string(&decimal)
+    }
+
+    fn resolved_options<'js>(&self, ctx: &Ctx<'js>) -> Result<Object<'js>> {
+        let options = Object::new(ctx.clone())?;
+        options.set("locale", self.locale.to_string())?;
+        options.set("numberingSystem", "latn")?;
+        options.set("style", "decimal")?;
+        options.set("minimumFractionDigits", self.minimum_fraction_digits)?;
+        options.set("maximumFractionDigits", self.maximum_fraction_digits)?;
+        options.set("useGrouping", self.use_grouping)?;
+        Ok(options)
+    }
+}
+
+impl<'js> Trace<'js> for NumberFormat {
+    fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+}
+
+impl<'js> JsClass<'js> for NumberFormat {
+    const NAME: &'static str = "NumberFormat";
+
+    type Mutable = Readable;
+
+    fn class_id() -> &'static ClassId {
+        static ID: ClassId = ClassId::new();
+        &ID
+    }
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+        let format = Function::new(
+            ctx.clone(),
+            |this: This<Class<'js, NumberFormat>>, value: Coerced<f64>| {
+                this.0.borrow().format(value.0)
+            },
+        )?
+        .with_name("format")?;
+        proto.set("format", format)?;
+        let resolved_options = Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, this: This<Class<'js, NumberFormat>>| {
+                this.0.borrow().resolved_options(&ctx)
+            },
+        )?
+        .with_name("resolvedOptions")?;
+        proto.set("resolvedOptions", resolved_options)?;
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        Ok(Some(Constructor::new_class::<NumberFormat, _, _>(
+            ctx.clone(),
+            |ctx: Ctx<'js>, locales: Opt<Value<'js>>, options: Opt<Option<Object<'js>>>| {
+                Class::instance(ctx.clone(), NumberFormat::new(&ctx, locales, options)?)
+            },
+        )?))
+    }
+}
+
+/// `Intl.DateTimeFormat` with the `dateStyle` and `timeStyle` options in UTC.
+pub(crate) struct DateTimeFormat {
+    locale: Locale,
+    formatter: DateTimeFormatter,
+    date_style: Option<&'static str>,
+    time_style: Option<&'static str>,
+}
+
+impl DateTimeFormat {
+    fn new<'js>(
+        ctx: &Ctx<'js>,
+        locales: Opt<Value<'js>>,
+        options: Opt<Option<Object<'js>>>,
+    ) -> Result<Self> {
+        let locale = locale(ctx, locales.0)?;
+        let options = options.0.flatten();
+        if let Some(time_zone) = option::<String>(&options, "timeZone")? {
+            if !time_zone.eq_ignore_ascii_case("UTC") {
+                return Err(Exception::throw_range(
+                    ctx,
+                    &format!("Unsupported time zone '{time_zone}', only UTC is supported"),
+                ));
+            }
+        }
+        let date_style = Self::style(ctx, &options, "dateStyle")?;
+        let time_style = Self::style(ctx, &options, "timeStyle")?;
+        if let Some(style @ ("full" | "long")) = time_style {
+            return Err(Exception::throw_range(
+                ctx,
+                &format!("Unsupported timeStyle '{style}', time zone names aren't supported"),
+            ));
+        }
+        let bag = match (date_style, time_style) {
+            (Some(date), Some(time)) => {
+                length::Bag::from_date_time_style(Self::date(date), Self::time(time))
+            }
+            (None, Some(time)) => length::Bag::from_time_style(Self::time(time)),
+            (date, None) => length::Bag::from_date_style(Self::date(date.unwrap_or("short"))),
+        };
+        let formatter =
+            DateTimeFormatter::try_new(&(&locale).into(), bag.into()).map_err(|error| {
+                Exception::throw_range(ctx, &format!("Unsupported locale '{locale}': {error}"))
+            })?;
+        Ok(Self {
+            locale,
+            formatter,
+            date_style,
+            time_style,
+        })
+    }
+
+    fn style<'js>(
+        ctx: &Ctx<'js>,
+        options: &Option<Object<'js>>,
+        name: &str,
+    ) -> Result<Option<&'static str>> {
+        let Some(style) = option::<String>(options, name)? else {
+            return Ok(None);
+        };
+        match STYLES.iter().find(|x| **x == style) {
+            Some(style) => Ok(Some(style)),
+            None => Err(Exception::throw_range(
+                ctx,
+                &format!("Invalid {name} '{style}'"),
+            )),
+        }
+    }
+
+    fn date(style: &str) -> length::Date {
+        match style {
+            "full" => length::Date::Full,
+            "long" => length::Date::Long,
+            "medium" => length::Date::Medium,
+            _ => length::Date::Short,
+        }
+    }
+
+    fn time(style: &str) -> length::Time {
+        match style {
+            "medium" => length::Time::Medium,
+            _ => length::Time::Short,
+        }
+    }
+
+    fn format<'js>(&self, ctx: &Ctx<'js>, date: Option<Coerced<f64>>) -> Result<String> {
+        let time = match date {
+            Some(Coerced(time)) => time,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |now| now.as_millis() as f64),
+        };
+        if !time.is_finite() {
+            return Err(Exception::throw_range(ctx, "Invalid time value"));
+        }
+        let (year, month, day, hour, minute, second) = civil_from_time(time);
+        let date = DateTime::try_new_iso_datetime(year, month, day, hour, minute, second)
+            .map_err(|_| Exception::throw_range(ctx, "Invalid time value"))?;
+        self.formatter
+            .format_to_string(&date.to_any())
+            .map_err(|error| Exception::throw_range(ctx, &error.to_string()))
+    }
+
+    fn resolved_options<'js>(&self, ctx: &Ctx<'js>) -> Result<Object<'js>> {
+        let options = Object::new(ctx.clone())?;
+        options.set("locale", self.locale.to_string())?;
+        options.set("calendar", "gregory")?;
+        options.set("numberingSystem", "latn")?;
+        options.set("timeZone", "UTC")?;
+        if let Some(date_style) = self.date_style {
+            options.set("dateStyle", date_style)?;
+        }
+        if let Some(time_style) = self.time_style {
+            options.set("timeStyle", time_style)?;
+        }
+        Ok(options)
+    }
+}
+
+impl<'js> Trace<'js> for DateTimeFormat {
+    fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+}
+
+impl<'js> JsClass<'js> for DateTimeFormat {
+    const NAME: &'static str = "DateTimeFormat";
+
+    type Mutable = Readable;
+
+    fn class_id() -> &'static ClassId {
+        static ID: ClassId = ClassId::new();
+        &ID
+    }
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+        let format = Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>,
+             this: This<Class<'js, DateTimeFormat>>,
+             date: Opt<Option<Coerced<f64>>>| {
+                this.0.borrow().format(&ctx, date.0.flatten())
+            },
+        )?
+        .with_name("format")?;
+        proto.set("format", format)?;
+        let resolved_options = Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, this: This<Class<'js, DateTimeFormat>>| {
+                this.0.borrow().resolved_options(&ctx)
+            },
+        )?
+        .with_name("resolvedOptions")?;
+        proto.set("resolvedOptions", resolved_options)?;
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        Ok(Some(Constructor::new_class::<DateTimeFormat, _, _>(
+            ctx.clone(),
+            |ctx: Ctx<'js>, locales: Opt<Value<'js>>, options: Opt<Option<Object<'js>>>| {
+                Class::instance(ctx.clone(), DateTimeFormat::new(&ctx, locales, options)?)
+            },
+        )?))
+    }
+}
+
+/// Split milliseconds since the epoch into the UTC year, month, day, hour, minute and second.
+fn civil_from_time(time: f64) -> (i32, u8, u8, u8, u8, u8) {
+    let seconds = (time / 1000.0).floor() as i64;
+    let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    // Days to civil date in the proleptic Gregorian calendar, with eras of 400 years starting
+    // on March 1st.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (
+        year as i32,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds % 3600 / 60) as u8,
+        (seconds % 60) as u8,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{context::intrinsic, Context, Runtime};
+
+    #[test]
+    fn civil_from_time() {
+        assert_eq!(super::civil_from_time(0.0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(
+            super::civil_from_time(1_705_323_900_000.0),
+            (2024, 1, 15, 13, 5, 0)
+        );
+        assert_eq!(
+            super::civil_from_time(951_782_400_000.0),
+            (2000, 2, 29, 0, 0, 0)
+        );
+        assert_eq!(super::civil_from_time(-1.0), (1969, 12, 31, 23, 59, 59));
+    }
+
+    #[test]
+    fn number_format() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::builder()
+            .with::<(intrinsic::Eval, intrinsic::Intl)>()
+            .build(&rt)
+            .unwrap();
+        ctx.with(|ctx| {
+            let res: Vec<String> = ctx
+                .eval(
+                    r#"[
+                        new Intl.NumberFormat("en-US").format(1234567.891),
+                        new Intl.NumberFormat("de-DE").format(1234567.891),
+                        new Intl.NumberFormat("en", { maximumFractionDigits: 0 }).format(2.5),
+                        new Intl.NumberFormat("en", { minimumFractionDigits: 2 }).format(1),
+                        new Intl.NumberFormat("en", { useGrouping: false }).format(1234),
+                        new Intl.NumberFormat().resolvedOptions().locale,
+                    ]"#,
+                )
+                .unwrap();
+            assert_eq!(
+                res,
+                [
+                    "1,234,567.891",
+                    "1.234.567,891",
+                    "3",
+                    "1.00",
+                    "1234",
+                    "en-US"
+                ]
+            );
+            let error: String = ctx
+                .eval(
+                    r#"try { new Intl.NumberFormat("en", { style: "currency" }) }
+                    catch (e) { e.name }"#,
+                )
+                .unwrap();
+            assert_eq!(error, "RangeError");
+        });
+    }
+
+    #[test]
+    fn date_time_format() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::builder()
+            .with::<(intrinsic::Date, intrinsic::Eval, intrinsic::Intl)>()
+            .build(&rt)
+            .unwrap();
+        ctx.with(|ctx| {
+            let res: Vec<String> = ctx
+                .eval(
+                    r#"const date = new Date(Date.UTC(2024, 0, 15, 13, 5));
+                    [
+                        new Intl.DateTimeFormat("en-US", { dateStyle: "medium" }).format(date),
+                        new Intl.DateTimeFormat("de-DE", { dateStyle: "medium" }).format(date),
+                        new Intl.DateTimeFormat("en-US").format(date),
+                        new Intl.DateTimeFormat("en-US", { timeStyle: "short", timeZone: "UTC" })
+                            .format(date.getTime()),
+                    ]"#,
+                )
+                .unwrap();
+            assert_eq!(res[..3], ["Jan 15, 2024", "15.01.2024", "1/15/24"]);
+            assert!(res[3].starts_with("1:05"), "{}", res[3]);
+            let error: String = ctx
+                .eval(
+                    r#"try { new Intl.DateTimeFormat("en", { timeZone: "Europe/Berlin" }) }
+                    catch (e) { e.name }"#,
+                )
+                .unwrap();
+            assert_eq!(error, "RangeError");
+        });
+    }
+}
@@ -21,20 +21,30 @@ pub mod context;
 pub use context::{Context, Ctx};
 pub mod class;
 pub use class::Class;
+pub mod guard;
+pub mod performance;
+pub mod sandbox;
+pub mod source;
+pub mod supervisor;
 pub use persistent::{Outlive, Persistent};
 pub use result::{CatchResultExt, CaughtError, CaughtResult, Error, Result, ThrowResultExt};
 pub use value::{
     array, atom, convert, function, module, object, promise, Array, Atom, BigInt, Coerced,
     Exception, Filter, FromAtom, FromIteratorJs, FromJs, Function, IntoAtom, IntoJs, IteratorJs,
-    Module, Null, Object, Promise, String, Symbol, Type, Undefined, Value,
+    LoneSurrogates, Module, Null, Object, Promise, String, Symbol, Type, Undefined, Value,
 };
 
 #[cfg(feature = "allocator")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "allocator")))]
 pub mod allocator;
+#[cfg(feature = "intl")]
+mod intl;
 #[cfg(feature = "loader")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
 pub mod loader;
+#[cfg(feature = "testing")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "testing")))]
+pub mod testing;
 
 #[cfg(feature = "futures")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
@@ -44,13 +54,41 @@ pub use context::MultiWith;
 #[cfg(feature = "futures")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
 pub use runtime::AsyncRuntime;
+#[cfg(feature = "arbitrary")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "arbitrary")))]
+pub use value::arbitrary;
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde")))]
+pub use value::serde;
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+pub use value::stream;
 #[cfg(feature = "array-buffer")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "array-buffer")))]
 pub use value::{ArrayBuffer, TypedArray};
 
-//#[doc(hidden)]
+#[cfg_attr(not(feature = "raw"), doc(hidden))]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "raw")))]
 pub mod qjs {
     //! Native low-level bindings
+    //!
+    //! The bindings of the QuickJS version used by this crate, re-exported from `rquickjs-sys`
+    //! so they always match the library. They are documented when the `raw` feature is
+    //! enabled, which marks the following items as stable: they are only changed or removed
+    //! in a breaking release of this crate.
+    //!
+    //! - The types [`JSRuntime`], [`JSContext`], [`JSValue`] and [`JSAtom`].
+    //! - Reference counting of values with [`JS_DupValue`], [`JS_FreeValue`] and
+    //!   [`JS_FreeValueRT`].
+    //! - Atoms with [`JS_NewAtomLen`], [`JS_DupAtom`], [`JS_FreeAtom`], [`JS_AtomToValue`],
+    //!   [`JS_AtomToString`], [`JS_AtomToCString`], [`JS_ValueToAtom`] and [`JS_FreeCString`].
+    //! - Exceptions with [`JS_Throw`] and [`JS_GetException`], and getting the runtime of a
+    //!   context with [`JS_GetRuntime`].
+    //!
+    //! Other items follow `rquickjs-sys` and the QuickJS version, and may change in any
+    //! release. Raw values and contexts are converted from and into the types of this crate with
+    //! the `as_raw` and `from_raw` functions, for example [`Value::as_raw`](crate::Value::as_raw)
+    //! and [`Ctx::from_raw`](crate::Ctx::from_raw).
     pub use rquickjs_sys::*;
 }
 
@@ -86,9 +124,13 @@ pub mod prelude {
     pub use crate::context::MultiWith;
     pub use crate::{
         context::Ctx,
-        convert::{Coerced, FromAtom, FromIteratorJs, FromJs, IntoAtom, IntoJs, IteratorJs, List},
+        convert::{
+            Coerced, FromAtom, FromIteratorJs, FromJs, IntoAtom, IntoJs, IteratorJs, List,
+            PrimitiveHint,
+        },
         function::{
-            Exhaustive, Flat, Func, FuncArg, IntoArg, IntoArgs, MutFn, OnceFn, Opt, Rest, This,
+            Exhaustive, Flat, Func, FuncArg, IntoArg, IntoArgs, MutFn, OnceFn, Opt, Rest,
+            SelfFunction, This,
         },
         result::{CatchResultExt, ThrowResultExt},
     };
@@ -97,6 +139,7 @@ pub mod prelude {
     pub use crate::{
         function::Async,
         promise::{Promise, Promised},
+        stream::{AsyncIterStream, Streamed},
     };
 }
 
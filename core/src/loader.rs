@@ -4,24 +4,53 @@ use std::{ffi::CStr, ptr};
 
 use crate::{module::Declared, qjs, Ctx, Module, Result};
 
+#[cfg(feature = "futures")]
+mod async_loader;
 mod builtin_loader;
 mod builtin_resolver;
 pub mod bundle;
+#[cfg(feature = "loader-cache")]
+mod caching_loader;
 mod compile;
+#[cfg(feature = "crypto")]
+mod crypto;
 mod file_resolver;
+mod host_modules;
+mod import_map;
 mod module_loader;
+mod node_resolver;
+mod resource_loader;
 mod script_loader;
+mod transformer;
 mod util;
+mod vfs;
+mod wasm_loader;
 
 #[cfg(feature = "dyn-load")]
 mod native_loader;
 
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+pub use async_loader::{AsyncLoader, AsyncModuleLoader, LoadFuture};
 pub use builtin_loader::BuiltinLoader;
 pub use builtin_resolver::BuiltinResolver;
+#[cfg(feature = "loader-cache")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader-cache")))]
+pub use caching_loader::{Cache, CacheKey, CachingLoader, DirCache, MemoryCache};
 pub use compile::Compile;
+#[cfg(feature = "crypto")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "crypto")))]
+pub use crypto::define_web_crypto;
 pub use file_resolver::FileResolver;
+pub use host_modules::HostModules;
+pub use import_map::ImportMap;
 pub use module_loader::ModuleLoader;
+pub use node_resolver::NodeResolver;
+pub use resource_loader::{MediaType, ResourceLoader};
 pub use script_loader::ScriptLoader;
+pub use transformer::{SourceMaps, Transformed, Transformer};
+pub use vfs::{MemoryVfs, Metadata, OverlayVfs, PhysicalVfs, Vfs};
+pub use wasm_loader::{WasmInstantiator, WasmLoader};
 
 #[cfg(feature = "dyn-load")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "dyn-load")))]
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use async_lock::Mutex as AsyncMutex;
+
+use crate::{
+    loader::{Loader, Resolver},
+    markers::ParallelSend,
+    util::lexer::import_specifiers,
+    AsyncContext, AsyncRuntime, Ctx, Error, Module, Mut, Ref, Result,
+};
+
+/// The future returned by [`AsyncLoader::load`].
+#[cfg(not(feature = "parallel"))]
+pub type LoadFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>>> + 'a>>;
+/// The future returned by [`AsyncLoader::load`].
+#[cfg(feature = "parallel")]
+pub type LoadFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>>;
+
+/// Asynchronous module source loader interface
+///
+/// Unlike [`Loader`] this only returns the source of the module, which allows the source to be
+/// fetched, for example from the network or a database, without holding the runtime lock.
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "loader", feature = "futures")))
+)]
+pub trait AsyncLoader {
+    /// Load the source of a module by its resolved name
+    fn load<'a>(&'a mut self, name: &'a str) -> LoadFuture<'a>;
+}
+
+struct Shared<R, L> {
+    resolver: Mut<R>,
+    loader: AsyncMutex<L>,
+    sources: Mut<HashMap<String, Vec<u8>>>,
+}
+
+/// A module loader which fetches module sources asynchronously
+///
+/// QuickJS loads modules synchronously, so the sources of a module and everything it imports
+/// are fetched with [`AsyncModuleLoader::preload`] before the module is imported. The static
+/// imports and exports of every loaded module, as well as dynamic imports with a string literal
+/// specifier, are followed. Importing a module which was not preloaded fails with a loading
+/// error.
+///
+/// ```
+/// # use rquickjs::{async_with, loader::{AsyncLoader, AsyncModuleLoader, BuiltinResolver, LoadFuture}, AsyncContext, AsyncRuntime, Module, Result};
+/// struct Fetch;
+///
+/// impl AsyncLoader for Fetch {
+///     fn load<'a>(&'a mut self, name: &'a str) -> LoadFuture<'a> {
+///         Box::pin(async move {
+///             // fetch the source from somewhere
+///             Ok(format!("export const name = '{name}';").into_bytes())
+///         })
+///     }
+/// }
+///
+/// # async fn run(){
+/// let rt = AsyncRuntime::new().unwrap();
+/// let ctx = AsyncContext::full(&rt).await.unwrap();
+/// let loader = AsyncModuleLoader::new(BuiltinResolver::default().with_module("remote"), Fetch);
+/// loader.install(&rt).await;
+///
+/// let name = loader.preload(&ctx, "remote").await.unwrap();
+/// async_with!(ctx => |ctx| {
+///     let promise = Module::import(&ctx, name).unwrap();
+///     promise.into_future::<()>().await.unwrap();
+/// }).await;
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "loader", feature = "futures")))
+)]
+pub struct AsyncModuleLoader<R, L> {
+    shared: Ref<Shared<R, L>>,
+}
+
+impl<R, L> Clone for AsyncModuleLoader<R, L> {
+    fn clone(&self) -> Self {
+        AsyncModuleLoader {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<R, L> AsyncModuleLoader<R, L>
+where
+    R: Resolver + ParallelSend + 'static,
+    L: AsyncLoader + ParallelSend + 'static,
+{
+    /// Create a new loader from a resolver and an asynchronous loader
+    pub fn new(resolver: R, loader: L) -> Self {
+        AsyncModuleLoader {
+            shared: Ref::new(Shared {
+                resolver: Mut::new(resolver),
+                loader: AsyncMutex::new(loader),
+                sources: Mut::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Set this loader as the module loader of the runtime
+    pub async fn install(&self, runtime: &AsyncRuntime) {
+        runtime
+            .set_loader(
+                PreloadedResolver(self.shared.clone()),
+                PreloadedLoader(self.shared.clone()),
+            )
+            .await;
+    }
+
+    /// Returns whether the source of a module with the given resolved name was loaded.
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.shared.sources.lock().contains_key(name)
+    }
+
+    /// Fetch the sources of a module and of the modules it imports
+    ///
+    /// Returns the resolved name of the module, which can be passed to
+    /// [`Module::import`](crate::Module::import).
+    pub async fn preload(&self, ctx: &AsyncContext, name: &str) -> Result<String> {
+        let mut root = None;
+        let mut queue = vec![(String::new(), name.to_string())];
+        while let Some((base, name)) = queue.pop() {
+            let shared = self.shared.clone();
+            let resolved = ctx
+                .with(move |ctx| shared.resolver.lock().resolve(&ctx, &base, &name))
+                .await?;
+            if root.is_none() {
+                root = Some(resolved.clone());
+            }
+            if self.is_loaded(&resolved) {
+                continue;
+            }
+
+            let source = self.shared.loader.lock().await.load(&resolved).await?;
+            if let Ok(text) = std::str::from_utf8(&source) {
+                // Reverse so modules are fetched in the order they are imported.
+                for specifier in import_specifiers(text).into_iter().rev() {
+                    queue.push((resolved.clone(), specifier));
+                }
+            }
+            self.shared.sources.lock().insert(resolved, source);
+        }
+        Ok(root.expect("at least one module was resolved"))
+    }
+}
+
+struct PreloadedResolver<R, L>(Ref<Shared<R, L>>);
+
+impl<R: Resolver, L> Resolver for PreloadedResolver<R, L> {
+    fn resolve<'js>(&mut self, ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        self.0.resolver.lock().resolve(ctx, base, name)
+    }
+}
+
+struct PreloadedLoader<R, L>(Ref<Shared<R, L>>);
+
+impl<R, L> Loader for PreloadedLoader<R, L> {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js>> {
+        let source =
+            self.0.sources.lock().get(name).cloned().ok_or_else(|| {
+                Error::new_loading_message(name, "module source was not preloaded")
+            })?;
+        Module::declare(ctx.clone(), name, source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{async_with, loader::BuiltinResolver};
+
+    struct TestLoader(Vec<String>);
+
+    impl AsyncLoader for TestLoader {
+        fn load<'a>(&'a mut self, name: &'a str) -> LoadFuture<'a> {
+            Box::pin(async move {
+                self.0.push(name.to_string());
+                let source = match name {
+                    "main" => "import { b } from 'b'; export const a = b + 1;",
+                    "b" => "export const b = 1; export const lazy = () => import('c');",
+                    "c" => "export default 3;",
+                    _ => return Err(Error::new_loading(name)),
+                };
+                Ok(source.as_bytes().to_vec())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn preload_and_import() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        let resolver = BuiltinResolver::default()
+            .with_module("main")
+            .with_module("b")
+            .with_module("c");
+        let loader = AsyncModuleLoader::new(resolver, TestLoader(Vec::new()));
+        loader.install(&rt).await;
+
+        let name = loader.preload(&ctx, "main").await.unwrap();
+        assert_eq!(name, "main");
+        assert!(loader.is_loaded("c"));
+        assert_eq!(
+            loader.shared.loader.lock().await.0,
+            ["main", "b", "c"].map(String::from)
+        );
+
+        async_with!(ctx => |ctx| {
+            let promise = Module::import(&ctx, "main").unwrap();
+            promise.into_future::<()>().await.unwrap();
+        })
+        .await;
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write as _},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    loader::{Loader, ScriptLoader},
+    module::Declared,
+    qjs, Ctx, Module, Result,
+};
+
+/// The key of a module in a [`Cache`], a SHA-256 hash.
+pub type CacheKey = [u8; 32];
+
+/// A storage for compiled module bytecode
+///
+/// # Safety
+/// QuickJS trusts the bytecode it loads, so [`Cache::get`] must only return bytecode which was
+/// previously passed to [`Cache::set`] with the same key. Returning anything else can result in
+/// undefined behavior.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader-cache")))]
+pub unsafe trait Cache {
+    /// Get the bytecode stored with the key, if any
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Vec<u8>>>;
+
+    /// Store the bytecode with the key
+    fn set(&mut self, key: &CacheKey, bytecode: &[u8]) -> Result<()>;
+}
+
+/// A cache which keeps bytecode in memory
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader-cache")))]
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: HashMap<CacheKey, Vec<u8>>,
+}
+
+impl MemoryCache {
+    /// Create a new empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached modules
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+unsafe impl Cache for MemoryCache {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Vec<u8>>> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn set(&mut self, key: &CacheKey, bytecode: &[u8]) -> Result<()> {
+        self.entries.insert(*key, bytecode.to_vec());
+        Ok(())
+    }
+}
+
+/// A cache which stores bytecode as files in a directory
+///
+/// Every module is stored in a file named after its key, so the cache persists across runtime
+/// restarts.
+///
+/// Bytecode is only compatible with the QuickJS library which wrote it. When linking a prebuilt
+/// library which can't be identified, see [`QUICKJS_VERSION`](crate::qjs::QUICKJS_VERSION), the
+/// cache is disabled: nothing is stored and every lookup misses.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader-cache")))]
+#[derive(Debug)]
+pub struct DirCache {
+    path: PathBuf,
+}
+
+impl DirCache {
+    /// Create a cache storing bytecode in the directory at `path`
+    ///
+    /// The directory is created when the first module is stored.
+    ///
+    /// # Safety
+    /// The files in the directory are loaded as bytecode without any validation, so the directory
+    /// must only be written by a `DirCache`.
+    pub unsafe fn new<P: Into<PathBuf>>(path: P) -> Self {
+        DirCache { path: path.into() }
+    }
+
+    /// Returns whether the cache stores bytecode, which requires an identified QuickJS library
+    pub fn is_enabled() -> bool {
+        qjs::QUICKJS_VERSION != "prebuilt"
+    }
+
+    fn file(&self, key: &CacheKey) -> PathBuf {
+        let mut name = String::with_capacity(key.len() * 2 + 5);
+        for byte in key {
+            write!(name, "{byte:02x}").unwrap();
+        }
+        name.push_str(".qjsc");
+        self.path.join(name)
+    }
+}
+
+unsafe impl Cache for DirCache {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Vec<u8>>> {
+        if !Self::is_enabled() {
+            return Ok(None);
+        }
+        match fs::read(self.file(key)) {
+            Ok(bytecode) => Ok(Some(bytecode)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&mut self, key: &CacheKey, bytecode: &[u8]) -> Result<()> {
+        if !Self::is_enabled() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.path)?;
+        // Write to a temporary file first so a concurrent reader never sees a partial file.
+        // The name of the temporary file is unique within the process and the process id makes it
+        // unique between processes.
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let file = self.file(key);
+        let tmp = file.with_extension(format!(
+            "tmp{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let res = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+            .and_then(|mut x| x.write_all(bytecode))
+            .and_then(|_| fs::rename(&tmp, &file));
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        Ok(res?)
+    }
+}
+
+/// The caching script module loader
+///
+/// This loader wraps a [`ScriptLoader`] and stores the bytecode of compiled modules in a
+/// [`Cache`], keyed by a hash of the module name and source. When a module with the same name and
+/// source is loaded again its bytecode is loaded from the cache instead of compiling the source.
+/// The key is computed from the source after it is transformed, so changing a transformer doesn't
+/// load stale bytecode and source maps are recorded for cached modules as well.
+///
+/// ```no_run
+/// # use rquickjs::{loader::{CachingLoader, DirCache, FileResolver, ScriptLoader}, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// // Safety: the directory is only used to cache bytecode
+/// let cache = unsafe { DirCache::new("target/module-cache") };
+/// rt.set_loader(
+///     FileResolver::default(),
+///     CachingLoader::new(ScriptLoader::default(), cache),
+/// );
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader-cache")))]
+#[derive(Debug)]
+pub struct CachingLoader<C> {
+    loader: ScriptLoader,
+    cache: C,
+}
+
+impl<C: Cache> CachingLoader<C> {
+    /// Create a new caching loader
+    pub fn new(loader: ScriptLoader, cache: C) -> Self {
+        CachingLoader { loader, cache }
+    }
+
+    /// Returns a reference to the cache
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Returns a mutable reference to the cache
+    pub fn cache_mut(&mut self) -> &mut C {
+        &mut self.cache
+    }
+
+    /// Returns the key under which the bytecode of a module is cached
+    ///
+    /// The key is a SHA-256 hash of the module name and source, the version of rquickjs and the
+    /// version of QuickJS, as bytecode is not compatible between versions. A prebuilt library is
+    /// identified by a hash of its contents.
+    pub fn cache_key(name: &str, source: &[u8]) -> CacheKey {
+        let mut hasher = Sha256::new();
+        for part in [
+            env!("CARGO_PKG_VERSION").as_bytes(),
+            qjs::QUICKJS_VERSION.as_bytes(),
+            name.as_bytes(),
+            source,
+        ] {
+            // Prefix every part with its length so different parts can't produce the same input.
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+impl<C: Cache> Loader for CachingLoader<C> {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, path: &str) -> Result<Module<'js, Declared>> {
+        let source = self.loader.read_source(path)?;
+        let source = self.loader.transform(path, source)?;
+        let key = Self::cache_key(path, &source);
+
+        if let Some(bytecode) = self.cache.get(&key)? {
+            // Safety: the cache only returns bytecode which it was given by this loader
+            return unsafe { Module::load_copied(ctx.clone(), &bytecode) };
+        }
+
+        let module = Module::declare(ctx.clone(), path, source)?;
+        self.cache.set(&key, &module.write(false)?)?;
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        loader::{BuiltinResolver, Transformed},
+        Context, Runtime,
+    };
+
+    #[test]
+    fn cache_key() {
+        let key = CachingLoader::<MemoryCache>::cache_key("a.js", b"export default 1");
+        assert_eq!(
+            key,
+            CachingLoader::<MemoryCache>::cache_key("a.js", b"export default 1")
+        );
+        assert_ne!(
+            key,
+            CachingLoader::<MemoryCache>::cache_key("a.js", b"export default 2")
+        );
+        assert_ne!(
+            key,
+            CachingLoader::<MemoryCache>::cache_key("b.js", b"export default 1")
+        );
+    }
+
+    #[test]
+    fn dir_cache() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-dir-cache-{}", std::process::id()));
+        let mut cache = unsafe { DirCache::new(&dir) };
+        let key = [1; 32];
+        if !DirCache::is_enabled() {
+            // An unidentified library never reads or writes bytecode.
+            cache.set(&key, b"bytecode").unwrap();
+            assert_eq!(cache.get(&key).unwrap(), None);
+            assert!(!dir.exists());
+            return;
+        }
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.set(&key, b"bytecode").unwrap();
+        cache.set(&key, b"bytecode").unwrap();
+        assert_eq!(cache.get(&key).unwrap().as_deref(), Some(&b"bytecode"[..]));
+        // No temporary files are left behind.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_from_cache() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-caching-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cached.js").to_string_lossy().into_owned();
+        fs::write(&path, "export const value = 42;").unwrap();
+
+        let mut loader = CachingLoader::new(ScriptLoader::default(), MemoryCache::new());
+        for _ in 0..2 {
+            let rt = Runtime::new().unwrap();
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                let module = loader.load(&ctx, &path).unwrap();
+                let (module, promise) = module.eval().unwrap();
+                promise.finish::<()>().unwrap();
+                assert_eq!(module.get::<_, i32>("value").unwrap(), 42);
+            });
+            assert_eq!(loader.cache().len(), 1);
+        }
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_loader(
+            BuiltinResolver::default().with_module(path.as_str()),
+            loader,
+        );
+        ctx.with(|ctx| {
+            let promise = Module::import(&ctx, path.as_str()).unwrap();
+            promise.finish::<()>().unwrap();
+        });
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn transformer_changes() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-transformed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("value.mjs").to_string_lossy().into_owned();
+        fs::write(&path, "export const value = VALUE;").unwrap();
+
+        let mut cache = MemoryCache::new();
+        for value in [1, 2] {
+            let transformer = move |_name: &str, source: Vec<u8>| {
+                let source = String::from_utf8(source).unwrap();
+                Ok(Transformed::new(
+                    source.replace("VALUE", &value.to_string()),
+                ))
+            };
+            let mut loader = CachingLoader::new(
+                ScriptLoader::default().with_transformer("mjs", transformer),
+                cache,
+            );
+            let rt = Runtime::new().unwrap();
+            let ctx = Context::full(&rt).unwrap();
+            ctx.with(|ctx| {
+                let module = loader.load(&ctx, &path).unwrap();
+                let (module, promise) = module.eval().unwrap();
+                promise.finish::<()>().unwrap();
+                assert_eq!(module.get::<_, i32>("value").unwrap(), value);
+            });
+            cache = std::mem::take(loader.cache_mut());
+        }
+        assert_eq!(cache.len(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
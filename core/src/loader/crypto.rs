@@ -0,0 +1,291 @@
+use std::slice;
+
+use hmac::{Hmac, Mac};
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+use crate::{ArrayBuffer, Ctx, Exception, Function, Object, Result, TypedArray, Value};
+
+/// The largest number of bytes `getRandomValues` fills at once, as in the Web Crypto API.
+const MAX_RANDOM_VALUES: usize = 65536;
+
+/// Run `$body` with `$hash` aliased to the hash function named by `$alg`.
+macro_rules! with_hash {
+    ($ctx:expr, $alg:expr, |$hash:ident| $body:expr) => {
+        match $alg.to_ascii_uppercase().as_str() {
+            "SHA-224" => {
+                type $hash = sha2::Sha224;
+                $body
+            }
+            "SHA-256" => {
+                type $hash = sha2::Sha256;
+                $body
+            }
+            "SHA-384" => {
+                type $hash = sha2::Sha384;
+                $body
+            }
+            "SHA-512" => {
+                type $hash = sha2::Sha512;
+                $body
+            }
+            "SHA3-224" => {
+                type $hash = sha3::Sha3_224;
+                $body
+            }
+            "SHA3-256" => {
+                type $hash = sha3::Sha3_256;
+                $body
+            }
+            "SHA3-384" => {
+                type $hash = sha3::Sha3_384;
+                $body
+            }
+            "SHA3-512" => {
+                type $hash = sha3::Sha3_512;
+                $body
+            }
+            _ => {
+                return Err(Exception::throw_type(
+                    $ctx,
+                    &format!("Unsupported hash algorithm '{}'", $alg),
+                ))
+            }
+        }
+    };
+}
+
+mod subtle_crypto;
+
+/// Copy the bytes of a string, `ArrayBuffer` or typed array.
+fn bytes<'js>(ctx: &Ctx<'js>, value: &Value<'js>) -> Result<Vec<u8>> {
+    if let Some(string) = value.as_string() {
+        return Ok(string.to_string()?.into_bytes());
+    }
+    if let Some(object) = value.as_object() {
+        if let Some(buffer) = object.as_array_buffer() {
+            return buffer
+                .as_bytes()
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| Exception::throw_type(ctx, "ArrayBuffer is detached"));
+        }
+        if let Some((_, len, ptr)) = TypedArray::<u8>::get_raw_bytes(value) {
+            return Ok(unsafe { slice::from_raw_parts(ptr.as_ptr(), len) }.to_vec());
+        }
+        // `get_raw_bytes` leaves an exception behind for other objects
+        ctx.catch();
+    }
+    Err(Exception::throw_type(
+        ctx,
+        "Expected a string, ArrayBuffer or typed array",
+    ))
+}
+
+fn hmac<'js>(ctx: &Ctx<'js>, alg: &str, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    Ok(with_hash!(ctx, alg, |H| {
+        let mut mac = <Hmac<H> as Mac>::new_from_slice(key)
+            .map_err(|_| Exception::throw_range(ctx, "Invalid HMAC key length"))?;
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }))
+}
+
+fn fill_random(ctx: &Ctx<'_>, buf: &mut [u8]) -> Result<()> {
+    getrandom::getrandom(buf).map_err(|error| {
+        Exception::throw_internal(ctx, &format!("Unable to get random bytes: {error}"))
+    })
+}
+
+fn is_integer_array(object: &Object<'_>) -> bool {
+    object.is_typed_array::<i8>()
+        || object.is_typed_array::<u8>()
+        || object.is_typed_array::<i16>()
+        || object.is_typed_array::<u16>()
+        || object.is_typed_array::<i32>()
+        || object.is_typed_array::<u32>()
+        || object.is_typed_array::<i64>()
+        || object.is_typed_array::<u64>()
+}
+
+fn get_random_values<'js>(ctx: Ctx<'js>, array: Value<'js>) -> Result<Value<'js>> {
+    if !array.as_object().map_or(false, is_integer_array) {
+        return Err(Exception::throw_type(
+            &ctx,
+            "Expected an integer typed array",
+        ));
+    }
+    let Some((_, len, ptr)) = TypedArray::<u8>::get_raw_bytes(&array) else {
+        ctx.catch();
+        return Err(Exception::throw_type(&ctx, "Typed array is detached"));
+    };
+    if len > MAX_RANDOM_VALUES {
+        return Err(Exception::throw_range(
+            &ctx,
+            &format!("Typed array of {len} bytes exceeds {MAX_RANDOM_VALUES} bytes"),
+        ));
+    }
+    fill_random(&ctx, unsafe {
+        slice::from_raw_parts_mut(ptr.as_ptr(), len)
+    })?;
+    Ok(array)
+}
+
+/// The exports of the `host:crypto` module.
+pub(crate) fn crypto_module<'js>(ctx: &Ctx<'js>) -> Result<Object<'js>> {
+    let exports = Object::new(ctx.clone())?;
+    exports.set(
+        "digest",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, alg: String, data: Value<'js>| -> Result<ArrayBuffer<'js>> {
+                let data = bytes(&ctx, &data)?;
+                let hash = with_hash!(&ctx, alg, |H| H::digest(&data).to_vec());
+                ArrayBuffer::new(ctx, hash)
+            },
+        )?,
+    )?;
+    exports.set(
+        "hmac",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>,
+             alg: String,
+             key: Value<'js>,
+             data: Value<'js>|
+             -> Result<ArrayBuffer<'js>> {
+                let key = bytes(&ctx, &key)?;
+                let data = bytes(&ctx, &data)?;
+                let mac = hmac(&ctx, &alg, &key, &data)?;
+                ArrayBuffer::new(ctx, mac)
+            },
+        )?,
+    )?;
+    exports.set(
+        "timingSafeEqual",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, a: Value<'js>, b: Value<'js>| -> Result<bool> {
+                let (a, b) = (bytes(&ctx, &a)?, bytes(&ctx, &b)?);
+                Ok(a.len() == b.len() && bool::from(a.as_slice().ct_eq(b.as_slice())))
+            },
+        )?,
+    )?;
+    exports.set(
+        "randomBytes",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, len: usize| -> Result<ArrayBuffer<'js>> {
+                let mut buf = vec![0; len];
+                fill_random(&ctx, &mut buf)?;
+                ArrayBuffer::new(ctx, buf)
+            },
+        )?,
+    )?;
+    exports.set(
+        "getRandomValues",
+        Function::new(ctx.clone(), get_random_values)?,
+    )?;
+    exports.set("subtle", subtle_crypto::subtle(ctx)?)?;
+    Ok(exports)
+}
+
+/// Define `crypto.getRandomValues` and `crypto.subtle` on the global object for compatibility
+/// with the web
+///
+/// The random values come from the CSPRNG of the operating system, like the
+/// `getRandomValues` export of the `host:crypto` module of [`HostModules`](super::HostModules).
+/// `crypto.subtle` implements a subset of the Web Crypto API returning promises:
+///
+/// - `digest` with the SHA-2 and SHA-3 families.
+/// - `generateKey`, `importKey` and `exportKey` in the `raw` format for `HMAC` and `AES-GCM`
+///   keys and `ECDSA` keys on the `P-256` curve. Private ECDSA keys can only be generated.
+/// - `sign` and `verify` with `HMAC` and `ECDSA` using `SHA-256`.
+/// - `encrypt` and `decrypt` with `AES-GCM` using 128 or 256 bit keys, 96 bit IVs and 128 bit
+///   tags.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "crypto")))]
+pub fn define_web_crypto(ctx: &Ctx<'_>) -> Result<()> {
+    let globals = ctx.globals();
+    let crypto = match globals.get::<_, Option<Object>>("crypto")? {
+        Some(crypto) => crypto,
+        None => {
+            let crypto = Object::new(ctx.clone())?;
+            globals.set("crypto", crypto.clone())?;
+            crypto
+        }
+    };
+    crypto.set(
+        "getRandomValues",
+        Function::new(ctx.clone(), get_random_values)?,
+    )?;
+    crypto.set("subtle", subtle_crypto::subtle(ctx)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::{BuiltinLoader, BuiltinResolver, HostModules},
+        Context, Module, Runtime,
+    };
+
+    #[test]
+    fn host_crypto() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let host = HostModules::default().with_crypto();
+        rt.set_loader(
+            (host.clone(), BuiltinResolver::default().with_module("main")),
+            (host, BuiltinLoader::default()),
+        );
+        ctx.with(|ctx| {
+            let source = r#"
+                import { digest, hmac, timingSafeEqual, randomBytes } from "host:crypto";
+                const hex = (buf) => [...new Uint8Array(buf)]
+                    .map((b) => b.toString(16).padStart(2, "0")).join("");
+                globalThis.res = [
+                    hex(digest("SHA-256", "abc")),
+                    hex(digest("sha3-256", new Uint8Array([97, 98, 99]).buffer)),
+                    hex(hmac("SHA-256", "key", "The quick brown fox jumps over the lazy dog")),
+                    timingSafeEqual("abc", new Uint8Array([97, 98, 99])),
+                    timingSafeEqual("abc", "abd"),
+                    randomBytes(16).byteLength,
+                ].join(" ");
+                try { digest("MD5", "abc") } catch (e) { globalThis.unsupported = e.message }
+            "#;
+            Module::evaluate(ctx.clone(), "main", source)
+                .unwrap()
+                .finish::<()>()
+                .unwrap();
+            let res: String = ctx.globals().get("res").unwrap();
+            assert_eq!(
+                res,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad \
+                 3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532 \
+                 f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8 \
+                 true false 16"
+            );
+            let unsupported: String = ctx.globals().get("unsupported").unwrap();
+            assert!(unsupported.contains("MD5"), "{unsupported}");
+        });
+    }
+
+    #[test]
+    fn web_crypto() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            super::define_web_crypto(&ctx).unwrap();
+            let filled: bool = ctx
+                .eval("crypto.getRandomValues(new Uint32Array(64)).some((x) => x != 0)")
+                .unwrap();
+            assert!(filled);
+            let errors: Vec<String> = ctx
+                .eval(
+                    r#"[new Float64Array(1), new Uint8Array(65537)].map((a) => {
+                        try { crypto.getRandomValues(a) } catch (e) { return e.name }
+                    })"#,
+                )
+                .unwrap();
+            assert_eq!(errors, ["TypeError", "RangeError"]);
+        });
+    }
+}
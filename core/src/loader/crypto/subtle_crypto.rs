@@ -0,0 +1,668 @@
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes128Gcm, Aes256Gcm, KeyInit, Nonce,
+};
+use p256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+use super::{bytes, fill_random, hmac};
+use crate::{
+    class::{ClassId, JsClass, Readable, Trace, Tracer},
+    value::Constructor,
+    ArrayBuffer, CatchResultExt, CaughtError, Class, Ctx, Error, Exception, FromJs, Function,
+    IntoJs, Object, Promise, Result, Value,
+};
+
+#[derive(Clone)]
+enum KeyData {
+    Hmac { hash: String, key: Vec<u8> },
+    AesGcm(Vec<u8>),
+    EcdsaPrivate(SigningKey),
+    EcdsaPublic(VerifyingKey),
+}
+
+/// The `CryptoKey` objects of `crypto.subtle` holding the key material out of reach of scripts.
+pub(crate) struct CryptoKey {
+    data: KeyData,
+    extractable: bool,
+    usages: Vec<String>,
+}
+
+impl<'js> Trace<'js> for CryptoKey {
+    fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+}
+
+impl<'js> JsClass<'js> for CryptoKey {
+    const NAME: &'static str = "CryptoKey";
+
+    type Mutable = Readable;
+
+    fn class_id() -> &'static ClassId {
+        static ID: ClassId = ClassId::new();
+        &ID
+    }
+
+    fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+        Ok(Some(Object::new(ctx.clone())?))
+    }
+
+    fn constructor(_ctx: &Ctx<'js>) -> Result<Option<Constructor<'js>>> {
+        Ok(None)
+    }
+}
+
+impl CryptoKey {
+    fn new(ctx: &Ctx<'_>, data: KeyData, extractable: bool, usages: Vec<String>) -> Result<Self> {
+        let allowed: &[&str] = match data {
+            KeyData::Hmac { .. } => &["sign", "verify"],
+            KeyData::AesGcm(_) => &["encrypt", "decrypt"],
+            KeyData::EcdsaPrivate(_) => &["sign"],
+            KeyData::EcdsaPublic(_) => &["verify"],
+        };
+        if let Some(usage) = usages
+            .iter()
+            .find(|usage| !allowed.contains(&usage.as_str()))
+        {
+            return Err(dom_error(
+                ctx,
+                "SyntaxError",
+                &format!("Usage '{usage}' isn't allowed for this key"),
+            ));
+        }
+        if usages.is_empty() && !matches!(data, KeyData::EcdsaPublic(_)) {
+            return Err(dom_error(ctx, "SyntaxError", "Key usages can't be empty"));
+        }
+        Ok(Self {
+            data,
+            extractable,
+            usages,
+        })
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        match self.data {
+            KeyData::Hmac { .. } => "HMAC",
+            KeyData::AesGcm(_) => "AES-GCM",
+            KeyData::EcdsaPrivate(_) | KeyData::EcdsaPublic(_) => "ECDSA",
+        }
+    }
+
+    fn algorithm<'js>(&self, ctx: &Ctx<'js>) -> Result<Object<'js>> {
+        let algorithm = Object::new(ctx.clone())?;
+        algorithm.set("name", self.algorithm_name())?;
+        match &self.data {
+            KeyData::Hmac { hash, key } => {
+                let hash_algorithm = Object::new(ctx.clone())?;
+                hash_algorithm.set("name", hash.as_str())?;
+                algorithm.set("hash", hash_algorithm)?;
+                algorithm.set("length", key.len() * 8)?;
+            }
+            KeyData::AesGcm(key) => algorithm.set("length", key.len() * 8)?,
+            KeyData::EcdsaPrivate(_) | KeyData::EcdsaPublic(_) => {
+                algorithm.set("namedCurve", "P-256")?
+            }
+        }
+        Ok(algorithm)
+    }
+
+    /// Fail unless the key is for `algorithm` and allowed to be used for `usage`.
+    fn check(&self, ctx: &Ctx<'_>, algorithm: &str, usage: &str) -> Result<()> {
+        if self.algorithm_name() != algorithm {
+            return Err(dom_error(
+                ctx,
+                "InvalidAccessError",
+                &format!("Key is for {} not {algorithm}", self.algorithm_name()),
+            ));
+        }
+        if !self.usages.iter().any(|x| x == usage) {
+            return Err(dom_error(
+                ctx,
+                "InvalidAccessError",
+                &format!("Key can't be used to {usage}"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn into_object<'js>(self, ctx: &Ctx<'js>) -> Result<Object<'js>> {
+        let kind = match self.data {
+            KeyData::Hmac { .. } | KeyData::AesGcm(_) => "secret",
+            KeyData::EcdsaPrivate(_) => "private",
+            KeyData::EcdsaPublic(_) => "public",
+        };
+        let algorithm = self.algorithm(ctx)?;
+        let extractable = self.extractable;
+        let usages = self.usages.clone();
+        let key = Class::instance(ctx.clone(), self)?.into_inner();
+        key.set("type", kind)?;
+        key.set("extractable", extractable)?;
+        key.set("algorithm", algorithm)?;
+        key.set("usages", usages)?;
+        Ok(key)
+    }
+}
+
+/// Throw an error with the name of a `DOMException`, which QuickJS doesn't have.
+fn dom_error(ctx: &Ctx<'_>, name: &str, message: &str) -> Error {
+    let error = Exception::from_message(ctx.clone(), message).and_then(|error| {
+        error.as_object().set("name", name)?;
+        Ok(error)
+    });
+    match error {
+        Ok(error) => ctx.throw(error.into_value()),
+        Err(error) => error,
+    }
+}
+
+fn not_supported(ctx: &Ctx<'_>, message: &str) -> Error {
+    dom_error(ctx, "NotSupportedError", message)
+}
+
+/// Settle a new promise with the result of an operation.
+fn promised<'js, T: IntoJs<'js>>(ctx: &Ctx<'js>, result: Result<T>) -> Result<Promise<'js>> {
+    let (promise, resolve, reject) = Promise::new(ctx)?;
+    match result.and_then(|value| value.into_js(ctx)).catch(ctx) {
+        Ok(value) => resolve.call::<_, ()>((value,))?,
+        Err(CaughtError::Exception(error)) => reject.call::<_, ()>((error,))?,
+        Err(CaughtError::Value(error)) => reject.call::<_, ()>((error,))?,
+        Err(CaughtError::Error(error)) => {
+            error.throw(ctx);
+            reject.call::<_, ()>((ctx.catch(),))?
+        }
+    }
+    Ok(promise)
+}
+
+/// The upper case name and the parameters of an algorithm given as name or object.
+fn algorithm<'js>(ctx: &Ctx<'js>, value: &Value<'js>) -> Result<(String, Option<Object<'js>>)> {
+    if let Some(name) = value.as_string() {
+        return Ok((name.to_string()?.to_ascii_uppercase(), None));
+    }
+    if let Some(params) = value.as_object() {
+        let name: String = params.get("name")?;
+        return Ok((name.to_ascii_uppercase(), Some(params.clone())));
+    }
+    Err(Exception::throw_type(
+        ctx,
+        "Expected an algorithm name or object",
+    ))
+}
+
+fn param<'js, T: FromJs<'js>>(
+    ctx: &Ctx<'js>,
+    params: &Option<Object<'js>>,
+    name: &str,
+) -> Result<Option<T>> {
+    match params {
+        Some(params) => params.get(name),
+        None => Ok(None),
+    }
+    .map_err(|_| Exception::throw_type(ctx, &format!("Invalid algorithm parameter '{name}'")))
+}
+
+fn required<'js, T: FromJs<'js>>(
+    ctx: &Ctx<'js>,
+    params: &Option<Object<'js>>,
+    name: &str,
+) -> Result<T> {
+    param(ctx, params, name)?
+        .ok_or_else(|| Exception::throw_type(ctx, &format!("Missing algorithm parameter '{name}'")))
+}
+
+/// The name of the `hash` parameter and the block size of the hash function.
+fn hash<'js>(ctx: &Ctx<'js>, params: &Option<Object<'js>>) -> Result<(String, usize)> {
+    let (name, _) = algorithm(ctx, &required(ctx, params, "hash")?)?;
+    let block_size = match name.as_str() {
+        "SHA-224" | "SHA-256" => 64,
+        "SHA-384" | "SHA-512" => 128,
+        "SHA3-224" => 144,
+        "SHA3-256" => 136,
+        "SHA3-384" => 104,
+        "SHA3-512" => 72,
+        _ => return Err(not_supported(ctx, &format!("Unsupported hash '{name}'"))),
+    };
+    Ok((name, block_size))
+}
+
+fn check_ecdsa_hash<'js>(ctx: &Ctx<'js>, params: &Option<Object<'js>>) -> Result<()> {
+    match hash(ctx, params)?.0.as_str() {
+        "SHA-256" => Ok(()),
+        name => Err(not_supported(
+            ctx,
+            &format!("ECDSA only supports SHA-256 not {name}"),
+        )),
+    }
+}
+
+fn check_curve<'js>(ctx: &Ctx<'js>, params: &Option<Object<'js>>) -> Result<()> {
+    let curve: String = required(ctx, params, "namedCurve")?;
+    if curve != "P-256" {
+        return Err(not_supported(ctx, &format!("Unsupported curve '{curve}'")));
+    }
+    Ok(())
+}
+
+fn check_aes_length(ctx: &Ctx<'_>, bytes: usize) -> Result<()> {
+    if bytes != 16 && bytes != 32 {
+        return Err(not_supported(
+            ctx,
+            &format!("Unsupported AES key length of {} bits", bytes * 8),
+        ));
+    }
+    Ok(())
+}
+
+fn random(ctx: &Ctx<'_>, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    fill_random(ctx, &mut buf)?;
+    Ok(buf)
+}
+
+fn generate_key<'js>(
+    ctx: &Ctx<'js>,
+    algorithm_value: Value<'js>,
+    extractable: bool,
+    usages: Vec<String>,
+) -> Result<Object<'js>> {
+    let (name, params) = algorithm(ctx, &algorithm_value)?;
+    let data = match name.as_str() {
+        "HMAC" => {
+            let (hash, block_size) = hash(ctx, &params)?;
+            let len = match param::<usize>(ctx, &params, "length")? {
+                Some(bits) if bits == 0 || bits % 8 != 0 => {
+                    return Err(not_supported(
+                        ctx,
+                        "HMAC key length must be a multiple of 8 bits",
+                    ))
+                }
+                Some(bits) => bits / 8,
+                None => block_size,
+            };
+            KeyData::Hmac {
+                hash,
+                key: random(ctx, len)?,
+            }
+        }
+        "AES-GCM" => {
+            let bits: usize = required(ctx, &params, "length")?;
+            check_aes_length(ctx, bits / 8)?;
+            KeyData::AesGcm(random(ctx, bits / 8)?)
+        }
+        "ECDSA" => {
+            check_curve(ctx, &params)?;
+            // Retry for the rare scalars out of the range of the curve order.
+            let private = loop {
+                if let Ok(key) = SigningKey::from_slice(&random(ctx, 32)?) {
+                    break key;
+                }
+            };
+            let public = private.verifying_key().clone();
+            let (private_usages, public_usages): (Vec<_>, Vec<_>) =
+                usages.into_iter().partition(|x| x == "sign");
+            let pair = Object::new(ctx.clone())?;
+            pair.set(
+                "privateKey",
+                CryptoKey::new(
+                    ctx,
+                    KeyData::EcdsaPrivate(private),
+                    extractable,
+                    private_usages,
+                )?
+                .into_object(ctx)?,
+            )?;
+            pair.set(
+                "publicKey",
+                CryptoKey::new(ctx, KeyData::EcdsaPublic(public), true, public_usages)?
+                    .into_object(ctx)?,
+            )?;
+            return Ok(pair);
+        }
+        _ => {
+            return Err(not_supported(
+                ctx,
+                &format!("Unsupported algorithm '{name}'"),
+            ))
+        }
+    };
+    CryptoKey::new(ctx, data, extractable, usages)?.into_object(ctx)
+}
+
+fn import_key<'js>(
+    ctx: &Ctx<'js>,
+    format: String,
+    key_data: Value<'js>,
+    algorithm_value: Value<'js>,
+    extractable: bool,
+    usages: Vec<String>,
+) -> Result<Object<'js>> {
+    if format != "raw" {
+        return Err(not_supported(
+            ctx,
+            &format!("Unsupported key format '{format}'"),
+        ));
+    }
+    let (name, params) = algorithm(ctx, &algorithm_value)?;
+    let key = bytes(ctx, &key_data)?;
+    let data = match name.as_str() {
+        "HMAC" => {
+            if key.is_empty() {
+                return Err(dom_error(ctx, "DataError", "HMAC key can't be empty"));
+            }
+            KeyData::Hmac {
+                hash: hash(ctx, &params)?.0,
+                key,
+            }
+        }
+        "AES-GCM" => {
+            check_aes_length(ctx, key.len())?;
+            KeyData::AesGcm(key)
+        }
+        "ECDSA" => {
+            check_curve(ctx, &params)?;
+            let key = VerifyingKey::from_sec1_bytes(&key)
+                .map_err(|_| dom_error(ctx, "DataError", "Invalid P-256 public key"))?;
+            KeyData::EcdsaPublic(key)
+        }
+        _ => {
+            return Err(not_supported(
+                ctx,
+                &format!("Unsupported algorithm '{name}'"),
+            ))
+        }
+    };
+    CryptoKey::new(ctx, data, extractable, usages)?.into_object(ctx)
+}
+
+fn export_key(ctx: &Ctx<'_>, format: String, key: &CryptoKey) -> Result<Vec<u8>> {
+    if format != "raw" {
+        return Err(not_supported(
+            ctx,
+            &format!("Unsupported key format '{format}'"),
+        ));
+    }
+    if !key.extractable {
+        return Err(dom_error(
+            ctx,
+            "InvalidAccessError",
+            "Key isn't extractable",
+        ));
+    }
+    match &key.data {
+        KeyData::Hmac { key, .. } | KeyData::AesGcm(key) => Ok(key.clone()),
+        KeyData::EcdsaPublic(key) => Ok(key.to_encoded_point(false).as_bytes().to_vec()),
+        KeyData::EcdsaPrivate(_) => Err(dom_error(
+            ctx,
+            "InvalidAccessError",
+            "Private keys can't be exported in the raw format",
+        )),
+    }
+}
+
+fn sign<'js>(
+    ctx: &Ctx<'js>,
+    algorithm_value: Value<'js>,
+    key: &CryptoKey,
+    data: Value<'js>,
+) -> Result<Vec<u8>> {
+    let (name, params) = algorithm(ctx, &algorithm_value)?;
+    key.check(ctx, &name, "sign")?;
+    let data = bytes(ctx, &data)?;
+    match &key.data {
+        KeyData::Hmac { hash, key } => hmac(ctx, hash, key, &data),
+        KeyData::EcdsaPrivate(key) => {
+            check_ecdsa_hash(ctx, &params)?;
+            let signature: Signature = key.sign(&data);
+            Ok(signature.to_bytes().to_vec())
+        }
+        _ => unreachable!("checked by the key usages"),
+    }
+}
+
+fn verify<'js>(
+    ctx: &Ctx<'js>,
+    algorithm_value: Value<'js>,
+    key: &CryptoKey,
+    signature: Value<'js>,
+    data: Value<'js>,
+) -> Result<bool> {
+    let (name, params) = algorithm(ctx, &algorithm_value)?;
+    key.check(ctx, &name, "verify")?;
+    let signature = bytes(ctx, &signature)?;
+    let data = bytes(ctx, &data)?;
+    match &key.data {
+        KeyData::Hmac { hash, key } => {
+            let expected = hmac(ctx, hash, key, &data)?;
+            Ok(expected.len() == signature.len() && bool::from(expected.ct_eq(&signature)))
+        }
+        KeyData::EcdsaPublic(key) => {
+            check_ecdsa_hash(ctx, &params)?;
+            Ok(Signature::from_slice(&signature)
+                .map_or(false, |signature| key.verify(&data, &signature).is_ok()))
+        }
+        _ => unreachable!("checked by the key usages"),
+    }
+}
+
+fn aes_gcm<C: KeyInit + Aead>(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    data: &[u8],
+    encrypt: bool,
+) -> Option<Vec<u8>> {
+    let cipher = C::new_from_slice(key).ok()?;
+    let nonce = Nonce::from_slice(iv);
+    let payload = Payload { msg: data, aad };
+    if encrypt {
+        cipher.encrypt(nonce, payload)
+    } else {
+        cipher.decrypt(nonce, payload)
+    }
+    .ok()
+}
+
+fn crypt<'js>(
+    ctx: &Ctx<'js>,
+    algorithm_value: Value<'js>,
+    key: &CryptoKey,
+    data: Value<'js>,
+    encrypt: bool,
+) -> Result<Vec<u8>> {
+    let (name, params) = algorithm(ctx, &algorithm_value)?;
+    key.check(ctx, &name, if encrypt { "encrypt" } else { "decrypt" })?;
+    let KeyData::AesGcm(key) = &key.data else {
+        unreachable!("checked by the key usages")
+    };
+    let iv = bytes(ctx, &required(ctx, &params, "iv")?)?;
+    if iv.len() != 12 {
+        return Err(not_supported(ctx, "AES-GCM only supports 96 bit IVs"));
+    }
+    if !matches!(param::<usize>(ctx, &params, "tagLength")?, None | Some(128)) {
+        return Err(not_supported(ctx, "AES-GCM only supports 128 bit tags"));
+    }
+    let aad = match param::<Value>(ctx, &params, "additionalData")? {
+        Some(aad) if !aad.is_undefined() => bytes(ctx, &aad)?,
+        _ => Vec::new(),
+    };
+    let data = bytes(ctx, &data)?;
+    let result = match key.len() {
+        16 => aes_gcm::<Aes128Gcm>(key, &iv, &aad, &data, encrypt),
+        _ => aes_gcm::<Aes256Gcm>(key, &iv, &aad, &data, encrypt),
+    };
+    result.ok_or_else(|| {
+        dom_error(
+            ctx,
+            "OperationError",
+            if encrypt {
+                "Encryption failed"
+            } else {
+                "Decryption failed"
+            },
+        )
+    })
+}
+
+fn buffer<'js>(ctx: &Ctx<'js>, result: Result<Vec<u8>>) -> Result<Promise<'js>> {
+    promised(
+        ctx,
+        result.and_then(|data| ArrayBuffer::new(ctx.clone(), data)),
+    )
+}
+
+/// The `crypto.subtle` object.
+pub(super) fn subtle<'js>(ctx: &Ctx<'js>) -> Result<Object<'js>> {
+    let subtle = Object::new(ctx.clone())?;
+    subtle.set(
+        "digest",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, algorithm_value: Value<'js>, data: Value<'js>| {
+                let result = algorithm(&ctx, &algorithm_value).and_then(|(name, _)| {
+                    let data = bytes(&ctx, &data)?;
+                    Ok(with_hash!(&ctx, name, |H| H::digest(&data).to_vec()))
+                });
+                buffer(&ctx, result)
+            },
+        )?,
+    )?;
+    subtle.set(
+        "generateKey",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, algorithm: Value<'js>, extractable: bool, usages: Vec<String>| {
+                promised(&ctx, generate_key(&ctx, algorithm, extractable, usages))
+            },
+        )?,
+    )?;
+    subtle.set(
+        "importKey",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>,
+             format: String,
+             key_data: Value<'js>,
+             algorithm: Value<'js>,
+             extractable: bool,
+             usages: Vec<String>| {
+                let result = import_key(&ctx, format, key_data, algorithm, extractable, usages);
+                promised(&ctx, result)
+            },
+        )?,
+    )?;
+    subtle.set(
+        "exportKey",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, format: String, key: Class<'js, CryptoKey>| {
+                buffer(&ctx, export_key(&ctx, format, &key.borrow()))
+            },
+        )?,
+    )?;
+    subtle.set(
+        "sign",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, algorithm: Value<'js>, key: Class<'js, CryptoKey>, data: Value<'js>| {
+                buffer(&ctx, sign(&ctx, algorithm, &key.borrow(), data))
+            },
+        )?,
+    )?;
+    subtle.set(
+        "verify",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>,
+             algorithm: Value<'js>,
+             key: Class<'js, CryptoKey>,
+             signature: Value<'js>,
+             data: Value<'js>| {
+                let result = verify(&ctx, algorithm, &key.borrow(), signature, data);
+                promised(&ctx, result)
+            },
+        )?,
+    )?;
+    subtle.set(
+        "encrypt",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, algorithm: Value<'js>, key: Class<'js, CryptoKey>, data: Value<'js>| {
+                buffer(&ctx, crypt(&ctx, algorithm, &key.borrow(), data, true))
+            },
+        )?,
+    )?;
+    subtle.set(
+        "decrypt",
+        Function::new(
+            ctx.clone(),
+            |ctx: Ctx<'js>, algorithm: Value<'js>, key: Class<'js, CryptoKey>, data: Value<'js>| {
+                buffer(&ctx, crypt(&ctx, algorithm, &key.borrow(), data, false))
+            },
+        )?,
+    )?;
+    Ok(subtle)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Context, Promise, Runtime};
+
+    #[test]
+    fn subtle() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            super::super::define_web_crypto(&ctx).unwrap();
+            let promise: Promise = ctx
+                .eval(
+                    r#"(async () => {
+                        const { subtle } = crypto;
+                        const hex = (buf) => [...new Uint8Array(buf)]
+                            .map((b) => b.toString(16).padStart(2, "0")).join("");
+                        const data = new Uint8Array([1, 2, 3]);
+                        const res = [hex(await subtle.digest("SHA-256", new Uint8Array([97, 98, 99])))];
+
+                        const hmac = await subtle.importKey(
+                            "raw", new Uint8Array([107, 101, 121]), { name: "HMAC", hash: "SHA-256" },
+                            false, ["sign", "verify"]);
+                        const mac = await subtle.sign("HMAC", hmac, data);
+                        res.push(hmac.type, hmac.algorithm.length, await subtle.verify("HMAC", hmac, mac, data));
+
+                        const ec = await subtle.generateKey(
+                            { name: "ECDSA", namedCurve: "P-256" }, false, ["sign", "verify"]);
+                        const alg = { name: "ECDSA", hash: "SHA-256" };
+                        const sig = await subtle.sign(alg, ec.privateKey, data);
+                        res.push(sig.byteLength, await subtle.verify(alg, ec.publicKey, sig, data));
+                        const raw = await subtle.exportKey("raw", ec.publicKey);
+                        const imported = await subtle.importKey(
+                            "raw", raw, { name: "ECDSA", namedCurve: "P-256" }, true, ["verify"]);
+                        res.push(await subtle.verify(alg, imported, sig, new Uint8Array([3, 2, 1])));
+
+                        const aes = await subtle.generateKey({ name: "AES-GCM", length: 256 }, false, ["encrypt", "decrypt"]);
+                        const iv = crypto.getRandomValues(new Uint8Array(12));
+                        const sealed = await subtle.encrypt({ name: "AES-GCM", iv }, aes, data);
+                        const opened = await subtle.decrypt({ name: "AES-GCM", iv }, aes, sealed);
+                        res.push(sealed.byteLength, hex(opened));
+                        new Uint8Array(sealed)[0] ^= 1;
+                        await subtle.decrypt({ name: "AES-GCM", iv }, aes, sealed).catch((e) => res.push(e.name));
+
+                        await subtle.exportKey("raw", aes).catch((e) => res.push(e.name));
+                        await subtle.sign("HMAC", aes, data).catch((e) => res.push(e.name));
+                        await subtle.digest("MD5", data).catch((e) => res.push(e.name));
+                        return res.join(" ");
+                    })()"#,
+                )
+                .unwrap();
+            let res: String = promise.finish().unwrap();
+            assert_eq!(
+                res,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad \
+                 secret 24 true 64 true false 19 010203 OperationError \
+                 InvalidAccessError InvalidAccessError TypeError"
+            );
+        });
+    }
+}
@@ -1,13 +1,28 @@
-use crate::{loader::Resolver, Ctx, Error, Result};
+use crate::{
+    loader::{PhysicalVfs, Resolver, Vfs},
+    Ctx, Error, Result,
+};
 use relative_path::{RelativePath, RelativePathBuf};
+use std::{fmt, sync::Arc};
 
 /// The file module resolver
 ///
 /// This resolver can be used as the nested backing resolver in user-defined resolvers.
-#[derive(Debug)]
+///
+/// Modules are looked up in a [`Vfs`], the [`PhysicalVfs`] of the whole disk by default.
 pub struct FileResolver {
     paths: Vec<RelativePathBuf>,
     patterns: Vec<String>,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl fmt::Debug for FileResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileResolver")
+            .field("paths", &self.paths)
+            .field("patterns", &self.patterns)
+            .finish()
+    }
 }
 
 impl FileResolver {
@@ -77,9 +92,29 @@ impl FileResolver {
         self
     }
 
+    /// Look up the modules in a virtual file system instead of the disk
+    pub fn set_vfs<V: Vfs + 'static>(&mut self, vfs: V) -> &mut Self {
+        self.vfs = Arc::new(vfs);
+        self
+    }
+
+    /// Look up the modules in a virtual file system instead of the disk
+    #[must_use]
+    pub fn with_vfs<V: Vfs + 'static>(mut self, vfs: V) -> Self {
+        self.set_vfs(vfs);
+        self
+    }
+
+    fn is_file(&self, path: &RelativePath) -> bool {
+        self.vfs
+            .normalize_path(path.as_str())
+            .and_then(|path| self.vfs.stat(&path))
+            .map_or(false, |metadata| !metadata.is_dir)
+    }
+
     fn try_patterns(&self, path: &RelativePath) -> Option<RelativePathBuf> {
         if let Some(extension) = &path.extension() {
-            if !is_file(path) {
+            if !self.is_file(path) {
                 return None;
             }
             // check for known extensions
@@ -99,7 +134,7 @@ impl FileResolver {
             self.patterns.iter().find_map(|pattern| {
                 let name = pattern.replace("{}", path.file_name()?);
                 let file = path.with_file_name(name);
-                if is_file(&file) {
+                if self.is_file(&file) {
                     Some(file)
                 } else {
                     None
@@ -114,6 +149,7 @@ impl Default for FileResolver {
         Self {
             paths: vec![],
             patterns: vec!["{}.js".into()],
+            vfs: Arc::new(PhysicalVfs::default()),
         }
     }
 }
@@ -139,7 +175,3 @@ impl Resolver for FileResolver {
         Ok(path.to_string())
     }
 }
-
-fn is_file<P: AsRef<RelativePath>>(path: P) -> bool {
-    path.as_ref().to_path(".").is_file()
-}
@@ -0,0 +1,303 @@
+use std::{collections::BTreeMap, env, sync::Arc};
+
+use crate::{
+    loader::{vfs::normalize, Loader, Resolver, Vfs},
+    module::Declared,
+    Ctx, Error, Function, Module, Object, Result,
+};
+
+/// Capability modules giving scripts access to the host
+///
+/// Instead of the raw modules of quickjs-libc, which give scripts access to the whole file
+/// system and process, the host grants only the capabilities it wants to give:
+///
+/// - `host:fs` exports `readFile`, `writeFile`, `readDir`, `remove` and `exists`, which work on
+///   a [`Vfs`] like [`MemoryVfs`](super::MemoryVfs) or [`PhysicalVfs`](super::PhysicalVfs). Files are read and written as UTF-8
+///   strings and paths can't leave the root of the file system.
+/// - `host:env` exports `get`, `has` and `keys` for the environment variables the host allowed
+///   or defined.
+/// - `host:crypto` (with the `crypto` feature) exports `digest(alg, data)` and
+///   `hmac(alg, key, data)` for the SHA-2 and SHA-3 families (`"SHA-256"`, `"SHA3-512"`, ...),
+///   `timingSafeEqual(a, b)`, `randomBytes(len)`, `getRandomValues(typedArray)` and the
+///   `subtle` subset of the Web Crypto API described at
+///   [`define_web_crypto`](super::define_web_crypto). Data is given as strings, `ArrayBuffer`s
+///   or typed arrays and results are `ArrayBuffer`s.
+///
+/// A module is only resolved when its capability was granted, so importing it otherwise fails
+/// like importing a missing module. The modules are both resolved and loaded by this type, so
+/// it is used as resolver and loader combined with others in tuples:
+///
+/// ```
+/// # use rquickjs::{loader::{BuiltinLoader, BuiltinResolver, HostModules, MemoryVfs}, Context, Module, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// let host = HostModules::default()
+///     .with_fs(MemoryVfs::default().with_file("config/name.txt", "world"))
+///     .with_env_value("GREETING", "hello");
+/// rt.set_loader(
+///     (host.clone(), BuiltinResolver::default().with_module("main")),
+///     (host, BuiltinLoader::default()),
+/// );
+/// ctx.with(|ctx| {
+///     let source = r#"
+///         import { readFile } from "host:fs";
+///         import { get } from "host:env";
+///         globalThis.x = `${get("GREETING")} ${readFile("/config/name.txt")}`;
+///     "#;
+///     Module::evaluate(ctx.clone(), "main", source).unwrap().finish::<()>().unwrap();
+///     assert_eq!(ctx.globals().get::<_, String>("x").unwrap(), "hello world");
+/// });
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Default, Clone)]
+pub struct HostModules {
+    fs: Option<Arc<dyn Vfs>>,
+    env: Option<Arc<BTreeMap<String, EnvVar>>>,
+    #[cfg(feature = "crypto")]
+    crypto: bool,
+}
+
+#[derive(Debug, Clone)]
+enum EnvVar {
+    /// Read from the environment of the process when accessed.
+    Process,
+    Value(String),
+}
+
+impl HostModules {
+    /// Grant the `host:fs` module, backed by the given file system
+    pub fn add_fs<F: Vfs + 'static>(&mut self, fs: F) -> &mut Self {
+        self.fs = Some(Arc::new(fs));
+        self
+    }
+
+    /// Grant the `host:fs` module, backed by the given file system
+    #[must_use]
+    pub fn with_fs<F: Vfs + 'static>(mut self, fs: F) -> Self {
+        self.add_fs(fs);
+        self
+    }
+
+    /// Grant the `host:env` module access to an environment variable of the process
+    pub fn add_env_var<N: Into<String>>(&mut self, name: N) -> &mut Self {
+        self.env_mut().insert(name.into(), EnvVar::Process);
+        self
+    }
+
+    /// Grant the `host:env` module access to an environment variable of the process
+    #[must_use]
+    pub fn with_env_var<N: Into<String>>(mut self, name: N) -> Self {
+        self.add_env_var(name);
+        self
+    }
+
+    /// Define an environment variable for the `host:env` module
+    pub fn add_env_value<N: Into<String>, V: Into<String>>(
+        &mut self,
+        name: N,
+        value: V,
+    ) -> &mut Self {
+        self.env_mut()
+            .insert(name.into(), EnvVar::Value(value.into()));
+        self
+    }
+
+    /// Define an environment variable for the `host:env` module
+    #[must_use]
+    pub fn with_env_value<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.add_env_value(name, value);
+        self
+    }
+
+    /// Grant the `host:crypto` module
+    #[cfg(feature = "crypto")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "crypto")))]
+    pub fn add_crypto(&mut self) -> &mut Self {
+        self.crypto = true;
+        self
+    }
+
+    /// Grant the `host:crypto` module
+    #[cfg(feature = "crypto")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "crypto")))]
+    #[must_use]
+    pub fn with_crypto(mut self) -> Self {
+        self.add_crypto();
+        self
+    }
+
+    fn env_mut(&mut self) -> &mut BTreeMap<String, EnvVar> {
+        Arc::make_mut(self.env.get_or_insert_with(Default::default))
+    }
+
+    fn fs_module<'js>(ctx: &Ctx<'js>, fs: &Arc<dyn Vfs>) -> Result<Object<'js>> {
+        let exports = Object::new(ctx.clone())?;
+        let fs_read = fs.clone();
+        exports.set(
+            "readFile",
+            Function::new(ctx.clone(), move |path: String| -> Result<String> {
+                let data = fs_read.read(&normalize(&path)?)?;
+                Ok(String::from_utf8(data)?)
+            })?,
+        )?;
+        let fs_write = fs.clone();
+        exports.set(
+            "writeFile",
+            Function::new(
+                ctx.clone(),
+                move |path: String, data: String| -> Result<()> {
+                    let path = normalize(&path)?;
+                    Ok(fs_write.write(&path, data.as_bytes())?)
+                },
+            )?,
+        )?;
+        let fs_read_dir = fs.clone();
+        exports.set(
+            "readDir",
+            Function::new(ctx.clone(), move |path: String| -> Result<Vec<String>> {
+                Ok(fs_read_dir.read_dir(&normalize(&path)?)?)
+            })?,
+        )?;
+        let fs_remove = fs.clone();
+        exports.set(
+            "remove",
+            Function::new(ctx.clone(), move |path: String| -> Result<()> {
+                Ok(fs_remove.remove(&normalize(&path)?)?)
+            })?,
+        )?;
+        let fs_exists = fs.clone();
+        exports.set(
+            "exists",
+            Function::new(ctx.clone(), move |path: String| -> Result<bool> {
+                Ok(fs_exists.stat(&normalize(&path)?).is_ok())
+            })?,
+        )?;
+        Ok(exports)
+    }
+
+    fn env_module<'js>(ctx: &Ctx<'js>, env: &Arc<BTreeMap<String, EnvVar>>) -> Result<Object<'js>> {
+        let get = |env: &BTreeMap<String, EnvVar>, name: &str| match env.get(name)? {
+            EnvVar::Process => env::var(name).ok(),
+            EnvVar::Value(value) => Some(value.clone()),
+        };
+
+        let exports = Object::new(ctx.clone())?;
+        let env_get = env.clone();
+        exports.set(
+            "get",
+            Function::new(ctx.clone(), move |name: String| get(&env_get, &name))?,
+        )?;
+        let env_has = env.clone();
+        exports.set(
+            "has",
+            Function::new(ctx.clone(), move |name: String| {
+                get(&env_has, &name).is_some()
+            })?,
+        )?;
+        let env_keys = env.clone();
+        exports.set(
+            "keys",
+            Function::new(ctx.clone(), move || {
+                env_keys
+                    .keys()
+                    .filter(|name| get(&env_keys, name).is_some())
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })?,
+        )?;
+        Ok(exports)
+    }
+
+    fn granted(&self, name: &str) -> bool {
+        match name {
+            "host:fs" => self.fs.is_some(),
+            "host:env" => self.env.is_some(),
+            #[cfg(feature = "crypto")]
+            "host:crypto" => self.crypto,
+            _ => false,
+        }
+    }
+}
+
+impl Resolver for HostModules {
+    fn resolve<'js>(&mut self, _ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        if self.granted(name) {
+            Ok(name.into())
+        } else {
+            Err(Error::new_resolving(base, name))
+        }
+    }
+}
+
+impl Loader for HostModules {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js, Declared>> {
+        #[cfg(feature = "crypto")]
+        if name == "host:crypto" && self.crypto {
+            let exports = super::crypto::crypto_module(ctx)?;
+            return Module::declare_object(ctx.clone(), name, exports);
+        }
+        let exports = match (name, &self.fs, &self.env) {
+            ("host:fs", Some(fs), _) => Self::fs_module(ctx, fs)?,
+            ("host:env", _, Some(env)) => Self::env_module(ctx, env)?,
+            _ => return Err(Error::new_loading(name)),
+        };
+        Module::declare_object(ctx.clone(), name, exports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        loader::{BuiltinLoader, BuiltinResolver, MemoryVfs},
+        Context, Runtime,
+    };
+
+    #[test]
+    fn capabilities() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let host = HostModules::default()
+            .with_fs(MemoryVfs::default())
+            .with_env_value("MODE", "test");
+        rt.set_loader(
+            (host.clone(), BuiltinResolver::default().with_module("main")),
+            (host, BuiltinLoader::default()),
+        );
+        ctx.with(|ctx| {
+            let source = r#"
+                import { readFile, writeFile, readDir, exists } from "host:fs";
+                import { get, has, keys } from "host:env";
+                writeFile("/out/result.txt", get("MODE"));
+                globalThis.res = [
+                    readFile("out/../out/result.txt"),
+                    readDir("/").join(),
+                    exists("out/result.txt"),
+                    has("HOME"),
+                    keys().join(),
+                ].join(" ");
+                try { readFile("../x") } catch (e) { globalThis.escape = e.message }
+            "#;
+            Module::evaluate(ctx.clone(), "main", source)
+                .unwrap()
+                .finish::<()>()
+                .unwrap();
+            let res: String = ctx.globals().get("res").unwrap();
+            assert_eq!(res, "test out true false MODE");
+            let escape: String = ctx.globals().get("escape").unwrap();
+            assert!(escape.contains("escapes the root"), "{escape}");
+        });
+
+        rt.set_loader(
+            (
+                HostModules::default(),
+                BuiltinResolver::default().with_module("main2"),
+            ),
+            (HostModules::default(), BuiltinLoader::default()),
+        );
+        ctx.with(|ctx| {
+            let res = Module::evaluate(ctx.clone(), "main2", "import 'host:fs';");
+            assert!(res.and_then(|x| x.finish::<()>()).is_err());
+        });
+    }
+}
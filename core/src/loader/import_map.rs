@@ -0,0 +1,236 @@
+use crate::{loader::Resolver, Ctx, Error, FromJs, Object, Result};
+use relative_path::RelativePath;
+use std::collections::HashMap;
+
+/// The specifier map of an import map, sorted by descending key length so the first match is
+/// the most specific one.
+#[derive(Debug, Default, Clone)]
+struct SpecifierMap(Vec<(String, String)>);
+
+impl SpecifierMap {
+    fn insert(&mut self, key: String, target: String) {
+        self.0.retain(|(existing, _)| *existing != key);
+        let idx = self
+            .0
+            .partition_point(|(existing, _)| existing.len() >= key.len());
+        self.0.insert(idx, (key, target));
+    }
+
+    fn map(&self, specifier: &str) -> Option<String> {
+        self.0.iter().find_map(|(key, target)| {
+            if key == specifier {
+                Some(target.clone())
+            } else if key.ends_with('/') && target.ends_with('/') {
+                let rest = specifier.strip_prefix(key.as_str())?;
+                Some(format!("{target}{rest}"))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// A module resolver which maps specifiers like a Web import map
+///
+/// An import map consists of `imports`, which map specifiers to module names, and `scopes`,
+/// which override them for modules whose name starts with the scope prefix. A key ending with
+/// `/` maps every specifier starting with it by replacing the prefix, its target must end with
+/// `/` too. When several keys match, the longest one wins, and scoped keys take precedence over
+/// the top-level imports.
+///
+/// Relative specifiers are joined with the name of the importing module before they are
+/// looked up, bare specifiers are looked up as is. Specifiers which aren't mapped fail to
+/// resolve, so the import map is usually combined with a backing resolver in a tuple:
+///
+/// ```
+/// # use rquickjs::{loader::{BuiltinLoader, BuiltinResolver, ImportMap}, Context, Module, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// let import_map = ImportMap::default()
+///     .with_import("lodash", "vendor/lodash/index.js")
+///     .with_import("lodash/", "vendor/lodash/");
+/// let resolver = BuiltinResolver::default().with_module("main.js");
+/// let loader = BuiltinLoader::default()
+///     .with_module("vendor/lodash/index.js", "export default 'index'")
+///     .with_module("vendor/lodash/add.js", "export default 'add'");
+/// rt.set_loader((import_map, resolver), loader);
+/// ctx.with(|ctx| {
+///     let source = "import a from 'lodash'; import b from 'lodash/add.js'; globalThis.x = a + b;";
+///     Module::evaluate(ctx.clone(), "main.js", source).unwrap().finish::<()>().unwrap();
+///     assert_eq!(ctx.globals().get::<_, String>("x").unwrap(), "indexadd");
+/// });
+/// ```
+///
+/// The loader applies to all contexts of a runtime. A context can use its own import map
+/// instead of the one of the resolver by setting it with
+/// [`Ctx::set_import_map`](crate::Ctx::set_import_map).
+#[derive(Debug, Default, Clone)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+    /// Parse an import map from its JSON representation
+    ///
+    /// The JSON must be an object with an optional `imports` object mapping specifiers to
+    /// module names and an optional `scopes` object mapping prefixes to such objects.
+    pub fn from_json<'js, S: Into<Vec<u8>>>(ctx: &Ctx<'js>, json: S) -> Result<Self> {
+        let object = Object::from_js(ctx, ctx.json_parse(json)?)?;
+        let mut import_map = Self::default();
+        if let Some(imports) = object.get::<_, Option<HashMap<String, String>>>("imports")? {
+            for (key, target) in imports {
+                import_map.add_import(key, target);
+            }
+        }
+        let scopes: Option<HashMap<String, HashMap<String, String>>> = object.get("scopes")?;
+        for (scope, imports) in scopes.into_iter().flatten() {
+            for (key, target) in imports {
+                import_map.add_scope_import(scope.clone(), key, target);
+            }
+        }
+        Ok(import_map)
+    }
+
+    /// Add a top-level mapping
+    pub fn add_import<K: Into<String>, T: Into<String>>(&mut self, key: K, target: T) -> &mut Self {
+        self.imports.insert(key.into(), target.into());
+        self
+    }
+
+    /// Add a top-level mapping
+    #[must_use]
+    pub fn with_import<K: Into<String>, T: Into<String>>(mut self, key: K, target: T) -> Self {
+        self.add_import(key, target);
+        self
+    }
+
+    /// Add a mapping which applies to modules whose name starts with `scope`
+    pub fn add_scope_import<S: Into<String>, K: Into<String>, T: Into<String>>(
+        &mut self,
+        scope: S,
+        key: K,
+        target: T,
+    ) -> &mut Self {
+        let scope = scope.into();
+        let idx = match self.scopes.iter().position(|(x, _)| *x == scope) {
+            Some(idx) => idx,
+            None => {
+                let idx = self.scopes.partition_point(|(x, _)| x.len() >= scope.len());
+                self.scopes.insert(idx, (scope, SpecifierMap::default()));
+                idx
+            }
+        };
+        self.scopes[idx].1.insert(key.into(), target.into());
+        self
+    }
+
+    /// Add a mapping which applies to modules whose name starts with `scope`
+    #[must_use]
+    pub fn with_scope_import<S: Into<String>, K: Into<String>, T: Into<String>>(
+        mut self,
+        scope: S,
+        key: K,
+        target: T,
+    ) -> Self {
+        self.add_scope_import(scope, key, target);
+        self
+    }
+
+    /// Map the specifier `name` imported by the module `base`, returns `None` if it isn't
+    /// mapped.
+    pub fn map(&self, base: &str, name: &str) -> Option<String> {
+        let specifier = if name.starts_with('.') {
+            match RelativePath::new(base).parent() {
+                Some(dir) => dir.join_normalized(name).to_string(),
+                None => name.to_string(),
+            }
+        } else {
+            name.to_string()
+        };
+        self.scopes
+            .iter()
+            .filter(|(scope, _)| base.starts_with(scope.as_str()))
+            .find_map(|(_, imports)| imports.map(&specifier))
+            .or_else(|| self.imports.map(&specifier))
+    }
+}
+
+impl Resolver for ImportMap {
+    fn resolve<'js>(&mut self, ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        let import_map = ctx.import_map();
+        import_map
+            .as_deref()
+            .unwrap_or(self)
+            .map(base, name)
+            .ok_or_else(|| Error::new_resolving(base, name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn mapping() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let mut import_map = ImportMap::from_json(
+                &ctx,
+                r#"{
+                    "imports": {
+                        "react": "vendor/react.js",
+                        "lib/": "vendor/lib/",
+                        "lib/special.js": "special.js",
+                        "app/util.js": "app/util2.js"
+                    },
+                    "scopes": {
+                        "legacy/": { "react": "vendor/react-old.js" }
+                    }
+                }"#,
+            )
+            .unwrap();
+            let mut resolve = |base, name| import_map.resolve(&ctx, base, name).ok();
+            assert_eq!(resolve("main.js", "react").unwrap(), "vendor/react.js");
+            assert_eq!(
+                resolve("main.js", "lib/a/b.js").unwrap(),
+                "vendor/lib/a/b.js"
+            );
+            assert_eq!(resolve("main.js", "lib/special.js").unwrap(), "special.js");
+            assert_eq!(resolve("app/main.js", "./util.js").unwrap(), "app/util2.js");
+            assert_eq!(
+                resolve("legacy/x.js", "react").unwrap(),
+                "vendor/react-old.js"
+            );
+            assert_eq!(resolve("legacy/x.js", "lib/").unwrap(), "vendor/lib/");
+            assert!(resolve("main.js", "other").is_none());
+            assert!(resolve("main.js", "reactx").is_none());
+
+            assert!(ImportMap::from_json(&ctx, r#"{ "imports": { "a": 1 } }"#).is_err());
+        });
+    }
+
+    #[test]
+    fn per_context() {
+        let rt = Runtime::new().unwrap();
+        let ctx_a = Context::full(&rt).unwrap();
+        let ctx_b = Context::full(&rt).unwrap();
+        let mut import_map = ImportMap::default().with_import("dep", "dep-default.js");
+        ctx_b.with(|ctx| {
+            ctx.set_import_map(Some(ImportMap::default().with_import("dep", "dep-b.js")));
+            let res = import_map.resolve(&ctx, "main.js", "dep").unwrap();
+            assert_eq!(res, "dep-b.js");
+        });
+        ctx_a.with(|ctx| {
+            let res = import_map.resolve(&ctx, "main.js", "dep").unwrap();
+            assert_eq!(res, "dep-default.js");
+        });
+        ctx_b.with(|ctx| {
+            ctx.set_import_map(None);
+            let res = import_map.resolve(&ctx, "main.js", "dep").unwrap();
+            assert_eq!(res, "dep-default.js");
+        });
+    }
+}
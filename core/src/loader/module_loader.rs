@@ -8,6 +8,36 @@ type LoadFn = for<'js> fn(Ctx<'js>, Vec<u8>) -> Result<Module<'js>>;
 /// The builtin native module loader
 ///
 /// This loader can be used as the nested backing loader in user-defined loaders.
+///
+/// Native modules implement [`ModuleDef`], usually with the `#[rquickjs::module]` attribute
+/// macro, and are imported by the name they are added with once the resolver accepts the name:
+///
+/// ```
+/// # use rquickjs::{loader::{BuiltinResolver, ModuleLoader}, Context, Module, Runtime};
+/// #[rquickjs::module]
+/// mod fs {
+///     #[rquickjs::function]
+///     pub fn read_file(path: String) -> String {
+///         format!("contents of {path}")
+///     }
+/// }
+///
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// rt.set_loader(
+///     BuiltinResolver::default().with_module("fs"),
+///     ModuleLoader::default().with_module("fs", js_fs),
+/// );
+/// ctx.with(|ctx| {
+///     let source = "import { read_file } from 'fs'; globalThis.text = read_file('a.txt');";
+///     Module::evaluate(ctx.clone(), "main", source)
+///         .unwrap()
+///         .finish::<()>()
+///         .unwrap();
+///     let text: String = ctx.globals().get("text").unwrap();
+///     assert_eq!(text, "contents of a.txt");
+/// });
+/// ```
 #[derive(Debug, Default)]
 pub struct ModuleLoader {
     modules: HashMap<String, LoadFn>,
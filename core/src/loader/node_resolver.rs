@@ -0,0 +1,514 @@
+use crate::{
+    loader::{Metadata, PhysicalVfs, Resolver, Vfs},
+    Ctx, Error, FromJs, Object, Result, Value,
+};
+use std::{
+    ffi::OsString,
+    fmt,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// The module resolver which implements the resolution of Node
+///
+/// Relative and absolute specifiers are resolved to files, trying the known extensions and
+/// directory indexes, the `main` field of a `package.json` included. Bare specifiers are looked
+/// up as packages in the `node_modules` directories of the importing module and its parents and
+/// in the added search paths. Specifiers starting with `#` are looked up in the `imports` field
+/// of the closest `package.json`.
+///
+/// A package which has an `exports` field can only be imported through it, subpath patterns
+/// like `"./utils/*"` and conditions included. The conditions are matched in the order of the
+/// keys in the `package.json`, `default` always matches, `import` matches by default and other
+/// conditions like `require` or `browser` can be added.
+///
+/// Files are looked up in a [`Vfs`], the [`PhysicalVfs`] of the whole disk by default.
+///
+/// ```no_run
+/// # use rquickjs::{loader::{NodeResolver, ScriptLoader}, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// let resolver = NodeResolver::default()
+///     .with_path("vendor")
+///     .with_condition("rquickjs");
+/// rt.set_loader(resolver, ScriptLoader::default().with_extension("mjs"));
+/// ```
+pub struct NodeResolver {
+    paths: Vec<PathBuf>,
+    conditions: Vec<String>,
+    extensions: Vec<String>,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl fmt::Debug for NodeResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeResolver")
+            .field("paths", &self.paths)
+            .field("conditions", &self.conditions)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+impl NodeResolver {
+    /// Add search path for packages
+    pub fn add_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Add search path for packages
+    #[must_use]
+    pub fn with_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.add_path(path);
+        self
+    }
+
+    /// Add export condition
+    pub fn add_condition<C: Into<String>>(&mut self, condition: C) -> &mut Self {
+        self.conditions.push(condition.into());
+        self
+    }
+
+    /// Add export condition
+    #[must_use]
+    pub fn with_condition<C: Into<String>>(mut self, condition: C) -> Self {
+        self.add_condition(condition);
+        self
+    }
+
+    /// Remove all export conditions, `default` still matches
+    pub fn clear_conditions(&mut self) -> &mut Self {
+        self.conditions.clear();
+        self
+    }
+
+    /// Add module file extension
+    pub fn add_extension<X: Into<String>>(&mut self, extension: X) -> &mut Self {
+        self.extensions.push(extension.into());
+        self
+    }
+
+    /// Add module file extension
+    #[must_use]
+    pub fn with_extension<X: Into<String>>(mut self, extension: X) -> Self {
+        self.add_extension(extension);
+        self
+    }
+
+    /// Look up the modules in a virtual file system instead of the disk
+    pub fn set_vfs<V: Vfs + 'static>(&mut self, vfs: V) -> &mut Self {
+        self.vfs = Arc::new(vfs);
+        self
+    }
+
+    /// Look up the modules in a virtual file system instead of the disk
+    #[must_use]
+    pub fn with_vfs<V: Vfs + 'static>(mut self, vfs: V) -> Self {
+        self.set_vfs(vfs);
+        self
+    }
+
+    fn stat(&self, path: &Path) -> Option<(String, Metadata)> {
+        let path = path.to_str()?;
+        // Virtual file systems only accept `/` as separator.
+        #[cfg(windows)]
+        let path = &path.replace('\\', "/");
+        let path = self.vfs.normalize_path(path).ok()?;
+        let metadata = self.vfs.stat(&path).ok()?;
+        Some((path, metadata))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        matches!(self.stat(path), Some((_, metadata)) if !metadata.is_dir)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.stat(path), Some((_, metadata)) if metadata.is_dir)
+    }
+
+    /// Read the `package.json` of a directory, if it has one.
+    fn read_package<'js>(&self, ctx: &Ctx<'js>, dir: &Path) -> Result<Option<Object<'js>>> {
+        let path = match self.stat(&dir.join("package.json")) {
+            Some((path, metadata)) if !metadata.is_dir => path,
+            _ => return Ok(None),
+        };
+        let json = ctx.json_parse(self.vfs.read(&path)?)?;
+        Object::from_js(ctx, json).map(Some)
+    }
+
+    fn resolve_path<'js>(&self, ctx: &Ctx<'js>, path: &Path) -> Result<Option<PathBuf>> {
+        if let Some(file) = self.resolve_file(path) {
+            return Ok(Some(file));
+        }
+        self.resolve_directory(ctx, path)
+    }
+
+    fn resolve_file(&self, path: &Path) -> Option<PathBuf> {
+        if self.is_file(path) {
+            return Some(path.to_path_buf());
+        }
+        self.extensions.iter().find_map(|extension| {
+            let mut file = OsString::from(path);
+            file.push(".");
+            file.push(extension);
+            let file = PathBuf::from(file);
+            self.is_file(&file).then_some(file)
+        })
+    }
+
+    fn resolve_directory<'js>(&self, ctx: &Ctx<'js>, dir: &Path) -> Result<Option<PathBuf>> {
+        if let Some(package) = self.read_package(ctx, dir)? {
+            if let Some(main) = package.get::<_, Option<String>>("main")? {
+                let main = normalize(&dir.join(main));
+                let file = self
+                    .resolve_file(&main)
+                    .or_else(|| self.resolve_file(&main.join("index")));
+                if file.is_some() {
+                    return Ok(file);
+                }
+            }
+        }
+        Ok(self.resolve_file(&dir.join("index")))
+    }
+
+    fn resolve_package<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        dir: &Path,
+        name: &str,
+    ) -> Result<Option<PathBuf>> {
+        // The name of scoped packages consists of two segments.
+        let split = if name.starts_with('@') {
+            name.match_indices('/').nth(1).map(|(idx, _)| idx)
+        } else {
+            name.find('/')
+        };
+        let (package_name, subpath) = match split {
+            Some(idx) => (&name[..idx], format!(".{}", &name[idx..])),
+            None => (name, ".".to_string()),
+        };
+        if package_name.is_empty() || (name.starts_with('@') && !package_name.contains('/')) {
+            return Ok(None);
+        }
+
+        let candidates = dir
+            .ancestors()
+            .map(|dir| dir.join("node_modules"))
+            .chain(self.paths.iter().cloned())
+            .map(|dir| dir.join(package_name));
+        for package_dir in candidates {
+            if !self.is_dir(&package_dir) {
+                continue;
+            }
+            let package = self.read_package(ctx, &package_dir)?;
+            let exports = match &package {
+                Some(package) => package.get::<_, Value>("exports")?,
+                None => Value::new_undefined(ctx.clone()),
+            };
+            // A package with exports can't be imported in any other way.
+            if !exports.is_undefined() && !exports.is_null() {
+                return self.resolve_exports(ctx, &package_dir, &subpath, exports);
+            }
+            let file = self.resolve_path(ctx, &normalize(&package_dir.join(&subpath)))?;
+            if file.is_some() {
+                return Ok(file);
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_exports<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        package_dir: &Path,
+        subpath: &str,
+        exports: Value<'js>,
+    ) -> Result<Option<PathBuf>> {
+        // Exports which aren't an object of subpaths are the export of the main subpath.
+        let subpaths = match exports.as_object() {
+            Some(object) if !exports.is_array() => {
+                let mut keys = object.keys::<String>();
+                match keys.next().transpose()? {
+                    Some(key) if key.starts_with('.') => Some(object.clone()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match subpaths {
+            Some(subpaths) => self.resolve_match(ctx, package_dir, subpath, &subpaths, false),
+            None if subpath == "." => self.resolve_target(ctx, package_dir, exports, None, false),
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_imports<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        dir: &Path,
+        name: &str,
+    ) -> Result<Option<PathBuf>> {
+        if name == "#" || name.starts_with("#/") {
+            return Ok(None);
+        }
+        for package_dir in dir.ancestors() {
+            if let Some(package) = self.read_package(ctx, package_dir)? {
+                return match package.get::<_, Option<Object>>("imports")? {
+                    Some(imports) => self.resolve_match(ctx, package_dir, name, &imports, true),
+                    None => Ok(None),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up a key in an `exports` or `imports` object, exact keys first, then the pattern
+    /// with the longest prefix.
+    fn resolve_match<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        package_dir: &Path,
+        key: &str,
+        map: &Object<'js>,
+        internal: bool,
+    ) -> Result<Option<PathBuf>> {
+        if !key.contains('*') && map.contains_key(key)? {
+            let target = map.get(key)?;
+            return self.resolve_target(ctx, package_dir, target, None, internal);
+        }
+        let mut best: Option<(String, String)> = None;
+        for pattern in map.keys::<String>() {
+            let pattern = pattern?;
+            let Some((prefix, suffix)) = pattern.split_once('*') else {
+                continue;
+            };
+            if suffix.contains('*') || !key.starts_with(prefix) || key.len() < pattern.len() {
+                continue;
+            }
+            let Some(matched) = key[prefix.len()..].strip_suffix(suffix) else {
+                continue;
+            };
+            let better = match &best {
+                Some((best, _)) => {
+                    let best_prefix = best.find('*').unwrap_or(best.len());
+                    prefix.len() > best_prefix
+                        || (prefix.len() == best_prefix && pattern.len() > best.len())
+                }
+                None => true,
+            };
+            if better {
+                best = Some((pattern.clone(), matched.to_string()));
+            }
+        }
+        match best {
+            Some((pattern, matched)) => {
+                let target = map.get(pattern)?;
+                self.resolve_target(ctx, package_dir, target, Some(&matched), internal)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_target<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        package_dir: &Path,
+        target: Value<'js>,
+        matched: Option<&str>,
+        internal: bool,
+    ) -> Result<Option<PathBuf>> {
+        if let Some(target) = target.as_string() {
+            let mut target = target.to_string()?;
+            if let Some(matched) = matched {
+                target = target.replace('*', matched);
+            }
+            if !target.starts_with("./") {
+                // Imports can map to packages, everything else must be inside the package.
+                if internal && !target.starts_with("../") && !Path::new(&target).is_absolute() {
+                    return self.resolve_package(ctx, package_dir, &target);
+                }
+                return Ok(None);
+            }
+            let path = normalize(&package_dir.join(&target));
+            let valid = path.starts_with(normalize(package_dir)) && self.is_file(&path);
+            return Ok(valid.then_some(path));
+        }
+        if let Some(targets) = target.as_array() {
+            for target in targets.iter::<Value>() {
+                let path = self.resolve_target(ctx, package_dir, target?, matched, internal)?;
+                if path.is_some() {
+                    return Ok(path);
+                }
+            }
+            return Ok(None);
+        }
+        if let Some(conditions) = target.as_object() {
+            for prop in conditions.props::<String, Value>() {
+                let (condition, target) = prop?;
+                if condition != "default" && !self.conditions.contains(&condition) {
+                    continue;
+                }
+                let path = self.resolve_target(ctx, package_dir, target, matched, internal)?;
+                if path.is_some() {
+                    return Ok(path);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for NodeResolver {
+    fn default() -> Self {
+        Self {
+            paths: vec![],
+            conditions: vec!["import".into()],
+            extensions: vec!["js".into(), "mjs".into(), "cjs".into()],
+            vfs: Arc::new(PhysicalVfs::default()),
+        }
+    }
+}
+
+impl Resolver for NodeResolver {
+    fn resolve<'js>(&mut self, ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        let dir = Path::new(base).parent().unwrap_or_else(|| Path::new(""));
+        let path = if name.starts_with("./")
+            || name.starts_with("../")
+            || name == "."
+            || name == ".."
+            || Path::new(name).is_absolute()
+        {
+            self.resolve_path(ctx, &normalize(&dir.join(name)))?
+        } else if name.starts_with('#') {
+            self.resolve_imports(ctx, dir, name)?
+        } else {
+            self.resolve_package(ctx, dir, name)?
+        };
+        path.map(|path| path.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::new_resolving(base, name))
+    }
+}
+
+/// Remove the `.` and `..` components of a path without accessing the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loader::MemoryVfs, Context, Runtime};
+    use std::fs;
+
+    #[test]
+    fn resolution() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-node-{}", std::process::id()));
+        let files = [
+            ("main.js", ""),
+            ("config.js", ""),
+            ("lib/index.js", ""),
+            (
+                "package.json",
+                r##"{ "imports": { "#config": { "default": "./config.js" }, "#dep": "legacy" } }"##,
+            ),
+            (
+                "node_modules/@scope/pkg/package.json",
+                r#"{
+                    "exports": {
+                        ".": { "require": "./cjs/index.cjs", "import": "./esm/index.js" },
+                        "./utils/*": "./src/utils/*.js",
+                        "./utils/internal/*": null,
+                        "./package.json": "./package.json"
+                    }
+                }"#,
+            ),
+            ("node_modules/@scope/pkg/esm/index.js", ""),
+            ("node_modules/@scope/pkg/cjs/index.cjs", ""),
+            ("node_modules/@scope/pkg/src/utils/a.js", ""),
+            ("node_modules/@scope/pkg/src/utils/internal/b.js", ""),
+            (
+                "node_modules/legacy/package.json",
+                r#"{ "main": "lib/main" }"#,
+            ),
+            ("node_modules/legacy/lib/main.js", ""),
+            ("node_modules/legacy/other/index.js", ""),
+        ];
+        for (name, source) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        let main = dir.join("main.js").to_string_lossy().into_owned();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let mut resolver = NodeResolver::default();
+            let mut resolve = |name| resolver.resolve(&ctx, &main, name).ok();
+            assert_eq!(resolve("./config").unwrap(), path("config.js"));
+            assert_eq!(resolve("./lib").unwrap(), path("lib/index.js"));
+            assert_eq!(
+                resolve("@scope/pkg").unwrap(),
+                path("node_modules/@scope/pkg/esm/index.js")
+            );
+            assert_eq!(
+                resolve("@scope/pkg/utils/a").unwrap(),
+                path("node_modules/@scope/pkg/src/utils/a.js")
+            );
+            assert!(resolve("@scope/pkg/utils/internal/b").is_none());
+            assert!(resolve("@scope/pkg/src/utils/a.js").is_none());
+            assert_eq!(
+                resolve("legacy").unwrap(),
+                path("node_modules/legacy/lib/main.js")
+            );
+            assert_eq!(
+                resolve("legacy/other").unwrap(),
+                path("node_modules/legacy/other/index.js")
+            );
+            assert_eq!(resolve("#config").unwrap(), path("config.js"));
+            assert_eq!(
+                resolve("#dep").unwrap(),
+                path("node_modules/legacy/lib/main.js")
+            );
+            assert!(resolve("#missing").is_none());
+            assert!(resolve("missing").is_none());
+
+            let mut resolver = NodeResolver::default();
+            resolver.clear_conditions().add_condition("require");
+            let res = resolver.resolve(&ctx, &main, "@scope/pkg").unwrap();
+            assert_eq!(res, path("node_modules/@scope/pkg/cjs/index.cjs"));
+        });
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn vfs_resolution() {
+        let vfs = MemoryVfs::default()
+            .with_file("app/main.js", "")
+            .with_file("node_modules/pkg/package.json", r#"{ "main": "lib/main" }"#)
+            .with_file("node_modules/pkg/lib/main.js", "");
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let mut resolver = NodeResolver::default().with_vfs(vfs);
+            let res = resolver.resolve(&ctx, "app/main.js", "pkg").unwrap();
+            assert_eq!(res, "node_modules/pkg/lib/main.js");
+            assert!(resolver.resolve(&ctx, "app/main.js", "../../pkg").is_err());
+        });
+    }
+}
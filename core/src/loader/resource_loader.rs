@@ -0,0 +1,251 @@
+use std::{
+    fmt::{self, Write},
+    sync::Arc,
+};
+
+use crate::{
+    loader::{util::check_extensions, Loader, PhysicalVfs, Vfs},
+    module::Declared,
+    Ctx, Error, Module, Result,
+};
+
+/// The kind of module created for a resource by the [`ResourceLoader`]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    /// The resource is parsed as JSON and exported as a deeply frozen value
+    Json,
+    /// The resource is exported as a string, it must be valid UTF-8
+    Text,
+    /// The resource is exported as an `Uint8Array`
+    Bytes,
+}
+
+/// The resource module loader
+///
+/// This loader turns files which are not scripts into modules with a single default export,
+/// so they can be imported like `import config from "./config.json"`. The kind of module is
+/// selected by the extension of the file, by default `json` files are imported as
+/// [`MediaType::Json`] and `txt` files as [`MediaType::Text`].
+///
+/// QuickJS does not pass import attributes to module loaders, so `with { type: "json" }` can't
+/// be used to select the media type.
+///
+/// Resources are read from a [`Vfs`], the [`PhysicalVfs`] of the whole disk by default.
+///
+/// This loader can be used as the nested backing loader in user-defined loaders.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+pub struct ResourceLoader {
+    extensions: Vec<(String, MediaType)>,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl fmt::Debug for ResourceLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceLoader")
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+impl ResourceLoader {
+    /// Add a file extension and the media type of the files
+    pub fn add_extension<X: Into<String>>(&mut self, extension: X, media: MediaType) -> &mut Self {
+        let extension = extension.into();
+        self.extensions.retain(|(known, _)| *known != extension);
+        self.extensions.push((extension, media));
+        self
+    }
+
+    /// Add a file extension and the media type of the files
+    #[must_use]
+    pub fn with_extension<X: Into<String>>(mut self, extension: X, media: MediaType) -> Self {
+        self.add_extension(extension, media);
+        self
+    }
+
+    /// Read the resources from a virtual file system instead of the disk
+    pub fn set_vfs<V: Vfs + 'static>(&mut self, vfs: V) -> &mut Self {
+        self.vfs = Arc::new(vfs);
+        self
+    }
+
+    /// Read the resources from a virtual file system instead of the disk
+    #[must_use]
+    pub fn with_vfs<V: Vfs + 'static>(mut self, vfs: V) -> Self {
+        self.set_vfs(vfs);
+        self
+    }
+
+    fn media_type(&self, path: &str) -> Option<MediaType> {
+        self.extensions
+            .iter()
+            .find(|(extension, _)| check_extensions(path, std::slice::from_ref(extension)))
+            .map(|(_, media)| *media)
+    }
+}
+
+impl Default for ResourceLoader {
+    fn default() -> Self {
+        Self {
+            extensions: vec![
+                ("json".into(), MediaType::Json),
+                ("txt".into(), MediaType::Text),
+            ],
+            vfs: Arc::new(PhysicalVfs::default()),
+        }
+    }
+}
+
+impl Loader for ResourceLoader {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, path: &str) -> Result<Module<'js, Declared>> {
+        let media = self
+            .media_type(path)
+            .ok_or_else(|| Error::new_loading(path))?;
+        let data = self.vfs.read(&self.vfs.normalize_path(path)?)?;
+        let source = module_source(path, media, data)?;
+        Module::declare(ctx.clone(), path, source)
+    }
+}
+
+/// Returns the source of a module exporting the resource.
+fn module_source(path: &str, media: MediaType, data: Vec<u8>) -> Result<String> {
+    let text = |data| {
+        String::from_utf8(data)
+            .map_err(|_| Error::new_loading_message(path, "resource is not valid UTF-8"))
+    };
+    Ok(match media {
+        MediaType::Json => format!(
+            "const freeze = (value) => {{\
+                if (value !== null && typeof value === 'object') {{\
+                    Object.values(value).forEach(freeze);\
+                    Object.freeze(value);\
+                }}\
+                return value;\
+            }};\
+            export default freeze(JSON.parse({}));",
+            string_literal(&text(data)?)
+        ),
+        MediaType::Text => format!("export default {};", string_literal(&text(data)?)),
+        MediaType::Bytes => {
+            let mut source = String::from("export default new Uint8Array([");
+            for byte in data {
+                write!(source, "{byte},").unwrap();
+            }
+            source.push_str("]);");
+            source
+        }
+    })
+}
+
+/// Returns a JavaScript string literal of the text.
+fn string_literal(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\u{2028}' | '\u{2029}' => write!(literal, "\\u{:04x}", c as u32).unwrap(),
+            c if c.is_control() => write!(literal, "\\u{:04x}", c as u32).unwrap(),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        loader::{BuiltinResolver, MemoryVfs},
+        Context, Function, Object, Runtime,
+    };
+
+    #[test]
+    fn literals() {
+        assert_eq!(string_literal("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+        assert_eq!(string_literal("\u{2028}"), r#""\u2028""#);
+    }
+
+    #[test]
+    fn media_types() {
+        let loader = ResourceLoader::default().with_extension("bin", MediaType::Bytes);
+        assert_eq!(loader.media_type("a/config.json"), Some(MediaType::Json));
+        assert_eq!(loader.media_type("notes.txt"), Some(MediaType::Text));
+        assert_eq!(loader.media_type("data.bin"), Some(MediaType::Bytes));
+        assert_eq!(loader.media_type("main.js"), None);
+
+        assert_eq!(
+            module_source("data.bin", MediaType::Bytes, vec![1, 2]).unwrap(),
+            "export default new Uint8Array([1,2,]);"
+        );
+        assert!(module_source("notes.txt", MediaType::Text, vec![0xff]).is_err());
+    }
+
+    #[test]
+    fn import_resources() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-resources-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("config.json").to_string_lossy().into_owned();
+        let text = dir.join("notes.txt").to_string_lossy().into_owned();
+        std::fs::write(&json, r#"{ "name": "test", "list": [1, 2] }"#).unwrap();
+        std::fs::write(&text, "line \"one\"\nline two").unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_loader(
+            BuiltinResolver::default()
+                .with_module(json.as_str())
+                .with_module(text.as_str()),
+            ResourceLoader::default(),
+        );
+        ctx.with(|ctx| {
+            let config: Object = Module::import(&ctx, json.as_str())
+                .unwrap()
+                .finish::<Object>()
+                .unwrap()
+                .get("default")
+                .unwrap();
+            assert_eq!(config.get::<_, String>("name").unwrap(), "test");
+            let is_frozen: Function = ctx.eval("Object.isFrozen").unwrap();
+            let list: Object = config.get("list").unwrap();
+            assert!(is_frozen.call::<_, bool>((list,)).unwrap());
+
+            let notes: String = Module::import(&ctx, text.as_str())
+                .unwrap()
+                .finish::<Object>()
+                .unwrap()
+                .get("default")
+                .unwrap();
+            assert_eq!(notes, "line \"one\"\nline two");
+        });
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_from_vfs() {
+        let vfs = MemoryVfs::default().with_file("data/notes.txt", "from memory");
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_loader(
+            BuiltinResolver::default().with_module("data/notes.txt"),
+            ResourceLoader::default().with_vfs(vfs),
+        );
+        ctx.with(|ctx| {
+            let notes: String = Module::import(&ctx, "data/notes.txt")
+                .unwrap()
+                .finish::<Object>()
+                .unwrap()
+                .get("default")
+                .unwrap();
+            assert_eq!(notes, "from memory");
+        });
+    }
+}
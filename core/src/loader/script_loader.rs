@@ -1,14 +1,44 @@
+use std::{fmt, sync::Arc};
+
 use crate::{
-    loader::{util::check_extensions, Loader},
+    loader::{util::check_extensions, Loader, PhysicalVfs, SourceMaps, Transformer, Vfs},
     Ctx, Error, Module, Result,
 };
 
 /// The script module loader
 ///
 /// This loader can be used as the nested backing loader in user-defined loaders.
-#[derive(Debug)]
+///
+/// Modules with an extension which has a [`Transformer`] registered are transformed before they
+/// are compiled, so for example TypeScript modules can be imported directly:
+///
+/// ```no_run
+/// # use rquickjs::{loader::{FileResolver, ScriptLoader, Transformed}, Result, Runtime};
+/// # fn transpile(source: &[u8]) -> Result<Transformed> { Ok(Transformed::new(source)) }
+/// let rt = Runtime::new().unwrap();
+/// let loader = ScriptLoader::default()
+///     .with_transformer("ts", |_name: &str, source: Vec<u8>| transpile(&source));
+/// rt.set_loader(FileResolver::default().with_pattern("{}.ts"), loader);
+/// ```
+///
+/// Scripts are read from a [`Vfs`], the [`PhysicalVfs`] of the whole disk by default.
 pub struct ScriptLoader {
     extensions: Vec<String>,
+    transformers: Vec<(String, Box<dyn Transformer + Send>)>,
+    source_maps: SourceMaps,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl fmt::Debug for ScriptLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptLoader")
+            .field("extensions", &self.extensions)
+            .field(
+                "transformers",
+                &self.transformers.iter().map(|x| &x.0).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
 }
 
 impl ScriptLoader {
@@ -24,23 +54,90 @@ impl ScriptLoader {
         self.add_extension(extension);
         self
     }
+
+    /// Add script file extension whose sources are transformed with `transformer`
+    pub fn add_transformer<X, T>(&mut self, extension: X, transformer: T) -> &mut Self
+    where
+        X: Into<String>,
+        T: Transformer + Send + 'static,
+    {
+        let extension = extension.into();
+        self.transformers
+            .retain(|(known_extension, _)| *known_extension != extension);
+        self.transformers
+            .push((extension.clone(), Box::new(transformer)));
+        self.add_extension(extension)
+    }
+
+    /// Add script file extension whose sources are transformed with `transformer`
+    #[must_use]
+    pub fn with_transformer<X, T>(mut self, extension: X, transformer: T) -> Self
+    where
+        X: Into<String>,
+        T: Transformer + Send + 'static,
+    {
+        self.add_transformer(extension, transformer);
+        self
+    }
+
+    /// Read the scripts from a virtual file system instead of the disk
+    pub fn set_vfs<V: Vfs + 'static>(&mut self, vfs: V) -> &mut Self {
+        self.vfs = Arc::new(vfs);
+        self
+    }
+
+    /// Read the scripts from a virtual file system instead of the disk
+    #[must_use]
+    pub fn with_vfs<V: Vfs + 'static>(mut self, vfs: V) -> Self {
+        self.set_vfs(vfs);
+        self
+    }
+
+    /// Get the source maps returned by the transformers
+    pub fn source_maps(&self) -> SourceMaps {
+        self.source_maps.clone()
+    }
+
+    /// Read the source of the script at `path`.
+    pub(crate) fn read_source(&self, path: &str) -> Result<Vec<u8>> {
+        if !check_extensions(path, &self.extensions) {
+            return Err(Error::new_loading(path));
+        }
+
+        Ok(self.vfs.read(&self.vfs.normalize_path(path)?)?)
+    }
+
+    /// Apply the transformer registered for the extension of `path` to a source.
+    pub(crate) fn transform(&mut self, path: &str, source: Vec<u8>) -> Result<Vec<u8>> {
+        let transformer = self
+            .transformers
+            .iter_mut()
+            .find(|(extension, _)| check_extensions(path, std::slice::from_ref(extension)));
+        let Some((_, transformer)) = transformer else {
+            return Ok(source);
+        };
+
+        let transformed = transformer.transform(path, source)?;
+        self.source_maps.set(path, transformed.source_map);
+        Ok(transformed.source)
+    }
 }
 
 impl Default for ScriptLoader {
     fn default() -> Self {
         Self {
             extensions: vec!["js".into()],
+            transformers: Vec::new(),
+            source_maps: SourceMaps::default(),
+            vfs: Arc::new(PhysicalVfs::default()),
         }
     }
 }
 
 impl Loader for ScriptLoader {
     fn load<'js>(&mut self, ctx: &Ctx<'js>, path: &str) -> Result<Module<'js>> {
-        if !check_extensions(path, &self.extensions) {
-            return Err(Error::new_loading(path));
-        }
-
-        let source: Vec<_> = std::fs::read(path)?;
+        let source = self.read_source(path)?;
+        let source = self.transform(path, source)?;
         Module::declare(ctx.clone(), path, source)
     }
 }
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Mut, Ref, Result};
+
+/// The output of a [`Transformer`]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transformed {
+    /// The transformed JavaScript source
+    pub source: Vec<u8>,
+    /// The source map from the transformed source to the original source, if any
+    pub source_map: Option<String>,
+}
+
+impl Transformed {
+    /// Create an output without a source map
+    pub fn new<S: Into<Vec<u8>>>(source: S) -> Self {
+        Transformed {
+            source: source.into(),
+            source_map: None,
+        }
+    }
+
+    /// Set the source map
+    #[must_use]
+    pub fn with_source_map<M: Into<String>>(mut self, source_map: M) -> Self {
+        self.source_map = Some(source_map.into());
+        self
+    }
+}
+
+/// Module source transformer interface
+///
+/// A transformer turns the source of a module into JavaScript before it is compiled, for example
+/// by transpiling TypeScript or JSX. Transformers are registered for a file extension with
+/// [`ScriptLoader::with_transformer`](crate::loader::ScriptLoader::with_transformer).
+///
+/// The trait is implemented for closures:
+///
+/// ```
+/// # use rquickjs::loader::{ScriptLoader, Transformed};
+/// let loader = ScriptLoader::default().with_transformer("mjs", |_name: &str, source: Vec<u8>| {
+///     Ok(Transformed::new(source))
+/// });
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+pub trait Transformer {
+    /// Transform the source of the module with the given name
+    fn transform(&mut self, name: &str, source: Vec<u8>) -> Result<Transformed>;
+}
+
+impl<F> Transformer for F
+where
+    F: FnMut(&str, Vec<u8>) -> Result<Transformed>,
+{
+    fn transform(&mut self, name: &str, source: Vec<u8>) -> Result<Transformed> {
+        self(name, source)
+    }
+}
+
+/// The source maps returned by the transformers of a loader
+///
+/// The handle can be cloned and queried after the loader was moved into a runtime.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Clone, Default)]
+pub struct SourceMaps(Ref<Mut<HashMap<String, String>>>);
+
+impl fmt::Debug for SourceMaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.lock().iter()).finish()
+    }
+}
+
+impl SourceMaps {
+    /// Get the source map of the module with the given name
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.lock().get(name).cloned()
+    }
+
+    pub(crate) fn set(&self, name: &str, source_map: Option<String>) {
+        let mut maps = self.0.lock();
+        match source_map {
+            Some(map) => maps.insert(name.to_string(), map),
+            None => maps.remove(name),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::loader::{ScriptLoader, Transformed};
+
+    #[test]
+    fn transform_by_extension() {
+        let mut loader =
+            ScriptLoader::default().with_transformer("ts", |name: &str, source: Vec<u8>| {
+                let source = String::from_utf8(source).unwrap().replace(": number", "");
+                Ok(Transformed::new(source).with_source_map(format!("map of {name}")))
+            });
+        let maps = loader.source_maps();
+
+        let source = loader
+            .transform("a.ts", b"let a: number = 1".to_vec())
+            .unwrap();
+        assert_eq!(source, b"let a = 1");
+        assert_eq!(maps.get("a.ts").as_deref(), Some("map of a.ts"));
+
+        let source = loader.transform("b.js", b"let b = 1".to_vec()).unwrap();
+        assert_eq!(source, b"let b = 1");
+        assert_eq!(maps.get("b.js"), None);
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// The metadata of an entry of a [`Vfs`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// The size of a file in bytes, `0` for directories
+    pub len: u64,
+}
+
+/// A virtual file system
+///
+/// Used by the loaders and resolvers to find and read modules, and by the `host:fs` module of
+/// [`HostModules`](super::HostModules), so the same code can run on the disk with
+/// [`PhysicalVfs`], in memory with [`MemoryVfs`] in tests, or on a combination of both with
+/// [`OverlayVfs`].
+///
+/// Paths are relative to the root of the file system, normalized and never leave the root:
+/// they don't contain `.` or `..` components and use `/` as separator. The root itself is the
+/// empty path.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+pub trait Vfs: Send + Sync {
+    /// Turn the name of a module into a path of the file system.
+    ///
+    /// By default the name is normalized, failing if it leaves the root.
+    fn normalize_path(&self, path: &str) -> io::Result<String> {
+        normalize(path)
+    }
+
+    /// Open a file for reading.
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Returns the metadata of a file or directory.
+    fn stat(&self, path: &str) -> io::Result<Metadata>;
+
+    /// Returns the sorted names of the entries of a directory.
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>>;
+
+    /// Read the contents of a file.
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.open(path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Write a file, creating it and its parent directories if they don't exist.
+    ///
+    /// The file system is read-only by default.
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let _ = (path, data);
+        Err(read_only())
+    }
+
+    /// Remove a file.
+    ///
+    /// The file system is read-only by default.
+    fn remove(&self, path: &str) -> io::Result<()> {
+        let _ = path;
+        Err(read_only())
+    }
+}
+
+impl<V: Vfs + ?Sized> Vfs for Arc<V> {
+    fn normalize_path(&self, path: &str) -> io::Result<String> {
+        (**self).normalize_path(path)
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        (**self).open(path)
+    }
+
+    fn stat(&self, path: &str) -> io::Result<Metadata> {
+        (**self).stat(path)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        (**self).read_dir(path)
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        (**self).read(path)
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        (**self).write(path, data)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        (**self).remove(path)
+    }
+}
+
+/// A [`Vfs`] kept in memory, directories exist as long as they contain a file
+///
+/// Clones share the files, so a file system can be filled in a test and read by a loader.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Debug, Default, Clone)]
+pub struct MemoryVfs {
+    files: Arc<RwLock<BTreeMap<String, Arc<[u8]>>>>,
+}
+
+impl MemoryVfs {
+    /// Add a file, the path must be normalized like the paths passed to [`Vfs`]
+    pub fn add_file<P: Into<String>, D: Into<Vec<u8>>>(&mut self, path: P, data: D) -> &mut Self {
+        let data: Vec<u8> = data.into();
+        self.files.write().unwrap().insert(path.into(), data.into());
+        self
+    }
+
+    /// Add a file, the path must be normalized like the paths passed to [`Vfs`]
+    #[must_use]
+    pub fn with_file<P: Into<String>, D: Into<Vec<u8>>>(mut self, path: P, data: D) -> Self {
+        self.add_file(path, data);
+        self
+    }
+}
+
+/// Returns the names of the entries of `dir` among the paths of files.
+fn children<'a, I: Iterator<Item = &'a String>>(paths: I, dir: &str) -> BTreeSet<&'a str> {
+    paths
+        .filter_map(|path| {
+            let rest = if dir.is_empty() {
+                path.as_str()
+            } else {
+                path.strip_prefix(dir)?.strip_prefix('/')?
+            };
+            rest.split('/').next()
+        })
+        .collect()
+}
+
+impl Vfs for MemoryVfs {
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        let data = self.files.read().unwrap().get(path).cloned();
+        let data = data.ok_or_else(not_found)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn stat(&self, path: &str) -> io::Result<Metadata> {
+        let files = self.files.read().unwrap();
+        if let Some(data) = files.get(path) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: data.len() as u64,
+            });
+        }
+        if path.is_empty() || !children(files.keys(), path).is_empty() {
+            return Ok(Metadata {
+                is_dir: true,
+                len: 0,
+            });
+        }
+        Err(not_found())
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let files = self.files.read().unwrap();
+        let names = children(files.keys(), path);
+        if names.is_empty() && !path.is_empty() {
+            return Err(not_found());
+        }
+        Ok(names.into_iter().map(Into::into).collect())
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let mut files = self.files.write().unwrap();
+        if path.is_empty() || !children(files.keys(), path).is_empty() {
+            return Err(io::Error::new(io::ErrorKind::Other, "path is a directory"));
+        }
+        files.insert(path.into(), data.into());
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        let mut files = self.files.write().unwrap();
+        files.remove(path).map(|_| ()).ok_or_else(not_found)
+    }
+}
+
+/// A [`Vfs`] backed by the disk
+///
+/// A file system created with [`PhysicalVfs::new`] is rooted at a directory. Besides the
+/// normalized paths, symbolic links are checked to not point outside of the directory.
+/// Dangling symbolic links are rejected, since writing to them would create their target.
+///
+/// The default file system is the whole disk, it is used by the loaders and resolvers when no
+/// other file system is set. Its paths are the names of the modules as they are, absolute or
+/// relative to the current directory.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Debug, Clone, Default)]
+pub struct PhysicalVfs {
+    root: Option<PathBuf>,
+}
+
+impl PhysicalVfs {
+    /// Create a file system rooted at an existing directory
+    pub fn new<P: Into<PathBuf>>(root: P) -> io::Result<Self> {
+        let root = fs::canonicalize(root.into())?;
+        Ok(PhysicalVfs { root: Some(root) })
+    }
+
+    /// Returns the real path of a file, checking that every symbolic link on the path resolves
+    /// to a path inside of the root.
+    fn real_path(&self, path: &str) -> io::Result<PathBuf> {
+        let Some(root) = &self.root else {
+            return Ok(PathBuf::from(path));
+        };
+        let mut current = root.clone();
+        for component in Path::new(path).components() {
+            current.push(component);
+            match fs::symlink_metadata(&current) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    // Fails for dangling links.
+                    let target = fs::canonicalize(&current).map_err(|_| escapes_root())?;
+                    if !target.starts_with(root) {
+                        return Err(escapes_root());
+                    }
+                }
+                Ok(_) => {}
+                // The components after a missing one don't exist either.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(root.join(path))
+    }
+}
+
+impl Vfs for PhysicalVfs {
+    fn normalize_path(&self, path: &str) -> io::Result<String> {
+        match self.root {
+            Some(_) => normalize(path),
+            None => Ok(path.into()),
+        }
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(fs::File::open(self.real_path(path)?)?))
+    }
+
+    fn stat(&self, path: &str) -> io::Result<Metadata> {
+        let metadata = fs::metadata(self.real_path(path)?)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+        })
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let mut names = fs::read_dir(self.real_path(path)?)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.real_path(path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        fs::remove_file(self.real_path(path)?)
+    }
+}
+
+/// A [`Vfs`] which layers a writable file system over a base
+///
+/// Reads look at the upper file system first and fall back to the lower one, directories list
+/// the entries of both. Writes and removals only affect the upper file system, so files of the
+/// lower file system can be shadowed but not removed. A common use is a [`MemoryVfs`] over a
+/// [`PhysicalVfs`] to let scripts write without changing the disk.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+#[derive(Clone)]
+pub struct OverlayVfs {
+    upper: Arc<dyn Vfs>,
+    lower: Arc<dyn Vfs>,
+}
+
+impl OverlayVfs {
+    /// Create an overlay of `upper` over `lower`
+    pub fn new<U: Vfs + 'static, L: Vfs + 'static>(upper: U, lower: L) -> Self {
+        OverlayVfs {
+            upper: Arc::new(upper),
+            lower: Arc::new(lower),
+        }
+    }
+}
+
+/// Returns whether the error of the upper file system lets the lower one be tried.
+fn fall_through(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::NotFound
+}
+
+impl Vfs for OverlayVfs {
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        match self.upper.open(path) {
+            Err(e) if fall_through(&e) => self.lower.open(path),
+            res => res,
+        }
+    }
+
+    fn stat(&self, path: &str) -> io::Result<Metadata> {
+        match self.upper.stat(path) {
+            Err(e) if fall_through(&e) => self.lower.stat(path),
+            res => res,
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let upper = self.upper.read_dir(path);
+        let lower = self.lower.read_dir(path);
+        let (upper, lower) = match (upper, lower) {
+            (Err(e), Err(_)) => return Err(e),
+            (upper, lower) => (upper.unwrap_or_default(), lower.unwrap_or_default()),
+        };
+        let names: BTreeSet<String> = upper.into_iter().chain(lower).collect();
+        Ok(names.into_iter().collect())
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        self.upper.write(path, data)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        self.upper.remove(path)
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no such file or directory")
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "file system is read-only")
+}
+
+fn escapes_root() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "path escapes the root")
+}
+
+/// Normalize a path, failing if it leaves the root.
+pub(crate) fn normalize(path: &str) -> io::Result<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop().ok_or_else(escapes_root)?;
+            }
+            part if part.contains(['\\', ':', '\0']) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid character in path",
+                ))
+            }
+            part => parts.push(part),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize("/a/./b//c.txt").unwrap(), "a/b/c.txt");
+        assert_eq!(normalize("a/../b").unwrap(), "b");
+        assert_eq!(normalize("/").unwrap(), "");
+        assert!(normalize("../etc/passwd").is_err());
+        assert!(normalize("a/../../b").is_err());
+        assert!(normalize("a\\..\\..\\b").is_err());
+        assert!(normalize("C:/b").is_err());
+    }
+
+    #[test]
+    fn memory_vfs() {
+        let vfs = MemoryVfs::default().with_file("a/b.txt", "b");
+        vfs.write("a/c/d.txt", b"d").unwrap();
+        assert_eq!(vfs.read_dir("").unwrap(), ["a"]);
+        assert_eq!(vfs.read_dir("a").unwrap(), ["b.txt", "c"]);
+        assert!(vfs.read_dir("x").is_err());
+        assert!(vfs.stat("a/c").unwrap().is_dir);
+        assert_eq!(vfs.stat("a/c/d.txt").unwrap().len, 1);
+        assert!(vfs.stat("a/x").is_err());
+        assert!(vfs.write("a", b"x").is_err());
+        // Clones share the files.
+        vfs.clone().remove("a/b.txt").unwrap();
+        assert!(vfs.read("a/b.txt").is_err());
+    }
+
+    #[test]
+    fn physical_vfs() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-vfs-{}", std::process::id()));
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let vfs = PhysicalVfs::new(&root).unwrap();
+        vfs.write("sub/file.txt", b"data").unwrap();
+        assert_eq!(vfs.read("sub/file.txt").unwrap(), b"data");
+        assert_eq!(vfs.read_dir("").unwrap(), ["sub"]);
+        assert!(vfs.stat("sub").unwrap().is_dir);
+        assert!(vfs.normalize_path("../secret.txt").is_err());
+
+        // The default file system takes the paths of the disk as they are.
+        let disk = PhysicalVfs::default();
+        let path = root.join("../secret.txt").to_string_lossy().into_owned();
+        assert_eq!(disk.normalize_path(&path).unwrap(), path);
+        assert_eq!(disk.read(&path).unwrap(), b"secret");
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&dir, root.join("link")).unwrap();
+            let err = vfs.read("link/secret.txt").unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+            std::os::unix::fs::symlink(dir.join("created.txt"), root.join("dangling")).unwrap();
+            let err = vfs.write("dangling", b"data").unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert!(!dir.join("created.txt").exists());
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn overlay_vfs() {
+        let lower = MemoryVfs::default()
+            .with_file("a.txt", "lower a")
+            .with_file("b.txt", "lower b");
+        let vfs = OverlayVfs::new(MemoryVfs::default(), lower.clone());
+        vfs.write("a.txt", b"upper a").unwrap();
+        vfs.write("c.txt", b"upper c").unwrap();
+        assert_eq!(vfs.read("a.txt").unwrap(), b"upper a");
+        assert_eq!(vfs.read("b.txt").unwrap(), b"lower b");
+        assert_eq!(vfs.read_dir("").unwrap(), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(lower.read("a.txt").unwrap(), b"lower a");
+        assert!(vfs.remove("b.txt").is_err());
+        vfs.remove("a.txt").unwrap();
+        assert_eq!(vfs.read("a.txt").unwrap(), b"lower a");
+    }
+
+    #[test]
+    fn loader_and_host_fs() {
+        use crate::{
+            loader::{FileResolver, HostModules, ScriptLoader},
+            Context, Module, Runtime,
+        };
+
+        let vfs = MemoryVfs::default()
+            .with_file("lib/util.js", "export const name = 'util';")
+            .with_file("data/input.txt", "input");
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let host = HostModules::default().with_fs(vfs.clone());
+        rt.set_loader(
+            (
+                host.clone(),
+                FileResolver::default()
+                    .with_path("lib")
+                    .with_vfs(vfs.clone()),
+            ),
+            (host, ScriptLoader::default().with_vfs(vfs.clone())),
+        );
+        ctx.with(|ctx| {
+            let source = r#"
+                import { name } from "util";
+                import { readFile, writeFile } from "host:fs";
+                writeFile("out.txt", `${name} ${readFile("data/input.txt")}`);
+            "#;
+            Module::evaluate(ctx.clone(), "main.js", source)
+                .unwrap()
+                .finish::<()>()
+                .unwrap();
+        });
+        assert_eq!(vfs.read("out.txt").unwrap(), b"util input");
+    }
+}
@@ -0,0 +1,172 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    loader::{util::check_extensions, Loader, PhysicalVfs, Vfs},
+    module::Declared,
+    Ctx, Error, Module, Object, Result,
+};
+
+/// WebAssembly module instantiation interface
+///
+/// QuickJS has no WebAssembly support, so instantiating a `.wasm` module is left to the host,
+/// for example by using a Rust WebAssembly runtime and wrapping the exports of the instance in
+/// JavaScript functions.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+pub trait WasmInstantiator {
+    /// Instantiate the WebAssembly binary of a module
+    ///
+    /// Returns an object whose properties are the exports of the module.
+    fn instantiate<'js>(
+        &mut self,
+        ctx: &Ctx<'js>,
+        name: &str,
+        binary: Vec<u8>,
+    ) -> Result<Object<'js>>;
+}
+
+/// The WebAssembly module loader
+///
+/// This loader routes imports of `.wasm` files to a host provided [`WasmInstantiator`]. The
+/// properties of the object returned by the instantiator become the exports of the module:
+///
+/// ```no_run
+/// # use rquickjs::{loader::{FileResolver, WasmInstantiator, WasmLoader}, Ctx, Function, Object, Result, Runtime};
+/// struct Host;
+///
+/// impl WasmInstantiator for Host {
+///     fn instantiate<'js>(&mut self, ctx: &Ctx<'js>, name: &str, binary: Vec<u8>) -> Result<Object<'js>> {
+///         // compile and instantiate the binary with a WebAssembly runtime
+///         let exports = Object::new(ctx.clone())?;
+///         exports.set("add", Function::new(ctx.clone(), |a: i32, b: i32| a + b)?)?;
+///         Ok(exports)
+///     }
+/// }
+///
+/// let rt = Runtime::new().unwrap();
+/// rt.set_loader(
+///     FileResolver::default().with_pattern("{}.wasm"),
+///     WasmLoader::new(Host),
+/// );
+/// ```
+///
+/// The binaries are read from a [`Vfs`], the [`PhysicalVfs`] of the whole disk by default.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+pub struct WasmLoader<I> {
+    instantiator: I,
+    extensions: Vec<String>,
+    vfs: Arc<dyn Vfs>,
+}
+
+impl<I: fmt::Debug> fmt::Debug for WasmLoader<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmLoader")
+            .field("instantiator", &self.instantiator)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+impl<I: WasmInstantiator> WasmLoader<I> {
+    /// Create a new loader for `.wasm` files
+    pub fn new(instantiator: I) -> Self {
+        WasmLoader {
+            instantiator,
+            extensions: vec!["wasm".into()],
+            vfs: Arc::new(PhysicalVfs::default()),
+        }
+    }
+
+    /// Add WebAssembly file extension
+    pub fn add_extension<X: Into<String>>(&mut self, extension: X) -> &mut Self {
+        self.extensions.push(extension.into());
+        self
+    }
+
+    /// Add WebAssembly file extension
+    #[must_use]
+    pub fn with_extension<X: Into<String>>(mut self, extension: X) -> Self {
+        self.add_extension(extension);
+        self
+    }
+
+    /// Read the binaries from a virtual file system instead of the disk
+    pub fn set_vfs<V: Vfs + 'static>(&mut self, vfs: V) -> &mut Self {
+        self.vfs = Arc::new(vfs);
+        self
+    }
+
+    /// Read the binaries from a virtual file system instead of the disk
+    #[must_use]
+    pub fn with_vfs<V: Vfs + 'static>(mut self, vfs: V) -> Self {
+        self.set_vfs(vfs);
+        self
+    }
+}
+
+impl<I: WasmInstantiator> Loader for WasmLoader<I> {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, path: &str) -> Result<Module<'js, Declared>> {
+        if !check_extensions(path, &self.extensions) {
+            return Err(Error::new_loading(path));
+        }
+
+        let binary = self.vfs.read(&self.vfs.normalize_path(path)?)?;
+        let exports = self.instantiator.instantiate(ctx, path, binary)?;
+        Module::declare_object(ctx.clone(), path, exports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        loader::{BuiltinLoader, BuiltinResolver},
+        Context, Function, Runtime,
+    };
+
+    struct Host;
+
+    impl WasmInstantiator for Host {
+        fn instantiate<'js>(
+            &mut self,
+            ctx: &Ctx<'js>,
+            _name: &str,
+            binary: Vec<u8>,
+        ) -> Result<Object<'js>> {
+            let exports = Object::new(ctx.clone())?;
+            exports.set("size", binary.len())?;
+            exports.set("add", Function::new(ctx.clone(), |a: i32, b: i32| a + b)?)?;
+            Ok(exports)
+        }
+    }
+
+    #[test]
+    fn import_wasm() {
+        let dir = std::env::temp_dir().join(format!("rquickjs-wasm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("math.wasm").to_string_lossy().into_owned();
+        std::fs::write(&path, b"\0asm\x01\0\0\0").unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_loader(
+            BuiltinResolver::default()
+                .with_module(path.as_str())
+                .with_module("main"),
+            (
+                WasmLoader::new(Host),
+                BuiltinLoader::default().with_module(
+                    "main",
+                    format!(
+                        "import {{ add, size }} from {path:?}; export const res = add(size, 2);"
+                    ),
+                ),
+            ),
+        );
+        ctx.with(|ctx| {
+            let namespace: Object = Module::import(&ctx, "main").unwrap().finish().unwrap();
+            assert_eq!(namespace.get::<_, i32>("res").unwrap(), 10);
+        });
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+//! The `performance` global with `now()` read from a host [`Clock`].
+//!
+//! Scripts measure time with `performance.now()`, which returns the milliseconds elapsed on the
+//! clock as a fractional number. The host chooses where the time comes from: a
+//! [`MonotonicClock`] for real time, a [`ManualClock`] which only moves when told to for tests
+//! and deterministic execution, or any other [`Clock`].
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use rquickjs::{performance::{self, ManualClock}, Context, Runtime};
+//! let rt = Runtime::new().unwrap();
+//! let ctx = Context::full(&rt).unwrap();
+//! let clock = ManualClock::default();
+//! ctx.with(|ctx| {
+//!     performance::define(&ctx, clock.clone()).unwrap();
+//!     clock.advance(Duration::from_micros(1500));
+//!     assert_eq!(ctx.eval::<f64, _>("performance.now()").unwrap(), 1.5);
+//! });
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{Ctx, Function, Object, Result};
+
+/// A source of the time returned by `performance.now()`.
+pub trait Clock: Send + Sync {
+    /// The time elapsed since the origin of the clock.
+    ///
+    /// It should never go backwards.
+    fn now(&self) -> Duration;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> Duration + Send + Sync,
+{
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// A clock measuring real time from its creation, which is never adjusted.
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    origin: Instant,
+}
+
+impl MonotonicClock {
+    /// Create a clock starting now.
+    pub fn new() -> Self {
+        MonotonicClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// A clock which only moves when it is advanced, so time is frozen otherwise.
+///
+/// Clones share the time, so a clone kept by the host controls the clock given to scripts.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock at the given time.
+    pub fn new(now: Duration) -> Self {
+        let clock = Self::default();
+        clock.set(now);
+        clock
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |now| {
+                Some(now.saturating_add(nanos))
+            })
+            .ok();
+    }
+
+    /// Set the time of the clock.
+    ///
+    /// Scripts may observe time going backwards when it is set to an earlier time.
+    pub fn set(&self, now: Duration) {
+        let nanos = u64::try_from(now.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.store(nanos, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Define the `performance` global object with a `now()` method reading the given clock.
+pub fn define<C: Clock + 'static>(ctx: &Ctx<'_>, clock: C) -> Result<()> {
+    let performance = Object::new(ctx.clone())?;
+    let now =
+        Function::new(ctx.clone(), move || clock.now().as_secs_f64() * 1000.0)?.with_name("now")?;
+    performance.set("now", now)?;
+    ctx.globals().set("performance", performance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::intrinsic, Context, Runtime};
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new(Duration::from_millis(5));
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(10));
+        assert_eq!(clock.now(), Duration::from_millis(15));
+        shared.set(Duration::ZERO);
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn performance_now() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            define(&ctx, || Duration::from_millis(250)).unwrap();
+            let now: f64 = ctx.eval("performance.now()").unwrap();
+            assert_eq!(now, 250.0);
+        });
+
+        let ctx = Context::builder()
+            .with::<(intrinsic::Eval, intrinsic::Performance)>()
+            .build(&rt)
+            .unwrap();
+        ctx.with(|ctx| {
+            let monotonic: bool = ctx
+                .eval("const a = performance.now(); const b = performance.now(); 0 <= a && a <= b")
+                .unwrap();
+            assert!(monotonic);
+        });
+    }
+}
@@ -8,7 +8,9 @@ use crate::{
 
 use std::{
     fmt,
+    hash::{Hash, Hasher},
     mem::{self, ManuallyDrop},
+    sync::{mpsc::Sender, Arc, Mutex},
 };
 
 /// The trait to help break lifetime rules when JS objects leaves current context via [`Persistent`] wrapper.
@@ -137,6 +139,55 @@ unsafe impl<'js, T> Outlive<'js> for Module<'js, T> {
     type Target<'to> = Module<'to, T>;
 }
 
+/// The value of a dropped [`Persistent`], which is freed the next time the runtime is entered.
+pub(crate) struct DeferredDrop {
+    ptr: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+unsafe impl Send for DeferredDrop {}
+
+impl DeferredDrop {
+    fn new<T>(value: T) -> Self {
+        unsafe fn drop_box<T>(ptr: *mut ()) {
+            mem::drop(Box::from_raw(ptr.cast::<T>()));
+        }
+
+        DeferredDrop {
+            ptr: Box::into_raw(Box::new(value)).cast(),
+            drop: drop_box::<T>,
+        }
+    }
+}
+
+impl Drop for DeferredDrop {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.ptr) }
+    }
+}
+
+/// The value of a persistent, shared between its clones.
+struct Shared<T> {
+    value: Option<T>,
+    /// The function to clone the value, set when the persistent is cloned.
+    clone: Mutex<Option<fn(&T) -> T>>,
+    drop_send: Sender<DeferredDrop>,
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let Some(value) = self.value.take() else {
+            return;
+        };
+        if mem::needs_drop::<T>() {
+            if let Err(error) = self.drop_send.send(DeferredDrop::new(value)) {
+                // The runtime is already freed, leaking the value is the only safe option.
+                mem::forget(error.0);
+            }
+        }
+    }
+}
+
 /// The wrapper for JS values to keep it from GC
 ///
 /// For example you can store JS functions for later use.
@@ -159,20 +210,59 @@ unsafe impl<'js, T> Outlive<'js> for Module<'js, T> {
 /// assert_eq!(res, 1);
 /// ```
 ///
+/// Saved values have the `'static` lifetime, so they can be kept in long-lived Rust state,
+/// for example a registry of callbacks which is filled by scripts and called later.
+/// ```
+/// # use std::collections::HashMap;
+/// # use rquickjs::{Runtime, Context, Persistent, Function};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// #[derive(Default)]
+/// struct Handlers {
+///     handlers: HashMap<String, Persistent<Function<'static>>>,
+/// }
+///
+/// let mut handlers = Handlers::default();
+/// ctx.with(|ctx| {
+///     let handler: Function = ctx.eval("name => `hello ${name}`").unwrap();
+///     let handler = Persistent::save(&ctx, handler);
+///     handlers.handlers.insert("greet".into(), handler);
+/// });
+/// let res: String = ctx.with(|ctx| {
+///     let handler = handlers.handlers["greet"].clone().restore(&ctx).unwrap();
+///     handler.call(("world",)).unwrap()
+/// });
+/// assert_eq!(res, "hello world");
+/// ```
+///
 /// It is an error (`Error::UnrelatedRuntime`) to restore the `Persistent` in a
 /// context who isn't part of the original `Runtime`.
 ///
+/// A `Persistent` never touches the runtime outside of [`Persistent::restore`], cloning it only
+/// shares the saved value and dropping it defers freeing the value until the runtime is entered
+/// again. So a `Persistent` can be dropped without holding the runtime lock, and with the
+/// `parallel` feature persistent JavaScript values can be sent to other threads.
+///
 /// NOTE: Be careful and ensure that no persistent links outlives the runtime,
 /// otherwise Runtime will abort the process when dropped.
 ///
-#[derive(Eq, PartialEq, Hash)]
 pub struct Persistent<T> {
     pub(crate) rt: *mut qjs::JSRuntime,
-    pub(crate) value: T,
+    value: Arc<Shared<T>>,
+}
+
+impl<T> Persistent<T> {
+    fn value(&self) -> &T {
+        self.value
+            .value
+            .as_ref()
+            .expect("value is only taken when the persistent is restored")
+    }
 }
 
 impl<T: Clone> Clone for Persistent<T> {
     fn clone(&self) -> Self {
+        *self.value.clone.lock().unwrap() = Some(T::clone);
         Persistent {
             rt: self.rt,
             value: self.value.clone(),
@@ -180,6 +270,21 @@ impl<T: Clone> Clone for Persistent<T> {
     }
 }
 
+impl<T: PartialEq> PartialEq for Persistent<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rt == other.rt && self.value() == other.value()
+    }
+}
+
+impl<T: Eq> Eq for Persistent<T> {}
+
+impl<T: Hash> Hash for Persistent<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rt.hash(state);
+        self.value().hash(state);
+    }
+}
+
 impl<T> fmt::Debug for Persistent<T>
 where
     T: fmt::Debug,
@@ -187,11 +292,35 @@ where
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("Persistent")
             .field("rt", &self.rt)
-            .field("value", &self.value)
+            .field("value", self.value())
             .finish()
     }
 }
 
+macro_rules! persistent_send_impls {
+    ($($type:ident,)*) => {
+        $(
+            // The value is only accessed while holding the runtime lock.
+            #[cfg(feature = "parallel")]
+            unsafe impl Send for Persistent<$type<'static>> {}
+        )*
+    };
+}
+
+persistent_send_impls! {
+    Value,
+    Symbol,
+    String,
+    Object,
+    Array,
+    BigInt,
+    Function,
+    Constructor,
+    Promise,
+    Exception,
+    Atom,
+}
+
 impl<T> Persistent<T> {
     unsafe fn outlive_transmute<'from, 'to, U>(t: U) -> U::Target<'to>
     where
@@ -224,9 +353,14 @@ impl<T> Persistent<T> {
         let outlived: T::Target<'static> =
             unsafe { Self::outlive_transmute::<'js, 'static, T>(val) };
         let ptr = unsafe { qjs::JS_GetRuntime(ctx.as_ptr()) };
+        let drop_send = unsafe { (*ctx.get_opaque()).drop_send.clone() };
         Persistent {
             rt: ptr,
-            value: outlived,
+            value: Arc::new(Shared {
+                value: Some(outlived),
+                clone: Mutex::new(None),
+                drop_send,
+            }),
         }
     }
 
@@ -239,7 +373,20 @@ impl<T> Persistent<T> {
         if self.rt != ctx_runtime_ptr {
             return Err(Error::UnrelatedRuntime);
         }
-        Ok(unsafe { Self::outlive_transmute::<'static, 'js, T>(self.value) })
+        // The runtime is locked, so the value can be cloned if it is shared.
+        let value = match Arc::try_unwrap(self.value) {
+            Ok(mut shared) => shared.value.take(),
+            Err(shared) => {
+                let clone = shared
+                    .clone
+                    .lock()
+                    .unwrap()
+                    .expect("shared persistents were cloned");
+                shared.value.as_ref().map(clone)
+            }
+        }
+        .expect("value is only taken when the persistent is restored");
+        Ok(unsafe { Self::outlive_transmute::<'static, 'js, T>(value) })
     }
 }
 
@@ -346,4 +493,23 @@ mod test {
             assert!(eq.as_bool().unwrap());
         });
     }
+
+    #[test]
+    fn deferred_drop() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+
+        let persistent = ctx.with(|ctx| {
+            let obj: Object = ctx.eval("({ a: 1 })").unwrap();
+            Persistent::save(&ctx, obj)
+        });
+        let count = rt.memory_usage().obj_count;
+        let shared = persistent.clone();
+        std::mem::drop(persistent);
+        std::mem::drop(shared);
+        // The object is only freed once the runtime is entered again.
+        assert_eq!(rt.memory_usage().obj_count, count);
+        ctx.with(|_| {});
+        assert_eq!(rt.memory_usage().obj_count, count - 1);
+    }
 }
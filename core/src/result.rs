@@ -14,8 +14,8 @@ use crate::context::AsyncContext;
 #[cfg(feature = "array-buffer")]
 use crate::value::array_buffer::AsSliceError;
 use crate::{
-    atom::PredefinedAtom, qjs, value::exception::ERROR_FORMAT_STR, Context, Ctx, Exception, Object,
-    StdResult, StdString, Type, Value,
+    atom::PredefinedAtom, qjs, runtime::ForbiddenSyntax, value::exception::ERROR_FORMAT_STR, Atom,
+    Context, Ctx, Exception, IntoJs, Object, StdResult, StdString, Type, Value,
 };
 
 /// Result type used throughout the library.
@@ -58,7 +58,9 @@ impl std::error::Error for BorrowError {}
 #[non_exhaustive]
 pub enum Error {
     /// Could not allocate memory
-    /// This is generally only triggered when out of memory.
+    /// This is generally only triggered when out of memory. In runtimes with a custom allocator,
+    /// like [`Runtime::new_with_alloc`](crate::Runtime::new_with_alloc), it is also returned when
+    /// QuickJS failed to allocate while evaluating a script or converting a value.
     Allocation,
     /// A module defined two exported values with the same name.
     DuplicateExports,
@@ -96,6 +98,16 @@ pub enum Error {
         to: &'static str,
         message: Option<StdString>,
     },
+    /// A JavaScript object contained itself while it was converted to a Rust type.
+    Cycle {
+        to: &'static str,
+    },
+    /// JavaScript objects were nested deeper than the limit while they were converted to a Rust
+    /// type, see [`Runtime::set_max_conversion_depth`](crate::Runtime::set_max_conversion_depth).
+    RecursionLimit {
+        to: &'static str,
+        limit: usize,
+    },
     /// Error matching of function arguments
     MissingArgs {
         expected: usize,
@@ -105,6 +117,21 @@ pub enum Error {
         expected: usize,
         given: usize,
     },
+    /// Error converting an argument of a function generated by the macros, wrapping the error of
+    /// the conversion.
+    ///
+    /// It is thrown as a `TypeError` with the wrapped error as its `cause`.
+    Argument {
+        /// The name of the function.
+        function: &'static str,
+        /// The index of the argument in the arguments of the call.
+        index: usize,
+        /// The name of the parameter, if it has one.
+        name: Option<&'static str>,
+        /// The type of the parameter.
+        expected: &'static str,
+        error: Box<Error>,
+    },
     #[cfg(feature = "loader")]
     /// Error when resolving js module
     Resolving {
@@ -125,6 +152,48 @@ pub enum Error {
     /// An error returned by a blocked on promise if block on the promise would result in a dead
     /// lock.
     WouldBlock,
+    /// The runtime was terminated with [`Runtime::terminate`](crate::Runtime::terminate), or a
+    /// promise awaited from Rust was not settled before its runtime was shut down, see
+    /// [`AsyncRuntime::shutdown`](crate::AsyncRuntime::shutdown).
+    Terminated,
+    /// An option had an invalid value.
+    InvalidOption {
+        name: StdString,
+        message: StdString,
+    },
+    /// A script used syntax forbidden by a [`ScriptPolicy`](crate::runtime::ScriptPolicy).
+    ///
+    /// The line and column are `-1` if the location is not known.
+    PolicyViolation {
+        syntax: ForbiddenSyntax,
+        file: StdString,
+        line: i32,
+        column: i32,
+    },
+    /// An expression evaluated in a [`Sandbox`](crate::sandbox::Sandbox) used up its fuel.
+    OutOfFuel,
+    /// A script evaluated with [`Ctx::eval_with_timeout`](crate::Ctx::eval_with_timeout) ran
+    /// longer than the timeout and was interrupted.
+    Timeout,
+    /// A job of a [`Supervisor`](crate::supervisor::Supervisor) ran longer than the timeout and
+    /// its runtime was killed.
+    Killed,
+    /// An exception thrown by an expression evaluated in a
+    /// [`Sandbox`](crate::sandbox::Sandbox), converted to its message.
+    Evaluation {
+        message: StdString,
+    },
+    /// A context used more memory than the limit set with
+    /// [`Ctx::set_memory_limit`](crate::Ctx::set_memory_limit).
+    #[cfg(feature = "allocator")]
+    ContextMemoryExceeded {
+        limit: usize,
+    },
+    /// An error raised while converting a value with [`serde`](crate::serde).
+    #[cfg(feature = "serde")]
+    Serde {
+        message: StdString,
+    },
     /// An error from QuickJS from which the specifics are unknown.
     /// Should eventually be removed as development progresses.
     Unknown,
@@ -193,6 +262,17 @@ impl Error {
         matches!(self, Error::Loading { .. })
     }
 
+    /// Create an invalid option error
+    pub fn new_invalid_option<N, M>(name: N, msg: M) -> Self
+    where
+        StdString: From<N> + From<M>,
+    {
+        Error::InvalidOption {
+            name: name.into(),
+            message: msg.into(),
+        }
+    }
+
     /// Returns whether the error is a QuickJS generated exception.
     pub fn is_exception(&self) -> bool {
         matches!(self, Error::Exception)
@@ -260,6 +340,31 @@ impl Error {
         matches!(self, Self::TooManyArgs { .. } | Self::MissingArgs { .. })
     }
 
+    /// Wrap an error converting an argument into an [`Error::Argument`].
+    ///
+    /// Errors which aren't caused by the value of the argument, like exceptions thrown while
+    /// converting it and allocation failures, are returned unchanged.
+    pub fn in_argument(
+        self,
+        function: &'static str,
+        index: usize,
+        name: Option<&'static str>,
+        expected: &'static str,
+    ) -> Self {
+        match self {
+            Error::Exception | Error::Allocation | Error::Terminated => self,
+            #[cfg(feature = "allocator")]
+            Error::ContextMemoryExceeded { .. } => self,
+            error => Error::Argument {
+                function,
+                index,
+                name,
+                expected,
+                error: Box::new(error),
+            },
+        }
+    }
+
     /// Optimized conversion to [`CString`]
     pub(crate) fn to_cstring(&self) -> CString {
         // stringify error with NUL at end
@@ -277,10 +382,14 @@ impl Error {
         match self {
             Exception => qjs::JS_EXCEPTION,
             Allocation => unsafe { qjs::JS_ThrowOutOfMemory(ctx.as_ptr()) },
+            #[cfg(feature = "allocator")]
+            ContextMemoryExceeded { .. } => unsafe { qjs::JS_ThrowOutOfMemory(ctx.as_ptr()) },
             InvalidString(_)
             | Utf8(_)
             | FromJs { .. }
             | IntoJs { .. }
+            | Cycle { .. }
+            | RecursionLimit { .. }
             | TooManyArgs { .. }
             | MissingArgs { .. } => {
                 let message = self.to_cstring();
@@ -292,6 +401,36 @@ impl Error {
                     )
                 }
             }
+            #[cfg(feature = "serde")]
+            Serde { .. } => {
+                let message = self.to_cstring();
+                unsafe {
+                    qjs::JS_ThrowTypeError(
+                        ctx.as_ptr(),
+                        ERROR_FORMAT_STR.as_ptr(),
+                        message.as_ptr(),
+                    )
+                }
+            }
+            Argument { error, .. } => {
+                error.throw(ctx);
+                let cause = ctx.catch();
+                let message = self.to_cstring();
+                unsafe {
+                    qjs::JS_ThrowTypeError(
+                        ctx.as_ptr(),
+                        ERROR_FORMAT_STR.as_ptr(),
+                        message.as_ptr(),
+                    );
+                }
+                let value = ctx.catch();
+                if let Some(object) = value.as_object() {
+                    if let Err(Error::Exception) = object.set("cause", cause) {
+                        return qjs::JS_EXCEPTION;
+                    }
+                }
+                unsafe { qjs::JS_Throw(ctx.as_ptr(), value.into_js_value()) }
+            }
             #[cfg(feature = "array-buffer")]
             AsSlice(_) => {
                 let message = self.to_cstring();
@@ -348,7 +487,14 @@ impl Error {
     }
 }
 
-impl StdError for Error {}
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Argument { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
@@ -400,6 +546,18 @@ impl Display for Error {
                     }
                 }
             }
+            Cycle { to } => {
+                "Error converting cyclic js object into type '".fmt(f)?;
+                to.fmt(f)?;
+                "'".fmt(f)?;
+            }
+            RecursionLimit { to, limit } => {
+                "Error converting js object into type '".fmt(f)?;
+                to.fmt(f)?;
+                "': nested deeper than ".fmt(f)?;
+                limit.fmt(f)?;
+                " levels".fmt(f)?;
+            }
             MissingArgs { expected, given } => {
                 "Error calling function with ".fmt(f)?;
                 given.fmt(f)?;
@@ -415,6 +573,27 @@ impl Display for Error {
                 expected.fmt(f)?;
                 " arguments".fmt(f)?;
             }
+            Argument {
+                function,
+                index,
+                name,
+                expected,
+                error,
+            } => {
+                "Error converting argument ".fmt(f)?;
+                index.fmt(f)?;
+                if let Some(name) = name {
+                    " '".fmt(f)?;
+                    name.fmt(f)?;
+                    "'".fmt(f)?;
+                }
+                " of '".fmt(f)?;
+                function.fmt(f)?;
+                "' to '".fmt(f)?;
+                expected.fmt(f)?;
+                "': ".fmt(f)?;
+                error.fmt(f)?;
+            }
             #[cfg(feature = "loader")]
             Resolving {
                 base,
@@ -458,6 +637,48 @@ impl Display for Error {
                 x.fmt(f)?;
             }
             WouldBlock => "Error blocking on a promise resulted in a dead lock".fmt(f)?,
+            Terminated => "The runtime was terminated".fmt(f)?,
+            #[cfg(feature = "allocator")]
+            ContextMemoryExceeded { limit } => {
+                "Context exceeded its memory limit of ".fmt(f)?;
+                limit.fmt(f)?;
+                " bytes".fmt(f)?;
+            }
+            #[cfg(feature = "serde")]
+            Serde { message } => {
+                "Serde error: ".fmt(f)?;
+                message.fmt(f)?;
+            }
+            InvalidOption { name, message } => {
+                "Invalid value for option '".fmt(f)?;
+                name.fmt(f)?;
+                "': ".fmt(f)?;
+                message.fmt(f)?;
+            }
+            PolicyViolation {
+                syntax,
+                file,
+                line,
+                column,
+            } => {
+                "Script uses forbidden syntax '".fmt(f)?;
+                syntax.fmt(f)?;
+                "' at ".fmt(f)?;
+                file.fmt(f)?;
+                if *line > -1 {
+                    ":".fmt(f)?;
+                    line.fmt(f)?;
+                    ":".fmt(f)?;
+                    column.fmt(f)?;
+                }
+            }
+            OutOfFuel => "Sandboxed expression ran out of fuel".fmt(f)?,
+            Timeout => "Script exceeded its timeout and was interrupted".fmt(f)?,
+            Killed => "Job exceeded the timeout and its runtime was killed".fmt(f)?,
+            Evaluation { message } => {
+                "Error evaluating expression: ".fmt(f)?;
+                message.fmt(f)?;
+            }
             #[cfg(feature = "array-buffer")]
             AsSlice(x) => {
                 "Could not convert array buffer to slice: ".fmt(f)?;
@@ -688,19 +909,148 @@ impl<'js> Ctx<'js> {
             if let Some(x) = (*self.get_opaque()).panic.take() {
                 panic::resume_unwind(x)
             }
-            Err(Error::Exception)
+            self.report_stack_overflow();
+            Err(self.pending_error())
+        }
+    }
+
+    /// Returns the error for the pending exception.
+    ///
+    /// The exception is cleared and [`Error::Allocation`] returned if it is the error QuickJS
+    /// throws when it runs out of memory and an allocation of the runtime actually failed since
+    /// the last exception. Allocation failures are only tracked in runtimes with a custom
+    /// allocator.
+    fn pending_error(&self) -> Error {
+        #[cfg(feature = "allocator")]
+        if let Some(limit) = self.take_memory_exceeded() {
+            self.take_out_of_memory();
+            self.catch();
+            return Error::ContextMemoryExceeded { limit };
+        }
+        #[cfg(feature = "allocator")]
+        if self.take_out_of_memory() {
+            // The failure may have been reported without an exception, so check the exception too.
+            let value = self.catch();
+            let out_of_memory = value
+                .as_object()
+                .cloned()
+                .and_then(Exception::from_object)
+                .filter(|x| x.message().as_deref() == Some("out of memory"))
+                .and_then(|x| x.get::<_, Option<StdString>>(PredefinedAtom::Name).ok())
+                .flatten()
+                .map_or(false, |name| name == "InternalError");
+            if out_of_memory {
+                return Error::Allocation;
+            }
+            self.throw(value);
+        }
+        if unsafe { (*self.get_opaque()).termination_requested() } {
+            self.catch();
+            return Error::Terminated;
+        }
+        if self.origin().is_some() {
+            let value = self.catch();
+            self.tag_origin(&value);
+            return self.throw(value);
+        }
+        Error::Exception
+    }
+
+    /// Attach the current origin label to an error object which doesn't carry one yet.
+    fn tag_origin(&self, value: &Value<'js>) {
+        let Some(origin) = self.origin() else {
+            return;
+        };
+        let Some(error) = value.as_object().filter(|x| x.is_error()) else {
+            return;
+        };
+        if error.contains_key("origin").unwrap_or(true) {
+            return;
+        }
+        let (Ok(key), Ok(origin)) = (Atom::from_str(self.clone(), "origin"), origin.into_js(self))
+        else {
+            return;
+        };
+        unsafe {
+            qjs::JS_DefinePropertyValue(
+                self.as_ptr(),
+                error.as_js_value(),
+                key.atom,
+                origin.into_js_value(),
+                (qjs::JS_PROP_CONFIGURABLE | qjs::JS_PROP_WRITABLE) as _,
+            );
+        }
+    }
+
+    /// Calls the stack overflow handler if one is set and the pending exception is a stack
+    /// overflow which was not yet reported.
+    ///
+    /// QuickJS can't inspect the pending exception without taking it, so it is taken and thrown
+    /// again. The engine remembers the error it threw for the last stack overflow, so errors
+    /// thrown by scripts are never reported and no script code runs while checking.
+    unsafe fn report_stack_overflow(&self) {
+        let opaque = self.get_opaque();
+        if (*opaque).stack_overflow_handler.is_none() {
+            return;
+        }
+
+        let value = self.catch();
+        let overflow = value
+            .as_object()
+            .filter(|x| {
+                x.get_ptr() != (*opaque).last_stack_overflow
+                    && qjs::JS_IsStackOverflow(self.as_ptr(), x.as_js_value()) != 0
+            })
+            .map(|x| crate::runtime::StackOverflow {
+                stack: self.own_data_string(x, qjs::JS_ATOM_stack as qjs::JSAtom),
+                max_stack_size: (*opaque).max_stack_size,
+            });
+        let ptr = if value.is_object() {
+            value.get_ptr()
+        } else {
+            std::ptr::null_mut()
+        };
+        self.throw(value);
+
+        if let Some(overflow) = overflow {
+            (*opaque).last_stack_overflow = ptr;
+            if let Some(handler) = (*opaque).stack_overflow_handler.as_mut() {
+                handler(&overflow);
+            }
+        }
+    }
+
+    /// Returns the string value of an own data property without calling getters.
+    fn own_data_string(&self, object: &Object<'js>, atom: qjs::JSAtom) -> Option<StdString> {
+        let mut desc = std::mem::MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+        let res = unsafe {
+            qjs::JS_GetOwnProperty(self.as_ptr(), desc.as_mut_ptr(), object.as_js_value(), atom)
+        };
+        if res != 1 {
+            return None;
         }
+        let desc = unsafe { desc.assume_init() };
+        // Take ownership of the descriptor values to free them, the value of an accessor property
+        // is undefined.
+        let (value, _getter, _setter) = unsafe {
+            (
+                Value::from_js_value(self.clone(), desc.value),
+                Value::from_js_value(self.clone(), desc.getter),
+                Value::from_js_value(self.clone(), desc.setter),
+            )
+        };
+        value.as_string().and_then(|x| x.to_string().ok())
     }
 
-    /// Returns [`Error::Exception`] if there is no existing panic,
-    /// otherwise continues panicking.
+    /// Returns [`Error::Exception`], or [`Error::Allocation`] if the pending exception is an out of
+    /// memory error, if there is no existing panic, otherwise continues panicking.
     pub(crate) fn raise_exception(&self) -> Error {
         // Safety
         unsafe {
             if let Some(x) = (*self.get_opaque()).panic.take() {
                 panic::resume_unwind(x)
             }
-            Error::Exception
         }
+        self.pending_error()
     }
 }
@@ -3,17 +3,25 @@
 #[cfg(feature = "futures")]
 mod r#async;
 mod base;
+mod options;
+mod policy;
 pub(crate) mod raw;
+mod sampler;
 #[cfg(feature = "futures")]
 pub(crate) mod schedular;
 
-pub use base::{Runtime, WeakRuntime};
+pub use base::{Runtime, TerminateHandle, WeakRuntime};
+pub use options::RuntimeOptions;
+pub use policy::{ForbiddenSyntax, ScriptPolicy};
 #[cfg(feature = "futures")]
 pub(crate) use r#async::InnerRuntime;
 #[cfg(feature = "futures")]
 pub use r#async::{AsyncRuntime, AsyncWeakRuntime};
+pub use sampler::HotFunction;
 #[cfg(feature = "futures")]
 mod spawner;
+#[cfg(feature = "futures")]
+pub use spawner::{DriveFuture, DriveOptions, JobErrorHandler};
 
 /// The type of the interrupt handler.
 #[cfg(not(feature = "parallel"))]
@@ -22,5 +30,182 @@ pub type InterruptHandler = Box<dyn FnMut() -> bool + 'static>;
 #[cfg(feature = "parallel")]
 pub type InterruptHandler = Box<dyn FnMut() -> bool + Send + 'static>;
 
-/// A struct with information about the runtimes memory usage.
-pub type MemoryUsage = crate::qjs::JSMemoryUsage;
+/// The type of the interrupt handler which receives the origin of the running code, set with
+/// [`Ctx::with_origin`](crate::Ctx::with_origin).
+#[cfg(not(feature = "parallel"))]
+pub type OriginInterruptHandler = Box<dyn FnMut(Option<&str>) -> bool + 'static>;
+/// The type of the interrupt handler which receives the origin of the running code, set with
+/// [`Ctx::with_origin`](crate::Ctx::with_origin).
+#[cfg(feature = "parallel")]
+pub type OriginInterruptHandler = Box<dyn FnMut(Option<&str>) -> bool + Send + 'static>;
+
+/// The type of the stack overflow handler.
+#[cfg(not(feature = "parallel"))]
+pub type StackOverflowHandler = Box<dyn FnMut(&StackOverflow) + 'static>;
+/// The type of the stack overflow handler.
+#[cfg(feature = "parallel")]
+pub type StackOverflowHandler = Box<dyn FnMut(&StackOverflow) + Send + 'static>;
+
+/// Information about a stack overflow, passed to the handler set with
+/// [`Runtime::set_stack_overflow_handler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackOverflow {
+    /// The JavaScript stack trace at the point of the overflow, if available.
+    pub stack: Option<String>,
+    /// The max stack size of the runtime at the time of the overflow.
+    pub max_stack_size: usize,
+}
+
+impl StackOverflow {
+    /// Returns the frames of the stack trace, innermost first.
+    pub fn frames(&self) -> impl Iterator<Item = &str> {
+        self.stack
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+    }
+
+    /// Returns the number of frames in the stack trace.
+    ///
+    /// QuickJS limits the length of the trace so deep call chains can report fewer frames than
+    /// were actually on the stack.
+    pub fn depth(&self) -> usize {
+        self.frames().count()
+    }
+}
+
+/// Statistics of the memory used by a runtime, returned by [`Runtime::memory_usage`].
+///
+/// The counts are taken when the statistics are computed, objects which are only referenced by
+/// cycles are counted until the garbage collector ran, see [`Runtime::run_gc`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MemoryUsage {
+    /// The bytes allocated by the runtime.
+    pub malloc_size: i64,
+    /// The memory limit of the runtime, see [`Runtime::set_memory_limit`].
+    pub malloc_limit: i64,
+    /// The estimated bytes used by the structures of the runtime counted below.
+    pub memory_used_size: i64,
+    /// The number of allocations.
+    pub malloc_count: i64,
+    /// The number of structures counted in `memory_used_size`.
+    pub memory_used_count: i64,
+    /// The number of atoms.
+    pub atom_count: i64,
+    /// The bytes used by atoms.
+    pub atom_size: i64,
+    /// The number of strings.
+    pub str_count: i64,
+    /// The bytes used by strings.
+    pub str_size: i64,
+    /// The number of objects.
+    pub obj_count: i64,
+    /// The bytes used by objects.
+    pub obj_size: i64,
+    /// The number of object properties.
+    pub prop_count: i64,
+    /// The bytes used by object properties.
+    pub prop_size: i64,
+    /// The number of object shapes.
+    pub shape_count: i64,
+    /// The bytes used by object shapes.
+    pub shape_size: i64,
+    /// The number of JavaScript functions.
+    pub js_func_count: i64,
+    /// The bytes used by JavaScript functions.
+    pub js_func_size: i64,
+    /// The bytes used by the bytecode of JavaScript functions.
+    pub js_func_code_size: i64,
+    /// The number of line number tables of JavaScript functions.
+    pub js_func_pc2line_count: i64,
+    /// The bytes used by line number tables.
+    pub js_func_pc2line_size: i64,
+    /// The number of column number tables of JavaScript functions.
+    pub js_func_pc2column_count: i64,
+    /// The bytes used by column number tables.
+    pub js_func_pc2column_size: i64,
+    /// The number of native functions.
+    pub c_func_count: i64,
+    /// The number of arrays.
+    pub array_count: i64,
+    /// The number of arrays stored as a contiguous list of values.
+    pub fast_array_count: i64,
+    /// The number of elements of fast arrays.
+    pub fast_array_elements: i64,
+    /// The number of array buffers and typed arrays.
+    pub binary_object_count: i64,
+    /// The bytes used by array buffers and typed arrays.
+    pub binary_object_size: i64,
+}
+
+impl From<crate::qjs::JSMemoryUsage> for MemoryUsage {
+    fn from(usage: crate::qjs::JSMemoryUsage) -> Self {
+        MemoryUsage {
+            malloc_size: usage.malloc_size,
+            malloc_limit: usage.malloc_limit,
+            memory_used_size: usage.memory_used_size,
+            malloc_count: usage.malloc_count,
+            memory_used_count: usage.memory_used_count,
+            atom_count: usage.atom_count,
+            atom_size: usage.atom_size,
+            str_count: usage.str_count,
+            str_size: usage.str_size,
+            obj_count: usage.obj_count,
+            obj_size: usage.obj_size,
+            prop_count: usage.prop_count,
+            prop_size: usage.prop_size,
+            shape_count: usage.shape_count,
+            shape_size: usage.shape_size,
+            js_func_count: usage.js_func_count,
+            js_func_size: usage.js_func_size,
+            js_func_code_size: usage.js_func_code_size,
+            js_func_pc2line_count: usage.js_func_pc2line_count,
+            js_func_pc2line_size: usage.js_func_pc2line_size,
+            js_func_pc2column_count: usage.js_func_pc2column_count,
+            js_func_pc2column_size: usage.js_func_pc2column_size,
+            c_func_count: usage.c_func_count,
+            array_count: usage.array_count,
+            fast_array_count: usage.fast_array_count,
+            fast_array_elements: usage.fast_array_elements,
+            binary_object_count: usage.binary_object_count,
+            binary_object_size: usage.binary_object_size,
+        }
+    }
+}
+
+/// A syntax error found by [`Runtime::check_syntax`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// The error message.
+    pub message: String,
+    /// The file name of the checked script.
+    pub file: Option<String>,
+    /// The line on which the error was found.
+    pub line: Option<i32>,
+    /// The column at which the error was found.
+    pub column: Option<i32>,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        "SyntaxError: ".fmt(f)?;
+        self.message.fmt(f)?;
+        if let Some(file) = self.file.as_ref() {
+            " at ".fmt(f)?;
+            file.fmt(f)?;
+        }
+        if let Some(line) = self.line.filter(|x| *x > -1) {
+            ":".fmt(f)?;
+            line.fmt(f)?;
+            if let Some(column) = self.column.filter(|x| *x > -1) {
+                ":".fmt(f)?;
+                column.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxError {}
@@ -14,16 +14,17 @@ use async_lock::Mutex;
 use super::{
     raw::{Opaque, RawRuntime},
     schedular::SchedularPoll,
-    spawner::DriveFuture,
-    InterruptHandler, MemoryUsage,
+    spawner::{DriveFuture, DriveOptions},
+    HotFunction, InterruptHandler, MemoryUsage, OriginInterruptHandler, StackOverflowHandler,
+    TerminateHandle,
 };
 #[cfg(feature = "allocator")]
 use crate::allocator::Allocator;
 #[cfg(feature = "loader")]
 use crate::loader::{Loader, Resolver};
 use crate::{
-    context::AsyncContext, result::AsyncJobException, util::ManualPoll, Ctx, Error, Exception,
-    Result,
+    context::AsyncContext, result::AsyncJobException, source::SourceFile, util::ManualPoll, Ctx,
+    Error, Exception, Result,
 };
 #[cfg(feature = "parallel")]
 use crate::{
@@ -181,6 +182,117 @@ impl AsyncRuntime {
         }
     }
 
+    /// Set an interrupt handler which also receives the origin label of the running code.
+    ///
+    /// The origin is set with [`Ctx::with_origin`] or [`EvalOptions::origin`], which lets a
+    /// host running the code of several tenants or plugins in one runtime attribute the time
+    /// spent to them. Replaces the handler set with [`AsyncRuntime::set_interrupt_handler`].
+    ///
+    /// [`EvalOptions::origin`]: crate::context::EvalOptions::origin
+    pub async fn set_interrupt_handler_with_origin(&self, handler: Option<OriginInterruptHandler>) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_origin_interrupt_handler(handler);
+        }
+    }
+
+    /// Set a closure which is called when a stack overflow exception is raised.
+    ///
+    /// See [`Runtime::set_stack_overflow_handler`](crate::Runtime::set_stack_overflow_handler).
+    #[inline]
+    pub async fn set_stack_overflow_handler(&self, handler: Option<StackOverflowHandler>) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_stack_overflow_handler(handler);
+        }
+    }
+
+    /// Enable or disable sampling of the running JavaScript function.
+    ///
+    /// See [`Runtime::set_sampling`](crate::Runtime::set_sampling).
+    pub async fn set_sampling(&self, interval: Option<u32>) {
+        unsafe {
+            self.inner.lock().await.runtime.set_sampling(interval);
+        }
+    }
+
+    /// Returns the `top_n` functions with the most samples, most sampled first.
+    ///
+    /// See [`Runtime::set_sampling`](crate::Runtime::set_sampling).
+    pub async fn hot_functions(&self, top_n: usize) -> Vec<HotFunction> {
+        let mut lock = self.inner.lock().await;
+        unsafe { lock.runtime.get_opaque_mut() }
+            .sampler
+            .as_ref()
+            .map(|sampler| sampler.hot_functions(top_n))
+            .unwrap_or_default()
+    }
+
+    /// Discard the samples taken so far.
+    pub async fn reset_samples(&self) {
+        let mut lock = self.inner.lock().await;
+        if let Some(sampler) = unsafe { lock.runtime.get_opaque_mut() }.sampler.as_mut() {
+            sampler.reset();
+        }
+    }
+
+    /// Set whether conversions of JavaScript objects to Rust maps are hardened against prototype
+    /// pollution.
+    ///
+    /// See [`Runtime::set_hardened_conversions`](crate::Runtime::set_hardened_conversions).
+    #[inline]
+    pub async fn set_hardened_conversions(&self, enabled: bool) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_hardened_conversions(enabled);
+        }
+    }
+
+    /// Set whether the sources of evaluated scripts and modules are kept by their file name.
+    ///
+    /// See [`Runtime::set_source_retention`](crate::Runtime::set_source_retention).
+    #[inline]
+    pub async fn set_source_retention(&self, enabled: bool) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_source_retention(enabled);
+        }
+    }
+
+    /// Returns the kept source of a script or module by its file name.
+    ///
+    /// See [`Runtime::source`](crate::Runtime::source).
+    #[inline]
+    pub async fn source(&self, name: &str) -> Option<Arc<SourceFile>> {
+        unsafe { self.inner.lock().await.runtime.source(name) }
+    }
+
+    /// Set the max nesting of JavaScript objects converted to Rust values.
+    ///
+    /// See [`Runtime::set_max_conversion_depth`](crate::Runtime::set_max_conversion_depth).
+    #[inline]
+    pub async fn set_max_conversion_depth(&self, depth: usize) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_max_conversion_depth(depth);
+        }
+    }
+
     /// Set the module loader
     #[cfg(feature = "loader")]
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
@@ -207,8 +319,11 @@ impl AsyncRuntime {
     ///
     /// Setting the limit to 0 is equivalent to unlimited memory.
     ///
-    /// Note that is a Noop when a custom allocator is being used,
-    /// as is the case for the "rust-alloc" or "allocator" features.
+    /// Allocations which would exceed the limit fail, so a script allocating too much memory
+    /// throws an out of memory error instead of exhausting the memory of the host. The limit is
+    /// also enforced when the runtime uses a custom allocator. Use
+    /// [`RuntimeOptions`](crate::runtime::RuntimeOptions) to configure the limits when the
+    /// runtime is created.
     pub async fn set_memory_limit(&self, limit: usize) {
         unsafe {
             self.inner.lock().await.runtime.set_memory_limit(limit);
@@ -224,7 +339,66 @@ impl AsyncRuntime {
         }
     }
 
+    /// Set a limit on the backtracking steps of a single regular expression execution.
+    ///
+    /// Patterns like `/(a+)+$/` can take exponential time on crafted input, which blocks the
+    /// thread since the interrupt handler is not called while a regular expression is executing.
+    /// When the limit is exceeded the execution is aborted with a catchable `RangeError`. `None`
+    /// removes the limit, which is the default.
+    pub async fn set_regexp_step_limit(&self, limit: Option<u64>) {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .set_regexp_step_limit(limit.unwrap_or(0));
+        }
+    }
+
+    /// Remove every loaded module from the module caches of the contexts of this runtime.
+    ///
+    /// Modules which are imported afterwards are resolved, loaded and evaluated again, so a
+    /// long running process can pick up changed scripts without creating a new runtime.
+    /// Namespaces and functions of the old modules keep working. Returns the names of the
+    /// removed modules.
+    ///
+    /// Loaders which cache sources or bytecode themselves still return the old module.
+    pub async fn clear_module_cache(&self) -> Vec<String> {
+        unsafe { self.inner.lock().await.runtime.invalidate_modules(None) }
+    }
+
+    /// Remove the module named `name` from the module caches of the contexts of this runtime.
+    ///
+    /// The modules which import the module, directly or indirectly, keep a reference to the old
+    /// module, so they are removed too. Returns the names of all removed modules, which are
+    /// evaluated again when they are imported next. See [`AsyncRuntime::clear_module_cache`].
+    pub async fn invalidate_module(&self, name: &str) -> Vec<String> {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .invalidate_modules(Some(name))
+        }
+    }
+
+    /// Returns the names of the modules [`AsyncRuntime::invalidate_module`] would remove,
+    /// without removing them.
+    pub async fn module_dependents(&self, name: &str) -> Vec<String> {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .stale_module_names(Some(name))
+        }
+    }
+
     /// Set a memory threshold for garbage collection.
+    ///
+    /// The garbage collector runs whenever the allocated memory grows past the threshold, after
+    /// which the threshold is raised relative to the memory still in use. A lower threshold keeps
+    /// the memory of short lived objects in check at the cost of more collections.
     pub async fn set_gc_threshold(&self, threshold: usize) {
         unsafe {
             self.inner.lock().await.runtime.set_gc_threshold(threshold);
@@ -245,9 +419,24 @@ impl AsyncRuntime {
         }
     }
 
+    /// Garbage collect the runtime when it becomes idle, once the allocated memory grew by more
+    /// than `threshold` bytes since the last collection. `None` disables it.
+    ///
+    /// The runtime is idle when [`AsyncRuntime::idle`] or the future returned by
+    /// [`AsyncRuntime::drive`] ran out of jobs and of spawned futures which can make progress.
+    /// Collecting the cycles there, in addition to the collections triggered by
+    /// [`AsyncRuntime::set_gc_threshold`] while allocating, shortens the pauses during latency
+    /// sensitive work. Checking the threshold computes the memory usage, see
+    /// [`AsyncRuntime::memory_usage`].
+    pub async fn gc_on_idle(&self, threshold: Option<usize>) {
+        unsafe {
+            self.inner.lock().await.runtime.set_idle_gc(threshold);
+        }
+    }
+
     /// Get memory usage stats
     pub async fn memory_usage(&self) -> MemoryUsage {
-        unsafe { self.inner.lock().await.runtime.memory_usage() }
+        unsafe { self.inner.lock().await.runtime.memory_usage() }.into()
     }
 
     /// Test for pending jobs
@@ -328,8 +517,14 @@ impl AsyncRuntime {
 
                 match unsafe { lock.runtime.get_opaque_mut() }.spawner().poll(cx) {
                     SchedularPoll::ShouldYield => return Poll::Pending,
-                    SchedularPoll::Empty => return Poll::Ready(()),
-                    SchedularPoll::Pending => return Poll::Pending,
+                    SchedularPoll::Empty => {
+                        unsafe { lock.runtime.collect_idle() };
+                        return Poll::Ready(());
+                    }
+                    SchedularPoll::Pending => {
+                        unsafe { lock.runtime.collect_idle() };
+                        return Poll::Pending;
+                    }
                     SchedularPoll::PendingProgress => {}
                 }
             }
@@ -341,11 +536,59 @@ impl AsyncRuntime {
         f.await
     }
 
+    /// Shut the runtime down, cancelling the futures running in it.
+    ///
+    /// Futures spawned with [`Ctx::spawn`] are dropped, including the ones of promises created
+    /// from Rust futures, and futures spawned afterwards are dropped without being polled.
+    /// Futures awaiting a promise with [`Promise::into_future`](crate::Promise::into_future)
+    /// which isn't settled yet are woken and resolve to [`Error::Terminated`], as do promises
+    /// awaited after the shutdown which are still pending. Scripts can still be evaluated and
+    /// jobs executed.
+    ///
+    /// Dropping the runtime drops its spawned futures as well, but only once the last context
+    /// is dropped, which a future awaiting a promise in [`AsyncContext::async_with`] prevents.
+    pub async fn shutdown(&self) {
+        let mut lock = self.inner.lock().await;
+        lock.drop_pending();
+        unsafe { lock.runtime.terminate() };
+    }
+
+    /// Terminate the runtime, see [`Runtime::terminate`](crate::Runtime::terminate).
+    ///
+    /// Unlike [`AsyncRuntime::shutdown`] evaluating code fails afterwards. To interrupt code
+    /// which is running use a [`TerminateHandle`] from [`AsyncRuntime::terminate_handle`].
+    pub async fn terminate(&self) {
+        unsafe { self.inner.lock().await.runtime.request_termination() }
+    }
+
+    /// Returns whether the runtime was terminated.
+    pub async fn is_terminated(&self) -> bool {
+        unsafe {
+            self.inner
+                .lock()
+                .await
+                .runtime
+                .get_opaque_mut()
+                .termination_requested()
+        }
+    }
+
+    /// Returns a handle which can terminate the runtime without locking it, from any thread.
+    pub async fn terminate_handle(&self) -> TerminateHandle {
+        TerminateHandle(unsafe { self.inner.lock().await.runtime.termination_flag() })
+    }
+
     /// Returns a future that completes when the runtime is dropped.
     /// If the future is polled it will drive futures spawned inside the runtime completing them
     /// even if runtime is currently not in use.
     pub fn drive(&self) -> DriveFuture {
-        DriveFuture::new(self.weak())
+        self.drive_with(DriveOptions::default())
+    }
+
+    /// Returns a future which drives the runtime like [`AsyncRuntime::drive`] with the given
+    /// options, for example a budget of jobs after which the future yields to the executor.
+    pub fn drive_with(&self, options: DriveOptions) -> DriveFuture {
+        DriveFuture::new(self.weak(), options)
     }
 }
 
@@ -443,6 +686,134 @@ mod test {
 
     });
 
+    async_test_case!(drive_with_budget => (rt,ctx){
+        use std::sync::{Arc, atomic::{Ordering,AtomicUsize}};
+        use crate::runtime::DriveOptions;
+
+        let drive = rt.drive_with(DriveOptions::default().with_max_jobs(4));
+        #[cfg(feature = "parallel")]
+        tokio::spawn(drive);
+        #[cfg(not(feature = "parallel"))]
+        tokio::task::spawn_local(drive);
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticks_clone = ticks.clone();
+        let tick = async move {
+            loop {
+                ticks_clone.fetch_add(1, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+            }
+        };
+        #[cfg(feature = "parallel")]
+        tokio::spawn(tick);
+        #[cfg(not(feature = "parallel"))]
+        tokio::task::spawn_local(tick);
+
+        async_with!(&ctx => |ctx|{
+            // A promise loop which never runs out of jobs.
+            ctx.eval::<(),_>("const loop = () => Promise.resolve().then(loop); loop();").unwrap();
+            // Spawning wakes the drive future.
+            ctx.spawn(async {});
+        }).await;
+
+        let before = ticks.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_secs_f64(0.01)).await;
+        assert!(ticks.load(Ordering::SeqCst) > before);
+    });
+
+    async_test_case!(drive_job_errors => (rt,ctx){
+        use std::sync::{Arc, atomic::{Ordering,AtomicUsize}};
+        use crate::runtime::DriveOptions;
+
+        rt.set_interrupt_handler(Some(Box::new(|| true))).await;
+        let errors = Arc::new(AtomicUsize::new(0));
+        let errors_clone = errors.clone();
+        let drive = rt.drive_with(DriveOptions::default().with_error_handler(move |_, _| {
+            errors_clone.fetch_add(1, Ordering::SeqCst);
+        }));
+        #[cfg(feature = "parallel")]
+        tokio::spawn(drive);
+        #[cfg(not(feature = "parallel"))]
+        tokio::task::spawn_local(drive);
+
+        async_with!(&ctx => |ctx|{
+            ctx.eval::<(),_>("Promise.resolve().then(() => { for(;;) {} })").unwrap();
+            ctx.spawn(async {});
+        }).await;
+
+        tokio::time::sleep(Duration::from_secs_f64(0.01)).await;
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+    });
+
+    async_test_case!(gc_on_idle => (rt,ctx){
+        let cycles = "for (let i = 0; i < 1000; i++) { const a = {}; a.self = a; }";
+        rt.set_gc_threshold(usize::MAX).await;
+        rt.gc_on_idle(Some(1 << 30)).await;
+        async_with!(&ctx => |ctx|{
+            ctx.eval::<(), _>(cycles).unwrap();
+        }).await;
+        let count = rt.memory_usage().await.obj_count;
+        rt.idle().await;
+        assert_eq!(rt.memory_usage().await.obj_count, count);
+
+        rt.gc_on_idle(Some(1024)).await;
+        async_with!(&ctx => |ctx|{
+            ctx.eval::<(), _>(cycles).unwrap();
+        }).await;
+        rt.idle().await;
+        assert!(rt.memory_usage().await.obj_count < count);
+    });
+
+    async_test_case!(shutdown => (rt,ctx){
+        use std::sync::{Arc, atomic::{Ordering,AtomicUsize}};
+
+        struct Guard(Arc<AtomicUsize>);
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let guard = Guard(dropped.clone());
+        let waiting_ctx = ctx.clone();
+        let waiting = async move {
+            async_with!(waiting_ctx => |ctx|{
+                ctx.spawn(async move {
+                    let _guard = guard;
+                    std::future::pending::<()>().await;
+                });
+                let promise: Promise = ctx.eval("new Promise(() => {})").unwrap();
+                promise.into_future::<()>().await
+            }).await
+        };
+        #[cfg(feature = "parallel")]
+        let waiting = tokio::spawn(waiting);
+        #[cfg(not(feature = "parallel"))]
+        let waiting = tokio::task::spawn_local(waiting);
+
+        tokio::time::sleep(Duration::from_secs_f64(0.01)).await;
+        assert!(!waiting.is_finished());
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        rt.shutdown().await;
+        // Spawned futures are dropped before the awaiting futures are woken.
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert!(matches!(waiting.await.unwrap(), Err(Error::Terminated)));
+
+        let guard = Guard(dropped.clone());
+        async_with!(ctx => |ctx|{
+            ctx.spawn(async move {
+                let _guard = guard;
+            });
+            let promise: Promise = ctx.eval("new Promise(() => {})").unwrap();
+            assert!(matches!(promise.into_future::<()>().await, Err(Error::Terminated)));
+            assert_eq!(ctx.eval::<i32, _>("1 + 1").unwrap(), 2);
+        }).await;
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+        assert!(!rt.is_job_pending().await);
+    });
+
     async_test_case!(no_drive => (rt,ctx){
         use std::sync::{Arc, atomic::{Ordering,AtomicUsize}};
 
@@ -479,6 +850,74 @@ mod test {
 
     });
 
+    async_test_case!(async_drop => (rt,ctx){
+        use std::{future::Future, pin::Pin, sync::{Arc, atomic::{Ordering,AtomicUsize}}};
+        use crate::class::{AsyncDrop, AsyncFinalizer, ClassId, JsClass, Readable, Trace, Tracer};
+
+        struct Resource(Option<Arc<AtomicUsize>>);
+
+        impl<'js> Trace<'js> for Resource {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        impl<'js> JsClass<'js> for Resource {
+            const NAME: &'static str = "Resource";
+
+            type Mutable = Readable;
+
+            fn class_id() -> &'static ClassId {
+                static ID: ClassId = ClassId::new();
+                &ID
+            }
+
+            fn prototype(ctx: &Ctx<'js>) -> Result<Option<Object<'js>>> {
+                Object::new(ctx.clone()).map(Some)
+            }
+
+            fn constructor(_ctx: &Ctx<'js>) -> Result<Option<function::Constructor<'js>>> {
+                Ok(None)
+            }
+
+            fn async_finalizer() -> Option<AsyncFinalizer> {
+                Some(AsyncFinalizer::new::<Self>())
+            }
+        }
+
+        impl<'js> AsyncDrop<'js> for Resource {
+            fn async_drop(self) -> Option<Pin<Box<dyn Future<Output = ()>>>> {
+                let closed = self.0?;
+                Some(Box::pin(async move {
+                    tokio::task::yield_now().await;
+                    closed.fetch_add(1, Ordering::SeqCst);
+                }))
+            }
+        }
+
+        let closed = Arc::new(AtomicUsize::new(0));
+        let closed_clone = closed.clone();
+        async_with!(&ctx => |ctx|{
+            Class::instance(ctx.clone(), Resource(Some(closed_clone))).unwrap();
+            // Resources which were closed explicitly are not cleaned up again.
+            Class::instance(ctx.clone(), Resource(None)).unwrap();
+        }).await;
+        assert_eq!(closed.load(Ordering::SeqCst), 0);
+        rt.idle().await;
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+
+        // Objects in a cycle with the globals are only collected when the runtime is freed,
+        // they are dropped without spawning their clean up.
+        let closed_clone = closed.clone();
+        async_with!(&ctx => |ctx|{
+            let resource = Class::instance(ctx.clone(), Resource(Some(closed_clone))).unwrap();
+            resource.set("globals", ctx.globals()).unwrap();
+            ctx.globals().set("resource", resource).unwrap();
+        }).await;
+        drop(ctx);
+        drop(rt);
+        assert_eq!(Arc::strong_count(&closed), 1);
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+    });
+
     async_test_case!(recursive_spawn => (rt,ctx){
         use tokio::sync::oneshot;
 
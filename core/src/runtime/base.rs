@@ -1,15 +1,30 @@
 //! QuickJS runtime related types.
 
 use super::{
+    policy::{self, ForbiddenSyntax, ScriptPolicy},
     raw::{Opaque, RawRuntime},
-    InterruptHandler, MemoryUsage,
+    HotFunction, InterruptHandler, MemoryUsage, OriginInterruptHandler, RuntimeOptions,
+    StackOverflowHandler, SyntaxError,
 };
 #[cfg(feature = "allocator")]
 use crate::allocator::Allocator;
 #[cfg(feature = "loader")]
 use crate::loader::{Loader, Resolver};
-use crate::{result::JobException, Context, Error, Mut, Ref, Result, Weak};
-use std::{ffi::CString, ptr::NonNull, result::Result as StdResult};
+use crate::{
+    qjs, result::JobException, source::SourceFile, Context, Ctx, Error, Exception, Mut, Ref,
+    Result, Weak,
+};
+use std::{
+    ffi::CString,
+    future::Future,
+    pin::Pin,
+    ptr::NonNull,
+    result::Result as StdResult,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 /// A weak handle to the runtime.
 ///
@@ -24,6 +39,24 @@ impl WeakRuntime {
     }
 }
 
+/// A handle to terminate a runtime from any thread, see [`Runtime::terminate`].
+///
+/// Holding onto this struct does not prevent the runtime from being dropped.
+#[derive(Clone, Debug)]
+pub struct TerminateHandle(pub(crate) Arc<AtomicBool>);
+
+impl TerminateHandle {
+    /// Terminate the runtime, see [`Runtime::terminate`].
+    pub fn terminate(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the runtime was terminated.
+    pub fn is_terminated(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 /// QuickJS runtime, entry point of the library.
 #[derive(Clone)]
 #[repr(transparent)]
@@ -46,6 +79,15 @@ impl Runtime {
         })
     }
 
+    /// Create a new runtime configured with the given options.
+    ///
+    /// Will fail if not enough memory was available or if an option has an invalid value.
+    pub fn from_options(options: &RuntimeOptions) -> Result<Self> {
+        let rt = Self::new()?;
+        options.apply(&rt)?;
+        Ok(rt)
+    }
+
     /// Create a new runtime using specified allocator
     ///
     /// Will generally only fail if not enough memory was available.
@@ -68,6 +110,49 @@ impl Runtime {
         WeakRuntime(Ref::downgrade(&self.inner))
     }
 
+    /// Terminate the runtime, for example to tear down the scripts of a tenant.
+    ///
+    /// The running code is interrupted with an uncatchable exception. Afterwards evaluating
+    /// code fails with [`Error::Terminated`], as does finishing a pending promise, and pending
+    /// jobs are not executed anymore. In an async runtime spawned futures, including timers, are
+    /// dropped once it is driven again and futures awaiting promises resolve to
+    /// [`Error::Terminated`]. Termination can't be undone.
+    ///
+    /// This function locks the runtime, so it can't interrupt code which is running. Get a
+    /// [`TerminateHandle`] with [`Runtime::terminate_handle`] beforehand to terminate the running
+    /// code, from another thread or from a JavaScript callback.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Error, Runtime};
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// let handle = rt.terminate_handle();
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    ///     handle.terminate();
+    /// });
+    /// ctx.with(|ctx| {
+    ///     assert!(matches!(ctx.eval::<(), _>("for(;;) {}"), Err(Error::Terminated)));
+    ///     assert!(matches!(ctx.eval::<i32, _>("1"), Err(Error::Terminated)));
+    /// });
+    /// ```
+    pub fn terminate(&self) {
+        unsafe { self.inner.lock().request_termination() }
+    }
+
+    /// Returns whether the runtime was terminated.
+    pub fn is_terminated(&self) -> bool {
+        unsafe { self.inner.lock().get_opaque_mut().termination_requested() }
+    }
+
+    /// Returns a handle which can terminate the runtime without locking it, from any thread.
+    ///
+    /// While a handle exists the engine regularly checks whether the runtime was terminated
+    /// when executing code, like with an interrupt handler.
+    pub fn terminate_handle(&self) -> TerminateHandle {
+        TerminateHandle(unsafe { self.inner.lock().termination_flag() })
+    }
+
     /// Set a closure which is regularly called by the engine when it is executing code.
     /// If the provided closure returns `true` the interpreter will raise and uncatchable
     /// exception and return control flow to the caller.
@@ -78,7 +163,140 @@ impl Runtime {
         }
     }
 
+    /// Set an interrupt handler which also receives the origin label of the running code.
+    ///
+    /// The origin is set with [`Ctx::with_origin`] or [`EvalOptions::origin`], which lets a
+    /// host running the code of several tenants or plugins in one runtime attribute the time
+    /// spent to them. Replaces the handler set with [`Runtime::set_interrupt_handler`].
+    ///
+    /// [`EvalOptions::origin`]: crate::context::EvalOptions::origin
+    pub fn set_interrupt_handler_with_origin(&self, handler: Option<OriginInterruptHandler>) {
+        unsafe {
+            self.inner.lock().set_origin_interrupt_handler(handler);
+        }
+    }
+
+    /// Set a closure which is called when a stack overflow exception is raised.
+    ///
+    /// The closure is called once for every overflow with the JavaScript stack trace at the point
+    /// of the overflow, before the exception is returned to Rust. This can be used to tell
+    /// pathological recursion apart from legitimate deep call chains and to tune
+    /// [`Runtime::set_max_stack_size`]. Only overflows detected by the engine are reported, not
+    /// errors thrown by scripts which look like one.
+    ///
+    /// The closure is called while the runtime is locked, so it must not use the runtime or its
+    /// contexts, which would panic or deadlock. Record the overflow and act on it after the
+    /// runtime was used instead.
+    pub fn set_stack_overflow_handler(&self, handler: Option<StackOverflowHandler>) {
+        unsafe {
+            self.inner.lock().set_stack_overflow_handler(handler);
+        }
+    }
+
+    /// Enable or disable sampling of the running JavaScript function.
+    ///
+    /// While enabled the innermost function of the stack is recorded every `interval` times the
+    /// engine checks for interrupts, which happens at a regular rate of executed instructions, so
+    /// the number of samples of a function approximates the interpreter time spent in it. The
+    /// results are returned by [`Runtime::hot_functions`]. Taking a sample captures the stack,
+    /// so a larger interval lowers the overhead. Disabling sampling discards the samples.
+    pub fn set_sampling(&self, interval: Option<u32>) {
+        unsafe {
+            self.inner.lock().set_sampling(interval);
+        }
+    }
+
+    /// Returns the `top_n` functions with the most samples, most sampled first.
+    ///
+    /// See [`Runtime::set_sampling`].
+    pub fn hot_functions(&self, top_n: usize) -> Vec<HotFunction> {
+        let mut lock = self.inner.lock();
+        unsafe { lock.get_opaque_mut() }
+            .sampler
+            .as_ref()
+            .map(|sampler| sampler.hot_functions(top_n))
+            .unwrap_or_default()
+    }
+
+    /// Discard the samples taken so far.
+    pub fn reset_samples(&self) {
+        let mut lock = self.inner.lock();
+        if let Some(sampler) = unsafe { lock.get_opaque_mut() }.sampler.as_mut() {
+            sampler.reset();
+        }
+    }
+
+    /// Set whether conversions of JavaScript objects to Rust maps are hardened against prototype
+    /// pollution.
+    ///
+    /// When enabled the `FromJs` implementations for maps skip `__proto__` and `constructor`
+    /// keys, see [`Filter::skip_prototype_keys`](crate::Filter::skip_prototype_keys).
+    /// Maps only ever read own enumerable properties. Disabled by default.
+    pub fn set_hardened_conversions(&self, enabled: bool) {
+        unsafe {
+            self.inner.lock().set_hardened_conversions(enabled);
+        }
+    }
+
+    /// Set whether the sources of evaluated scripts and modules are kept by their file name.
+    ///
+    /// Kept sources can be retrieved with [`Runtime::source`] or [`Ctx::source`](crate::Ctx::source)
+    /// to map the line and column numbers of errors to the source, see [`source`](crate::source).
+    /// A later source with the same file name replaces an earlier one. Disabling retention drops
+    /// all kept sources. Disabled by default.
+    pub fn set_source_retention(&self, enabled: bool) {
+        unsafe {
+            self.inner.lock().set_source_retention(enabled);
+        }
+    }
+
+    /// Returns the kept source of a script or module by its file name.
+    ///
+    /// Returns `None` if source retention is disabled, see [`Runtime::set_source_retention`].
+    pub fn source(&self, name: &str) -> Option<Arc<SourceFile>> {
+        unsafe { self.inner.lock().source(name) }
+    }
+
+    /// Set the max nesting of JavaScript objects converted to Rust values.
+    ///
+    /// Converting an array or object nested deeper than the limit into a Rust collection fails
+    /// with [`Error::RecursionLimit`](crate::Error::RecursionLimit) instead of overflowing the
+    /// stack, see [`ConversionGuard`](crate::convert::ConversionGuard). Defaults to 256.
+    pub fn set_max_conversion_depth(&self, depth: usize) {
+        unsafe {
+            self.inner.lock().set_max_conversion_depth(depth);
+        }
+    }
+
     /// Set the module loader
+    ///
+    /// The resolver turns the specifier of an `import` into a module name, relative to the name
+    /// of the importing module, and the loader then declares the module with that name. Without
+    /// a loader importing a module which wasn't declared fails. For example with
+    /// [`FileResolver`] and [`ScriptLoader`] modules are imported from files:
+    ///
+    /// ```
+    /// # use rquickjs::{loader::{FileResolver, MemoryVfs, ScriptLoader}, Context, Module, Runtime};
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// // Files are read from the disk unless a virtual file system is set.
+    /// let vfs = MemoryVfs::default().with_file("lib/foo.js", "export const foo = 42;");
+    /// rt.set_loader(
+    ///     FileResolver::default().with_vfs(vfs.clone()),
+    ///     ScriptLoader::default().with_vfs(vfs),
+    /// );
+    /// ctx.with(|ctx| {
+    ///     let source = "import { foo } from './foo.js'; globalThis.foo = foo;";
+    ///     Module::evaluate(ctx.clone(), "lib/main.js", source)
+    ///         .unwrap()
+    ///         .finish::<()>()
+    ///         .unwrap();
+    ///     assert_eq!(ctx.globals().get::<_, i32>("foo").unwrap(), 42);
+    /// });
+    /// ```
+    ///
+    /// [`FileResolver`]: crate::loader::FileResolver
+    /// [`ScriptLoader`]: crate::loader::ScriptLoader
     #[cfg(feature = "loader")]
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
     pub fn set_loader<R, L>(&self, resolver: R, loader: L)
@@ -104,8 +322,11 @@ impl Runtime {
     ///
     /// Setting the limit to 0 is equivalent to unlimited memory.
     ///
-    /// Note that is a Noop when a custom allocator is being used,
-    /// as is the case for the "rust-alloc" or "allocator" features.
+    /// Allocations which would exceed the limit fail, so a script allocating too much memory
+    /// throws an out of memory error instead of exhausting the memory of the host. The limit is
+    /// also enforced when the runtime uses a custom allocator. Use
+    /// [`RuntimeOptions`](crate::runtime::RuntimeOptions) to configure the limits when the
+    /// runtime is created.
     pub fn set_memory_limit(&self, limit: usize) {
         unsafe {
             self.inner.lock().set_memory_limit(limit);
@@ -121,13 +342,151 @@ impl Runtime {
         }
     }
 
+    /// Set a limit on the backtracking steps of a single regular expression execution.
+    ///
+    /// Patterns like `/(a+)+$/` can take exponential time on crafted input, which blocks the
+    /// thread since the interrupt handler is not called while a regular expression is executing.
+    /// When the limit is exceeded the execution is aborted with a catchable `RangeError`. `None`
+    /// removes the limit, which is the default.
+    pub fn set_regexp_step_limit(&self, limit: Option<u64>) {
+        unsafe {
+            self.inner.lock().set_regexp_step_limit(limit.unwrap_or(0));
+        }
+    }
+
+    /// Remove every loaded module from the module caches of the contexts of this runtime.
+    ///
+    /// Modules which are imported afterwards are resolved, loaded and evaluated again, so a
+    /// long running process can pick up changed scripts without creating a new runtime.
+    /// Namespaces and functions of the old modules keep working. Returns the names of the
+    /// removed modules.
+    ///
+    /// Loaders which cache sources or bytecode themselves still return the old module.
+    pub fn clear_module_cache(&self) -> Vec<String> {
+        unsafe { self.inner.lock().invalidate_modules(None) }
+    }
+
+    /// Remove the module named `name` from the module caches of the contexts of this runtime.
+    ///
+    /// The modules which import the module, directly or indirectly, keep a reference to the old
+    /// module, so they are removed too. Returns the names of all removed modules, which are
+    /// evaluated again when they are imported next. See [`Runtime::clear_module_cache`].
+    pub fn invalidate_module(&self, name: &str) -> Vec<String> {
+        unsafe { self.inner.lock().invalidate_modules(Some(name)) }
+    }
+
+    /// Returns the names of the modules [`Runtime::invalidate_module`] would remove, without
+    /// removing them.
+    pub fn module_dependents(&self, name: &str) -> Vec<String> {
+        unsafe { self.inner.lock().stale_module_names(Some(name)) }
+    }
+
     /// Set a memory threshold for garbage collection.
+    ///
+    /// The garbage collector runs whenever the allocated memory grows past the threshold, after
+    /// which the threshold is raised relative to the memory still in use. A lower threshold keeps
+    /// the memory of short lived objects in check at the cost of more collections.
     pub fn set_gc_threshold(&self, threshold: usize) {
         unsafe {
             self.inner.lock().set_gc_threshold(threshold);
         }
     }
 
+    /// Check the syntax of a script without executing it.
+    ///
+    /// The source is only compiled, no code is run. If the source contains `import` or `export`
+    /// statements it is checked as a module, otherwise as a strict global script like
+    /// [`Ctx::eval`](crate::Ctx::eval).
+    ///
+    /// Returns `Ok(None)` if the source is valid and the first syntax error found otherwise.
+    pub fn check_syntax<S: Into<Vec<u8>>>(
+        &self,
+        source: S,
+        file_name: &str,
+    ) -> Result<Option<SyntaxError>> {
+        let source = source.into();
+        let flag = if is_module(&source) {
+            qjs::JS_EVAL_TYPE_MODULE
+        } else {
+            qjs::JS_EVAL_TYPE_GLOBAL
+        } | qjs::JS_EVAL_FLAG_STRICT;
+
+        let context = Context::full(self)?;
+        context.with(|ctx| compile_only(&ctx, source, file_name, flag))
+    }
+
+    /// Check a script against a policy of forbidden syntax without executing it.
+    ///
+    /// The check is done before execution so it can be used to reject untrusted scripts on
+    /// ingestion. It only finds syntactic uses, for example `globalThis["ev" + "al"]` is not
+    /// detected, so it should be combined with removing the corresponding globals where that
+    /// matters. `with` statements and top-level await in scripts are confirmed with the compiler.
+    ///
+    /// Returns [`Error::PolicyViolation`] for the first forbidden syntax found. Syntax errors are
+    /// not reported, use [`Runtime::check_syntax`] for those.
+    pub fn check_policy<S: Into<Vec<u8>>>(
+        &self,
+        source: S,
+        file_name: &str,
+        policy: &ScriptPolicy,
+    ) -> Result<()> {
+        let source = source.into();
+        let module = is_module(&source);
+        let candidates = policy::scan(std::str::from_utf8(&source)?);
+
+        let context = Context::full(self)?;
+        context.with(|ctx| {
+            let compiles = |flag: u32| -> Result<bool> {
+                compile_only(&ctx, source.clone(), file_name, flag).map(|x| x.is_none())
+            };
+
+            // Modules are always strict and always allow await.
+            let confirmed = |syntax: ForbiddenSyntax| -> Result<bool> {
+                Ok(match syntax {
+                    ForbiddenSyntax::With if module => false,
+                    ForbiddenSyntax::With => {
+                        compiles(qjs::JS_EVAL_TYPE_GLOBAL)?
+                            && !compiles(qjs::JS_EVAL_TYPE_GLOBAL | qjs::JS_EVAL_FLAG_STRICT)?
+                    }
+                    ForbiddenSyntax::TopLevelAwait if !module => {
+                        !compiles(qjs::JS_EVAL_TYPE_GLOBAL)?
+                            && compiles(qjs::JS_EVAL_TYPE_GLOBAL | qjs::JS_EVAL_FLAG_ASYNC)?
+                    }
+                    _ => true,
+                })
+            };
+
+            let violation =
+                |syntax: ForbiddenSyntax, line: i32, column: i32| Error::PolicyViolation {
+                    syntax,
+                    file: file_name.to_string(),
+                    line,
+                    column,
+                };
+
+            let mut checked = Vec::new();
+            for candidate in candidates {
+                let syntax = candidate.syntax;
+                if !policy.forbids(syntax) || checked.contains(&syntax) {
+                    continue;
+                }
+                checked.push(syntax);
+                if confirmed(syntax)? {
+                    return Err(violation(syntax, candidate.line, candidate.column));
+                }
+            }
+
+            // Await hidden from the lexical scan, for example by unicode escapes. `with` can't be
+            // confirmed without a candidate since other sloppy mode syntax fails in strict mode too.
+            let syntax = ForbiddenSyntax::TopLevelAwait;
+            if policy.forbids(syntax) && !module && !checked.contains(&syntax) && confirmed(syntax)?
+            {
+                return Err(violation(syntax, -1, -1));
+            }
+            Ok(())
+        })
+    }
+
     /// Manually run the garbage collection.
     ///
     /// Most of QuickJS values are reference counted and
@@ -141,8 +500,22 @@ impl Runtime {
     }
 
     /// Get memory usage stats
+    ///
+    /// Computing the statistics walks the objects of the runtime, so for monitoring a long
+    /// running process it should be called periodically rather than after every evaluation.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Runtime};
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| ctx.eval::<(), _>("globalThis.list = [1, 2, 3]").unwrap());
+    /// rt.run_gc();
+    /// let usage = rt.memory_usage();
+    /// assert!(usage.array_count > 0);
+    /// assert!(usage.malloc_size > 0);
+    /// ```
     pub fn memory_usage(&self) -> MemoryUsage {
-        unsafe { self.inner.lock().memory_usage() }
+        unsafe { self.inner.lock().memory_usage() }.into()
     }
 
     /// Test for pending jobs
@@ -169,6 +542,37 @@ impl Runtime {
             })
         })
     }
+
+    /// Run a future created from the context on the current thread until it is complete.
+    ///
+    /// This is a blocking entry point for futures using JavaScript values, see
+    /// [`Ctx::block_on`] for how the future is driven. Instead of deadlocking, this function
+    /// returns [`Error::WouldBlock`] when the runtime is already in use on this thread, for
+    /// example when called from a function called by JavaScript, or when the future can't make
+    /// any more progress. Returns [`Error::UnrelatedRuntime`] if the context belongs to another
+    /// runtime.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Runtime};
+    /// let rt = Runtime::new().unwrap();
+    /// let ctx = Context::full(&rt).unwrap();
+    /// let res = rt
+    ///     .block_on_js(&ctx, |ctx| Box::pin(async move { ctx.eval::<i32, _>("1 + 1") }))
+    ///     .unwrap();
+    /// assert_eq!(res.unwrap(), 2);
+    /// ```
+    pub fn block_on_js<F, R>(&self, context: &Context, f: F) -> Result<R>
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> Pin<Box<dyn Future<Output = R> + 'js>>,
+    {
+        if !Ref::ptr_eq(&self.inner, &context.0.rt.inner) {
+            return Err(Error::UnrelatedRuntime);
+        }
+        if self.inner.is_locked_by_current_thread() {
+            return Err(Error::WouldBlock);
+        }
+        context.with(|ctx| ctx.block_on(f(ctx.clone())))
+    }
 }
 
 // Since all functions which use runtime are behind a mutex
@@ -186,6 +590,47 @@ unsafe impl Sync for Runtime {}
 #[cfg(feature = "parallel")]
 unsafe impl Sync for WeakRuntime {}
 
+/// Returns whether the source should be compiled as a module.
+fn is_module(source: &[u8]) -> bool {
+    unsafe { qjs::JS_DetectModule(source.as_ptr() as *const _, source.len() as _) != 0 }
+}
+
+/// Compiles the source without running it, returning the syntax error if there was one.
+fn compile_only(
+    ctx: &Ctx<'_>,
+    source: Vec<u8>,
+    file_name: &str,
+    flag: u32,
+) -> Result<Option<SyntaxError>> {
+    let file_name_c = CString::new(file_name)?;
+    let flag = flag | qjs::JS_EVAL_FLAG_COMPILE_ONLY;
+    match unsafe { ctx.eval_raw(source, &file_name_c, flag as i32) } {
+        Ok(val) => {
+            unsafe { qjs::JS_FreeValue(ctx.as_ptr(), val) };
+            Ok(None)
+        }
+        Err(Error::Exception) => {
+            let value = ctx.catch();
+            let error = match value.as_object().cloned().and_then(Exception::from_object) {
+                Some(e) => SyntaxError {
+                    message: e.message().unwrap_or_default(),
+                    file: e.file().or_else(|| Some(file_name.to_string())),
+                    line: e.line(),
+                    column: e.column(),
+                },
+                None => SyntaxError {
+                    message: format!("{:?}", value),
+                    file: Some(file_name.to_string()),
+                    line: None,
+                    column: None,
+                },
+            };
+            Ok(Some(error))
+        }
+        Err(e) => Err(e),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -197,4 +642,392 @@ mod test {
         rt.set_gc_threshold(0xFF);
         rt.run_gc();
     }
+
+    #[test]
+    fn memory_usage() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let before = rt.memory_usage();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>("globalThis.objects = Array.from({ length: 100 }, () => ({}))")
+                .unwrap();
+        });
+        let after = rt.memory_usage();
+        assert!(after.obj_count >= before.obj_count + 100);
+        assert!(after.malloc_size > before.malloc_size);
+        assert!(after.malloc_count > 0 && after.atom_count > 0);
+    }
+
+    #[test]
+    fn memory_limit() {
+        let rt = Runtime::new().unwrap();
+        rt.set_memory_limit(4 * 1024 * 1024);
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let source =
+                "(() => { const list = []; for(;;) list.push(new Array(1024).fill(0)) })()";
+            let res = ctx.eval::<(), _>(source);
+            assert!(matches!(res, Err(Error::Exception)));
+            let error = ctx.catch();
+            let message = error.as_exception().and_then(|error| error.message());
+            assert!(message.unwrap_or_default().contains("out of memory"));
+        });
+        // The memory of the script can be collected afterwards.
+        rt.run_gc();
+        ctx.with(|ctx| ctx.eval::<(), _>("new Array(1024).fill(0)").unwrap());
+    }
+
+    #[test]
+    fn terminate() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>("Promise.resolve().then(() => { globalThis.ran = true })")
+                .unwrap();
+        });
+        assert!(rt.is_job_pending());
+
+        let handle = rt.terminate_handle();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            handle.terminate();
+        });
+        ctx.with(|ctx| {
+            let res = ctx.eval::<(), _>("try { for(;;) {} } catch(e) {}");
+            assert!(matches!(res, Err(Error::Terminated)));
+        });
+        thread.join().unwrap();
+
+        assert!(rt.is_terminated());
+        assert!(!rt.is_job_pending());
+        assert!(matches!(rt.execute_pending_job(), Ok(false)));
+        ctx.with(|ctx| {
+            assert!(matches!(ctx.eval::<i32, _>("1"), Err(Error::Terminated)));
+            assert!(ctx
+                .globals()
+                .get::<_, Option<bool>>("ran")
+                .unwrap()
+                .is_none());
+            let (promise, _, _) = crate::Promise::new(&ctx).unwrap();
+            assert!(matches!(promise.finish::<()>(), Err(Error::Terminated)));
+        });
+    }
+
+    #[test]
+    fn stack_overflow_handler() {
+        use std::sync::{Arc, Mutex};
+
+        let rt = Runtime::new().unwrap();
+        rt.set_max_stack_size(128 * 1024);
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_clone = reported.clone();
+        rt.set_stack_overflow_handler(Some(Box::new(move |overflow| {
+            reported_clone.lock().unwrap().push(overflow.clone());
+        })));
+
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let res =
+                ctx.eval::<(), _>("function recurse(n) { return recurse(n + 1) }; recurse(0)");
+            assert!(matches!(res, Err(Error::Exception)));
+            let usage: usize = ctx
+                .eval::<crate::Function, _>("(f) => f()")
+                .unwrap()
+                .call((crate::Function::new(ctx.clone(), |ctx: Ctx| {
+                    ctx.stack_usage()
+                }),))
+                .unwrap();
+            assert!(usage > 0 && usage < 128 * 1024);
+            // Only errors thrown by the engine are reported and errors of scripts aren't inspected.
+            for script in [
+                "throw new Error('stack overflow')",
+                "throw new RangeError('stack overflow')",
+                "var e = new Error('stack overflow'); \
+                 Object.defineProperty(e, 'name', { get() { globalThis.inspected = true } }); \
+                 throw e",
+            ] {
+                let res = ctx.eval::<(), _>(script);
+                assert!(matches!(res, Err(Error::Exception)));
+            }
+            let inspected: Option<bool> = ctx.globals().get("inspected").unwrap();
+            assert_eq!(inspected, None);
+        });
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].max_stack_size, 128 * 1024);
+        assert!(reported[0].frames().any(|x| x.contains("recurse")));
+    }
+
+    #[cfg(feature = "loader")]
+    #[test]
+    fn module_invalidation() {
+        use crate::{
+            loader::{BuiltinResolver, Loader},
+            module::Declared,
+            Module,
+        };
+        use std::{
+            collections::HashMap,
+            sync::{Arc, Mutex},
+        };
+
+        struct SourceLoader(Arc<Mutex<HashMap<&'static str, &'static str>>>);
+
+        impl Loader for SourceLoader {
+            fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js, Declared>> {
+                let source = self.0.lock().unwrap()[name];
+                Module::declare(ctx.clone(), name, source)
+            }
+        }
+
+        let sources = Arc::new(Mutex::new(HashMap::from([
+            ("dep", "export default 1"),
+            ("main", "import dep from 'dep'; export default dep"),
+            ("other", "export default 'other'"),
+        ])));
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_loader(
+            BuiltinResolver::default()
+                .with_module("dep")
+                .with_module("main")
+                .with_module("other"),
+            SourceLoader(sources.clone()),
+        );
+        let import = |name: &str| {
+            ctx.with(|ctx| {
+                let namespace: crate::Object =
+                    Module::import(&ctx, name).unwrap().finish().unwrap();
+                namespace
+                    .get::<_, crate::Value>("default")
+                    .unwrap()
+                    .as_int()
+            })
+        };
+        let sorted = |mut names: Vec<String>| {
+            names.sort();
+            names
+        };
+
+        assert_eq!(import("main"), Some(1));
+        import("other");
+        assert_eq!(sorted(rt.module_dependents("dep")), ["dep", "main"]);
+
+        sources.lock().unwrap().insert("dep", "export default 2");
+        assert_eq!(import("main"), Some(1));
+        assert_eq!(sorted(rt.invalidate_module("dep")), ["dep", "main"]);
+        assert!(rt.module_dependents("dep").is_empty());
+        assert_eq!(rt.module_dependents("other"), ["other"]);
+        assert_eq!(import("main"), Some(2));
+
+        assert_eq!(sorted(rt.clear_module_cache()), ["dep", "main", "other"]);
+        assert!(rt.clear_module_cache().is_empty());
+    }
+
+    #[test]
+    fn regexp_step_limit() {
+        let rt = Runtime::new().unwrap();
+        rt.set_regexp_step_limit(Some(100_000));
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let res: String = ctx
+                .eval(
+                    r#"
+                    try {
+                        /(a+)+$/.test("a".repeat(40) + "b");
+                        "finished"
+                    } catch (e) {
+                        e instanceof RangeError ? e.message : "wrong error"
+                    }
+                    "#,
+                )
+                .unwrap();
+            assert_eq!(res, "regexp step limit exceeded");
+            // The limit applies to every execution, not to the runtime as a whole.
+            let res: bool = ctx.eval(r#"/(a+)+$/.test("aaaa")"#).unwrap();
+            assert!(res);
+        });
+
+        rt.set_regexp_step_limit(None);
+        ctx.with(|ctx| {
+            let res: bool = ctx.eval(r#"/(a+)+$/.test("a".repeat(12) + "b")"#).unwrap();
+            assert!(!res);
+        });
+    }
+
+    #[test]
+    fn check_syntax() {
+        let rt = Runtime::new().unwrap();
+        assert_eq!(
+            rt.check_syntax("let a = 1; a + 1", "valid.js").unwrap(),
+            None
+        );
+        assert_eq!(
+            rt.check_syntax("import { a } from 'a'; export const b = a;", "module.js")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            rt.check_syntax("throw new Error()", "throws.js").unwrap(),
+            None
+        );
+
+        let error = rt
+            .check_syntax("let a = 1;\nlet b = ;", "invalid.js")
+            .unwrap()
+            .unwrap();
+        assert_eq!(error.file.as_deref(), Some("invalid.js"));
+        assert_eq!(error.line, Some(2));
+        assert!(!error.message.is_empty());
+    }
+
+    #[test]
+    fn check_policy() {
+        let rt = Runtime::new().unwrap();
+        let policy = ScriptPolicy::strict();
+
+        rt.check_policy("let a = [1].with(0, 2); a.eval = 1;", "ok.js", &policy)
+            .unwrap();
+        rt.check_policy("eval('1')", "eval.js", &ScriptPolicy::default())
+            .unwrap();
+
+        let err = rt
+            .check_policy("let a = 1;\neval('a')", "eval.js", &policy)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PolicyViolation {
+                syntax: ForbiddenSyntax::Eval,
+                line: 2,
+                column: 1,
+                ..
+            }
+        ));
+
+        for source in [
+            r"\u0065val('1')",
+            "globalThis.eval('1')",
+            "(0, globalThis).eval('1')",
+        ] {
+            let err = rt.check_policy(source, "eval.js", &policy).unwrap_err();
+            assert!(matches!(
+                err,
+                Error::PolicyViolation {
+                    syntax: ForbiddenSyntax::Eval,
+                    ..
+                }
+            ));
+        }
+
+        let err = rt
+            .check_policy("with (Math) { max(1, 2) }", "with.js", &policy)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PolicyViolation {
+                syntax: ForbiddenSyntax::With,
+                ..
+            }
+        ));
+
+        let err = rt
+            .check_policy("import('foo')", "import.js", &policy)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PolicyViolation {
+                syntax: ForbiddenSyntax::DynamicImport,
+                ..
+            }
+        ));
+
+        let err = rt
+            .check_policy("await Promise.resolve(1)", "await.js", &policy)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PolicyViolation {
+                syntax: ForbiddenSyntax::TopLevelAwait,
+                ..
+            }
+        ));
+        rt.check_policy("async function f() { await 1 }", "await.js", &policy)
+            .unwrap();
+
+        // Sloppy mode syntax other than `with` and property keys are allowed.
+        for source in [
+            "var a = 010;",
+            "function f(a, a) {}",
+            "var x = 1; delete x;",
+            "let o = { eval: 1, with() {} }; o.eval;",
+        ] {
+            rt.check_policy(source, "ok.js", &policy).unwrap();
+        }
+    }
+
+    #[test]
+    fn block_on_js() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let res = rt
+            .block_on_js(&ctx, |ctx| {
+                Box::pin(async move { ctx.eval::<i32, _>("1 + 1") })
+            })
+            .unwrap();
+        assert_eq!(res.unwrap(), 2);
+
+        let other = Context::full(&Runtime::new().unwrap()).unwrap();
+        let res = rt.block_on_js(&other, |_| Box::pin(async {}));
+        assert!(matches!(res, Err(Error::UnrelatedRuntime)));
+
+        ctx.with(|_| {
+            let res = rt.block_on_js(&ctx, |_| Box::pin(async {}));
+            assert!(matches!(res, Err(Error::WouldBlock)));
+        });
+
+        // Another thread holding the runtime is waited for.
+        #[cfg(feature = "parallel")]
+        {
+            let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+            let thread_ctx = ctx.clone();
+            let handle = std::thread::spawn(move || {
+                thread_ctx.with(|_| {
+                    locked_tx.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                })
+            });
+            locked_rx.recv().unwrap();
+            let res = rt
+                .block_on_js(&ctx, |ctx| Box::pin(async move { ctx.eval::<i32, _>("2") }))
+                .unwrap();
+            assert_eq!(res.unwrap(), 2);
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn interrupt_handler_with_origin() {
+        use std::sync::{Arc, Mutex};
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let seen = Arc::new(Mutex::new(None));
+        let seen_handler = seen.clone();
+        rt.set_interrupt_handler_with_origin(Some(Box::new(move |origin| {
+            *seen_handler.lock().unwrap() = origin.map(str::to_string);
+            origin == Some("runaway")
+        })));
+        ctx.with(|ctx| {
+            ctx.with_origin("fine", || {
+                ctx.eval::<(), _>("for (let i = 0; i < 100000; i++) {}")
+            })
+            .unwrap();
+            assert_eq!(seen.lock().unwrap().as_deref(), Some("fine"));
+            let res = ctx.with_origin("runaway", || ctx.eval::<(), _>("while (true) {}"));
+            assert!(matches!(res, Err(Error::Exception)));
+            assert_eq!(seen.lock().unwrap().as_deref(), Some("runaway"));
+        });
+        rt.set_interrupt_handler_with_origin(None);
+    }
 }
@@ -0,0 +1,225 @@
+//! Runtime configuration which can be loaded from configuration files or the environment.
+
+use std::{env, fmt, str::FromStr};
+
+#[cfg(feature = "loader")]
+use crate::loader::{FileResolver, ScriptLoader};
+use crate::{context::IntrinsicKind, Error, Result, Runtime};
+
+/// Options for configuring a runtime and its contexts.
+///
+/// Every option is optional, options which are not set leave the runtime default untouched.
+/// With the `serde` feature enabled the options can be deserialized from any configuration format
+/// supported by serde, they can also be read from environment variables with
+/// [`RuntimeOptions::from_env`].
+///
+/// ```
+/// # use rquickjs::{runtime::RuntimeOptions, Context, Runtime};
+/// let options = RuntimeOptions::default()
+///     .with_memory_limit(64 * 1024 * 1024)
+///     .with_max_stack_size(512 * 1024);
+/// let rt = Runtime::from_options(&options).unwrap();
+/// let ctx = Context::from_options(&rt, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RuntimeOptions {
+    /// The limit on the memory the runtime will use, see [`Runtime::set_memory_limit`].
+    pub memory_limit: Option<usize>,
+    /// The limit on the stack size the runtime will use, see [`Runtime::set_max_stack_size`].
+    pub max_stack_size: Option<usize>,
+    /// The memory threshold for garbage collection, see [`Runtime::set_gc_threshold`].
+    pub gc_threshold: Option<usize>,
+    /// The limit on the steps of a regular expression execution, see
+    /// [`Runtime::set_regexp_step_limit`].
+    pub regexp_step_limit: Option<u64>,
+    /// The info of the runtime, see [`Runtime::set_info`].
+    pub info: Option<String>,
+    /// The intrinsics registered in contexts created with [`Context::from_options`].
+    ///
+    /// If not set all intrinsics are registered.
+    ///
+    /// [`Context::from_options`]: crate::Context::from_options
+    pub intrinsics: Option<Vec<IntrinsicKind>>,
+    /// The paths which are searched for modules.
+    ///
+    /// If not empty a [`FileResolver`] with these paths and a [`ScriptLoader`] are set as the
+    /// module loader of the runtime.
+    #[cfg(feature = "loader")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+    pub loader_paths: Vec<String>,
+}
+
+impl RuntimeOptions {
+    /// Set the memory limit.
+    #[must_use]
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = Some(limit);
+        self
+    }
+
+    /// Set the max stack size.
+    #[must_use]
+    pub fn with_max_stack_size(mut self, limit: usize) -> Self {
+        self.max_stack_size = Some(limit);
+        self
+    }
+
+    /// Set the garbage collection threshold.
+    #[must_use]
+    pub fn with_gc_threshold(mut self, threshold: usize) -> Self {
+        self.gc_threshold = Some(threshold);
+        self
+    }
+
+    /// Set the regular expression step limit.
+    #[must_use]
+    pub fn with_regexp_step_limit(mut self, limit: u64) -> Self {
+        self.regexp_step_limit = Some(limit);
+        self
+    }
+
+    /// Set the runtime info.
+    #[must_use]
+    pub fn with_info<S: Into<String>>(mut self, info: S) -> Self {
+        self.info = Some(info.into());
+        self
+    }
+
+    /// Set the intrinsics to register.
+    #[must_use]
+    pub fn with_intrinsics<I: IntoIterator<Item = IntrinsicKind>>(mut self, intrinsics: I) -> Self {
+        self.intrinsics = Some(intrinsics.into_iter().collect());
+        self
+    }
+
+    /// Add a path to search for modules.
+    #[cfg(feature = "loader")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "loader")))]
+    #[must_use]
+    pub fn with_loader_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.loader_paths.push(path.into());
+        self
+    }
+
+    /// Read the options from environment variables.
+    ///
+    /// Every option is read from the variable with the option name in upper case prefixed with
+    /// `prefix`, for example `QJS_MEMORY_LIMIT` for the prefix `QJS_`. Variables which are not
+    /// set leave the option unset. `{prefix}INTRINSICS` is a comma separated list of intrinsic
+    /// names and `{prefix}LOADER_PATHS` is a list of paths separated like the `PATH` variable.
+    pub fn from_env(prefix: &str) -> Result<Self> {
+        let var = |name: &str| env::var(format!("{prefix}{name}")).ok();
+
+        let mut options = RuntimeOptions {
+            memory_limit: var("MEMORY_LIMIT")
+                .map(|x| parse_option("memory_limit", &x))
+                .transpose()?,
+            max_stack_size: var("MAX_STACK_SIZE")
+                .map(|x| parse_option("max_stack_size", &x))
+                .transpose()?,
+            gc_threshold: var("GC_THRESHOLD")
+                .map(|x| parse_option("gc_threshold", &x))
+                .transpose()?,
+            regexp_step_limit: var("REGEXP_STEP_LIMIT")
+                .map(|x| parse_option("regexp_step_limit", &x))
+                .transpose()?,
+            info: var("INFO"),
+            ..Default::default()
+        };
+
+        if let Some(intrinsics) = var("INTRINSICS") {
+            options.intrinsics = Some(
+                intrinsics
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|x| !x.is_empty())
+                    .map(IntrinsicKind::from_str)
+                    .collect::<Result<_>>()?,
+            );
+        }
+
+        #[cfg(feature = "loader")]
+        if let Some(paths) = env::var_os(format!("{prefix}LOADER_PATHS")) {
+            options.loader_paths = env::split_paths(&paths)
+                .map(|x| x.to_string_lossy().into_owned())
+                .collect();
+        }
+
+        Ok(options)
+    }
+
+    /// Apply the options to a runtime.
+    pub fn apply(&self, runtime: &Runtime) -> Result<()> {
+        if let Some(limit) = self.memory_limit {
+            runtime.set_memory_limit(limit);
+        }
+        if let Some(limit) = self.max_stack_size {
+            runtime.set_max_stack_size(limit);
+        }
+        if let Some(threshold) = self.gc_threshold {
+            runtime.set_gc_threshold(threshold);
+        }
+        if let Some(limit) = self.regexp_step_limit {
+            runtime.set_regexp_step_limit(Some(limit));
+        }
+        if let Some(ref info) = self.info {
+            runtime.set_info(info.as_str())?;
+        }
+        #[cfg(feature = "loader")]
+        if !self.loader_paths.is_empty() {
+            let resolver = FileResolver::default().with_paths(self.loader_paths.iter().cloned());
+            runtime.set_loader(resolver, ScriptLoader::default());
+        }
+        Ok(())
+    }
+}
+
+fn parse_option<T>(name: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| Error::new_invalid_option(name, format!("`{value}`: {e}")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Context;
+
+    #[test]
+    fn from_env() {
+        env::set_var("RQUICKJS_TEST_OPTS_MEMORY_LIMIT", "1048576");
+        env::set_var("RQUICKJS_TEST_OPTS_INTRINSICS", "json, eval");
+        env::set_var("RQUICKJS_TEST_OPTS_REGEXP_STEP_LIMIT", "100000");
+        let options = RuntimeOptions::from_env("RQUICKJS_TEST_OPTS_").unwrap();
+        assert_eq!(options.memory_limit, Some(1048576));
+        assert_eq!(options.max_stack_size, None);
+        assert_eq!(options.regexp_step_limit, Some(100000));
+        assert_eq!(
+            options.intrinsics,
+            Some(vec![IntrinsicKind::Json, IntrinsicKind::Eval])
+        );
+
+        env::set_var("RQUICKJS_TEST_OPTS_BAD_MAX_STACK_SIZE", "lots");
+        let err = RuntimeOptions::from_env("RQUICKJS_TEST_OPTS_BAD_").unwrap_err();
+        assert!(matches!(err, Error::InvalidOption { ref name, .. } if name == "max_stack_size"));
+    }
+
+    #[test]
+    fn from_options() {
+        let options = RuntimeOptions::default()
+            .with_max_stack_size(512 * 1024)
+            .with_info("test runtime")
+            .with_intrinsics([IntrinsicKind::Eval]);
+        let rt = Runtime::from_options(&options).unwrap();
+        let ctx = Context::from_options(&rt, &options).unwrap();
+        let res: bool = ctx.with(|ctx| ctx.eval("typeof JSON === 'undefined'").unwrap());
+        assert!(res);
+    }
+}
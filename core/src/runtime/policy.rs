@@ -0,0 +1,370 @@
+//! Policies restricting which syntax a script may use.
+
+use std::fmt;
+
+use crate::util::lexer::{unescape, Lexer, Spanned, Token};
+
+/// A syntax category which can be forbidden by a [`ScriptPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForbiddenSyntax {
+    /// References to the global `eval` function.
+    Eval,
+    /// The `with` statement.
+    With,
+    /// Dynamic imports with `import(...)`.
+    DynamicImport,
+    /// `await` outside of async functions.
+    TopLevelAwait,
+}
+
+impl fmt::Display for ForbiddenSyntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ForbiddenSyntax::Eval => "eval".fmt(f),
+            ForbiddenSyntax::With => "with statement".fmt(f),
+            ForbiddenSyntax::DynamicImport => "dynamic import".fmt(f),
+            ForbiddenSyntax::TopLevelAwait => "top-level await".fmt(f),
+        }
+    }
+}
+
+/// A policy of syntax which scripts are not allowed to use, checked with
+/// [`Runtime::check_policy`](crate::Runtime::check_policy).
+///
+/// By default nothing is forbidden.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScriptPolicy {
+    /// Forbid references to `eval`.
+    ///
+    /// The check is lexical: it finds references to `eval`, also when written with escapes, and
+    /// accesses of `eval` on `globalThis`, `self`, `window` or `global`. It can't find `eval`
+    /// reached through an alias or a computed key like `globalThis["ev" + "al"]`, so to make
+    /// `eval` unavailable remove it from the globals of the context as well.
+    pub forbid_eval: bool,
+    /// Forbid the `with` statement.
+    pub forbid_with: bool,
+    /// Forbid `import(...)`.
+    pub forbid_dynamic_import: bool,
+    /// Forbid `await` outside of async functions.
+    pub forbid_top_level_await: bool,
+}
+
+impl ScriptPolicy {
+    /// A policy forbidding all the syntax categories.
+    pub fn strict() -> Self {
+        ScriptPolicy {
+            forbid_eval: true,
+            forbid_with: true,
+            forbid_dynamic_import: true,
+            forbid_top_level_await: true,
+        }
+    }
+
+    /// Forbid a syntax category.
+    #[must_use]
+    pub fn forbid(mut self, syntax: ForbiddenSyntax) -> Self {
+        match syntax {
+            ForbiddenSyntax::Eval => self.forbid_eval = true,
+            ForbiddenSyntax::With => self.forbid_with = true,
+            ForbiddenSyntax::DynamicImport => self.forbid_dynamic_import = true,
+            ForbiddenSyntax::TopLevelAwait => self.forbid_top_level_await = true,
+        }
+        self
+    }
+
+    /// Returns whether the syntax category is forbidden.
+    pub fn forbids(&self, syntax: ForbiddenSyntax) -> bool {
+        match syntax {
+            ForbiddenSyntax::Eval => self.forbid_eval,
+            ForbiddenSyntax::With => self.forbid_with,
+            ForbiddenSyntax::DynamicImport => self.forbid_dynamic_import,
+            ForbiddenSyntax::TopLevelAwait => self.forbid_top_level_await,
+        }
+    }
+}
+
+/// A possible use of forbidden syntax found by [`scan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Candidate {
+    pub syntax: ForbiddenSyntax,
+    pub line: i32,
+    pub column: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A `(`, `[`, `{` or `${`, `async_fn` is true for the body of an async function.
+    Group { async_fn: bool },
+    /// The expression body of an arrow function.
+    Arrow { async_fn: bool },
+    /// An object literal or destructuring pattern.
+    Object,
+    /// The body of a class.
+    Class,
+}
+
+/// Keywords after which a `{` starts an object literal or pattern instead of a block.
+const OBJECT_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "yield",
+    "await",
+    "const",
+    "let",
+    "var",
+];
+
+/// Names of the global object, accessing `eval` on them references the global `eval`.
+const GLOBAL_OBJECTS: &[&str] = &["globalThis", "self", "window", "global"];
+
+/// Returns whether the member access with the `.`, `?.` or `[` at `idx` is on the global object,
+/// like `globalThis.eval` or `(0, globalThis).eval`.
+fn on_global_object(tokens: &[Spanned], idx: usize) -> bool {
+    let object = match idx.checked_sub(1).map(|x| tokens[x].token) {
+        Some(Token::Punct(")")) => idx.checked_sub(2).map(|x| tokens[x].token),
+        object => object,
+    };
+    matches!(object, Some(Token::Ident(x)) if GLOBAL_OBJECTS.contains(&&*unescape(x)))
+}
+
+/// Returns whether a `{` after `prev` starts an object literal or pattern.
+fn opens_object(prev: Option<Token>, scope: Option<&Scope>) -> bool {
+    match prev {
+        Some(Token::Punct(")" | "]" | "}" | ";" | "{" | "=>")) => false,
+        // Labels and `case` end with a colon too, only trust it inside of an object.
+        Some(Token::Punct(":")) => scope == Some(&Scope::Object),
+        Some(Token::Punct(_)) => true,
+        Some(Token::Ident(ident)) => OBJECT_KEYWORDS.contains(&ident),
+        _ => false,
+    }
+}
+
+/// Returns whether the identifier at `idx` is the name of a property or method in an object
+/// literal or class body, which does not reference a binding.
+///
+/// Shorthand properties like `{ eval }` do reference the binding so they are not keys.
+fn is_property_key(tokens: &[Spanned], idx: usize, scope: Option<&Scope>) -> bool {
+    let mut start = idx;
+    while start > 0
+        && matches!(
+            tokens[start - 1].token,
+            Token::Ident("get" | "set" | "async" | "static") | Token::Punct("*")
+        )
+    {
+        start -= 1;
+    }
+    let prev = start.checked_sub(1).map(|x| tokens[x].token);
+    let next = tokens.get(idx + 1).map(|x| x.token);
+    match scope {
+        Some(Scope::Object) => {
+            matches!(prev, Some(Token::Punct("{" | ",")))
+                && matches!(next, Some(Token::Punct(":" | "(")))
+        }
+        Some(Scope::Class) => matches!(prev, Some(Token::Punct("{" | ";" | "}"))),
+        _ => false,
+    }
+}
+
+/// Scans a script for possible uses of forbidden syntax.
+///
+/// This is a purely lexical scan, `with` and top-level await are verified with the compiler by
+/// the caller. Escapes in identifiers are decoded, so `\u0065val` is found as `eval`.
+pub(crate) fn scan(source: &str) -> Vec<Candidate> {
+    let tokens = Lexer::new(source).tokenize();
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut pending_async: Option<usize> = None;
+    let mut pending_class: Option<usize> = None;
+    let mut candidates = Vec::new();
+
+    for (idx, spanned) in tokens.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|x| tokens[x].token);
+        let next = tokens.get(idx + 1).map(|x| x.token);
+        let after_member = matches!(prev, Some(Token::Punct(".") | Token::Punct("?.")));
+        let depth = scopes.len();
+
+        let syntax = match spanned.token {
+            // `async` is still needed to find the body of async methods.
+            Token::Ident(ident)
+                if ident != "async" && is_property_key(&tokens, idx, scopes.last()) =>
+            {
+                None
+            }
+            Token::Ident(ident) if after_member => (unescape(ident) == "eval"
+                && on_global_object(&tokens, idx - 1))
+            .then_some(ForbiddenSyntax::Eval),
+            Token::Str(x) if prev == Some(Token::Punct("[")) => (unescape(x) == "eval"
+                && on_global_object(&tokens, idx - 1))
+            .then_some(ForbiddenSyntax::Eval),
+            Token::Ident(ident) => match &*unescape(ident) {
+                "eval" => Some(ForbiddenSyntax::Eval),
+                "with" if next == Some(Token::Punct("(")) => Some(ForbiddenSyntax::With),
+                "import" if next == Some(Token::Punct("(")) => Some(ForbiddenSyntax::DynamicImport),
+                "await" => {
+                    let in_async = scopes.iter().any(|x| {
+                        matches!(
+                            x,
+                            Scope::Group { async_fn: true } | Scope::Arrow { async_fn: true }
+                        )
+                    });
+                    (!in_async).then_some(ForbiddenSyntax::TopLevelAwait)
+                }
+                "async"
+                    if matches!(
+                        next,
+                        Some(Token::Ident(_) | Token::Punct("(") | Token::Punct("*"))
+                    ) =>
+                {
+                    pending_async = Some(depth);
+                    None
+                }
+                "class" => {
+                    pending_class = Some(depth);
+                    None
+                }
+                _ => None,
+            },
+            Token::Punct(punct) => {
+                match punct {
+                    "(" | "[" | "${" => scopes.push(Scope::Group { async_fn: false }),
+                    "{" => {
+                        let async_fn = pending_async == Some(depth);
+                        let scope = if async_fn {
+                            pending_async = None;
+                            Scope::Group { async_fn }
+                        } else if pending_class == Some(depth) {
+                            pending_class = None;
+                            Scope::Class
+                        } else if opens_object(prev, scopes.last()) {
+                            Scope::Object
+                        } else {
+                            Scope::Group { async_fn }
+                        };
+                        scopes.push(scope);
+                    }
+                    ")" | "]" | "}" => {
+                        while let Some(Scope::Arrow { .. }) = scopes.last() {
+                            scopes.pop();
+                        }
+                        scopes.pop();
+                    }
+                    "," | ";" => {
+                        while let Some(Scope::Arrow { .. }) = scopes.last() {
+                            scopes.pop();
+                        }
+                        if punct == ";" && pending_async.map(|x| x >= scopes.len()).unwrap_or(false)
+                        {
+                            pending_async = None;
+                        }
+                    }
+                    "=>" if next != Some(Token::Punct("{")) => {
+                        let async_fn = pending_async == Some(depth);
+                        if async_fn {
+                            pending_async = None;
+                        }
+                        scopes.push(Scope::Arrow { async_fn });
+                    }
+                    _ => {}
+                }
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(syntax) = syntax {
+            candidates.push(Candidate {
+                syntax,
+                line: spanned.line,
+                column: spanned.column,
+            });
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<ForbiddenSyntax> {
+        scan(source).into_iter().map(|x| x.syntax).collect()
+    }
+
+    #[test]
+    fn scan_categories() {
+        assert_eq!(kinds("eval('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("obj.eval('1'); 'eval'; // eval"), []);
+        assert_eq!(kinds("with (obj) { a }"), [ForbiddenSyntax::With]);
+        assert_eq!(kinds("[1, 2].with(0, 3)"), []);
+        assert_eq!(
+            kinds("import('a').then(x => x)"),
+            [ForbiddenSyntax::DynamicImport]
+        );
+        assert_eq!(kinds("import.meta.url"), []);
+        assert_eq!(kinds("`${eval}`"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("let r = /eval(/g; r.test(x)"), []);
+    }
+
+    #[test]
+    fn scan_eval_bypasses() {
+        assert_eq!(kinds(r"\u0065val('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds(r"\u{65}val('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds(r"ev\u0061l('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("globalThis.eval('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("window?.eval('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("self.eval('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("(0, globalThis).eval('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("globalThis['eval']('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(
+            kinds(r#"globalThis["\x65val"]('1')"#),
+            [ForbiddenSyntax::Eval]
+        );
+        assert_eq!(kinds(r"globalThis.\u0065val('1')"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("obj['eval']('1'); obj.window.evaluate()"), []);
+    }
+
+    #[test]
+    fn scan_property_keys() {
+        assert_eq!(kinds("({ eval: 1 })"), []);
+        assert_eq!(kinds("let o = { a: 1, eval() {}, get with() {} }"), []);
+        assert_eq!(
+            kinds("class A { eval() {} static with = 1; import() {} }"),
+            []
+        );
+        assert_eq!(kinds("const { eval: e } = obj"), []);
+        assert_eq!(kinds("let o = { eval }"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("let o = { a: eval }"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("let o = { [eval]: 1 }"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("let o = { a: b * eval(1) }"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("if (a) { eval: 1 }"), [ForbiddenSyntax::Eval]);
+        assert_eq!(kinds("class A { a = eval }"), [ForbiddenSyntax::Eval]);
+    }
+
+    #[test]
+    fn scan_await() {
+        assert_eq!(kinds("await foo()"), [ForbiddenSyntax::TopLevelAwait]);
+        assert_eq!(kinds("async function f() { await foo() }"), []);
+        assert_eq!(kinds("const f = async () => { await foo() }"), []);
+        assert_eq!(kinds("const f = async x => await x, y = 1"), []);
+        assert_eq!(
+            kinds("const f = async x => x, y = await 1"),
+            [ForbiddenSyntax::TopLevelAwait]
+        );
+        assert_eq!(kinds("class A { async run(a = {}) { await a } }"), []);
+    }
+
+    #[test]
+    fn scan_location() {
+        let candidates = scan("let a = 1;\n  eval(a)");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!((candidates[0].line, candidates[0].column), (2, 3));
+    }
+}
@@ -1,17 +1,45 @@
 use std::{
-    any::Any, ffi::CString, marker::PhantomData, mem, panic, ptr::NonNull,
+    any::Any,
+    ffi::{CStr, CString},
+    marker::PhantomData,
+    mem, panic,
+    ptr::{self, NonNull},
     result::Result as StdResult,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    time::Instant,
 };
 
 #[cfg(feature = "allocator")]
-use crate::allocator::{Allocator, AllocatorHolder};
+use crate::allocator::{Allocator, AllocatorHolder, MemoryAccounting, MemoryScope};
 #[cfg(feature = "loader")]
-use crate::loader::{Loader, LoaderHolder, Resolver};
-use crate::qjs::{self, size_t};
+use crate::loader::{ImportMap, Loader, LoaderHolder, Resolver};
+use crate::{
+    persistent::DeferredDrop,
+    qjs::{self, size_t},
+    source::SourceFile,
+    StdString,
+};
+use std::collections::HashMap;
 
 #[cfg(feature = "futures")]
 use super::spawner::Spawner;
-use super::InterruptHandler;
+use super::{sampler::Sampler, InterruptHandler, OriginInterruptHandler, StackOverflowHandler};
+
+/// The default max stack size of QuickJS.
+const DEFAULT_MAX_STACK_SIZE: usize = 256 * 1024;
+/// The default max nesting of objects converted to Rust values.
+const DEFAULT_MAX_CONVERSION_DEPTH: usize = 256;
+
+/// Returns an approximation of the current stack pointer.
+#[inline(never)]
+pub(crate) fn stack_pointer() -> usize {
+    let marker = 0u8;
+    ptr::addr_of!(marker) as usize
+}
 
 /// Opaque book keeping data for Rust.
 pub(crate) struct Opaque<'js> {
@@ -19,36 +47,176 @@ pub(crate) struct Opaque<'js> {
     pub panic: Option<Box<dyn Any + Send + 'static>>,
 
     /// The user provided interrupt handler, if any.
-    pub interrupt_handler: Option<InterruptHandler>,
+    pub interrupt_handler: Option<OriginInterruptHandler>,
+    /// The origin label of the running code, set with [`Ctx::with_origin`](crate::Ctx::with_origin).
+    pub origin: Option<Arc<str>>,
+    /// The sampler of running functions, if enabled.
+    pub sampler: Option<Sampler>,
+    /// When the running evaluation is interrupted, set with
+    /// [`Ctx::eval_with_timeout`](crate::Ctx::eval_with_timeout).
+    pub deadline: Option<Instant>,
+
+    /// The user provided stack overflow handler, if any.
+    pub stack_overflow_handler: Option<StackOverflowHandler>,
+    /// The last exception object reported to the stack overflow handler, used to report an
+    /// overflow only once while it unwinds through Rust frames.
+    pub last_stack_overflow: *mut qjs::c_void,
+
+    /// Whether conversions to Rust maps skip the `__proto__` and `constructor` keys.
+    pub hardened_conversions: bool,
+    /// The max nesting of objects converted to Rust values.
+    pub max_conversion_depth: usize,
+    /// The objects which are currently converted to Rust values, outermost first.
+    pub conversion_path: Vec<*mut qjs::c_void>,
+
+    /// The stack pointer when the stack top of the runtime was last updated.
+    pub stack_top: usize,
+    /// The max stack size of the runtime.
+    pub max_stack_size: usize,
+
+    /// The sources of evaluated scripts and modules by file name, if source retention is enabled.
+    pub sources: Option<HashMap<StdString, Arc<SourceFile>>>,
+
+    /// The atoms of static strings, by the address and length of the string, created once and
+    /// freed with the runtime.
+    pub static_atoms: HashMap<(usize, usize), qjs::JSAtom>,
+
+    /// The values of dropped persistents, which are freed when the runtime is entered.
+    pub drop_send: Sender<DeferredDrop>,
+    pub drop_recv: Receiver<DeferredDrop>,
+
+    /// The import maps of contexts which override the one of the resolver, by context pointer.
+    #[cfg(feature = "loader")]
+    pub import_maps: HashMap<usize, Arc<ImportMap>>,
+
+    /// The memory accounting of the custom allocator, null if the runtime doesn't use one.
+    #[cfg(feature = "allocator")]
+    pub memory_accounting: *mut MemoryAccounting,
 
     #[cfg(feature = "futures")]
     pub spawner: Option<Spawner>,
+    /// Whether a future is currently driven by [`Ctx::block_on`](crate::Ctx::block_on).
+    #[cfg(feature = "futures")]
+    pub blocking: bool,
+    /// Whether the runtime was shut down, after which spawned futures are dropped and awaited
+    /// promises fail.
+    #[cfg(feature = "futures")]
+    pub terminated: bool,
+    /// The wakers of futures awaiting promises by id, woken when the runtime is shut down.
+    ///
+    /// A waker is removed when its promise settles or its future is dropped. Declared after
+    /// `spawner` so the map is still alive when the spawned futures are dropped.
+    #[cfg(feature = "futures")]
+    pub promise_wakers: HashMap<usize, std::rc::Weak<std::cell::RefCell<std::task::Waker>>>,
+    /// The id of the next waker in `promise_wakers`.
+    #[cfg(feature = "futures")]
+    pub next_promise_waker: usize,
+    /// The growth of the allocated memory after which the idle runtime is garbage collected,
+    /// with the allocated memory after the last collection.
+    #[cfg(feature = "futures")]
+    pub idle_gc: Option<(usize, usize)>,
+    /// Set by [`Runtime::terminate`](crate::Runtime::terminate), possibly from another thread.
+    pub termination: Arc<AtomicBool>,
 
     _marker: PhantomData<&'js ()>,
 }
 
 impl<'js> Opaque<'js> {
     pub fn new() -> Self {
+        let (drop_send, drop_recv) = mpsc::channel();
         Opaque {
             panic: None,
             interrupt_handler: None,
+            origin: None,
+            sampler: None,
+            deadline: None,
+            stack_overflow_handler: None,
+            last_stack_overflow: ptr::null_mut(),
+            hardened_conversions: false,
+            max_conversion_depth: DEFAULT_MAX_CONVERSION_DEPTH,
+            conversion_path: Vec::new(),
+            stack_top: stack_pointer(),
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            sources: None,
+            static_atoms: HashMap::new(),
+            drop_send,
+            drop_recv,
+            #[cfg(feature = "loader")]
+            import_maps: HashMap::new(),
+            #[cfg(feature = "allocator")]
+            memory_accounting: ptr::null_mut(),
             #[cfg(feature = "futures")]
             spawner: None,
+            #[cfg(feature = "futures")]
+            blocking: false,
+            #[cfg(feature = "futures")]
+            terminated: false,
+            #[cfg(feature = "futures")]
+            promise_wakers: HashMap::new(),
+            #[cfg(feature = "futures")]
+            next_promise_waker: 0,
+            #[cfg(feature = "futures")]
+            idle_gc: None,
+            termination: Arc::new(AtomicBool::new(false)),
             _marker: PhantomData,
         }
     }
 
     #[cfg(feature = "futures")]
     pub fn with_spawner() -> Self {
+        let (drop_send, drop_recv) = mpsc::channel();
         Opaque {
             panic: None,
             interrupt_handler: None,
+            origin: None,
+            sampler: None,
+            deadline: None,
+            stack_overflow_handler: None,
+            last_stack_overflow: ptr::null_mut(),
+            hardened_conversions: false,
+            max_conversion_depth: DEFAULT_MAX_CONVERSION_DEPTH,
+            conversion_path: Vec::new(),
+            stack_top: stack_pointer(),
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            sources: None,
+            static_atoms: HashMap::new(),
+            drop_send,
+            drop_recv,
+            #[cfg(feature = "loader")]
+            import_maps: HashMap::new(),
+            #[cfg(feature = "allocator")]
+            memory_accounting: ptr::null_mut(),
             #[cfg(feature = "futures")]
             spawner: Some(Spawner::new()),
+            #[cfg(feature = "futures")]
+            blocking: false,
+            #[cfg(feature = "futures")]
+            terminated: false,
+            #[cfg(feature = "futures")]
+            promise_wakers: HashMap::new(),
+            #[cfg(feature = "futures")]
+            next_promise_waker: 0,
+            #[cfg(feature = "futures")]
+            idle_gc: None,
+            termination: Arc::new(AtomicBool::new(false)),
             _marker: PhantomData,
         }
     }
 
+    /// Whether [`Runtime::terminate`](crate::Runtime::terminate) was called.
+    pub fn termination_requested(&self) -> bool {
+        self.termination.load(Ordering::Relaxed)
+    }
+
+    /// Whether the runtime was terminated or, for an async runtime, shut down.
+    pub fn is_terminated(&self) -> bool {
+        #[cfg(feature = "futures")]
+        if self.terminated {
+            return true;
+        }
+        self.termination_requested()
+    }
+
     #[cfg(feature = "futures")]
     pub fn spawner(&mut self) -> &mut Spawner {
         self.spawner
@@ -79,8 +247,18 @@ unsafe impl Send for RawRuntime {}
 impl Drop for RawRuntime {
     fn drop(&mut self) {
         unsafe {
+            self.free_deferred();
+            // Drop the spawned futures while the opaque is intact, objects finalized from now on
+            // are dropped synchronously.
+            #[cfg(feature = "futures")]
+            self.terminate();
             let ptr = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr());
             let opaque: Box<Opaque> = Box::from_raw(ptr as *mut _);
+            for atom in opaque.static_atoms.values() {
+                qjs::JS_FreeAtomRT(self.rt.as_ptr(), *atom);
+            }
+            // The objects collected by freeing the runtime must not see the freed opaque.
+            qjs::JS_SetRuntimeOpaque(self.rt.as_ptr(), ptr::null_mut());
             mem::drop(opaque);
             qjs::JS_FreeRuntime(self.rt.as_ptr())
         }
@@ -97,9 +275,10 @@ impl RawRuntime {
     }
 
     #[allow(dead_code)]
-    pub unsafe fn new_base(opaque: Opaque<'static>) -> Option<Self> {
+    pub unsafe fn new_base(mut opaque: Opaque<'static>) -> Option<Self> {
         let rt = qjs::JS_NewRuntime();
         let rt = NonNull::new(rt)?;
+        opaque.stack_top = stack_pointer();
 
         let opaque = Box::into_raw(Box::new(opaque));
         unsafe { qjs::JS_SetRuntimeOpaque(rt.as_ptr(), opaque as *mut _) };
@@ -115,7 +294,7 @@ impl RawRuntime {
     }
 
     #[cfg(feature = "allocator")]
-    pub unsafe fn new_with_allocator<A>(opaque: Opaque<'static>, allocator: A) -> Option<Self>
+    pub unsafe fn new_with_allocator<A>(mut opaque: Opaque<'static>, allocator: A) -> Option<Self>
     where
         A: Allocator + 'static,
     {
@@ -125,6 +304,8 @@ impl RawRuntime {
 
         let rt = qjs::JS_NewRuntime2(&functions, opaque_ptr as _);
         let rt = NonNull::new(rt)?;
+        opaque.stack_top = stack_pointer();
+        opaque.memory_accounting = allocator.accounting();
 
         let opaque = Box::into_raw(Box::new(opaque));
         unsafe { qjs::JS_SetRuntimeOpaque(rt.as_ptr(), opaque as *mut _) };
@@ -142,6 +323,48 @@ impl RawRuntime {
         #[cfg(feature = "parallel")]
         unsafe {
             qjs::JS_UpdateStackTop(self.rt.as_ptr());
+            let opaque = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque;
+            (*opaque).stack_top = stack_pointer();
+        }
+    }
+
+    /// Drop the state kept for a context, called when a context is created or freed since
+    /// freed contexts can be allocated at the same address again.
+    pub fn forget_context(&self, ctx: NonNull<qjs::JSContext>) {
+        #[cfg(feature = "loader")]
+        unsafe {
+            let opaque = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque;
+            (*opaque).import_maps.remove(&(ctx.as_ptr() as usize));
+        }
+        #[cfg(feature = "allocator")]
+        unsafe {
+            let opaque = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque;
+            if let Some(accounting) = (*opaque).memory_accounting.as_mut() {
+                accounting.forget(ctx);
+            }
+        }
+        #[cfg(not(any(feature = "loader", feature = "allocator")))]
+        let _ = ctx;
+    }
+
+    /// Attribute the memory allocated until the returned scope is dropped to `ctx`.
+    #[cfg(feature = "allocator")]
+    pub fn enter_context(&self, ctx: NonNull<qjs::JSContext>) -> MemoryScope {
+        unsafe {
+            let opaque = qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque;
+            match (*opaque).memory_accounting.as_mut() {
+                Some(accounting) => accounting.enter(ctx),
+                None => MemoryScope::none(),
+            }
+        }
+    }
+
+    /// Free the values of persistents which were dropped since the runtime was last entered.
+    pub fn free_deferred(&self) {
+        let opaque = unsafe { qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque };
+        // Freeing a value can drop more persistents, so don't hold a reference to the opaque.
+        while let Ok(deferred) = unsafe { (*opaque).drop_recv.try_recv() } {
+            mem::drop(deferred);
         }
     }
 
@@ -150,10 +373,20 @@ impl RawRuntime {
     }
 
     pub fn is_job_pending(&self) -> bool {
-        0 != unsafe { qjs::JS_IsJobPending(self.rt.as_ptr()) }
+        let opaque = unsafe { &*(qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()) as *mut Opaque) };
+        !opaque.termination_requested() && 0 != unsafe { qjs::JS_IsJobPending(self.rt.as_ptr()) }
     }
 
     pub fn execute_pending_job(&mut self) -> StdResult<bool, *mut qjs::JSContext> {
+        self.free_deferred();
+        if unsafe { self.get_opaque_mut() }.termination_requested() {
+            // The jobs of a terminated runtime are cancelled, they are freed with the runtime.
+            #[cfg(feature = "futures")]
+            unsafe {
+                self.terminate()
+            };
+            return Ok(false);
+        }
         let mut ctx_ptr = mem::MaybeUninit::<*mut qjs::JSContext>::uninit();
         let result = unsafe { qjs::JS_ExecutePendingJob(self.rt.as_ptr(), ctx_ptr.as_mut_ptr()) };
         if result == 0 {
@@ -199,10 +432,98 @@ impl RawRuntime {
     ///
     /// The default values is 256x1024 bytes.
     pub unsafe fn set_max_stack_size(&mut self, limit: usize) {
+        self.get_opaque_mut().max_stack_size = limit;
         let limit: size_t = limit.try_into().unwrap_or(size_t::MAX);
         qjs::JS_SetMaxStackSize(self.rt.as_ptr(), limit);
     }
 
+    /// Set a limit on the backtracking steps of a single regular expression execution.
+    ///
+    /// Setting the limit to 0 removes the limit.
+    pub unsafe fn set_regexp_step_limit(&mut self, limit: u64) {
+        qjs::JS_SetRegExpStepLimit(self.rt.as_ptr(), limit);
+    }
+
+    /// Returns the loaded modules named `name` and every module which imports one of them,
+    /// directly or indirectly, or all loaded modules if `name` is `None`.
+    unsafe fn stale_modules(
+        &self,
+        name: Option<&str>,
+    ) -> Vec<(*mut qjs::JSContext, *mut qjs::JSModuleDef, String)> {
+        let mut stale = Vec::new();
+        let mut ctx = qjs::JS_GetNextContext(self.rt.as_ptr(), ptr::null_mut());
+        while !ctx.is_null() {
+            let mut modules = Vec::new();
+            let mut module = qjs::JS_GetNextLoadedModule(ctx, ptr::null_mut());
+            while !module.is_null() {
+                let atom = qjs::JS_GetModuleName(ctx, module);
+                let c_str = qjs::JS_AtomToCString(ctx, atom);
+                let module_name = CStr::from_ptr(c_str).to_string_lossy().into_owned();
+                qjs::JS_FreeCString(ctx, c_str);
+                qjs::JS_FreeAtom(ctx, atom);
+                let marked = name.map_or(true, |name| name == module_name);
+                modules.push((module, module_name, marked));
+                module = qjs::JS_GetNextLoadedModule(ctx, module);
+            }
+
+            // Importers of stale modules are stale too, repeat until no importer is added.
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for idx in 0..modules.len() {
+                    let module = modules[idx].0;
+                    if modules[idx].2 {
+                        continue;
+                    }
+                    let imports_stale = (0..qjs::JS_GetModuleRequireCount(module)).any(|req| {
+                        let req = qjs::JS_GetModuleRequire(module, req);
+                        modules.iter().any(|(x, _, marked)| *marked && *x == req)
+                    });
+                    if imports_stale {
+                        modules[idx].2 = true;
+                        changed = true;
+                    }
+                }
+            }
+
+            stale.extend(
+                modules
+                    .into_iter()
+                    .filter(|(_, _, marked)| *marked)
+                    .map(|(module, name, _)| (ctx, module, name)),
+            );
+            ctx = qjs::JS_GetNextContext(self.rt.as_ptr(), ctx);
+        }
+        stale
+    }
+
+    /// Returns the names of the modules [`RawRuntime::invalidate_modules`] would invalidate.
+    pub unsafe fn stale_module_names(&self, name: Option<&str>) -> Vec<String> {
+        let mut names = Vec::new();
+        for (_, _, name) in self.stale_modules(name) {
+            // Contexts have their own module caches which can load the same module.
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Remove the loaded modules named `name` and the modules which import them from the
+    /// module caches of all contexts, or all modules if `name` is `None`.
+    ///
+    /// Returns the names of the removed modules.
+    pub unsafe fn invalidate_modules(&mut self, name: Option<&str>) -> Vec<String> {
+        let mut names = Vec::new();
+        for (ctx, module, name) in self.stale_modules(name) {
+            qjs::JS_InvalidateModule(ctx, module);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
     /// Set a memory threshold for garbage collection.
     pub unsafe fn set_gc_threshold(&self, threshold: usize) {
         qjs::JS_SetGCThreshold(self.rt.as_ptr(), threshold as _);
@@ -215,9 +536,35 @@ impl RawRuntime {
     /// references. The garbage collector is only for collecting
     /// cyclic references.
     pub unsafe fn run_gc(&mut self) {
+        self.free_deferred();
         qjs::JS_RunGC(self.rt.as_ptr());
     }
 
+    /// Garbage collect the runtime when it becomes idle once the allocated memory grew by more
+    /// than `threshold` bytes since the last collection, or stop doing so.
+    #[cfg(feature = "futures")]
+    pub unsafe fn set_idle_gc(&mut self, threshold: Option<usize>) {
+        let allocated = self.memory_usage().malloc_size as usize;
+        self.get_opaque_mut().idle_gc = threshold.map(|threshold| (threshold, allocated));
+    }
+
+    /// Run the idle garbage collection if it is enabled and the threshold was exceeded.
+    #[cfg(feature = "futures")]
+    pub unsafe fn collect_idle(&mut self) {
+        let Some((threshold, last)) = self.get_opaque_mut().idle_gc else {
+            return;
+        };
+        let allocated = self.memory_usage().malloc_size as usize;
+        // Memory freed by reference counting lowers the base of the growth.
+        let last = if allocated.saturating_sub(last) > threshold {
+            self.run_gc();
+            self.memory_usage().malloc_size as usize
+        } else {
+            last.min(allocated)
+        };
+        self.get_opaque_mut().idle_gc = Some((threshold, last));
+    }
+
     /// Get memory usage stats
     pub unsafe fn memory_usage(&mut self) -> qjs::JSMemoryUsage {
         let mut stats = mem::MaybeUninit::uninit();
@@ -229,33 +576,147 @@ impl RawRuntime {
     /// If the provided closure returns `true` the interpreter will raise and uncatchable
     /// exception and return control flow to the caller.
     pub unsafe fn set_interrupt_handler(&mut self, handler: Option<InterruptHandler>) {
-        unsafe extern "C" fn interrupt_handler_trampoline(
-            _rt: *mut qjs::JSRuntime,
-            opaque: *mut ::std::os::raw::c_void,
-        ) -> ::std::os::raw::c_int {
-            let catch_unwind = panic::catch_unwind(move || {
-                let opaque = &mut *(opaque as *mut Opaque);
-                opaque.interrupt_handler.as_mut().expect("handler is set")()
-            });
-            let should_interrupt = match catch_unwind {
-                Ok(should_interrupt) => should_interrupt,
-                Err(panic) => {
-                    let opaque = &mut *(opaque as *mut Opaque);
-                    opaque.panic = Some(panic);
-                    // Returning true here will cause the interpreter to raise an un-catchable exception.
-                    // The Rust code that is running the interpreter will see that exception and continue
-                    // the panic handling. See crate::result::{handle_exception, handle_panic} for details.
-                    true
-                }
-            };
-            should_interrupt as _
-        }
+        let handler = handler.map(|mut handler| {
+            Box::new(move |_: Option<&str>| handler()) as OriginInterruptHandler
+        });
+        self.set_origin_interrupt_handler(handler);
+    }
 
-        qjs::JS_SetInterruptHandler(
-            self.rt.as_ptr(),
-            handler.as_ref().map(|_| interrupt_handler_trampoline as _),
-            qjs::JS_GetRuntimeOpaque(self.rt.as_ptr()),
-        );
+    /// Set an interrupt handler which receives the origin of the running code.
+    pub unsafe fn set_origin_interrupt_handler(&mut self, handler: Option<OriginInterruptHandler>) {
         self.get_opaque_mut().interrupt_handler = handler;
+        self.update_interrupt_handler();
+    }
+
+    /// Enable sampling the running function every `interval` interrupt checks, or disable it.
+    pub unsafe fn set_sampling(&mut self, interval: Option<u32>) {
+        let sampler = interval.and_then(|interval| Sampler::new(self.rt.as_ptr(), interval));
+        self.get_opaque_mut().sampler = sampler;
+        self.update_interrupt_handler();
+    }
+
+    /// Terminate the runtime, see [`Runtime::terminate`](crate::Runtime::terminate).
+    pub unsafe fn request_termination(&mut self) {
+        self.get_opaque_mut()
+            .termination
+            .store(true, Ordering::Relaxed);
+        self.update_interrupt_handler();
     }
+
+    /// Returns the termination flag of the runtime.
+    ///
+    /// The interrupt trampoline stays installed while the flag is shared, so setting it
+    /// interrupts running code.
+    pub unsafe fn termination_flag(&mut self) -> Arc<AtomicBool> {
+        let flag = self.get_opaque_mut().termination.clone();
+        self.update_interrupt_handler();
+        flag
+    }
+
+    unsafe fn update_interrupt_handler(&mut self) {
+        update_interrupt_handler(self.rt.as_ptr());
+    }
+
+    /// Shut the runtime down, dropping the spawned futures and waking the futures awaiting
+    /// promises so they fail. Also called by the first job execution after the runtime was
+    /// terminated.
+    #[cfg(feature = "futures")]
+    pub unsafe fn terminate(&mut self) {
+        let opaque = self.get_opaque_mut();
+        opaque.terminated = true;
+        if let Some(spawner) = opaque.spawner.as_mut() {
+            spawner.clear();
+        }
+        for waker in mem::take(&mut opaque.promise_wakers).into_values() {
+            if let Some(waker) = waker.upgrade() {
+                waker.borrow().wake_by_ref();
+            }
+        }
+    }
+
+    /// Set whether conversions to Rust maps skip the `__proto__` and `constructor` keys.
+    pub unsafe fn set_hardened_conversions(&mut self, enabled: bool) {
+        self.get_opaque_mut().hardened_conversions = enabled;
+    }
+
+    /// Set whether the sources of evaluated scripts and modules are kept, dropping the kept
+    /// sources when disabled.
+    pub unsafe fn set_source_retention(&mut self, enabled: bool) {
+        let sources = &mut self.get_opaque_mut().sources;
+        match (enabled, sources.is_some()) {
+            (true, false) => *sources = Some(HashMap::new()),
+            (false, true) => *sources = None,
+            _ => {}
+        }
+    }
+
+    /// Returns the kept source of a script or module.
+    pub unsafe fn source(&mut self, name: &str) -> Option<Arc<SourceFile>> {
+        self.get_opaque_mut().sources.as_ref()?.get(name).cloned()
+    }
+
+    /// Set the max nesting of objects converted to Rust values.
+    pub unsafe fn set_max_conversion_depth(&mut self, depth: usize) {
+        self.get_opaque_mut().max_conversion_depth = depth;
+    }
+
+    /// Set a closure which is called when a stack overflow exception is raised.
+    pub unsafe fn set_stack_overflow_handler(&mut self, handler: Option<StackOverflowHandler>) {
+        self.get_opaque_mut().stack_overflow_handler = handler;
+    }
+}
+
+/// Install the interrupt trampoline if it is needed, to call the interrupt handler and the sampler
+/// and to interrupt a terminated runtime or an evaluation past its deadline.
+pub(crate) unsafe fn update_interrupt_handler(rt: *mut qjs::JSRuntime) {
+    unsafe extern "C" fn interrupt_handler_trampoline(
+        _rt: *mut qjs::JSRuntime,
+        opaque: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int {
+        let catch_unwind = panic::catch_unwind(move || {
+            let opaque = &mut *(opaque as *mut Opaque);
+            if opaque.termination_requested() {
+                return true;
+            }
+            if opaque
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline)
+            {
+                return true;
+            }
+            let origin = opaque.origin.as_deref();
+            if let Some(sampler) = opaque.sampler.as_mut() {
+                sampler.tick(origin);
+            }
+            opaque
+                .interrupt_handler
+                .as_mut()
+                .map_or(false, |handler| handler(origin))
+        });
+        let should_interrupt = match catch_unwind {
+            Ok(should_interrupt) => should_interrupt,
+            Err(panic) => {
+                let opaque = &mut *(opaque as *mut Opaque);
+                opaque.panic = Some(panic);
+                // Returning true here will cause the interpreter to raise an un-catchable exception.
+                // The Rust code that is running the interpreter will see that exception and continue
+                // the panic handling. See crate::result::{handle_exception, handle_panic} for details.
+                true
+            }
+        };
+        should_interrupt as _
+    }
+
+    let opaque = &*(qjs::JS_GetRuntimeOpaque(rt) as *const Opaque);
+    let enabled = opaque.interrupt_handler.is_some()
+        || opaque.sampler.is_some()
+        || opaque.deadline.is_some()
+        // A shared flag can be set from another thread at any time.
+        || Arc::strong_count(&opaque.termination) > 1
+        || opaque.termination_requested();
+    qjs::JS_SetInterruptHandler(
+        rt,
+        enabled.then_some(interrupt_handler_trampoline as _),
+        qjs::JS_GetRuntimeOpaque(rt),
+    );
 }
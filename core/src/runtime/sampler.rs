@@ -0,0 +1,210 @@
+use std::{collections::HashMap, ffi::CStr, ptr::NonNull};
+
+use crate::{cstr, qjs};
+
+/// A JavaScript function and the number of times it was running when the sampler took a sample,
+/// returned by [`Runtime::hot_functions`](crate::Runtime::hot_functions).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HotFunction {
+    /// The name of the function, `<anonymous>` for anonymous functions.
+    pub name: String,
+    /// The file the function was defined in, `None` for native functions.
+    pub file: Option<String>,
+    /// The origin label of the code which was running, set with
+    /// [`Ctx::with_origin`](crate::Ctx::with_origin).
+    pub origin: Option<String>,
+    /// The number of samples taken while the function was running.
+    pub samples: u64,
+}
+
+/// Samples the innermost function of the JavaScript stack from the interrupt handler.
+pub(crate) struct Sampler {
+    /// A context only used to capture the stack, which is shared by all contexts of the runtime.
+    ctx: NonNull<qjs::JSContext>,
+    interval: u32,
+    countdown: u32,
+    samples: HashMap<(String, Option<String>, Option<String>), u64>,
+}
+
+impl Sampler {
+    pub unsafe fn new(rt: *mut qjs::JSRuntime, interval: u32) -> Option<Self> {
+        let ctx = NonNull::new(qjs::JS_NewContextRaw(rt))?;
+        qjs::JS_AddIntrinsicBaseObjects(ctx.as_ptr());
+        let interval = interval.max(1);
+        Some(Sampler {
+            ctx,
+            interval,
+            countdown: interval,
+            samples: HashMap::new(),
+        })
+    }
+
+    /// Called on every interrupt check, takes a sample every `interval` calls.
+    pub unsafe fn tick(&mut self, origin: Option<&str>) {
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return;
+        }
+        self.countdown = self.interval;
+
+        if let Some(stack) = self.capture_stack() {
+            if let Some((name, file)) = innermost_frame(&stack) {
+                let key = (name, file, origin.map(str::to_string));
+                *self.samples.entry(key).or_default() += 1;
+            }
+        }
+    }
+
+    /// Returns the stack of the currently running JavaScript code.
+    unsafe fn capture_stack(&self) -> Option<String> {
+        let ctx = self.ctx.as_ptr();
+        // Errors capture the stack of the runtime when they are created.
+        let error = qjs::JS_NewError(ctx);
+        if qjs::JS_IsException(error) {
+            qjs::JS_FreeValue(ctx, qjs::JS_GetException(ctx));
+            return None;
+        }
+        let stack = qjs::JS_GetPropertyStr(ctx, error, cstr!("stack").as_ptr());
+        qjs::JS_FreeValue(ctx, error);
+        if !qjs::JS_IsString(stack) {
+            qjs::JS_FreeValue(ctx, stack);
+            return None;
+        }
+        let ptr = qjs::JS_ToCString(ctx, stack);
+        qjs::JS_FreeValue(ctx, stack);
+        if ptr.is_null() {
+            return None;
+        }
+        let res = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+        qjs::JS_FreeCString(ctx, ptr);
+        Some(res)
+    }
+
+    pub fn hot_functions(&self, top_n: usize) -> Vec<HotFunction> {
+        let mut functions: Vec<_> = self
+            .samples
+            .iter()
+            .map(|((name, file, origin), samples)| HotFunction {
+                name: name.clone(),
+                file: file.clone(),
+                origin: origin.clone(),
+                samples: *samples,
+            })
+            .collect();
+        functions.sort_by(|a, b| {
+            b.samples
+                .cmp(&a.samples)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.file.cmp(&b.file))
+                .then_with(|| a.origin.cmp(&b.origin))
+        });
+        functions.truncate(top_n);
+        functions
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe { qjs::JS_FreeContext(self.ctx.as_ptr()) }
+    }
+}
+
+/// Parses the function name and file of the first frame of a stack like
+/// `    at name (file:line:column)`.
+fn innermost_frame(stack: &str) -> Option<(String, Option<String>)> {
+    let frame = stack.lines().map(str::trim).find(|x| !x.is_empty())?;
+    let frame = frame.strip_prefix("at ")?;
+    let (name, location) = match frame.rfind(" (") {
+        Some(idx) if frame.ends_with(')') => (&frame[..idx], &frame[idx + 2..frame.len() - 1]),
+        _ => (frame, ""),
+    };
+    let file = match location {
+        "" | "native" => None,
+        location => {
+            let mut file = location;
+            // Strip the line and column.
+            while let Some((rest, number)) = file.rsplit_once(':') {
+                if number.is_empty() || !number.bytes().all(|x| x.is_ascii_digit()) {
+                    break;
+                }
+                file = rest;
+            }
+            Some(file.to_string())
+        }
+    };
+    Some((name.to_string(), file))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Runtime};
+
+    #[test]
+    fn parse_frames() {
+        assert_eq!(
+            innermost_frame("    at fib (script.js:3:10)\n    at <eval> (script.js:5)\n"),
+            Some(("fib".into(), Some("script.js".into())))
+        );
+        assert_eq!(
+            innermost_frame("    at sort (native)\n"),
+            Some(("sort".into(), None))
+        );
+        assert_eq!(
+            innermost_frame("    at <anonymous> (C:\\a.js:1)"),
+            Some(("<anonymous>".into(), Some("C:\\a.js".into())))
+        );
+        assert_eq!(innermost_frame(""), None);
+    }
+
+    #[test]
+    fn hot_functions() {
+        let rt = Runtime::new().unwrap();
+        rt.set_sampling(Some(1));
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                r#"
+                function hot() { let x = 0; for (let i = 0; i < 1000000; i++) x += i; return x; }
+                function cold() { return 1; }
+                hot();
+                cold();
+                "#,
+            )
+            .unwrap();
+        });
+
+        let functions = rt.hot_functions(1);
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "hot");
+        assert_eq!(functions[0].file.as_deref(), Some("eval_script"));
+        assert_eq!(functions[0].origin, None);
+        assert!(functions[0].samples > 0);
+
+        rt.reset_samples();
+        assert!(rt.hot_functions(10).is_empty());
+        rt.set_sampling(None);
+    }
+
+    #[test]
+    fn origin() {
+        let rt = Runtime::new().unwrap();
+        rt.set_sampling(Some(1));
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                "function hot() { let x = 0; for (let i = 0; i < 1000000; i++) x += i; }",
+            )
+            .unwrap();
+            ctx.with_origin("plugin", || ctx.eval::<(), _>("hot()").unwrap());
+        });
+
+        let functions = rt.hot_functions(1);
+        assert_eq!(functions[0].name, "hot");
+        assert_eq!(functions[0].origin.as_deref(), Some("plugin"));
+    }
+}
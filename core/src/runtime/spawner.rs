@@ -2,8 +2,9 @@ use super::{
     schedular::{Schedular, SchedularPoll},
     AsyncWeakRuntime, InnerRuntime,
 };
-use crate::AsyncRuntime;
+use crate::{markers::ParallelSend, qjs, AsyncRuntime, Ctx, Value};
 use std::{
+    fmt,
     future::Future,
     pin::Pin,
     task::{ready, Context, Poll, Waker},
@@ -41,11 +42,94 @@ impl Spawner {
         self.schedular.is_empty()
     }
 
+    /// Drop all spawned futures.
+    pub fn clear(&mut self) {
+        self.schedular.clear();
+    }
+
     pub fn poll(&mut self, cx: &mut Context) -> SchedularPoll {
         unsafe { self.schedular.poll(cx) }
     }
 }
 
+/// The type of the handler for exceptions thrown by jobs, see [`DriveOptions::with_error_handler`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[cfg(not(feature = "parallel"))]
+pub type JobErrorHandler = Box<dyn for<'js> FnMut(Ctx<'js>, Value<'js>) + 'static>;
+/// The type of the handler for exceptions thrown by jobs, see [`DriveOptions::with_error_handler`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[cfg(feature = "parallel")]
+pub type JobErrorHandler = Box<dyn for<'js> FnMut(Ctx<'js>, Value<'js>) + Send + 'static>;
+
+/// Options for driving the jobs and spawned futures of a runtime with
+/// [`AsyncRuntime::drive_with`].
+///
+/// By default all pending jobs are executed every time the drive future is polled, so a promise
+/// loop which keeps queueing jobs never yields to the executor. Setting a job budget makes the
+/// future yield after executing that many jobs, letting other tasks on the executor run before
+/// the next batch.
+///
+/// ```
+/// # use rquickjs::{runtime::DriveOptions, AsyncRuntime};
+/// # async fn run(){
+/// let rt = AsyncRuntime::new().unwrap();
+/// let drive = rt.drive_with(
+///     DriveOptions::default()
+///         .with_max_jobs(64)
+///         .with_error_handler(|_ctx, error| eprintln!("error executing job: {error:?}")),
+/// );
+/// # }
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[derive(Default)]
+pub struct DriveOptions {
+    max_jobs: Option<usize>,
+    error_handler: Option<JobErrorHandler>,
+}
+
+impl fmt::Debug for DriveOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DriveOptions")
+            .field("max_jobs", &self.max_jobs)
+            .field("error_handler", &self.error_handler.is_some())
+            .finish()
+    }
+}
+
+impl DriveOptions {
+    /// Set the maximum number of jobs executed before yielding to the executor.
+    #[must_use]
+    pub fn with_max_jobs(mut self, max_jobs: usize) -> Self {
+        self.max_jobs = Some(max_jobs.max(1));
+        self
+    }
+
+    /// Set the handler called with the exception thrown by a job.
+    ///
+    /// Without a handler exceptions thrown by jobs are ignored.
+    #[must_use]
+    pub fn with_error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: for<'js> FnMut(Ctx<'js>, Value<'js>) + ParallelSend + 'static,
+    {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    fn budget_exhausted(&self, jobs: usize) -> bool {
+        self.max_jobs.map_or(false, |max| jobs >= max)
+    }
+
+    /// Takes the exception thrown by a job in the context and passes it to the handler.
+    unsafe fn handle_error(&mut self, ctx: *mut qjs::JSContext) {
+        let ctx = Ctx::from_ptr(ctx);
+        let error = ctx.catch();
+        if let Some(handler) = self.error_handler.as_mut() {
+            handler(ctx, error);
+        }
+    }
+}
+
 enum DriveFutureState {
     Initial,
     Lock {
@@ -55,9 +139,12 @@ enum DriveFutureState {
     },
 }
 
+/// The future returned by [`AsyncRuntime::drive`] and [`AsyncRuntime::drive_with`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
 pub struct DriveFuture {
     rt: AsyncWeakRuntime,
     state: DriveFutureState,
+    options: DriveOptions,
 }
 
 #[cfg(feature = "parallel")]
@@ -66,10 +153,11 @@ unsafe impl Send for DriveFuture {}
 unsafe impl Sync for DriveFuture {}
 
 impl DriveFuture {
-    pub(crate) fn new(rt: AsyncWeakRuntime) -> Self {
+    pub(crate) fn new(rt: AsyncWeakRuntime, options: DriveOptions) -> Self {
         Self {
             rt,
             state: DriveFutureState::Initial,
+            options,
         }
     }
 }
@@ -114,16 +202,33 @@ impl Future for DriveFuture {
                 .spawner()
                 .listen(cx.waker().clone());
 
+            let mut jobs = 0;
             loop {
-                // TODO: Handle error.
-                if let Ok(true) = lock.runtime.execute_pending_job() {
-                    continue;
+                if this.options.budget_exhausted(jobs) {
+                    // Yield to the executor and continue with the next batch when polled again.
+                    cx.waker().wake_by_ref();
+                    break;
+                }
+
+                match lock.runtime.execute_pending_job() {
+                    Ok(true) => {
+                        jobs += 1;
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(ctx) => {
+                        jobs += 1;
+                        unsafe { this.options.handle_error(ctx) };
+                        continue;
+                    }
                 }
 
                 // TODO: Handle error.
                 match unsafe { lock.runtime.get_opaque_mut() }.spawner().poll(cx) {
-                    SchedularPoll::ShouldYield | SchedularPoll::Empty | SchedularPoll::Pending => {
-                        break
+                    SchedularPoll::ShouldYield => break,
+                    SchedularPoll::Empty | SchedularPoll::Pending => {
+                        unsafe { lock.runtime.collect_idle() };
+                        break;
                     }
                     SchedularPoll::PendingProgress => {}
                 }
@@ -2,7 +2,13 @@
 use std::cell::RefCell as Cell;
 
 #[cfg(feature = "parallel")]
-use std::sync::Mutex as Cell;
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex as Cell, MutexGuard,
+    },
+};
 
 #[cfg(not(feature = "parallel"))]
 pub use std::{
@@ -11,14 +17,72 @@ pub use std::{
 };
 
 #[cfg(feature = "parallel")]
-pub use std::sync::{Arc as Ref, MutexGuard as Lock, Weak};
+pub use std::sync::{Arc as Ref, Weak};
 
+#[cfg(not(feature = "parallel"))]
 #[repr(transparent)]
 pub struct Mut<T: ?Sized>(Cell<T>);
 
+#[cfg(feature = "parallel")]
+pub struct Mut<T: ?Sized> {
+    /// The thread holding the lock, see [`current_thread`], or `0`.
+    owner: AtomicUsize,
+    cell: Cell<T>,
+}
+
+/// Returns an id of the current thread which is unique among the running threads.
+#[cfg(feature = "parallel")]
+fn current_thread() -> usize {
+    thread_local! {
+        static THREAD: u8 = const { 0 };
+    }
+    THREAD.with(|x| x as *const u8 as usize)
+}
+
+/// The guard of a locked [`Mut`].
+#[cfg(feature = "parallel")]
+pub struct Lock<'a, T: ?Sized> {
+    guard: MutexGuard<'a, T>,
+    owner: &'a AtomicUsize,
+}
+
+#[cfg(feature = "parallel")]
+impl<T: ?Sized> Deref for Lock<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T: ?Sized> DerefMut for Lock<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T: ?Sized> Drop for Lock<'_, T> {
+    fn drop(&mut self) {
+        self.owner.store(0, Ordering::Relaxed);
+    }
+}
+
 impl<T> Mut<T> {
     pub fn new(inner: T) -> Self {
-        Self(Cell::new(inner))
+        #[cfg(not(feature = "parallel"))]
+        {
+            Self(Cell::new(inner))
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            Self {
+                owner: AtomicUsize::new(0),
+                cell: Cell::new(inner),
+            }
+        }
     }
 }
 
@@ -37,7 +101,7 @@ impl<T: ?Sized> Mut<T> {
 
         #[cfg(feature = "parallel")]
         {
-            self.0.lock().unwrap()
+            self.guard(self.cell.lock().unwrap())
         }
     }
 
@@ -49,7 +113,30 @@ impl<T: ?Sized> Mut<T> {
 
         #[cfg(feature = "parallel")]
         {
-            self.0.lock().ok()
+            self.cell.lock().ok().map(|x| self.guard(x))
+        }
+    }
+
+    /// Returns whether the lock is held by the current thread.
+    pub fn is_locked_by_current_thread(&self) -> bool {
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.0.try_borrow_mut().is_err()
+        }
+
+        // Only the current thread stores its own id, so it always sees its latest store.
+        #[cfg(feature = "parallel")]
+        {
+            self.owner.load(Ordering::Relaxed) == current_thread()
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn guard<'a>(&'a self, guard: MutexGuard<'a, T>) -> Lock<'a, T> {
+        self.owner.store(current_thread(), Ordering::Relaxed);
+        Lock {
+            guard,
+            owner: &self.owner,
         }
     }
 }
@@ -0,0 +1,262 @@
+//! A preset for evaluating untrusted expressions.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::{
+    context::intrinsic,
+    performance::{self, ManualClock, MonotonicClock},
+    CaughtError, Context, Error, FromJs, IntoJs, Result, Runtime,
+};
+
+/// Removes every way to generate code at runtime, run once when the sandbox is created.
+///
+/// Async functions need the Promise intrinsic, which isn't added, so only the prototypes of
+/// normal and generator functions have a constructor.
+const LOCKDOWN: &str = r#"
+(() => {
+    const forbidden = function () {
+        throw new TypeError("code generation is not allowed in the sandbox");
+    };
+    for (const f of [function () {}, function* () {}]) {
+        Object.defineProperty(Object.getPrototypeOf(f), "constructor", { value: forbidden });
+    }
+    delete globalThis.eval;
+    delete globalThis.Function;
+})();
+"#;
+
+/// Options for creating a [`Sandbox`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SandboxOptions {
+    /// The fuel of a single evaluation.
+    ///
+    /// One unit of fuel is used up every time the engine checks for interrupts, which happens
+    /// about every ten thousand function calls or loop iterations. Defaults to `1000`.
+    pub fuel: u64,
+    /// The limit on the memory the sandbox will use, defaults to 16 MiB.
+    pub memory_limit: usize,
+    /// The limit on the stack size the sandbox will use, defaults to 256 KiB.
+    pub max_stack_size: usize,
+    /// Allow measuring time with `performance.now()`, defaults to `false`.
+    ///
+    /// High resolution timing lets scripts observe how long the host takes and makes evaluations
+    /// depend on the speed of the machine. Without this capability `performance.now()` always
+    /// returns `0`.
+    pub high_resolution_time: bool,
+}
+
+impl Default for SandboxOptions {
+    fn default() -> Self {
+        SandboxOptions {
+            fuel: 1000,
+            memory_limit: 16 * 1024 * 1024,
+            max_stack_size: 256 * 1024,
+            high_resolution_time: false,
+        }
+    }
+}
+
+impl SandboxOptions {
+    /// Set the fuel of a single evaluation.
+    #[must_use]
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Set the memory limit.
+    #[must_use]
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Set the max stack size.
+    #[must_use]
+    pub fn with_max_stack_size(mut self, limit: usize) -> Self {
+        self.max_stack_size = limit;
+        self
+    }
+
+    /// Allow or deny measuring time with `performance.now()`.
+    #[must_use]
+    pub fn with_high_resolution_time(mut self, allow: bool) -> Self {
+        self.high_resolution_time = allow;
+        self
+    }
+}
+
+/// A runtime and context configured for evaluating user defined expressions, like formulas in a
+/// spreadsheet or rules in a configuration file.
+///
+/// The context of the sandbox only has the base objects, which include `Math`, `JSON` and a
+/// `performance` whose clock is frozen unless [`SandboxOptions::high_resolution_time`] is set.
+/// Scripts can't generate code at runtime as `eval` and the `Function` constructors are removed.
+/// Every evaluation gets a fixed amount of fuel and is aborted with [`Error::OutOfFuel`] when it
+/// is used up, the memory and the stack of the sandbox are limited as well.
+///
+/// ```
+/// # use rquickjs::sandbox::Sandbox;
+/// let sandbox = Sandbox::new().unwrap();
+/// let total: f64 = sandbox
+///     .eval_expr(
+///         "Math.round(price * quantity * (1 + tax))",
+///         [("price", 9.5), ("quantity", 3.0), ("tax", 0.2)],
+///     )
+///     .unwrap();
+/// assert_eq!(total, 34.0);
+/// ```
+pub struct Sandbox {
+    context: Context,
+    fuel: Arc<AtomicU64>,
+    options: SandboxOptions,
+}
+
+impl Sandbox {
+    /// Create a sandbox with the default options.
+    pub fn new() -> Result<Self> {
+        Self::from_options(SandboxOptions::default())
+    }
+
+    /// Create a sandbox with the given options.
+    pub fn from_options(options: SandboxOptions) -> Result<Self> {
+        let runtime = Runtime::new()?;
+        runtime.set_memory_limit(options.memory_limit);
+        runtime.set_max_stack_size(options.max_stack_size);
+
+        let fuel = Arc::new(AtomicU64::new(0));
+        let handler_fuel = fuel.clone();
+        runtime.set_interrupt_handler(Some(Box::new(move || {
+            handler_fuel
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_sub(1))
+                .is_err()
+        })));
+
+        // The eval intrinsic is needed to compile expressions, it is removed from the globals by
+        // the lockdown script.
+        let context = Context::custom::<(intrinsic::Eval, intrinsic::Json)>(&runtime)?;
+        context.with(|ctx| {
+            ctx.eval::<(), _>(LOCKDOWN)?;
+            if options.high_resolution_time {
+                performance::define(&ctx, MonotonicClock::new())
+            } else {
+                performance::define(&ctx, ManualClock::default())
+            }
+        })?;
+
+        Ok(Sandbox {
+            context,
+            fuel,
+            options,
+        })
+    }
+
+    /// Returns the context of the sandbox, for example to define helper functions.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Evaluate an expression with the given bindings as variables.
+    ///
+    /// Exceptions thrown by the expression are returned as [`Error::Evaluation`].
+    pub fn eval_expr<T, I, N, B>(&self, expr: &str, bindings: I) -> Result<T>
+    where
+        T: for<'js> FromJs<'js>,
+        I: IntoIterator<Item = (N, B)>,
+        N: AsRef<str>,
+        B: for<'js> IntoJs<'js>,
+    {
+        self.fuel.store(self.options.fuel, Ordering::Relaxed);
+        self.context.with(|ctx| {
+            let source = format!("return ({expr}\n);");
+            ctx.eval_with_bindings(source, bindings)
+                .map_err(|error| match CaughtError::from_error(&ctx, error) {
+                    CaughtError::Error(error) => error,
+                    _ if self.fuel.load(Ordering::Relaxed) == 0 => Error::OutOfFuel,
+                    error => Error::Evaluation {
+                        message: error.to_string(),
+                    },
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eval_expr() {
+        let sandbox = Sandbox::new().unwrap();
+        let res: i32 = sandbox
+            .eval_expr("a * b + Math.max(a, b)", [("a", 3), ("b", 4)])
+            .unwrap();
+        assert_eq!(res, 16);
+        let res: String = sandbox
+            .eval_expr("JSON.stringify(list)", [("list", vec![1, 2])])
+            .unwrap();
+        assert_eq!(res, "[1,2]");
+    }
+
+    #[test]
+    fn no_code_generation() {
+        let sandbox = Sandbox::new().unwrap();
+        for expr in [
+            "eval('1')",
+            "Function('return 1')()",
+            "(() => {}).constructor('return 1')()",
+            "(function* () {}).constructor('yield 1')",
+        ] {
+            let res = sandbox.eval_expr::<(), _, &str, i32>(expr, []);
+            assert!(matches!(res, Err(Error::Evaluation { .. })), "{expr}");
+        }
+        let res: bool = sandbox
+            .eval_expr(
+                "typeof Date === 'undefined' && typeof Promise === 'undefined'",
+                [("x", 0)],
+            )
+            .unwrap();
+        assert!(res);
+    }
+
+    #[test]
+    fn expression_escaping_function() {
+        let sandbox = Sandbox::new().unwrap();
+        let res = sandbox.eval_expr::<(), _, _, _>(
+            "0); }); globalThis.leak = 1; (function(){ return (0",
+            [("x", 0)],
+        );
+        assert!(matches!(res, Err(Error::Evaluation { .. })));
+        let leaked: bool = sandbox
+            .eval_expr("typeof leak !== 'undefined'", [("x", 0)])
+            .unwrap();
+        assert!(!leaked);
+    }
+
+    #[test]
+    fn high_resolution_time() {
+        let sandbox = Sandbox::new().unwrap();
+        let now: f64 = sandbox.eval_expr("performance.now()", [("x", 0)]).unwrap();
+        assert_eq!(now, 0.0);
+
+        let options = SandboxOptions::default().with_high_resolution_time(true);
+        let sandbox = Sandbox::from_options(options).unwrap();
+        let monotonic: bool = sandbox
+            .eval_expr("performance.now() <= performance.now()", [("x", 0)])
+            .unwrap();
+        assert!(monotonic);
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let sandbox = Sandbox::from_options(SandboxOptions::default().with_fuel(10)).unwrap();
+        let res = sandbox.eval_expr::<(), _, _, _>("(() => { while (true) {} })()", [("x", 0)]);
+        assert!(matches!(res, Err(Error::OutOfFuel)));
+        // The fuel is refilled for every evaluation.
+        let res: i32 = sandbox.eval_expr("x + 1", [("x", 1)]).unwrap();
+        assert_eq!(res, 2);
+    }
+}
@@ -0,0 +1,205 @@
+//! Mapping between byte offsets and lines and columns of evaluated sources.
+//!
+//! When source retention is enabled with [`Runtime::set_source_retention`] the source of every
+//! evaluated script and module is kept by its file name and can be retrieved with
+//! [`Ctx::source`] or [`Runtime::source`], for example to underline the location of an error:
+//!
+//! ```
+//! # use rquickjs::{Context, Runtime, CaughtError, CatchResultExt, source::Position};
+//! let rt = Runtime::new().unwrap();
+//! rt.set_source_retention(true);
+//! let ctx = Context::full(&rt).unwrap();
+//! ctx.with(|ctx| {
+//!     let err = ctx.eval::<(), _>("let a = 1;\nlet b = ;").catch(&ctx).unwrap_err();
+//!     let CaughtError::Exception(err) = err else { panic!() };
+//!     let source = ctx.source(&err.file().unwrap()).unwrap();
+//!     let line = err.line().unwrap() as u32;
+//!     assert_eq!(source.line(line), Some(&b"let b = ;"[..]));
+//!     assert_eq!(source.offset(Position { line, column: 1 }), Some(11));
+//! });
+//! ```
+//!
+//! [`Runtime::set_source_retention`]: crate::Runtime::set_source_retention
+//! [`Runtime::source`]: crate::Runtime::source
+//! [`Ctx::source`]: crate::Ctx::source
+
+use std::string::String as StdString;
+
+/// A position in a source, as used by the line and column numbers of errors.
+///
+/// Both the line and column start at 1. Columns count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// The source of a script or module with an index of its lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    name: StdString,
+    text: Box<[u8]>,
+    /// The byte offset of the start of every line.
+    line_starts: Vec<usize>,
+}
+
+fn is_char_start(byte: u8) -> bool {
+    byte & 0xC0 != 0x80
+}
+
+impl SourceFile {
+    /// Create the index of a source with a file name.
+    pub fn new<N, T>(name: N, text: T) -> Self
+    where
+        N: Into<StdString>,
+        T: Into<Vec<u8>>,
+    {
+        let text = text.into().into_boxed_slice();
+        let line_starts = std::iter::once(0)
+            .chain(
+                text.iter()
+                    .enumerate()
+                    .filter(|(_, &byte)| byte == b'\n')
+                    .map(|(idx, _)| idx + 1),
+            )
+            .collect();
+        SourceFile {
+            name: name.into(),
+            text,
+            line_starts,
+        }
+    }
+
+    /// Returns the file name of the source.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the text of the source.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// Returns the number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the text of a line without its line terminator.
+    pub fn line(&self, line: u32) -> Option<&[u8]> {
+        let (start, end) = self.line_bounds(line)?;
+        let text = &self.text[start..end];
+        Some(text.strip_suffix(b"\r").unwrap_or(text))
+    }
+
+    /// Returns the position of a byte offset.
+    ///
+    /// The offset of the end of the source is valid. Returns `None` for offsets past the end or
+    /// inside a multi-byte character.
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        match self.text.get(offset) {
+            Some(&byte) if !is_char_start(byte) => return None,
+            None if offset > self.text.len() => return None,
+            _ => {}
+        }
+        let idx = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.text[self.line_starts[idx]..offset]
+            .iter()
+            .filter(|&&b| is_char_start(b))
+            .count();
+        Some(Position {
+            line: idx as u32 + 1,
+            column: column as u32 + 1,
+        })
+    }
+
+    /// Returns the byte offset of a position.
+    ///
+    /// The column after the last character of a line is valid. Returns `None` for positions
+    /// outside of the source.
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let (start, end) = self.line_bounds(position.line)?;
+        let column = position.column.checked_sub(1)? as usize;
+        // The start of every character, followed by the end of the line.
+        self.text[start..end]
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| is_char_start(b))
+            .map(|(idx, _)| start + idx)
+            .chain(std::iter::once(end))
+            .nth(column)
+    }
+
+    /// Returns the byte range of a line, without the `\n` terminating it.
+    fn line_bounds(&self, line: u32) -> Option<(usize, usize)> {
+        let idx = (line as usize).checked_sub(1)?;
+        let start = *self.line_starts.get(idx)?;
+        let end = self
+            .line_starts
+            .get(idx + 1)
+            .map_or(self.text.len(), |&next| next - 1);
+        Some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    fn pos(line: u32, column: u32) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn positions() {
+        let source = SourceFile::new("test.js", "let a;\r\nconst é = 'ü';\n");
+        assert_eq!(source.line_count(), 3);
+        assert_eq!(source.line(1), Some(&b"let a;"[..]));
+        assert_eq!(source.line(2), Some("const é = 'ü';".as_bytes()));
+        assert_eq!(source.line(3), Some(&b""[..]));
+        assert_eq!(source.line(0), None);
+        assert_eq!(source.line(4), None);
+
+        assert_eq!(source.position(0), Some(pos(1, 1)));
+        assert_eq!(source.position(8), Some(pos(2, 1)));
+        // `=` after the two byte `é`
+        assert_eq!(source.position(17), Some(pos(2, 9)));
+        assert_eq!(source.position(15), None);
+        assert_eq!(source.position(source.text().len()), Some(pos(3, 1)));
+        assert_eq!(source.position(source.text().len() + 1), None);
+
+        assert_eq!(source.offset(pos(2, 9)), Some(17));
+        assert_eq!(source.offset(pos(2, 1)), Some(8));
+        assert_eq!(source.offset(pos(1, 7)), Some(6));
+        assert_eq!(source.offset(pos(1, 9)), None);
+        assert_eq!(source.offset(pos(3, 1)), Some(source.text().len()));
+        assert_eq!(source.offset(pos(2, 0)), None);
+        assert_eq!(source.offset(pos(4, 1)), None);
+        for offset in [0, 5, 8, 14, 17, 22] {
+            let position = source.position(offset).unwrap();
+            assert_eq!(source.offset(position), Some(offset));
+        }
+    }
+
+    #[test]
+    fn retention() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>("1").unwrap();
+            assert!(ctx.source("eval_script").is_none());
+        });
+        rt.set_source_retention(true);
+        ctx.with(|ctx| {
+            let _ = ctx.eval::<(), _>("let a = 1;\nthrow new Error('x');");
+            Module::declare(ctx.clone(), "mod", "export const b = 2;").unwrap();
+            let source = ctx.source("eval_script").unwrap();
+            assert_eq!(source.line(2), Some(&b"throw new Error('x');"[..]));
+            assert_eq!(ctx.source("mod").unwrap().text(), b"export const b = 2;");
+        });
+        assert!(rt.source("mod").is_some());
+        rt.set_source_retention(false);
+        assert!(rt.source("mod").is_none());
+    }
+}
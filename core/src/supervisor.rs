@@ -0,0 +1,435 @@
+//! Running jobs on a pool of runtimes which are restarted when they hang.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{CaughtError, Context, Ctx, Error, Result, Runtime, StdResult};
+
+/// Options for creating a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupervisorOptions {
+    /// The number of runtimes, each of which runs on its own thread. Defaults to the available
+    /// parallelism.
+    pub workers: usize,
+    /// How long a job may run before its runtime is killed, defaults to 5 seconds.
+    pub timeout: Duration,
+    /// How often the watchdog checks the runtimes, defaults to 50 milliseconds.
+    pub check_interval: Duration,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        SupervisorOptions {
+            workers: thread::available_parallelism().map_or(1, |x| x.get()),
+            timeout: Duration::from_secs(5),
+            check_interval: Duration::from_millis(50),
+        }
+    }
+}
+
+impl SupervisorOptions {
+    /// Set the number of runtimes.
+    #[must_use]
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Set how long a job may run.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how often the watchdog checks the runtimes.
+    #[must_use]
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+}
+
+/// The health of a runtime of a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// The runtime is waiting for a job.
+    Idle,
+    /// The runtime is running a job which didn't exceed the timeout yet.
+    Busy,
+    /// The job exceeded the timeout and is interrupted at the next interrupt check, after which
+    /// the runtime is recreated.
+    Wedged,
+    /// The job exceeded the timeout and didn't reach an interrupt check for another timeout since,
+    /// most likely because it is blocked in a native call which can't be interrupted. The runtime
+    /// is recreated when the call returns.
+    Stuck,
+    /// The runtime is being created, either for its first job or after it was killed.
+    Restarting,
+    /// Creating the runtime failed, it is created again for the next job.
+    Failed,
+}
+
+/// The status of a runtime of a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerStatus {
+    /// The health of the runtime.
+    pub health: Health,
+    /// The number of jobs the runtime finished.
+    pub jobs: u64,
+    /// The number of times the runtime was recreated after it was killed or a job panicked.
+    pub restarts: u64,
+    /// The memory used by the runtime after its last job, see
+    /// [`Runtime::memory_usage`](crate::Runtime::memory_usage).
+    pub memory_used: i64,
+}
+
+type Init = dyn Fn(&Runtime) -> Result<Context> + Send + Sync;
+
+/// A job gets the context of its worker, or the error creating it, and the kill flag. Returns
+/// whether the runtime has to be recreated.
+type Job = Box<dyn FnOnce(StdResult<&Context, Error>, &AtomicBool) -> bool + Send>;
+
+/// The state of a worker shared with the watchdog and the interrupt handler.
+struct Worker {
+    status: Mutex<WorkerStatus>,
+    /// When the current job started, in milliseconds since the supervisor was created.
+    started: AtomicU64,
+    /// When the interrupt handler last ran, in milliseconds since the supervisor was created.
+    heartbeat: AtomicU64,
+    kill: AtomicBool,
+}
+
+impl Worker {
+    fn set_health(&self, health: Health) {
+        self.status.lock().unwrap().health = health;
+    }
+}
+
+/// Owns a pool of runtimes on their own threads and runs jobs on them, restarting runtimes
+/// which hang.
+///
+/// Every runtime gets a context from the `init` function, which is also used to recreate the
+/// runtime. A watchdog thread checks the runtimes, and interrupts a job which runs longer than
+/// [`SupervisorOptions::timeout`]. The job then returns [`Error::Killed`] and the runtime is
+/// dropped and created again, since the state of an interrupted script can't be trusted.
+/// Jobs blocked in a native call can't be interrupted, their runtime is reported as
+/// [`Health::Stuck`] until the call returns.
+///
+/// The supervisor sets the interrupt handler of the runtimes, so `init` must not set one.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use rquickjs::{supervisor::{Supervisor, SupervisorOptions}, Context, Error};
+/// let options = SupervisorOptions::default()
+///     .with_workers(2)
+///     .with_timeout(Duration::from_millis(200));
+/// let supervisor = Supervisor::new(options, Context::full).unwrap();
+/// let sum = supervisor.spawn(|ctx| ctx.eval::<i32, _>("1 + 2"));
+/// let hang = supervisor.spawn(|ctx| ctx.eval::<(), _>("while (true) {}"));
+/// assert_eq!(sum.join().unwrap(), 3);
+/// assert!(matches!(hang.join(), Err(Error::Killed)));
+/// ```
+pub struct Supervisor {
+    jobs: Option<Sender<Job>>,
+    workers: Vec<Arc<Worker>>,
+    threads: Vec<JoinHandle<()>>,
+    stop_watchdog: Option<Sender<()>>,
+    watchdog: Option<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Create a supervisor, `init` creates the context of every runtime.
+    ///
+    /// Fails if a thread could not be spawned, runtimes are created by their thread when they
+    /// get their first job.
+    pub fn new<F>(options: SupervisorOptions, init: F) -> Result<Self>
+    where
+        F: Fn(&Runtime) -> Result<Context> + Send + Sync + 'static,
+    {
+        let epoch = Instant::now();
+        let init: Arc<Init> = Arc::new(init);
+        let (job_send, job_recv) = mpsc::channel::<Job>();
+        let job_recv = Arc::new(Mutex::new(job_recv));
+
+        let mut workers = Vec::new();
+        let mut threads = Vec::new();
+        for idx in 0..options.workers.max(1) {
+            let worker = Arc::new(Worker {
+                status: Mutex::new(WorkerStatus {
+                    health: Health::Idle,
+                    jobs: 0,
+                    restarts: 0,
+                    memory_used: 0,
+                }),
+                started: AtomicU64::new(0),
+                heartbeat: AtomicU64::new(0),
+                kill: AtomicBool::new(false),
+            });
+            let thread = thread::Builder::new()
+                .name(format!("rquickjs-worker-{idx}"))
+                .spawn({
+                    let worker = worker.clone();
+                    let init = init.clone();
+                    let job_recv = job_recv.clone();
+                    move || run_worker(&worker, &*init, &job_recv, epoch)
+                })?;
+            workers.push(worker);
+            threads.push(thread);
+        }
+
+        let (stop_send, stop_recv) = mpsc::channel();
+        let watchdog = thread::Builder::new()
+            .name("rquickjs-watchdog".into())
+            .spawn({
+                let workers = workers.clone();
+                move || run_watchdog(&workers, &stop_recv, options, epoch)
+            })?;
+
+        Ok(Supervisor {
+            jobs: Some(job_send),
+            workers,
+            threads,
+            stop_watchdog: Some(stop_send),
+            watchdog: Some(watchdog),
+        })
+    }
+
+    /// Run a job on the next free runtime.
+    ///
+    /// Exceptions thrown by the job are returned as [`Error::Evaluation`] since the runtime may
+    /// be gone when the result is retrieved. A job which exceeds the timeout returns
+    /// [`Error::Killed`], and a panic of the job is resumed by [`JobHandle::join`].
+    pub fn spawn<F, R>(&self, f: F) -> JobHandle<R>
+    where
+        F: for<'js> FnOnce(Ctx<'js>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (send, recv) = mpsc::sync_channel(1);
+        let job: Job = Box::new(move |context, kill| {
+            let context = match context {
+                Ok(x) => x,
+                Err(error) => {
+                    let _ = send.send(Ok(Err(error)));
+                    return false;
+                }
+            };
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                context.with(|ctx| {
+                    f(ctx.clone()).map_err(|error| match CaughtError::from_error(&ctx, error) {
+                        _ if kill.load(Ordering::Relaxed) => Error::Killed,
+                        CaughtError::Error(error) => error,
+                        error => Error::Evaluation {
+                            message: error.to_string(),
+                        },
+                    })
+                })
+            }));
+            let restart = res.is_err() || kill.load(Ordering::Relaxed);
+            let _ = send.send(res);
+            restart
+        });
+        if let Some(jobs) = &self.jobs {
+            // The workers only stop when the supervisor is dropped.
+            let _ = jobs.send(job);
+        }
+        JobHandle { recv }
+    }
+
+    /// Returns the status of every runtime.
+    pub fn status(&self) -> Vec<WorkerStatus> {
+        self.workers
+            .iter()
+            .map(|worker| *worker.status.lock().unwrap())
+            .collect()
+    }
+}
+
+impl Drop for Supervisor {
+    /// Waits for the queued jobs to finish.
+    fn drop(&mut self) {
+        self.jobs = None;
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+        self.stop_watchdog = None;
+        if let Some(watchdog) = self.watchdog.take() {
+            let _ = watchdog.join();
+        }
+    }
+}
+
+/// The result of a job run by a [`Supervisor`].
+pub struct JobHandle<R> {
+    recv: Receiver<thread::Result<Result<R>>>,
+}
+
+impl<R> JobHandle<R> {
+    /// Wait for the job to finish, resuming its panic if it panicked.
+    pub fn join(self) -> Result<R> {
+        match self.recv.recv() {
+            Ok(Ok(res)) => res,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            Err(_) => Err(Error::Unknown),
+        }
+    }
+}
+
+fn millis_since(epoch: Instant) -> u64 {
+    epoch.elapsed().as_millis() as u64
+}
+
+fn create_context(worker: &Arc<Worker>, init: &Init, epoch: Instant) -> Result<Context> {
+    worker.set_health(Health::Restarting);
+    let runtime = Runtime::new()?;
+    let handler_worker = worker.clone();
+    runtime.set_interrupt_handler(Some(Box::new(move || {
+        handler_worker
+            .heartbeat
+            .store(millis_since(epoch), Ordering::Relaxed);
+        handler_worker.kill.load(Ordering::Relaxed)
+    })));
+    init(&runtime)
+}
+
+fn run_worker(worker: &Arc<Worker>, init: &Init, jobs: &Mutex<Receiver<Job>>, epoch: Instant) {
+    let mut context = None;
+    loop {
+        let job = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => break,
+        };
+        if context.is_none() {
+            match create_context(worker, init, epoch) {
+                Ok(x) => context = Some(x),
+                Err(error) => {
+                    worker.set_health(Health::Failed);
+                    job(Err(error), &worker.kill);
+                    continue;
+                }
+            }
+        }
+        let current = context.as_ref().unwrap();
+
+        worker.kill.store(false, Ordering::Relaxed);
+        worker.started.store(millis_since(epoch), Ordering::Relaxed);
+        worker.set_health(Health::Busy);
+        let restart = job(Ok(current), &worker.kill);
+
+        let memory_used = current.runtime().memory_usage().memory_used_size;
+        {
+            let mut status = worker.status.lock().unwrap();
+            status.jobs += 1;
+            status.memory_used = memory_used;
+            status.health = Health::Idle;
+            if restart {
+                status.restarts += 1;
+            }
+        }
+        if restart {
+            context = None;
+        }
+    }
+}
+
+fn run_watchdog(
+    workers: &[Arc<Worker>],
+    stop: &Receiver<()>,
+    options: SupervisorOptions,
+    epoch: Instant,
+) {
+    let timeout = options.timeout.as_millis() as u64;
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(options.check_interval) {
+        let now = millis_since(epoch);
+        for worker in workers {
+            let mut status = worker.status.lock().unwrap();
+            let started = worker.started.load(Ordering::Relaxed);
+            match status.health {
+                Health::Busy if now.saturating_sub(started) > timeout => {
+                    worker.kill.store(true, Ordering::Relaxed);
+                    status.health = Health::Wedged;
+                }
+                Health::Wedged
+                    if now.saturating_sub(started) > 2 * timeout
+                        && worker.heartbeat.load(Ordering::Relaxed) < started + timeout =>
+                {
+                    status.health = Health::Stuck;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn supervisor(workers: usize) -> Supervisor {
+        let options = SupervisorOptions::default()
+            .with_workers(workers)
+            .with_timeout(Duration::from_millis(100))
+            .with_check_interval(Duration::from_millis(10));
+        Supervisor::new(options, Context::full).unwrap()
+    }
+
+    #[test]
+    fn run_jobs() {
+        let supervisor = supervisor(2);
+        let handles: Vec<_> = (0..8)
+            .map(|i| supervisor.spawn(move |ctx| ctx.eval::<i32, _>(format!("{i} * 2"))))
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), i as i32 * 2);
+        }
+        let res = supervisor
+            .spawn(|ctx| ctx.eval::<(), _>("throw new Error('oops')"))
+            .join();
+        assert!(matches!(res, Err(Error::Evaluation { .. })));
+        let status = supervisor.status();
+        assert_eq!(status.iter().map(|x| x.jobs).sum::<u64>(), 9);
+        assert!(status
+            .iter()
+            .all(|x| x.health == Health::Idle && x.restarts == 0));
+    }
+
+    #[test]
+    fn kill_wedged() {
+        let supervisor = supervisor(1);
+        supervisor
+            .spawn(|ctx| ctx.globals().set("marker", 1))
+            .join()
+            .unwrap();
+        let res = supervisor
+            .spawn(|ctx| ctx.eval::<(), _>("while (true) {}"))
+            .join();
+        assert!(matches!(res, Err(Error::Killed)));
+        // The runtime was recreated.
+        let marker: Option<i32> = supervisor
+            .spawn(|ctx| ctx.globals().get("marker"))
+            .join()
+            .unwrap();
+        assert_eq!(marker, None);
+        assert_eq!(supervisor.status()[0].restarts, 1);
+    }
+
+    #[test]
+    fn stuck_in_native_call() {
+        let supervisor = supervisor(1);
+        let handle = supervisor.spawn(|_| {
+            thread::sleep(Duration::from_millis(400));
+            Ok(())
+        });
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(supervisor.status()[0].health, Health::Stuck);
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,322 @@
+//! Utilities for testing bindings.
+//!
+//! Every project embedding the engine ends up writing the same boilerplate to test its
+//! bindings: creating a runtime, evaluating snippets and comparing the results, draining the job
+//! queue, faking `setTimeout` and loading scripts from files. This module bundles those helpers.
+//! They panic with the message of the JavaScript exception instead of returning errors, which is
+//! what a test wants.
+//!
+//! ```
+//! # use rquickjs::{assert_eval_eq, testing::TestRuntime};
+//! let rt = TestRuntime::new();
+//! rt.eval::<()>("var log = []; setTimeout(() => log.push('timeout'), 100);");
+//! rt.eval::<()>("Promise.resolve().then(() => log.push('job'))");
+//! rt.advance_timers(100);
+//! rt.with(|ctx| assert_eval_eq!(ctx, "log.join()", "job,timeout".to_string()));
+//! ```
+
+use std::{
+    mem::MaybeUninit,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    qjs, CaughtError, Context, Ctx, Error, FromJs, Function, Object, Persistent, Result, Runtime,
+    Value,
+};
+
+/// Installs the fake timer functions, returns the object controlling them.
+const FAKE_TIMERS: &str = r#"
+(() => {
+    let now = 0;
+    let nextId = 1;
+    const timers = new Map();
+    const add = (callback, delay, args, repeat) => {
+        const id = nextId++;
+        delay = Math.max(0, Number(delay) || 0);
+        timers.set(id, { callback, delay, args, repeat, at: now + delay });
+        return id;
+    };
+    const clear = (id) => {
+        timers.delete(id);
+    };
+    globalThis.setTimeout = (callback, delay, ...args) => add(callback, delay, args, false);
+    globalThis.setInterval = (callback, delay, ...args) => add(callback, delay, args, true);
+    globalThis.clearTimeout = clear;
+    globalThis.clearInterval = clear;
+    return {
+        // Runs the earliest timer due at `end`, or moves the clock to `end` if there is none.
+        next(end) {
+            let next;
+            for (const entry of timers) {
+                if (entry[1].at <= end && (!next || entry[1].at < next[1].at)) {
+                    next = entry;
+                }
+            }
+            if (!next) {
+                now = end;
+                return false;
+            }
+            const [id, timer] = next;
+            now = timer.at;
+            if (timer.repeat) {
+                timer.at += Math.max(timer.delay, 1);
+            } else {
+                timers.delete(id);
+            }
+            timer.callback(...timer.args);
+            return true;
+        },
+        now() {
+            return now;
+        },
+        pending() {
+            return timers.size;
+        },
+    };
+})()
+"#;
+
+/// Evaluate a script and convert the result to the type of `expected`, used by
+/// [`assert_eval_eq!`](crate::assert_eval_eq).
+///
+/// Panics with the exception if the script throws.
+pub fn eval_like<'js, T: FromJs<'js>>(ctx: &Ctx<'js>, source: &str, expected: &T) -> T {
+    let _ = expected;
+    unwrap_js(ctx, ctx.eval(source))
+}
+
+/// Assert that a script evaluates to the expected value.
+///
+/// The result is converted to the type of the expected value, which must implement
+/// [`FromJs`](crate::FromJs), [`PartialEq`] and [`Debug`](std::fmt::Debug). A script which
+/// throws makes the assertion fail with the exception.
+///
+/// ```
+/// # use rquickjs::{assert_eval_eq, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     assert_eval_eq!(ctx, "1 + 2", 3);
+///     assert_eval_eq!(ctx, "[1, 2].map(x => x * 2)", vec![2, 4]);
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_eval_eq {
+    ($ctx:expr, $source:expr, $expected:expr $(,)?) => {{
+        let source = $source;
+        let expected = $expected;
+        let actual = $crate::testing::eval_like(&$ctx, source, &expected);
+        assert_eq!(actual, expected, "evaluating `{}`", source);
+    }};
+}
+
+/// Returns the path of a fixture relative to the manifest directory of the crate under test.
+///
+/// Absolute paths are returned unchanged.
+pub fn fixture_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) if path.is_relative() => Path::new(&dir).join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn unwrap_js<'js, T>(ctx: &Ctx<'js>, res: Result<T>) -> T {
+    match res {
+        Ok(x) => x,
+        Err(error) => panic!("{}", CaughtError::from_error(ctx, error)),
+    }
+}
+
+/// A runtime and context for tests, with fake timers and helpers which drain the job queue.
+///
+/// The context has all standard intrinsics as well as `setTimeout`, `setInterval`,
+/// `clearTimeout` and `clearInterval` globals. Timers never fire on their own, the clock only
+/// moves with [`TestRuntime::advance_timers`].
+pub struct TestRuntime {
+    // Dropped before the runtime.
+    timers: Persistent<Object<'static>>,
+    context: Context,
+    runtime: Runtime,
+}
+
+impl TestRuntime {
+    /// Create a test runtime, panics if the runtime can't be created.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let runtime = Runtime::new().expect("failed to create the runtime");
+        let context = Context::full(&runtime).expect("failed to create the context");
+        let timers = context.with(|ctx| {
+            let timers = unwrap_js(&ctx, ctx.eval::<Object, _>(FAKE_TIMERS));
+            Persistent::save(&ctx, timers)
+        });
+        TestRuntime {
+            timers,
+            context,
+            runtime,
+        }
+    }
+
+    /// Returns the runtime.
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    /// Returns the context.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Run a closure with the context.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(Ctx) -> R,
+    {
+        self.context.with(f)
+    }
+
+    /// Evaluate a script, then run all pending jobs.
+    ///
+    /// If the script evaluates to a promise, the promise is driven to completion and its result
+    /// is returned instead. Panics if the script throws or the promise is rejected.
+    pub fn eval<T>(&self, source: &str) -> T
+    where
+        T: for<'js> FromJs<'js>,
+    {
+        self.with(|ctx| {
+            let value = unwrap_js(&ctx, ctx.eval::<Value, _>(source));
+            self.finish(&ctx, value)
+        })
+    }
+
+    /// Evaluate a fixture script, see [`fixture_path`] for how the path is resolved.
+    ///
+    /// Like [`TestRuntime::eval`] promises are driven to completion and pending jobs are run.
+    pub fn eval_fixture<T, P>(&self, path: P) -> T
+    where
+        T: for<'js> FromJs<'js>,
+        P: AsRef<Path>,
+    {
+        let path = fixture_path(path);
+        self.with(|ctx| {
+            let value = match ctx.eval_file::<Value, _>(&path) {
+                Err(Error::Io(error)) => {
+                    panic!("failed to read fixture `{}`: {}", path.display(), error)
+                }
+                res => unwrap_js(&ctx, res),
+            };
+            self.finish(&ctx, value)
+        })
+    }
+
+    fn finish<'js, T: FromJs<'js>>(&self, ctx: &Ctx<'js>, value: Value<'js>) -> T {
+        let value = match value.try_into_promise() {
+            Ok(promise) => unwrap_js(ctx, promise.finish::<Value>()),
+            Err(value) => value,
+        };
+        self.run_jobs_in(ctx);
+        unwrap_js(ctx, T::from_js(ctx, value))
+    }
+
+    /// Run pending jobs until the job queue is empty, panics if a job throws.
+    pub fn run_jobs(&self) {
+        self.with(|ctx| self.run_jobs_in(&ctx))
+    }
+
+    fn run_jobs_in(&self, ctx: &Ctx<'_>) {
+        loop {
+            let mut job_ctx = MaybeUninit::<*mut qjs::JSContext>::uninit();
+            let res = unsafe {
+                qjs::JS_ExecutePendingJob(qjs::JS_GetRuntime(ctx.as_ptr()), job_ctx.as_mut_ptr())
+            };
+            if res == 0 {
+                break;
+            }
+            if res < 0 {
+                panic!(
+                    "job raised an exception: {}",
+                    CaughtError::from_error(ctx, Error::Exception)
+                );
+            }
+        }
+    }
+
+    /// Move the clock of the fake timers forward, running due timers in order.
+    ///
+    /// Pending jobs are run after every timer, so promises resolved by a timer settle before the
+    /// next timer fires. Panics if a timer callback throws.
+    pub fn advance_timers(&self, ms: u64) {
+        self.with(|ctx| {
+            let timers = self.timers.clone().restore(&ctx).unwrap();
+            let now: f64 = unwrap_js(&ctx, timers.get::<_, Function>("now").unwrap().call(()));
+            let end = now + ms as f64;
+            let next: Function = timers.get("next").unwrap();
+            loop {
+                let ran: bool = unwrap_js(&ctx, next.call((end,)));
+                self.run_jobs_in(&ctx);
+                if !ran {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Returns the number of fake timers which haven't fired yet, including intervals.
+    pub fn pending_timers(&self) -> usize {
+        self.with(|ctx| {
+            let timers = self.timers.clone().restore(&ctx).unwrap();
+            let pending: Function = timers.get("pending").unwrap();
+            unwrap_js(&ctx, pending.call(()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eval_and_timers() {
+        let rt = TestRuntime::new();
+        rt.eval::<()>(
+            r#"
+            var log = [];
+            setTimeout(() => log.push("b"), 20);
+            setTimeout(() => {
+                log.push("a");
+                Promise.resolve().then(() => log.push("a job"));
+            }, 10);
+            var interval = setInterval(() => log.push("i"), 15);
+            "#,
+        );
+        assert_eq!(rt.pending_timers(), 3);
+        rt.advance_timers(19);
+        rt.with(|ctx| assert_eval_eq!(ctx, "log.join()", "a,a job,i".to_string()));
+        rt.advance_timers(11);
+        rt.eval::<()>("clearInterval(interval)");
+        rt.with(|ctx| assert_eval_eq!(ctx, "log.join()", "a,a job,i,b,i".to_string()));
+        assert_eq!(rt.pending_timers(), 0);
+    }
+
+    #[test]
+    fn eval_promise() {
+        let rt = TestRuntime::new();
+        let res: i32 = rt.eval("(async () => { await null; return 42; })()");
+        assert_eq!(res, 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "failed")]
+    fn eval_rejected() {
+        let rt = TestRuntime::new();
+        rt.eval::<()>("Promise.reject(new Error('failed'))");
+    }
+
+    #[test]
+    fn fixture() {
+        let path = fixture_path("Cargo.toml");
+        assert!(path.is_absolute());
+        assert_eq!(fixture_path(&path), path);
+    }
+}
@@ -1,5 +1,7 @@
 //! Module with some util types.
 
+pub(crate) mod lexer;
+
 #[cfg(feature = "futures")]
 pub use self::futures::*;
 
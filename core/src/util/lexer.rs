@@ -0,0 +1,397 @@
+//! A minimal JavaScript tokenizer for finding specific syntax without parsing.
+
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    Ident(&'a str),
+    Punct(&'a str),
+    /// A string literal, the contents between the quotes without unescaping.
+    Str(&'a str),
+    Literal,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Spanned<'a> {
+    pub token: Token<'a>,
+    pub line: i32,
+    pub column: i32,
+}
+
+/// A minimal JavaScript tokenizer.
+///
+/// Only distinguishes identifiers, punctuation and literals, which is enough to find specific
+/// syntax without building an AST.
+pub(crate) struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: i32,
+    column: i32,
+    /// Open braces, `true` for template substitutions.
+    braces: Vec<bool>,
+    tokens: Vec<Spanned<'a>>,
+}
+
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$' || b == b'#' || b == b'\\' || b >= 0x80
+}
+
+fn is_ident_continue(b: u8) -> bool {
+    is_ident_start(b) || b.is_ascii_digit()
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+            column: 1,
+            braces: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let b = self.peek(0)?;
+        self.pos += 1;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if b & 0xC0 != 0x80 {
+            self.column += 1;
+        }
+        Some(b)
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match self.tokens.last().map(|x| x.token) {
+            None => true,
+            Some(Token::Str(_) | Token::Literal) => false,
+            Some(Token::Ident(x)) => REGEX_KEYWORDS.contains(&x),
+            Some(Token::Punct(x)) => !matches!(x, ")" | "]" | "}"),
+        }
+    }
+
+    fn skip_string(&mut self, quote: u8) {
+        self.bump();
+        while let Some(b) = self.bump() {
+            match b {
+                b'\\' => {
+                    self.bump();
+                }
+                b'\n' => break,
+                b if b == quote => break,
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_regex(&mut self) {
+        self.bump();
+        let mut in_class = false;
+        while let Some(b) = self.bump() {
+            match b {
+                b'\\' => {
+                    self.bump();
+                }
+                b'[' => in_class = true,
+                b']' => in_class = false,
+                b'/' if !in_class => break,
+                b'\n' => break,
+                _ => {}
+            }
+        }
+        while self.peek(0).map(is_ident_continue).unwrap_or(false) {
+            self.bump();
+        }
+    }
+
+    /// Skips the rest of a template, returns true if it stopped at a substitution.
+    fn skip_template(&mut self) -> bool {
+        while let Some(b) = self.bump() {
+            match b {
+                b'\\' => {
+                    self.bump();
+                }
+                b'`' => return false,
+                b'$' if self.peek(0) == Some(b'{') => {
+                    self.bump();
+                    self.braces.push(true);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn push(&mut self, token: Token<'a>, line: i32, column: i32) {
+        self.tokens.push(Spanned {
+            token,
+            line,
+            column,
+        })
+    }
+
+    fn push_template(&mut self, line: i32, column: i32) {
+        let token = if self.skip_template() {
+            Token::Punct("${")
+        } else {
+            Token::Literal
+        };
+        self.push(token, line, column);
+    }
+
+    pub fn tokenize(mut self) -> Vec<Spanned<'a>> {
+        while let Some(b) = self.peek(0) {
+            let (start, line, column) = (self.pos, self.line, self.column);
+            match b {
+                b' ' | b'\t' | b'\r' | b'\n' | 0x0b | 0x0c => {
+                    self.bump();
+                }
+                b'/' if self.peek(1) == Some(b'/') => {
+                    while self.peek(0).map(|x| x != b'\n').unwrap_or(false) {
+                        self.bump();
+                    }
+                }
+                b'/' if self.peek(1) == Some(b'*') => {
+                    self.bump();
+                    self.bump();
+                    while self.peek(0).is_some()
+                        && !(self.peek(0) == Some(b'*') && self.peek(1) == Some(b'/'))
+                    {
+                        self.bump();
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                b'/' if self.regex_allowed() => {
+                    self.skip_regex();
+                    self.push(Token::Literal, line, column);
+                }
+                b'\'' | b'"' => {
+                    self.skip_string(b);
+                    let end = if self.src.as_bytes()[self.pos - 1] == b {
+                        self.pos - 1
+                    } else {
+                        self.pos
+                    };
+                    let contents = &self.src[start + 1..end.max(start + 1)];
+                    self.push(Token::Str(contents), line, column);
+                }
+                b'`' => {
+                    self.bump();
+                    self.push_template(line, column);
+                }
+                b'{' => {
+                    self.bump();
+                    self.braces.push(false);
+                    self.push(Token::Punct("{"), line, column);
+                }
+                b'}' => {
+                    self.bump();
+                    let template = self.braces.pop().unwrap_or(false);
+                    self.push(Token::Punct("}"), line, column);
+                    if template {
+                        let (line, column) = (self.line, self.column);
+                        self.push_template(line, column);
+                    }
+                }
+                b'0'..=b'9' => {
+                    while self
+                        .peek(0)
+                        .map(|x| is_ident_continue(x) || x == b'.')
+                        .unwrap_or(false)
+                    {
+                        self.bump();
+                    }
+                    self.push(Token::Literal, line, column);
+                }
+                b'.' if self.peek(1).map(|x| x.is_ascii_digit()).unwrap_or(false) => {
+                    self.bump();
+                    while self.peek(0).map(is_ident_continue).unwrap_or(false) {
+                        self.bump();
+                    }
+                    self.push(Token::Literal, line, column);
+                }
+                b if is_ident_start(b) => {
+                    while self.peek(0).map(is_ident_continue).unwrap_or(false) {
+                        // Include code point escapes like `\u{65}`.
+                        if self.peek(0) == Some(b'\\')
+                            && self.peek(1) == Some(b'u')
+                            && self.peek(2) == Some(b'{')
+                        {
+                            while !matches!(self.bump(), Some(b'}') | None) {}
+                        } else {
+                            self.bump();
+                        }
+                    }
+                    let ident = &self.src[start..self.pos];
+                    self.push(Token::Ident(ident), line, column);
+                }
+                _ => {
+                    let rest = &self.src.as_bytes()[start..];
+                    let len = if rest.starts_with(b"...") {
+                        3
+                    } else if rest.starts_with(b"=>")
+                        || (rest.starts_with(b"?.")
+                            && !rest.get(2).map(|x| x.is_ascii_digit()).unwrap_or(false))
+                    {
+                        2
+                    } else {
+                        1
+                    };
+                    for _ in 0..len {
+                        self.bump();
+                    }
+                    let punct = &self.src[start..self.pos];
+                    self.push(Token::Punct(punct), line, column);
+                }
+            }
+        }
+        self.tokens
+    }
+}
+
+/// Returns the module specifiers of the static imports and exports in the source, as well as of
+/// dynamic imports with a string literal specifier.
+#[cfg_attr(not(all(feature = "loader", feature = "futures")), allow(dead_code))]
+pub(crate) fn import_specifiers(source: &str) -> Vec<String> {
+    let tokens = Lexer::new(source).tokenize();
+    let mut specifiers = Vec::new();
+    for (idx, spanned) in tokens.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|x| tokens[x].token);
+        if matches!(prev, Some(Token::Punct(".") | Token::Punct("?."))) {
+            continue;
+        }
+        let next = |offset: usize| tokens.get(idx + offset).map(|x| x.token);
+        let specifier = match (spanned.token, next(1), next(2), next(3)) {
+            // `import "a"`, `import a from "a"` and `export * from "a"`
+            (Token::Ident("import" | "from"), Some(Token::Str(x)), _, _) => x,
+            // `import("a")`
+            (
+                Token::Ident("import"),
+                Some(Token::Punct("(")),
+                Some(Token::Str(x)),
+                Some(Token::Punct(")") | Token::Punct(",")),
+            ) => x,
+            _ => continue,
+        };
+        if !specifiers.iter().any(|x| x == specifier) {
+            specifiers.push(specifier.to_string());
+        }
+    }
+    specifiers
+}
+
+/// Decodes the escapes of an identifier or the contents of a string literal.
+///
+/// Invalid escapes are kept as they are.
+pub(crate) fn unescape(src: &str) -> Cow<'_, str> {
+    if !src.contains('\\') {
+        return Cow::Borrowed(src);
+    }
+    let mut res = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(idx) = rest.find('\\') {
+        res.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        let (hex, len) = if let Some(x) = rest.strip_prefix("u{") {
+            match x.find('}') {
+                Some(end) => (&x[..end], end + 3),
+                None => ("", 0),
+            }
+        } else if rest.starts_with('u') {
+            (rest.get(1..5).unwrap_or(""), 5)
+        } else if rest.starts_with('x') {
+            (rest.get(1..3).unwrap_or(""), 3)
+        } else {
+            ("", 0)
+        };
+        match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            Some(c) => {
+                res.push(c);
+                rest = &rest[len..];
+            }
+            None => match rest.chars().next() {
+                Some(c) if len == 0 => {
+                    res.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+                _ => res.push('\\'),
+            },
+        }
+    }
+    res.push_str(rest);
+    Cow::Owned(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tokenize_strings() {
+        let tokens = Lexer::new(r#"let a = "b\"c"; 'd'"#).tokenize();
+        let strings = tokens
+            .iter()
+            .filter_map(|x| match x.token {
+                Token::Str(x) => Some(x),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(strings, [r#"b\"c"#, "d"]);
+    }
+
+    #[test]
+    fn unescape_escapes() {
+        assert_eq!(unescape("eval"), "eval");
+        assert_eq!(unescape(r"\u0065val"), "eval");
+        assert_eq!(unescape(r"\u{65}v\u{0061}l"), "eval");
+        assert_eq!(unescape(r"\x65v\al"), "eval");
+        assert_eq!(unescape(r"\u00"), r"\u00");
+        let tokens = Lexer::new(r"\u{65}val(1)").tokenize();
+        assert_eq!(tokens[0].token, Token::Ident(r"\u{65}val"));
+    }
+
+    #[test]
+    fn find_import_specifiers() {
+        let source = r#"
+            import a from "./a.js";
+            import { b } from './b.js';
+            import "./c.js";
+            export * from "./d.js";
+            const from = "not a module";
+            const e = await import("./e.js");
+            const f = import(name);
+            obj.import("./g.js");
+            // import "./h.js";
+        "#;
+        assert_eq!(
+            import_specifiers(source),
+            ["./a.js", "./b.js", "./c.js", "./d.js", "./e.js"]
+        );
+    }
+}
@@ -1,6 +1,11 @@
 use crate::{qjs, Ctx, Error, Result};
-use std::{fmt, hash::Hash, mem, ops::Deref, result::Result as StdResult, str};
+use std::{
+    collections::HashSet, fmt, hash::Hash, mem, ops::Deref, result::Result as StdResult, str,
+};
 
+#[cfg(feature = "arbitrary")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "arbitrary")))]
+pub mod arbitrary;
 pub mod array;
 pub mod atom;
 mod bigint;
@@ -10,6 +15,12 @@ pub mod function;
 pub mod module;
 pub mod object;
 pub mod promise;
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde")))]
+pub mod serde;
+#[cfg(feature = "futures")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+pub mod stream;
 mod string;
 mod symbol;
 
@@ -22,7 +33,7 @@ pub use function::{Constructor, Function};
 pub use module::Module;
 pub use object::{Filter, Object};
 pub use promise::Promise;
-pub use string::String;
+pub use string::{LoneSurrogates, String};
 pub use symbol::Symbol;
 
 #[cfg(feature = "array-buffer")]
@@ -294,6 +305,15 @@ impl<'js> Value<'js> {
         qjs::JS_TAG_UNDEFINED == tag
     }
 
+    /// Returns if the value is `null` or `undefined`, the values the `??` operator and optional
+    /// chaining treat as missing.
+    ///
+    /// Use the [`Null`] and [`Undefined`] types to require one of them in conversions.
+    #[inline]
+    pub fn is_nullish(&self) -> bool {
+        self.is_null() || self.is_undefined()
+    }
+
     /// Check if the value is a bool
     #[inline]
     pub fn is_bool(&self) -> bool {
@@ -390,11 +410,80 @@ impl<'js> Value<'js> {
         self
     }
 
+    /// Freeze the value and every object reachable from it through own data properties, like
+    /// calling `Object.freeze` on the whole object graph.
+    ///
+    /// Freezing host data before exposing it prevents scripts from mutating configuration which
+    /// is shared by reference. Objects referenced more than once, including cycles, are frozen
+    /// once. Getters are not invoked and prototypes are not frozen. Like `Object.freeze` this
+    /// fails for typed arrays which have elements. Primitive values are left as is.
+    pub fn deep_freeze(&self) -> Result<()> {
+        let mut visited = HashSet::new();
+        let mut pending: Vec<Object<'js>> = self.as_object().cloned().into_iter().collect();
+        while let Some(object) = pending.pop() {
+            if !visited.insert(unsafe { object.get_ptr() } as usize) {
+                continue;
+            }
+            object.freeze_with(|value| pending.extend(value.into_object()))?;
+        }
+        Ok(())
+    }
+
     /// Convert from value to specified type
     pub fn get<T: FromJs<'js>>(&self) -> Result<T> {
         T::from_js(self.ctx(), self.clone())
     }
 
+    /// Get a property like the optional chaining `value?.[key]` does.
+    ///
+    /// Returns `None` if the value is `null` or `undefined`, instead of failing like a property
+    /// access on those does, and if the property is `null` or `undefined`. Exceptions thrown by
+    /// getters and errors converting the property are still returned.
+    ///
+    /// ```
+    /// # use rquickjs::{Context, Runtime, Value};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let config: Value = ctx.eval("({ retries: 3, proxy: null })").unwrap();
+    ///     assert_eq!(config.get_opt::<_, i32>("retries").unwrap(), Some(3));
+    ///     assert_eq!(config.get_opt::<_, i32>("timeout").unwrap(), None);
+    ///     let port = config.get_opt_path::<i32, _>(["proxy", "port"]).unwrap();
+    ///     assert_eq!(port, None);
+    /// });
+    /// ```
+    pub fn get_opt<K: IntoAtom<'js>, V: FromJs<'js>>(&self, key: K) -> Result<Option<V>> {
+        self.get_opt_path([key])
+    }
+
+    /// Get a nested property like the optional chaining `value?.[a]?.[b]` does.
+    ///
+    /// Returns `None` as soon as the value or a property along the path is `null` or
+    /// `undefined`, see [`Value::get_opt`]. An empty path converts the value itself.
+    pub fn get_opt_path<V, I>(&self, path: I) -> Result<Option<V>>
+    where
+        V: FromJs<'js>,
+        I: IntoIterator,
+        I::Item: IntoAtom<'js>,
+    {
+        let mut value = self.clone();
+        for key in path {
+            if value.is_nullish() {
+                return Ok(None);
+            }
+            let atom = key.into_atom(&self.ctx)?;
+            value = unsafe {
+                let val = qjs::JS_GetProperty(self.ctx.as_ptr(), value.value, atom.atom);
+                let val = self.ctx.handle_exception(val)?;
+                Value::from_js_value(self.ctx.clone(), val)
+            };
+        }
+        if value.is_nullish() {
+            return Ok(None);
+        }
+        V::from_js(&self.ctx, value).map(Some)
+    }
+
     /// Returns the raw C library JavaScript value.
     pub fn as_raw(&self) -> qjs::JSValue {
         self.value
@@ -773,6 +862,57 @@ void_types! {
 mod test {
     use crate::*;
 
+    #[test]
+    fn nullish() {
+        test_with(|ctx| {
+            let null: Value = ctx.eval("null").unwrap();
+            let undefined: Value = ctx.eval("undefined").unwrap();
+            let zero: Value = ctx.eval("0").unwrap();
+            assert!(null.is_nullish() && undefined.is_nullish());
+            assert!(!zero.is_nullish());
+
+            assert_eq!(null.get::<Null>().unwrap(), Null);
+            assert_eq!(undefined.get::<Undefined>().unwrap(), Undefined);
+            assert!(undefined.get::<Null>().is_err());
+            assert!(null.get::<Undefined>().is_err());
+            assert!(zero.get::<Null>().is_err());
+
+            let func = Function::new(ctx.clone(), || Null).unwrap();
+            ctx.globals().set("f", func).unwrap();
+            ctx.globals().set("u", Undefined).unwrap();
+            let res: bool = ctx.eval("f() === null && u === undefined").unwrap();
+            assert!(res);
+        })
+    }
+
+    #[test]
+    fn get_opt() {
+        test_with(|ctx| {
+            let val: Value = ctx
+                .eval("({ a: { b: [1, { c: 'x' }], n: null }, s: 'abc', get e() { throw new Error('e') } })")
+                .unwrap();
+            assert_eq!(val.get_opt::<_, StdString>("s").unwrap().unwrap(), "abc");
+            assert_eq!(val.get_opt::<_, i32>("missing").unwrap(), None);
+            let c: Option<StdString> = val.get_opt_path(["a", "b", "1", "c"]).unwrap();
+            assert_eq!(c.unwrap(), "x");
+            let len: Option<i32> = val.get_opt_path(["s", "length"]).unwrap();
+            assert_eq!(len, Some(3));
+            let res: Option<i32> = val.get_opt_path(["a", "n", "x", "y"]).unwrap();
+            assert_eq!(res, None);
+            let res: Option<i32> = val.get_opt_path(["missing", "x"]).unwrap();
+            assert_eq!(res, None);
+            let res: Option<Value> = Value::new_null(ctx.clone()).get_opt("x").unwrap();
+            assert!(res.is_none());
+
+            assert!(matches!(val.get_opt::<_, i32>("e"), Err(Error::Exception)));
+            ctx.catch();
+            assert!(matches!(
+                val.get_opt::<_, i32>("s"),
+                Err(Error::FromJs { .. })
+            ));
+        })
+    }
+
     #[test]
     fn type_matches() {
         assert!(Type::Bool.interpretable_as(Type::Bool));
@@ -0,0 +1,231 @@
+//! Random JavaScript values for property based testing.
+//!
+//! [`ArbitraryValue`] implements [`Arbitrary`], so fuzzers and property testing tools based on
+//! the `arbitrary` crate can generate JavaScript values to test conversion code with. Rust types
+//! which implement `Arbitrary`, for example by deriving it, can be checked with [`round_trip`].
+//!
+//! ```
+//! # use arbitrary::{Arbitrary, Unstructured};
+//! # use rquickjs::{arbitrary::{round_trip, ArbitraryValue}, Context, Runtime};
+//! # let rt = Runtime::new().unwrap();
+//! # let ctx = Context::full(&rt).unwrap();
+//! let bytes = [7u8; 64];
+//! let mut u = Unstructured::new(&bytes);
+//! let value = ArbitraryValue::arbitrary(&mut u).unwrap();
+//! ctx.with(|ctx| {
+//!     let first = round_trip(&ctx, value).unwrap();
+//!     let second = round_trip(&ctx, first.clone()).unwrap();
+//!     assert_eq!(first, second);
+//! });
+//! ```
+
+use ::arbitrary::{Arbitrary, Unstructured};
+
+use crate::{
+    convert::ConversionGuard, Array, BigInt, Ctx, Error, FromJs, IntoJs, Object, Result, StdString,
+    Type, Value,
+};
+
+/// The maximum nesting of arrays and objects.
+const MAX_DEPTH: usize = 4;
+
+/// The maximum number of elements of a generated array or object.
+const MAX_LEN: usize = 16;
+
+/// A JavaScript value which doesn't belong to a context.
+///
+/// Only the values which can be generated are represented, which are the primitive values
+/// except symbols, and arrays and plain objects of them. Numbers are kept apart as integers and
+/// floats like QuickJS does, a float is never converted to an integer value.
+///
+/// Values compare equal like with `Object.is`, except that `0` and `-0` are equal. The
+/// properties of objects are compared in order, note that JavaScript orders integer keys first.
+#[derive(Debug, Clone)]
+pub enum ArbitraryValue {
+    Undefined,
+    Null,
+    Bool(bool),
+    Int(i32),
+    Float(f64),
+    String(StdString),
+    BigInt(i64),
+    Array(Vec<ArbitraryValue>),
+    Object(Vec<(StdString, ArbitraryValue)>),
+}
+
+impl PartialEq for ArbitraryValue {
+    fn eq(&self, other: &Self) -> bool {
+        use ArbitraryValue::*;
+        match (self, other) {
+            (Undefined, Undefined) | (Null, Null) => true,
+            (Bool(a), Bool(b)) => a == b,
+            (Int(a), Int(b)) => a == b,
+            (Float(a), Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (String(a), String(b)) => a == b,
+            (BigInt(a), BigInt(b)) => a == b,
+            (Array(a), Array(b)) => a == b,
+            (Object(a), Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl ArbitraryValue {
+    fn arbitrary_depth(u: &mut Unstructured<'_>, depth: usize) -> arbitrary::Result<Self> {
+        // Arrays and objects can only be generated above the maximum depth.
+        let kinds = if depth < MAX_DEPTH { 9 } else { 7 };
+        Ok(match u.choose_index(kinds)? {
+            0 => ArbitraryValue::Undefined,
+            1 => ArbitraryValue::Null,
+            2 => ArbitraryValue::Bool(u.arbitrary()?),
+            3 => ArbitraryValue::Int(u.arbitrary()?),
+            4 => ArbitraryValue::Float(u.arbitrary()?),
+            5 => ArbitraryValue::String(u.arbitrary()?),
+            6 => ArbitraryValue::BigInt(u.arbitrary()?),
+            7 => {
+                let len = u.arbitrary_len::<u8>()?.min(MAX_LEN);
+                let items = (0..len)
+                    .map(|_| Self::arbitrary_depth(u, depth + 1))
+                    .collect::<arbitrary::Result<_>>()?;
+                ArbitraryValue::Array(items)
+            }
+            _ => {
+                let len = u.arbitrary_len::<u8>()?.min(MAX_LEN);
+                let mut props: Vec<(StdString, ArbitraryValue)> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key: StdString = u.arbitrary()?;
+                    let value = Self::arbitrary_depth(u, depth + 1)?;
+                    // A key can only exist once in an object.
+                    if !props.iter().any(|(x, _)| *x == key) {
+                        props.push((key, value));
+                    }
+                }
+                ArbitraryValue::Object(props)
+            }
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ArbitraryValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Self::arbitrary_depth(u, 0)
+    }
+}
+
+impl<'js> IntoJs<'js> for ArbitraryValue {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        Ok(match self {
+            ArbitraryValue::Undefined => Value::new_undefined(ctx.clone()),
+            ArbitraryValue::Null => Value::new_null(ctx.clone()),
+            ArbitraryValue::Bool(x) => Value::new_bool(ctx.clone(), x),
+            ArbitraryValue::Int(x) => Value::new_int(ctx.clone(), x),
+            ArbitraryValue::Float(x) => Value::new_float(ctx.clone(), x),
+            ArbitraryValue::String(x) => x.into_js(ctx)?,
+            ArbitraryValue::BigInt(x) => BigInt::from_i64(ctx.clone(), x)?.into_value(),
+            ArbitraryValue::Array(items) => {
+                let array = Array::new(ctx.clone())?;
+                for (idx, item) in items.into_iter().enumerate() {
+                    array.set(idx, item)?;
+                }
+                array.into_value()
+            }
+            ArbitraryValue::Object(props) => {
+                let object = Object::new(ctx.clone())?;
+                for (key, value) in props {
+                    object.set(key, value)?;
+                }
+                object.into_value()
+            }
+        })
+    }
+}
+
+impl<'js> FromJs<'js> for ArbitraryValue {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        let type_ = value.type_of();
+        let _guard = ConversionGuard::enter(ctx, &value, "ArbitraryValue")?;
+        Ok(match type_ {
+            Type::Uninitialized | Type::Undefined => ArbitraryValue::Undefined,
+            Type::Null => ArbitraryValue::Null,
+            Type::Bool => ArbitraryValue::Bool(value.as_bool().unwrap()),
+            Type::Int => ArbitraryValue::Int(value.as_int().unwrap()),
+            Type::Float => ArbitraryValue::Float(value.as_float().unwrap()),
+            Type::String => ArbitraryValue::String(value.into_string().unwrap().to_string()?),
+            Type::BigInt => ArbitraryValue::BigInt(value.into_big_int().unwrap().to_i64()?),
+            Type::Array => {
+                let items = value
+                    .into_array()
+                    .unwrap()
+                    .iter::<ArbitraryValue>()
+                    .collect::<Result<_>>()?;
+                ArbitraryValue::Array(items)
+            }
+            Type::Object => {
+                let props = value
+                    .into_object()
+                    .unwrap()
+                    .props::<StdString, ArbitraryValue>()
+                    .collect::<Result<_>>()?;
+                ArbitraryValue::Object(props)
+            }
+            _ => return Err(Error::new_from_js(type_.as_str(), "ArbitraryValue")),
+        })
+    }
+}
+
+/// Convert a value to JavaScript and back again.
+///
+/// Property tests can check that conversions of a type don't lose information by comparing the
+/// result with the original value.
+pub fn round_trip<'js, T>(ctx: &Ctx<'js>, value: T) -> Result<T>
+where
+    T: IntoJs<'js> + FromJs<'js>,
+{
+    let value = value.into_js(ctx)?;
+    T::from_js(ctx, value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_with;
+
+    #[test]
+    fn round_trips() {
+        test_with(|ctx| {
+            let value = ArbitraryValue::Object(vec![
+                ("b".to_string(), ArbitraryValue::Float(f64::NAN)),
+                (
+                    "a".to_string(),
+                    ArbitraryValue::Array(vec![
+                        ArbitraryValue::Int(1),
+                        ArbitraryValue::Float(1.0),
+                        ArbitraryValue::BigInt(i64::MIN),
+                        ArbitraryValue::String("\0 ü".to_string()),
+                        ArbitraryValue::Undefined,
+                        ArbitraryValue::Null,
+                        ArbitraryValue::Bool(true),
+                    ]),
+                ),
+            ]);
+            assert_eq!(round_trip(&ctx, value.clone()).unwrap(), value);
+            let value = vec![Some("x".to_string()), None];
+            assert_eq!(round_trip(&ctx, value.clone()).unwrap(), value);
+        })
+    }
+
+    #[test]
+    fn generate() {
+        test_with(|ctx| {
+            for seed in 0u8..32 {
+                let bytes: Vec<u8> = (0..256u32)
+                    .map(|x| (x as u8).wrapping_mul(seed).wrapping_add(seed))
+                    .collect();
+                let mut u = Unstructured::new(&bytes);
+                let value = ArbitraryValue::arbitrary(&mut u).unwrap();
+                let first = round_trip(&ctx, value).unwrap();
+                assert_eq!(round_trip(&ctx, first.clone()).unwrap(), first);
+            }
+        })
+    }
+}
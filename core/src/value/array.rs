@@ -44,27 +44,17 @@ impl<'js> Array<'js> {
 
     /// Get the value at an index in the JavaScript array.
     pub fn get<V: FromJs<'js>>(&self, idx: usize) -> Result<V> {
-        let ctx = self.ctx();
-        let obj = self.0.as_js_value();
-        let val = unsafe {
-            let val = qjs::JS_GetPropertyUint32(ctx.as_ptr(), obj, idx as _);
-            let val = ctx.handle_exception(val)?;
-            Value::from_js_value(ctx.clone(), val)
-        };
-        V::from_js(ctx, val)
+        self.0.get_index(idx)
     }
 
     /// Set the value at an index in the JavaScript array.
     pub fn set<V: IntoJs<'js>>(&self, idx: usize, val: V) -> Result<()> {
-        let ctx = self.ctx();
-        let obj = self.0.as_js_value();
-        let val = val.into_js(ctx)?.into_js_value();
-        unsafe {
-            if 0 > qjs::JS_SetPropertyUint32(ctx.as_ptr(), obj, idx as _, val) {
-                return Err(ctx.raise_exception());
-            }
-        }
-        Ok(())
+        self.0.set_index(idx, val)
+    }
+
+    /// Append a value to the end of the JavaScript array.
+    pub fn push<V: IntoJs<'js>>(&self, val: V) -> Result<()> {
+        self.set(self.len(), val)
     }
 
     /// Get an iterator over elements of an array
@@ -254,6 +244,20 @@ mod test {
         })
     }
 
+    #[test]
+    fn push() {
+        test_with(|ctx| {
+            let array = Array::new(ctx.clone()).unwrap();
+            array.push(1).unwrap();
+            array.push("two").unwrap();
+            assert_eq!(array.len(), 2);
+            assert_eq!(array.get::<StdString>(1).unwrap(), "two");
+            ctx.globals().set("array", array).unwrap();
+            let res: StdString = ctx.eval("array.push(true); array.join()").unwrap();
+            assert_eq!(res, "1,two,true");
+        })
+    }
+
     #[test]
     fn collect_js() {
         test_with(|ctx| {
@@ -60,12 +60,21 @@ impl<'js> ArrayBuffer<'js> {
             unsafe { Vec::from_raw_parts(ptr, capacity, capacity) };
         }
 
+        // Vectors of byte sized types have the layout of byte vectors, their buffers are freed
+        // with `drop_bytes` so `transfer_to_rust` can recognize them.
+        let free_func: qjs::JSFreeArrayBufferDataFunc =
+            if size_of::<T>() == 1 && mem::align_of::<T>() == 1 {
+                Some(drop_bytes)
+            } else {
+                Some(drop_raw::<T>)
+            };
+
         Ok(Self(Object(unsafe {
             let val = qjs::JS_NewArrayBuffer(
                 ctx.as_ptr(),
                 ptr as _,
                 size as _,
-                Some(drop_raw::<T>),
+                free_func,
                 capacity as _,
                 0,
             );
@@ -125,6 +134,40 @@ impl<'js> ArrayBuffer<'js> {
         unsafe { qjs::JS_DetachArrayBuffer(self.0.ctx.as_ptr(), self.0.as_js_value()) }
     }
 
+    /// Detach the array buffer and move its bytes to Rust.
+    ///
+    /// Typed arrays and data views on the buffer become empty, so JavaScript can't observe the
+    /// bytes after they are handed off, for example to another thread. The bytes are moved
+    /// without copying them if the buffer was created from a vector of bytes with
+    /// [`ArrayBuffer::new`], which is the reverse handoff from Rust to JavaScript, and copied
+    /// otherwise.
+    ///
+    /// Fails with [`AsSliceError::BufferUsed`] if the buffer was already detached.
+    pub fn transfer_to_rust(mut self) -> Result<Vec<u8>> {
+        let mut size = MaybeUninit::<qjs::size_t>::uninit();
+        let mut opaque = MaybeUninit::<*mut c_void>::uninit();
+        let ptr = unsafe {
+            qjs::JS_TransferArrayBuffer(
+                self.0.ctx.as_ptr(),
+                self.0.as_js_value(),
+                Some(drop_bytes),
+                size.as_mut_ptr(),
+                opaque.as_mut_ptr(),
+            )
+        };
+        if !ptr.is_null() {
+            let len = unsafe { size.assume_init() } as usize;
+            let capacity = unsafe { opaque.assume_init() } as usize;
+            return Ok(unsafe { Vec::from_raw_parts(ptr, len, capacity) });
+        }
+        let bytes = self
+            .as_bytes()
+            .ok_or(Error::AsSlice(AsSliceError::BufferUsed))?
+            .to_vec();
+        self.detach();
+        Ok(bytes)
+    }
+
     /// Reference to value
     #[inline]
     pub fn as_value(&self) -> &Value<'js> {
@@ -187,6 +230,12 @@ impl<'js> ArrayBuffer<'js> {
     }
 }
 
+/// Frees the buffers of array buffers created from vectors of bytes.
+extern "C" fn drop_bytes(_rt: *mut qjs::JSRuntime, opaque: *mut c_void, ptr: *mut c_void) {
+    let capacity = opaque as usize;
+    unsafe { Vec::from_raw_parts(ptr as *mut u8, capacity, capacity) };
+}
+
 impl<'js, T: TypedArrayItem> AsRef<[T]> for ArrayBuffer<'js> {
     fn as_ref(&self) -> &[T] {
         self.as_slice().expect("ArrayBuffer was detached")
@@ -348,4 +397,29 @@ mod test {
             assert_eq!(val.as_bytes().unwrap(), &res)
         });
     }
+
+    #[test]
+    fn transfer_to_rust() {
+        use super::AsSliceError;
+
+        test_with(|ctx| {
+            let bytes = vec![1u8, 2, 3];
+            let ptr = bytes.as_ptr();
+            let buffer = ArrayBuffer::new(ctx.clone(), bytes).unwrap();
+            ctx.globals().set("a", buffer.clone()).unwrap();
+            ctx.eval::<(), _>("var view = new Uint8Array(a)").unwrap();
+            let bytes = buffer.transfer_to_rust().unwrap();
+            assert_eq!(bytes, [1, 2, 3]);
+            assert_eq!(bytes.as_ptr(), ptr);
+            let res: StdString = ctx.eval("[a.byteLength, view.length].join()").unwrap();
+            assert_eq!(res, "0,0");
+
+            let buffer: ArrayBuffer = ctx.eval("new Uint8Array([4, 5]).buffer").unwrap();
+            assert_eq!(buffer.clone().transfer_to_rust().unwrap(), [4, 5]);
+            assert!(matches!(
+                buffer.transfer_to_rust(),
+                Err(Error::AsSlice(AsSliceError::BufferUsed))
+            ));
+        });
+    }
 }
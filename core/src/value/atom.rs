@@ -1,6 +1,6 @@
 //!  QuickJS atom functionality.
 
-use crate::{qjs, Ctx, Error, Result, String, Value};
+use crate::{qjs, Ctx, Result, String, Value};
 use std::{ffi::CStr, hash::Hash, string::String as StdString};
 
 mod predefined;
@@ -53,19 +53,38 @@ impl<'js> Atom<'js> {
     pub fn from_u32(ctx: Ctx<'js>, val: u32) -> Result<Atom<'js>> {
         let atom = unsafe { qjs::JS_NewAtomUInt32(ctx.as_ptr(), val) };
         if atom == qjs::JS_ATOM_NULL {
-            // Should never invoke a callback so no panics
-            return Err(Error::Exception);
+            return Err(ctx.raise_exception());
         }
         Ok(Atom { atom, ctx })
     }
 
+    /// Create an atom from an index.
+    ///
+    /// Indices larger than a `u32` are converted to their string representation, which is the
+    /// key JavaScript uses for them.
+    pub fn from_index(ctx: Ctx<'js>, val: usize) -> Result<Atom<'js>> {
+        match u32::try_from(val) {
+            Ok(x) => Self::from_u32(ctx, x),
+            Err(_) => Self::from_str(ctx, &val.to_string()),
+        }
+    }
+
+    /// Create an atom from an `i64`.
+    ///
+    /// Values which are not a valid `u32` are converted to their string representation.
+    pub fn from_i64(ctx: Ctx<'js>, val: i64) -> Result<Atom<'js>> {
+        match u32::try_from(val) {
+            Ok(x) => Self::from_u32(ctx, x),
+            Err(_) => Self::from_str(ctx, &val.to_string()),
+        }
+    }
+
     /// Create an atom from an `i32` via value
     pub fn from_i32(ctx: Ctx<'js>, val: i32) -> Result<Atom<'js>> {
         let atom =
             unsafe { qjs::JS_ValueToAtom(ctx.as_ptr(), qjs::JS_MKVAL(qjs::JS_TAG_INT, val)) };
         if atom == qjs::JS_ATOM_NULL {
-            // Should never invoke a callback so no panics
-            return Err(Error::Exception);
+            return Err(ctx.raise_exception());
         }
         Ok(Atom { atom, ctx })
     }
@@ -75,8 +94,7 @@ impl<'js> Atom<'js> {
         let val = if val { qjs::JS_TRUE } else { qjs::JS_FALSE };
         let atom = unsafe { qjs::JS_ValueToAtom(ctx.as_ptr(), val) };
         if atom == qjs::JS_ATOM_NULL {
-            // Should never invoke a callback so no panics
-            return Err(Error::Exception);
+            return Err(ctx.raise_exception());
         }
         Ok(Atom { atom, ctx })
     }
@@ -85,8 +103,7 @@ impl<'js> Atom<'js> {
     pub fn from_f64(ctx: Ctx<'js>, val: f64) -> Result<Atom<'js>> {
         let atom = unsafe { qjs::JS_ValueToAtom(ctx.as_ptr(), qjs::JS_NewFloat64(val)) };
         if atom == qjs::JS_ATOM_NULL {
-            // Should never invoke a callback so no panics
-            return Err(Error::Exception);
+            return Err(ctx.raise_exception());
         }
         Ok(Atom { atom, ctx })
     }
@@ -97,13 +114,44 @@ impl<'js> Atom<'js> {
             let ptr = name.as_ptr() as *const std::os::raw::c_char;
             let atom = qjs::JS_NewAtomLen(ctx.as_ptr(), ptr, name.len() as _);
             if atom == qjs::JS_ATOM_NULL {
-                // Should never invoke a callback so no panics
-                return Err(Error::Exception);
+                return Err(ctx.raise_exception());
             }
             Ok(Atom { atom, ctx })
         }
     }
 
+    /// Create an atom from a static Rust string, which is only interned once per runtime.
+    ///
+    /// The atom is cached until the runtime is dropped, so repeated lookups of the same
+    /// property name skip hashing and interning the string. The code generated by `#[methods]`,
+    /// `#[class]` and `#[interface]` uses it for the names of methods and properties.
+    ///
+    /// Strings are cached by their address, so leaked strings, for example from `Box::leak`, are
+    /// cached separately even if they are equal. At most [`Atom::MAX_STATIC_ATOMS`] strings are
+    /// cached per runtime, later ones are interned every time like with [`Atom::from_str`].
+    ///
+    /// Only atoms are cached, there is no cache of other metadata per function or per context:
+    /// class ids are already assigned once per process and generated functions take their
+    /// parameters by position, so the names of properties are the only strings the generated code
+    /// would otherwise intern again.
+    pub fn from_static(ctx: Ctx<'js>, name: &'static str) -> Result<Atom<'js>> {
+        let key = (name.as_ptr() as usize, name.len());
+        unsafe {
+            let atoms = &mut (*ctx.get_opaque()).static_atoms;
+            if let Some(&atom) = atoms.get(&key) {
+                return Ok(Atom::from_atom_val_dup(ctx, atom));
+            }
+            let atom = Atom::from_str(ctx.clone(), name)?;
+            if atoms.len() < Self::MAX_STATIC_ATOMS {
+                atoms.insert(key, qjs::JS_DupAtom(ctx.as_ptr(), atom.atom));
+            }
+            Ok(atom)
+        }
+    }
+
+    /// The max number of strings cached by [`Atom::from_static`] per runtime.
+    pub const MAX_STATIC_ATOMS: usize = 4096;
+
     /// Create an atom from a predefined atom.
     pub fn from_predefined(ctx: Ctx<'js>, predefined: PredefinedAtom) -> Atom<'js> {
         unsafe { Atom::from_atom_val(ctx, predefined as qjs::JSAtom) }
@@ -114,16 +162,14 @@ impl<'js> Atom<'js> {
         unsafe {
             let c_str = qjs::JS_AtomToCString(self.ctx.as_ptr(), self.atom);
             if c_str.is_null() {
-                // Might not ever happen but I am not 100% sure
-                // so just incase check it.
-                qjs::JS_FreeCString(self.ctx.as_ptr(), c_str);
-                return Err(Error::Unknown);
+                // Converting can fail when allocating the C string fails.
+                return Err(self.ctx.raise_exception());
             }
             let bytes = CStr::from_ptr(c_str).to_bytes();
-            // Safety: QuickJS should return valid utf8 so this should be safe.
-            let res = std::str::from_utf8_unchecked(bytes).to_string();
+            // Names can contain lone surrogates, which QuickJS encodes as invalid UTF-8.
+            let res = std::str::from_utf8(bytes).map(|s| s.to_string());
             qjs::JS_FreeCString(self.ctx.as_ptr(), c_str);
-            Ok(res)
+            Ok(res?)
         }
     }
 
@@ -168,3 +214,35 @@ impl<'js> Drop for Atom<'js> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn from_static() {
+        test_with(|ctx| {
+            let name: &'static str = "cached";
+            let a = Atom::from_static(ctx.clone(), name).unwrap();
+            let b = Atom::from_static(ctx.clone(), name).unwrap();
+            assert_eq!(a, b);
+            assert_eq!(a, Atom::from_str(ctx.clone(), "cached").unwrap());
+            assert_eq!(b.to_string().unwrap(), "cached");
+            let atoms = unsafe { &(*ctx.get_opaque()).static_atoms };
+            assert_eq!(atoms.len(), 1);
+        })
+    }
+
+    #[test]
+    fn from_static_bounded() {
+        test_with(|ctx| {
+            for i in 0..Atom::MAX_STATIC_ATOMS + 16 {
+                let name: &'static str = Box::leak(format!("leaked{i}").into_boxed_str());
+                let atom = Atom::from_static(ctx.clone(), name).unwrap();
+                assert_eq!(atom.to_string().unwrap(), name);
+            }
+            let atoms = unsafe { &(*ctx.get_opaque()).static_atoms };
+            assert_eq!(atoms.len(), Atom::MAX_STATIC_ATOMS);
+        })
+    }
+}
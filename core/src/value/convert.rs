@@ -5,7 +5,19 @@ use crate::{Atom, Ctx, Result, Value};
 mod atom;
 mod coerce;
 mod from;
+mod guard;
 mod into;
+#[cfg(feature = "serde_json")]
+mod json;
+mod list;
+mod memo;
+#[cfg(feature = "ndarray")]
+mod shaped;
+
+pub use guard::ConversionGuard;
+pub use memo::Memoized;
+#[cfg(feature = "ndarray")]
+pub use shaped::Shaped;
 
 /// The wrapper for values to force coercion
 ///
@@ -49,6 +61,14 @@ pub struct Coerced<T>(pub T);
 /// to one if it is required.
 pub trait FromJs<'js>: Sized {
     fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self>;
+
+    /// Convert a list into a vector of this type, used by the implementation for [`Vec`].
+    ///
+    /// The element types of typed arrays also accept typed arrays.
+    #[doc(hidden)]
+    fn vec_from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Vec<Self>> {
+        list::array_into_vec(ctx, value)
+    }
 }
 
 /// Trait for converting values from atoms.
@@ -68,6 +88,18 @@ pub trait FromIteratorJs<'js, A>: Sized {
 /// For converting Rust values to JavaScript values
 pub trait IntoJs<'js> {
     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>>;
+
+    /// Convert a list of this type, used by the implementations for [`Vec`] and slices.
+    ///
+    /// The element types of typed arrays create a typed array instead of an array.
+    #[doc(hidden)]
+    fn list_into_js<I>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>>
+    where
+        I: IntoIterator<Item = Self>,
+        Self: Sized,
+    {
+        list::list_into_array(list, ctx)
+    }
 }
 
 /// Trait for converting values to atoms.
@@ -97,3 +129,37 @@ where
 /// A helper type for turning a tuple into a JavaScript array.
 /// Implements [`IntoJs`] and [`FromJs`] for tuples of various lengths
 pub struct List<T>(pub T);
+
+/// The hint passed to a method renamed to
+/// [`PredefinedAtom::SymbolToPrimitive`](crate::atom::PredefinedAtom::SymbolToPrimitive),
+/// the type of primitive JavaScript wants the object converted to.
+///
+/// ```
+/// # use rquickjs::{Runtime, Context, Result, Function, convert::PrimitiveHint};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// # ctx.with(|ctx| -> Result<()> {
+/// #
+/// let to_primitive = Function::new(ctx.clone(), |hint: PrimitiveHint| match hint {
+///     PrimitiveHint::Number => "42",
+///     PrimitiveHint::String => "forty-two",
+///     PrimitiveHint::Default => "default",
+/// });
+/// ctx.globals().set("toPrimitive", to_primitive)?;
+/// let res: String = ctx.eval(
+///     "const v = { [Symbol.toPrimitive]: toPrimitive }; `${+v} ${v} ${v + ''}`",
+/// )?;
+/// assert_eq!(res, "42 forty-two default");
+/// #
+/// # Ok(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimitiveHint {
+    /// The object is converted to a number, for example by `+obj`.
+    Number,
+    /// The object is converted to a string, for example in a template string.
+    String,
+    /// The type is unspecified, for example in `obj + ''` or `obj == 1`.
+    Default,
+}
@@ -1,5 +1,6 @@
 use crate::{
-    atom::PredefinedAtom, qjs, Atom, Ctx, FromAtom, IntoAtom, Result, StdString, String, Value,
+    atom::PredefinedAtom, qjs, Atom, Ctx, Error, FromAtom, IntoAtom, Result, StdString, String,
+    Value,
 };
 
 impl<'js> FromAtom<'js> for Atom<'js> {
@@ -26,6 +27,31 @@ impl<'js> FromAtom<'js> for StdString {
     }
 }
 
+macro_rules! from_atom_impls {
+    ($($type:ident)*) => {
+        $(
+            /// Parses the key as a number, failing for keys which are not a number or are out of
+            /// range, for example negative keys for unsigned types.
+            impl<'js> FromAtom<'js> for $type {
+                fn from_atom(atom: Atom<'js>) -> Result<Self> {
+                    let key = atom.to_string()?;
+                    key.parse().map_err(|_| {
+                        Error::new_from_js_message(
+                            "atom",
+                            stringify!($type),
+                            format!("key `{key}` is not a valid {}", stringify!($type)),
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_atom_impls! {
+    u8 u16 u32 u64 usize i8 i16 i32 i64 isize
+}
+
 impl<'js> IntoAtom<'js> for PredefinedAtom {
     fn into_atom(self, ctx: &Ctx<'js>) -> Result<Atom<'js>> {
         Ok(unsafe { Atom::from_atom_val_dup(ctx.clone(), self as qjs::JSAtom) })
@@ -62,6 +88,15 @@ impl<'js> IntoAtom<'js> for &StdString {
     }
 }
 
+impl<'js> IntoAtom<'js> for u64 {
+    fn into_atom(self, ctx: &Ctx<'js>) -> Result<Atom<'js>> {
+        match usize::try_from(self) {
+            Ok(x) => Atom::from_index(ctx.clone(), x),
+            Err(_) => Atom::from_str(ctx.clone(), &self.to_string()),
+        }
+    }
+}
+
 macro_rules! into_atom_impls {
 	  ($($from:ident: $($type:ident)*,)*) => {
 		    $(
@@ -80,5 +115,7 @@ into_atom_impls! {
     from_bool: bool,
     from_u32: u8 u16 u32,
     from_i32: i8 i16 i32,
+    from_i64: i64 isize,
+    from_index: usize,
     from_f64: f32 f64,
 }
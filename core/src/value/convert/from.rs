@@ -1,13 +1,14 @@
 use crate::{
-    convert::List, Array, Ctx, Error, FromAtom, FromJs, Object, Result, StdString, String, Type,
-    Value,
+    convert::{list::PrimitiveList, ConversionGuard, List, PrimitiveHint},
+    Array, Ctx, Error, Filter, FromAtom, FromJs, Object, Result, StdString, String, Type, Value,
 };
 use std::{
     cell::{Cell, RefCell},
     collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    ffi::{CString, OsString},
     hash::{BuildHasher, Hash},
-    rc::Rc,
-    sync::{Arc, Mutex, RwLock},
+    path::PathBuf,
+    sync::{Mutex, RwLock},
     time::{Duration, SystemTime},
 };
 
@@ -29,6 +30,51 @@ impl<'js> FromJs<'js> for StdString {
     }
 }
 
+/// Convert from a JS string, keeping lone surrogates on Windows and failing on them elsewhere
+impl<'js> FromJs<'js> for PathBuf {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        OsString::from_js(ctx, value).map(PathBuf::from)
+    }
+}
+
+/// Convert from a JS string, keeping lone surrogates on Windows and failing on them elsewhere
+impl<'js> FromJs<'js> for OsString {
+    #[cfg(windows)]
+    fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        use std::os::windows::ffi::OsStringExt;
+        // Windows strings are UTF-16 which may contain lone surrogates, like JS strings.
+        String::from_value(value).map(|string| OsString::from_wide(&string.to_utf16()))
+    }
+
+    #[cfg(not(windows))]
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        StdString::from_js(ctx, value).map(OsString::from)
+    }
+}
+
+/// Convert from a JS string, failing when it contains a null character
+impl<'js> FromJs<'js> for CString {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        Ok(CString::new(StdString::from_js(ctx, value)?)?)
+    }
+}
+
+impl<'js> FromJs<'js> for PrimitiveHint {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        let type_name = value.type_name();
+        match StdString::from_js(ctx, value)?.as_str() {
+            "number" => Ok(PrimitiveHint::Number),
+            "string" => Ok(PrimitiveHint::String),
+            "default" => Ok(PrimitiveHint::Default),
+            _ => Err(Error::new_from_js_message(
+                type_name,
+                "PrimitiveHint",
+                "Unknown hint",
+            )),
+        }
+    }
+}
+
 /// Convert from JS as any
 impl<'js> FromJs<'js> for () {
     fn from_js(_: &Ctx<'js>, _: Value<'js>) -> Result<Self> {
@@ -141,8 +187,9 @@ macro_rules! from_js_impls {
             where
                 $($type: FromJs<'js>,)*
             {
-                fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
                     let array = Array::from_value(value)?;
+                    let _guard = ConversionGuard::enter(ctx, array.as_value(), "List")?;
 
                     let tuple_len = 0 $(+ from_js_impls!(@one $type))*;
                     let array_len = array.len();
@@ -165,8 +212,9 @@ macro_rules! from_js_impls {
                 T: FromJs<'js> $(+ $($guard)*)*,
                 $($param: $($pguard)*,)*
             {
-                fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
                     let array = Array::from_value(value)?;
+                    let _guard = ConversionGuard::enter(ctx, array.as_value(), stringify!($type))?;
                     array.iter().collect::<Result<_>>()
                 }
             }
@@ -183,9 +231,15 @@ macro_rules! from_js_impls {
                 V: FromJs<'js>,
                 $($param: $($pguard)*,)*
             {
-                fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
                     let object = Object::from_value(value)?;
-                    object.props().collect::<Result<_>>()
+                    let _guard = ConversionGuard::enter(ctx, object.as_value(), stringify!($type))?;
+                    let filter = if ctx.hardened_conversions() {
+                        Filter::default().skip_prototype_keys()
+                    } else {
+                        Filter::default()
+                    };
+                    object.own_props(filter).collect::<Result<_>>()
                 }
             }
         )*
@@ -203,6 +257,8 @@ macro_rules! from_js_impls {
                         _ => Err(Error::new_from_js(type_.as_str(), stringify!($type))),
                     }
                 }
+
+                from_js_impls!(@list);
             }
         )*
     };
@@ -217,11 +273,20 @@ macro_rules! from_js_impls {
                         number_match_range(num, $type::MIN as $base, $type::MAX as $base, stringify!($base), stringify!($type))?;
                         Ok(num as $type)
                     }
+
+                    from_js_impls!(@list);
                 }
             )*
         )*
     };
 
+    // for the lists of primitive types
+    (@list) => {
+        fn vec_from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Vec<Self>> {
+            <Self as PrimitiveList>::vec_from_js(ctx, value)
+        }
+    };
+
     (@one $($t:tt)*) => { 1 };
 
     (@idx A) => { 0 };
@@ -258,8 +323,6 @@ from_js_impls! {
 from_js_impls! {
     ref:
     Box,
-    Rc,
-    Arc,
     Cell,
     RefCell,
     Mutex,
@@ -288,8 +351,6 @@ from_js_impls! {
 
 from_js_impls! {
     list:
-    /// Convert from JS array to Rust vector
-    Vec,
     /// Convert from JS array to Rust vector deque
     VecDeque,
     /// Convert from JS array to Rust linked list
@@ -320,6 +381,48 @@ impl<'js> FromJs<'js> for f32 {
     fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
         f64::from_js(ctx, value).map(|value| value as _)
     }
+
+    from_js_impls!(@list);
+}
+
+/// Convert from JS array or typed array to Rust vector
+impl<'js, T> FromJs<'js> for Vec<T>
+where
+    T: FromJs<'js>,
+{
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        T::vec_from_js(ctx, value)
+    }
+}
+
+/// Convert from JS array or typed array to Rust array of the same length
+impl<'js, T, const N: usize> FromJs<'js> for [T; N]
+where
+    T: FromJs<'js>,
+{
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        // Check arrays before converting the elements to report the length mismatch first.
+        if let Some(array) = value.as_array() {
+            array_match_size(array.len(), N)?;
+        }
+        let vec = Vec::<T>::from_js(ctx, value)?;
+        array_match_size(vec.len(), N)?;
+        Ok(vec
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("the length was checked")))
+    }
+}
+
+fn array_match_size(actual: usize, expected: usize) -> Result<()> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::new_from_js_message(
+            "array",
+            "fixed size array",
+            format!("Expected {expected} values, got {actual}"),
+        ))
+    }
 }
 
 fn date_to_millis<'js>(ctx: &Ctx<'js>, value: Value<'js>) -> Result<i64> {
@@ -386,6 +489,59 @@ chrono_from_js_impls! {
 
 #[cfg(test)]
 mod test {
+    #[test]
+    fn js_to_paths() {
+        use crate::{test_with, Error};
+        use std::{
+            ffi::{CString, OsString},
+            path::PathBuf,
+        };
+
+        test_with(|ctx| {
+            let res: PathBuf = ctx.eval("'dir/file.js'").unwrap();
+            assert_eq!(res, PathBuf::from("dir/file.js"));
+            let res: OsString = ctx.eval("'name'").unwrap();
+            assert_eq!(res, "name");
+            let res: CString = ctx.eval("'name'").unwrap();
+            assert_eq!(res.as_bytes(), b"name");
+            assert!(matches!(
+                ctx.eval::<CString, _>("'a\\0b'"),
+                Err(Error::InvalidString(_))
+            ));
+            #[cfg(not(windows))]
+            assert!(matches!(
+                ctx.eval::<PathBuf, _>("'\\ud800.js'"),
+                Err(Error::Utf8(_))
+            ));
+            #[cfg(windows)]
+            {
+                use std::os::windows::ffi::OsStrExt;
+                let res: PathBuf = ctx.eval("'\\ud800.js'").unwrap();
+                let units = res.as_os_str().encode_wide().collect::<Vec<_>>();
+                assert_eq!(units, [0xD800, 0x2E, 0x6A, 0x73]);
+            }
+        });
+    }
+
+    #[test]
+    fn js_to_fixed_size_array() {
+        use crate::{test_with, Error};
+
+        test_with(|ctx| {
+            let res: [i32; 3] = ctx.eval("[1, 2, 3]").unwrap();
+            assert_eq!(res, [1, 2, 3]);
+            let res: [[f64; 2]; 2] = ctx.eval("[[0.5, 1], [2, 3]]").unwrap();
+            assert_eq!(res, [[0.5, 1.0], [2.0, 3.0]]);
+            let res: [String; 0] = ctx.eval("[]").unwrap();
+            assert_eq!(res.len(), 0);
+            let err = ctx.eval::<[i32; 3], _>("[1, 'a']").unwrap_err();
+            assert!(matches!(err, Error::FromJs { .. }));
+            assert!(err.to_string().contains("Expected 3 values, got 2"));
+            let err = ctx.eval::<[u8; 2], _>("new Uint8Array(3)").unwrap_err();
+            assert!(err.to_string().contains("Expected 2 values, got 3"));
+        });
+    }
+
     #[test]
     fn js_to_system_time() {
         use crate::{Context, Runtime};
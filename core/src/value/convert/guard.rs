@@ -0,0 +1,116 @@
+use std::marker::PhantomData;
+
+use crate::{Ctx, Error, Result, Value};
+
+/// A guard which tracks a JavaScript object while it is converted to a Rust value.
+///
+/// Converting a cyclic object into a recursive Rust type would never end, and converting a very
+/// deeply nested object would overflow the stack. The [`FromJs`](crate::FromJs) implementations
+/// of the Rust collections enter a guard for the array or object they convert, which fails with
+/// [`Error::Cycle`] if the object is already being converted further up, or with
+/// [`Error::RecursionLimit`] if more objects than the limit set with
+/// [`Runtime::set_max_conversion_depth`](crate::Runtime::set_max_conversion_depth) are nested.
+///
+/// Only objects which contain themselves are rejected, an object referenced multiple times
+/// without a cycle is converted every time. Implementations of `FromJs` for recursive types
+/// should enter a guard like the collections do.
+///
+/// ```
+/// # use rquickjs::{Context, Error, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let res = ctx.eval::<Vec<Vec<Vec<i32>>>, _>("const a = []; a.push(a); a");
+///     assert!(matches!(res, Err(Error::Cycle { .. })));
+/// });
+/// ```
+#[must_use = "the object is only tracked while the guard is alive"]
+pub struct ConversionGuard<'js> {
+    ctx: Ctx<'js>,
+    tracked: bool,
+    // The path must be popped in order, so the guard stays on its thread.
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<'js> ConversionGuard<'js> {
+    /// Start converting a value into the Rust type named `to`.
+    ///
+    /// Values which aren't objects can't contain themselves and aren't tracked.
+    pub fn enter(ctx: &Ctx<'js>, value: &Value<'js>, to: &'static str) -> Result<Self> {
+        let tracked = value.is_object();
+        if tracked {
+            let ptr = unsafe { value.get_ptr() };
+            let opaque = unsafe { &mut *ctx.get_opaque() };
+            if opaque.conversion_path.contains(&ptr) {
+                return Err(Error::Cycle { to });
+            }
+            if opaque.conversion_path.len() >= opaque.max_conversion_depth {
+                return Err(Error::RecursionLimit {
+                    to,
+                    limit: opaque.max_conversion_depth,
+                });
+            }
+            opaque.conversion_path.push(ptr);
+        }
+        Ok(ConversionGuard {
+            ctx: ctx.clone(),
+            tracked,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl Drop for ConversionGuard<'_> {
+    fn drop(&mut self) {
+        if self.tracked {
+            unsafe { (*self.ctx.get_opaque()).conversion_path.pop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{Context, Error, Function, Runtime, Value};
+
+    #[test]
+    fn cycles() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let res = ctx.eval::<HashMap<String, Vec<HashMap<String, Vec<Value>>>>, _>(
+                "const o = { a: [] }; o.a.push(o); o",
+            );
+            assert!(matches!(res, Err(Error::Cycle { to: "HashMap" })));
+
+            // Shared objects without a cycle are fine.
+            let res: Vec<Vec<i32>> = ctx.eval("const x = [1]; [x, x, [x[0]]]").unwrap();
+            assert_eq!(res, [[1], [1], [1]]);
+
+            // The error is thrown to JavaScript as a type error.
+            let f = Function::new(ctx.clone(), |_: Vec<Vec<i32>>| ()).unwrap();
+            ctx.globals().set("f", f).unwrap();
+            let res: bool = ctx
+                .eval("const c = []; c.push(c); try { f(c); false } catch (e) { e instanceof TypeError }")
+                .unwrap();
+            assert!(res);
+        });
+    }
+
+    #[test]
+    fn depth_limit() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        rt.set_max_conversion_depth(3);
+        ctx.with(|ctx| {
+            let res: Vec<Vec<Vec<i32>>> = ctx.eval("[[[1]]]").unwrap();
+            assert_eq!(res, [[[1]]]);
+            let res = ctx.eval::<Vec<Vec<Vec<Vec<i32>>>>, _>("[[[[1]]]]");
+            assert!(matches!(res, Err(Error::RecursionLimit { limit: 3, .. })));
+            // The path is unwound after an error.
+            let res: Vec<Vec<Vec<i32>>> = ctx.eval("[[[2]]]").unwrap();
+            assert_eq!(res, [[[2]]]);
+        });
+    }
+}
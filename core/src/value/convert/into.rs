@@ -1,11 +1,13 @@
 use crate::{
-    convert::{IteratorJs, List},
+    convert::{list::PrimitiveList, IteratorJs, List},
     value::Constructor,
     Array, Ctx, Error, IntoAtom, IntoJs, Object, Result, StdResult, StdString, String, Value,
 };
 use std::{
     cell::{Cell, RefCell},
     collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    ffi::{CStr, CString, OsStr, OsString},
+    path::{Path, PathBuf},
     sync::{Mutex, RwLock},
     time::SystemTime,
 };
@@ -46,14 +48,111 @@ impl<'js> IntoJs<'js> for &str {
     }
 }
 
+#[cfg(windows)]
+fn os_str_into_js<'js>(ctx: &Ctx<'js>, _from: &'static str, s: &OsStr) -> Result<Value<'js>> {
+    use std::os::windows::ffi::OsStrExt;
+    // Windows strings are UTF-16 which may contain lone surrogates, like JS strings.
+    let units = s.encode_wide().collect::<Vec<_>>();
+    String::from_utf16(ctx.clone(), &units).map(|String(value)| value)
+}
+
+#[cfg(not(windows))]
+fn os_str_into_js<'js>(ctx: &Ctx<'js>, from: &'static str, s: &OsStr) -> Result<Value<'js>> {
+    s.to_str()
+        .ok_or_else(|| Error::new_into_js_message(from, "string", format!("{s:?} is not UTF-8")))?
+        .into_js(ctx)
+}
+
+macro_rules! os_str_impls {
+    ($($type:ty => $name:literal: $this:ident => $os_str:expr,)*) => {
+        $(
+            /// Convert to a JS string, keeping lone surrogates on Windows and failing when it
+            /// isn't valid UTF-8 elsewhere
+            impl<'js> IntoJs<'js> for $type {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    let $this = self;
+                    os_str_into_js(ctx, $name, $os_str)
+                }
+            }
+        )*
+    };
+}
+
+os_str_impls! {
+    &OsStr => "OsStr": this => this,
+    OsString => "OsString": this => &this,
+    &OsString => "OsString": this => this,
+    &Path => "Path": this => this.as_os_str(),
+    PathBuf => "PathBuf": this => this.as_os_str(),
+    &PathBuf => "PathBuf": this => this.as_os_str(),
+}
+
+/// Convert to a JS string, failing when it isn't valid UTF-8
+impl<'js> IntoJs<'js> for &CStr {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.to_str()
+            .map_err(|error| Error::new_into_js_message("CStr", "string", error.to_string()))?
+            .into_js(ctx)
+    }
+}
+
+impl<'js> IntoJs<'js> for CString {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.as_c_str().into_js(ctx)
+    }
+}
+
+impl<'js> IntoJs<'js> for &CString {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.as_c_str().into_js(ctx)
+    }
+}
+
+/// Convert from Rust slice to JS array, or a typed array for the element types of typed arrays
 impl<'js, T> IntoJs<'js> for &[T]
 where
     for<'a> &'a T: IntoJs<'js>,
 {
     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
-        self.iter()
-            .collect_js(ctx)
-            .map(|Array(value)| value.into_value())
+        <&T>::list_into_js(self, ctx)
+    }
+}
+
+/// Convert from Rust vector to JS array, or a typed array for the element types of typed arrays
+impl<'js, T> IntoJs<'js> for Vec<T>
+where
+    T: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        T::list_into_js(self, ctx)
+    }
+}
+
+impl<'js, T> IntoJs<'js> for &Vec<T>
+where
+    for<'a> &'a T: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.as_slice().into_js(ctx)
+    }
+}
+
+/// Convert from Rust array to JS array, or a typed array for the element types of typed arrays
+impl<'js, T, const N: usize> IntoJs<'js> for [T; N]
+where
+    T: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        T::list_into_js(self, ctx)
+    }
+}
+
+impl<'js, T, const N: usize> IntoJs<'js> for &[T; N]
+where
+    for<'a> &'a T: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        self.as_slice().into_js(ctx)
     }
 }
 
@@ -325,12 +424,16 @@ macro_rules! into_js_impls {
                     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
                         Ok(Value::$new(ctx.clone(), self as _))
                     }
+
+                    into_js_impls!(@list $type);
                 }
 
                 impl<'js> IntoJs<'js> for &$type {
                     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
                         (*self).into_js(ctx)
                     }
+
+                    into_js_impls!(@list_ref $type);
                 }
             )*
         )*
@@ -350,17 +453,40 @@ macro_rules! into_js_impls {
                             (self as $alt2).into_js(ctx)
                         }
                     }
+
+                    into_js_impls!(@list $type);
                 }
 
                 impl<'js> IntoJs<'js> for &$type {
                     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
                         (*self).into_js(ctx)
                     }
+
+                    into_js_impls!(@list_ref $type);
                 }
             )*
         )*
     };
 
+    // for the lists of primitive types
+    (@list $type:ty) => {
+        fn list_into_js<I>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            <$type as PrimitiveList>::list_into_js(list, ctx)
+        }
+    };
+
+    (@list_ref $type:ty) => {
+        fn list_into_js<I>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            <$type as PrimitiveList>::list_into_js(list.into_iter().copied(), ctx)
+        }
+    };
+
     (@idx A) => { 0 };
     (@idx B) => { 1 };
     (@idx C) => { 2 };
@@ -407,8 +533,6 @@ into_js_impls! {
 
 into_js_impls! {
     list:
-    /// Convert from Rust vector to JS array
-    Vec,
     /// Convert from Rust vector deque to JS array
     VecDeque,
     /// Convert from Rust linked list to JS array
@@ -499,6 +623,61 @@ impl<'js, Tz: chrono::TimeZone> IntoJs<'js> for chrono::DateTime<Tz> {
 
 #[cfg(test)]
 mod test {
+    #[test]
+    fn fixed_size_array_to_js() {
+        use crate::{test_with, StdString};
+
+        test_with(|ctx| {
+            let globs = ctx.globals();
+            globs.set("point", [1.5, 2.0]).unwrap();
+            globs.set("names", ["a", "b", "c"]).unwrap();
+            let sizes = vec![[1u32, 2], [3, 4]];
+            globs.set("sizes", &sizes).unwrap();
+            let res: StdString = ctx
+                .eval("point.length + ' ' + point[0] + ' ' + names.join('') + ' ' + sizes[1][1]")
+                .unwrap();
+            assert_eq!(res, format!("2 1.5 abc {}", sizes[1][1]));
+        });
+    }
+
+    #[test]
+    fn paths_to_js() {
+        use crate::{test_with, Error, IntoJs, StdString};
+        use std::{ffi::CString, path::PathBuf};
+
+        test_with(|ctx| {
+            let globs = ctx.globals();
+            globs.set("path", PathBuf::from("dir/file.js")).unwrap();
+            globs.set("name", CString::new("name").unwrap()).unwrap();
+            let res: StdString = ctx.eval("path + ' ' + name").unwrap();
+            assert_eq!(res, "dir/file.js name");
+
+            let invalid = CString::new(vec![0xff]).unwrap();
+            assert!(matches!(
+                invalid.into_js(&ctx),
+                Err(Error::IntoJs { from: "CStr", .. })
+            ));
+            #[cfg(unix)]
+            {
+                use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+                let invalid = OsStr::from_bytes(b"file\xff.js");
+                assert!(matches!(
+                    invalid.into_js(&ctx),
+                    Err(Error::IntoJs { from: "OsStr", .. })
+                ));
+            }
+            #[cfg(windows)]
+            {
+                use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+                let lone = OsString::from_wide(&[0x61, 0xD800]);
+                globs.set("lone", &lone).unwrap();
+                let res: Vec<u16> = ctx.eval("[...lone].map((c) => c.charCodeAt(0))").unwrap();
+                assert_eq!(res, [0x61, 0xD800]);
+                assert_eq!(ctx.eval::<OsString, _>("lone").unwrap(), lone);
+            }
+        });
+    }
+
     #[test]
     fn system_time_to_js() {
         use crate::{Context, IntoJs, Runtime};
@@ -530,7 +709,7 @@ mod test {
             let globs = ctx.globals();
             globs.set("ts", ts.into_js(&ctx).unwrap()).unwrap();
             let res: i64 = ctx.eval("ts.getTime()").unwrap();
-            assert_eq!(-(millis as i64), res as _);
+            assert_eq!(-(millis as i64), res);
         });
     }
 
@@ -0,0 +1,174 @@
+use serde_json::{Map, Number, Value as JsonValue};
+
+use crate::{
+    convert::ConversionGuard, Array, Ctx, Error, FromJs, IntoJs, Object, Result, StdString, Type,
+    Value,
+};
+
+/// Convert a JSON value into JS without serializing it to a string first.
+///
+/// Integers in the range of `i32` become integer values, all other numbers become floats, which
+/// loses precision for integers above `2^53` like `JSON.parse` does.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde_json")))]
+impl<'js> IntoJs<'js> for JsonValue {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        Ok(match self {
+            JsonValue::Null => Value::new_null(ctx.clone()),
+            JsonValue::Bool(x) => Value::new_bool(ctx.clone(), x),
+            JsonValue::Number(x) => x.into_js(ctx)?,
+            JsonValue::String(x) => x.into_js(ctx)?,
+            JsonValue::Array(x) => {
+                let array = Array::new(ctx.clone())?;
+                for (idx, item) in x.into_iter().enumerate() {
+                    array.set(idx, item)?;
+                }
+                array.into_value()
+            }
+            JsonValue::Object(x) => x.into_js(ctx)?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde_json")))]
+impl<'js> IntoJs<'js> for Number {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        if let Some(x) = self.as_i64().and_then(|x| i32::try_from(x).ok()) {
+            return Ok(Value::new_int(ctx.clone(), x));
+        }
+        // Every number is representable as a float unless serde_json uses arbitrary precision.
+        let x = self.as_f64().unwrap_or(f64::NAN);
+        Ok(Value::new_float(ctx.clone(), x))
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde_json")))]
+impl<'js> IntoJs<'js> for Map<StdString, JsonValue> {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let object = Object::new(ctx.clone())?;
+        for (key, value) in self {
+            object.set(key, value)?;
+        }
+        Ok(object.into_value())
+    }
+}
+
+/// Convert a JS value into JSON without serializing it to a string first.
+///
+/// Objects are converted to maps of their own enumerable string keyed properties and
+/// `undefined` becomes `null`. Floats without a fractional part which fit in an `i64` become
+/// integers, and `NaN` and infinities become `null`, matching `JSON.stringify`. Functions,
+/// symbols and big integers outside of the `i64` range can't be converted.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde_json")))]
+impl<'js> FromJs<'js> for JsonValue {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        value_to_json(ctx, value)
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "serde_json")))]
+impl<'js> FromJs<'js> for Map<StdString, JsonValue> {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        let type_ = value.type_of();
+        match value.into_object() {
+            Some(object) if type_ == Type::Object => object_to_json(ctx, &object),
+            _ => Err(Error::new_from_js(type_.as_str(), "serde_json::Map")),
+        }
+    }
+}
+
+fn value_to_json<'js>(ctx: &Ctx<'js>, value: Value<'js>) -> Result<JsonValue> {
+    let type_ = value.type_of();
+    Ok(match type_ {
+        Type::Uninitialized | Type::Undefined | Type::Null => JsonValue::Null,
+        Type::Bool => JsonValue::Bool(value.as_bool().unwrap()),
+        Type::Int => JsonValue::Number(value.as_int().unwrap().into()),
+        Type::Float => float_to_json(value.as_float().unwrap()),
+        Type::String => JsonValue::String(value.into_string().unwrap().to_string()?),
+        Type::BigInt => {
+            let x = value.into_big_int().unwrap().to_i64().map_err(|_| {
+                Error::new_from_js_message("bigint", "serde_json::Value", "Out of range")
+            })?;
+            JsonValue::Number(x.into())
+        }
+        Type::Array => {
+            let array = value.into_array().unwrap();
+            let _guard = ConversionGuard::enter(ctx, array.as_value(), "serde_json::Value")?;
+            let items = array
+                .iter::<Value>()
+                .map(|item| value_to_json(ctx, item?))
+                .collect::<Result<_>>()?;
+            JsonValue::Array(items)
+        }
+        Type::Object => JsonValue::Object(object_to_json(ctx, &value.into_object().unwrap())?),
+        _ => return Err(Error::new_from_js(type_.as_str(), "serde_json::Value")),
+    })
+}
+
+fn object_to_json<'js>(ctx: &Ctx<'js>, object: &Object<'js>) -> Result<Map<StdString, JsonValue>> {
+    let _guard = ConversionGuard::enter(ctx, object.as_value(), "serde_json::Value")?;
+    object
+        .props::<StdString, Value>()
+        .map(|prop| {
+            let (key, value) = prop?;
+            Ok((key, value_to_json(ctx, value)?))
+        })
+        .collect()
+}
+
+fn float_to_json(x: f64) -> JsonValue {
+    // `i64::MAX as f64` rounds up to 2^63, which is out of range.
+    if x.fract() == 0.0 && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+        return JsonValue::Number((x as i64).into());
+    }
+    Number::from_f64(x).map_or(JsonValue::Null, JsonValue::Number)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_with;
+    use serde_json::json;
+
+    #[test]
+    fn round_trip() {
+        test_with(|ctx| {
+            let value = json!({
+                "null": null,
+                "bool": true,
+                "int": -5,
+                "large": 9_007_199_254_740_000_i64,
+                "float": 1.5,
+                "string": "ü",
+                "array": [1, [], {}],
+                "nested": { "a": { "b": "c" } },
+            });
+            ctx.globals().set("value", value.clone()).unwrap();
+            let res: bool = ctx
+                .eval("typeof value.large === 'number' && value.nested.a.b === 'c'")
+                .unwrap();
+            assert!(res);
+            let res: JsonValue = ctx.globals().get("value").unwrap();
+            assert_eq!(res, value);
+        })
+    }
+
+    #[test]
+    fn from_js() {
+        test_with(|ctx| {
+            let res: JsonValue = ctx
+                .eval("({ a: undefined, b: 2 ** 40, c: 1 / 0, d: 10n, e: 0.5, [Symbol()]: 1 })")
+                .unwrap();
+            assert_eq!(
+                res,
+                json!({ "a": null, "b": 1_099_511_627_776_i64, "c": null, "d": 10, "e": 0.5 })
+            );
+
+            let res = ctx.eval::<JsonValue, _>("[() => 1]");
+            assert!(matches!(res, Err(Error::FromJs { .. })));
+            let res = ctx.eval::<JsonValue, _>("const x = {}; x.x = x; x");
+            assert!(matches!(res, Err(Error::Cycle { .. })));
+            let res = ctx.eval::<Map<StdString, JsonValue>, _>("[]");
+            assert!(matches!(res, Err(Error::FromJs { .. })));
+        })
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    convert::{ConversionGuard, IteratorJs},
+    Array, Ctx, FromJs, IntoJs, Result, Value,
+};
+#[cfg(feature = "array-buffer")]
+use crate::{value::typed_array::TypedArrayItem, TypedArray};
+#[cfg(feature = "array-buffer")]
+use std::{mem, slice};
+
+pub(crate) fn list_into_array<'js, I>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>>
+where
+    I: IntoIterator,
+    I::Item: IntoJs<'js>,
+{
+    list.into_iter()
+        .collect_js(ctx)
+        .map(|Array(value)| value.into_value())
+}
+
+pub(crate) fn array_into_vec<'js, T: FromJs<'js>>(
+    ctx: &Ctx<'js>,
+    value: Value<'js>,
+) -> Result<Vec<T>> {
+    let array = Array::from_value(value)?;
+    let _guard = ConversionGuard::enter(ctx, array.as_value(), "Vec")?;
+    array.iter().collect()
+}
+
+/// Convert a list of numbers to a typed array with a single copy.
+///
+/// Contexts without the typed array intrinsic get an array instead.
+#[cfg(all(feature = "array-buffer", not(feature = "vec-as-array")))]
+fn list_into_typed_array<'js, T, I>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>>
+where
+    T: TypedArrayItem + IntoJs<'js>,
+    I: IntoIterator<Item = T>,
+{
+    if !TypedArray::<T>::is_available(ctx) {
+        return list_into_array(list, ctx);
+    }
+    let vec: Vec<T> = list.into_iter().collect();
+    TypedArray::new(ctx.clone(), vec).map(TypedArray::into_value)
+}
+
+/// Convert an array or a typed array to a vector of numbers.
+///
+/// Typed arrays of the same type are copied at once, others are converted element by element.
+#[cfg(feature = "array-buffer")]
+fn typed_array_into_vec<'js, T>(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Vec<T>>
+where
+    T: TypedArrayItem + FromJs<'js>,
+{
+    if let Some(object) = value.as_object().filter(|_| !value.is_array()) {
+        if let Some((size, len, ptr)) = TypedArray::<T>::get_raw_bytes(&value) {
+            let count = len / size;
+            if size == mem::size_of::<T>() && object.is_typed_array::<T>() {
+                let items = unsafe { slice::from_raw_parts(ptr.as_ptr().cast::<T>(), count) };
+                return Ok(items.to_vec());
+            }
+            return (0..count).map(|index| object.get(index)).collect();
+        }
+        // `get_raw_bytes` leaves an exception behind for other objects
+        ctx.catch();
+    }
+    array_into_vec(ctx, value)
+}
+
+/// The conversions of vectors and slices of primitive types, which use typed arrays for the
+/// element types of typed arrays.
+pub(crate) trait PrimitiveList<'js>: IntoJs<'js> + FromJs<'js> {
+    fn list_into_js<I: IntoIterator<Item = Self>>(list: I, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        list_into_array(list, ctx)
+    }
+
+    fn vec_from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Vec<Self>> {
+        array_into_vec(ctx, value)
+    }
+}
+
+macro_rules! primitive_lists {
+    (typed: $($typed:ty)*; array: $($array:ty)*;) => {
+        $(
+            impl<'js> PrimitiveList<'js> for $typed {
+                #[cfg(all(feature = "array-buffer", not(feature = "vec-as-array")))]
+                fn list_into_js<I: IntoIterator<Item = Self>>(
+                    list: I,
+                    ctx: &Ctx<'js>,
+                ) -> Result<Value<'js>> {
+                    list_into_typed_array(list, ctx)
+                }
+
+                #[cfg(feature = "array-buffer")]
+                fn vec_from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Vec<Self>> {
+                    typed_array_into_vec(ctx, value)
+                }
+            }
+        )*
+        $(impl<'js> PrimitiveList<'js> for $array {})*
+    };
+}
+
+// 64 bit integers stay arrays as their typed arrays hold big integers instead of numbers.
+primitive_lists! {
+    typed: i8 u8 i16 u16 i32 u32 f32 f64;
+    array: bool i64 u64 usize isize;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn vec_conversions() {
+        test_with(|ctx| {
+            ctx.globals().set("floats", vec![0.5f64, 1.5]).unwrap();
+            ctx.globals().set("bytes", &[1u8, 2, 3][..]).unwrap();
+            ctx.globals().set("flags", vec![true, false]).unwrap();
+            let types: StdString = ctx
+                .eval("[floats, bytes, flags].map((x) => x.constructor.name).join()")
+                .unwrap();
+            #[cfg(all(feature = "array-buffer", not(feature = "vec-as-array")))]
+            assert_eq!(types, "Float64Array,Uint8Array,Array");
+            #[cfg(not(all(feature = "array-buffer", not(feature = "vec-as-array"))))]
+            assert_eq!(types, "Array,Array,Array");
+
+            let res: Vec<u8> = ctx.eval("[4, 5]").unwrap();
+            assert_eq!(res, [4, 5]);
+            assert!(ctx.eval::<Vec<u8>, _>("({ length: 1 })").is_err());
+
+            #[cfg(feature = "array-buffer")]
+            {
+                let res: Vec<f64> = ctx.eval("new Float64Array([1, 2.5])").unwrap();
+                assert_eq!(res, [1.0, 2.5]);
+                let res: Vec<i32> = ctx.eval("new Uint8Array([1, 255])").unwrap();
+                assert_eq!(res, [1, 255]);
+                assert!(ctx.eval::<Vec<u8>, _>("new Float32Array([0.5])").is_err());
+            }
+        })
+    }
+}
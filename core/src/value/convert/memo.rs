@@ -0,0 +1,389 @@
+use std::{any::type_name, cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+
+use crate::{qjs, Ctx, Error, FromJs, IntoJs, Result, Value};
+
+/// The wrapper for values to convert with a conversion cache.
+///
+/// Converting a graph of shared [`Rc`] or [`Arc`] nodes normally converts a node again every
+/// time it is referenced, duplicating it in the result. While a `Memoized` value is converted,
+/// every shared node is converted once and all references to it refer to the same JavaScript
+/// value, so the JavaScript graph has the same shape as the Rust one. Likewise a JavaScript
+/// object referenced multiple times is converted into a single `Rc` or `Arc` by
+/// [`FromJs`].
+///
+/// The cache only lives while the value is converted, nothing is cached between calls.
+///
+/// Cycles can't be represented by converting values one at a time, so a cycle makes the
+/// conversion fail with an error instead of recursing endlessly.
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use rquickjs::{convert::Memoized, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let shared = Rc::new(vec![1, 2, 3]);
+///     let graph = vec![shared.clone(), shared];
+///     ctx.globals().set("graph", Memoized(graph)).unwrap();
+///     assert!(ctx.eval::<bool, _>("graph[0] === graph[1]").unwrap());
+///
+///     let Memoized(graph): Memoized<Vec<Rc<Vec<i32>>>> =
+///         ctx.eval("const x = [1, 2]; [x, x]").unwrap();
+///     assert!(Rc::ptr_eq(&graph[0], &graph[1]));
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Memoized<T>(pub T);
+
+impl<'js, T: IntoJs<'js>> IntoJs<'js> for Memoized<T> {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        with_cache(ctx, || self.0.into_js(ctx))
+    }
+}
+
+impl<'js, T: FromJs<'js>> FromJs<'js> for Memoized<T> {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        with_cache(ctx, || T::from_js(ctx, value)).map(Memoized)
+    }
+}
+
+/// A shared pointer with its type erased, kept in the cache so the address it points to isn't
+/// reused by another node while the cache lives.
+struct Erased {
+    ptr: *const (),
+    drop: unsafe fn(*const ()),
+}
+
+impl Drop for Erased {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.ptr) }
+    }
+}
+
+/// The conversion cache of a context, the key of an entry is the address of the shared value
+/// and the name of its type.
+struct Cache {
+    ctx: *mut qjs::JSContext,
+    /// The JS values of converted Rust nodes, `None` while a node is converted.
+    into: HashMap<(usize, &'static str), (Erased, Option<qjs::JSValue>)>,
+    /// The Rust nodes of converted JS objects, `None` while an object is converted. The object
+    /// is referenced for the same reason as the erased pointer.
+    from: HashMap<(usize, &'static str), (qjs::JSValue, Option<Erased>)>,
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        for (_, (_, value)) in self.into.drain() {
+            if let Some(value) = value {
+                unsafe { qjs::JS_FreeValue(self.ctx, value) };
+            }
+        }
+        for (_, (value, _)) in self.from.drain() {
+            unsafe { qjs::JS_FreeValue(self.ctx, value) };
+        }
+    }
+}
+
+thread_local! {
+    static CACHES: RefCell<Vec<Cache>> = RefCell::new(Vec::new());
+}
+
+/// Run a conversion with a cache, reusing the active cache of the context if there is one.
+fn with_cache<'js, R>(ctx: &Ctx<'js>, f: impl FnOnce() -> Result<R>) -> Result<R> {
+    if is_cached(ctx) {
+        return f();
+    }
+
+    struct Guard;
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            // Free the cache outside of the borrow, dropping nodes may convert values.
+            let cache = CACHES.with(|caches| caches.borrow_mut().pop());
+            drop(cache);
+        }
+    }
+
+    CACHES.with(|caches| {
+        caches.borrow_mut().push(Cache {
+            ctx: ctx.as_ptr(),
+            into: HashMap::new(),
+            from: HashMap::new(),
+        })
+    });
+    let _guard = Guard;
+    f()
+}
+
+fn is_cached(ctx: &Ctx<'_>) -> bool {
+    CACHES.with(|caches| {
+        caches
+            .borrow()
+            .last()
+            .map_or(false, |cache| cache.ctx == ctx.as_ptr())
+    })
+}
+
+/// Access the active cache of the context, returns `None` if there is none.
+fn cache<R>(ctx: &Ctx<'_>, f: impl FnOnce(&mut Cache) -> R) -> Option<R> {
+    CACHES.with(|caches| {
+        caches
+            .borrow_mut()
+            .last_mut()
+            .filter(|cache| cache.ctx == ctx.as_ptr())
+            .map(f)
+    })
+}
+
+/// Convert a shared node, or return the value it was converted to before.
+fn shared_into_js<'js>(
+    ctx: &Ctx<'js>,
+    ptr: usize,
+    name: &'static str,
+    erased: impl FnOnce() -> Erased,
+    convert: impl FnOnce() -> Result<Value<'js>>,
+) -> Result<Value<'js>> {
+    let key = (ptr, name);
+    let cached = cache(ctx, |cache| match cache.into.get(&key) {
+        Some((_, Some(value))) => Ok(Some(unsafe { qjs::JS_DupValue(*value) })),
+        Some((_, None)) => Err(Error::new_into_js_message(
+            name,
+            "value",
+            "Cyclic reference",
+        )),
+        None => {
+            cache.into.insert(key, (erased(), None));
+            Ok(None)
+        }
+    });
+    match cached {
+        None => convert(),
+        Some(Err(error)) => Err(error),
+        Some(Ok(Some(value))) => Ok(unsafe { Value::from_js_value(ctx.clone(), value) }),
+        Some(Ok(None)) => {
+            let res = convert();
+            cache(ctx, |cache| match &res {
+                Ok(value) => {
+                    let value = unsafe { qjs::JS_DupValue(value.as_js_value()) };
+                    if let Some(entry) = cache.into.get_mut(&key) {
+                        entry.1 = Some(value);
+                    }
+                }
+                // A failed conversion may be retried as another type, for example by `Either`.
+                Err(_) => {
+                    cache.into.remove(&key);
+                }
+            });
+            res
+        }
+    }
+}
+
+/// Convert a JS object into a shared node, or return the node it was converted to before.
+fn shared_from_js<'js, P>(
+    ctx: &Ctx<'js>,
+    value: Value<'js>,
+    name: &'static str,
+    erase: impl FnOnce(&P) -> Erased,
+    restore: impl FnOnce(&Erased) -> P,
+    convert: impl FnOnce(Value<'js>) -> Result<P>,
+) -> Result<P> {
+    if !value.is_object() {
+        return convert(value);
+    }
+    let key = (unsafe { value.get_ptr() } as usize, name);
+    let cached = cache(ctx, |cache| match cache.from.get(&key) {
+        Some((_, Some(erased))) => Ok(Some(restore(erased))),
+        Some((_, None)) => Err(Error::new_from_js_message(
+            "object",
+            name,
+            "Cyclic reference",
+        )),
+        None => {
+            let object = unsafe { qjs::JS_DupValue(value.as_js_value()) };
+            cache.from.insert(key, (object, None));
+            Ok(None)
+        }
+    });
+    match cached {
+        None => convert(value),
+        Some(Err(error)) => Err(error),
+        Some(Ok(Some(node))) => Ok(node),
+        Some(Ok(None)) => {
+            let res = convert(value);
+            let removed = cache(ctx, |cache| match &res {
+                Ok(node) => {
+                    if let Some(entry) = cache.from.get_mut(&key) {
+                        entry.1 = Some(erase(node));
+                    }
+                    None
+                }
+                Err(_) => cache.from.remove(&key),
+            });
+            if let Some(Some((object, _))) = removed {
+                unsafe { qjs::JS_FreeValue(ctx.as_ptr(), object) };
+            }
+            res
+        }
+    }
+}
+
+macro_rules! shared_impls {
+    ($($type:ident,)*) => {
+        $(
+            impl<'js, T> IntoJs<'js> for &$type<T>
+            where
+                for<'r> &'r T: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    shared_into_js(
+                        ctx,
+                        $type::as_ptr(self) as *const () as usize,
+                        type_name::<$type<T>>(),
+                        || {
+                            Erased {
+                                ptr: $type::into_raw($type::clone(self)) as *const (),
+                                drop: |ptr| unsafe { $type::decrement_strong_count(ptr as *const T) },
+                            }
+                        },
+                        || self.as_ref().into_js(ctx),
+                    )
+                }
+            }
+
+            impl<'js, T> IntoJs<'js> for $type<T>
+            where
+                for<'r> &'r T: IntoJs<'js>,
+            {
+                fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+                    (&self).into_js(ctx)
+                }
+            }
+
+            impl<'js, T> FromJs<'js> for $type<T>
+            where
+                T: FromJs<'js>,
+            {
+                fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                    shared_from_js(
+                        ctx,
+                        value,
+                        type_name::<$type<T>>(),
+                        |node: &$type<T>| {
+                            Erased {
+                                ptr: $type::into_raw($type::clone(node)) as *const (),
+                                drop: |ptr| unsafe { $type::decrement_strong_count(ptr as *const T) },
+                            }
+                        },
+                        |erased| unsafe {
+                            $type::increment_strong_count(erased.ptr as *const T);
+                            $type::from_raw(erased.ptr as *const T)
+                        },
+                        |value| T::from_js(ctx, value).map($type::new),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+shared_impls! {
+    Rc,
+    Arc,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_with, Array, Object};
+
+    struct Node {
+        name: String,
+        children: RefCell<Vec<Rc<Node>>>,
+    }
+
+    impl<'js> IntoJs<'js> for &Node {
+        fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+            let object = Object::new(ctx.clone())?;
+            object.set("name", self.name.as_str())?;
+            object.set("children", &*self.children.borrow())?;
+            Ok(object.into_value())
+        }
+    }
+
+    fn node(name: &str, children: Vec<Rc<Node>>) -> Rc<Node> {
+        Rc::new(Node {
+            name: name.to_string(),
+            children: RefCell::new(children),
+        })
+    }
+
+    #[test]
+    fn into_js_shared() {
+        test_with(|ctx| {
+            // Without a cache this graph converts into 2^16 leaves.
+            let mut graph = node("leaf", vec![]);
+            for _ in 0..16 {
+                graph = node("branch", vec![graph.clone(), graph]);
+            }
+            ctx.globals().set("graph", Memoized(&graph)).unwrap();
+            let res: bool = ctx
+                .eval("graph.children[0] === graph.children[1] && graph.children[0].children[1].name === 'branch'")
+                .unwrap();
+            assert!(res);
+
+            let array = Array::new(ctx.clone()).unwrap();
+            let leaf = node("leaf", vec![]);
+            array.set(0, leaf.clone()).unwrap();
+            array.set(1, leaf).unwrap();
+            let first: Value = array.get(0).unwrap();
+            let second: Value = array.get(1).unwrap();
+            assert_ne!(first, second);
+        })
+    }
+
+    #[test]
+    fn into_js_cycle() {
+        test_with(|ctx| {
+            let root = node("root", vec![]);
+            root.children.borrow_mut().push(root.clone());
+            let res = Memoized(&root).into_js(&ctx);
+            assert!(matches!(res, Err(Error::IntoJs { .. })));
+            // Break the cycle so the test doesn't leak.
+            root.children.borrow_mut().clear();
+        })
+    }
+
+    #[test]
+    fn from_js_shared() {
+        test_with(|ctx| {
+            let Memoized(res): Memoized<Vec<Arc<Vec<i32>>>> =
+                ctx.eval("const x = [1]; [x, x, [1]]").unwrap();
+            assert!(Arc::ptr_eq(&res[0], &res[1]));
+            assert!(!Arc::ptr_eq(&res[0], &res[2]));
+
+            let Memoized(res): Memoized<Vec<Arc<String>>> = ctx.eval("['a', 'a']").unwrap();
+            assert_eq!(*res[0], "a");
+
+            let res = ctx
+                .eval::<Memoized<Vec<Rc<Vec<i32>>>>, _>("[[1], [1]]")
+                .unwrap();
+            assert!(!Rc::ptr_eq(&res.0[0], &res.0[1]));
+        })
+    }
+
+    #[test]
+    fn from_js_cycle() {
+        test_with(|ctx| {
+            #[allow(dead_code)]
+            struct Cyclic(Vec<Rc<Cyclic>>);
+            impl<'js> FromJs<'js> for Cyclic {
+                fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+                    Vec::from_js(ctx, value).map(Cyclic)
+                }
+            }
+            let res = ctx.eval::<Memoized<Rc<Cyclic>>, _>("const c = []; c.push(c); c");
+            assert!(matches!(res, Err(Error::FromJs { .. })));
+        })
+    }
+}
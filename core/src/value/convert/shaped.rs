@@ -0,0 +1,277 @@
+use ndarray::{Array, ArrayBase, ArrayD, ArrayViewD, Data, Dimension, IxDyn};
+
+use crate::{
+    value::typed_array::TypedArrayItem, Array as JsArray, Ctx, Error, FromJs, IntoJs, Object,
+    Result, TypedArray, Value,
+};
+
+/// The wrapper for n-dimensional arrays to convert them as a flat typed array and a shape.
+///
+/// `ndarray` arrays convert to nested arrays with typed arrays as their rows by default. Wrapped
+/// in `Shaped` they instead convert to an object `{ data, shape }`, where `data` is a typed array
+/// holding all elements in row-major order and `shape` is an array with the length of each axis,
+/// which copies the elements at once. Both forms are accepted when converting to an `ndarray`
+/// array.
+///
+/// ```
+/// # use ndarray::array;
+/// # use rquickjs::{convert::Shaped, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let matrix = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+///     ctx.globals().set("rows", matrix.view()).unwrap();
+///     ctx.globals().set("matrix", Shaped(matrix.view())).unwrap();
+///     assert_eq!(ctx.eval::<f64, _>("rows[1][2]").unwrap(), 6.0);
+///     assert_eq!(ctx.eval::<String, _>("matrix.shape.join('x')").unwrap(), "2x3");
+///     assert_eq!(ctx.eval::<f64, _>("matrix.data[5]").unwrap(), 6.0);
+///
+///     let Shaped(transposed): Shaped<ndarray::Array2<f64>> = ctx
+///         .eval("({ data: new Float64Array([1, 4, 2, 5, 3, 6]), shape: [3, 2] })")
+///         .unwrap();
+///     assert_eq!(transposed, matrix.t());
+/// });
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Shaped<T>(pub T);
+
+fn typed_array<'js, A>(ctx: &Ctx<'js>, view: ArrayViewD<'_, A>) -> Result<Value<'js>>
+where
+    A: TypedArrayItem,
+{
+    let array = match view.as_slice() {
+        Some(slice) => TypedArray::new_copy(ctx.clone(), slice)?,
+        None => TypedArray::new(ctx.clone(), view.iter().copied().collect::<Vec<_>>())?,
+    };
+    Ok(array.into_value())
+}
+
+fn nested_into_js<'js, A>(ctx: &Ctx<'js>, view: ArrayViewD<'_, A>) -> Result<Value<'js>>
+where
+    A: TypedArrayItem + IntoJs<'js>,
+{
+    match view.ndim() {
+        0 => match view.iter().next() {
+            Some(item) => item.into_js(ctx),
+            None => Ok(Value::new_undefined(ctx.clone())),
+        },
+        1 => typed_array(ctx, view),
+        _ => {
+            let array = JsArray::new(ctx.clone())?;
+            for (idx, row) in view.outer_iter().enumerate() {
+                array.set(idx, nested_into_js(ctx, row)?)?;
+            }
+            Ok(array.into_value())
+        }
+    }
+}
+
+/// Convert from an `ndarray` array to nested JS arrays with typed arrays as the rows
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+impl<'js, A, S, D> IntoJs<'js> for ArrayBase<S, D>
+where
+    A: TypedArrayItem + IntoJs<'js>,
+    S: Data<Elem = A>,
+    D: Dimension,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        (&self).into_js(ctx)
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+impl<'js, A, S, D> IntoJs<'js> for &ArrayBase<S, D>
+where
+    A: TypedArrayItem + IntoJs<'js>,
+    S: Data<Elem = A>,
+    D: Dimension,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        nested_into_js(ctx, self.view().into_dyn())
+    }
+}
+
+/// Convert from an `ndarray` array to an object with a flat typed array and a shape
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+impl<'js, A, S, D> IntoJs<'js> for Shaped<ArrayBase<S, D>>
+where
+    A: TypedArrayItem,
+    S: Data<Elem = A>,
+    D: Dimension,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let object = Object::new(ctx.clone())?;
+        let data = self.0.as_standard_layout();
+        object.set("data", typed_array(ctx, data.view().into_dyn())?)?;
+        object.set("shape", self.0.shape())?;
+        Ok(object.into_value())
+    }
+}
+
+/// The shape and elements of nested arrays being converted.
+struct Nested<A> {
+    shape: Vec<usize>,
+    data: Vec<A>,
+    row_depth: Option<usize>,
+}
+
+impl<A> Nested<A> {
+    fn axis(&mut self, depth: usize, len: usize) -> Result<()> {
+        match self.shape.get(depth) {
+            Some(&expected) if expected != len => Err(ragged()),
+            Some(_) => Ok(()),
+            None => {
+                self.shape.push(len);
+                Ok(())
+            }
+        }
+    }
+
+    fn flatten<'js>(&mut self, ctx: &Ctx<'js>, value: Value<'js>, depth: usize) -> Result<()>
+    where
+        A: FromJs<'js>,
+    {
+        // Arrays of arrays or typed arrays are axes, other arrays and typed arrays are rows.
+        let axis = value.as_array().filter(|array| {
+            array
+                .get::<Value>(0)
+                .map_or(false, |first| first.is_object())
+        });
+        if let Some(axis) = axis {
+            self.axis(depth, axis.len())?;
+            for item in axis.iter() {
+                self.flatten(ctx, item?, depth + 1)?;
+            }
+            return Ok(());
+        }
+        if *self.row_depth.get_or_insert(depth) != depth {
+            return Err(ragged());
+        }
+        let row = Vec::<A>::from_js(ctx, value)?;
+        self.axis(depth, row.len())?;
+        self.data.extend(row);
+        Ok(())
+    }
+}
+
+fn ragged() -> Error {
+    Error::new_from_js_message("array", "ndarray", "Nested arrays are ragged")
+}
+
+fn from_shape<A, D: Dimension>(shape: Vec<usize>, data: Vec<A>) -> Result<Array<A, D>> {
+    ArrayD::from_shape_vec(IxDyn(&shape), data)
+        .and_then(|array| array.into_dimensionality())
+        .map_err(|error| Error::new_from_js_message("array", "ndarray", error.to_string()))
+}
+
+fn shaped_from_js<'js, A, D>(ctx: &Ctx<'js>, object: &Object<'js>) -> Result<Array<A, D>>
+where
+    A: FromJs<'js>,
+    D: Dimension,
+{
+    let data = Vec::<A>::from_js(ctx, object.get("data")?)?;
+    from_shape(object.get("shape")?, data)
+}
+
+/// Convert from nested JS arrays or typed arrays, or an object with a flat typed array and a
+/// shape, to an `ndarray` array
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+impl<'js, A, D> FromJs<'js> for Array<A, D>
+where
+    A: FromJs<'js>,
+    D: Dimension,
+{
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        if let Some(object) = value.as_object() {
+            if !value.is_array() && object.contains_key("shape")? {
+                return shaped_from_js(ctx, object);
+            }
+        } else {
+            return from_shape(Vec::new(), vec![A::from_js(ctx, value)?]);
+        }
+        let mut nested = Nested {
+            shape: Vec::new(),
+            data: Vec::new(),
+            row_depth: None,
+        };
+        nested.flatten(ctx, value, 0)?;
+        from_shape(nested.shape, nested.data)
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "ndarray")))]
+impl<'js, A, D> FromJs<'js> for Shaped<Array<A, D>>
+where
+    A: FromJs<'js>,
+    D: Dimension,
+{
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        Array::from_js(ctx, value).map(Shaped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{array, Array1, Array2, Array3, ArrayD};
+
+    use crate::{convert::Shaped, *};
+
+    #[test]
+    fn nested() {
+        test_with(|ctx| {
+            let matrix = array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+            ctx.globals().set("matrix", &matrix).unwrap();
+            ctx.globals().set("column", matrix.column(1)).unwrap();
+            let res: StdString = ctx
+                .eval(
+                    r#"[
+                        matrix.length, matrix[2].constructor.name, matrix[2][1],
+                        column.constructor.name, column.join(),
+                    ].join(" ")"#,
+                )
+                .unwrap();
+            assert_eq!(res, "3 Float64Array 6 Float64Array 2,4,6");
+
+            let res: Array2<f64> = ctx.eval("matrix").unwrap();
+            assert_eq!(res, matrix);
+            let res: Array2<u8> = ctx.eval("[[1, 2], [3, 4]]").unwrap();
+            assert_eq!(res, array![[1, 2], [3, 4]]);
+            let res: Array3<i32> = ctx
+                .eval("[[new Int32Array([1, 2])], [new Int32Array([3, 4])]]")
+                .unwrap();
+            assert_eq!(res.shape(), [2, 1, 2]);
+            let res: ArrayD<f64> = ctx.eval("2.5").unwrap();
+            assert_eq!(res.ndim(), 0);
+            assert_eq!(res.iter().next(), Some(&2.5));
+
+            assert!(ctx.eval::<Array2<f64>, _>("[[1, 2], [3]]").is_err());
+            assert!(ctx.eval::<Array2<f64>, _>("[1, 2]").is_err());
+            assert!(ctx.eval::<ArrayD<f64>, _>("[[1, 2], [[3], [4]]]").is_err());
+        })
+    }
+
+    #[test]
+    fn shaped() {
+        test_with(|ctx| {
+            let matrix = array![[1u16, 2, 3], [4, 5, 6]];
+            ctx.globals()
+                .set("matrix", Shaped(matrix.t().to_owned()))
+                .unwrap();
+            let res: StdString = ctx
+                .eval("[matrix.data.constructor.name, matrix.data.join(), matrix.shape.join()].join(' ')")
+                .unwrap();
+            assert_eq!(res, "Uint16Array 1,4,2,5,3,6 3,2");
+
+            let Shaped(res): Shaped<Array2<u16>> = ctx.eval("matrix").unwrap();
+            assert_eq!(res, matrix.t());
+            let res: Array1<f32> = ctx
+                .eval("({ data: new Float32Array([0.5, 1.5]), shape: [2] })")
+                .unwrap();
+            assert_eq!(res, array![0.5, 1.5]);
+            assert!(ctx
+                .eval::<Array2<f32>, _>("({ data: [1, 2, 3], shape: [2, 2] })")
+                .is_err());
+        })
+    }
+}
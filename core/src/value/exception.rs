@@ -128,6 +128,13 @@ impl<'js> Exception<'js> {
             .map(|x| x.0)
     }
 
+    /// Returns the origin label which was active when the error was thrown.
+    ///
+    /// See [`Ctx::with_origin`](crate::Ctx::with_origin).
+    pub fn origin(&self) -> Option<String> {
+        self.get::<_, Option<String>>("origin").ok().flatten()
+    }
+
     /// Returns the error stack.
     ///
     /// Same as retrieving `error.stack` in JavaScript.
@@ -4,21 +4,25 @@ use crate::{
     atom::PredefinedAtom,
     class::{Class, JsClass},
     function::ffi::RustFunc,
-    qjs, Ctx, Error, FromJs, IntoJs, Object, Result, Value,
+    qjs, Ctx, Error, Exception, FromJs, IntoJs, Object, Result, Value,
 };
 
 mod args;
+mod builder;
 mod ffi;
 mod into_func;
 mod params;
 mod types;
 
 pub use args::{Args, IntoArg, IntoArgs};
+pub use builder::FunctionBuilder;
 pub use ffi::{RustFunction, StaticJsFn};
 pub use params::{FromParam, FromParams, ParamRequirement, Params, ParamsAccessor};
 #[cfg(feature = "futures")]
 pub use types::Async;
-pub use types::{Exhaustive, Flat, Func, FuncArg, MutFn, Null, OnceFn, Opt, Rest, This};
+pub use types::{
+    Exhaustive, Flat, Func, FuncArg, MutFn, Null, OnceFn, Opt, Rest, SelfFunction, This,
+};
 
 /// A trait for converting a Rust function to a JavaScript function.
 pub trait IntoJsFunc<'js, P> {
@@ -43,6 +47,37 @@ pub struct Function<'js>(pub(crate) Object<'js>);
 
 impl<'js> Function<'js> {
     /// Create a new function from a Rust function which implements [`IntoJsFunc`].
+    ///
+    /// Any `Fn` closure or function whose parameters implement [`FromParam`] and whose return
+    /// value implements [`IntoJs`] can be used directly. `FnMut` and `FnOnce` closures must be
+    /// wrapped in [`MutFn`] and [`OnceFn`]: calling a `MutFn` while it is already running, or a
+    /// `OnceFn` a second time, throws an error.
+    ///
+    /// ```
+    /// # use rquickjs::{Runtime, Context, Function, function::{MutFn, OnceFn}};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let add = Function::new(ctx.clone(), |a: i32, b: i32| a + b).unwrap();
+    ///     ctx.globals().set("add", add).unwrap();
+    ///
+    ///     let mut count = 0;
+    ///     let next = Function::new(ctx.clone(), MutFn::new(move || {
+    ///         count += 1;
+    ///         count
+    ///     }))
+    ///     .unwrap();
+    ///     ctx.globals().set("next", next).unwrap();
+    ///
+    ///     let name = String::from("plugin");
+    ///     let take = Function::new(ctx.clone(), OnceFn::new(move || name)).unwrap();
+    ///     ctx.globals().set("take", take).unwrap();
+    ///
+    ///     let res: String = ctx.eval("[add(1, 2), next(), next(), take()].join()").unwrap();
+    ///     assert_eq!(res, "3,1,2,plugin");
+    ///     assert!(ctx.eval::<String, _>("take()").is_err());
+    /// });
+    /// ```
     pub fn new<P, F>(ctx: Ctx<'js>, f: F) -> Result<Self>
     where
         F: IntoJsFunc<'js, P> + 'js,
@@ -57,7 +92,31 @@ impl<'js> Function<'js> {
         Function(cls.into_inner()).with_length(F::param_requirements().min())
     }
 
+    /// Create a builder for a Rust function with a custom name, length or prototype.
+    pub fn builder(ctx: Ctx<'js>) -> FunctionBuilder<'js> {
+        FunctionBuilder::new(ctx)
+    }
+
     /// Call the function with given arguments.
+    ///
+    /// Arguments are given as a tuple of values implementing [`IntoArg`], with [`This`] setting
+    /// the `this` value of the call. To call a JavaScript callback later, outside of the current
+    /// [`Context::with`](crate::Context::with) scope, store it in a [`Persistent`](crate::Persistent).
+    ///
+    /// ```
+    /// # use rquickjs::{Runtime, Context, Function, function::This, Object};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let add: Function = ctx.eval("(a, b) => a + b").unwrap();
+    ///     assert_eq!(add.call::<_, i32>((1, 2)).unwrap(), 3);
+    ///
+    ///     let get: Function = ctx.eval("function(key) { return this[key] }").unwrap();
+    ///     let obj = Object::new(ctx.clone()).unwrap();
+    ///     obj.set("x", 42).unwrap();
+    ///     assert_eq!(get.call::<_, i32>((This(obj), "x")).unwrap(), 42);
+    /// });
+    /// ```
     pub fn call<A, R>(&self, args: A) -> Result<R>
     where
         A: IntoArgs<'js>,
@@ -99,6 +158,26 @@ impl<'js> Function<'js> {
         args.defer(self.clone())
     }
 
+    /// Create a bound function with a fixed `this` value and leading arguments, like
+    /// `Function.prototype.bind`.
+    ///
+    /// The result is a bound function of the engine, so its `name` is `bound` followed by the
+    /// name of this function, its `length` is reduced by the number of bound arguments and it
+    /// can be used with `new` if this function is a constructor.
+    pub fn bind<T, A>(&self, this: T, args: A) -> Result<Function<'js>>
+    where
+        T: IntoJs<'js>,
+        A: IntoArgs<'js>,
+    {
+        let ctx = self.ctx();
+        let bind: Function = Function::prototype(ctx.clone()).get("bind")?;
+        let mut accum_args = Args::new(ctx.clone(), args.num_args() + 1);
+        accum_args.this(self.clone())?;
+        accum_args.push_arg(this)?;
+        args.into_args(&mut accum_args)?;
+        accum_args.apply(&bind)
+    }
+
     /// Set the `name` property of this function
     pub fn set_name<S: AsRef<str>>(&self, name: S) -> Result<()> {
         let name = name.as_ref().into_js(self.ctx())?;
@@ -193,6 +272,10 @@ pub struct Constructor<'js>(pub(crate) Function<'js>);
 impl<'js> Constructor<'js> {
     /// Creates a Rust constructor function for a Rust class.
     ///
+    /// Like the constructor of a JavaScript class the function can only be called with `new`.
+    /// The class can be extended by JavaScript classes, `super(...)` calls the Rust function
+    /// and the returned instance gets the prototype of the derived class.
+    ///
     /// Note that this function creates a constructor from a given function, the returned constructor
     /// is thus not the same as the one returned from [`JsClass::constructor`].
     pub fn new_class<C, F, P>(ctx: Ctx<'js>, f: F) -> Result<Self>
@@ -202,12 +285,25 @@ impl<'js> Constructor<'js> {
     {
         let func = Box::new(move |params: Params<'_, 'js>| -> Result<Value<'js>> {
             params.check_params(F::param_requirements())?;
-            let this = params.this();
             let ctx = params.ctx().clone();
-            let proto = this
-                .into_function()
-                .map(|func| func.get(PredefinedAtom::Prototype))
-                .unwrap_or_else(|| Ok(Class::<C>::prototype(ctx.clone())))?;
+            if !params.is_constructor() {
+                return Err(Exception::throw_type(
+                    &ctx,
+                    &format!(
+                        "class constructor {} cannot be invoked without 'new'",
+                        C::NAME
+                    ),
+                ));
+            }
+            // `this` is `new.target`, which is the derived class if a JavaScript class extends
+            // this one, so derived instances get the prototype of the derived class.
+            let new_target_proto = match params.this().into_function() {
+                Some(new_target) => {
+                    new_target.get::<_, Option<Object>>(PredefinedAtom::Prototype)?
+                }
+                None => None,
+            };
+            let proto = new_target_proto.or_else(|| Class::<C>::prototype(ctx.clone()));
 
             let res = f.call(params)?;
             res.as_object()
@@ -434,6 +530,39 @@ mod test {
         })
     }
 
+    #[test]
+    fn bind_js_fn() {
+        test_with(|ctx| {
+            let f: Function = ctx
+                .eval("function f(a, b) { return this.val * a + b; } f")
+                .unwrap();
+            let obj = Object::new(ctx).unwrap();
+            obj.set("val", 3).unwrap();
+
+            let bound = f.bind(obj, (2,)).unwrap();
+            let res: i32 = bound.call((1,)).unwrap();
+            assert_eq!(res, 7);
+            assert_eq!(bound.get::<_, StdString>("name").unwrap(), "bound f");
+            assert_eq!(bound.get::<_, i32>("length").unwrap(), 1);
+        })
+    }
+
+    #[test]
+    fn self_function() {
+        test_with(|ctx| {
+            let counter = Object::new(ctx.clone()).unwrap();
+            counter.set("count", 0).unwrap();
+            let increment = SelfFunction::new(counter, |This(this): This<Object>, n: i32| {
+                let count = this.get::<_, i32>("count")? + n;
+                this.set("count", count)?;
+                Ok::<_, Error>(count)
+            });
+            ctx.globals().set("increment", increment).unwrap();
+            let res: i32 = ctx.eval("increment(1); increment.call({}, 2)").unwrap();
+            assert_eq!(res, 3);
+        })
+    }
+
     #[test]
     fn call_js_fn_with_1_arg_deferred() {
         let rt = Runtime::new().unwrap();
@@ -0,0 +1,168 @@
+use crate::{Ctx, Function, Object, Result};
+
+use super::{Constructor, IntoJsFunc};
+
+/// A builder for Rust functions which look like functions defined in JavaScript.
+///
+/// Created with [`Function::builder`]. Without further configuration the built function is the
+/// same as one created with [`Function::new`].
+///
+/// ```
+/// # use rquickjs::{Context, Ctx, Function, Object, Result, Runtime};
+/// fn point<'js>(ctx: Ctx<'js>, x: f64, y: f64) -> Result<Object<'js>> {
+///     let point = Object::new(ctx)?;
+///     point.set("x", x)?;
+///     point.set("y", y)?;
+///     Ok(point)
+/// }
+///
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let point = Function::builder(ctx.clone())
+///         .name("Point")
+///         .constructor(true)
+///         .build(point)
+///         .unwrap();
+///     ctx.globals().set("Point", point).unwrap();
+///     let res: bool = ctx
+///         .eval("Point.name === 'Point' && Point.length === 2 && new Point(1, 2) instanceof Point")
+///         .unwrap();
+///     assert!(res);
+/// });
+/// ```
+#[must_use]
+pub struct FunctionBuilder<'js> {
+    ctx: Ctx<'js>,
+    name: Option<String>,
+    length: Option<usize>,
+    constructor: bool,
+    prototype: Option<Object<'js>>,
+}
+
+impl<'js> FunctionBuilder<'js> {
+    pub(crate) fn new(ctx: Ctx<'js>) -> Self {
+        FunctionBuilder {
+            ctx,
+            name: None,
+            length: None,
+            constructor: false,
+            prototype: None,
+        }
+    }
+
+    /// Set the `name` property of the function.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the `length` property of the function.
+    ///
+    /// Defaults to the number of required parameters of the Rust function.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Set whether the function can be called with `new`.
+    ///
+    /// A constructor without an explicit prototype gets a new, empty prototype object like a
+    /// JavaScript function, and objects returned when it is called with `new` get the
+    /// `prototype` of `new.target`.
+    pub fn constructor(mut self, constructor: bool) -> Self {
+        self.constructor = constructor;
+        self
+    }
+
+    /// Set the object used as the `prototype` property of the function.
+    ///
+    /// The `constructor` property of the prototype is set to the function. Setting a prototype
+    /// also makes the function a constructor.
+    pub fn prototype(mut self, prototype: Object<'js>) -> Self {
+        self.prototype = Some(prototype);
+        self.constructor = true;
+        self
+    }
+
+    /// Create the function from a Rust function.
+    pub fn build<P, F>(self, f: F) -> Result<Function<'js>>
+    where
+        F: IntoJsFunc<'js, P> + 'js,
+    {
+        let func = if self.constructor {
+            let prototype = match self.prototype {
+                Some(prototype) => prototype,
+                None => Object::new(self.ctx.clone())?,
+            };
+            Constructor::new_prototype(&self.ctx, prototype, f)?.0
+        } else {
+            Function::new(self.ctx, f)?
+        };
+        if let Some(name) = self.name {
+            func.set_name(name)?;
+        }
+        func.set_length(self.length.unwrap_or(F::param_requirements().min()))?;
+        Ok(func)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{atom::PredefinedAtom, test_with};
+
+    fn has_prototype(func: &Function) -> bool {
+        func.contains_key(PredefinedAtom::Prototype).unwrap()
+    }
+
+    #[test]
+    fn defaults() {
+        test_with(|ctx| {
+            let func = Function::builder(ctx.clone())
+                .build(|a: i32, b: i32| a + b)
+                .unwrap();
+            assert_eq!(func.get::<_, usize>(PredefinedAtom::Length).unwrap(), 2);
+            assert!(!func.is_constructor());
+            assert!(!has_prototype(&func));
+            let res: i32 = func.call((1, 2)).unwrap();
+            assert_eq!(res, 3);
+        })
+    }
+
+    fn point<'js>(ctx: Ctx<'js>, x: f64, y: f64) -> Result<Object<'js>> {
+        let point = Object::new(ctx)?;
+        point.set("x", x)?;
+        point.set("y", y)?;
+        Ok(point)
+    }
+
+    #[test]
+    fn introspection() {
+        test_with(|ctx| {
+            let proto = Object::new(ctx.clone()).unwrap();
+            proto.set("kind", "point").unwrap();
+            let func = Function::builder(ctx.clone())
+                .name("Point")
+                .length(3)
+                .prototype(proto)
+                .build(point)
+                .unwrap();
+            assert!(func.is_constructor());
+            ctx.globals().set("Point", func).unwrap();
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    Point.name === "Point" &&
+                    Point.length === 3 &&
+                    Point.prototype.constructor === Point &&
+                    Point.prototype.kind === "point" &&
+                    new Point(1, 2) instanceof Point &&
+                    new Point(1, 2).kind === "point"
+                "#,
+                )
+                .unwrap();
+            assert!(res);
+        })
+    }
+}
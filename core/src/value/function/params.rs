@@ -149,6 +149,11 @@ impl<'a, 'js> ParamsAccessor<'a, 'js> {
         unsafe { Value::from_js_value_const(self.params.ctx.clone(), res) }
     }
 
+    /// Returns the index of the next argument.
+    pub fn index(&self) -> usize {
+        self.offset
+    }
+
     /// returns the number of arguments remaining
     pub fn len(&self) -> usize {
         self.params.args.len() - self.offset
@@ -39,6 +39,34 @@ where
     }
 }
 
+/// Helper type for creating a function from a closure which is bound to a `this` value.
+///
+/// The closure receives the bound value as its [`This`] parameter. The value is kept alive by
+/// the bound function, see [`Function::bind`], so the closure doesn't need to capture a
+/// [`Persistent`](crate::Persistent) handle to it.
+pub struct SelfFunction<T, F, P>(T, F, PhantomData<P>);
+
+impl<'js, T, F, P> SelfFunction<T, F, P>
+where
+    T: IntoJs<'js>,
+    F: IntoJsFunc<'js, P>,
+{
+    pub fn new(this: T, func: F) -> Self {
+        SelfFunction(this, func, PhantomData)
+    }
+}
+
+impl<'js, T, F, P> IntoJs<'js> for SelfFunction<T, F, P>
+where
+    T: IntoJs<'js>,
+    F: IntoJsFunc<'js, P> + 'js,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let function = Function::new(ctx.clone(), self.1)?.bind(self.0, ())?;
+        function.into_js(ctx)
+    }
+}
+
 /// helper type for working setting and retrieving `this` values.
 pub struct This<T>(pub T);
 
@@ -86,7 +114,7 @@ impl<T> From<T> for MutFn<T> {
     }
 }
 
-/// Helper type for creating a function from a closure which implements [`FnMut`]
+/// Helper type for creating a function from a closure which implements [`FnOnce`]
 ///
 /// When called, will take the internal value leaving it empty. If the internal
 /// value was already empty it will return a error.
@@ -42,6 +42,10 @@ macro_rules! module_init {
     };
 }
 
+/// The key of `import.meta` which holds the exports of a module declared with
+/// [`Module::declare_object`] until it is evaluated.
+const OBJECT_EXPORTS_KEY: &str = "__rquickjs_exports";
+
 /// The raw module load function (`js_module_init`)
 pub type ModuleLoadFn =
     unsafe extern "C" fn(*mut qjs::JSContext, *const qjs::c_char) -> *mut qjs::JSModuleDef;
@@ -218,6 +222,58 @@ impl<'js> Module<'js, Declared> {
         //Ok(())
     }
 
+    /// Declare a module exporting the own enumerable string keyed properties of an object.
+    ///
+    /// The values are read from the object when the module is evaluated.
+    pub fn declare_object<N>(ctx: Ctx<'js>, name: N, exports: Object<'js>) -> Result<Module<'js>>
+    where
+        N: Into<Vec<u8>>,
+    {
+        let name = CString::new(name)?;
+        let ptr =
+            unsafe { qjs::JS_NewCModule(ctx.as_ptr(), name.as_ptr(), Some(Self::eval_object_fn)) };
+        let ptr = NonNull::new(ptr).ok_or(Error::Unknown)?;
+        let m = unsafe { Module::from_ptr(ctx, ptr) };
+
+        let decl = Declarations(m);
+        for key in exports.keys::<String>() {
+            decl.declare(key?)?;
+        }
+        // A native module has no code which could access its `import.meta`, so the object is
+        // kept there until the module is evaluated.
+        decl.0.meta()?.set(OBJECT_EXPORTS_KEY, exports)?;
+
+        Ok(decl.0)
+    }
+
+    unsafe extern "C" fn eval_object_fn(
+        ctx: *mut qjs::JSContext,
+        ptr: *mut qjs::JSModuleDef,
+    ) -> qjs::c_int {
+        let ctx = Ctx::from_ptr(ctx);
+        // Should never be null
+        let ptr = NonNull::new(ptr).unwrap();
+        let module = unsafe { Module::<Declared>::from_ptr(ctx.clone(), ptr) };
+        let res = (|| -> Result<()> {
+            let meta = module.meta()?;
+            let exports: Object = meta.get(OBJECT_EXPORTS_KEY)?;
+            meta.remove(OBJECT_EXPORTS_KEY)?;
+            let target = Exports(module);
+            for entry in exports.props::<String, Value>() {
+                let (key, value) = entry?;
+                target.export(key, value)?;
+            }
+            Ok(())
+        })();
+        match res {
+            Ok(_) => 0,
+            Err(error) => {
+                error.throw(&ctx);
+                -1
+            }
+        }
+    }
+
     /// Evaluate the source of a module.
     ///
     /// This function returns a promise which resolved when the modules was fully compiled and
@@ -259,16 +315,32 @@ impl<'js> Module<'js, Declared> {
     /// # Safety
     /// User must ensure that bytes handed to this function contain valid bytecode.
     pub unsafe fn load(ctx: Ctx<'js>, bytes: &[u8]) -> Result<Module<'js, Declared>> {
+        Self::read(
+            ctx,
+            bytes,
+            qjs::JS_READ_OBJ_BYTECODE | qjs::JS_READ_OBJ_ROM_DATA,
+        )
+    }
+
+    /// Load a module from quickjs bytecode, copying the bytecode.
+    ///
+    /// Unlike [`Module::load`] the bytes don't have to outlive the module.
+    ///
+    /// # Safety
+    /// User must ensure that bytes handed to this function contain valid bytecode.
+    pub(crate) unsafe fn load_copied(ctx: Ctx<'js>, bytes: &[u8]) -> Result<Module<'js, Declared>> {
+        Self::read(ctx, bytes, qjs::JS_READ_OBJ_BYTECODE)
+    }
+
+    unsafe fn read(ctx: Ctx<'js>, bytes: &[u8], flags: u32) -> Result<Module<'js, Declared>> {
         let module = unsafe {
-            qjs::JS_ReadObject(
-                ctx.as_ptr(),
-                bytes.as_ptr(),
-                bytes.len() as _,
-                (qjs::JS_READ_OBJ_BYTECODE | qjs::JS_READ_OBJ_ROM_DATA) as i32,
-            )
+            qjs::JS_ReadObject(ctx.as_ptr(), bytes.as_ptr(), bytes.len() as _, flags as i32)
         };
         let module = ctx.handle_exception(module)?;
-        debug_assert_eq!(qjs::JS_TAG_MODULE, unsafe { qjs::JS_VALUE_GET_TAG(module) });
+        if unsafe { qjs::JS_VALUE_GET_TAG(module) } != qjs::JS_TAG_MODULE {
+            unsafe { qjs::JS_FreeValue(ctx.as_ptr(), module) };
+            return Err(Error::new_from_js("value", "module"));
+        }
         let module_ptr =
             unsafe { NonNull::new(qjs::JS_VALUE_GET_PTR(module).cast()).ok_or(Error::Unknown)? };
         unsafe { Ok(Module::from_ptr(ctx, module_ptr)) }
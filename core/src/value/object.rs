@@ -4,9 +4,17 @@ use crate::{
     convert::FromIteratorJs, qjs, Array, Atom, Ctx, FromAtom, FromJs, IntoAtom, IntoJs, Result,
     Value,
 };
-use std::{iter::FusedIterator, marker::PhantomData, mem};
+use std::{
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+};
 
+mod literal;
 mod property;
+pub use literal::{ArrayLiteral, ObjectLiteral};
+#[doc(hidden)]
+pub use literal::{LiteralItems, LiteralProps};
 pub use property::{Accessor, AsProperty, Property, PropertyFlags};
 
 /// Rust representation of a JavaScript object.
@@ -35,6 +43,9 @@ impl<'js> Object<'js> {
     }
 
     /// check whether the object contains a certain key.
+    ///
+    /// Like the JavaScript `in` operator this also finds keys on the prototype chain, use
+    /// [`Object::contains_own_key`] to only check the object itself.
     pub fn contains_key<K>(&self, k: K) -> Result<bool>
     where
         K: IntoAtom<'js>,
@@ -49,6 +60,28 @@ impl<'js> Object<'js> {
         }
     }
 
+    /// check whether the object itself contains a certain key, ignoring its prototype chain.
+    ///
+    /// Same as `Object.hasOwn` in JavaScript.
+    pub fn contains_own_key<K>(&self, k: K) -> Result<bool>
+    where
+        K: IntoAtom<'js>,
+    {
+        let atom = k.into_atom(self.ctx())?;
+        unsafe {
+            let res = qjs::JS_GetOwnProperty(
+                self.0.ctx.as_ptr(),
+                std::ptr::null_mut(),
+                self.0.as_js_value(),
+                atom.atom,
+            );
+            if res < 0 {
+                return Err(self.0.ctx.raise_exception());
+            }
+            Ok(res == 1)
+        }
+    }
+
     /// Set a member of an object to a certain value
     pub fn set<K: IntoAtom<'js>, V: IntoJs<'js>>(&self, key: K, value: V) -> Result<()> {
         let atom = key.into_atom(self.ctx())?;
@@ -67,6 +100,47 @@ impl<'js> Object<'js> {
         Ok(())
     }
 
+    /// Get the value at an index.
+    ///
+    /// Indices which fit in a `u32` are accessed directly, larger indices fall back to the
+    /// property with the canonical string representation of the index as key.
+    pub fn get_index<V: FromJs<'js>>(&self, idx: usize) -> Result<V> {
+        let idx = match u32::try_from(idx) {
+            Ok(x) => x,
+            Err(_) => return self.get(Atom::from_index(self.ctx().clone(), idx)?),
+        };
+        let ctx = self.ctx();
+        V::from_js(ctx, unsafe {
+            let val = qjs::JS_GetPropertyUint32(ctx.as_ptr(), self.0.as_js_value(), idx);
+            let val = ctx.handle_exception(val)?;
+            Value::from_js_value(ctx.clone(), val)
+        })
+    }
+
+    /// Set the value at an index.
+    ///
+    /// See [`Object::get_index`] for how indices are mapped to properties.
+    pub fn set_index<V: IntoJs<'js>>(&self, idx: usize, value: V) -> Result<()> {
+        let idx = match u32::try_from(idx) {
+            Ok(x) => x,
+            Err(_) => return self.set(Atom::from_index(self.ctx().clone(), idx)?, value),
+        };
+        let ctx = self.ctx();
+        let val = value.into_js(ctx)?;
+        unsafe {
+            if qjs::JS_SetPropertyUint32(
+                ctx.as_ptr(),
+                self.0.as_js_value(),
+                idx,
+                val.into_js_value(),
+            ) < 0
+            {
+                return Err(ctx.raise_exception());
+            }
+        }
+        Ok(())
+    }
+
     /// Remove a member of an object
     pub fn remove<K: IntoAtom<'js>>(&self, key: K) -> Result<()> {
         let atom = key.into_atom(self.ctx())?;
@@ -91,7 +165,7 @@ impl<'js> Object<'js> {
 
     /// Get the number of properties
     pub fn len(&self) -> usize {
-        self.keys::<Atom>().count()
+        self.keys::<Atom>().len()
     }
 
     /// Get own string enumerable property names of an object
@@ -100,6 +174,12 @@ impl<'js> Object<'js> {
     }
 
     /// Get own property names of an object
+    ///
+    /// The keys are returned in the order of `Reflect.ownKeys`: integer indices in ascending
+    /// order, then strings and then symbols, both in the order they were created. The keys are
+    /// retrieved when the iterator is created, so the iterator knows its exact length, which lets
+    /// collecting it allocate once, and can be iterated from both ends. This applies to the
+    /// property and value iterators of an object as well.
     pub fn own_keys<K: FromAtom<'js>>(&self, filter: Filter) -> ObjectKeysIter<'js, K> {
         ObjectKeysIter {
             state: Some(IterState::new(&self.0, filter.flags)),
@@ -113,6 +193,25 @@ impl<'js> Object<'js> {
     }
 
     /// Get own properties of an object
+    ///
+    /// The keys can be converted to any [`FromAtom`] type. With [`Atom`] keys, which the values
+    /// are looked up with as well, the keys can be used for further property operations without
+    /// converting them to strings and back:
+    ///
+    /// ```
+    /// # use rquickjs::{Atom, Context, Object, Runtime, Value, object::Filter};
+    /// # let rt = Runtime::new().unwrap();
+    /// # let ctx = Context::full(&rt).unwrap();
+    /// ctx.with(|ctx| {
+    ///     let source: Object = ctx.eval("({ a: 1, b: 2 })").unwrap();
+    ///     let target = Object::new(ctx.clone()).unwrap();
+    ///     for prop in source.own_props::<Atom, Value>(Filter::default()) {
+    ///         let (key, value) = prop.unwrap();
+    ///         target.set(key, value).unwrap();
+    ///     }
+    ///     assert_eq!(target.get::<_, i32>("b").unwrap(), 2);
+    /// });
+    /// ```
     pub fn own_props<K: FromAtom<'js>, V: FromJs<'js>>(
         &self,
         filter: Filter,
@@ -125,12 +224,12 @@ impl<'js> Object<'js> {
     }
 
     /// Get own string enumerable property values of an object
-    pub fn values<K: FromAtom<'js>>(&self) -> ObjectValuesIter<'js, K> {
+    pub fn values<V: FromJs<'js>>(&self) -> ObjectValuesIter<'js, V> {
         self.own_values(Filter::default())
     }
 
     /// Get own property values of an object
-    pub fn own_values<K: FromAtom<'js>>(&self, filter: Filter) -> ObjectValuesIter<'js, K> {
+    pub fn own_values<V: FromJs<'js>>(&self, filter: Filter) -> ObjectValuesIter<'js, V> {
         ObjectValuesIter {
             state: Some(IterState::new(&self.0, filter.flags)),
             object: self.clone(),
@@ -168,6 +267,70 @@ impl<'js> Object<'js> {
         }
     }
 
+    /// Freeze the object, like `Object.freeze`.
+    ///
+    /// Only the own properties of the object are frozen, use [`Value::deep_freeze`] to also
+    /// freeze the objects it references.
+    pub fn freeze(&self) -> Result<()> {
+        self.freeze_with(|_| {})
+    }
+
+    /// Freeze the object, calling `f` with the value of every own data property.
+    pub(crate) fn freeze_with<F: FnMut(Value<'js>)>(&self, mut f: F) -> Result<()> {
+        let ctx = &self.0.ctx;
+        if unsafe { qjs::JS_PreventExtensions(ctx.as_ptr(), self.0.as_js_value()) } < 0 {
+            return Err(ctx.raise_exception());
+        }
+        for key in self.own_keys::<Atom>(Filter::new().string().symbol()) {
+            let key = key?;
+            let mut desc = MaybeUninit::<qjs::JSPropertyDescriptor>::uninit();
+            let res = unsafe {
+                qjs::JS_GetOwnProperty(
+                    ctx.as_ptr(),
+                    desc.as_mut_ptr(),
+                    self.0.as_js_value(),
+                    key.atom,
+                )
+            };
+            if res < 0 {
+                return Err(ctx.raise_exception());
+            }
+            if res == 0 {
+                continue;
+            }
+            let desc = unsafe { desc.assume_init() };
+            // Take ownership of the descriptor values to free them.
+            let (value, _getter, _setter) = unsafe {
+                (
+                    Value::from_js_value(ctx.clone(), desc.value),
+                    Value::from_js_value(ctx.clone(), desc.getter),
+                    Value::from_js_value(ctx.clone(), desc.setter),
+                )
+            };
+
+            let mut flags = qjs::JS_PROP_HAS_CONFIGURABLE | qjs::JS_PROP_THROW;
+            if desc.flags & qjs::JS_PROP_GETSET as qjs::c_int == 0 {
+                flags |= qjs::JS_PROP_HAS_WRITABLE;
+                f(value);
+            }
+            let res = unsafe {
+                qjs::JS_DefineProperty(
+                    ctx.as_ptr(),
+                    self.0.as_js_value(),
+                    key.atom,
+                    qjs::JS_UNDEFINED,
+                    qjs::JS_UNDEFINED,
+                    qjs::JS_UNDEFINED,
+                    flags as _,
+                )
+            };
+            if res < 0 {
+                return Err(ctx.raise_exception());
+            }
+        }
+        Ok(())
+    }
+
     /// Check instance of object
     pub fn is_instance_of(&self, class: impl AsRef<Value<'js>>) -> bool {
         let class = class.as_ref();
@@ -237,8 +400,22 @@ impl Filter {
         self.flags |= qjs::JS_GPN_ENUM_ONLY as qjs::c_int;
         self
     }
+
+    /// Exclude the `__proto__` and `constructor` properties.
+    ///
+    /// Objects created from untrusted input, for example with `JSON.parse`, can have these as
+    /// own properties. Skipping them when converting the object to Rust prevents prototype
+    /// pollution if the converted data is later turned back into JavaScript objects.
+    #[must_use]
+    pub fn skip_prototype_keys(mut self) -> Self {
+        self.flags |= FILTER_SKIP_PROTOTYPE_KEYS;
+        self
+    }
 }
 
+/// Filter flag handled by rquickjs instead of QuickJS, chosen to not overlap with `JS_GPN_*`.
+const FILTER_SKIP_PROTOTYPE_KEYS: qjs::c_int = 1 << 16;
+
 struct IterState<'js> {
     ctx: Ctx<'js>,
     enums: *mut qjs::JSPropertyEnum,
@@ -259,13 +436,28 @@ impl<'js> IterState<'js> {
                 enums.as_mut_ptr(),
                 count.as_mut_ptr(),
                 obj.value,
-                flags,
+                flags & !FILTER_SKIP_PROTOTYPE_KEYS,
             ) < 0
             {
                 return Err(ctx.raise_exception());
             }
-            let enums = enums.assume_init();
-            let count = count.assume_init();
+            let enums: *mut qjs::JSPropertyEnum = enums.assume_init();
+            let mut count = count.assume_init();
+            if flags & FILTER_SKIP_PROTOTYPE_KEYS != 0 {
+                let mut kept = 0;
+                for index in 0..count as usize {
+                    let elem = enums.add(index).read();
+                    if elem.atom == qjs::JS_ATOM___proto__ as qjs::JSAtom
+                        || elem.atom == qjs::JS_ATOM_constructor as qjs::JSAtom
+                    {
+                        qjs::JS_FreeAtom(ctx.as_ptr(), elem.atom);
+                    } else {
+                        enums.add(kept).write(elem);
+                        kept += 1;
+                    }
+                }
+                count = kept as _;
+            }
             (enums, count)
         };
 
@@ -396,10 +588,11 @@ where
     K: FromAtom<'js>,
 {
     fn len(&self) -> usize {
-        if let Some(Ok(state)) = &self.state {
-            state.len()
-        } else {
-            0
+        match &self.state {
+            Some(Ok(state)) => state.len(),
+            // The error is yielded as the only item.
+            Some(Err(_)) => 1,
+            None => 0,
         }
     }
 }
@@ -480,10 +673,11 @@ where
     V: FromJs<'js>,
 {
     fn len(&self) -> usize {
-        if let Some(Ok(state)) = &self.state {
-            state.len()
-        } else {
-            0
+        match &self.state {
+            Some(Ok(state)) => state.len(),
+            // The error is yielded as the only item.
+            Some(Err(_)) => 1,
+            None => 0,
         }
     }
 }
@@ -560,10 +754,11 @@ where
     V: FromJs<'js>,
 {
     fn len(&self) -> usize {
-        if let Some(Ok(state)) = &self.state {
-            state.len()
-        } else {
-            0
+        match &self.state {
+            Some(Ok(state)) => state.len(),
+            // The error is yielded as the only item.
+            Some(Err(_)) => 1,
+            None => 0,
         }
     }
 }
@@ -638,6 +833,100 @@ mod test {
         });
     }
 
+    #[test]
+    fn iterate_values() {
+        test_with(|ctx| {
+            let val: Object = ctx.eval("({ a: [1, 2], b: [3] })").unwrap();
+            let values = val
+                .values::<Vec<i32>>()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(values, [vec![1, 2], vec![3]]);
+            let props = val
+                .props::<Atom, Value>()
+                .map(|prop| prop.and_then(|(key, _)| key.to_string()))
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(props, ["a", "b"]);
+        });
+    }
+
+    #[test]
+    fn skip_prototype_keys() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        let source = r#"JSON.parse('{"a": 1, "__proto__": { "b": 2 }, "constructor": 3}')"#;
+        ctx.with(|ctx| {
+            let val: Object = ctx.eval(source).unwrap();
+            assert_eq!(val.keys::<StdString>().count(), 3);
+            let keys = val
+                .own_keys::<StdString>(Filter::default().skip_prototype_keys())
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(keys, ["a"]);
+
+            let map: std::collections::HashMap<StdString, Value> = ctx.eval(source).unwrap();
+            assert_eq!(map.len(), 3);
+        });
+
+        rt.set_hardened_conversions(true);
+        ctx.with(|ctx| {
+            let map: std::collections::HashMap<StdString, Value> = ctx.eval(source).unwrap();
+            assert_eq!(map.len(), 1);
+            assert!(map.contains_key("a"));
+        });
+    }
+
+    #[test]
+    fn contains_own_key() {
+        test_with(|ctx| {
+            let val: Object = ctx
+                .eval("let obj = Object.create({ inherited: 1 }); obj.own = 2; obj")
+                .unwrap();
+            assert!(val.contains_key("inherited").unwrap());
+            assert!(!val.contains_own_key("inherited").unwrap());
+            assert!(val.contains_own_key("own").unwrap());
+            assert!(val.contains_key("toString").unwrap());
+            assert!(!val.contains_own_key("toString").unwrap());
+        });
+    }
+
+    #[test]
+    fn indexed_access() {
+        test_with(|ctx| {
+            let val = Object::new(ctx.clone()).unwrap();
+            val.set_index(3, "a").unwrap();
+            let text: StdString = val.get_index(3).unwrap();
+            assert_eq!(text, "a");
+            let text: StdString = val.get("3").unwrap();
+            assert_eq!(text, "a");
+
+            let large = u32::MAX as usize;
+            val.set_index(large, "b").unwrap();
+            let text: StdString = val.get("4294967295").unwrap();
+            assert_eq!(text, "b");
+
+            #[cfg(target_pointer_width = "64")]
+            {
+                let larger = u32::MAX as usize + 10;
+                val.set_index(larger, "c").unwrap();
+                let text: StdString = val.get_index(larger).unwrap();
+                assert_eq!(text, "c");
+                let text: StdString = val.get("4294967305").unwrap();
+                assert_eq!(text, "c");
+            }
+
+            val.set(-1, "d").unwrap();
+            let text: StdString = val.get("-1").unwrap();
+            assert_eq!(text, "d");
+
+            let map: std::collections::BTreeMap<u32, i32> = ctx.eval("({ 1: 10, 2: 20 })").unwrap();
+            assert_eq!(map.get(&2), Some(&20));
+            let res: Result<std::collections::BTreeMap<u32, i32>> = ctx.eval("({ '-1': 10 })");
+            assert!(matches!(res, Err(Error::FromJs { .. })));
+        });
+    }
+
     #[test]
     fn types() {
         test_with(|ctx| {
@@ -720,6 +1009,15 @@ mod test {
             assert_eq!(pairs[1].1, "abc");
             assert_eq!(pairs[2].0, "");
             assert_eq!(pairs[2].1, "def");
+
+            let pairs = val
+                .own_props(Filter::new().string())
+                .collect::<Result<Vec<(Atom, Value)>>>()
+                .unwrap();
+            assert_eq!(pairs.len(), 3);
+            assert_eq!(pairs[1].0.to_string().unwrap(), "str");
+            assert_eq!(val.get::<_, StdString>(pairs[1].0.clone()).unwrap(), "abc");
+            assert!(pairs[0].1.is_string());
         })
     }
 
@@ -754,6 +1052,45 @@ mod test {
         })
     }
 
+    #[test]
+    fn iter_order_and_len() {
+        test_with(|ctx| {
+            let val: Object = ctx
+                .eval(r#"({ b: 1, 2: 2, [Symbol("s")]: 3, a: 4, 1: 5 })"#)
+                .unwrap();
+            let mut keys = val.own_keys::<StdString>(Filter::new().string());
+            assert_eq!(keys.len(), 4);
+            assert_eq!(keys.next().unwrap().unwrap(), "1");
+            assert_eq!(keys.next_back().unwrap().unwrap(), "a");
+            assert_eq!(keys.len(), 2);
+            let keys = keys.collect::<Result<Vec<StdString>>>().unwrap();
+            assert_eq!(keys, ["2", "b"]);
+            assert_eq!(
+                val.own_keys::<Atom>(Filter::new().string().symbol()).len(),
+                5
+            );
+
+            let values = val
+                .values::<i32>()
+                .rev()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(values, [4, 1, 2, 5]);
+            assert_eq!(val.props::<StdString, i32>().size_hint(), (4, Some(4)));
+            assert_eq!(val.len(), 4);
+
+            let val: Object = ctx
+                .eval("new Proxy({}, { ownKeys() { throw new Error('keys') } })")
+                .unwrap();
+            let mut keys = val.keys::<StdString>();
+            assert_eq!(keys.len(), 1);
+            assert!(keys.next().unwrap().is_err());
+            assert_eq!(keys.len(), 0);
+            assert!(keys.next().is_none());
+            ctx.catch();
+        })
+    }
+
     #[test]
     fn iter_take() {
         test_with(|ctx| {
@@ -801,4 +1138,40 @@ mod test {
             );
         })
     }
+
+    #[test]
+    fn deep_freeze() {
+        test_with(|ctx| {
+            let config: Object = ctx
+                .eval(
+                    r#"
+                    const shared = { level: 1 };
+                    const config = { list: [shared], nested: { shared }, [Symbol.iterator]: shared };
+                    config.nested.parent = config;
+                    Object.defineProperty(config, "lazy", { get() { throw new Error("called") } });
+                    config
+                    "#,
+                )
+                .unwrap();
+            config.as_value().deep_freeze().unwrap();
+
+            ctx.globals().set("config", config).unwrap();
+            let res: bool = ctx
+                .eval(
+                    r#"
+                    Object.isFrozen(config)
+                        && Object.isFrozen(config.list)
+                        && Object.isFrozen(config.list[0])
+                        && Object.isFrozen(config.nested)
+                        && Object.isFrozen(Object.prototype) === false
+                    "#,
+                )
+                .unwrap();
+            assert!(res);
+            assert!(ctx.eval::<(), _>("config.list[0].level = 2").is_err());
+            ctx.catch();
+
+            Value::new_int(ctx.clone(), 1).deep_freeze().unwrap();
+        })
+    }
 }
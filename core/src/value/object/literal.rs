@@ -0,0 +1,238 @@
+use crate::{Array, Ctx, IntoAtom, IntoJs, Object, Result, Value};
+
+/// Create a JavaScript object from a literal, like [`serde_json::json!`].
+///
+/// Keys are identifiers, literals or expressions in brackets, values are any Rust expression
+/// which implements [`IntoJs`](crate::IntoJs). A value in braces is a nested object and a value
+/// in brackets a nested array, written with the same syntax.
+///
+/// The macro doesn't need a context, it returns an [`ObjectLiteral`] which creates the object
+/// when it is converted with [`IntoJs`](crate::IntoJs). So it can be passed anywhere a value is
+/// expected, for example as the value of a property, an argument or the return value of a
+/// function.
+///
+/// [`serde_json::json!`]: https://docs.rs/serde_json/latest/serde_json/macro.json.html
+///
+/// ```
+/// # use rquickjs::{obj, Context, Object, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let name = "b";
+///     let value = obj! {
+///         "a": 1,
+///         [name]: [1, 2, 3],
+///         c: { nested: true, sum: 1 + 2 },
+///     };
+///     ctx.globals().set("value", value).unwrap();
+///     let res: String = ctx.eval("JSON.stringify(value)").unwrap();
+///     assert_eq!(res, r#"{"a":1,"b":[1,2,3],"c":{"nested":true,"sum":3}}"#);
+/// });
+/// ```
+#[macro_export]
+macro_rules! obj {
+    ($($tt:tt)*) => {
+        $crate::object::ObjectLiteral($crate::__obj_props!($($tt)*))
+    };
+}
+
+/// Create a JavaScript array from a literal.
+///
+/// Elements are written like the values of [`obj!`](crate::obj), the macro returns an
+/// [`ArrayLiteral`] which creates the array when it is converted.
+///
+/// ```
+/// # use rquickjs::{array, Context, Runtime};
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     ctx.globals().set("value", array![1, "two", { three: 3 }, [4]]).unwrap();
+///     let res: String = ctx.eval("JSON.stringify(value)").unwrap();
+///     assert_eq!(res, r#"[1,"two",{"three":3},[4]]"#);
+/// });
+/// ```
+#[macro_export]
+macro_rules! array {
+    ($($tt:tt)*) => {
+        $crate::object::ArrayLiteral($crate::__array_items!(@item [] $($tt)*))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obj_props {
+    () => {
+        ()
+    };
+    ($key:ident : $($rest:tt)*) => {
+        $crate::__obj_props!(@value [stringify!($key)] [] $($rest)*)
+    };
+    ($key:literal : $($rest:tt)*) => {
+        $crate::__obj_props!(@value [$key] [] $($rest)*)
+    };
+    ([$($key:tt)+] : $($rest:tt)*) => {
+        $crate::__obj_props!(@value [$($key)+] [] $($rest)*)
+    };
+    (@value [$($key:tt)+] [$($value:tt)+] , $($rest:tt)*) => {
+        ($($key)+, $crate::__js_value!($($value)+), $crate::__obj_props!($($rest)*))
+    };
+    (@value [$($key:tt)+] [$($value:tt)+]) => {
+        ($($key)+, $crate::__js_value!($($value)+), ())
+    };
+    (@value [$($key:tt)+] [$($value:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__obj_props!(@value [$($key)+] [$($value)* $next] $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __array_items {
+    (@item []) => {
+        ()
+    };
+    (@item [$($value:tt)+]) => {
+        ($crate::__js_value!($($value)+), ())
+    };
+    (@item [$($value:tt)+] , $($rest:tt)*) => {
+        ($crate::__js_value!($($value)+), $crate::__array_items!(@item [] $($rest)*))
+    };
+    (@item [$($value:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__array_items!(@item [$($value)* $next] $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __js_value {
+    ({ $($tt:tt)* }) => {
+        $crate::obj!($($tt)*)
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::array!($($tt)*)
+    };
+    ($value:expr) => {
+        $value
+    };
+}
+
+/// The properties of an [`ObjectLiteral`], a list of `(key, value, rest)` tuples ending with
+/// `()`.
+#[doc(hidden)]
+pub trait LiteralProps<'js> {
+    fn set_props(self, object: &Object<'js>) -> Result<()>;
+}
+
+impl<'js> LiteralProps<'js> for () {
+    fn set_props(self, _object: &Object<'js>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'js, K, V, R> LiteralProps<'js> for (K, V, R)
+where
+    K: IntoAtom<'js>,
+    V: IntoJs<'js>,
+    R: LiteralProps<'js>,
+{
+    fn set_props(self, object: &Object<'js>) -> Result<()> {
+        object.set(self.0, self.1)?;
+        self.2.set_props(object)
+    }
+}
+
+/// The elements of an [`ArrayLiteral`], a list of `(value, rest)` tuples ending with `()`.
+#[doc(hidden)]
+pub trait LiteralItems<'js> {
+    fn set_items(self, array: &Array<'js>, index: usize) -> Result<()>;
+}
+
+impl<'js> LiteralItems<'js> for () {
+    fn set_items(self, _array: &Array<'js>, _index: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'js, V, R> LiteralItems<'js> for (V, R)
+where
+    V: IntoJs<'js>,
+    R: LiteralItems<'js>,
+{
+    fn set_items(self, array: &Array<'js>, index: usize) -> Result<()> {
+        array.set(index, self.0)?;
+        self.1.set_items(array, index + 1)
+    }
+}
+
+/// An object literal created by the [`obj!`](crate::obj) macro.
+///
+/// The object is created when the literal is converted into a JavaScript value.
+pub struct ObjectLiteral<P>(#[doc(hidden)] pub P);
+
+impl<'js, P: LiteralProps<'js>> IntoJs<'js> for ObjectLiteral<P> {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let object = Object::new(ctx.clone())?;
+        self.0.set_props(&object)?;
+        Ok(object.into_value())
+    }
+}
+
+/// An array literal created by the [`array!`](crate::array) macro.
+///
+/// The array is created when the literal is converted into a JavaScript value.
+pub struct ArrayLiteral<I>(#[doc(hidden)] pub I);
+
+impl<'js, I: LiteralItems<'js>> IntoJs<'js> for ArrayLiteral<I> {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let array = Array::new(ctx.clone())?;
+        self.0.set_items(&array, 0)?;
+        Ok(array.into_value())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{test_with, Array, IntoJs, Null, Object};
+
+    #[test]
+    fn nested() {
+        test_with(|ctx| {
+            let key = String::from("computed");
+            let value = obj! {
+                "a": 1,
+                b: [1, 2, 3],
+                c: { nested: true, "d": [{ e: Null }, [], {}] },
+                [key.as_str()]: key.len() * 2,
+                last: "x" // no trailing comma
+            }
+            .into_js(&ctx)
+            .unwrap();
+            let object = Object::from_value(value).unwrap();
+            assert_eq!(object.keys::<String>().count(), 5);
+            ctx.globals().set("value", object).unwrap();
+            let res: String = ctx.eval("JSON.stringify(value)").unwrap();
+            assert_eq!(
+                res,
+                r#"{"a":1,"b":[1,2,3],"c":{"nested":true,"d":[{"e":null},[],{}]},"computed":16,"last":"x"}"#
+            );
+        })
+    }
+
+    #[test]
+    fn arrays() {
+        test_with(|ctx| {
+            let array = Array::from_value(array![].into_js(&ctx).unwrap()).unwrap();
+            assert!(array.is_empty());
+            let array = Array::from_value(
+                array![1 + 1, "two", vec![3], { four: 4 },]
+                    .into_js(&ctx)
+                    .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(array.len(), 4);
+            assert_eq!(array.get::<i32>(0).unwrap(), 2);
+            assert_eq!(array.get::<Vec<i32>>(2).unwrap(), [3]);
+            let four: Object = array.get(3).unwrap();
+            assert_eq!(four.get::<_, i32>("four").unwrap(), 4);
+        })
+    }
+}
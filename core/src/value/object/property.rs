@@ -70,7 +70,7 @@ pub trait AsProperty<'js, P> {
 }
 
 macro_rules! wrapper_impls {
-	  ($($(#[$type_meta:meta])* $type:ident<$($param:ident),*>($($field:ident)*; $($flag:ident)*))*) => {
+	  ($($(#[$type_meta:meta])* $type:ident<$($param:ident),*>($($field:ident)*; $($flag:ident / $set_flag:ident)*))*) => {
         $(
             $(#[$type_meta])*
             #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "properties")))]
@@ -82,6 +82,7 @@ macro_rules! wrapper_impls {
 
             impl<$($param),*> $type<$($param),*> {
                 $(wrapper_impls!{@flag $flag concat!("Make the property to be ", stringify!($flag))})*
+                $(wrapper_impls!{@set_flag $flag $set_flag concat!("Set whether the property is ", stringify!($flag))})*
             }
         )*
 	  };
@@ -95,6 +96,19 @@ macro_rules! wrapper_impls {
         }
     };
 
+    (@set_flag $flag:ident $set_flag:ident $doc:expr) => {
+        #[doc = $doc]
+        #[must_use]
+        pub fn $set_flag(mut self, $flag: bool) -> Self {
+            if $flag {
+                self.flags |= wrapper_impls!(@flag $flag);
+            } else {
+                self.flags &= !wrapper_impls!(@flag $flag);
+            }
+            self
+        }
+    };
+
     (@flag $flag:ident) => { wrapper_impls!{@_flag $flag} as PropertyFlags };
     (@_flag configurable) => { qjs::JS_PROP_CONFIGURABLE };
     (@_flag enumerable) => { qjs::JS_PROP_ENUMERABLE };
@@ -120,9 +134,9 @@ where
 
 wrapper_impls! {
     /// The data descriptor of a property
-    Property<T>(value; writable configurable enumerable)
+    Property<T>(value; writable / set_writable configurable / set_configurable enumerable / set_enumerable)
     /// The accessor descriptor of a readonly property
-    Accessor<G, S>(get set; configurable enumerable)
+    Accessor<G, S>(get set; configurable / set_configurable enumerable / set_enumerable)
 }
 
 /// Create property data descriptor from value
@@ -135,6 +149,13 @@ impl<T> From<T> for Property<T> {
     }
 }
 
+impl<T> Property<T> {
+    /// Create a readonly, non-configurable and non-enumerable data descriptor from a value
+    pub fn value(value: T) -> Self {
+        Self::from(value)
+    }
+}
+
 impl<'js, T> AsProperty<'js, T> for Property<T>
 where
     T: IntoJs<'js>,
@@ -169,6 +190,16 @@ impl<G> Accessor<G, ()> {
         }
     }
 
+    /// Create accessor from getter, same as [`Accessor::new_get`]
+    pub fn getter(get: G) -> Self {
+        Self::new_get(get)
+    }
+
+    /// Add setter to accessor, same as [`Accessor::set`]
+    pub fn setter<S>(self, set: S) -> Accessor<G, S> {
+        self.set(set)
+    }
+
     /// Add setter to accessor
     pub fn set<S>(self, set: S) -> Accessor<G, S> {
         Accessor {
@@ -319,6 +350,40 @@ mod test {
         });
     }
 
+    #[test]
+    fn property_builders() {
+        test_with(|ctx| {
+            let obj = Object::new(ctx.clone()).unwrap();
+            obj.prop(
+                "ro",
+                Property::value(1)
+                    .writable()
+                    .set_writable(false)
+                    .enumerable(),
+            )
+            .unwrap();
+            obj.prop(
+                "acc",
+                Accessor::getter(|| 2)
+                    .setter(|_: i32| {})
+                    .set_enumerable(true),
+            )
+            .unwrap();
+            ctx.globals().set("obj", obj).unwrap();
+            let res: StdString = ctx
+                .eval(
+                    r#"
+                    const ro = Object.getOwnPropertyDescriptor(obj, "ro");
+                    const acc = Object.getOwnPropertyDescriptor(obj, "acc");
+                    [ro.value, ro.writable, ro.enumerable, acc.enumerable, acc.configurable, obj.acc]
+                        .join()
+                    "#,
+                )
+                .unwrap();
+            assert_eq!(res, "1,false,true,true,false,2");
+        });
+    }
+
     #[test]
     fn property_with_data_descriptor_writable() {
         test_with(|ctx| {
@@ -128,6 +128,9 @@ impl<'js> Promise<'js> {
             }
 
             if !self.ctx.execute_pending_job() {
+                if unsafe { (*self.ctx.get_opaque()).termination_requested() } {
+                    return Err(Error::Terminated);
+                }
                 return Err(Error::WouldBlock);
             }
         }
@@ -154,7 +157,8 @@ impl<'js> Promise<'js> {
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 #[derive(Debug)]
 pub struct PromiseFuture<'js, T> {
-    state: Option<Rc<RefCell<Waker>>>,
+    /// The id of the waker registered in the runtime and the waker.
+    state: Option<(usize, Rc<RefCell<Waker>>)>,
     promise: Promise<'js>,
     _marker: PhantomData<T>,
 }
@@ -177,11 +181,26 @@ where
             return Poll::Ready(x);
         }
 
+        let opaque = unsafe { &mut *this.promise.ctx.get_opaque() };
+        if opaque.is_terminated() {
+            return Poll::Ready(Err(Error::Terminated));
+        }
+
+        // Futures driven by `Ctx::block_on` are polled again after every job, so the waker is not
+        // registered, which lets `block_on` detect that only jobs could resolve the promise.
+        if opaque.blocking {
+            return Poll::Pending;
+        }
+
         if this.state.is_none() {
             let inner = Rc::new(RefCell::new(cx.waker().clone()));
-            this.state = Some(inner.clone());
+            let id = opaque.next_promise_waker;
+            opaque.next_promise_waker += 1;
+            opaque.promise_wakers.insert(id, Rc::downgrade(&inner));
+            this.state = Some((id, inner.clone()));
 
-            let resolve = Function::new(this.promise.ctx.clone(), move || {
+            let resolve = Function::new(this.promise.ctx.clone(), move |ctx: Ctx<'js>| {
+                unsafe { (*ctx.get_opaque()).promise_wakers.remove(&id) };
                 inner.borrow().wake_by_ref();
             })?;
 
@@ -194,6 +213,7 @@ where
         this.state
             .as_ref()
             .unwrap()
+            .1
             .borrow_mut()
             .clone_from(cx.waker());
 
@@ -201,6 +221,18 @@ where
     }
 }
 
+#[cfg(feature = "futures")]
+impl<'js, T> Drop for PromiseFuture<'js, T> {
+    fn drop(&mut self) {
+        if let Some((id, _)) = self.state.as_ref() {
+            // The opaque is null if the future is dropped while the runtime is freed.
+            if let Some(opaque) = unsafe { self.promise.ctx.get_opaque().as_mut() } {
+                opaque.promise_wakers.remove(id);
+            }
+        }
+    }
+}
+
 /// Wrapper for futures to convert to JS promises
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
 #[repr(transparent)]
@@ -415,6 +447,39 @@ mod test {
         .await
     }
 
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn promise_wakers_removed() {
+        use std::{
+            future::{poll_fn, Future},
+            pin::Pin,
+            task::Poll,
+        };
+
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+
+        async_with!(ctx => |ctx| {
+            ctx.globals().set("setTimeout",Func::from(Async(set_timeout))).unwrap();
+
+            for _ in 0..100 {
+                let promise: Promise = ctx.eval("new Promise(() => {})").unwrap();
+                let mut future = promise.into_future::<()>();
+                let pending = poll_fn(|cx| Poll::Ready(Pin::new(&mut future).poll(cx).is_pending())).await;
+                assert!(pending);
+            }
+            assert!(unsafe { (*ctx.get_opaque()).promise_wakers.is_empty() });
+
+            let promise: Promise = ctx
+                .eval("new Promise((resolve) => setTimeout(() => resolve(1), 10))")
+                .unwrap();
+            let future = promise.into_future::<i32>();
+            assert_eq!(future.await.unwrap(), 1);
+            assert!(unsafe { (*ctx.get_opaque()).promise_wakers.is_empty() });
+        })
+        .await
+    }
+
     #[cfg(feature = "futures")]
     #[tokio::test]
     async fn promised() {
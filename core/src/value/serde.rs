@@ -0,0 +1,831 @@
+//! Converting Rust values to and from JavaScript values with serde.
+//!
+//! [`to_js`] converts any type implementing [`Serialize`] and [`from_js`] any type implementing
+//! [`DeserializeOwned`], so types deriving the serde traits don't need handwritten
+//! [`IntoJs`](crate::IntoJs) and [`FromJs`](crate::FromJs) implementations. Values are mapped
+//! like `serde_json` maps them to JSON:
+//!
+//! - Structs and maps become objects, map keys must be strings, numbers or booleans.
+//! - Sequences, tuples and bytes become arrays.
+//! - `None`, `()` and unit structs become `null`, they are deserialized from `null` and
+//!   `undefined`.
+//! - Unit variants become the name of the variant, other variants become an object with the name
+//!   of the variant as the only key.
+//! - Integers become numbers, so integers above `2^53` lose precision. Numbers without a fractional
+//!   part and big integers are deserialized as integers.
+//!
+//! Objects are deserialized from their own enumerable string keyed properties. Functions and
+//! symbols can't be deserialized.
+//!
+//! ```
+//! # use rquickjs::{Context, Runtime};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//!     proxy: Option<String>,
+//! }
+//!
+//! let rt = Runtime::new().unwrap();
+//! let ctx = Context::full(&rt).unwrap();
+//! ctx.with(|ctx| {
+//!     let value = ctx.eval("({ name: 'app', retries: 3 })").unwrap();
+//!     let mut config: Config = rquickjs::serde::from_js(value).unwrap();
+//!     assert_eq!(config.retries, 3);
+//!     assert_eq!(config.proxy, None);
+//!
+//!     config.retries += 1;
+//!     let value = rquickjs::serde::to_js(&ctx, &config).unwrap();
+//!     ctx.globals().set("config", value).unwrap();
+//!     assert_eq!(ctx.eval::<i32, _>("config.retries").unwrap(), 4);
+//! });
+//! ```
+
+use std::fmt::Display;
+
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Unexpected, Visitor},
+    forward_to_deserialize_any, ser, Serialize,
+};
+
+use crate::{
+    convert::ConversionGuard, object::ObjectIter, Array, Ctx, Error, IntoJs, Object, Result,
+    StdString, Type, Value,
+};
+
+/// Convert a Rust value into a JavaScript value.
+pub fn to_js<'js, T: Serialize + ?Sized>(ctx: &Ctx<'js>, value: &T) -> Result<Value<'js>> {
+    value.serialize(Serializer::new(ctx.clone()))
+}
+
+/// Convert a JavaScript value into a Rust value.
+pub fn from_js<'js, T: DeserializeOwned>(value: Value<'js>) -> Result<T> {
+    T::deserialize(Deserializer::new(value))
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Error::Serde {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Error::Serde {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// A serializer which creates JavaScript values, see [`to_js`].
+pub struct Serializer<'js> {
+    ctx: Ctx<'js>,
+}
+
+impl<'js> Serializer<'js> {
+    /// Create a serializer for values of the given context.
+    pub fn new(ctx: Ctx<'js>) -> Self {
+        Serializer { ctx }
+    }
+}
+
+impl<'js> ser::Serializer for Serializer<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+    type SerializeSeq = SerializeArray<'js>;
+    type SerializeTuple = SerializeArray<'js>;
+    type SerializeTupleStruct = SerializeArray<'js>;
+    type SerializeTupleVariant = SerializeArray<'js>;
+    type SerializeMap = SerializeObject<'js>;
+    type SerializeStruct = SerializeObject<'js>;
+    type SerializeStructVariant = SerializeObject<'js>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value<'js>> {
+        i32::from(v).into_js(&self.ctx)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value<'js>> {
+        i32::from(v).into_js(&self.ctx)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value<'js>> {
+        i32::from(v).into_js(&self.ctx)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value<'js>> {
+        i32::from(v).into_js(&self.ctx)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value<'js>> {
+        f64::from(v).into_js(&self.ctx)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value<'js>> {
+        v.encode_utf8(&mut [0; 4]).into_js(&self.ctx)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value<'js>> {
+        v.into_js(&self.ctx)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value<'js>> {
+        let array = Array::new(self.ctx)?;
+        for (index, byte) in v.iter().enumerate() {
+            array.set(index, i32::from(*byte))?;
+        }
+        Ok(array.into_value())
+    }
+
+    fn serialize_none(self) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value<'js>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<'js>> {
+        Ok(Value::new_null(self.ctx))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value<'js>> {
+        variant.into_js(&self.ctx)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value<'js>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value<'js>> {
+        let value = to_js(&self.ctx, value)?;
+        wrap_variant(value, Some(variant))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeArray<'js>> {
+        SerializeArray::new(self.ctx, None)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SerializeArray<'js>> {
+        SerializeArray::new(self.ctx, None)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SerializeArray<'js>> {
+        SerializeArray::new(self.ctx, None)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeArray<'js>> {
+        SerializeArray::new(self.ctx, Some(variant))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObject<'js>> {
+        SerializeObject::new(self.ctx, None)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeObject<'js>> {
+        SerializeObject::new(self.ctx, None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeObject<'js>> {
+        SerializeObject::new(self.ctx, Some(variant))
+    }
+}
+
+/// Wrap the value of an enum variant in an object with the name of the variant as key.
+fn wrap_variant<'js>(value: Value<'js>, variant: Option<&'static str>) -> Result<Value<'js>> {
+    match variant {
+        Some(variant) => {
+            let object = Object::new(value.ctx().clone())?;
+            object.set(variant, value)?;
+            Ok(object.into_value())
+        }
+        None => Ok(value),
+    }
+}
+
+/// Serializes sequences, tuples and tuple variants into an array.
+pub struct SerializeArray<'js> {
+    array: Array<'js>,
+    len: usize,
+    variant: Option<&'static str>,
+}
+
+impl<'js> SerializeArray<'js> {
+    fn new(ctx: Ctx<'js>, variant: Option<&'static str>) -> Result<Self> {
+        Ok(SerializeArray {
+            array: Array::new(ctx)?,
+            len: 0,
+            variant,
+        })
+    }
+}
+
+impl<'js> ser::SerializeSeq for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let value = to_js(self.array.ctx(), value)?;
+        self.array.set(self.len, value)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        wrap_variant(self.array.into_value(), self.variant)
+    }
+}
+
+impl<'js> ser::SerializeTuple for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'js> ser::SerializeTupleStruct for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'js> ser::SerializeTupleVariant for SerializeArray<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes maps, structs and struct variants into an object.
+pub struct SerializeObject<'js> {
+    object: Object<'js>,
+    key: Option<Value<'js>>,
+    variant: Option<&'static str>,
+}
+
+impl<'js> SerializeObject<'js> {
+    fn new(ctx: Ctx<'js>, variant: Option<&'static str>) -> Result<Self> {
+        Ok(SerializeObject {
+            object: Object::new(ctx)?,
+            key: None,
+            variant,
+        })
+    }
+}
+
+impl<'js> ser::SerializeMap for SerializeObject<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = to_js(self.object.ctx(), key)?;
+        match key.type_of() {
+            Type::String | Type::Int | Type::Float | Type::Bool => {
+                self.key = Some(key);
+                Ok(())
+            }
+            type_ => Err(ser::Error::custom(format!(
+                "Map keys must be strings, numbers or booleans, not {type_}"
+            ))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value should be called after serialize_key");
+        let value = to_js(self.object.ctx(), value)?;
+        self.object.set(key, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        wrap_variant(self.object.into_value(), self.variant)
+    }
+}
+
+impl<'js> ser::SerializeStruct for SerializeObject<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let value = to_js(self.object.ctx(), value)?;
+        self.object.set(key, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        wrap_variant(self.object.into_value(), self.variant)
+    }
+}
+
+impl<'js> ser::SerializeStructVariant for SerializeObject<'js> {
+    type Ok = Value<'js>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value<'js>> {
+        ser::SerializeStruct::end(self)
+    }
+}
+
+/// A deserializer which reads JavaScript values, see [`from_js`].
+pub struct Deserializer<'js> {
+    value: Value<'js>,
+}
+
+impl<'js> Deserializer<'js> {
+    /// Create a deserializer for the given value.
+    pub fn new(value: Value<'js>) -> Self {
+        Deserializer { value }
+    }
+}
+
+impl<'de, 'js> de::Deserializer<'de> for Deserializer<'js> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let value = self.value;
+        let ctx = value.ctx().clone();
+        match value.type_of() {
+            Type::Uninitialized | Type::Undefined | Type::Null => visitor.visit_unit(),
+            Type::Bool => visitor.visit_bool(value.as_bool().unwrap()),
+            Type::Int => visitor.visit_i32(value.as_int().unwrap()),
+            Type::Float => {
+                let x = value.as_float().unwrap();
+                // `i64::MAX as f64` is rounded up to 2^63, which doesn't fit.
+                if x.fract() == 0.0 && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+                    visitor.visit_i64(x as i64)
+                } else {
+                    visitor.visit_f64(x)
+                }
+            }
+            Type::String => visitor.visit_string(value.into_string().unwrap().to_string()?),
+            Type::BigInt => visitor.visit_i64(value.into_big_int().unwrap().to_i64()?),
+            Type::Array => {
+                let array = value.into_array().unwrap();
+                let _guard = ConversionGuard::enter(&ctx, array.as_value(), "Deserialize")?;
+                let len = array.len();
+                visitor.visit_seq(SeqAccess {
+                    array,
+                    index: 0,
+                    len,
+                })
+            }
+            Type::Object | Type::Exception | Type::Promise => {
+                let object = value.into_object().unwrap();
+                let _guard = ConversionGuard::enter(&ctx, object.as_value(), "Deserialize")?;
+                visitor.visit_map(MapAccess {
+                    props: object.props(),
+                    value: None,
+                })
+            }
+            type_ => Err(Error::new_from_js(type_.as_str(), "Deserialize")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.value.is_nullish() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Some(variant) = self.value.as_string() {
+            return visitor.visit_enum(EnumAccess {
+                variant: variant.to_string()?,
+                value: None,
+            });
+        }
+        if self.value.type_of() == Type::Object {
+            let mut props = self.value.as_object().unwrap().props::<StdString, Value>();
+            if let (Some(prop), None) = (props.next(), props.next()) {
+                let (variant, value) = prop?;
+                return visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                });
+            }
+            return Err(de::Error::invalid_value(
+                Unexpected::Map,
+                &"an object with a single key",
+            ));
+        }
+        Err(de::Error::invalid_type(
+            Unexpected::Other(self.value.type_name()),
+            &"a string or an object",
+        ))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SeqAccess<'js> {
+    array: Array<'js>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de, 'js> de::SeqAccess<'de> for SeqAccess<'js> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let value = self.array.get(self.index)?;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(value)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct MapAccess<'js> {
+    props: ObjectIter<'js, StdString, Value<'js>>,
+    value: Option<Value<'js>>,
+}
+
+impl<'de, 'js> de::MapAccess<'de> for MapAccess<'js> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.props.next() {
+            Some(prop) => {
+                let (key, value) = prop?;
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed should be called after next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.props.len())
+    }
+}
+
+struct EnumAccess<'js> {
+    variant: StdString,
+    value: Option<Value<'js>>,
+}
+
+impl<'de, 'js> de::EnumAccess<'de> for EnumAccess<'js> {
+    type Error = Error;
+    type Variant = VariantAccess<'js>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(KeyDeserializer(self.variant))?;
+        Ok((variant, VariantAccess { value: self.value }))
+    }
+}
+
+struct VariantAccess<'js> {
+    value: Option<Value<'js>>,
+}
+
+impl<'js> VariantAccess<'js> {
+    fn into_value(self, expected: &'static str) -> Result<Value<'js>> {
+        self.value
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::UnitVariant, &expected))
+    }
+}
+
+impl<'de, 'js> de::VariantAccess<'de> for VariantAccess<'js> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            Some(value) if !value.is_nullish() => Err(de::Error::invalid_type(
+                Unexpected::Other(value.type_name()),
+                &"a unit variant",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let value = self.into_value("a newtype variant")?;
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        let value = self.into_value("a tuple variant")?;
+        de::Deserializer::deserialize_seq(Deserializer::new(value), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value = self.into_value("a struct variant")?;
+        de::Deserializer::deserialize_map(Deserializer::new(value), visitor)
+    }
+}
+
+/// Deserializes property keys, which are parsed for numeric and boolean map keys.
+struct KeyDeserializer(StdString);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                match self.0.parse() {
+                    Ok(x) => visitor.$visit(x),
+                    Err(_) => visitor.visit_string(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf option unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_js, to_js};
+    use crate::{test_with, Error, Value};
+    use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+        Line(i32, i32),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Scene {
+        name: String,
+        shapes: Vec<Shape>,
+        origin: (i64, i64),
+        layers: BTreeMap<u8, String>,
+        hidden: Option<bool>,
+        #[serde(with = "serde_bytes_as_seq")]
+        data: Vec<u8>,
+    }
+
+    mod serde_bytes_as_seq {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(bytes)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u8>, D::Error> {
+            Vec::deserialize(deserializer)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        test_with(|ctx| {
+            let scene = Scene {
+                name: "scene".into(),
+                shapes: vec![
+                    Shape::Empty,
+                    Shape::Circle(0.5),
+                    Shape::Rect {
+                        width: 2,
+                        height: 3,
+                    },
+                    Shape::Line(-1, 1),
+                ],
+                origin: (-1, 1 << 40),
+                layers: [(1, "a".to_string()), (20, "b".to_string())].into(),
+                hidden: None,
+                data: vec![0, 255],
+            };
+            let value = to_js(&ctx, &scene).unwrap();
+            ctx.globals().set("scene", value.clone()).unwrap();
+            let json: String = ctx.eval("JSON.stringify(scene)").unwrap();
+            assert_eq!(
+                json,
+                r#"{"name":"scene","shapes":["Empty",{"Circle":0.5},{"Rect":{"width":2,"height":3}},{"Line":[-1,1]}],"origin":[-1,1099511627776],"layers":{"1":"a","20":"b"},"hidden":null,"data":[0,255]}"#
+            );
+            assert_eq!(from_js::<Scene>(value).unwrap(), scene);
+        })
+    }
+
+    #[test]
+    fn from_script() {
+        test_with(|ctx| {
+            let value: Value = ctx
+                .eval("({ b: 2.0, a: 1, big: 2n ** 40n, skip: undefined, list: [1, 2.5] })")
+                .unwrap();
+            let map: HashMap<String, Option<f64>> = from_js(value.clone()).unwrap();
+            assert_eq!(map["b"], Some(2.0));
+            assert_eq!(map["big"], Some((1u64 << 40) as f64));
+            assert_eq!(map["skip"], None);
+
+            #[derive(Deserialize)]
+            struct Partial {
+                a: u8,
+                big: u64,
+            }
+            let partial: Partial = from_js(value.clone()).unwrap();
+            assert_eq!((partial.a, partial.big), (1, 1 << 40));
+
+            let err = from_js::<Vec<i32>>(ctx.eval("[1, 2.5]").unwrap()).unwrap_err();
+            assert!(matches!(err, Error::Serde { .. }), "{err}");
+            #[derive(Debug, Deserialize)]
+            struct Missing {
+                #[allow(dead_code)]
+                missing: u8,
+            }
+            let err = from_js::<Missing>(value).unwrap_err();
+            assert!(err.to_string().contains("missing field `missing`"), "{err}");
+            let err = from_js::<()>(ctx.eval("() => {}").unwrap()).unwrap_err();
+            assert!(matches!(err, Error::FromJs { .. }));
+            let err = from_js::<String>(ctx.eval("Symbol()").unwrap());
+            assert!(err.is_err());
+        })
+    }
+
+    #[test]
+    fn cycle() {
+        test_with(|ctx| {
+            let value = ctx.eval("const a = {}; a.a = a; a").unwrap();
+            let err = from_js::<HashMap<String, HashMap<String, ()>>>(value).unwrap_err();
+            assert!(matches!(err, Error::Cycle { .. }));
+        })
+    }
+
+    #[test]
+    fn invalid_map_key() {
+        test_with(|ctx| {
+            let map: BTreeMap<Vec<u8>, u8> = [(vec![1], 1)].into();
+            assert!(matches!(to_js(&ctx, &map), Err(Error::Serde { .. })));
+        })
+    }
+}
@@ -0,0 +1,256 @@
+//! Conversion between Rust streams and JavaScript async iterables.
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context as TaskContext, Poll},
+};
+
+use async_lock::Mutex;
+use futures_core::Stream;
+
+use crate::{
+    function::This,
+    promise::{MaybePromise, MaybePromiseFuture, Promised},
+    Ctx, FromJs, Function, IntoJs, Object, Result, Symbol, Value,
+};
+
+/// Wrapper for streams to convert to JS async iterables
+///
+/// The stream is converted into an object implementing the async iterator protocol, so it can be
+/// consumed with `for await`. Concurrent calls to `next` are served in order and calling
+/// `return`, for example when breaking out of the loop, drops the stream.
+///
+/// ```
+/// # use futures_rs as futures;
+/// # use rquickjs::{stream::Streamed, Context, Runtime};
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     let numbers = Streamed(futures::stream::iter([1, 2, 3]));
+///     ctx.globals().set("numbers", numbers).unwrap();
+/// });
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[repr(transparent)]
+pub struct Streamed<S>(pub S);
+
+impl<S> From<S> for Streamed<S> {
+    fn from(stream: S) -> Self {
+        Self(stream)
+    }
+}
+
+type SharedStream<S> = Rc<Mutex<Option<Pin<Box<S>>>>>;
+
+impl<'js, S> IntoJs<'js> for Streamed<S>
+where
+    S: Stream + 'js,
+    S::Item: IntoJs<'js>,
+{
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let stream: SharedStream<S> = Rc::new(Mutex::new(Some(Box::pin(self.0))));
+        let iterator = Object::new(ctx.clone())?;
+
+        let next_stream = stream.clone();
+        let next = Function::new(ctx.clone(), move |ctx: Ctx<'js>| {
+            let stream = next_stream.clone();
+            Promised(async move {
+                let mut stream = stream.lock().await;
+                let item = match stream.as_mut() {
+                    Some(stream) => std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await,
+                    None => None,
+                };
+                if item.is_none() {
+                    *stream = None;
+                }
+                iterator_result(&ctx, item)
+            })
+        })?;
+        iterator.set("next", next)?;
+
+        let r#return = Function::new(ctx.clone(), move |ctx: Ctx<'js>| {
+            let stream = stream.clone();
+            Promised(async move {
+                *stream.lock().await = None;
+                iterator_result::<S::Item>(&ctx, None)
+            })
+        })?;
+        iterator.set("return", r#return)?;
+
+        let this = Function::new(ctx.clone(), |this: This<Object<'js>>| this.0)?;
+        iterator.set(Symbol::async_iterator(ctx.clone()).as_atom(), this)?;
+
+        Ok(iterator.into_value())
+    }
+}
+
+/// Returns an object of the iterator result protocol.
+fn iterator_result<'js, T: IntoJs<'js>>(ctx: &Ctx<'js>, item: Option<T>) -> Result<Object<'js>> {
+    let result = Object::new(ctx.clone())?;
+    match item {
+        Some(value) => {
+            result.set("value", value)?;
+            result.set("done", false)?;
+        }
+        None => result.set("done", true)?,
+    }
+    Ok(result)
+}
+
+/// A JavaScript async iterable as a Rust stream
+///
+/// The stream is created from any value implementing the async iterable protocol, like async
+/// generators or streams converted with [`Streamed`]. Every item is converted into `T`, when
+/// the iterator throws or an item can't be converted the stream yields the error and ends.
+///
+/// ```
+/// # use rquickjs::{stream::AsyncIterStream, Context, Runtime};
+/// # use futures_rs::StreamExt;
+/// # async fn run(){
+/// let rt = Runtime::new().unwrap();
+/// let ctx = Context::full(&rt).unwrap();
+/// let sum = ctx
+///     .async_with(|ctx| {
+///         Box::pin(async move {
+///             let mut stream: AsyncIterStream<i32> = ctx
+///                 .eval("(async function* () { yield 1; yield 2; })()")
+///                 .unwrap();
+///             let mut sum = 0;
+///             while let Some(item) = stream.next().await {
+///                 sum += item.unwrap();
+///             }
+///             sum
+///         })
+///     })
+///     .await;
+/// assert_eq!(sum, 3);
+/// # }
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "futures")))]
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct AsyncIterStream<'js, T> {
+    iterator: Object<'js>,
+    next: Function<'js>,
+    pending: Option<MaybePromiseFuture<'js, Object<'js>>>,
+    done: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<'js, T> FromJs<'js> for AsyncIterStream<'js, T> {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        let iterable = Object::from_js(ctx, value)?;
+        let method: Function = iterable.get(Symbol::async_iterator(ctx.clone()).as_atom())?;
+        let iterator: Object = method.call((This(iterable),))?;
+        let next = iterator.get("next")?;
+        Ok(AsyncIterStream {
+            iterator,
+            next,
+            pending: None,
+            done: false,
+            _marker: PhantomData,
+        })
+    }
+}
+
+// Nothing is actually pinned so the stream is unpin.
+impl<'js, T> Unpin for AsyncIterStream<'js, T> {}
+
+impl<'js, T: FromJs<'js>> AsyncIterStream<'js, T> {
+    fn poll_result(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<Option<T>>> {
+        if self.pending.is_none() {
+            let result: MaybePromise = self.next.call((This(self.iterator.clone()),))?;
+            self.pending = Some(result.into_future());
+        }
+
+        let pending = Pin::new(self.pending.as_mut().unwrap());
+        let result = match pending.poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.pending = None;
+
+        let result = result?;
+        if result.get::<_, Option<bool>>("done")?.unwrap_or(false) {
+            return Poll::Ready(Ok(None));
+        }
+        Poll::Ready(result.get("value").map(Some))
+    }
+}
+
+impl<'js, T: FromJs<'js>> Stream for AsyncIterStream<'js, T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        match this.poll_result(cx) {
+            Poll::Ready(Ok(Some(item))) => Poll::Ready(Some(Ok(item))),
+            Poll::Ready(Ok(None)) => {
+                this.done = true;
+                Poll::Ready(None)
+            }
+            Poll::Ready(Err(error)) => {
+                this.done = true;
+                Poll::Ready(Some(Err(error)))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_rs::{stream, StreamExt};
+
+    use super::*;
+    use crate::{async_with, AsyncContext, AsyncRuntime, Error};
+
+    #[tokio::test]
+    async fn stream_to_async_iterable() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        async_with!(ctx => |ctx| {
+            ctx.globals()
+                .set("numbers", Streamed(stream::iter([1, 2, 3])))
+                .unwrap();
+            let sum: i32 = ctx
+                .eval::<crate::Promise, _>(
+                    "(async () => { let sum = 0; for await (const n of numbers) sum += n; return sum })()",
+                )
+                .unwrap()
+                .into_future()
+                .await
+                .unwrap();
+            assert_eq!(sum, 6);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn async_iterable_to_stream() {
+        let rt = AsyncRuntime::new().unwrap();
+        let ctx = AsyncContext::full(&rt).await.unwrap();
+        async_with!(ctx => |ctx| {
+            let stream: AsyncIterStream<i32> = ctx
+                .eval("(async function* () { yield 1; yield 2; throw new Error('end') })()")
+                .unwrap();
+            let items: Vec<_> = stream.collect().await;
+            assert_eq!(items.len(), 3);
+            assert_eq!(*items[0].as_ref().unwrap(), 1);
+            assert_eq!(*items[1].as_ref().unwrap(), 2);
+            assert!(matches!(items[2], Err(Error::Exception)));
+
+            let stream: AsyncIterStream<i32> = ctx
+                .eval("({ [Symbol.asyncIterator]() { return this }, next: async () => ({ done: true }) })")
+                .unwrap();
+            assert_eq!(stream.count().await, 0);
+        })
+        .await;
+    }
+}
@@ -1,5 +1,31 @@
-use crate::{qjs, Ctx, Error, Result, StdString, Value};
-use std::{mem, slice, str};
+use crate::{qjs, Ctx, Result, StdString, Value};
+use std::{
+    mem,
+    ops::{Bound, RangeBounds},
+    os::raw::c_int,
+    slice,
+};
+
+/// How lone surrogates are handled when converting a string to UTF-8.
+///
+/// JavaScript strings are sequences of UTF-16 code units which don't have to be valid UTF-16, a
+/// surrogate can appear without its pair, for example after slicing a string in the middle of an
+/// emoji. Such strings have no UTF-8 representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LoneSurrogates {
+    /// Fail the conversion with an [`Error::Utf8`](crate::Error::Utf8).
+    #[default]
+    Error,
+    /// Replace every lone surrogate with `U+FFFD REPLACEMENT CHARACTER`.
+    Replace,
+}
+
+/// The characters of a string as QuickJS stores them.
+#[derive(Debug, Clone, Copy)]
+enum Chars<'a> {
+    Latin1(&'a [u8]),
+    Utf16(&'a [u16]),
+}
 
 /// Rust representation of a JavaScript string.
 #[derive(Debug, Clone, PartialEq, Hash)]
@@ -8,21 +34,150 @@ pub struct String<'js>(pub(crate) Value<'js>);
 
 impl<'js> String<'js> {
     /// Convert the JavaScript string to a Rust string.
+    ///
+    /// Fails with an [`Error::Utf8`](crate::Error::Utf8) when the string contains lone
+    /// surrogates, see [`String::to_string_with`] for other ways to handle them.
     pub fn to_string(&self) -> Result<StdString> {
+        let bytes = self.to_wtf8()?;
+        Ok(StdString::from_utf8(bytes)?)
+    }
+
+    /// Convert the JavaScript string to a Rust string, handling lone surrogates as given.
+    pub fn to_string_with(&self, surrogates: LoneSurrogates) -> Result<StdString> {
+        match (self.chars(), surrogates) {
+            (Chars::Latin1(bytes), _) => Ok(bytes.iter().map(|&byte| char::from(byte)).collect()),
+            (Chars::Utf16(_), LoneSurrogates::Error) => self.to_string(),
+            (Chars::Utf16(units), LoneSurrogates::Replace) => {
+                Ok(char::decode_utf16(units.iter().copied())
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect())
+            }
+        }
+    }
+
+    /// Convert the JavaScript string to [WTF-8](https://simonsapin.github.io/wtf-8/).
+    ///
+    /// WTF-8 is UTF-8 which also encodes lone surrogates, so it represents every JavaScript
+    /// string and equals UTF-8 for strings without lone surrogates.
+    pub fn to_wtf8(&self) -> Result<Vec<u8>> {
         let mut len = mem::MaybeUninit::uninit();
         let ptr = unsafe {
             qjs::JS_ToCStringLen(self.0.ctx.as_ptr(), len.as_mut_ptr(), self.0.as_js_value())
         };
         if ptr.is_null() {
-            // Might not ever happen but I am not 100% sure
-            // so just incase check it.
-            return Err(Error::Unknown);
+            // Converting can fail when allocating the C string fails.
+            return Err(self.0.ctx.raise_exception());
         }
         let len = unsafe { len.assume_init() };
         let bytes: &[u8] = unsafe { slice::from_raw_parts(ptr as _, len as _) };
-        let result = str::from_utf8(bytes).map(|s| s.into());
+        let result = bytes.to_vec();
         unsafe { qjs::JS_FreeCString(self.0.ctx.as_ptr(), ptr) };
-        Ok(result?)
+        Ok(result)
+    }
+
+    /// Convert the JavaScript string to its UTF-16 code units, including lone surrogates.
+    pub fn to_utf16(&self) -> Vec<u16> {
+        match self.chars() {
+            Chars::Latin1(bytes) => bytes.iter().map(|&byte| u16::from(byte)).collect(),
+            Chars::Utf16(units) => units.to_vec(),
+        }
+    }
+
+    /// Returns the length of the string in UTF-16 code units, like `length` in JavaScript.
+    pub fn len(&self) -> usize {
+        match self.chars() {
+            Chars::Latin1(bytes) => bytes.len(),
+            Chars::Utf16(units) => units.len(),
+        }
+    }
+
+    /// Returns whether the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the string is stored with 16 bit code units.
+    ///
+    /// QuickJS stores strings which only contain characters up to `U+00FF` as Latin-1, with a
+    /// byte per character, and all other strings as UTF-16. Latin-1 strings never contain lone
+    /// surrogates.
+    pub fn is_wide(&self) -> bool {
+        matches!(self.chars(), Chars::Utf16(_))
+    }
+
+    /// Returns the UTF-16 code unit at an index, like `charCodeAt` in JavaScript.
+    ///
+    /// Returns `None` when the index is out of bounds.
+    pub fn char_at(&self, idx: usize) -> Option<u16> {
+        match self.chars() {
+            Chars::Latin1(bytes) => bytes.get(idx).map(|&byte| u16::from(byte)),
+            Chars::Utf16(units) => units.get(idx).copied(),
+        }
+    }
+
+    /// Returns the code point starting at a UTF-16 index, like `codePointAt` in JavaScript.
+    ///
+    /// A surrogate pair starting at the index is combined into one code point, any other code
+    /// unit, including a lone surrogate, is returned as is. Returns `None` when the index is out
+    /// of bounds.
+    pub fn code_point_at(&self, idx: usize) -> Option<u32> {
+        let units = match self.chars() {
+            Chars::Latin1(bytes) => return bytes.get(idx).map(|&byte| u32::from(byte)),
+            Chars::Utf16(units) => units,
+        };
+        let first = *units.get(idx)?;
+        match (first, units.get(idx + 1)) {
+            (0xD800..=0xDBFF, Some(&second @ 0xDC00..=0xDFFF)) => {
+                Some(0x10000 + ((u32::from(first) - 0xD800) << 10) + (u32::from(second) - 0xDC00))
+            }
+            _ => Some(u32::from(first)),
+        }
+    }
+
+    /// Create a new string from a range of UTF-16 code units of this string, like `substring`
+    /// in JavaScript.
+    ///
+    /// Only the code units in the range are copied. Bounds past the end of the string are
+    /// clamped to its length and a range with its start after its end results in an empty
+    /// string.
+    pub fn substring<R: RangeBounds<usize>>(&self, range: R) -> Result<Self> {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(len);
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        }
+        .clamp(start, len);
+        let ctx = self.0.ctx.clone();
+        match self.chars() {
+            Chars::Latin1(bytes) => {
+                let units: Vec<u16> = bytes[start..end].iter().map(|&b| u16::from(b)).collect();
+                Self::from_utf16(ctx, &units)
+            }
+            Chars::Utf16(units) => Self::from_utf16(ctx, &units[start..end]),
+        }
+    }
+
+    fn chars(&self) -> Chars<'_> {
+        let mut len = 0;
+        let mut wide: c_int = 0;
+        unsafe {
+            let ptr = qjs::JS_GetStringBuffer(self.0.as_js_value(), &mut len, &mut wide);
+            if len == 0 {
+                return Chars::Latin1(&[]);
+            }
+            if wide != 0 {
+                Chars::Utf16(slice::from_raw_parts(ptr.cast(), len as _))
+            } else {
+                Chars::Latin1(slice::from_raw_parts(ptr.cast(), len as _))
+            }
+        }
     }
 
     /// Create a new JavaScript string from an Rust string.
@@ -35,6 +190,15 @@ impl<'js> String<'js> {
             String::from_js_value(ctx, js_val)
         })
     }
+
+    /// Create a new JavaScript string from UTF-16 code units, which may contain lone surrogates.
+    pub fn from_utf16(ctx: Ctx<'js>, units: &[u16]) -> Result<Self> {
+        Ok(unsafe {
+            let js_val = qjs::JS_NewStringUTF16(ctx.as_ptr(), units.as_ptr(), units.len() as _);
+            let js_val = ctx.handle_exception(js_val)?;
+            String::from_js_value(ctx, js_val)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -57,4 +221,59 @@ mod test {
             assert_eq!(text, "foobar".to_string());
         });
     }
+
+    #[test]
+    fn encodings() {
+        test_with(|ctx| {
+            let latin1: String = ctx.eval("'caf\\u00e9'").unwrap();
+            assert!(!latin1.is_wide());
+            assert_eq!(latin1.len(), 4);
+            assert_eq!(latin1.to_string().unwrap(), "café");
+            assert_eq!(latin1.to_wtf8().unwrap(), "café".as_bytes());
+
+            let emoji: String = ctx.eval("'a\\u{1F600}'").unwrap();
+            assert!(emoji.is_wide());
+            assert_eq!(emoji.len(), 3);
+            assert_eq!(emoji.to_utf16(), "a😀".encode_utf16().collect::<Vec<_>>());
+            assert_eq!(emoji.to_string().unwrap(), "a😀");
+
+            let lone: String = ctx.eval("'a\\u{1F600}'.slice(0, 2)").unwrap();
+            assert!(matches!(lone.to_string(), Err(Error::Utf8(_))));
+            assert_eq!(
+                lone.to_string_with(LoneSurrogates::Replace).unwrap(),
+                "a\u{FFFD}"
+            );
+            assert_eq!(lone.to_wtf8().unwrap(), b"a\xED\xA0\xBD");
+            assert_eq!(lone.to_utf16(), [0x61, 0xD83D]);
+
+            let string = String::from_utf16(ctx.clone(), &[0x61, 0xD83D]).unwrap();
+            let func: Function = ctx.eval("x => x.charCodeAt(1)").unwrap();
+            assert_eq!(func.call::<_, u16>((string,)).unwrap(), 0xD83D);
+        });
+    }
+
+    #[test]
+    fn indexing() {
+        test_with(|ctx| {
+            let latin1: String = ctx.eval("'caf\\u00e9'").unwrap();
+            assert_eq!(latin1.char_at(3), Some(0xE9));
+            assert_eq!(latin1.code_point_at(3), Some(0xE9));
+            assert_eq!(latin1.char_at(4), None);
+            let sub = latin1.substring(1..).unwrap();
+            assert!(!sub.is_wide());
+            assert_eq!(sub.to_string().unwrap(), "afé");
+
+            let emoji: String = ctx.eval("'a\\u{1F600}b'").unwrap();
+            assert_eq!(emoji.char_at(1), Some(0xD83D));
+            assert_eq!(emoji.code_point_at(1), Some(0x1F600));
+            assert_eq!(emoji.code_point_at(2), Some(0xDE00));
+            assert_eq!(emoji.code_point_at(4), None);
+            assert_eq!(emoji.substring(1..3).unwrap().to_string().unwrap(), "😀");
+            assert_eq!(emoji.substring(..2).unwrap().to_utf16(), [0x61, 0xD83D]);
+            assert_eq!(emoji.substring(3..10).unwrap().to_string().unwrap(), "b");
+            let (start, end) = (3, 1);
+            assert!(emoji.substring(start..end).unwrap().is_empty());
+            assert!(!emoji.substring(3..).unwrap().is_wide());
+        });
+    }
 }
@@ -18,27 +18,31 @@ use super::{array_buffer::RawArrayBuffer, Constructor};
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "array-buffer")))]
 pub trait TypedArrayItem: Copy {
     const CLASS_NAME: PredefinedAtom;
+    /// The QuickJS class id of the typed array, from the `JS_CLASS_*_ARRAY` enum in `quickjs.c`.
+    #[doc(hidden)]
+    const CLASS_ID: qjs::JSClassID;
 }
 
 macro_rules! typedarray_items {
-    ($($name:ident: $type:ty,)*) => {
+    ($($name:ident: $type:ty = $id:literal,)*) => {
         $(impl TypedArrayItem for $type {
             const CLASS_NAME: PredefinedAtom = PredefinedAtom::$name;
+            const CLASS_ID: qjs::JSClassID = $id;
         })*
     };
 }
 
 typedarray_items! {
-    Int8Array: i8,
-    Uint8Array: u8,
-    Int16Array: i16,
-    Uint16Array: u16,
-    Int32Array: i32,
-    Uint32Array: u32,
-    Float32Array: f32,
-    Float64Array: f64,
-    BigInt64Array: i64,
-    BigUint64Array: u64,
+    Int8Array: i8 = 22,
+    Uint8Array: u8 = 23,
+    Int16Array: i16 = 24,
+    Uint16Array: u16 = 25,
+    Int32Array: i32 = 26,
+    Uint32Array: u32 = 27,
+    Float32Array: f32 = 30,
+    Float64Array: f64 = 31,
+    BigInt64Array: i64 = 28,
+    BigUint64Array: u64 = 29,
 }
 
 /// Rust representation of a JavaScript objects of TypedArray classes.
@@ -101,6 +105,22 @@ impl<'js, T> TypedArray<'js, T> {
         Self::from_arraybuffer(ab)
     }
 
+    /// Returns whether the context has the typed array class of `T`.
+    ///
+    /// Checks the prototype of the class, so unlike looking up the constructor it doesn't depend
+    /// on the globals of the context.
+    pub(crate) fn is_available(ctx: &Ctx<'js>) -> bool
+    where
+        T: TypedArrayItem,
+    {
+        unsafe {
+            let proto = qjs::JS_GetClassProto(ctx.as_ptr(), T::CLASS_ID);
+            let available = qjs::JS_IsObject(proto);
+            qjs::JS_FreeValue(ctx.as_ptr(), proto);
+            available
+        }
+    }
+
     /// Get the length of the typed array in elements.
     pub fn len(&self) -> usize {
         //Self::get_raw(&self.0).expect("Not a TypedArray").0
@@ -335,6 +355,47 @@ impl<'js> Object<'js> {
 mod test {
     use crate::*;
 
+    fn class_proto<'js, T: value::typed_array::TypedArrayItem>(ctx: &Ctx<'js>) -> Value<'js> {
+        unsafe {
+            let proto = qjs::JS_GetClassProto(ctx.as_ptr(), T::CLASS_ID);
+            Value::from_js_value(ctx.clone(), proto)
+        }
+    }
+
+    #[test]
+    fn class_ids() {
+        test_with(|ctx| {
+            let protos: Vec<Value> = ctx
+                .eval(
+                    r#"
+                    [
+                        Int8Array, Uint8Array, Int16Array, Uint16Array, Int32Array, Uint32Array,
+                        Float32Array, Float64Array, BigInt64Array, BigUint64Array,
+                    ].map((x) => x.prototype)
+                    "#,
+                )
+                .unwrap();
+            let ids = [
+                class_proto::<i8>(&ctx),
+                class_proto::<u8>(&ctx),
+                class_proto::<i16>(&ctx),
+                class_proto::<u16>(&ctx),
+                class_proto::<i32>(&ctx),
+                class_proto::<u32>(&ctx),
+                class_proto::<f32>(&ctx),
+                class_proto::<f64>(&ctx),
+                class_proto::<i64>(&ctx),
+                class_proto::<u64>(&ctx),
+            ];
+            assert_eq!(protos, ids);
+            assert!(TypedArray::<u8>::is_available(&ctx));
+        });
+
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::base(&rt).unwrap();
+        ctx.with(|ctx| assert!(!TypedArray::<u8>::is_available(&ctx)));
+    }
+
     #[test]
     fn from_javascript_i8() {
         test_with(|ctx| {
@@ -0,0 +1,36 @@
+//! Evaluates scripts while failing an allocation chosen by the fuzzer.
+//!
+//! Run with `cargo fuzz run eval_oom`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rquickjs::{allocator::FailingAllocator, Context, Error, Runtime};
+
+fuzz_target!(|data: (u16, &str)| {
+    let (fail_at, source) = data;
+    let allocator = FailingAllocator::new();
+    let injector = allocator.injector();
+    let rt = Runtime::new_with_alloc(allocator).unwrap();
+    rt.set_max_stack_size(256 * 1024);
+    // Interrupt scripts which loop forever.
+    let mut fuel = 1000u32;
+    rt.set_interrupt_handler(Some(Box::new(move || {
+        fuel = fuel.saturating_sub(1);
+        fuel == 0
+    })));
+    let ctx = Context::full(&rt).unwrap();
+    ctx.with(|ctx| {
+        injector.fail_at(usize::from(fail_at) + 1);
+        match ctx.eval::<Vec<String>, _>(source) {
+            Ok(_) | Err(Error::Allocation | Error::Exception | Error::FromJs { .. }) => {}
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+        ctx.catch();
+    });
+
+    // The runtime must still be usable after an allocation failed.
+    injector.disable();
+    rt.set_interrupt_handler(None);
+    ctx.with(|ctx| assert_eq!(ctx.eval::<i32, _>("1 + 1").unwrap(), 2));
+});
@@ -0,0 +1,2 @@
+const answer = 6 * 7;
+answer
@@ -17,6 +17,9 @@ use crate::{
 #[derive(Debug, Default, Clone)]
 pub(crate) struct ClassConfig {
     pub frozen: bool,
+    pub exotic: bool,
+    pub indexed: bool,
+    pub async_drop: bool,
     pub crate_: Option<String>,
     pub rename: Option<String>,
     pub rename_all: Option<Case>,
@@ -24,6 +27,9 @@ pub(crate) struct ClassConfig {
 
 pub(crate) enum ClassOption {
     Frozen(FlagOption<kw::frozen>),
+    Exotic(FlagOption<kw::exotic>),
+    Indexed(FlagOption<kw::indexed>),
+    AsyncDrop(FlagOption<kw::async_drop>),
     Crate(ValueOption<Token![crate], LitStr>),
     Rename(ValueOption<kw::rename, LitStr>),
     RenameAll(ValueOption<kw::rename_all, Case>),
@@ -33,6 +39,12 @@ impl Parse for ClassOption {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         if input.peek(kw::frozen) {
             input.parse().map(Self::Frozen)
+        } else if input.peek(kw::exotic) {
+            input.parse().map(Self::Exotic)
+        } else if input.peek(kw::indexed) {
+            input.parse().map(Self::Indexed)
+        } else if input.peek(kw::async_drop) {
+            input.parse().map(Self::AsyncDrop)
         } else if input.peek(Token![crate]) {
             input.parse().map(Self::Crate)
         } else if input.peek(kw::rename) {
@@ -51,6 +63,15 @@ impl ClassConfig {
             ClassOption::Frozen(ref x) => {
                 self.frozen = x.is_true();
             }
+            ClassOption::Exotic(ref x) => {
+                self.exotic = x.is_true();
+            }
+            ClassOption::Indexed(ref x) => {
+                self.indexed = x.is_true();
+            }
+            ClassOption::AsyncDrop(ref x) => {
+                self.async_drop = x.is_true();
+            }
             ClassOption::Crate(ref x) => {
                 self.crate_ = Some(x.value.value());
             }
@@ -320,6 +341,29 @@ impl Class {
         let mutability = self.mutability();
         let props = self.expand_props(&crate_name);
         let reexpand = self.reexpand();
+        let async_finalizer = self.config().async_drop.then(|| {
+            quote! {
+                fn async_finalizer() -> Option<#crate_name::class::AsyncFinalizer>{
+                    Some(#crate_name::class::AsyncFinalizer::new::<Self>())
+                }
+            }
+        });
+        let exotic = match (self.config().exotic, self.config().indexed) {
+            (true, true) => abort!(
+                class_name,
+                "a class can't have both the `exotic` and the `indexed` flag"
+            ),
+            (true, false) => Some(quote!(new)),
+            (false, true) => Some(quote!(indexed)),
+            (false, false) => None,
+        }
+        .map(|constructor| {
+            quote! {
+                fn exotic() -> Option<#crate_name::class::ExoticMethods>{
+                    Some(#crate_name::class::ExoticMethods::#constructor::<Self>())
+                }
+            }
+        });
 
         quote! {
             #reexpand
@@ -354,6 +398,10 @@ impl Class {
                         let implementor = #crate_name::class::impl_::ConstructorCreate::<Self>::new();
                         (&implementor).create_constructor(ctx)
                     }
+
+                    #exotic
+
+                    #async_finalizer
                 }
 
                 impl #generics_with_lifetimes #crate_name::IntoJs<'js> for #class_name #generics{
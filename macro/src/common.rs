@@ -1,12 +1,12 @@
 use convert_case::Case as ConvertCase;
-use proc_macro2::Span;
+use proc_macro2::{Ident, Span, TokenStream};
 use proc_macro_crate::FoundCrate;
 use proc_macro_error::{abort, abort_call_site};
-use quote::{ToTokens, TokenStreamExt};
+use quote::{quote, ToTokens, TokenStreamExt};
 use syn::{
     fold::Fold,
     parse::{Parse, ParseStream},
-    Generics, Lifetime, LifetimeParam, LitStr, Type,
+    Expr, ExprLit, Generics, Lifetime, LifetimeParam, Lit, LitStr, Type,
 };
 
 /// prefix for getter implementations
@@ -101,6 +101,18 @@ pub(crate) fn crate_ident() -> String {
     }
 }
 
+/// Expand a property key on `object`, caching the atom of string literals in the runtime.
+pub fn expand_key(lib_crate: &Ident, object: &Ident, name: &Expr) -> TokenStream {
+    match name {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(_), ..
+        }) => quote! {
+            #lib_crate::Atom::from_static(#object.ctx().clone(), #name)?
+        },
+        _ => quote!(#name),
+    }
+}
+
 /// Add the 'js lifetime to a list of existing lifetimes, if it doesn't already exits.
 pub fn add_js_lifetime(generics: &Generics) -> Generics {
     let mut generics = generics.clone();
@@ -143,6 +155,9 @@ impl<'a> Fold for SelfReplacer<'a> {
 
 pub(crate) mod kw {
     syn::custom_keyword!(frozen);
+    syn::custom_keyword!(exotic);
+    syn::custom_keyword!(indexed);
+    syn::custom_keyword!(async_drop);
     syn::custom_keyword!(skip_trace);
     syn::custom_keyword!(rename);
     syn::custom_keyword!(rename_all);
@@ -157,4 +172,7 @@ pub(crate) mod kw {
     syn::custom_keyword!(prefix);
     syn::custom_keyword!(declare);
     syn::custom_keyword!(evaluate);
+    syn::custom_keyword!(default);
+    syn::custom_keyword!(options);
+    syn::custom_keyword!(readonly);
 }
@@ -4,7 +4,7 @@ use crate::common::crate_ident;
 use proc_macro2::TokenStream;
 use proc_macro_error::abort;
 use quote::{format_ident, quote};
-use rquickjs_core::{Context, Module, Result, Runtime};
+use rquickjs_core::{CatchResultExt, Context, Module, Result, Runtime};
 use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
@@ -52,39 +52,7 @@ pub fn embed(modules: EmbedModules) -> TokenStream {
             .map(|x| x.1.value())
             .unwrap_or_else(|| f.name.value());
 
-        let path = Path::new(&path);
-
-        let path = if path.is_relative() {
-            let full_path = Path::new(
-                &env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR should be set"),
-            )
-            .join(path);
-            match full_path.canonicalize() {
-                Ok(x) => x,
-                Err(e) => {
-                    abort!(
-                        f.name,
-                        "Error loading embedded js module from path `{}`: {}",
-                        full_path.display(),
-                        e
-                    );
-                }
-            }
-        } else {
-            path.to_owned()
-        };
-
-        let source = match std::fs::read_to_string(&path) {
-            Ok(x) => x,
-            Err(e) => {
-                abort!(
-                    f.name,
-                    "Error loading embedded js module from path `{}`: {}",
-                    path.display(),
-                    e
-                );
-            }
-        };
+        let source = read_source(&f.name, &path);
         files.push((f.name.value(), source));
     }
 
@@ -116,6 +84,70 @@ pub fn embed(modules: EmbedModules) -> TokenStream {
     expand(&res)
 }
 
+/// Read the source of an embedded file, relative paths are relative to the crate manifest.
+fn read_source(span: &LitStr, path: &str) -> String {
+    let path = Path::new(path);
+
+    let path = if path.is_relative() {
+        let full_path =
+            Path::new(&env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR should be set"))
+                .join(path);
+        match full_path.canonicalize() {
+            Ok(x) => x,
+            Err(e) => {
+                abort!(
+                    span,
+                    "Error loading embedded js file from path `{}`: {}",
+                    full_path.display(),
+                    e
+                );
+            }
+        }
+    } else {
+        path.to_owned()
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(e) => {
+            abort!(
+                span,
+                "Error loading embedded js file from path `{}`: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Implementation of the script macro
+pub fn embed_script(path: LitStr) -> TokenStream {
+    let source = read_source(&path, &path.value());
+
+    let res = (|| -> std::result::Result<Vec<u8>, String> {
+        let rt = Runtime::new().map_err(|e| e.to_string())?;
+        let ctx = Context::full(&rt).map_err(|e| e.to_string())?;
+        ctx.with(|ctx| ctx.compile(source).catch(&ctx).map_err(|e| e.to_string()))
+    })();
+
+    let bytecode = match res {
+        Ok(x) => x,
+        Err(e) => {
+            abort!(path, "Error compiling embedded js script: {}", e);
+        }
+    };
+
+    expand_script(&bytecode)
+}
+
+fn expand_script(bytecode: &[u8]) -> TokenStream {
+    let lib_crate = crate_ident();
+    let lib_crate = format_ident!("{}", lib_crate);
+    quote! {
+        unsafe { #lib_crate::context::CompiledScript::from_bytecode(&[#(#bytecode),*]) }
+    }
+}
+
 fn to_entries(modules: impl Iterator<Item = (String, Vec<u8>)>) -> Vec<(String, TokenStream)> {
     modules
         .map(|(name, data)| (name, quote! { &[#(#data),*] }))
@@ -161,7 +193,7 @@ pub fn expand(modules: &[(String, TokenStream)]) -> TokenStream {
 
 #[cfg(test)]
 mod test {
-    use super::{expand, to_entries, EmbedModules};
+    use super::{expand, expand_script, to_entries, EmbedModules};
     use quote::quote;
 
     #[cfg(feature = "phf")]
@@ -196,6 +228,15 @@ mod test {
         assert_eq_tokens!(tokens, expected);
     }
 
+    #[test]
+    fn test_expand_script() {
+        let tokens = expand_script(&[1u8, 2, 3, 4]);
+        let expected = quote! {
+            unsafe { rquickjs::context::CompiledScript::from_bytecode(&[1u8, 2u8, 3u8, 4u8]) }
+        };
+        assert_eq_tokens!(tokens, expected);
+    }
+
     #[test]
     fn parse() {
         let data = quote! {
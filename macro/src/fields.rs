@@ -18,6 +18,8 @@ pub struct FieldConfig {
     pub enumerable: bool,
     pub configurable: bool,
     pub skip_trace: bool,
+    pub readonly: bool,
+    pub skip: bool,
     pub rename: Option<String>,
 }
 
@@ -28,6 +30,8 @@ pub(crate) enum FieldOption {
     Enumerable(FlagOption<kw::enumerable>),
     Configurable(FlagOption<kw::configurable>),
     SkipTrace(FlagOption<kw::skip_trace>),
+    Readonly(FlagOption<kw::readonly>),
+    Skip(FlagOption<kw::skip>),
     Rename(ValueOption<kw::rename, LitStr>),
 }
 
@@ -43,6 +47,10 @@ impl Parse for FieldOption {
             input.parse().map(Self::Configurable)
         } else if input.peek(kw::skip_trace) {
             input.parse().map(Self::SkipTrace)
+        } else if input.peek(kw::readonly) {
+            input.parse().map(Self::Readonly)
+        } else if input.peek(kw::skip) {
+            input.parse().map(Self::Skip)
         } else if input.peek(kw::rename) {
             input.parse().map(Self::Rename)
         } else {
@@ -89,6 +97,12 @@ impl FieldConfig {
             FieldOption::SkipTrace(ref x) => {
                 self.skip_trace = x.is_true();
             }
+            FieldOption::Readonly(ref x) => {
+                self.readonly = x.is_true();
+            }
+            FieldOption::Skip(ref x) => {
+                self.skip = x.is_true();
+            }
             FieldOption::Rename(ref x) => {
                 self.rename = Some(x.value.value());
             }
@@ -148,15 +162,20 @@ impl Field {
 }
 
 impl Field {
+    /// Returns whether the field has a getter on the JavaScript side.
+    pub fn has_getter(&self) -> bool {
+        self.config.get && !self.config.skip
+    }
+
+    /// Returns whether the field has a setter on the JavaScript side.
+    pub fn has_setter(&self) -> bool {
+        self.config.set && !self.config.readonly && !self.config.skip
+    }
+
     pub fn expand_prop_config(&self) -> TokenStream {
-        let mut res = TokenStream::new();
-        if self.config.configurable {
-            res.extend(quote!(.configurable()));
-        }
-        if self.config.enumerable {
-            res.extend(quote!(.enumerable()));
-        }
-        res
+        let configurable = self.config.configurable;
+        let enumerable = self.config.enumerable;
+        quote!(.set_configurable(#configurable).set_enumerable(#enumerable))
     }
 
     pub fn expand_trace_body_named(&self, lib_crate: &Ident) -> TokenStream {
@@ -182,7 +201,7 @@ impl Field {
     }
 
     pub fn expand_property_named(&self, crate_name: &Ident, case: Option<Case>) -> TokenStream {
-        if !(self.config.get || self.config.set) {
+        if !(self.has_getter() || self.has_setter()) {
             return TokenStream::new();
         }
 
@@ -200,12 +219,15 @@ impl Field {
         };
 
         quote! {
-            proto.prop(#name, #accessor #prop_config)?;
+            proto.prop(
+                #crate_name::Atom::from_static(proto.ctx().clone(), #name)?,
+                #accessor #prop_config
+            )?;
         }
     }
 
     pub fn expand_property_unnamed(&self, crate_name: &Ident, name: u32) -> TokenStream {
-        if !(self.config.get || self.config.set) {
+        if !(self.has_getter() || self.has_setter()) {
             return TokenStream::new();
         }
 
@@ -225,7 +247,7 @@ impl Field {
     }
 
     pub fn expand_accessor(&self, field: &Ident, crate_name: &Ident, ty: &Type) -> TokenStream {
-        if self.config.get && self.config.set {
+        if self.has_getter() && self.has_setter() {
             quote! {
                 #crate_name::object::Accessor::new(
                     |this: #crate_name::function::This<#crate_name::class::OwnedBorrow<'js, Self>>|{
@@ -236,7 +258,7 @@ impl Field {
                     }
                 )
             }
-        } else if self.config.get {
+        } else if self.has_getter() {
             quote! {
                 #crate_name::object::Accessor::new_get(
                     |this: #crate_name::function::This<#crate_name::class::OwnedBorrow<'js, Self>>|{
@@ -244,7 +266,7 @@ impl Field {
                     },
                 )
             }
-        } else if self.config.set {
+        } else if self.has_setter() {
             quote! {
                 #crate_name::object::Accessor::new_set(
                     |mut this: #crate_name::function::This<#crate_name::class::OwnedBorrowMut<'js, Self>>, v: #ty|{
@@ -1,17 +1,19 @@
 use convert_case::Casing;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use proc_macro_error::abort;
 use quote::{format_ident, quote};
 use syn::{
+    ext::IdentExt,
     fold::Fold,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
+    spanned::Spanned,
     token::Comma,
-    FnArg, LitStr, Signature, Token, Type, Visibility,
+    Expr, FnArg, LitStr, Pat, Signature, Token, Type, Visibility,
 };
 
 use crate::{
-    attrs::{take_attributes, OptionList, ValueOption},
+    attrs::{take_attributes, FlagOption, OptionList, ValueOption},
     common::{crate_ident, kw, AbortResultExt, Case, SelfReplacer, BASE_PREFIX},
 };
 
@@ -42,6 +44,58 @@ impl Parse for FunctionOption {
     }
 }
 
+/// An option which can be set on a single parameter of a function with `#[qjs(option)]`.
+pub(crate) enum ParamOption {
+    Default(DefaultOption),
+    Options(FlagOption<kw::options>),
+}
+
+impl Parse for ParamOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::default) {
+            input.parse().map(Self::Default)
+        } else if input.peek(kw::options) {
+            input.parse().map(Self::Options)
+        } else {
+            Err(syn::Error::new(input.span(), "invalid parameter attribute"))
+        }
+    }
+}
+
+/// The `default` parameter option, either a flag or assigned an expression in a string literal.
+pub(crate) struct DefaultOption {
+    pub _key: kw::default,
+    pub value: Option<(syn::token::Eq, LitStr)>,
+}
+
+impl Parse for DefaultOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: kw::default = input.parse()?;
+        let value = input
+            .peek(Token![=])
+            .then(|| {
+                let assign: Token![=] = input.parse()?;
+                let value: LitStr = input.parse()?;
+                syn::Result::Ok((assign, value))
+            })
+            .transpose()?;
+
+        Ok(DefaultOption { _key: key, value })
+    }
+}
+
+impl DefaultOption {
+    /// Returns the expression which produces the default value.
+    pub fn expr(&self) -> syn::Result<TokenStream> {
+        if let Some((_, ref lit)) = self.value {
+            let expr: Expr = lit.parse()?;
+            Ok(quote!(#expr))
+        } else {
+            Ok(quote!(::core::default::Default::default()))
+        }
+    }
+}
+
 impl FunctionConfig {
     pub fn apply(&mut self, option: &FunctionOption) {
         match option {
@@ -106,7 +160,7 @@ pub(crate) fn expand(options: OptionList<FunctionOption>, mut item: syn::ItemFn)
     let crate_name = format_ident!("{}", config.crate_name());
     let prefix = config.prefix.as_deref().unwrap_or(BASE_PREFIX);
 
-    let func = JsFunction::new(item.vis.clone(), &item.sig, None);
+    let func = JsFunction::new(item.vis.clone(), &mut item.sig, None);
 
     let carry_type = func.expand_carry_type(prefix);
     let impl_ = func.expand_to_js_function_impl(prefix, &crate_name);
@@ -134,14 +188,17 @@ pub(crate) struct JsFunction {
 }
 
 impl JsFunction {
-    pub fn new(vis: Visibility, sig: &Signature, self_type: Option<&Type>) -> Self {
+    /// Parses the function signature.
+    ///
+    /// Removes any `qjs` attributes from the parameters in the signature.
+    pub fn new(vis: Visibility, sig: &mut Signature, self_type: Option<&Type>) -> Self {
         let Signature {
             ref asyncness,
             ref unsafety,
             ref abi,
             ref variadic,
             ref ident,
-            ref inputs,
+            ref mut inputs,
             ..
         } = sig;
 
@@ -205,7 +262,9 @@ impl JsFunction {
     }
 
     pub fn expand_to_js_function_body(&self, lib_crate: &Ident) -> TokenStream {
-        let arg_extract = self.params.expand_extract(lib_crate);
+        let arg_extract = self
+            .params
+            .expand_extract(lib_crate, &self.name.to_string());
         let arg_apply = self.params.expand_apply();
         let rust_function = &self.rust_function;
 
@@ -265,6 +324,14 @@ pub(crate) struct JsParams {
 }
 
 impl JsParams {
+    /// Dereferences the receiver an additional time when applying it to the function, for
+    /// calling methods of a trait object stored in a box.
+    pub fn deref_receiver(&mut self) {
+        for param in self.params.iter_mut().filter(|x| x.is_this) {
+            param.deref_this = true;
+        }
+    }
+
     pub fn expand_apply(&self) -> TokenStream {
         let iter = self.params.iter().map(|x| x.expand_apply());
         quote! { #(#iter),* }
@@ -277,8 +344,11 @@ impl JsParams {
             .collect()
     }
 
-    pub fn expand_extract(&self, lib_crate: &Ident) -> TokenStream {
-        let res = self.params.iter().map(|x| x.expand_extract(lib_crate));
+    pub fn expand_extract(&self, lib_crate: &Ident, function: &str) -> TokenStream {
+        let res = self
+            .params
+            .iter()
+            .map(|x| x.expand_extract(lib_crate, function));
         quote!(#(#res)*)
     }
 }
@@ -294,8 +364,13 @@ pub(crate) enum ParamKind {
 pub(crate) struct JsParam {
     kind: ParamKind,
     number: usize,
+    /// The name of the parameter if it is bound to an identifier.
+    name: Option<String>,
     tokens: TokenStream,
     is_this: bool,
+    default: Option<TokenStream>,
+    is_options: bool,
+    deref_this: bool,
 }
 
 impl JsParam {
@@ -315,8 +390,16 @@ impl JsParam {
             ParamKind::Borrow => quote!(&*#t),
             ParamKind::BorrowMut => quote!(&mut *#t),
         };
-        if self.is_this {
+        if self.is_this && self.deref_this {
+            match self.kind {
+                ParamKind::Value => quote!(#t.0),
+                ParamKind::Borrow => quote!(&**#t.0),
+                ParamKind::BorrowMut => quote!(&mut **#t.0),
+            }
+        } else if self.is_this {
             quote!(#apply.0)
+        } else if let Some(default) = self.default.as_ref() {
+            quote!(#apply.unwrap_or_else(|| #default))
         } else {
             apply
         }
@@ -333,29 +416,132 @@ impl JsParam {
             quote!(
                 #lib_crate::function::This<#ty>
             )
+        } else if self.default.is_some() {
+            quote!(
+                #lib_crate::function::Opt<#ty>
+            )
         } else {
             ty
         }
     }
 
-    pub fn expand_extract(&self, lib_crate: &Ident) -> TokenStream {
+    pub fn expand_extract(&self, lib_crate: &Ident, function: &str) -> TokenStream {
         let ty = self.expand_type(lib_crate);
         let binding = self.expand_binding();
+        if self.is_this {
+            return quote! {
+                let #binding = <#ty as #lib_crate::function::FromParam>::from_param(&mut _params)?;
+            };
+        }
+        let t = &self.tokens;
+        let name = match self.name.as_ref() {
+            Some(name) => quote!(Some(#name)),
+            None => quote!(None),
+        };
+        let expected = type_name(t);
+        let in_argument = quote! {
+            .map_err(|error| error.in_argument(#function, _index, #name, #expected))
+        };
+        if self.is_options {
+            return quote! {
+                let _index = _params.index();
+                let #binding = if _params.is_empty() {
+                    None
+                } else {
+                    let arg = _params.arg();
+                    if arg.is_nullish() {
+                        None
+                    } else if arg.is_object() {
+                        Some(<#t as #lib_crate::FromJs>::from_js(&ctx, arg)#in_argument?)
+                    } else {
+                        return Err(#lib_crate::Error::new_from_js(arg.type_name(), "options object")
+                            .in_argument(#function, _index, #name, #expected));
+                    }
+                };
+            };
+        }
+        if self.default.is_some() {
+            // Like `Opt` but an explicit `undefined` is also treated as a missing argument, matching
+            // JavaScript default parameter semantics.
+            return quote! {
+                let _index = _params.index();
+                let #binding = if _params.is_empty() {
+                    None
+                } else {
+                    let arg = _params.arg();
+                    if arg.is_undefined() {
+                        None
+                    } else {
+                        Some(<#t as #lib_crate::FromJs>::from_js(&ctx, arg)#in_argument?)
+                    }
+                };
+            };
+        }
         quote! {
-            let #binding = <#ty as #lib_crate::function::FromParam>::from_param(&mut _params)?;
+            let _index = _params.index();
+            let #binding = <#ty as #lib_crate::function::FromParam>::from_param(&mut _params)#in_argument?;
         }
     }
 }
 
+/// Returns the name of a type as written in the source, for error messages.
+pub(crate) fn type_name(ty: &TokenStream) -> String {
+    ty.to_string()
+        .replace(" :: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+}
+
+/// Returns whether a parameter of this type doesn't require an argument, so it can follow
+/// parameters with a default value.
+fn is_optional_param(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    matches!(
+        path.path.segments.last(),
+        Some(x) if x.ident == "Ctx" || x.ident == "Opt" || x.ident == "Rest"
+    )
+}
+
 impl JsParams {
-    pub fn from_input(inputs: &Punctuated<FnArg, Comma>, self_type: Option<&Type>) -> Self {
+    pub fn from_input(inputs: &mut Punctuated<FnArg, Comma>, self_type: Option<&Type>) -> Self {
         let mut types = Vec::<JsParam>::new();
 
         let mut self_replacer = self_type.map(SelfReplacer::with);
 
-        for (idx, arg) in inputs.iter().enumerate() {
+        let mut options_span: Option<Span> = None;
+        let mut has_default = false;
+
+        for (idx, arg) in inputs.iter_mut().enumerate() {
             match arg {
                 FnArg::Typed(pat) => {
+                    if let Some(span) = options_span {
+                        abort!(span, "the options parameter must be the last parameter");
+                    }
+
+                    let mut default = None;
+                    let mut is_options = false;
+                    take_attributes(&mut pat.attrs, |attr| {
+                        if !attr.path().is_ident("qjs") {
+                            return Ok(false);
+                        }
+
+                        let options: OptionList<ParamOption> = attr.parse_args()?;
+                        for option in options.0.iter() {
+                            match option {
+                                ParamOption::Default(x) => default = Some(x.expr()?),
+                                ParamOption::Options(x) => is_options = x.is_true(),
+                            }
+                        }
+
+                        Ok(true)
+                    })
+                    .unwrap_or_abort();
+
                     let (stream, kind) = match *pat.ty {
                         Type::Reference(ref borrow) => {
                             let ty = (*borrow.elem).clone();
@@ -388,11 +574,44 @@ impl JsParams {
                         }
                     };
 
+                    if is_options {
+                        if default.is_some() {
+                            abort!(pat, "an options parameter can't also have a default value");
+                        }
+                        options_span = Some(pat.span());
+                        default = Some(quote!(::core::default::Default::default()));
+                    }
+
+                    if default.is_some() && !matches!(kind, ParamKind::Value) {
+                        abort!(
+                            pat.ty,
+                            "default values are not supported for borrowed parameters"
+                        );
+                    }
+
+                    if default.is_some() {
+                        has_default = true;
+                    } else if has_default && !is_optional_param(&pat.ty) {
+                        abort!(
+                            pat,
+                            "parameters with a default value must come after the other parameters"
+                        );
+                    }
+
+                    let name = match *pat.pat {
+                        Pat::Ident(ref x) => Some(x.ident.unraw().to_string()),
+                        _ => None,
+                    };
+
                     types.push(JsParam {
                         kind,
                         tokens: stream,
                         number: idx,
+                        name,
                         is_this: false,
+                        default,
+                        is_options,
+                        deref_this: false,
                     });
                 }
                 FnArg::Receiver(recv) => {
@@ -412,8 +631,12 @@ impl JsParams {
                         types.push(JsParam {
                             kind,
                             number: idx,
+                            name: None,
                             tokens: stream,
                             is_this: true,
+                            default: None,
+                            is_options: false,
+                            deref_this: false,
                         })
                     } else {
                         abort!(
@@ -0,0 +1,371 @@
+use convert_case::Casing;
+use proc_macro2::{Ident, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    FnArg, ItemTrait, LitStr, ReturnType, Token, TraitItem, TraitItemFn, Type, TypeParamBound,
+    Visibility,
+};
+
+use crate::{
+    attrs::{take_attributes, FlagOption, OptionList, ValueOption},
+    common::{crate_ident, kw, AbortResultExt, Case},
+    function::JsFunction,
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct InterfaceConfig {
+    pub crate_: Option<String>,
+    pub rename: Option<String>,
+    pub rename_all: Option<Case>,
+}
+
+pub(crate) enum InterfaceOption {
+    Crate(ValueOption<Token![crate], LitStr>),
+    Rename(ValueOption<kw::rename, LitStr>),
+    RenameAll(ValueOption<kw::rename_all, Case>),
+}
+
+impl Parse for InterfaceOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![crate]) {
+            input.parse().map(Self::Crate)
+        } else if input.peek(kw::rename) {
+            input.parse().map(Self::Rename)
+        } else if input.peek(kw::rename_all) {
+            input.parse().map(Self::RenameAll)
+        } else {
+            Err(syn::Error::new(input.span(), "invalid interface attribute"))
+        }
+    }
+}
+
+impl InterfaceConfig {
+    pub fn apply(&mut self, option: &InterfaceOption) {
+        match option {
+            InterfaceOption::Crate(ref x) => {
+                self.crate_ = Some(x.value.value());
+            }
+            InterfaceOption::Rename(ref x) => {
+                self.rename = Some(x.value.value());
+            }
+            InterfaceOption::RenameAll(ref x) => {
+                self.rename_all = Some(x.value);
+            }
+        }
+    }
+
+    pub fn crate_name(&self) -> String {
+        self.crate_.clone().unwrap_or_else(crate_ident)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct InterfaceMethodConfig {
+    pub skip: bool,
+    pub rename: Option<String>,
+}
+
+pub(crate) enum InterfaceMethodOption {
+    Skip(FlagOption<kw::skip>),
+    Rename(ValueOption<kw::rename, LitStr>),
+}
+
+impl Parse for InterfaceMethodOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::skip) {
+            input.parse().map(Self::Skip)
+        } else if input.peek(kw::rename) {
+            input.parse().map(Self::Rename)
+        } else {
+            Err(syn::Error::new(
+                input.span(),
+                "invalid interface method attribute",
+            ))
+        }
+    }
+}
+
+impl InterfaceMethodConfig {
+    pub fn apply(&mut self, option: &InterfaceMethodOption) {
+        match option {
+            InterfaceMethodOption::Skip(ref x) => {
+                self.skip = x.is_true();
+            }
+            InterfaceMethodOption::Rename(ref x) => {
+                self.rename = Some(x.value.value());
+            }
+        }
+    }
+}
+
+/// A method of the trait which is accessible from JavaScript.
+struct InterfaceMethod {
+    config: InterfaceMethodConfig,
+    function: JsFunction,
+    item: TraitItemFn,
+}
+
+impl InterfaceMethod {
+    fn js_name(&self, case: Option<Case>) -> String {
+        if let Some(x) = self.config.rename.as_ref() {
+            return x.clone();
+        }
+        let name = self.function.name.to_string();
+        if let Some(case) = case {
+            return name.to_case(case.to_convert_case());
+        }
+        name
+    }
+
+    /// Expands the implementation of the method for the adapter which calls the JavaScript
+    /// object.
+    fn expand_adapter_method(&self, crate_name: &Ident, js_name: &str) -> TokenStream {
+        let mut sig = self.item.sig.clone();
+        let mut args = Vec::new();
+        for (idx, input) in sig.inputs.iter_mut().enumerate() {
+            if let FnArg::Typed(pat) = input {
+                let arg = format_ident!("arg_{idx}");
+                *pat.pat = syn::parse_quote!(#arg);
+                args.push(arg);
+            }
+        }
+
+        let ok = result_ok_type(&sig.output).expect("checked by the caller");
+        quote! {
+            #sig {
+                let res = (|| -> #crate_name::Result<#ok> {
+                    let name = #crate_name::Atom::from_static(self.0.ctx().clone(), #js_name)?;
+                    let func: #crate_name::Function<'js> = self.0.get(name)?;
+                    func.call((#crate_name::function::This(self.0.clone()), #(#args,)*))
+                })();
+                res.map_err(::core::convert::Into::into)
+            }
+        }
+    }
+}
+
+/// Returns the ok type of the return type if it is a `Result`.
+fn result_ok_type(output: &ReturnType) -> Option<&Type> {
+    let ReturnType::Type(_, ref ty) = output else {
+        return None;
+    };
+    let Type::Path(ref path) = **ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(ref args) = last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+pub(crate) fn expand(options: OptionList<InterfaceOption>, mut item: ItemTrait) -> TokenStream {
+    let mut config = InterfaceConfig::default();
+    for option in options.0.iter() {
+        config.apply(option)
+    }
+
+    take_attributes(&mut item.attrs, |attr| {
+        if !attr.path().is_ident("qjs") {
+            return Ok(false);
+        }
+
+        let options: OptionList<InterfaceOption> = attr.parse_args()?;
+        for option in options.0.iter() {
+            config.apply(option)
+        }
+
+        Ok(true)
+    })
+    .unwrap_or_abort();
+
+    if let Some(param) = item.generics.params.first() {
+        abort!(param, "#[interface] can't be applied to generic traits");
+    }
+    if let Some(bound) = item
+        .supertraits
+        .iter()
+        .find(|x| !matches!(x, TypeParamBound::Lifetime(_)))
+    {
+        abort!(
+            bound,
+            "#[interface] can't be applied to traits with supertraits"
+        );
+    }
+
+    let trait_name = item.ident.clone();
+    let crate_name = format_ident!("{}", config.crate_name());
+    // Implementations are traced when they are owned by JavaScript.
+    item.supertraits
+        .push(syn::parse_quote!(for<'js> #crate_name::class::Trace<'js>));
+    let self_ty: Type = syn::parse_quote!(Box<dyn #trait_name>);
+    let prefix = format!("__impl_{}_", trait_name);
+
+    let mut methods = Vec::new();
+    for trait_item in item.items.iter_mut() {
+        let TraitItem::Fn(func) = trait_item else {
+            continue;
+        };
+
+        let mut method_config = InterfaceMethodConfig::default();
+        take_attributes(&mut func.attrs, |attr| {
+            if !attr.path().is_ident("qjs") {
+                return Ok(false);
+            }
+
+            let options: OptionList<InterfaceMethodOption> = attr.parse_args()?;
+            for option in options.0.iter() {
+                method_config.apply(option)
+            }
+
+            Ok(true)
+        })
+        .unwrap_or_abort();
+
+        let has_receiver = matches!(func.sig.inputs.first(), Some(FnArg::Receiver(_)));
+        if method_config.skip || !has_receiver {
+            if func.default.is_none() {
+                abort!(
+                    func.sig.ident,
+                    "methods which are not accessible from JavaScript need a default implementation"
+                );
+            }
+            continue;
+        }
+
+        if let Some(param) = func.sig.generics.params.first() {
+            abort!(
+                param,
+                "generic methods can't be accessed from JavaScript, mark the method with #[qjs(skip)]"
+            );
+        }
+        if let Some(FnArg::Receiver(recv)) = func.sig.inputs.first() {
+            if recv.reference.is_none() {
+                abort!(
+                    recv,
+                    "methods taking self by value can't be accessed from JavaScript"
+                );
+            }
+        }
+        if result_ok_type(&func.sig.output).is_none() {
+            abort!(
+                func.sig,
+                "methods accessible from JavaScript must return a `Result` as the JavaScript implementation can fail, mark the method with #[qjs(skip)]"
+            );
+        }
+
+        let mut function = JsFunction::new(Visibility::Inherited, &mut func.sig, Some(&self_ty));
+        let ident = &func.sig.ident;
+        function.rust_function = quote!(<dyn #trait_name as #trait_name>::#ident);
+        function.params.deref_receiver();
+
+        methods.push(InterfaceMethod {
+            config: method_config,
+            function,
+            item: func.clone(),
+        });
+    }
+
+    let javascript_name = config
+        .rename
+        .clone()
+        .unwrap_or_else(|| trait_name.to_string());
+    let module_name = format_ident!("__impl_interface_{}_", trait_name);
+
+    let carry_types = methods.iter().map(|x| {
+        let carry_type = x.function.expand_carry_type(&prefix);
+        let impl_ = x.function.expand_to_js_function_impl(&prefix, &crate_name);
+        let into_js = x.function.expand_into_js_impl(&prefix, &crate_name);
+        quote! {
+            #carry_type
+            #impl_
+            #into_js
+        }
+    });
+
+    let proto_apply = methods.iter().map(|x| {
+        let name = x.js_name(config.rename_all);
+        let carry_name = x.function.expand_carry_type_name(&prefix);
+        quote! {
+            proto.set(#name, #carry_name)?;
+        }
+    });
+
+    let adapter_methods = methods.iter().map(|x| {
+        let name = x.js_name(config.rename_all);
+        x.expand_adapter_method(&crate_name, &name)
+    });
+
+    quote! {
+        #item
+
+        #[allow(non_snake_case)]
+        mod #module_name {
+            pub use super::*;
+
+            #(#carry_types)*
+
+            impl<'js> #crate_name::class::Trace<'js> for #self_ty {
+                fn trace<'a>(&self, tracer: #crate_name::class::Tracer<'a, 'js>) {
+                    <dyn #trait_name as #crate_name::class::Trace<'js>>::trace(&**self, tracer)
+                }
+            }
+
+            impl<'js> #crate_name::class::JsClass<'js> for #self_ty {
+                const NAME: &'static str = #javascript_name;
+
+                type Mutable = #crate_name::class::Writable;
+
+                fn class_id() -> &'static #crate_name::class::ClassId {
+                    static ID: #crate_name::class::ClassId = #crate_name::class::ClassId::new();
+                    &ID
+                }
+
+                fn prototype(ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::Object<'js>>> {
+                    let proto = #crate_name::Object::new(ctx.clone())?;
+                    #(#proto_apply)*
+                    Ok(Some(proto))
+                }
+
+                fn constructor(_ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::function::Constructor<'js>>> {
+                    Ok(None)
+                }
+            }
+
+            impl<'js> #crate_name::IntoJs<'js> for #self_ty {
+                fn into_js(self, ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<#crate_name::Value<'js>> {
+                    let cls = #crate_name::class::Class::<Self>::instance(ctx.clone(), self)?;
+                    #crate_name::IntoJs::into_js(cls, ctx)
+                }
+            }
+
+            /// Implements the trait by calling the methods of a JavaScript object.
+            pub struct JsAdapter<'js>(pub #crate_name::Object<'js>);
+
+            // The adapter is never owned by JavaScript as it isn't `'static`, the object is kept
+            // alive by its reference count.
+            impl<'js, 'a> #crate_name::class::Trace<'a> for JsAdapter<'js> {
+                fn trace<'b>(&self, _tracer: #crate_name::class::Tracer<'b, 'a>) {}
+            }
+
+            impl<'js> #trait_name for JsAdapter<'js> {
+                #(#adapter_methods)*
+            }
+
+            impl<'js> #crate_name::FromJs<'js> for Box<dyn #trait_name + 'js> {
+                fn from_js(_ctx: &#crate_name::Ctx<'js>, value: #crate_name::Value<'js>) -> #crate_name::Result<Self> {
+                    let object = #crate_name::Object::from_value(value)?;
+                    Ok(Box::new(JsAdapter(object)))
+                }
+            }
+        }
+    }
+}
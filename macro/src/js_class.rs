@@ -0,0 +1,271 @@
+use proc_macro2::{Ident, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    Data, DataStruct, DeriveInput, Visibility,
+};
+
+use crate::{
+    attrs::{take_attributes, FlagOption, OptionList},
+    class::{ClassConfig, ClassOption},
+    common::{add_js_lifetime, kw, AbortResultExt},
+    fields::{Field, Fields},
+    function::type_name,
+};
+
+pub(crate) enum JsClassOption {
+    Class(ClassOption),
+    Constructor(FlagOption<kw::constructor>),
+}
+
+impl Parse for JsClassOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::constructor) {
+            input.parse().map(Self::Constructor)
+        } else {
+            input.parse().map(Self::Class)
+        }
+    }
+}
+
+/// Marks the fields which are accessible from JavaScript.
+///
+/// Public fields get a getter and, unless the field is read-only or the class is frozen, a setter.
+fn expose_fields(fields: &mut [Field], frozen: bool) {
+    for field in fields.iter_mut() {
+        if matches!(field.vis, Visibility::Public(_)) {
+            field.config.get = true;
+            field.config.set = !frozen;
+        }
+    }
+}
+
+/// Expands the `IntoJsFunc` implementation for the generated constructor.
+///
+/// The constructor takes an argument for every exposed field in declaration order, the remaining
+/// fields are initialized with their default value.
+fn expand_constructor(
+    crate_name: &Ident,
+    class_name: &Ident,
+    carry_name: &Ident,
+    fields: &Fields,
+) -> TokenStream {
+    let (fields, named) = match fields {
+        Fields::Named(x) => (x.as_slice(), true),
+        Fields::Unnamed(x) => (x.as_slice(), false),
+        Fields::Unit => (&[][..], true),
+    };
+
+    let exposed = fields
+        .iter()
+        .filter(|x| x.has_getter() || x.has_setter())
+        .collect::<Vec<_>>();
+    let arg_types = exposed.iter().map(|x| &x.ty).collect::<Vec<_>>();
+    let arg_type_tuple = quote!((#(#arg_types,)*));
+
+    let function = class_name.to_string();
+    let extract = exposed.iter().enumerate().map(|(idx, field)| {
+        let binding = format_ident!("tmp_{idx}");
+        let ty = &field.ty;
+        let name = match field.ident.as_ref() {
+            Some(name) => {
+                let name = name.unraw().to_string();
+                quote!(Some(#name))
+            }
+            None => quote!(None),
+        };
+        let expected = type_name(&ty.to_token_stream());
+        quote! {
+            let _index = _params.index();
+            let #binding = <#ty as #crate_name::function::FromParam>::from_param(&mut _params)
+                .map_err(|error| error.in_argument(#function, _index, #name, #expected))?;
+        }
+    });
+
+    let mut arg_idx = 0;
+    let init = fields.iter().map(|field| {
+        let value = if field.has_getter() || field.has_setter() {
+            let binding = format_ident!("tmp_{arg_idx}");
+            arg_idx += 1;
+            quote!(#binding)
+        } else {
+            quote!(::core::default::Default::default())
+        };
+        if let Some(ident) = field.ident.as_ref() {
+            quote!(#ident: #value)
+        } else {
+            value
+        }
+    });
+    let init = if named {
+        quote!(#class_name { #(#init),* })
+    } else {
+        quote!(#class_name ( #(#init),* ))
+    };
+
+    quote! {
+        #[allow(non_camel_case_types)]
+        pub struct #carry_name;
+
+        impl<'js> #crate_name::function::IntoJsFunc<'js,#arg_type_tuple> for #carry_name {
+            fn param_requirements() -> #crate_name::function::ParamRequirement {
+                #crate_name::function::ParamRequirement::none()
+                #(.combine(<#arg_types as #crate_name::function::FromParam>::param_requirement()))*
+            }
+
+            fn call<'a>(&self, params: #crate_name::function::Params<'a,'js>) -> #crate_name::Result<#crate_name::Value<'js>>{
+                let ctx = params.ctx().clone();
+                params.check_params(Self::param_requirements())?;
+                let mut _params = params.access();
+                #(#extract)*
+                #crate_name::IntoJs::into_js(#init, &ctx)
+            }
+        }
+    }
+}
+
+pub(crate) fn expand(mut input: DeriveInput) -> TokenStream {
+    let mut config = ClassConfig::default();
+    let mut constructor = false;
+
+    take_attributes(&mut input.attrs, |attr| {
+        if !attr.path().is_ident("qjs") {
+            return Ok(false);
+        }
+
+        let options: OptionList<JsClassOption> = attr.parse_args()?;
+        for option in options.0.iter() {
+            match option {
+                JsClassOption::Class(x) => config.apply(x),
+                JsClassOption::Constructor(x) => constructor = x.is_true(),
+            }
+        }
+        Ok(true)
+    })
+    .unwrap_or_abort();
+
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = input;
+
+    let Data::Struct(DataStruct { fields, .. }) = data else {
+        abort!(ident, "JsClass can only be derived for structs");
+    };
+
+    let mut fields = Fields::from_fields(fields);
+    match fields {
+        Fields::Named(ref mut x) | Fields::Unnamed(ref mut x) => expose_fields(x, config.frozen),
+        Fields::Unit => {}
+    }
+
+    let crate_name = format_ident!("{}", config.crate_name());
+    let javascript_name = config.rename.clone().unwrap_or_else(|| ident.to_string());
+    let module_name = format_ident!("__impl_js_class_{}_", ident);
+    let generics_with_lifetimes = add_js_lifetime(&generics);
+
+    let mutability = if config.frozen {
+        quote!(Readable)
+    } else {
+        quote!(Writable)
+    };
+
+    let props = match fields {
+        Fields::Named(ref x) => {
+            let props = x
+                .iter()
+                .map(|x| x.expand_property_named(&crate_name, config.rename_all));
+            quote!(#(#props)*)
+        }
+        Fields::Unnamed(ref x) => {
+            let props = x
+                .iter()
+                .enumerate()
+                .map(|(idx, x)| x.expand_property_unnamed(&crate_name, idx.try_into().unwrap()));
+            quote!(#(#props)*)
+        }
+        Fields::Unit => TokenStream::new(),
+    };
+
+    let (constructor_impl, constructor_body) = if constructor {
+        if generics.type_params().next().is_some() || generics.const_params().next().is_some() {
+            abort!(
+                generics,
+                "a constructor can't be generated for structs with generic parameters"
+            );
+        }
+        let carry_name = format_ident!("__impl_constructor_{}", ident);
+        let constructor_impl = expand_constructor(&crate_name, &ident, &carry_name, &fields);
+        let constructor_body = quote! {
+            let constr = #crate_name::function::Constructor::new_class::<Self,_,_>(ctx.clone(),#carry_name)?;
+            Ok(Some(constr))
+        };
+        (constructor_impl, constructor_body)
+    } else {
+        let constructor_body = quote! {
+            use #crate_name::class::impl_::ConstructorCreator;
+
+            let implementor = #crate_name::class::impl_::ConstructorCreate::<Self>::new();
+            (&implementor).create_constructor(ctx)
+        };
+        (TokenStream::new(), constructor_body)
+    };
+
+    quote! {
+        #[allow(non_snake_case)]
+        mod #module_name{
+            pub use super::*;
+
+            #constructor_impl
+
+            impl #generics_with_lifetimes #crate_name::class::JsClass<'js> for #ident #generics{
+                const NAME: &'static str = #javascript_name;
+
+                type Mutable = #crate_name::class::#mutability;
+
+                fn class_id() -> &'static #crate_name::class::ClassId{
+                    static ID: #crate_name::class::ClassId =  #crate_name::class::ClassId::new();
+                    &ID
+                }
+
+                fn prototype(ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::Object<'js>>>{
+                    use #crate_name::class::impl_::MethodImplementor;
+
+                    let proto = #crate_name::Object::new(ctx.clone())?;
+                    #props
+                    let implementor = #crate_name::class::impl_::MethodImpl::<Self>::new();
+                    (&implementor).implement(&proto)?;
+                    Ok(Some(proto))
+                }
+
+                fn constructor(ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<Option<#crate_name::function::Constructor<'js>>>{
+                    #constructor_body
+                }
+            }
+
+            impl #generics_with_lifetimes #crate_name::IntoJs<'js> for #ident #generics{
+                fn into_js(self,ctx: &#crate_name::Ctx<'js>) -> #crate_name::Result<#crate_name::Value<'js>>{
+                    let cls = #crate_name::class::Class::<Self>::instance(ctx.clone(),self)?;
+                    #crate_name::IntoJs::into_js(cls, ctx)
+                }
+            }
+
+            impl #generics_with_lifetimes #crate_name::FromJs<'js> for #ident #generics
+            where
+                for<'a> #crate_name::class::impl_::CloneWrapper<'a,Self>: #crate_name::class::impl_::CloneTrait<Self>,
+            {
+                fn from_js(ctx: &#crate_name::Ctx<'js>, value: #crate_name::Value<'js>) -> #crate_name::Result<Self>{
+                    use #crate_name::class::impl_::CloneTrait;
+
+                    let value = #crate_name::class::Class::<Self>::from_js(ctx,value)?;
+                    let borrow = value.try_borrow()?;
+                    Ok(#crate_name::class::impl_::CloneWrapper(&*borrow).wrap_clone())
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use attrs::OptionList;
 use class::ClassOption;
 use function::FunctionOption;
+use interface::InterfaceOption;
 use methods::ImplOption;
 use module::ModuleOption;
 use proc_macro::TokenStream as TokenStream1;
 use proc_macro_error::{abort, proc_macro_error};
-use syn::{parse_macro_input, DeriveInput, Item};
+use syn::{parse_macro_input, DeriveInput, Item, LitStr};
 
 #[cfg(test)]
 macro_rules! assert_eq_tokens {
@@ -22,6 +23,8 @@ mod common;
 mod embed;
 mod fields;
 mod function;
+mod interface;
+mod js_class;
 mod methods;
 mod module;
 mod trace;
@@ -42,6 +45,9 @@ mod trace;
 /// | `rename`     | String    | Changes the name of the implemented class on the JavaScript side.                                                                                                                       |
 /// | `rename_all` | Casing    | Converts the case of all the fields of this struct which have implement accessors. Can be one of `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`,`snake_case`, or `SCREAMING_SNAKE` |
 /// | `frozen`     | Flag      | Changes the class implementation to only allow borrowing immutably.  Trying to borrow mutably will result in an error.                                                                  |
+/// | `exotic`     | Flag      | Enables the property access hooks of the class, which requires the class to implement [`Exotic`](rquickjs_core::class::Exotic).                                                         |
+/// | `indexed`    | Flag      | Makes the objects of the class array like, which requires the class to implement [`Indexed`](rquickjs_core::class::Indexed).                                                            |
+/// | `async_drop` | Flag      | Spawns the clean up of garbage collected objects on the async runtime, which requires the class to implement [`AsyncDrop`](rquickjs_core::class::AsyncDrop).                            |
 ///
 /// # Field options
 ///
@@ -55,6 +61,8 @@ mod trace;
 /// | `set`          | Flag      | Creates a setter for this field, allowing write access to the field from JavaSccript.   |
 /// | `enumerable`   | Flag      | Makes the field, if it has a getter or setter, enumerable in JavaScript.                |
 /// | `configurable` | Flag      | Makes the field, if it has a getter or setter, configurable in JavaScript.              |
+/// | `readonly`     | Flag      | Prevents a setter from being created for this field, even if `set` is specified.        |
+/// | `skip`         | Flag      | Prevents any accessors from being created for this field.                               |
 /// | `skip_trace`   | Flag      | Skips the field deriving the `Trace` trait.                                             |
 /// | `rename`       | String    | Changes the name of the field getter and/or setter to the specified name in JavaScript. |
 ///
@@ -126,6 +134,44 @@ pub fn class(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
 /// then when you use closures or the functions for which the proper traits are already
 /// implemented..
 ///
+/// # Parameter options
+///
+/// The parameters of the function can be tagged with an attribute to change how the argument is
+/// retrieved from JavaScript. These attributes are all in the form of `#[qjs(option = value)]`.
+///
+/// | **Option** | **Value**              | **Description**                                                                                                                                                             |
+/// |------------|------------------------|-----------------------------------------------------------------------------------------------------------------------------------------------------------------------------|
+/// | `default`  | Flag or String         | Makes the parameter optional. If the argument is omitted or `undefined` the given expression, or `Default::default()` when used as a flag, is used instead. Must only be used on trailing parameters, only `Ctx`, `Opt` and `Rest` parameters can follow it. |
+/// | `options`  | Flag                   | Marks the last parameter as an options object. The argument must be an object, which is converted with `FromJs`, or omitted, `undefined` or `null` in which case `Default::default()` is used. |
+///
+/// When an argument can't be converted, the function throws a `TypeError` naming the function, the
+/// index and name of the parameter and its type, with the error of the conversion as its `cause`,
+/// see `Error::Argument`.
+///
+/// # Example
+/// ```
+/// use rquickjs::{CatchResultExt, Context, Function, Runtime};
+///
+/// #[rquickjs::function]
+/// pub fn greet(name: String, #[qjs(default = "\"Hello\".to_string()")] greeting: String) -> String {
+///     format!("{greeting}, {name}!")
+/// }
+///
+/// pub fn main() {
+///     let rt = Runtime::new().unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///
+///     ctx.with(|ctx| {
+///         ctx.globals()
+///             .set("greet", Function::new(ctx.clone(), js_greet).unwrap())
+///             .unwrap();
+///         let res: String = ctx.eval("greet('world')").catch(&ctx).unwrap();
+///         assert_eq!(res, "Hello, world!");
+///         let res: String = ctx.eval("greet('world', 'Bye')").catch(&ctx).unwrap();
+///         assert_eq!(res, "Bye, world!");
+///     })
+/// }
+/// ```
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn function(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
@@ -179,6 +225,56 @@ pub fn function(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
 /// | `constructor`  | Flag                                                              | Marks this method a the constructor for this type.                                              |
 /// | `skip`         | Flag                                                              | Skips defining this method on the JavaScript class.                                             |
 ///
+/// The parameters of each method can be tagged with the same options as the parameters of a
+/// function using the [`macro@function`] attribute, for example `#[qjs(default = "0")]`.
+///
+/// The names of methods and accessors are interned once per runtime with
+/// [`Atom::from_static`](rquickjs_core::Atom::from_static), the generated code doesn't cache any
+/// other metadata.
+///
+/// Methods of any `#[class]` type can be bound this way, no wrapper functions are needed. Methods
+/// without an option are defined on the prototype and getters and setters become accessor
+/// properties on the prototype:
+///
+/// ```
+/// # use rquickjs::{class::Trace, Class, Context, Runtime};
+/// #[derive(Trace)]
+/// #[rquickjs::class]
+/// pub struct MyType {
+///     factor: f64,
+/// }
+///
+/// #[rquickjs::methods]
+/// impl MyType {
+///     pub fn method(&self, x: i32) -> i32 {
+///         x * self.factor as i32
+///     }
+///
+///     #[qjs(get)]
+///     pub fn prop(&self) -> f64 {
+///         self.factor
+///     }
+///
+///     #[qjs(set, rename = "prop")]
+///     pub fn set_prop(&mut self, v: f64) {
+///         self.factor = v
+///     }
+/// }
+///
+/// # let rt = Runtime::new().unwrap();
+/// # let ctx = Context::full(&rt).unwrap();
+/// ctx.with(|ctx| {
+///     Class::<MyType>::define(&ctx.globals()).unwrap();
+///     ctx.globals().set("obj", MyType { factor: 2.0 }).unwrap();
+///     assert_eq!(ctx.eval::<i32, _>("obj.prop = 3; obj.method(2)").unwrap(), 6);
+/// });
+/// ```
+///
+/// Renaming methods to `PredefinedAtom::ToString`, `PredefinedAtom::ToJSON` or
+/// `PredefinedAtom::SymbolToPrimitive` customizes how objects of the class are converted to
+/// strings, serialized by `JSON.stringify` and converted to primitives. A `Symbol.toPrimitive`
+/// method can take a [`PrimitiveHint`](rquickjs_core::convert::PrimitiveHint) argument.
+///
 /// # Example
 /// ```
 /// use rquickjs::{
@@ -293,6 +389,100 @@ pub fn methods(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
     }
 }
 
+/// An attribute for exposing trait objects of a trait to JavaScript.
+///
+/// The attribute implements [`JsClass`](rquickjs_core::class::JsClass) for `Box<dyn Trait>`,
+/// allowing boxed trait objects to be passed to JavaScript as objects whose methods are dispatched
+/// dynamically to the Rust implementation. In the other direction it implements `FromJs` for
+/// `Box<dyn Trait + 'js>` by wrapping any JavaScript object in an adapter which implements the
+/// trait by calling the methods of the same name on the object.
+///
+/// The methods accessible from JavaScript must return a `Result`, the adapter returns an error when
+/// the JavaScript object doesn't have the method, the method throws or its result can't be
+/// converted.
+///
+/// The attribute adds [`Trace`](rquickjs_core::class::Trace) as a supertrait so implementations
+/// owned by JavaScript are traced, implement it with `#[derive(Trace)]`.
+///
+/// # Limitations
+/// The trait can't be generic and can't have any supertraits. Methods which are generic, don't
+/// take `&self` or `&mut self`, or are skipped need a default implementation which the adapter
+/// will use.
+///
+/// # Attribute options
+///
+/// | **Option**   | **Value** | **Description**                                                                                                                                                                         |
+/// |--------------|-----------|-----------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------|
+/// | `crate`      | String    | Changes the name from which the attribute tries to use rquickjs types. Use when the name behind which the rquickjs crate is declared is not properly resolved by the macro.             |
+/// | `rename`     | String    | Changes the name of the class on the JavaScript side.                                                                                                                                   |
+/// | `rename_all` | Casing    | Converts the case of all the methods. Can be one of `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`,`snake_case`, or `SCREAMING_SNAKE`                                            |
+///
+/// # Method options
+///
+/// | **Option** | **Value** | **Description**                                                         |
+/// |------------|-----------|-------------------------------------------------------------------------|
+/// | `rename`   | String    | Changes the name of the method on the JavaScript side.                  |
+/// | `skip`     | Flag      | Doesn't make the method accessible from JavaScript.                     |
+///
+/// # Example
+/// ```
+/// use rquickjs::{class::Trace, CatchResultExt, Context, Result, Runtime};
+///
+/// #[rquickjs::interface(rename_all = "camelCase")]
+/// pub trait Greeter {
+///     fn greet(&self, name: String) -> Result<String>;
+///
+///     fn greet_loudly(&self, name: String) -> Result<String>;
+/// }
+///
+/// #[derive(Trace)]
+/// struct English;
+///
+/// impl Greeter for English {
+///     fn greet(&self, name: String) -> Result<String> {
+///         Ok(format!("Hello, {name}!"))
+///     }
+///
+///     fn greet_loudly(&self, name: String) -> Result<String> {
+///         Ok(self.greet(name)?.to_uppercase())
+///     }
+/// }
+///
+/// pub fn main() {
+///     let rt = Runtime::new().unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///
+///     ctx.with(|ctx| {
+///         // Pass a Rust implementation to JavaScript.
+///         let greeter: Box<dyn Greeter> = Box::new(English);
+///         ctx.globals().set("english", greeter).unwrap();
+///         let res: String = ctx.eval("english.greetLoudly('world')").catch(&ctx).unwrap();
+///         assert_eq!(res, "HELLO, WORLD!");
+///
+///         // Use a JavaScript implementation from Rust.
+///         let greeter: Box<dyn Greeter> = ctx
+///             .eval("({ greet: (name) => `Hi, ${name}!` })")
+///             .catch(&ctx)
+///             .unwrap();
+///         assert_eq!(greeter.greet("world".to_string()).unwrap(), "Hi, world!");
+///         // The object doesn't implement `greetLoudly`.
+///         assert!(greeter.greet_loudly("world".to_string()).is_err());
+///     })
+/// }
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn interface(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
+    let options = parse_macro_input!(attr as OptionList<InterfaceOption>);
+    let item = parse_macro_input!(item as Item);
+    match item {
+        Item::Trait(item) => interface::expand(options, item).into(),
+        item => {
+            abort!(item, "#[interface] macro can only be used on traits")
+        }
+    }
+}
+
 /// An attribute which generates code for exporting a module to Rust.
 ///
 /// Any supported item inside the module which is marked as `pub` will be exported as a JavaScript value.
@@ -479,6 +669,80 @@ pub fn trace(stream: TokenStream1) -> TokenStream1 {
     trace::expand(derive_input).into()
 }
 
+/// A macro for deriving [`JsClass`](rquickjs_core::class::JsClass) for a plain struct.
+///
+/// A lighter alternative to the [`macro@class`] attribute: every `pub` field of the struct becomes
+/// an accessor on the JavaScript side, with a getter and, unless the field is read-only, a setter.
+/// Like the [`macro@class`] attribute the struct must also implement
+/// [`Trace`](rquickjs_core::class::Trace) and, for the getters, the fields must implement `Clone`.
+/// Methods can still be added with a [`macro@methods`] impl block.
+///
+/// # Attribute options
+///
+/// The derive accepts the same options as the [`macro@class`] attribute through a `qjs`
+/// attribute on the struct: `#[qjs(rename = "AnotherName")]`.
+///
+/// | **Option**    | **Value** | **Description**                                                                                                                                                                         |
+/// |---------------|-----------|-----------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------|
+/// | `crate`       | String    | Changes the name from which the attribute tries to use rquickjs types. Use when the name behind which the rquickjs crate is declared is not properly resolved by the macro.             |
+/// | `rename`      | String    | Changes the name of the implemented class on the JavaScript side.                                                                                                                       |
+/// | `rename_all`  | Casing    | Converts the case of all the fields of this struct which have implement accessors. Can be one of `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`,`snake_case`, or `SCREAMING_SNAKE` |
+/// | `frozen`      | Flag      | Changes the class implementation to only allow borrowing immutably. No setters are generated for the fields.                                                                           |
+/// | `constructor` | Flag      | Generates a constructor which takes the value of every public field as an argument in declaration order. The remaining fields are initialized with `Default::default()`.             |
+///
+/// # Field options
+///
+/// | **Option**     | **Value** | **Description**                                                                         |
+/// |----------------|-----------|-----------------------------------------------------------------------------------------|
+/// | `readonly`     | Flag      | Only creates a getter for this field.                                                   |
+/// | `skip`         | Flag      | Doesn't make this field accessible from JavaScript.                                     |
+/// | `enumerable`   | Flag      | Makes the field enumerable in JavaScript.                                               |
+/// | `configurable` | Flag      | Makes the field configurable in JavaScript.                                             |
+/// | `skip_trace`   | Flag      | Skips the field deriving the `Trace` trait.                                             |
+/// | `rename`       | String    | Changes the name of the field getter and/or setter to the specified name in JavaScript. |
+///
+/// # Example
+/// ```
+/// use rquickjs::{class::{JsClass, Trace}, CatchResultExt, Class, Context, Runtime};
+///
+/// #[derive(Trace, JsClass)]
+/// #[qjs(rename_all = "camelCase", constructor)]
+/// pub struct Point {
+///     pub x: f64,
+///     pub y: f64,
+///     #[qjs(readonly)]
+///     pub unit_name: String,
+///     /// Not accessible from JavaScript.
+///     cache: Option<f64>,
+/// }
+///
+/// pub fn main() {
+///     let rt = Runtime::new().unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///
+///     ctx.with(|ctx| {
+///         Class::<Point>::define(&ctx.globals()).unwrap();
+///         ctx.eval::<(), _>(
+///             r#"
+///             let p = new Point(1, 2, "px");
+///             p.x = 3;
+///             if(p.x !== 3 || p.unitName !== "px" || p.cache !== undefined){
+///                 throw new Error("invalid point")
+///             }
+///         "#,
+///         )
+///         .catch(&ctx)
+///         .unwrap();
+///     })
+/// }
+/// ```
+#[proc_macro_derive(JsClass, attributes(qjs))]
+#[proc_macro_error]
+pub fn js_class(stream: TokenStream1) -> TokenStream1 {
+    let derive_input = parse_macro_input!(stream as DeriveInput);
+    js_class::expand(derive_input).into()
+}
+
 /// A macro for embedding JavaScript code into a binary.
 ///
 /// Compiles a JavaScript module to bytecode and then compiles the resulting bytecode into the
@@ -525,3 +789,35 @@ pub fn embed(item: TokenStream1) -> TokenStream1 {
     let embed_modules: embed::EmbedModules = parse_macro_input!(item);
     embed::embed(embed_modules).into()
 }
+
+/// A macro for embedding a JavaScript script into a binary.
+///
+/// Compiles a JavaScript script to bytecode at build time and expands to a
+/// [`CompiledScript`](rquickjs_core::context::CompiledScript) containing the bytecode, so the
+/// source of the script doesn't have to be shipped with the binary. The path is relative to the
+/// crate manifest file. Syntax errors in the script are reported as compile errors. Use
+/// [`embed!`] for modules instead.
+///
+/// # Usage
+///
+/// ```
+/// use rquickjs::{context::CompiledScript, embed_script, Context, Runtime};
+///
+/// static SCRIPT: CompiledScript = embed_script!("my_script.js");
+///
+/// fn main() {
+///     let rt = Runtime::new().unwrap();
+///     let ctx = Context::full(&rt).unwrap();
+///
+///     ctx.with(|ctx| {
+///         let answer: i32 = SCRIPT.eval(&ctx).unwrap();
+///         assert_eq!(answer, 42);
+///     })
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn embed_script(item: TokenStream1) -> TokenStream1 {
+    let path: LitStr = parse_macro_input!(item);
+    embed::embed_script(path).into()
+}
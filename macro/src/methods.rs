@@ -204,7 +204,13 @@ pub(crate) fn expand(options: OptionList<ImplOption>, item: ItemImpl) -> TokenSt
         .iter()
         .filter(|&func| (!func.config.r#static))
         .map(|func| {
-            func.expand_apply_to_object(&prefix, &self_ty, &proto_ident, config.rename_all)
+            func.expand_apply_to_object(
+                &crate_name,
+                &prefix,
+                &self_ty,
+                &proto_ident,
+                config.rename_all,
+            )
         });
     let accessor_apply_proto = accessors
         .values()
@@ -223,6 +229,7 @@ pub(crate) fn expand(options: OptionList<ImplOption>, item: ItemImpl) -> TokenSt
                 .filter(|&func| func.config.r#static)
                 .map(|func| {
                     func.expand_apply_to_object(
+                        &crate_name,
                         &prefix,
                         &self_ty,
                         &constructor_ident,
@@ -1,8 +1,8 @@
 use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::emit_warning;
-use quote::quote;
+use quote::{format_ident, quote};
 
-use crate::common::{Case, GET_PREFIX, SET_PREFIX};
+use crate::common::{expand_key, Case, GET_PREFIX, SET_PREFIX};
 
 use super::method::Method;
 
@@ -70,20 +70,14 @@ impl JsAccessor {
                 let enumerable = get.config.enumerable || set.config.enumerable;
 
                 let name = get.name(case);
-
-                let configurable = configurable
-                    .then(|| quote!(.configurable()))
-                    .unwrap_or_default();
-                let enumerable = enumerable
-                    .then(|| quote!(.enumerable()))
-                    .unwrap_or_default();
+                let name = expand_key(lib_crate, &format_ident!("_proto"), &name);
 
                 let get_name = get.function.expand_carry_type_name(GET_PREFIX);
                 let set_name = set.function.expand_carry_type_name(SET_PREFIX);
                 quote! {_proto.prop(#name,
                         #lib_crate::object::Accessor::new(#get_name,#set_name)
-                        #configurable
-                        #enumerable
+                        .set_configurable(#configurable)
+                        .set_enumerable(#enumerable)
                 )?;}
             }
             (Some(get), None) => {
@@ -91,19 +85,13 @@ impl JsAccessor {
                 let enumerable = get.config.enumerable;
 
                 let name = get.name(case);
-
-                let configurable = configurable
-                    .then(|| quote!(.configurable()))
-                    .unwrap_or_default();
-                let enumerable = enumerable
-                    .then(|| quote!(.enumerable()))
-                    .unwrap_or_default();
+                let name = expand_key(lib_crate, &format_ident!("_proto"), &name);
 
                 let get_name = get.function.expand_carry_type_name(GET_PREFIX);
                 quote! {_proto.prop(#name,
                         #lib_crate::object::Accessor::new_get(#get_name)
-                        #configurable
-                        #enumerable
+                        .set_configurable(#configurable)
+                        .set_enumerable(#enumerable)
                 )?;}
             }
             (None, Some(set)) => {
@@ -111,19 +99,13 @@ impl JsAccessor {
                 let enumerable = set.config.enumerable;
 
                 let name = set.name(case);
-
-                let configurable = configurable
-                    .then(|| quote!(.configurable()))
-                    .unwrap_or_default();
-                let enumerable = enumerable
-                    .then(|| quote!(.enumerable()))
-                    .unwrap_or_default();
+                let name = expand_key(lib_crate, &format_ident!("_proto"), &name);
 
                 let set_name = set.function.expand_carry_type_name(GET_PREFIX);
                 quote! {_proto.prop(#name,
                         #lib_crate::object::Accessor::new_set(#set_name)
-                        #configurable
-                        #enumerable
+                        .set_configurable(#configurable)
+                        .set_enumerable(#enumerable)
                 )?;}
             }
             (None, None) => TokenStream::new(),
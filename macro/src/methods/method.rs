@@ -10,7 +10,7 @@ use syn::{
 
 use crate::{
     attrs::{take_attributes, FlagOption, OptionList, ValueOption},
-    common::{kw, AbortResultExt, Case},
+    common::{expand_key, kw, AbortResultExt, Case},
     function::JsFunction,
 };
 
@@ -152,7 +152,7 @@ impl Method {
             mut attrs,
             vis,
             defaultness,
-            sig,
+            mut sig,
             block,
         } = func;
 
@@ -186,7 +186,7 @@ impl Method {
 
         attrs.retain(|x| !x.path().is_ident("qjs"));
 
-        let function = JsFunction::new(vis.clone(), &sig, Some(self_ty));
+        let function = JsFunction::new(vis.clone(), &mut sig, Some(self_ty));
 
         Method {
             config,
@@ -264,6 +264,7 @@ impl Method {
 
     pub(crate) fn expand_apply_to_object(
         &self,
+        lib_crate: &Ident,
         prefix: &str,
         self_ty: &Type,
         object_name: &Ident,
@@ -272,10 +273,10 @@ impl Method {
         if self.config.skip {
             return TokenStream::new();
         }
-        let func_name_str = self.name(case);
+        let func_name = expand_key(lib_crate, object_name, &self.name(case));
         let js_func_name = self.function.expand_carry_type_name(prefix);
         quote! {
-            #object_name.set(#func_name_str,<#self_ty>::#js_func_name)?;
+            #object_name.set(#func_name,<#self_ty>::#js_func_name)?;
         }
     }
 }
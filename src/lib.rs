@@ -54,9 +54,17 @@
 //! [`Runtime::set_loader`]. The resolvers and loaders can be easily combined via tuples. When the
 //! previous resolver or loader failed the next one will be applied.
 //!
+//! - `loader-cache` adds [`CachingLoader`](loader::CachingLoader) which caches the bytecode of
+//! loaded modules.
+//!
 //! - `dyn-load` adds support for loadable native modules (so/dll/dylib).
 //!
-//! - `array-buffer` adds support for [`ArrayBuffer`] and [`TypedArray`].
+//! - `array-buffer` adds support for [`ArrayBuffer`] and [`TypedArray`]. Vectors and slices of
+//! 8, 16 and 32 bit integers and floats are then converted to typed arrays with a single copy,
+//! and converted back from both arrays and typed arrays.
+//!
+//! - `vec-as-array` keeps converting vectors and slices of numbers to arrays when `array-buffer`
+//! is enabled.
 //!
 //! - `futures` adds support for async Rust. When enabled the library exports [`AsyncRuntime`] and
 //! [`AsyncContext`]. These are the asynchronous variants of the normal runtime and context. In
@@ -73,6 +81,13 @@
 //!
 //! - `phf` enables using Perfect Hash Function for builtin modules lookup
 //!
+//! - `raw` documents the raw QuickJS bindings in [`qjs`], re-exported from `rquickjs-sys`, and
+//! guarantees the stability of the commonly needed items listed there.
+//!
+//! - `serde` adds the [`serde`] module converting any type implementing `Serialize` or
+//! `Deserialize` to and from JavaScript values, and support for deserializing
+//! [`RuntimeOptions`](runtime::RuntimeOptions) from configuration files.
+//!
 //! ## Extra types
 //!
 //! This crate has support for conversion of many Rust types like [`Option`],
@@ -84,6 +99,13 @@
 //! - `indexmap` adds [`FromJs`]/[`IntoJs`] implementations for [`IndexSet`](`indexmap::IndexSet`)
 //! and [`IndexMap`](`indexmap_rs::IndexMap`)
 //!
+//! - `serde_json` adds [`FromJs`]/[`IntoJs`] implementations for `serde_json::Value`, converting
+//! JSON values directly without serializing them to a string
+//!
+//! - `ndarray` adds [`FromJs`]/[`IntoJs`] implementations for `ndarray` arrays, converting them
+//! to nested typed arrays or, wrapped in [`Shaped`](convert::Shaped), to a flat typed array with
+//! a shape
+//!
 //! ## Bindings
 //!
 //! The bindings are pre-generated for the following platforms:
@@ -134,7 +156,7 @@ pub use rquickjs_core::*;
 
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "macro")))]
 #[cfg(feature = "macro")]
-pub use rquickjs_macro::{class, embed, function, methods, module};
+pub use rquickjs_macro::{class, embed, embed_script, function, interface, methods, module};
 
 pub mod class {
     //! JavaScript classes defined from Rust.
@@ -142,7 +164,7 @@ pub mod class {
     pub use rquickjs_core::class::*;
     #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "macro")))]
     #[cfg(feature = "macro")]
-    pub use rquickjs_macro::Trace;
+    pub use rquickjs_macro::{JsClass, Trace};
 }
 
 // The following imports needed to linking docs
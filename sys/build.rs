@@ -1,5 +1,7 @@
 use std::{
+    collections::hash_map::DefaultHasher,
     env, fs,
+    hash::{Hash, Hasher},
     io::Write,
     path::{Path, PathBuf},
     process::{self, Command, Stdio},
@@ -121,6 +123,23 @@ fn main() {
     let out_dir = env::var("OUT_DIR").expect("No OUT_DIR env var is set by cargo");
     let out_dir = Path::new(&out_dir);
 
+    if let Some(prebuilt) = Prebuilt::from_env() {
+        prebuilt.link();
+        let header_file = prebuilt.copy_headers(out_dir);
+        let defines: Vec<(String, Option<&str>)> =
+            vec![("_GNU_SOURCE".into(), None), ("CONFIG_BIGNUM".into(), None)];
+        bindgen(out_dir, header_file, &defines, prebuilt.cflags.clone());
+        // The version of a prebuilt library is unknown, so identify it by its contents instead.
+        // A dynamic library can be replaced after linking, so it remains unidentified.
+        match prebuilt.identity() {
+            Some(hash) => {
+                println!("cargo:rustc-env=RQUICKJS_SYS_QUICKJS_VERSION=prebuilt-{hash:016x}")
+            }
+            None => println!("cargo:rustc-env=RQUICKJS_SYS_QUICKJS_VERSION=prebuilt"),
+        }
+        return;
+    }
+
     let header_files = [
         "libbf.h",
         "libregexp-opcode.h",
@@ -146,11 +165,20 @@ fn main() {
         "error_column_number.patch",
         "get_function_proto.patch",
         "check_stack_overflow.patch",
+        "stack_overflow_error.patch",
         "infinity_handling.patch",
+        "regexp_step_limit.patch",
+        "module_invalidation.patch",
+        "string_buffer.patch",
+        "array_buffer_transfer.patch",
     ];
 
     let version =
         fs::read_to_string(src_dir.join("VERSION")).expect("failed to read quickjs VERSION file");
+    println!(
+        "cargo:rustc-env=RQUICKJS_SYS_QUICKJS_VERSION={}",
+        version.trim()
+    );
     let version = format!("\"{}\"", version.trim());
 
     let mut defines = vec![
@@ -184,6 +212,9 @@ fn main() {
             .expect("Unable to copy source; try 'git submodule update --init'");
     }
     fs::copy("quickjs.bind.h", out_dir.join("quickjs.bind.h")).expect("Unable to copy source");
+    // Not built, but patched by `error_column_number.patch`, which must apply cleanly.
+    fs::copy(src_dir.join("Makefile"), out_dir.join("Makefile"))
+        .expect("Unable to copy source; try 'git submodule update --init'");
 
     // applying patches
     for file in &patch_files {
@@ -236,6 +267,162 @@ fn main() {
     builder.compile("libquickjs.a");
 }
 
+/// A prebuilt QuickJS library to link against instead of compiling the vendored sources.
+///
+/// The library must be built from sources with the patches in the `patches` directory applied.
+struct Prebuilt {
+    lib_dirs: Vec<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+    libs: Vec<String>,
+    link_kind: String,
+    cflags: Vec<String>,
+}
+
+impl Prebuilt {
+    /// Read the prebuilt library configuration from the environment.
+    ///
+    /// `QUICKJS_LIB_DIR` selects the directory containing the library, otherwise if
+    /// `QUICKJS_USE_PKG_CONFIG` is set the library is looked up with `pkg-config`. Every variable
+    /// can be suffixed with the target, like `QUICKJS_LIB_DIR_aarch64_unknown_linux_gnu`, to only
+    /// apply to that target.
+    fn from_env() -> Option<Self> {
+        for name in [
+            "QUICKJS_LIB_DIR",
+            "QUICKJS_INCLUDE_DIR",
+            "QUICKJS_LIB_NAME",
+            "QUICKJS_STATIC",
+            "QUICKJS_USE_PKG_CONFIG",
+        ] {
+            println!("cargo:rerun-if-env-changed={name}");
+            println!("cargo:rerun-if-env-changed={name}_{}", target_suffix());
+        }
+
+        let link_kind = match target_env("QUICKJS_STATIC").as_deref() {
+            Some("0") => "dylib",
+            _ => "static",
+        }
+        .to_string();
+
+        if let Some(lib_dir) = target_env("QUICKJS_LIB_DIR") {
+            return Some(Prebuilt {
+                lib_dirs: vec![lib_dir.into()],
+                include_dirs: target_env("QUICKJS_INCLUDE_DIR")
+                    .map(|x| vec![x.into()])
+                    .unwrap_or_default(),
+                libs: vec![target_env("QUICKJS_LIB_NAME").unwrap_or_else(|| "quickjs".into())],
+                link_kind,
+                cflags: Vec::new(),
+            });
+        }
+
+        target_env("QUICKJS_USE_PKG_CONFIG")?;
+        let mut prebuilt = Prebuilt {
+            lib_dirs: Vec::new(),
+            include_dirs: Vec::new(),
+            libs: Vec::new(),
+            link_kind,
+            cflags: Vec::new(),
+        };
+        for flag in pkg_config(&["--libs", "--cflags", "quickjs"]).split_whitespace() {
+            if let Some(dir) = flag.strip_prefix("-L") {
+                prebuilt.lib_dirs.push(dir.into());
+            } else if let Some(lib) = flag.strip_prefix("-l") {
+                prebuilt.libs.push(lib.into());
+            } else if let Some(dir) = flag.strip_prefix("-I") {
+                prebuilt.include_dirs.push(dir.into());
+            } else if flag.starts_with("-D") {
+                prebuilt.cflags.push(flag.into());
+            }
+        }
+        if let Some(dir) = target_env("QUICKJS_INCLUDE_DIR") {
+            prebuilt.include_dirs.insert(0, dir.into());
+        }
+        Some(prebuilt)
+    }
+
+    fn link(&self) {
+        for dir in &self.lib_dirs {
+            println!("cargo:rustc-link-search=native={}", dir.display());
+        }
+        for (idx, lib) in self.libs.iter().enumerate() {
+            // Only QuickJS itself honors `QUICKJS_STATIC`, its dependencies are system libraries.
+            if idx == 0 {
+                println!("cargo:rustc-link-lib={}={lib}", self.link_kind);
+            } else {
+                println!("cargo:rustc-link-lib={lib}");
+            }
+        }
+    }
+
+    /// Returns a hash of the statically linked QuickJS library, if it can be found.
+    fn identity(&self) -> Option<u64> {
+        if self.link_kind != "static" {
+            return None;
+        }
+        let lib = self.libs.first()?;
+        let path = self
+            .lib_dirs
+            .iter()
+            .flat_map(|dir| {
+                [
+                    dir.join(format!("lib{lib}.a")),
+                    dir.join(format!("{lib}.lib")),
+                ]
+            })
+            .find(|path| path.is_file())?;
+        println!("cargo:rerun-if-changed={}", path.display());
+        let mut hasher = DefaultHasher::new();
+        fs::read(path).ok()?.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Copy the headers needed to generate bindings and returns the header to generate them from.
+    fn copy_headers(&self, out_dir: &Path) -> PathBuf {
+        fs::copy("quickjs.bind.h", out_dir.join("quickjs.bind.h")).expect("Unable to copy source");
+        if cfg!(feature = "bindgen") {
+            for file in ["quickjs.h", "quickjs-atom.h"] {
+                let src = self
+                    .include_dirs
+                    .iter()
+                    .map(|dir| dir.join(file))
+                    .find(|path| path.exists())
+                    .unwrap_or_else(|| {
+                        panic!("Unable to find `{file}`, set QUICKJS_INCLUDE_DIR to the directory containing the QuickJS headers")
+                    });
+                fs::copy(src, out_dir.join(file)).expect("Unable to copy header");
+            }
+        }
+        out_dir.join("quickjs.bind.h")
+    }
+}
+
+/// Returns the target formatted to suffix an environment variable.
+fn target_suffix() -> String {
+    env::var("TARGET").unwrap().replace('-', "_")
+}
+
+/// Returns the target specific value of an environment variable, or else its general value.
+fn target_env(name: &str) -> Option<String> {
+    env::var(format!("{name}_{}", target_suffix()))
+        .or_else(|_| env::var(name))
+        .ok()
+}
+
+fn pkg_config(args: &[&str]) -> String {
+    let pkg_config = env::var("PKG_CONFIG").unwrap_or_else(|_| "pkg-config".into());
+    let output = Command::new(&pkg_config)
+        .args(args)
+        .output()
+        .unwrap_or_else(|e| panic!("Unable to execute `{pkg_config}`: {e}"));
+    if !output.status.success() {
+        panic!(
+            "`{pkg_config}` failed to find quickjs: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    String::from_utf8(output.stdout).expect("pkg-config output is not UTF-8")
+}
+
 fn feature_to_cargo(name: impl AsRef<str>) -> String {
     format!("CARGO_FEATURE_{}", feature_to_define(name))
 }
@@ -248,18 +435,26 @@ fn patch<D: AsRef<Path>, P: AsRef<Path>>(out_dir: D, patch: P) {
     let mut child = Command::new("patch")
         .args(["-p1", "-f"])
         .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
         .current_dir(out_dir)
         .spawn()
         .expect("Unable to execute patch, you may need to install it: {}");
     println!("Applying patch {}", patch.as_ref().display());
     {
-        let patch = fs::read(patch).expect("Unable to read patch");
+        let patch = fs::read(patch.as_ref()).expect("Unable to read patch");
 
         let stdin = child.stdin.as_mut().unwrap();
         stdin.write_all(&patch).expect("Unable to apply patch");
     }
 
-    child.wait_with_output().expect("Unable to apply patch");
+    let output = child.wait_with_output().expect("Unable to apply patch");
+    if !output.status.success() {
+        panic!(
+            "Unable to apply patch {}: {}",
+            patch.as_ref().display(),
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
 }
 
 #[cfg(not(feature = "bindgen"))]
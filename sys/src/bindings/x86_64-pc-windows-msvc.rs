@@ -1,4 +1,4 @@
-/* automatically generated by rust-bindgen 0.69.4 */
+/* automatically generated by rust-bindgen 0.69.4 */
 
 pub const JS_PROP_CONFIGURABLE: u32 = 1;
 pub const JS_PROP_WRITABLE: u32 = 2;
@@ -1365,6 +1365,9 @@ extern "C" {
 extern "C" {
     pub fn JS_ThrowOutOfMemory(ctx: *mut JSContext) -> JSValue;
 }
+extern "C" {
+    pub fn JS_IsStackOverflow(ctx: *mut JSContext, val: JSValue) -> ::std::os::raw::c_int;
+}
 extern "C" {
     pub fn __JS_FreeValue(ctx: *mut JSContext, v: JSValue);
 }
@@ -1431,6 +1434,16 @@ extern "C" {
 extern "C" {
     pub fn JS_FreeCString(ctx: *mut JSContext, ptr: *const ::std::os::raw::c_char);
 }
+extern "C" {
+    pub fn JS_GetStringBuffer(
+        val: JSValue,
+        plen: *mut u32,
+        pwide: *mut ::std::os::raw::c_int,
+    ) -> *const ::std::os::raw::c_void;
+}
+extern "C" {
+    pub fn JS_NewStringUTF16(ctx: *mut JSContext, buf: *const u16, len: size_t) -> JSValue;
+}
 extern "C" {
     pub fn JS_NewObjectProtoClass(
         ctx: *mut JSContext,
@@ -1749,6 +1762,15 @@ extern "C" {
 extern "C" {
     pub fn JS_DetachArrayBuffer(ctx: *mut JSContext, obj: JSValue);
 }
+extern "C" {
+    pub fn JS_TransferArrayBuffer(
+        ctx: *mut JSContext,
+        obj: JSValue,
+        free_func: JSFreeArrayBufferDataFunc,
+        psize: *mut size_t,
+        popaque: *mut *mut ::std::os::raw::c_void,
+    ) -> *mut u8;
+}
 extern "C" {
     pub fn JS_GetArrayBuffer(ctx: *mut JSContext, psize: *mut size_t, obj: JSValue) -> *mut u8;
 }
@@ -1882,6 +1904,9 @@ extern "C" {
         opaque: *mut ::std::os::raw::c_void,
     );
 }
+extern "C" {
+    pub fn JS_SetRegExpStepLimit(rt: *mut JSRuntime, limit: u64);
+}
 extern "C" {
     pub fn JS_SetCanBlock(rt: *mut JSRuntime, can_block: ::std::os::raw::c_int);
 }
@@ -1922,6 +1947,22 @@ extern "C" {
 extern "C" {
     pub fn JS_GetModuleName(ctx: *mut JSContext, m: *mut JSModuleDef) -> JSAtom;
 }
+extern "C" {
+    pub fn JS_GetNextContext(rt: *mut JSRuntime, ctx: *mut JSContext) -> *mut JSContext;
+}
+extern "C" {
+    pub fn JS_GetNextLoadedModule(ctx: *mut JSContext, m: *mut JSModuleDef) -> *mut JSModuleDef;
+}
+extern "C" {
+    pub fn JS_GetModuleRequireCount(m: *mut JSModuleDef) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn JS_GetModuleRequire(m: *mut JSModuleDef, idx: ::std::os::raw::c_int)
+        -> *mut JSModuleDef;
+}
+extern "C" {
+    pub fn JS_InvalidateModule(ctx: *mut JSContext, m: *mut JSModuleDef);
+}
 extern "C" {
     pub fn JS_GetModuleNamespace(ctx: *mut JSContext, m: *mut JSModuleDef) -> JSValue;
 }
@@ -11,6 +11,12 @@ use std::ptr;
 pub const SIZE_T_ERROR: &str =
     "conversion between C type 'size_t' and Rust type 'usize' overflowed.";
 
+/// The version of the QuickJS library
+///
+/// When linking a prebuilt static library this is `"prebuilt-"` followed by a hash of the library,
+/// and only `"prebuilt"` when the library couldn't be identified.
+pub const QUICKJS_VERSION: &str = env!("RQUICKJS_SYS_QUICKJS_VERSION");
+
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 #[cfg(not(feature = "bindgen"))]
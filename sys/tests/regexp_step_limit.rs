@@ -0,0 +1,71 @@
+//! Runs the regexp step limit patch against the vendored QuickJS sources.
+
+use std::ffi::{CStr, CString};
+
+use rquickjs_sys as qjs;
+
+/// Evaluates a script and returns its result converted to a string.
+unsafe fn eval(ctx: *mut qjs::JSContext, source: &str) -> Result<String, String> {
+    // QuickJS requires the source to be nul terminated.
+    let source = CString::new(source).unwrap();
+    let name = CString::new("test.js").unwrap();
+    let value = qjs::JS_Eval(
+        ctx,
+        source.as_ptr(),
+        source.as_bytes().len() as _,
+        name.as_ptr(),
+        qjs::JS_EVAL_TYPE_GLOBAL as _,
+    );
+    let thrown = qjs::JS_IsException(value);
+    let value = if thrown {
+        qjs::JS_GetException(ctx)
+    } else {
+        value
+    };
+    let ptr = qjs::JS_ToCString(ctx, value);
+    let string = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+    qjs::JS_FreeCString(ctx, ptr);
+    qjs::JS_FreeValue(ctx, value);
+    if thrown {
+        Err(string)
+    } else {
+        Ok(string)
+    }
+}
+
+#[test]
+fn catchable_range_error() {
+    unsafe {
+        let rt = qjs::JS_NewRuntime();
+        qjs::JS_SetRegExpStepLimit(rt, 100_000);
+        let ctx = qjs::JS_NewContext(rt);
+
+        let res = eval(
+            ctx,
+            r#"
+            try {
+                /(a+)+$/.test("a".repeat(40) + "b");
+                "finished"
+            } catch (e) {
+                e instanceof RangeError ? e.message : "wrong error"
+            }
+            "#,
+        );
+        assert_eq!(res, Ok("regexp step limit exceeded".into()));
+
+        // Uncaught the error propagates out of the evaluation.
+        let res = eval(ctx, r#"/(a+)+$/.exec("a".repeat(40) + "b")"#);
+        assert_eq!(res, Err("RangeError: regexp step limit exceeded".into()));
+
+        // The context remains usable and short inputs stay below the limit.
+        let res = eval(ctx, r#"/(a+)+$/.test("aaaa")"#);
+        assert_eq!(res, Ok("true".into()));
+
+        qjs::JS_SetRegExpStepLimit(rt, 0);
+        let res = eval(ctx, r#"/(a+)+$/.test("a".repeat(12) + "b")"#);
+        assert_eq!(res, Ok("false".into()));
+
+        qjs::JS_FreeContext(ctx);
+        qjs::JS_FreeRuntime(rt);
+    }
+}
@@ -0,0 +1,55 @@
+//! Runs the stack overflow error patch against the vendored QuickJS sources.
+
+use std::ffi::CString;
+
+use rquickjs_sys as qjs;
+
+/// Evaluates a script and returns whether it threw the error of a stack overflow.
+unsafe fn throws_stack_overflow(ctx: *mut qjs::JSContext, source: &str) -> bool {
+    // QuickJS requires the source to be nul terminated.
+    let source = CString::new(source).unwrap();
+    let name = CString::new("test.js").unwrap();
+    let value = qjs::JS_Eval(
+        ctx,
+        source.as_ptr(),
+        source.as_bytes().len() as _,
+        name.as_ptr(),
+        qjs::JS_EVAL_TYPE_GLOBAL as _,
+    );
+    assert!(qjs::JS_IsException(value));
+    let error = qjs::JS_GetException(ctx);
+    let res = qjs::JS_IsStackOverflow(ctx, error) != 0;
+    qjs::JS_FreeValue(ctx, error);
+    res
+}
+
+#[test]
+fn identifies_engine_error() {
+    unsafe {
+        let rt = qjs::JS_NewRuntime();
+        qjs::JS_SetMaxStackSize(rt, 128 * 1024);
+        let ctx = qjs::JS_NewContext(rt);
+
+        assert!(throws_stack_overflow(
+            ctx,
+            "function recurse() { return recurse() }; recurse()"
+        ));
+        // The error stays identified when it is caught and thrown again.
+        assert!(throws_stack_overflow(
+            ctx,
+            "try { (function f() { f() })() } catch (e) { e.name = 'RangeError'; throw e }"
+        ));
+        // Errors thrown by scripts are never identified, whatever their message.
+        assert!(!throws_stack_overflow(
+            ctx,
+            "throw new RangeError('stack overflow')"
+        ));
+        assert!(!throws_stack_overflow(
+            ctx,
+            "var e = new Error('stack overflow'); e.name = 'InternalError'; throw e"
+        ));
+
+        qjs::JS_FreeContext(ctx);
+        qjs::JS_FreeRuntime(rt);
+    }
+}
@@ -0,0 +1,8 @@
+use rquickjs::function;
+
+#[function]
+fn add(#[qjs(default = "1")] a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn main() {}
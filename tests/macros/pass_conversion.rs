@@ -0,0 +1,74 @@
+use rquickjs::{
+    atom::PredefinedAtom, class::Trace, convert::PrimitiveHint, CatchResultExt, Class, Context,
+    Ctx, Object, Result, Runtime, Value,
+};
+
+#[derive(Trace)]
+#[rquickjs::class]
+pub struct Money {
+    cents: i64,
+    currency: String,
+}
+
+#[rquickjs::methods]
+impl Money {
+    #[qjs(constructor)]
+    pub fn new(cents: i64, currency: String) -> Self {
+        Money { cents, currency }
+    }
+
+    #[qjs(rename = PredefinedAtom::ToString)]
+    pub fn format(&self) -> String {
+        format!("{}.{:02} {}", self.cents / 100, self.cents % 100, self.currency)
+    }
+
+    #[qjs(rename = PredefinedAtom::ToJSON)]
+    pub fn to_json<'js>(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
+        let res = Object::new(ctx)?;
+        res.set("cents", self.cents)?;
+        res.set("currency", self.currency.as_str())?;
+        Ok(res)
+    }
+
+    #[qjs(rename = PredefinedAtom::SymbolToPrimitive)]
+    pub fn to_primitive<'js>(&self, ctx: Ctx<'js>, hint: PrimitiveHint) -> Result<Value<'js>> {
+        match hint {
+            PrimitiveHint::Number => Ok(Value::new_number(ctx, self.cents as f64 / 100.0)),
+            PrimitiveHint::String | PrimitiveHint::Default => {
+                rquickjs::String::from_str(ctx, &self.format()).map(|s| s.into_value())
+            }
+        }
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        Class::<Money>::define(&ctx.globals()).unwrap();
+
+        ctx.eval::<(), _>(
+            r#"
+            const m = new Money(1250, "EUR");
+            if(`${m}` !== "12.50 EUR"){
+                throw new Error(1)
+            }
+            if(m.toString() !== "12.50 EUR"){
+                throw new Error(2)
+            }
+            if(+m !== 12.5){
+                throw new Error(3)
+            }
+            if(m + "" !== "12.50 EUR"){
+                throw new Error(4)
+            }
+            if(JSON.stringify({ price: m }) !== '{"price":{"cents":1250,"currency":"EUR"}}'){
+                throw new Error(5)
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+    });
+}
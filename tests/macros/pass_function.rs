@@ -0,0 +1,125 @@
+use rquickjs::{
+    class::Trace, CatchResultExt, Class, Context, Ctx, FromJs, Function, Object, Result, Runtime,
+    Value,
+};
+
+#[rquickjs::function]
+pub fn add(a: u32, #[qjs(default = "1")] b: u32, #[qjs(default)] c: u32) -> u32 {
+    a + b + c
+}
+
+#[derive(Default)]
+pub struct FetchOptions {
+    method: Option<String>,
+    retries: Option<u32>,
+}
+
+impl<'js> FromJs<'js> for FetchOptions {
+    fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> Result<Self> {
+        let obj = Object::from_value(value)?;
+        Ok(FetchOptions {
+            method: obj.get("method")?,
+            retries: obj.get("retries")?,
+        })
+    }
+}
+
+#[rquickjs::function]
+pub fn fetch(url: String, #[qjs(options)] options: FetchOptions) -> String {
+    format!(
+        "{} {} {}",
+        options.method.as_deref().unwrap_or("GET"),
+        url,
+        options.retries.unwrap_or(0)
+    )
+}
+
+#[derive(Trace)]
+#[rquickjs::class]
+pub struct Counter {
+    value: i32,
+}
+
+#[rquickjs::methods]
+impl Counter {
+    #[qjs(constructor)]
+    pub fn new(#[qjs(default = "10")] value: i32) -> Self {
+        Counter { value }
+    }
+
+    pub fn step(&mut self, #[qjs(default = "1")] by: i32) -> i32 {
+        self.value += by;
+        self.value
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        ctx.globals()
+            .set("add", Function::new(ctx.clone(), js_add).unwrap())
+            .unwrap();
+        ctx.globals()
+            .set("fetch", Function::new(ctx.clone(), js_fetch).unwrap())
+            .unwrap();
+        Class::<Counter>::define(&ctx.globals()).unwrap();
+
+        ctx.eval::<(), _>(
+            r#"
+            if(add(1) !== 2){
+                throw new Error(1)
+            }
+            if(add(1, 2) !== 3){
+                throw new Error(2)
+            }
+            if(add(1, undefined, 3) !== 5){
+                throw new Error(3)
+            }
+            let c = new Counter();
+            if(c.step() !== 11){
+                throw new Error(4)
+            }
+            if(c.step(-5) !== 6){
+                throw new Error(5)
+            }
+            if(new Counter(1).step() !== 2){
+                throw new Error(6)
+            }
+            if(fetch("a") !== "GET a 0"){
+                throw new Error(7)
+            }
+            if(fetch("a", { method: "POST" }) !== "POST a 0"){
+                throw new Error(8)
+            }
+            if(fetch("a", { retries: 3 }) !== "GET a 3"){
+                throw new Error(9)
+            }
+            if(fetch("a", null) !== "GET a 0"){
+                throw new Error(10)
+            }
+            try {
+                fetch("a", 1);
+                throw new Error(11)
+            } catch(e) {
+                if(!(e instanceof TypeError)){
+                    throw e
+                }
+            }
+            try {
+                add(1, "2");
+                throw new Error(12)
+            } catch(e) {
+                if(!(e instanceof TypeError)
+                    || !e.message.includes("argument 1 'b' of 'add' to 'u32'")
+                    || !(e.cause instanceof TypeError)){
+                    throw e
+                }
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+    });
+}
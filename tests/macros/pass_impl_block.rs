@@ -0,0 +1,63 @@
+use rquickjs::{class::Trace, CatchResultExt, Class, Context, Runtime};
+
+#[derive(Trace)]
+#[rquickjs::class]
+pub struct MyType {
+    factor: f64,
+}
+
+#[rquickjs::methods]
+impl MyType {
+    pub fn method(&self, x: i32) -> i32 {
+        x * self.factor as i32
+    }
+
+    #[qjs(get)]
+    pub fn prop(&self) -> f64 {
+        self.factor
+    }
+
+    #[qjs(set, rename = "prop")]
+    pub fn set_prop(&mut self, v: f64) {
+        self.factor = v
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        Class::<MyType>::define(&ctx.globals()).unwrap();
+        ctx.globals()
+            .set("obj", MyType { factor: 2.0 })
+            .unwrap();
+
+        ctx.eval::<(), _>(
+            r#"
+            if(obj.method(3) !== 6){
+                throw new Error(1)
+            }
+            if(obj.prop !== 2){
+                throw new Error(2)
+            }
+            obj.prop = 4;
+            if(obj.method(3) !== 12){
+                throw new Error(3)
+            }
+            if(Object.getOwnPropertyNames(obj).length !== 0){
+                throw new Error(4)
+            }
+            let proto = MyType.prototype;
+            if(typeof proto.method !== "function"){
+                throw new Error(5)
+            }
+            if(typeof Object.getOwnPropertyDescriptor(proto, "prop").get !== "function"){
+                throw new Error(6)
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+    });
+}
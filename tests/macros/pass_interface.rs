@@ -0,0 +1,95 @@
+use rquickjs::{class::Trace, CatchResultExt, Context, Function, Result, Runtime};
+
+#[rquickjs::interface(rename_all = "camelCase")]
+pub trait Counter {
+    fn get(&self) -> Result<u32>;
+
+    fn add(&mut self, #[qjs(default = "1")] amount: u32) -> Result<u32>;
+
+    fn try_reset(&mut self) -> Result<bool>;
+
+    #[qjs(skip)]
+    fn description(&self) -> String {
+        "counter".to_string()
+    }
+}
+
+#[derive(Default, Trace)]
+pub struct RustCounter {
+    value: u32,
+}
+
+impl Counter for RustCounter {
+    fn get(&self) -> Result<u32> {
+        Ok(self.value)
+    }
+
+    fn add(&mut self, amount: u32) -> Result<u32> {
+        self.value += amount;
+        Ok(self.value)
+    }
+
+    fn try_reset(&mut self) -> Result<bool> {
+        self.value = 0;
+        Ok(true)
+    }
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        let counter: Box<dyn Counter> = Box::new(RustCounter::default());
+        ctx.globals().set("counter", counter).unwrap();
+
+        ctx.eval::<(), _>(
+            r#"
+            if(counter.add() !== 1){
+                throw new Error(1)
+            }
+            if(counter.add(4) !== 5){
+                throw new Error(2)
+            }
+            if(counter.get() !== 5){
+                throw new Error(3)
+            }
+            if(counter.tryReset() !== true || counter.get() !== 0){
+                throw new Error(4)
+            }
+            if(counter.description !== undefined){
+                throw new Error(5)
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+
+        let mut js_counter: Box<dyn Counter> = ctx
+            .eval(
+                r#"
+                ({
+                    value: 10,
+                    get() { return this.value },
+                    add(amount) { this.value += amount; return this.value },
+                    tryReset() { throw new Error("can't reset") },
+                })
+                "#,
+            )
+            .catch(&ctx)
+            .unwrap();
+        assert_eq!(js_counter.get().unwrap(), 10);
+        assert_eq!(js_counter.add(2).unwrap(), 12);
+        assert!(js_counter.try_reset().is_err());
+        assert_eq!(js_counter.description(), "counter");
+
+        // Missing methods are reported as errors.
+        let empty: Box<dyn Counter> = ctx.eval("({})").catch(&ctx).unwrap();
+        assert!(empty.get().is_err());
+
+        let func: Function = ctx.eval("(c) => c.add(3)").unwrap();
+        let rust_counter: Box<dyn Counter> = Box::new(RustCounter { value: 2 });
+        let res: u32 = func.call((rust_counter,)).unwrap();
+        assert_eq!(res, 5);
+    });
+}
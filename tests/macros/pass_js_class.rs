@@ -0,0 +1,74 @@
+use rquickjs::{
+    class::{JsClass, Trace},
+    CatchResultExt, Class, Context, Object, Runtime,
+};
+
+#[derive(Trace, JsClass, Clone)]
+#[qjs(rename = "Vec2", rename_all = "camelCase", constructor)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+    #[qjs(readonly)]
+    pub unit_name: String,
+    #[qjs(skip)]
+    pub skipped: u32,
+    hidden: u32,
+}
+
+#[derive(Trace, JsClass)]
+#[qjs(frozen)]
+pub struct Frozen<'js> {
+    pub value: u32,
+    pub object: Object<'js>,
+}
+
+pub fn main() {
+    let rt = Runtime::new().unwrap();
+    let ctx = Context::full(&rt).unwrap();
+
+    ctx.with(|ctx| {
+        Class::<Vector>::define(&ctx.globals()).unwrap();
+        ctx.globals()
+            .set(
+                "f",
+                Frozen {
+                    value: 1,
+                    object: Object::new(ctx.clone()).unwrap(),
+                },
+            )
+            .unwrap();
+
+        ctx.eval::<(), _>(
+            r#"
+            let v = new Vec2(1, 2, "px");
+            if(v.x !== 1 || v.y !== 2){
+                throw new Error(1)
+            }
+            v.y = 5;
+            if(v.y !== 5){
+                throw new Error(2)
+            }
+            if(v.unitName !== "px"){
+                throw new Error(3)
+            }
+            v.unitName = "em";
+            if(v.unitName !== "px"){
+                throw new Error(4)
+            }
+            if(v.skipped !== undefined || v.hidden !== undefined){
+                throw new Error(5)
+            }
+            if(f.value !== 1 || typeof f.object !== "object"){
+                throw new Error(6)
+            }
+        "#,
+        )
+        .catch(&ctx)
+        .unwrap();
+
+        let v: Vector = ctx.eval("v").unwrap();
+        assert_eq!(v.y, 5.0);
+        assert_eq!(v.skipped, 0);
+        assert_eq!(v.hidden, 0);
+    });
+}